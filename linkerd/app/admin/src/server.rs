@@ -8,29 +8,57 @@
 //! * `PUT /proxy-log-level` -- sets a new tracing filter.
 //! * `GET /tasks` -- returns a dump of spawned Tokio tasks (when enabled by the
 //!   tracing configuration).
+//! * `GET /errors` -- returns the errors most recently recorded by the proxy.
+//! * `GET /top-clients` -- returns the clients that have sent the most requests
+//!   to each inbound server.
+//! * `GET /policies` -- returns the checksum of the policy enforced on each
+//!   inbound port.
+//! * `GET|PUT|DELETE /opaque-ports` -- lists, adds, or removes inbound ports on
+//!   which protocol detection is disabled by default.
+//! * `POST /capture` -- records tap events for a matching connection or route to
+//!   a local file for a bounded duration.
+//! * `GET|PUT|DELETE /chaos` -- lists, enables, or disables faults injected
+//!   into outbound routes' requests, for mTLS clients with a permitted identity.
 //! * `POST /shutdown` -- shuts down the proxy.
+//!
+//! When configured, inbound requests may also be mirrored to consumers of a
+//! local Unix socket (see [`mirror`]).
 
-use futures::future::{self, TryFutureExt};
+use futures::future::{self, FutureExt, TryFutureExt};
 use http::StatusCode;
 use hyper::{
     body::{Body, HttpBody},
     Request, Response,
 };
 use linkerd_app_core::{
+    identity,
     metrics::{self as metrics, FmtMetrics},
-    proxy::http::ClientHandle,
+    proxy::{http::ClientHandle, tap},
     trace, Error, Result,
 };
+use linkerd_app_inbound::{
+    policy::OpaquePorts, policy_updates::PolicyUpdateMetrics, top_clients::TopClients,
+};
+use linkerd_app_outbound as outbound;
 use std::{
+    collections::HashSet,
     future::Future,
+    path::PathBuf,
     pin::Pin,
     task::{Context, Poll},
 };
 use tokio::sync::mpsc;
 
+mod capture;
+mod chaos;
+mod errors;
 mod json;
 mod log;
+pub(crate) mod mirror;
+mod opaque_ports;
+mod policies;
 mod readiness;
+mod top_clients;
 
 pub use self::readiness::{Latch, Readiness};
 
@@ -40,6 +68,11 @@ pub struct Admin<M> {
     tracing: trace::Handle,
     ready: Readiness,
     shutdown_tx: mpsc::UnboundedSender<()>,
+    capture: Option<capture::Capture>,
+    top_clients: Option<TopClients>,
+    policies: Option<PolicyUpdateMetrics>,
+    opaque_ports: Option<OpaquePorts>,
+    chaos: Option<chaos::Chaos>,
     #[cfg(feature = "pprof")]
     pprof: Option<crate::pprof::Pprof>,
 }
@@ -58,12 +91,57 @@ impl<M> Admin<M> {
             ready,
             shutdown_tx,
             tracing,
+            capture: None,
+            top_clients: None,
+            policies: None,
+            opaque_ports: None,
+            chaos: None,
 
             #[cfg(feature = "pprof")]
             pprof: None,
         }
     }
 
+    /// Enables reporting of the clients that send the most requests to each
+    /// inbound server.
+    pub fn with_top_clients(mut self, top_clients: TopClients) -> Self {
+        self.top_clients = Some(top_clients);
+        self
+    }
+
+    /// Enables reporting of the checksum of the policy enforced on each
+    /// inbound port.
+    pub fn with_policies(mut self, policies: PolicyUpdateMetrics) -> Self {
+        self.policies = Some(policies);
+        self
+    }
+
+    /// Enables updating the set of inbound ports on which protocol detection
+    /// is disabled by default.
+    pub fn with_opaque_ports(mut self, opaque_ports: OpaquePorts) -> Self {
+        self.opaque_ports = Some(opaque_ports);
+        self
+    }
+
+    /// Enables captures, which are written to files in `dir`.
+    pub fn with_capture(mut self, tap: tap::Server, dir: PathBuf) -> Self {
+        self.capture = Some(capture::Capture::new(tap, dir));
+        self
+    }
+
+    /// Enables injecting faults into outbound routes' requests. Only clients
+    /// that authenticate with one of `identities` may manage faults.
+    pub fn with_chaos(
+        mut self,
+        faults: outbound::Chaos,
+        identities: HashSet<identity::Id>,
+    ) -> Self {
+        if !identities.is_empty() {
+            self.chaos = Some(chaos::Chaos::new(faults, identities));
+        }
+        self
+    }
+
     #[cfg(feature = "pprof")]
     pub fn with_profiling(mut self, enabled: bool) -> Self {
         self.pprof = enabled.then_some(crate::pprof::Pprof);
@@ -185,6 +263,14 @@ impl<M> Admin<M> {
             .expect("builder with known status code must not fail")
     }
 
+    fn forbidden_unauthenticated() -> Response<Body> {
+        Response::builder()
+            .status(http::StatusCode::FORBIDDEN)
+            .header(http::header::CONTENT_TYPE, "text/plain")
+            .body("Requests are only permitted from authenticated clients.".into())
+            .expect("builder with known status code must not fail")
+    }
+
     fn client_is_localhost<B>(req: &Request<B>) -> bool {
         req.extensions()
             .get::<ClientHandle>()
@@ -252,6 +338,89 @@ where
 
             "/env.json" => Box::pin(future::ok(Self::env_rsp(req))),
 
+            "/errors" => {
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                if req.method() != http::Method::GET {
+                    return Box::pin(future::ok(Self::method_not_allowed()));
+                }
+
+                Box::pin(future::ok(errors::serve(req)))
+            }
+
+            "/top-clients" => {
+                let Some(top_clients) = self.top_clients.as_ref() else {
+                    return Box::pin(future::ok(Self::not_found()));
+                };
+
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                if req.method() != http::Method::GET {
+                    return Box::pin(future::ok(Self::method_not_allowed()));
+                }
+
+                Box::pin(future::ok(top_clients::serve(top_clients, req)))
+            }
+
+            "/policies" => {
+                let Some(policies) = self.policies.as_ref() else {
+                    return Box::pin(future::ok(Self::not_found()));
+                };
+
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                if req.method() != http::Method::GET {
+                    return Box::pin(future::ok(Self::method_not_allowed()));
+                }
+
+                Box::pin(future::ok(policies::serve(policies, req)))
+            }
+
+            "/opaque-ports" => {
+                let Some(opaque_ports) = self.opaque_ports.as_ref() else {
+                    return Box::pin(future::ok(Self::not_found()));
+                };
+
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                Box::pin(future::ok(opaque_ports::serve(opaque_ports, req)))
+            }
+
+            "/capture" => {
+                let Some(capture) = self.capture.clone() else {
+                    return Box::pin(future::ok(Self::not_found()));
+                };
+
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                if req.method() != http::Method::POST {
+                    return Box::pin(future::ok(Self::method_not_allowed()));
+                }
+
+                Box::pin(capture.serve(req).map(Ok))
+            }
+
+            "/chaos" => {
+                let Some(chaos) = self.chaos.as_ref() else {
+                    return Box::pin(future::ok(Self::not_found()));
+                };
+                if !chaos.is_authorized(&req) {
+                    return Box::pin(future::ok(Self::forbidden_unauthenticated()));
+                }
+
+                Box::pin(future::ok(chaos.serve(req)))
+            }
+
             "/shutdown" => {
                 if req.method() == http::Method::POST {
                     if Self::client_is_localhost(&req) {
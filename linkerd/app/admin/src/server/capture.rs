@@ -0,0 +1,471 @@
+//! Records tap events for a single connection or route to a local file.
+//!
+//! Captures are intended for support escalations: an operator triggers a
+//! capture for a bounded duration and retrieves the resulting file from the
+//! proxy's filesystem. Only one capture may run at a time.
+
+use super::json;
+use futures::StreamExt;
+use hyper::{Body, StatusCode};
+use linkerd2_proxy_api::{
+    http_types,
+    tap::{observe_request, tap_event, ObserveRequest, TapEvent},
+};
+use linkerd_app_core::proxy::tap;
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::io::AsyncWriteExt;
+
+const DEFAULT_DURATION: Duration = Duration::from_secs(30);
+const MAX_DURATION: Duration = Duration::from_secs(5 * 60);
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 10_000;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Capture {
+    tap: tap::Server,
+    dir: PathBuf,
+    active: Arc<AtomicBool>,
+}
+
+/// Parameters for a single capture, parsed from the request's query string.
+/// Request mirrors are described by the same parameters.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct Params {
+    pub(super) duration: Duration,
+    limit: u32,
+    pub(super) bodies: bool,
+    source: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+    authority: Option<String>,
+    path: Option<String>,
+    route: Option<String>,
+}
+
+/// Clears the active flag when a capture completes (or fails to start).
+struct Active(Arc<AtomicBool>);
+
+// === impl Capture ===
+
+impl Capture {
+    pub(crate) fn new(tap: tap::Server, dir: PathBuf) -> Self {
+        Self {
+            tap,
+            dir,
+            active: Default::default(),
+        }
+    }
+
+    /// Starts a capture described by the request's query string, returning
+    /// the path of the file to which events are written.
+    ///
+    /// The capture runs in the background until its duration elapses or its
+    /// event limit is reached.
+    pub(super) async fn serve<B>(self, req: http::Request<B>) -> http::Response<Body> {
+        if let Err(not_acceptable) = json::accepts_json(&req) {
+            return not_acceptable;
+        }
+
+        let params = match Params::parse(req.uri().query().unwrap_or_default()) {
+            Ok(params) => params,
+            Err(error) => return json::json_error_rsp(error, StatusCode::BAD_REQUEST),
+        };
+
+        if self
+            .active
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return json::json_error_rsp("a capture is already in progress", StatusCode::CONFLICT);
+        }
+        let active = Active(self.active.clone());
+
+        let events = match self.tap.observe_local(params.to_observe(), params.bodies) {
+            Ok(events) => events,
+            Err(status) => {
+                return json::json_error_rsp(status.message(), StatusCode::BAD_REQUEST);
+            }
+        };
+
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let path = self
+            .dir
+            .join(format!("linkerd-capture-{}.jsonl", started_at.as_millis()));
+        let file = match tokio::fs::File::create(&path).await {
+            Ok(file) => file,
+            Err(error) => {
+                tracing::warn!(%error, path = %path.display(), "Failed to create capture file");
+                return json::json_error_rsp(error, StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+        tracing::info!(path = %path.display(), ?params, "Starting capture");
+
+        let rsp = json::json_rsp(&serde_json::json!({
+            "path": path.display().to_string(),
+            "seconds": params.duration.as_secs_f64(),
+            "limit": params.limit,
+            "bodies": params.bodies,
+        }));
+
+        tokio::spawn(async move {
+            match record(events, file, &params).await {
+                Ok(events) => tracing::info!(path = %path.display(), events, "Capture complete"),
+                Err(error) => tracing::warn!(%error, path = %path.display(), "Capture failed"),
+            }
+            drop(active);
+        });
+
+        rsp
+    }
+}
+
+/// Writes events to the file as JSON, one per line, until the capture's
+/// duration elapses or the event stream ends. The first line describes the
+/// capture. Returns the number of events written.
+async fn record(
+    mut events: tap::ResponseStream,
+    file: tokio::fs::File,
+    params: &Params,
+) -> std::io::Result<usize> {
+    let mut file = tokio::io::BufWriter::new(file);
+    write_line(&mut file, &params.to_json()).await?;
+
+    let deadline = tokio::time::sleep(params.duration);
+    tokio::pin!(deadline);
+
+    let mut count = 0;
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            ev = events.next() => match ev {
+                Some(Ok(ev)) => {
+                    write_line(&mut file, &event_json(&ev)).await?;
+                    count += 1;
+                }
+                Some(Err(_)) | None => break,
+            },
+        }
+    }
+
+    file.flush().await?;
+    Ok(count)
+}
+
+async fn write_line(
+    file: &mut tokio::io::BufWriter<tokio::fs::File>,
+    value: &serde_json::Value,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    file.write_all(&line).await
+}
+
+fn event_json(ev: &TapEvent) -> serde_json::Value {
+    use serde_json::json;
+    use tap_event::http::Event;
+
+    fn addr(addr: &Option<linkerd2_proxy_api::net::TcpAddress>) -> serde_json::Value {
+        addr.clone()
+            .and_then(|a| SocketAddr::try_from(a).ok())
+            .map_or(serde_json::Value::Null, |a| a.to_string().into())
+    }
+
+    fn labels(labels: Option<&std::collections::HashMap<String, String>>) -> serde_json::Value {
+        labels.map_or(serde_json::Value::Null, |l| json!(l))
+    }
+
+    fn headers(headers: &Option<http_types::Headers>) -> serde_json::Value {
+        headers
+            .iter()
+            .flat_map(|h| h.headers.iter())
+            .map(|h| json!([h.name, String::from_utf8_lossy(&h.value)]))
+            .collect()
+    }
+
+    fn id(id: &Option<tap_event::http::StreamId>) -> serde_json::Value {
+        id.as_ref()
+            .map_or(serde_json::Value::Null, |id| json!([id.base, id.stream]))
+    }
+
+    fn seconds(d: Option<(i64, i32)>) -> serde_json::Value {
+        d.map_or(serde_json::Value::Null, |(secs, nanos)| {
+            json!(secs as f64 + f64::from(nanos) / 1e9)
+        })
+    }
+
+    let http = match ev.event.as_ref() {
+        Some(tap_event::Event::Http(tap_event::Http { event: Some(ev) })) => match ev {
+            Event::RequestInit(req) => json!({
+                "requestInit": {
+                    "id": id(&req.id),
+                    "method": req.method.clone()
+                        .and_then(|m| http::Method::try_from(m).ok())
+                        .map(|m| m.to_string()),
+                    "scheme": req.scheme.clone()
+                        .and_then(|s| http::uri::Scheme::try_from(s).ok())
+                        .map(|s| s.to_string()),
+                    "authority": req.authority,
+                    "path": req.path,
+                    "headers": headers(&req.headers),
+                }
+            }),
+            Event::ResponseInit(rsp) => json!({
+                "responseInit": {
+                    "id": id(&rsp.id),
+                    "sinceRequestInit": seconds(rsp.since_request_init.as_ref().map(|d| (d.seconds, d.nanos))),
+                    "httpStatus": rsp.http_status,
+                    "headers": headers(&rsp.headers),
+                }
+            }),
+            Event::ResponseEnd(end) => json!({
+                "responseEnd": {
+                    "id": id(&end.id),
+                    "sinceRequestInit": seconds(end.since_request_init.as_ref().map(|d| (d.seconds, d.nanos))),
+                    "sinceResponseInit": seconds(end.since_response_init.as_ref().map(|d| (d.seconds, d.nanos))),
+                    "responseBytes": end.response_bytes,
+                    "grpcStatus": match end.eos.as_ref().and_then(|e| e.end.as_ref()) {
+                        Some(linkerd2_proxy_api::tap::eos::End::GrpcStatusCode(c)) => Some(*c),
+                        _ => None,
+                    },
+                    "resetErrorCode": match end.eos.as_ref().and_then(|e| e.end.as_ref()) {
+                        Some(linkerd2_proxy_api::tap::eos::End::ResetErrorCode(c)) => Some(*c),
+                        _ => None,
+                    },
+                    "trailers": headers(&end.trailers),
+                }
+            }),
+        },
+        _ => serde_json::Value::Null,
+    };
+
+    json!({
+        "source": addr(&ev.source),
+        "sourceMeta": labels(ev.source_meta.as_ref().map(|m| &m.labels)),
+        "destination": addr(&ev.destination),
+        "destinationMeta": labels(ev.destination_meta.as_ref().map(|m| &m.labels)),
+        "routeMeta": labels(ev.route_meta.as_ref().map(|m| &m.labels)),
+        "proxyDirection": tap_event::ProxyDirection::try_from(ev.proxy_direction)
+            .map(|d| d.as_str_name())
+            .unwrap_or("UNKNOWN"),
+        "http": http,
+    })
+}
+
+// === impl Params ===
+
+impl Params {
+    pub(super) fn parse(query: &str) -> Result<Self, String> {
+        let mut params = Self {
+            duration: DEFAULT_DURATION,
+            limit: DEFAULT_LIMIT,
+            ..Default::default()
+        };
+
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            let (key, value) = (&*key, &*value);
+            let invalid = || format!("invalid value for {key}: {value:?}");
+            match key {
+                "seconds" => {
+                    let secs = value.parse::<f64>().map_err(|_| invalid())?;
+                    params.duration = Duration::try_from_secs_f64(secs)
+                        .ok()
+                        .filter(|d| !d.is_zero() && *d <= MAX_DURATION)
+                        .ok_or_else(|| {
+                            format!(
+                                "seconds must be positive and at most {}",
+                                MAX_DURATION.as_secs()
+                            )
+                        })?;
+                }
+                "limit" => {
+                    params.limit = value
+                        .parse::<u32>()
+                        .map_err(|_| invalid())?
+                        .clamp(1, MAX_LIMIT);
+                }
+                "bodies" => {
+                    params.bodies =
+                        value.is_empty() || value.parse::<bool>().map_err(|_| invalid())?
+                }
+                "source" => params.source = Some(value.parse().map_err(|_| invalid())?),
+                "destination" => params.destination = Some(value.parse().map_err(|_| invalid())?),
+                "authority" => params.authority = Some(value.to_string()).filter(|v| !v.is_empty()),
+                "path" => params.path = Some(value.to_string()).filter(|v| !v.is_empty()),
+                "route" => params.route = Some(value.to_string()).filter(|v| !v.is_empty()),
+                _ => return Err(format!("unexpected query parameter: {key}")),
+            }
+        }
+
+        if params.source.is_none()
+            && params.destination.is_none()
+            && params.authority.is_none()
+            && params.path.is_none()
+            && params.route.is_none()
+        {
+            return Err(
+                "a capture requires at least one of source, destination, authority, path, or route"
+                    .to_string(),
+            );
+        }
+
+        Ok(params)
+    }
+
+    /// Describes the capture's parameters.
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "seconds": self.duration.as_secs_f64(),
+            "limit": self.limit,
+            "bodies": self.bodies,
+            "source": self.source.map(|a| a.to_string()),
+            "destination": self.destination.map(|a| a.to_string()),
+            "authority": self.authority,
+            "path": self.path,
+            "route": self.route,
+        })
+    }
+
+    /// Builds a tap request that matches all of the capture's criteria.
+    pub(super) fn to_observe(&self) -> ObserveRequest {
+        use observe_request::r#match::{self as m, http::string_match};
+
+        // Matches a single address: an IP and port on one side of a connection.
+        fn addr(addr: SocketAddr, side: fn(m::Tcp) -> m::Match) -> observe_request::Match {
+            let ip = m::tcp::Match::Netmask(m::tcp::Netmask {
+                ip: Some(addr.ip().into()),
+                mask: if addr.is_ipv4() { 32 } else { 128 },
+            });
+            let port = m::tcp::Match::Ports(m::tcp::PortRange {
+                min: addr.port().into(),
+                max: addr.port().into(),
+            });
+            observe_request::Match {
+                r#match: Some(m::Match::All(m::Seq {
+                    matches: [ip, port]
+                        .into_iter()
+                        .map(|tcp| observe_request::Match {
+                            r#match: Some(side(m::Tcp { r#match: Some(tcp) })),
+                        })
+                        .collect(),
+                })),
+            }
+        }
+
+        fn http(m: m::http::Match) -> m::Match {
+            m::Match::Http(m::Http { r#match: Some(m) })
+        }
+
+        let mut matches = Vec::new();
+        if let Some(src) = self.source {
+            matches.push(addr(src, m::Match::Source));
+        }
+        if let Some(dst) = self.destination {
+            matches.push(addr(dst, m::Match::Destination));
+        }
+        if let Some(authority) = self.authority.clone() {
+            matches.push(observe_request::Match {
+                r#match: Some(http(m::http::Match::Authority(m::http::StringMatch {
+                    r#match: Some(string_match::Match::Exact(authority)),
+                }))),
+            });
+        }
+        if let Some(path) = self.path.clone() {
+            matches.push(observe_request::Match {
+                r#match: Some(http(m::http::Match::Path(m::http::StringMatch {
+                    r#match: Some(string_match::Match::Prefix(path)),
+                }))),
+            });
+        }
+        if let Some(route) = self.route.clone() {
+            matches.push(observe_request::Match {
+                r#match: Some(m::Match::RouteLabel(m::Label {
+                    key: "route_name".to_string(),
+                    value: route,
+                })),
+            });
+        }
+
+        ObserveRequest {
+            limit: self.limit,
+            r#match: Some(observe_request::Match {
+                r#match: Some(m::Match::All(m::Seq { matches })),
+            }),
+            // Captures always include headers, since they are typically
+            // needed to diagnose a problem.
+            extract: Some(observe_request::Extract {
+                extract: Some(observe_request::extract::Extract::Http(
+                    observe_request::extract::Http {
+                        extract: Some(observe_request::extract::http::Extract::Headers(
+                            observe_request::extract::http::Headers {},
+                        )),
+                    },
+                )),
+            }),
+        }
+    }
+}
+
+// === impl Active ===
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_params() {
+        let params = Params::parse("destination=10.1.1.1:8080&path=/api&bodies&seconds=5")
+            .expect("params must parse");
+        assert_eq!(
+            params,
+            Params {
+                duration: Duration::from_secs(5),
+                limit: DEFAULT_LIMIT,
+                bodies: true,
+                destination: Some(([10, 1, 1, 1], 8080).into()),
+                path: Some("/api".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_params() {
+        let params = Params::parse("route=my%20route&path=%2Fapi%3Fv%3D1&authority=a+b")
+            .expect("params must parse");
+        assert_eq!(params.route.as_deref(), Some("my route"));
+        assert_eq!(params.path.as_deref(), Some("/api?v=1"));
+        assert_eq!(params.authority.as_deref(), Some("a b"));
+    }
+
+    #[test]
+    fn requires_a_filter() {
+        assert!(Params::parse("").is_err());
+        assert!(Params::parse("seconds=10&bodies=true").is_err());
+        assert!(Params::parse("route=").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_params() {
+        assert!(Params::parse("route=r&seconds=0").is_err());
+        assert!(Params::parse("route=r&seconds=3600").is_err());
+        assert!(Params::parse("route=r&limit=-1").is_err());
+        assert!(Params::parse("destination=10.1.1.1").is_err());
+        assert!(Params::parse("route=r&bogus=1").is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Enables faults on outbound routes for chaos experiments.
+//!
+//! Faults are keyed by route name and expire automatically. Because they
+//! degrade traffic, only clients that authenticate over mTLS with one of a
+//! configured set of identities may list or change them.
+
+use super::json;
+use hyper::{Body, StatusCode};
+use linkerd_app_core::{identity, tls};
+use linkerd_app_outbound::{Chaos as Faults, Fault};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tokio::time;
+
+const DEFAULT_DURATION: Duration = Duration::from_secs(60);
+const MAX_DURATION: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Debug)]
+pub(crate) struct Chaos {
+    faults: Faults,
+    identities: Arc<HashSet<identity::Id>>,
+}
+
+/// Parameters for enabling a fault, parsed from the request's query string.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Params {
+    route: String,
+    delay: Option<Duration>,
+    abort: Option<StatusCode>,
+    percent: u32,
+    duration: Duration,
+}
+
+// === impl Chaos ===
+
+impl Chaos {
+    pub(crate) fn new(faults: Faults, identities: HashSet<identity::Id>) -> Self {
+        Self {
+            faults,
+            identities: Arc::new(identities),
+        }
+    }
+
+    /// Returns true if the request was sent over mTLS by a permitted client.
+    pub(super) fn is_authorized<B>(&self, req: &http::Request<B>) -> bool {
+        match req.extensions().get::<tls::ConditionalServerTls>() {
+            Some(tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(tls::ClientId(id)),
+                ..
+            })) => self.identities.contains(id),
+            _ => false,
+        }
+    }
+
+    /// Lists active faults on `GET`, enables a fault on `PUT`, and disables a
+    /// route's fault on `DELETE`.
+    pub(super) fn serve<B>(&self, req: http::Request<B>) -> http::Response<Body> {
+        if let Err(not_acceptable) = json::accepts_json(&req) {
+            return not_acceptable;
+        }
+
+        let query = req.uri().query().unwrap_or_default();
+        match *req.method() {
+            http::Method::GET => self.list(),
+            http::Method::PUT => match Params::parse(query) {
+                Ok(params) => self.enable(params),
+                Err(error) => json::json_error_rsp(error, StatusCode::BAD_REQUEST),
+            },
+            http::Method::DELETE => match Params::parse_route(query) {
+                Ok(route) => self.disable(&route),
+                Err(error) => json::json_error_rsp(error, StatusCode::BAD_REQUEST),
+            },
+            _ => http::Response::builder()
+                .status(StatusCode::METHOD_NOT_ALLOWED)
+                .body(Body::empty())
+                .expect("builder with known status code must not fail"),
+        }
+    }
+
+    fn list(&self) -> http::Response<Body> {
+        let now = time::Instant::now();
+        let faults = self
+            .faults
+            .faults()
+            .iter()
+            .map(|(route, fault)| to_json(route, fault, now))
+            .collect::<Vec<_>>();
+        json::json_rsp(&faults)
+    }
+
+    fn enable(&self, params: Params) -> http::Response<Body> {
+        let now = time::Instant::now();
+        let fault = Fault {
+            delay: params.delay,
+            abort: params.abort,
+            percent: params.percent,
+            expires_at: now + params.duration,
+        };
+        tracing::info!(
+            route = %params.route,
+            ?fault.delay,
+            ?fault.abort,
+            percent = fault.percent,
+            seconds = params.duration.as_secs(),
+            "Enabling fault",
+        );
+        let rsp = json::json_rsp(&to_json(&params.route, &fault, now));
+        self.faults.enable(params.route, fault);
+        rsp
+    }
+
+    fn disable(&self, route: &str) -> http::Response<Body> {
+        if !self.faults.disable(route) {
+            return json::json_error_rsp(
+                format!("no fault is enabled for route {route}"),
+                StatusCode::NOT_FOUND,
+            );
+        }
+        tracing::info!(%route, "Disabled fault");
+        http::Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .expect("builder with known status code must not fail")
+    }
+}
+
+fn to_json(route: &str, fault: &Fault, now: time::Instant) -> serde_json::Value {
+    serde_json::json!({
+        "route": route,
+        "delay_ms": fault.delay.map(|d| d.as_millis() as u64),
+        "abort": fault.abort.map(|s| s.as_u16()),
+        "percent": fault.percent,
+        "seconds": fault.expires_at.saturating_duration_since(now).as_secs_f64(),
+    })
+}
+
+// === impl Params ===
+
+impl Params {
+    fn parse(query: &str) -> Result<Self, String> {
+        let mut params = Self {
+            percent: 100,
+            duration: DEFAULT_DURATION,
+            ..Default::default()
+        };
+
+        for kv in query.split('&').filter(|kv| !kv.is_empty()) {
+            let (key, value) = kv.split_once('=').unwrap_or((kv, ""));
+            let invalid = || format!("invalid value for {key}: {value:?}");
+            match key {
+                "route" => params.route = value.to_string(),
+                "delay_ms" => {
+                    let ms = value.parse::<u64>().map_err(|_| invalid())?;
+                    params.delay = Some(Duration::from_millis(ms)).filter(|d| !d.is_zero());
+                }
+                "abort" => {
+                    let code = value.parse::<u16>().map_err(|_| invalid())?;
+                    params.abort = Some(
+                        StatusCode::from_u16(code)
+                            .ok()
+                            .filter(|s| s.is_client_error() || s.is_server_error())
+                            .ok_or_else(|| "abort must be a 4XX or 5XX status".to_string())?,
+                    );
+                }
+                "percent" => {
+                    params.percent = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|p| (1..=100).contains(p))
+                        .ok_or_else(|| "percent must be between 1 and 100".to_string())?;
+                }
+                "seconds" => {
+                    let secs = value.parse::<f64>().map_err(|_| invalid())?;
+                    params.duration = Duration::try_from_secs_f64(secs)
+                        .ok()
+                        .filter(|d| !d.is_zero() && *d <= MAX_DURATION)
+                        .ok_or_else(|| {
+                            format!(
+                                "seconds must be positive and at most {}",
+                                MAX_DURATION.as_secs()
+                            )
+                        })?;
+                }
+                _ => return Err(format!("unexpected query parameter: {key}")),
+            }
+        }
+
+        if params.route.is_empty() {
+            return Err("a route is required".to_string());
+        }
+        if params.delay.is_none() && params.abort.is_none() {
+            return Err("a fault requires at least one of delay_ms or abort".to_string());
+        }
+
+        Ok(params)
+    }
+
+    fn parse_route(query: &str) -> Result<String, String> {
+        let mut route = None;
+        for kv in query.split('&').filter(|kv| !kv.is_empty()) {
+            match kv.split_once('=').unwrap_or((kv, "")) {
+                ("route", value) if !value.is_empty() => route = Some(value.to_string()),
+                (key, _) => return Err(format!("unexpected query parameter: {key}")),
+            }
+        }
+        route.ok_or_else(|| "a route is required".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_params() {
+        let params =
+            Params::parse("route=books&delay_ms=250&abort=503&percent=10").expect("must parse");
+        assert_eq!(
+            params,
+            Params {
+                route: "books".to_string(),
+                delay: Some(Duration::from_millis(250)),
+                abort: Some(StatusCode::SERVICE_UNAVAILABLE),
+                percent: 10,
+                duration: DEFAULT_DURATION,
+            }
+        );
+
+        assert_eq!(Params::parse_route("route=books"), Ok("books".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_params() {
+        assert!(Params::parse("").is_err());
+        assert!(Params::parse("route=books").is_err());
+        assert!(Params::parse("delay_ms=10").is_err());
+        assert!(Params::parse("route=books&abort=200").is_err());
+        assert!(Params::parse("route=books&abort=503&percent=0").is_err());
+        assert!(Params::parse("route=books&abort=503&seconds=7200").is_err());
+        assert!(Params::parse("route=books&abort=503&bogus=1").is_err());
+        assert!(Params::parse_route("").is_err());
+        assert!(Params::parse_route("route=books&abort=503").is_err());
+    }
+
+    #[test]
+    fn requires_permitted_identity() {
+        let id = "web.default.serviceaccount.identity.linkerd.cluster.local"
+            .parse::<identity::Id>()
+            .unwrap();
+        let chaos = Chaos::new(Faults::default(), Some(id.clone()).into_iter().collect());
+
+        let req = |tls: Option<tls::ConditionalServerTls>| {
+            let mut req = http::Request::new(());
+            if let Some(tls) = tls {
+                req.extensions_mut().insert(tls);
+            }
+            req
+        };
+        let established = |id| {
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(tls::ClientId(id)),
+                client_fingerprint: None,
+                negotiated_protocol: None,
+            })
+        };
+
+        assert!(chaos.is_authorized(&req(Some(established(id)))));
+        assert!(!chaos.is_authorized(&req(Some(established(
+            "other.default.serviceaccount.identity.linkerd.cluster.local"
+                .parse()
+                .unwrap()
+        )))));
+        assert!(
+            !chaos.is_authorized(&req(Some(tls::ConditionalServerTls::None(
+                tls::NoServerTls::Loopback
+            ))))
+        );
+        assert!(!chaos.is_authorized(&req(None)));
+    }
+}
@@ -0,0 +1,57 @@
+use super::json;
+use hyper::Body;
+use linkerd_app_core::errors::recent::{ErrorRecord, RECENT_ERRORS};
+use std::time::UNIX_EPOCH;
+
+/// Serves the errors most recently recorded by the proxy as a JSON array,
+/// oldest first.
+pub(super) fn serve<B>(req: http::Request<B>) -> http::Response<Body> {
+    if let Err(not_acceptable) = json::accepts_json(&req) {
+        return not_acceptable;
+    }
+
+    let records = RECENT_ERRORS
+        .records()
+        .iter()
+        .map(to_json)
+        .collect::<Vec<_>>();
+    json::json_rsp(&records)
+}
+
+fn to_json(record: &ErrorRecord) -> serde_json::Value {
+    // Timestamps are reported as fractional seconds since the Unix epoch.
+    let timestamp = record
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default();
+    let labels = record
+        .labels
+        .iter()
+        .map(|(k, v)| (k.to_string(), serde_json::Value::from(v.clone())))
+        .collect::<serde_json::Map<_, _>>();
+    serde_json::json!({
+        "timestamp": timestamp,
+        "error": record.error,
+        "sources": record.sources,
+        "labels": labels,
+        "backtrace": record.backtrace,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_records() {
+        let error = linkerd_app_core::Error::from("boom");
+        let record = ErrorRecord::new(&*error).with_label("server.addr", "10.1.1.1:8080");
+        let json = to_json(&record);
+        assert_eq!(json["error"], "boom");
+        assert_eq!(json["sources"], serde_json::json!([]));
+        assert_eq!(json["labels"]["server.addr"], "10.1.1.1:8080");
+        assert!(json["backtrace"].is_null());
+        assert!(json["timestamp"].as_f64().unwrap() > 0.0);
+    }
+}
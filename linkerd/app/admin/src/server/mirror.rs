@@ -0,0 +1,167 @@
+//! Mirrors inbound requests to consumers of a local Unix socket.
+//!
+//! Node tooling connects to the socket and writes a single line describing
+//! the requests to mirror, using the same parameters as a capture (e.g.
+//! `path=/api&bodies&seconds=60`). Each tap event for a matching inbound
+//! request is then written to the connection as a length-delimited `TapEvent`
+//! message until the mirror's duration elapses, its event limit is reached, or
+//! the consumer disconnects. Events include the request's headers and, if
+//! bodies are requested and tap body capture is enabled, the leading bytes of
+//! its body.
+//!
+//! Mirrored requests are observed through the tap server, so they are subject
+//! to the same limits on header and body extraction as tap. The socket is only
+//! served when tap is enabled, and it may only be opened by the proxy's user.
+
+use super::capture::Params;
+use futures::StreamExt;
+use linkerd2_proxy_api::tap::{tap_event::ProxyDirection, TapEvent};
+use linkerd_app_core::{drain, proxy::tap};
+use prost::Message;
+use std::{io, os::unix::fs::PermissionsExt, path::PathBuf};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+};
+
+/// The longest parameter line that a consumer may send.
+const MAX_PARAMS_LEN: u64 = 4 * 1024;
+
+pub(crate) struct Mirror {
+    tap: tap::Server,
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+// === impl Mirror ===
+
+impl Mirror {
+    /// Binds the mirror's socket at `path`, replacing any socket left behind
+    /// by a previous proxy.
+    pub(crate) fn bind(tap: tap::Server, path: PathBuf) -> io::Result<Self> {
+        match std::fs::remove_file(&path) {
+            Ok(()) => tracing::debug!(path = %path.display(), "Removed stale mirror socket"),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        tracing::info!(path = %path.display(), "Serving request mirror");
+        Ok(Self {
+            tap,
+            path,
+            listener,
+        })
+    }
+
+    /// Accepts consumers until the proxy shuts down.
+    pub(crate) async fn serve(self, drain: drain::Watch) {
+        let shutdown = drain.signaled();
+        tokio::pin!(shutdown);
+        loop {
+            let stream = tokio::select! {
+                _ = &mut shutdown => break,
+                res = self.listener.accept() => match res {
+                    Ok((stream, _)) => stream,
+                    Err(error) => {
+                        tracing::warn!(%error, "Failed to accept mirror consumer");
+                        continue;
+                    }
+                },
+            };
+            let tap = self.tap.clone();
+            tokio::spawn(async move {
+                match mirror(tap, stream).await {
+                    Ok(events) => tracing::debug!(events, "Mirror complete"),
+                    Err(error) => tracing::debug!(%error, "Mirror failed"),
+                }
+            });
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Reads a consumer's parameters and writes matching inbound events to it.
+/// Returns the number of events written.
+async fn mirror(tap: tap::Server, stream: UnixStream) -> io::Result<usize> {
+    let (read, mut write) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(read.take(MAX_PARAMS_LEN))
+        .read_line(&mut line)
+        .await?;
+
+    let params = match Params::parse(line.trim()) {
+        Ok(params) => params,
+        Err(error) => return reject(&mut write, &error).await,
+    };
+    let mut events = match tap.observe_local(params.to_observe(), params.bodies) {
+        Ok(events) => events,
+        Err(status) => return reject(&mut write, status.message()).await,
+    };
+    tracing::debug!(?params, "Mirroring requests");
+
+    let deadline = tokio::time::sleep(params.duration);
+    tokio::pin!(deadline);
+
+    let mut count = 0;
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            ev = events.next() => match ev {
+                Some(Ok(ev)) if is_inbound(&ev) => {
+                    write.write_all(&ev.encode_length_delimited_to_vec()).await?;
+                    count += 1;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => break,
+            },
+        }
+    }
+
+    write.shutdown().await?;
+    Ok(count)
+}
+
+/// Informs the consumer that its parameters are invalid.
+async fn reject(write: &mut (impl AsyncWriteExt + Unpin), error: &str) -> io::Result<usize> {
+    write
+        .write_all(format!("error: {error}\n").as_bytes())
+        .await?;
+    write.shutdown().await?;
+    Ok(0)
+}
+
+fn is_inbound(ev: &TapEvent) -> bool {
+    ev.proxy_direction == ProxyDirection::Inbound as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn rejects_invalid_params() {
+        let dir = std::env::temp_dir().join(format!("linkerd-mirror-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mirror.sock");
+
+        let (_, tap) = tap::new(Default::default());
+        let mirror = Mirror::bind(tap, path.clone()).expect("socket must bind");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let (drain_tx, drain_rx) = drain::channel();
+        let serve = tokio::spawn(mirror.serve(drain_rx));
+
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        stream.write_all(b"seconds=10\n").await.unwrap();
+        let mut rsp = String::new();
+        stream.read_to_string(&mut rsp).await.unwrap();
+        assert!(rsp.starts_with("error: "), "{rsp}");
+
+        drain_tx.drain().await;
+        serve.await.unwrap();
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir(&dir);
+    }
+}
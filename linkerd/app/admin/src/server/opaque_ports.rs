@@ -0,0 +1,57 @@
+use super::json;
+use hyper::{Body, StatusCode};
+use linkerd_app_inbound::policy::OpaquePorts;
+
+/// Lists the inbound ports on which protocol detection is disabled by default
+/// on `GET`, adds ports on `PUT`, and removes ports on `DELETE`.
+///
+/// Ports are updated with a `ports` query parameter holding a comma-separated
+/// list of ports and port ranges, e.g. `?ports=8080,9000-9010`. Updates apply
+/// to connections on ports whose policies have not already been discovered.
+pub(super) fn serve<B>(opaque_ports: &OpaquePorts, req: http::Request<B>) -> http::Response<Body> {
+    if let Err(not_acceptable) = json::accepts_json(&req) {
+        return not_acceptable;
+    }
+
+    match *req.method() {
+        http::Method::GET => list(opaque_ports),
+        http::Method::PUT | http::Method::DELETE => {
+            let query = req.uri().query().unwrap_or_default();
+            let ports = match query.split('&').find_map(|kv| kv.strip_prefix("ports=")) {
+                Some(ports) => ports,
+                None => {
+                    return json::json_error_rsp("missing ports parameter", StatusCode::BAD_REQUEST)
+                }
+            };
+            let ports = match OpaquePorts::parse(ports) {
+                Ok(ports) => ports,
+                Err(error) => return json::json_error_rsp(error, StatusCode::BAD_REQUEST),
+            };
+
+            if req.method() == http::Method::PUT {
+                opaque_ports.insert(&ports);
+            } else {
+                opaque_ports.remove(&ports);
+            }
+            tracing::info!(
+                method = %req.method(),
+                ports = ?ports.iter().collect::<Vec<_>>(),
+                "Updated opaque ports",
+            );
+            list(opaque_ports)
+        }
+        _ => http::Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .body(Body::empty())
+            .expect("builder with known status code must not fail"),
+    }
+}
+
+fn list(opaque_ports: &OpaquePorts) -> http::Response<Body> {
+    let ranges = opaque_ports
+        .ranges()
+        .into_iter()
+        .map(|r| serde_json::json!({ "start": r.start(), "end": r.end() }))
+        .collect::<Vec<_>>();
+    json::json_rsp(&ranges)
+}
@@ -0,0 +1,28 @@
+use super::json;
+use hyper::Body;
+use linkerd_app_inbound::policy_updates::{PolicyUpdateMetrics, PortPolicy};
+
+/// Serves the checksum of the policy enforced on each inbound port as a JSON
+/// array, so that operators can verify that proxies have converged.
+pub(super) fn serve<B>(
+    policies: &PolicyUpdateMetrics,
+    req: http::Request<B>,
+) -> http::Response<Body> {
+    if let Err(not_acceptable) = json::accepts_json(&req) {
+        return not_acceptable;
+    }
+
+    let ports = policies.snapshot().iter().map(to_json).collect::<Vec<_>>();
+    json::json_rsp(&ports)
+}
+
+fn to_json(policy: &PortPolicy) -> serde_json::Value {
+    serde_json::json!({
+        "port": policy.port,
+        // Checksums are formatted as strings, since JSON numbers can't
+        // represent all 64-bit integers.
+        "checksum": format!("{:016x}", policy.checksum),
+        "updates": policy.updates,
+        "age_seconds": policy.age.as_secs(),
+    })
+}
@@ -0,0 +1,44 @@
+use super::json;
+use hyper::Body;
+use linkerd_app_inbound::top_clients::{ClientCount, ServerTopClients, TopClients};
+
+/// Serves the clients that have sent the most requests to each inbound server
+/// as a JSON array.
+pub(super) fn serve<B>(top_clients: &TopClients, req: http::Request<B>) -> http::Response<Body> {
+    if let Err(not_acceptable) = json::accepts_json(&req) {
+        return not_acceptable;
+    }
+
+    let servers = top_clients
+        .snapshot()
+        .iter()
+        .map(to_json)
+        .collect::<Vec<_>>();
+    json::json_rsp(&servers)
+}
+
+fn to_json(server: &ServerTopClients) -> serde_json::Value {
+    fn counts(counts: &[ClientCount]) -> Vec<serde_json::Value> {
+        counts
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "client": c.client.to_string(),
+                    "count": c.count,
+                    "error": c.error,
+                })
+            })
+            .collect()
+    }
+
+    let meta = &server.server.0;
+    serde_json::json!({
+        "server": {
+            "group": meta.group(),
+            "kind": meta.kind(),
+            "name": meta.name(),
+        },
+        "requests": counts(&server.requests),
+        "denials": counts(&server.denials),
+    })
+}
@@ -1,9 +1,10 @@
+use futures::future::{self, FutureExt};
 use linkerd_app_core::{
     classify,
     config::ServerConfig,
     detect, drain, errors, identity,
     metrics::{self, FmtMetrics},
-    proxy::http,
+    proxy::{http, tap},
     serve,
     svc::{self, ExtractParam, InsertParam, Param},
     tls, trace,
@@ -13,7 +14,13 @@ use linkerd_app_core::{
     Error, Result,
 };
 use linkerd_app_inbound as inbound;
-use std::{pin::Pin, time::Duration};
+use linkerd_app_outbound as outbound;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    pin::Pin,
+    time::Duration,
+};
 use thiserror::Error;
 use tokio::sync::mpsc;
 use tracing::debug;
@@ -22,6 +29,22 @@ use tracing::debug;
 pub struct Config {
     pub server: ServerConfig,
     pub metrics_retain_idle: Duration,
+    /// If set, latency histograms use sparse buckets with this growth factor.
+    pub metrics_histogram_growth_factor: Option<metrics::GrowthFactor>,
+    /// Overrides the bounds of latency histograms by metric family.
+    pub metrics_latency_buckets: HashMap<String, &'static metrics::Bounds>,
+    /// If set, per-peer TCP metrics are recorded for outbound connections.
+    pub metrics_peers: Option<transport::metrics::peer::Config>,
+    /// If set, long-lived connections' `TCP_INFO` is sampled at this interval.
+    pub metrics_socket_health: Option<transport::metrics::health::Config>,
+    /// The directory to which captures are written.
+    pub capture_dir: PathBuf,
+    /// If set, inbound requests may be mirrored to consumers of a Unix socket
+    /// at this path. Only set when tap is enabled.
+    pub mirror_socket: Option<PathBuf>,
+    /// The client identities permitted to inject faults into outbound routes.
+    /// Fault injection is disabled when empty.
+    pub chaos_identities: HashSet<identity::Id>,
     #[cfg(feature = "pprof")]
     pub enable_profiling: bool,
 }
@@ -82,6 +105,9 @@ impl Config {
         report: R,
         metrics: inbound::InboundMetrics,
         trace: trace::Handle,
+        tap: tap::Server,
+        chaos: outbound::Chaos,
+        opaque_ports: inbound::policy::OpaquePorts,
         drain: drain::Watch,
         shutdown: mpsc::UnboundedSender<()>,
     ) -> Result<Task>
@@ -99,13 +125,25 @@ impl Config {
 
         let (ready, latch) = crate::server::Readiness::new();
 
-        #[cfg_attr(not(feature = "pprof"), allow(unused_mut))]
-        let admin = crate::server::Admin::new(report, ready, shutdown, trace);
+        let mirror = self
+            .mirror_socket
+            .map(|path| crate::server::mirror::Mirror::bind(tap.clone(), path))
+            .transpose()?;
+
+        let admin = crate::server::Admin::new(report, ready, shutdown, trace)
+            .with_capture(tap, self.capture_dir)
+            .with_top_clients(metrics.http_authz.top_clients())
+            .with_policies(metrics.policy_updates.clone())
+            .with_opaque_ports(opaque_ports)
+            .with_chaos(chaos, self.chaos_identities);
 
         #[cfg(feature = "pprof")]
         let admin = admin.with_profiling(self.enable_profiling);
 
         let http = svc::stack(move |_| admin.clone())
+            // Describes the client's TLS status so that endpoints can
+            // authenticate clients.
+            .push(http::insert::NewInsert::<tls::ConditionalServerTls, _>::layer())
             .push(
                 metrics
                     .proxy
@@ -123,7 +161,12 @@ impl Config {
 
         let tcp = http
             .unlift_new()
-            .push(http::NewServeHttp::layer(Default::default(), drain.clone()))
+            .push(http::NewServeHttp::layer(
+                Default::default(),
+                None,
+                false,
+                drain.clone(),
+            ))
             .push_filter(
                 |(http, tcp): (
                     Result<Option<http::Version>, detect::DetectTimeoutError<_>>,
@@ -186,7 +229,11 @@ impl Config {
             .arc_new_tcp()
             .into_inner();
 
-        let serve = Box::pin(serve::serve(listen, tcp, drain.signaled()));
+        let serve = serve::serve(listen, tcp, drain.clone().signaled());
+        let serve: Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>> = match mirror {
+            Some(mirror) => Box::pin(future::join(serve, mirror.serve(drain)).map(|_| ())),
+            None => Box::pin(serve),
+        };
         Ok(Task {
             listen_addr,
             latch,
@@ -267,6 +314,12 @@ impl Param<metrics::EndpointLabels> for Permitted {
     }
 }
 
+impl Param<tls::ConditionalServerTls> for Permitted {
+    fn param(&self) -> tls::ConditionalServerTls {
+        self.http.tcp.tls.clone()
+    }
+}
+
 // === TlsParams ===
 
 impl<T> ExtractParam<tls::server::Timeout, T> for TlsParams {
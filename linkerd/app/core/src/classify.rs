@@ -2,7 +2,7 @@ use crate::profiles;
 pub use classify::gate;
 use linkerd_error::Error;
 use linkerd_proxy_client_policy as client_policy;
-use linkerd_proxy_http::{classify, HasH2Reason, ResponseTimeoutError};
+use linkerd_proxy_http::{classify, h2, HasH2Reason, ResponseTimeoutError};
 use std::borrow::Cow;
 use tonic as grpc;
 use tracing::trace;
@@ -157,6 +157,11 @@ impl classify::ClassifyResponse for Response {
     }
 
     fn error(self, err: &Error) -> Self::Class {
+        // Calls cancelled by their clients are not failures of the server.
+        if matches!(self, Self::Grpc(_)) && is_cancelled(err) {
+            return Class::Grpc(Ok(grpc::Code::Cancelled));
+        }
+
         let msg = if err.is::<ResponseTimeoutError>() {
             "timeout".into()
         } else {
@@ -199,6 +204,9 @@ impl classify::ClassifyEos for Eos {
     }
 
     fn error(self, err: &Error) -> Class {
+        if matches!(self, Self::GrpcOpen(_)) && is_cancelled(err) {
+            return Class::Grpc(Ok(grpc::Code::Cancelled));
+        }
         Class::Error(h2_error(err).into())
     }
 }
@@ -221,6 +229,13 @@ fn h2_error(err: &Error) -> String {
     }
 }
 
+/// Returns true if the request's stream was cancelled, which indicates that
+/// its client went away.
+fn is_cancelled(err: &Error) -> bool {
+    crate::errors::is_caused_by::<crate::errors::ClientCancelled>(&**err)
+        || err.h2_reason() == Some(h2::Reason::CANCEL)
+}
+
 fn is_grpc(hdrs: &http::HeaderMap) -> bool {
     hdrs.get(http::header::CONTENT_TYPE)
         .and_then(|v| v.to_str().ok())
@@ -386,4 +401,15 @@ mod tests {
 
         assert_eq!(class, Class::Grpc(Err(tonic::Code::DeadlineExceeded)));
     }
+
+    #[test]
+    fn grpc_client_cancelled() {
+        let err = linkerd_error::Error::from(crate::errors::ClientCancelled);
+        let class = super::Response::Grpc(Default::default()).error(&err);
+        assert_eq!(class, Class::Grpc(Ok(tonic::Code::Cancelled)));
+
+        let err = linkerd_error::Error::from(crate::errors::ClientCancelled);
+        let class = super::Response::default().error(&err);
+        assert!(class.is_failure());
+    }
 }
@@ -2,7 +2,7 @@ pub use crate::exp_backoff::ExponentialBackoff;
 use crate::{
     proxy::http::{self, h1, h2},
     svc::{queue, CloneParam, ExtractParam, Param},
-    transport::{Keepalive, ListenAddr},
+    transport::{AcceptShards, Keepalive, ListenAddr, ReusePort, SocketOptions},
 };
 use std::time::Duration;
 
@@ -10,7 +10,18 @@ use std::time::Duration;
 pub struct ServerConfig {
     pub addr: ListenAddr,
     pub keepalive: Keepalive,
+    pub socket_options: SocketOptions,
+    pub accept_shards: AcceptShards,
+    pub reuse_port: ReusePort,
     pub h2_settings: h2::Settings,
+
+    /// Limits how long accepted connections are served before they are
+    /// gracefully closed.
+    pub max_connection_age: Option<Duration>,
+
+    /// Whether HTTP/1 requests retain the client's acceptance of trailers, so
+    /// that trailers are preserved when requests are bridged to HTTP/2.
+    pub preserve_trailers: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -18,6 +29,7 @@ pub struct ConnectConfig {
     pub backoff: ExponentialBackoff,
     pub timeout: Duration,
     pub keepalive: Keepalive,
+    pub socket_options: SocketOptions,
     pub h1_settings: h1::PoolSettings,
     pub h2_settings: h2::Settings,
 }
@@ -28,6 +40,8 @@ pub struct ProxyConfig {
     pub connect: ConnectConfig,
     pub max_in_flight_requests: usize,
     pub detect_protocol_timeout: Duration,
+    /// Names the request header that carries a request's deadline, if any.
+    pub deadline_header: Option<http::HeaderName>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -78,3 +92,21 @@ impl Param<Keepalive> for ServerConfig {
         self.keepalive
     }
 }
+
+impl Param<SocketOptions> for ServerConfig {
+    fn param(&self) -> SocketOptions {
+        self.socket_options
+    }
+}
+
+impl Param<AcceptShards> for ServerConfig {
+    fn param(&self) -> AcceptShards {
+        self.accept_shards
+    }
+}
+
+impl Param<ReusePort> for ServerConfig {
+    fn param(&self) -> ReusePort {
+        self.reuse_port
+    }
+}
@@ -14,9 +14,14 @@ pub struct Config {
     pub addr: ControlAddr,
     pub connect: config::ConnectConfig,
     pub buffer: config::QueueConfig,
+    /// Overrides how the controller's certificate is verified, so that it
+    /// need not be issued by the workload's trust anchors.
+    pub trust: Option<identity::ServerTrust>,
+    /// A bound service account token with which requests are authenticated.
+    pub token: Option<identity::client::TokenSource>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ControlAddr {
     pub addr: Addr,
     pub identity: tls::ConditionalClientTls,
@@ -72,6 +77,11 @@ impl fmt::Display for ControlAddr {
 pub type RspBody =
     linkerd_http_metrics::requests::ResponseBody<http::balance::Body<hyper::Body>, classify::Eos>;
 
+/// A control plane client. All of a client's gRPC streams are multiplexed
+/// over a single HTTP/2 connection per control plane endpoint.
+pub type Client =
+    svc::BoxCloneSyncService<http::Request<tonic::body::BoxBody>, http::Response<RspBody>>;
+
 const EWMA_CONFIG: http::balance::EwmaConfig = http::balance::EwmaConfig {
     default_rtt: time::Duration::from_millis(30),
     decay: time::Duration::from_secs(10),
@@ -84,10 +94,7 @@ impl Config {
         metrics: metrics::ControlHttp,
         registry: &mut prom::Registry,
         identity: identity::NewClient,
-    ) -> svc::ArcNewService<
-        (),
-        svc::BoxCloneSyncService<http::Request<tonic::body::BoxBody>, http::Response<RspBody>>,
-    > {
+    ) -> svc::ArcNewService<(), Client> {
         let addr = self.addr;
         tracing::trace!(%addr, "Building");
 
@@ -111,13 +118,31 @@ impl Config {
             }
         };
 
-        let client = svc::stack(ConnectTcp::new(self.connect.keepalive))
-            .push(tls::Client::layer(identity))
-            .push_connect_timeout(self.connect.timeout)
-            .push_map_target(|(_version, target)| target)
-            .push(self::client::layer())
-            .push_on_service(svc::MapErr::layer_boxed())
-            .into_new_service();
+        // Control plane streams are long-lived watches, so connections are not
+        // recycled by age or request count. Flow control windows bound the
+        // buffering of each watch, and HTTP/2 PINGs detect unhealthy
+        // connections so that their watches are re-established.
+        let h2_settings = http::h2::Settings {
+            max_connection_age: None,
+            max_requests_per_connection: None,
+            ..self.connect.h2_settings
+        };
+
+        let identity = match self.trust {
+            Some(ref trust) => identity.with_server_trust(trust),
+            None => identity,
+        };
+
+        let client = svc::stack(ConnectTcp::new(
+            self.connect.keepalive,
+            self.connect.socket_options,
+        ))
+        .push(tls::Client::layer(identity))
+        .push_connect_timeout(self.connect.timeout)
+        .push_map_target(|(_version, target)| target)
+        .push(self::client::layer(h2_settings))
+        .push_on_service(svc::MapErr::layer_boxed())
+        .into_new_service();
 
         let endpoint = client
             // Ensure that connection is driven independently of the load
@@ -142,6 +167,8 @@ impl Config {
             .push(classify::NewClassify::layer_default());
 
         balance
+            .push_on_service(self::encoding::layer(registry))
+            .push_on_service(self::bearer::layer(self.token))
             .push(self::add_origin::layer())
             .push(svc::NewMapErr::layer_from_target::<ControlError, _>())
             .instrument(|c: &ControlAddr| info_span!("controller", addr = %c.addr))
@@ -229,6 +256,164 @@ mod add_origin {
     }
 }
 
+/// Authenticates requests with a bound service account token.
+mod bearer {
+    use crate::{identity::client::TokenSource, svc};
+    use std::task::{Context, Poll};
+    use tracing::warn;
+
+    pub fn layer<S>(
+        token: Option<TokenSource>,
+    ) -> impl svc::layer::Layer<S, Service = AddBearer<S>> + Clone {
+        svc::layer::mk(move |inner| AddBearer {
+            token: token.clone(),
+            inner,
+        })
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct AddBearer<S> {
+        token: Option<TokenSource>,
+        inner: S,
+    }
+
+    // === impl AddBearer ===
+
+    impl<B, S: tower::Service<http::Request<B>>> tower::Service<http::Request<B>> for AddBearer<S> {
+        type Response = S::Response;
+        type Error = S::Error;
+        type Future = S::Future;
+
+        #[inline]
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+            if let Some(token) = self.token.as_ref() {
+                // Control plane requests are long-lived watches, so the token
+                // is read for each request to pick up rotated tokens. If the
+                // token cannot be read, the request is sent without it and
+                // the controller's rejection is retried like any other error.
+                match token.load().map_err(Into::into).and_then(|t| bearer(&t)) {
+                    Ok(value) => {
+                        req.headers_mut().insert(http::header::AUTHORIZATION, value);
+                    }
+                    Err(error) => {
+                        warn!(
+                            audience = token.audience(),
+                            %error,
+                            "Failed to load control plane token"
+                        );
+                    }
+                }
+            }
+            self.inner.call(req)
+        }
+    }
+
+    fn bearer(token: &[u8]) -> Result<http::HeaderValue, crate::Error> {
+        let token = std::str::from_utf8(token)?.trim();
+        let mut value = http::HeaderValue::from_str(&format!("Bearer {token}"))?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+/// Counts responses by the compression encoding negotiated for their messages.
+mod encoding {
+    use crate::{
+        metrics::prom::{self, encoding::EncodeLabelSet},
+        svc,
+    };
+    use futures::ready;
+    use pin_project::pin_project;
+    use std::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    pub fn layer<S>(
+        registry: &mut prom::Registry,
+    ) -> impl svc::layer::Layer<S, Service = RecordEncoding<S>> + Clone {
+        let encodings = prom::Family::default();
+        registry.register(
+            "response_encodings",
+            "The total number of responses by the compression encoding of their messages",
+            encodings.clone(),
+        );
+        svc::layer::mk(move |inner| RecordEncoding {
+            encodings: encodings.clone(),
+            inner,
+        })
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct RecordEncoding<S> {
+        encodings: prom::Family<Labels, prom::Counter>,
+        inner: S,
+    }
+
+    #[pin_project]
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        encodings: prom::Family<Labels, prom::Counter>,
+    }
+
+    #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+    pub struct Labels {
+        encoding: &'static str,
+    }
+
+    // === impl RecordEncoding ===
+
+    impl<B, RspB, S> tower::Service<http::Request<B>> for RecordEncoding<S>
+    where
+        S: tower::Service<http::Request<B>, Response = http::Response<RspB>>,
+    {
+        type Response = S::Response;
+        type Error = S::Error;
+        type Future = ResponseFuture<S::Future>;
+
+        #[inline]
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            ResponseFuture {
+                inner: self.inner.call(req),
+                encodings: self.encodings.clone(),
+            }
+        }
+    }
+
+    // === impl ResponseFuture ===
+
+    impl<B, E, F> Future for ResponseFuture<F>
+    where
+        F: Future<Output = Result<http::Response<B>, E>>,
+    {
+        type Output = F::Output;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let this = self.project();
+            let rsp = ready!(this.inner.poll(cx))?;
+            // Servers only compress messages with an encoding that the client
+            // accepts, so any other value is unexpected.
+            let encoding = match rsp.headers().get("grpc-encoding").map(|v| v.as_bytes()) {
+                None | Some(b"identity") => "identity",
+                Some(b"gzip") => "gzip",
+                Some(_) => "unknown",
+            };
+            this.encodings.get_or_create(&Labels { encoding }).inc();
+            Poll::Ready(Ok(rsp))
+        }
+    }
+}
+
 mod balance {
     use super::{client::Target, ControlAddr};
     use crate::{
@@ -361,12 +546,12 @@ mod client {
 
     // === impl Layer ===
 
-    pub fn layer<C, B>() -> impl svc::Layer<C, Service = Client<C, B>> + Copy
+    pub fn layer<C, B>(settings: H2Settings) -> impl svc::Layer<C, Service = Client<C, B>> + Copy
     where
         http::h2::Connect<C, B>: tower::Service<Target>,
     {
-        svc::layer::mk(|mk_conn| {
-            let inner = http::h2::Connect::new(mk_conn, H2Settings::default());
+        svc::layer::mk(move |mk_conn| {
+            let inner = http::h2::Connect::new(mk_conn, settings);
             Client { inner }
         })
     }
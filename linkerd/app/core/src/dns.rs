@@ -7,6 +7,7 @@ pub struct Config {
     pub min_ttl: Option<Duration>,
     pub max_ttl: Option<Duration>,
     pub resolv_conf_path: PathBuf,
+    pub suffix_routes: Vec<SuffixRoute>,
 }
 
 pub struct Dns {
@@ -32,4 +33,8 @@ impl ConfigureResolver for Config {
         opts.negative_min_ttl = self.min_ttl;
         opts.negative_max_ttl = self.max_ttl;
     }
+
+    fn suffix_routes(&self) -> Vec<SuffixRoute> {
+        self.suffix_routes.clone()
+    }
 }
@@ -1,6 +1,17 @@
+mod code;
+pub mod panic;
+pub mod recent;
+pub mod resets;
 pub mod respond;
+mod source;
 
-pub use self::respond::{HttpRescue, NewRespond, NewRespondService, SyntheticHttpResponse};
+pub use self::{
+    code::ErrorCode,
+    panic::{CatchPanic, Panicked},
+    resets::StreamResets,
+    respond::{HttpRescue, NewRespond, NewRespondService, SyntheticHttpResponse},
+    source::ErrorSource,
+};
 pub use linkerd_error::{cause_ref, is_caused_by};
 pub use linkerd_proxy_http::h2::H2Error;
 pub use linkerd_stack::{FailFastError, LoadShedError};
@@ -10,6 +21,12 @@ pub use tonic::Code as Grpc;
 #[error("connect timed out after {0:?}")]
 pub struct ConnectTimeout(pub(crate) std::time::Duration);
 
+/// Indicates that a request's client went away before the request was
+/// answered.
+#[derive(Debug, thiserror::Error)]
+#[error("client cancelled the request")]
+pub struct ClientCancelled;
+
 /// Returns `true` if `error` was caused by a gRPC error with the provided
 /// status code.
 #[inline]
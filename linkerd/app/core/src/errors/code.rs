@@ -0,0 +1,88 @@
+use std::fmt;
+
+/// A machine-readable code that describes why the proxy failed a request.
+///
+/// Codes are reported in the `l5d-proxy-error-code` header and the body of
+/// synthesized responses, and they label the proxy's error metrics. Unlike
+/// error messages, codes are stable and may be matched by clients.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// No route matched the request.
+    RouteNotFound,
+    /// The request was not authorized by policy.
+    Unauthorized,
+    /// The request's client is not authenticated.
+    Unauthenticated,
+    /// The request's response timeout expired.
+    ResponseTimeout,
+    /// The deadline in the request's deadline header expired.
+    DeadlineExceeded,
+    /// No endpoints were available to process the request.
+    FailFast,
+    /// The proxy was at capacity and shed the request.
+    LoadShed,
+    /// The request exceeded its route's rate limit.
+    RateLimited,
+    /// A connection could not be established or failed.
+    ConnectError,
+    /// A connection could not be established before its timeout.
+    ConnectTimeout,
+    /// The request's required identity did not match its endpoint's identity.
+    IdentityRequired,
+    /// The request was routed back to the proxy that sent it.
+    LoopDetected,
+    /// The request was redirected by policy.
+    Redirect,
+    /// Policy injected a failure for the request.
+    InjectedFailure,
+    /// The protocol could not be detected before a timeout.
+    DetectTimeout,
+    /// The request's backend could not be dispatched to.
+    BadGateway,
+    /// The request's target exceeded the proxy's length limit.
+    UriTooLong,
+    /// The request's body exceeded its route's size limit.
+    PayloadTooLarge,
+    /// The request's stream was cancelled.
+    Cancelled,
+    /// The request's stream was reset with an error.
+    StreamReset,
+    /// The proxy encountered an unexpected error.
+    Internal,
+}
+
+// === impl ErrorCode ===
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RouteNotFound => "route-not-found",
+            Self::Unauthorized => "unauthorized",
+            Self::Unauthenticated => "unauthenticated",
+            Self::ResponseTimeout => "response-timeout",
+            Self::DeadlineExceeded => "deadline-exceeded",
+            Self::FailFast => "failfast",
+            Self::LoadShed => "loadshed",
+            Self::RateLimited => "rate-limited",
+            Self::ConnectError => "connect-error",
+            Self::ConnectTimeout => "connect-timeout",
+            Self::IdentityRequired => "identity-required",
+            Self::LoopDetected => "loop-detected",
+            Self::Redirect => "redirect",
+            Self::InjectedFailure => "injected-failure",
+            Self::DetectTimeout => "detect-timeout",
+            Self::BadGateway => "bad-gateway",
+            Self::UriTooLong => "uri-too-long",
+            Self::PayloadTooLarge => "payload-too-large",
+            Self::Cancelled => "cancelled",
+            Self::StreamReset => "stream-reset",
+            Self::Internal => "internal-error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
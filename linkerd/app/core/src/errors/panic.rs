@@ -0,0 +1,208 @@
+use super::recent::{ErrorRecord, RECENT_ERRORS};
+use crate::{svc, Error};
+use pin_project::pin_project;
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::RefCell,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Once,
+    },
+    task::{Context, Poll},
+};
+
+/// Indicates that a service panicked while handling a request.
+#[derive(Debug, thiserror::Error)]
+#[error("panicked: {0}")]
+pub struct Panicked(String);
+
+/// Converts panics in the inner service (and its response futures) into
+/// [`Panicked`] errors.
+///
+/// This prevents a panic in a single request from tearing down the connection
+/// (and every other request multiplexed on it).
+#[derive(Clone, Debug)]
+pub struct CatchPanic<S> {
+    inner: S,
+}
+
+/// Drives an inner future, converting a panic into a [`Panicked`] error.
+#[pin_project]
+#[derive(Debug)]
+pub struct Catch<F> {
+    #[pin]
+    inner: F,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: Option<Catch<F>>,
+    panicked: Option<Panicked>,
+}
+
+/// The number of panics that have been caught.
+static PANICS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The backtrace of the most recent panic on this thread.
+    static BACKTRACE: RefCell<Option<Backtrace>> = RefCell::new(None);
+}
+
+/// Installs a panic hook that captures a backtrace for each panic, so that the
+/// backtrace may be recorded when the panic is caught.
+///
+/// The prior hook is invoked after the backtrace is captured.
+pub fn install_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let prior = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            BACKTRACE.with(|bt| *bt.borrow_mut() = Some(Backtrace::force_capture()));
+            prior(info);
+        }));
+    });
+}
+
+/// Returns the total number of panics that have been caught.
+pub fn panics_total() -> u64 {
+    PANICS.load(Ordering::Relaxed)
+}
+
+/// Wraps `inner` so that a panic while it is polled is returned as an error.
+pub fn catch<F: Future>(inner: F) -> Catch<F> {
+    Catch { inner }
+}
+
+fn caught(payload: Box<dyn Any + Send>) -> Panicked {
+    let message = payload
+        .downcast_ref::<&'static str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    PANICS.fetch_add(1, Ordering::Relaxed);
+
+    let backtrace = BACKTRACE.with(|bt| bt.borrow_mut().take());
+    tracing::error!(%message, "Caught panic");
+    let error = Panicked(message);
+    RECENT_ERRORS
+        .record(ErrorRecord::new(&error).with_backtrace(backtrace.map(|bt| bt.to_string())));
+    error
+}
+
+// === impl CatchPanic ===
+
+impl<S> CatchPanic<S> {
+    pub fn layer() -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<Req, S> svc::Service<Req> for CatchPanic<S>
+where
+    S: svc::Service<Req>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        let inner = &mut self.inner;
+        match panic::catch_unwind(AssertUnwindSafe(|| inner.poll_ready(cx))) {
+            Ok(poll) => poll.map_err(Into::into),
+            Err(payload) => Poll::Ready(Err(caught(payload).into())),
+        }
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let inner = &mut self.inner;
+        match panic::catch_unwind(AssertUnwindSafe(|| inner.call(req))) {
+            Ok(future) => ResponseFuture {
+                inner: Some(catch(future)),
+                panicked: None,
+            },
+            Err(payload) => ResponseFuture {
+                inner: None,
+                panicked: Some(caught(payload)),
+            },
+        }
+    }
+}
+
+// === impl Catch ===
+
+impl<F: Future> Future for Catch<F> {
+    type Output = Result<F::Output, Panicked>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = self.project().inner;
+        match panic::catch_unwind(AssertUnwindSafe(|| inner.poll(cx))) {
+            Ok(poll) => poll.map(Ok),
+            Err(payload) => Poll::Ready(Err(caught(payload))),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, T, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<Error>,
+{
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match this.inner.as_pin_mut() {
+            Some(inner) => inner.poll(cx).map(|res| match res {
+                Ok(res) => res.map_err(Into::into),
+                Err(panicked) => Err(panicked.into()),
+            }),
+            None => Poll::Ready(Err(this
+                .panicked
+                .take()
+                .expect("polled after complete")
+                .into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svc::{Layer, ServiceExt};
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn converts_panics_to_errors() {
+        install_hook();
+        let before = panics_total();
+
+        let svc = CatchPanic::layer().layer(svc::mk(|fail: bool| async move {
+            if fail {
+                panic!("boom");
+            }
+            Ok::<_, Error>(())
+        }));
+
+        svc.clone().oneshot(false).await.expect("must not fail");
+        let error = svc.oneshot(true).await.expect_err("must fail");
+        assert!(crate::is_caused_by::<Panicked>(&*error));
+        assert_eq!(error.to_string(), "panicked: boom");
+        assert_eq!(panics_total(), before + 1);
+
+        let recorded = RECENT_ERRORS
+            .records()
+            .into_iter()
+            .rev()
+            .find(|r| r.error == "panicked: boom")
+            .expect("panic must be recorded");
+        assert!(recorded.backtrace.is_some());
+    }
+}
@@ -0,0 +1,124 @@
+use parking_lot::{const_mutex, Mutex};
+use std::{collections::VecDeque, time::SystemTime};
+
+/// The errors most recently recorded by the proxy, so that they may be
+/// inspected via the admin server.
+pub static RECENT_ERRORS: RecentErrors = RecentErrors::new(100);
+
+/// A bounded buffer of error records. Once the buffer is full, the oldest
+/// record is discarded as each new record is added.
+#[derive(Debug)]
+pub struct RecentErrors {
+    capacity: usize,
+    records: Mutex<VecDeque<ErrorRecord>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ErrorRecord {
+    pub timestamp: SystemTime,
+    pub error: String,
+
+    /// The errors that caused this error, outermost first.
+    pub sources: Vec<String>,
+
+    /// Describes the target (e.g. the connection's addresses) for which the
+    /// error occurred.
+    pub labels: Vec<(&'static str, String)>,
+
+    /// The backtrace captured when the error occurred, if one is available.
+    pub backtrace: Option<String>,
+}
+
+// === impl RecentErrors ===
+
+impl RecentErrors {
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            records: const_mutex(VecDeque::new()),
+        }
+    }
+
+    pub fn record(&self, record: ErrorRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.records.lock();
+        while records.len() >= self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Returns the buffered records, oldest first.
+    pub fn records(&self) -> Vec<ErrorRecord> {
+        self.records.lock().iter().cloned().collect()
+    }
+}
+
+// === impl ErrorRecord ===
+
+impl ErrorRecord {
+    /// Describes `error` and the chain of errors that caused it.
+    pub fn new(error: &(dyn std::error::Error + 'static)) -> Self {
+        let mut sources = Vec::new();
+        let mut source = error.source();
+        while let Some(e) = source {
+            sources.push(e.to_string());
+            source = e.source();
+        }
+        Self {
+            timestamp: SystemTime::now(),
+            error: error.to_string(),
+            sources,
+            labels: Vec::new(),
+            backtrace: None,
+        }
+    }
+
+    pub fn with_label(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.labels.push((key, value.to_string()));
+        self
+    }
+
+    pub fn with_backtrace(mut self, backtrace: Option<String>) -> Self {
+        self.backtrace = backtrace;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("outer")]
+    struct Outer(#[source] std::io::Error);
+
+    fn record(error: &str) -> ErrorRecord {
+        ErrorRecord::new(&*crate::Error::from(error))
+    }
+
+    #[test]
+    fn discards_oldest() {
+        let recent = RecentErrors::new(2);
+        recent.record(record("a"));
+        recent.record(record("b"));
+        recent.record(record("c"));
+        let errors = recent
+            .records()
+            .into_iter()
+            .map(|r| r.error)
+            .collect::<Vec<_>>();
+        assert_eq!(errors, ["b", "c"]);
+    }
+
+    #[test]
+    fn describes_sources() {
+        let error = Outer(std::io::Error::new(std::io::ErrorKind::Other, "inner"));
+        let record = ErrorRecord::new(&error).with_label("direction", "inbound");
+        assert_eq!(record.error, "outer");
+        assert_eq!(record.sources, ["inner"]);
+        assert_eq!(record.labels, [("direction", "inbound".to_string())]);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    metrics::{metrics, Counter, FmtLabels, FmtMetrics, Metric},
+    svc, Error,
+};
+use futures::{ready, TryFuture};
+use linkerd_proxy_http::{h2::Reason, BoxBody, HasH2Reason, HttpBody};
+use parking_lot::RwLock;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+metrics! {
+    inbound_http_stream_resets_total: Counter {
+        "The total number of inbound HTTP/2 streams that were reset, by error code."
+    },
+    outbound_http_stream_resets_total: Counter {
+        "The total number of outbound HTTP/2 streams that were reset, by error code."
+    }
+}
+
+/// Counts HTTP/2 streams that fail with a `RST_STREAM` (or `GOAWAY`) error
+/// code.
+///
+/// A stream's reset reason is recorded when its response fails or when either
+/// its request or response body fails with an HTTP/2 error. Each stream is
+/// counted at most once.
+#[derive(Clone, Debug)]
+pub struct StreamResets {
+    metric: Metric<'static, &'static str, Counter>,
+    by_reason: Arc<RwLock<HashMap<ResetReason, Counter>>>,
+}
+
+/// Records the reset reasons of the streams handled by the inner service.
+#[derive(Clone, Debug)]
+pub struct RecordResets<S> {
+    inner: S,
+    resets: StreamResets,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    stream: Stream,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResetBody {
+    #[pin]
+    inner: BoxBody,
+    stream: Stream,
+}
+
+/// Tracks whether a stream's reset has already been recorded.
+#[derive(Clone, Debug)]
+struct Stream {
+    resets: StreamResets,
+    recorded: Arc<AtomicBool>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct ResetReason(u32);
+
+// === impl StreamResets ===
+
+impl StreamResets {
+    pub fn inbound() -> Self {
+        Self::new(inbound_http_stream_resets_total)
+    }
+
+    pub fn outbound() -> Self {
+        Self::new(outbound_http_stream_resets_total)
+    }
+
+    fn new(metric: Metric<'static, &'static str, Counter>) -> Self {
+        Self {
+            metric,
+            by_reason: Default::default(),
+        }
+    }
+
+    pub fn layer<S>(&self) -> impl svc::layer::Layer<S, Service = RecordResets<S>> + Clone {
+        let resets = self.clone();
+        svc::layer::mk(move |inner| RecordResets {
+            inner,
+            resets: resets.clone(),
+        })
+    }
+
+    fn record(&self, reason: Reason) {
+        self.by_reason
+            .write()
+            .entry(ResetReason(reason.into()))
+            .or_default()
+            .incr();
+    }
+}
+
+impl FmtMetrics for StreamResets {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let by_reason = self.by_reason.read();
+        if by_reason.is_empty() {
+            return Ok(());
+        }
+        self.metric.fmt_help(f)?;
+        self.metric.fmt_scopes(f, by_reason.iter(), |c| c)
+    }
+}
+
+// === impl RecordResets ===
+
+impl<S> svc::Service<http::Request<BoxBody>> for RecordResets<S>
+where
+    S: svc::Service<http::Request<BoxBody>, Response = http::Response<BoxBody>>,
+    S::Error: Into<Error>,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        let stream = Stream {
+            resets: self.resets.clone(),
+            recorded: Arc::new(AtomicBool::new(false)),
+        };
+        let req = req.map(|inner| {
+            BoxBody::new(ResetBody {
+                inner,
+                stream: stream.clone(),
+            })
+        });
+        ResponseFuture {
+            inner: self.inner.call(req),
+            stream,
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: TryFuture<Ok = http::Response<BoxBody>>,
+    F::Error: Into<Error>,
+{
+    type Output = Result<http::Response<BoxBody>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match ready!(this.inner.try_poll(cx)) {
+            Ok(rsp) => {
+                let stream = this.stream.clone();
+                Poll::Ready(Ok(
+                    rsp.map(|inner| BoxBody::new(ResetBody { inner, stream }))
+                ))
+            }
+            Err(e) => {
+                let error = e.into();
+                this.stream.observe(&error);
+                Poll::Ready(Err(error))
+            }
+        }
+    }
+}
+
+// === impl ResetBody ===
+
+impl HttpBody for ResetBody {
+    type Data = <BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let res = ready!(this.inner.poll_data(cx));
+        if let Some(Err(error)) = res.as_ref() {
+            this.stream.observe(error);
+        }
+        Poll::Ready(res)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let this = self.project();
+        let res = ready!(this.inner.poll_trailers(cx));
+        if let Err(error) = res.as_ref() {
+            this.stream.observe(error);
+        }
+        Poll::Ready(res)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl Stream ===
+
+impl Stream {
+    fn observe(&self, error: &Error) {
+        if let Some(reason) = error.h2_reason() {
+            if !self.recorded.swap(true, Ordering::Relaxed) {
+                self.resets.record(reason);
+            }
+        }
+    }
+}
+
+// === impl ResetReason ===
+
+impl FmtLabels for ResetReason {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Formats the error code as it is named in the spec, e.g.
+        // `REFUSED_STREAM`.
+        write!(f, "reason=\"{:?}\"", Reason::from(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_reasons() {
+        let resets = StreamResets::outbound();
+        resets.record(Reason::CANCEL);
+        resets.record(Reason::CANCEL);
+        resets.record(Reason::REFUSED_STREAM);
+        resets.record(Reason::ENHANCE_YOUR_CALM);
+
+        let text = resets.as_display().to_string();
+        assert!(text.contains("# TYPE outbound_http_stream_resets_total counter\n"));
+        assert!(text.contains("outbound_http_stream_resets_total{reason=\"CANCEL\"} 2\n"));
+        assert!(text.contains("outbound_http_stream_resets_total{reason=\"REFUSED_STREAM\"} 1\n"));
+        assert!(
+            text.contains("outbound_http_stream_resets_total{reason=\"ENHANCE_YOUR_CALM\"} 1\n")
+        );
+    }
+
+    #[test]
+    fn records_each_stream_once() {
+        let resets = StreamResets::inbound();
+        let stream = Stream {
+            resets: resets.clone(),
+            recorded: Arc::new(AtomicBool::new(false)),
+        };
+        let error = Error::from(linkerd_proxy_http::h2::H2Error::from(Reason::CANCEL));
+        stream.observe(&error);
+        stream.observe(&error);
+        stream.observe(&Error::from("not a reset"));
+
+        let text = resets.as_display().to_string();
+        assert!(text.contains("inbound_http_stream_resets_total{reason=\"CANCEL\"} 1\n"));
+    }
+}
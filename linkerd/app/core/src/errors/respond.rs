@@ -1,4 +1,6 @@
+use super::ErrorCode;
 use crate::svc;
+use bytes::Bytes;
 use http::header::{HeaderValue, LOCATION};
 use linkerd_error::{Error, Result};
 use linkerd_error_respond as respond;
@@ -15,6 +17,7 @@ use tracing::{debug, info_span, warn};
 
 pub const L5D_PROXY_CONNECTION: &str = "l5d-proxy-connection";
 pub const L5D_PROXY_ERROR: &str = "l5d-proxy-error";
+pub const L5D_PROXY_ERROR_CODE: &str = "l5d-proxy-error-code";
 
 pub fn layer<R, P: Clone, N>(
     params: P,
@@ -33,6 +36,7 @@ pub trait HttpRescue<E> {
 
 #[derive(Clone, Debug)]
 pub struct SyntheticHttpResponse {
+    code: ErrorCode,
     grpc_status: tonic::Code,
     http_status: http::StatusCode,
     close_connection: bool,
@@ -75,6 +79,7 @@ pub enum ResponseBody<R, B> {
 }
 
 const GRPC_CONTENT_TYPE: &str = "application/grpc";
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
 const GRPC_STATUS: &str = "grpc-status";
 const GRPC_MESSAGE: &str = "grpc-message";
 
@@ -98,6 +103,7 @@ impl SyntheticHttpResponse {
 
     pub fn internal_error(msg: impl Into<Cow<'static, str>>) -> Self {
         Self {
+            code: ErrorCode::Internal,
             close_connection: true,
             http_status: http::StatusCode::INTERNAL_SERVER_ERROR,
             grpc_status: tonic::Code::Internal,
@@ -108,6 +114,7 @@ impl SyntheticHttpResponse {
 
     pub fn bad_gateway(msg: impl ToString) -> Self {
         Self {
+            code: ErrorCode::BadGateway,
             close_connection: true,
             http_status: http::StatusCode::BAD_GATEWAY,
             grpc_status: tonic::Code::Unavailable,
@@ -118,6 +125,7 @@ impl SyntheticHttpResponse {
 
     pub fn gateway_timeout(msg: impl ToString) -> Self {
         Self {
+            code: ErrorCode::ResponseTimeout,
             close_connection: true,
             http_status: http::StatusCode::GATEWAY_TIMEOUT,
             grpc_status: tonic::Code::Unavailable,
@@ -126,8 +134,22 @@ impl SyntheticHttpResponse {
         }
     }
 
+    /// Describes a request that timed out. gRPC clients are informed that
+    /// their deadline was exceeded.
+    pub fn timeout(msg: impl ToString) -> Self {
+        Self {
+            code: ErrorCode::ResponseTimeout,
+            close_connection: true,
+            http_status: http::StatusCode::GATEWAY_TIMEOUT,
+            grpc_status: tonic::Code::DeadlineExceeded,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+        }
+    }
+
     pub fn unavailable(msg: impl ToString) -> Self {
         Self {
+            code: ErrorCode::LoadShed,
             close_connection: true,
             http_status: http::StatusCode::SERVICE_UNAVAILABLE,
             grpc_status: tonic::Code::Unavailable,
@@ -136,8 +158,35 @@ impl SyntheticHttpResponse {
         }
     }
 
+    /// Describes a request that its client cancelled. gRPC clients are
+    /// informed that the call was cancelled rather than failed.
+    pub fn cancelled(msg: impl ToString) -> Self {
+        Self {
+            code: ErrorCode::Cancelled,
+            close_connection: false,
+            http_status: http::StatusCode::BAD_GATEWAY,
+            grpc_status: tonic::Code::Cancelled,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+        }
+    }
+
+    /// Describes a request that exceeded its route's rate limit. Clients may
+    /// retry the request once the limit permits it.
+    pub fn rate_limited(msg: impl ToString) -> Self {
+        Self {
+            code: ErrorCode::RateLimited,
+            close_connection: false,
+            http_status: http::StatusCode::TOO_MANY_REQUESTS,
+            grpc_status: tonic::Code::ResourceExhausted,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+        }
+    }
+
     pub fn unauthenticated(msg: impl ToString) -> Self {
         Self {
+            code: ErrorCode::Unauthenticated,
             http_status: http::StatusCode::FORBIDDEN,
             grpc_status: tonic::Code::Unauthenticated,
             close_connection: false,
@@ -148,6 +197,7 @@ impl SyntheticHttpResponse {
 
     pub fn permission_denied(msg: impl ToString) -> Self {
         Self {
+            code: ErrorCode::Unauthorized,
             http_status: http::StatusCode::FORBIDDEN,
             grpc_status: tonic::Code::PermissionDenied,
             close_connection: false,
@@ -158,6 +208,7 @@ impl SyntheticHttpResponse {
 
     pub fn loop_detected(msg: impl ToString) -> Self {
         Self {
+            code: ErrorCode::LoopDetected,
             http_status: http::StatusCode::LOOP_DETECTED,
             grpc_status: tonic::Code::Aborted,
             close_connection: true,
@@ -168,6 +219,7 @@ impl SyntheticHttpResponse {
 
     pub fn not_found(msg: impl ToString) -> Self {
         Self {
+            code: ErrorCode::RouteNotFound,
             http_status: http::StatusCode::NOT_FOUND,
             grpc_status: tonic::Code::NotFound,
             close_connection: false,
@@ -176,8 +228,31 @@ impl SyntheticHttpResponse {
         }
     }
 
+    pub fn uri_too_long(msg: impl ToString) -> Self {
+        Self {
+            code: ErrorCode::UriTooLong,
+            http_status: http::StatusCode::URI_TOO_LONG,
+            grpc_status: tonic::Code::InvalidArgument,
+            close_connection: false,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+        }
+    }
+
+    pub fn payload_too_large(msg: impl ToString) -> Self {
+        Self {
+            code: ErrorCode::PayloadTooLarge,
+            http_status: http::StatusCode::PAYLOAD_TOO_LARGE,
+            grpc_status: tonic::Code::ResourceExhausted,
+            close_connection: false,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+        }
+    }
+
     pub fn redirect(http_status: http::StatusCode, location: &http::Uri) -> Self {
         Self {
+            code: ErrorCode::Redirect,
             http_status,
             grpc_status: tonic::Code::NotFound,
             close_connection: false,
@@ -191,6 +266,7 @@ impl SyntheticHttpResponse {
 
     pub fn response(http_status: http::StatusCode, message: impl Into<Cow<'static, str>>) -> Self {
         Self {
+            code: ErrorCode::InjectedFailure,
             http_status,
             location: None,
             grpc_status: tonic::Code::FailedPrecondition,
@@ -201,6 +277,7 @@ impl SyntheticHttpResponse {
 
     pub fn grpc(grpc_status: tonic::Code, message: impl Into<Cow<'static, str>>) -> Self {
         Self {
+            code: ErrorCode::InjectedFailure,
             grpc_status,
             http_status: http::StatusCode::OK,
             location: None,
@@ -209,6 +286,11 @@ impl SyntheticHttpResponse {
         }
     }
 
+    /// Overrides the response's default error code.
+    pub fn with_code(self, code: ErrorCode) -> Self {
+        Self { code, ..self }
+    }
+
     #[inline]
     fn message(&self) -> HeaderValue {
         match self.message {
@@ -232,7 +314,8 @@ impl SyntheticHttpResponse {
         if emit_headers {
             rsp = rsp
                 .header(GRPC_MESSAGE, self.message())
-                .header(L5D_PROXY_ERROR, self.message());
+                .header(L5D_PROXY_ERROR, self.message())
+                .header(L5D_PROXY_ERROR_CODE, self.code.as_str());
         }
 
         if self.close_connection && emit_headers {
@@ -245,7 +328,7 @@ impl SyntheticHttpResponse {
     }
 
     #[inline]
-    fn http_response<B: Default>(
+    fn http_response<B: Default + From<Bytes>>(
         &self,
         version: http::Version,
         emit_headers: bool,
@@ -253,17 +336,19 @@ impl SyntheticHttpResponse {
     ) -> http::Response<B> {
         debug!(
             status = %self.http_status,
+            code = %self.code,
             ?version,
             close = %self.close_connection,
             "Handling error on HTTP connection"
         );
         let mut rsp = http::Response::builder()
             .status(self.http_status)
-            .version(version)
-            .header(http::header::CONTENT_LENGTH, "0");
+            .version(version);
 
         if emit_headers {
-            rsp = rsp.header(L5D_PROXY_ERROR, self.message());
+            rsp = rsp
+                .header(L5D_PROXY_ERROR, self.message())
+                .header(L5D_PROXY_ERROR_CODE, self.code.as_str());
         }
 
         if self.close_connection {
@@ -280,13 +365,40 @@ impl SyntheticHttpResponse {
             }
         }
 
+        // Redirects are not failures, so they are not described by a problem
+        // body.
         if let Some(loc) = &self.location {
-            rsp = rsp.header(LOCATION, loc);
+            return rsp
+                .header(LOCATION, loc)
+                .header(http::header::CONTENT_LENGTH, "0")
+                .body(B::default())
+                .expect("error response must be valid");
         }
 
-        rsp.body(B::default())
+        let body = self.problem_details(emit_headers);
+        rsp.header(http::header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)
+            .header(http::header::CONTENT_LENGTH, body.len())
+            .body(B::from(body))
             .expect("error response must be valid")
     }
+
+    /// Describes the failure as an RFC 7807 problem details document.
+    ///
+    /// Error messages may describe the proxy's configuration, so they are
+    /// only included when informational headers are emitted.
+    fn problem_details(&self, emit_detail: bool) -> Bytes {
+        let mut problem = serde_json::json!({
+            "title": self.http_status.canonical_reason().unwrap_or("Proxy Error"),
+            "status": self.http_status.as_u16(),
+            "code": self.code.as_str(),
+        });
+        if emit_detail {
+            problem["detail"] = serde_json::Value::from(&*self.message);
+        }
+        serde_json::to_vec(&problem)
+            .expect("problem details must serialize")
+            .into()
+    }
 }
 
 // === impl ExtractRespond ===
@@ -368,7 +480,7 @@ impl<R> Respond<R> {
 
 impl<B, R> respond::Respond<http::Response<B>, Error> for Respond<R>
 where
-    B: Default + hyper::body::HttpBody,
+    B: Default + From<Bytes> + hyper::body::HttpBody,
     R: HttpRescue<Error> + Clone,
 {
     type Response = http::Response<ResponseBody<R, B>>;
@@ -430,6 +542,12 @@ impl<R, B: Default + hyper::body::HttpBody> Default for ResponseBody<R, B> {
     }
 }
 
+impl<R, B: From<Bytes>> From<Bytes> for ResponseBody<R, B> {
+    fn from(bytes: Bytes) -> Self {
+        ResponseBody::Passthru(B::from(bytes))
+    }
+}
+
 impl<R, B> hyper::body::HttpBody for ResponseBody<R, B>
 where
     B: hyper::body::HttpBody<Error = Error>,
@@ -545,3 +663,60 @@ fn code_header(code: tonic::Code) -> HeaderValue {
         Code::Unauthenticated => HeaderValue::from_static("16"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn problem_details() {
+        let rsp = SyntheticHttpResponse::gateway_timeout("no endpoints available")
+            .with_code(ErrorCode::FailFast)
+            .http_response::<hyper::Body>(http::Version::HTTP_11, true, false);
+        assert_eq!(rsp.status(), http::StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(rsp.headers()[L5D_PROXY_ERROR_CODE], "failfast");
+        assert_eq!(
+            rsp.headers()[http::header::CONTENT_TYPE],
+            PROBLEM_CONTENT_TYPE
+        );
+
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            problem,
+            serde_json::json!({
+                "title": "Gateway Timeout",
+                "status": 504,
+                "code": "failfast",
+                "detail": "no endpoints available",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn problem_details_without_detail() {
+        let rsp = SyntheticHttpResponse::not_found("no route").http_response::<hyper::Body>(
+            http::Version::HTTP_2,
+            false,
+            false,
+        );
+        assert!(rsp.headers().get(L5D_PROXY_ERROR_CODE).is_none());
+
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["code"], "route-not-found");
+        assert!(problem.get("detail").is_none());
+    }
+
+    #[test]
+    fn grpc_timeout() {
+        let rsp = SyntheticHttpResponse::timeout("HTTP response timeout after 1s")
+            .grpc_response::<hyper::Body>(true);
+        assert_eq!(rsp.status(), http::StatusCode::OK);
+        assert_eq!(rsp.headers()[GRPC_STATUS], "4");
+        assert_eq!(
+            rsp.headers()[GRPC_MESSAGE],
+            "HTTP response timeout after 1s"
+        );
+    }
+}
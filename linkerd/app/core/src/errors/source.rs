@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// Identifies the party that caused the proxy to fail a request.
+///
+/// Sources label the proxy's error metrics so that failures caused by a
+/// request's client or upstream can be distinguished from those caused by the
+/// proxy itself.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorSource {
+    /// The request's client cancelled the request or sent a malformed request.
+    Client,
+    /// The proxy failed the request, e.g. due to a timeout or policy.
+    Proxy,
+    /// The request's upstream refused the connection or reset the stream.
+    Upstream,
+}
+
+// === impl ErrorSource ===
+
+impl ErrorSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Client => "client",
+            Self::Proxy => "proxy",
+            Self::Upstream => "upstream",
+        }
+    }
+}
+
+impl fmt::Display for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
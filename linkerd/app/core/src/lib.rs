@@ -34,6 +34,7 @@ pub use linkerd_addr::{self as addr, Addr, AddrMatch, IpMatch, NameAddr, NameMat
 pub use linkerd_conditional::Conditional;
 pub use linkerd_detect as detect;
 pub use linkerd_dns;
+pub use linkerd_duplex as duplex;
 pub use linkerd_error::{cause_ref, is_caused_by, Error, Infallible, Recover, Result};
 pub use linkerd_exp_backoff as exp_backoff;
 pub use linkerd_http_metrics as http_metrics;
@@ -44,6 +45,7 @@ pub use linkerd_service_profiles as profiles;
 pub use linkerd_stack_metrics as stack_metrics;
 pub use linkerd_stack_tracing as stack_tracing;
 pub use linkerd_tls as tls;
+pub use linkerd_trace_context as trace_context;
 pub use linkerd_tracing as trace;
 pub use linkerd_transport_header as transport_header;
 
@@ -15,15 +15,38 @@ use crate::{
     transport::{self, labels::TlsConnect},
 };
 use linkerd_addr::Addr;
+use linkerd_io::{BufPool, BufPoolStats};
 pub use linkerd_metrics::*;
 use linkerd_proxy_server_policy as policy;
 use std::{
+    collections::HashMap,
     fmt::{self, Write},
     net::SocketAddr,
     sync::Arc,
     time::Duration,
 };
 
+metrics! {
+    buffer_pool_buffers_max_idle: Gauge {
+        "The maximum number of buffers held by a buffer pool for reuse."
+    },
+    buffer_pool_buffers_idle: Gauge {
+        "The number of buffers held by a buffer pool for reuse."
+    },
+    buffer_pool_buffers_in_use: Gauge {
+        "The number of buffers taken from a buffer pool that have not been returned."
+    },
+    buffer_pool_allocations_total: Counter {
+        "The total number of buffers allocated by a buffer pool."
+    },
+    buffer_pool_reuses_total: Counter {
+        "The total number of buffers reused from a buffer pool."
+    },
+    panics_total: Counter {
+        "The total number of panics caught while handling connections and requests."
+    }
+}
+
 pub type ControlHttp = http_metrics::Requests<ControlLabels, Class>;
 
 pub type HttpEndpoint = http_metrics::Requests<EndpointLabels, Class>;
@@ -48,6 +71,8 @@ pub struct Proxy {
     pub http_profile_route_retry: HttpProfileRouteRetry,
     pub http_endpoint: HttpEndpoint,
     pub transport: transport::Metrics,
+    pub peers: transport::metrics::peer::Registry,
+    pub socket_health: transport::metrics::health::Registry,
     pub stack: Stack,
 }
 
@@ -124,6 +149,16 @@ pub enum Direction {
     Out,
 }
 
+/// Reports the utilization of the proxy's shared buffer pools.
+#[derive(Copy, Clone, Debug)]
+struct BufPools(());
+
+#[derive(Clone, Debug)]
+struct Panics(());
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct BufPoolLabel(&'static str);
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Authority<'a>(&'a http::uri::Authority);
 
@@ -144,20 +179,47 @@ where
 
 impl Metrics {
     pub fn new(retain_idle: Duration) -> (Self, impl FmtMetrics + Clone + Send + 'static) {
+        Self::with_latency_bounds(retain_idle, &HashMap::default(), None, None)
+    }
+
+    /// Like [`Metrics::new`], but response latency histograms use the bounds
+    /// configured for their metric family (e.g. `route_response_latency_ms`),
+    /// if any, and per-peer and socket health transport metrics are recorded
+    /// if configured.
+    pub fn with_latency_bounds(
+        retain_idle: Duration,
+        latency_bounds: &HashMap<String, &'static Bounds>,
+        peers: Option<transport::metrics::peer::Config>,
+        socket_health: Option<transport::metrics::health::Config>,
+    ) -> (Self, impl FmtMetrics + Clone + Send + 'static) {
+        let latency_buckets = |family: &str| {
+            latency_bounds
+                .get(family)
+                .copied()
+                .map(latency::Buckets::Fixed)
+                .unwrap_or_default()
+        };
+
         let (control, control_report) = {
-            let m = http_metrics::Requests::<ControlLabels, Class>::default();
+            let m = http_metrics::Requests::<ControlLabels, Class>::new(latency_buckets(
+                "control_response_latency_ms",
+            ));
             let r = m.clone().into_report(retain_idle).with_prefix("control");
             (m, r)
         };
 
         let (http_endpoint, endpoint_report) = {
-            let m = http_metrics::Requests::<EndpointLabels, Class>::default();
+            let m = http_metrics::Requests::<EndpointLabels, Class>::new(latency_buckets(
+                "response_latency_ms",
+            ));
             let r = m.clone().into_report(retain_idle);
             (m, r)
         };
 
         let (http_profile_route, profile_route_report) = {
-            let m = http_metrics::Requests::<ProfileRouteLabels, Class>::default();
+            let m = http_metrics::Requests::<ProfileRouteLabels, Class>::new(latency_buckets(
+                "route_response_latency_ms",
+            ));
             let r = m.clone().into_report(retain_idle).with_prefix("route");
             (m, r)
         };
@@ -180,6 +242,12 @@ impl Metrics {
         let stack = stack_metrics::Registry::default();
 
         let (transport, transport_report) = transport::Metrics::new(retain_idle);
+        let (peers, peers_report) = peers
+            .map(|config| transport::metrics::peer::new(config, retain_idle))
+            .unwrap_or_default();
+        let (socket_health, socket_health_report) = socket_health
+            .map(transport::metrics::health::new)
+            .unwrap_or_default();
 
         let proxy = Proxy {
             http_endpoint,
@@ -188,6 +256,8 @@ impl Metrics {
             http_profile_route_actual,
             stack: stack.clone(),
             transport,
+            peers,
+            socket_health,
         };
 
         let (opencensus, opencensus_report) = opencensus::metrics::new();
@@ -204,13 +274,77 @@ impl Metrics {
             .and_report(actual_report)
             .and_report(control_report)
             .and_report(transport_report)
+            .and_report(peers_report)
+            .and_report(socket_health_report)
             .and_report(opencensus_report)
-            .and_report(stack);
+            .and_report(stack)
+            .and_report(BufPools(()))
+            .and_report(Panics(()));
 
         (metrics, report)
     }
 }
 
+// === impl Panics ===
+
+impl FmtMetrics for Panics {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        panics_total.fmt_help(f)?;
+        let panics = Counter::from(crate::errors::panic::panics_total());
+        panics_total.fmt_metric(f, &panics)
+    }
+}
+
+// === impl BufPools ===
+
+impl FmtMetrics for BufPools {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pools: [(&'static str, &BufPool); 1] = [("tcp_copy", &linkerd_duplex::COPY_BUFFERS)];
+        let stats = pools
+            .into_iter()
+            .map(|(name, pool)| (BufPoolLabel(name), pool.stats()))
+            .collect::<Vec<(BufPoolLabel, BufPoolStats)>>();
+
+        buffer_pool_buffers_max_idle.fmt_help(f)?;
+        for (label, stats) in &stats {
+            let max_idle = Gauge::from(stats.max_idle as u64);
+            buffer_pool_buffers_max_idle.fmt_metric_labeled(f, &max_idle, label)?;
+        }
+
+        buffer_pool_buffers_idle.fmt_help(f)?;
+        for (label, stats) in &stats {
+            let idle = Gauge::from(stats.idle as u64);
+            buffer_pool_buffers_idle.fmt_metric_labeled(f, &idle, label)?;
+        }
+
+        buffer_pool_buffers_in_use.fmt_help(f)?;
+        for (label, stats) in &stats {
+            let in_use = Gauge::from(stats.in_use as u64);
+            buffer_pool_buffers_in_use.fmt_metric_labeled(f, &in_use, label)?;
+        }
+
+        buffer_pool_allocations_total.fmt_help(f)?;
+        for (label, stats) in &stats {
+            let allocated = Counter::from(stats.allocated);
+            buffer_pool_allocations_total.fmt_metric_labeled(f, &allocated, label)?;
+        }
+
+        buffer_pool_reuses_total.fmt_help(f)?;
+        for (label, stats) in &stats {
+            let reused = Counter::from(stats.reused);
+            buffer_pool_reuses_total.fmt_metric_labeled(f, &reused, label)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FmtLabels for BufPoolLabel {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pool=\"{}\"", self.0)
+    }
+}
+
 // === impl CtlLabels ===
 
 impl Param<ControlLabels> for control::ControlAddr {
@@ -1,11 +1,11 @@
 use crate::{
-    io, is_caused_by,
+    errors, io, is_caused_by,
     svc::{self, Param},
     Result,
 };
 use futures::prelude::*;
 use linkerd_error::Error;
-use linkerd_proxy_transport::AddrPair;
+use linkerd_proxy_transport::{AddrPair, ClientAddr, ServerAddr};
 use tower::util::ServiceExt;
 use tracing::{debug, debug_span, info, instrument::Instrument, warn};
 
@@ -45,9 +45,10 @@ pub async fn serve<M, S, I, A>(
                     let accept = new_accept.new_service(addrs);
 
                     // Dispatch all of the work for a given connection onto a
-                    // connection-specific task.
+                    // connection-specific task. Panics are caught so that they
+                    // are recorded before the task completes.
                     tokio::spawn(
-                        async move {
+                        errors::panic::catch(async move {
                             match accept.ready_oneshot().err_into::<Error>().await {
                                 Ok(mut accept) => {
                                     match accept
@@ -71,6 +72,7 @@ pub async fn serve<M, S, I, A>(
                                                 server.addr = %server_addr,
                                                 "Connection closed"
                                             );
+                                            record_error(&*error, client_addr, server_addr);
                                         }
                                     }
                                     // Hold the service until the connection is complete. This
@@ -80,9 +82,10 @@ pub async fn serve<M, S, I, A>(
                                 }
                                 Err(error) => {
                                     warn!(error, client.addr = %client_addr, "Server failed to become ready");
+                                    record_error(&*error, client_addr, server_addr);
                                 }
                             }
-                        }
+                        })
                         .instrument(span.exit().or_current()),
                     );
                 }
@@ -99,3 +102,15 @@ pub async fn serve<M, S, I, A>(
         _ = shutdown => {}
     }
 }
+
+fn record_error(
+    error: &(dyn std::error::Error + 'static),
+    client_addr: ClientAddr,
+    server_addr: ServerAddr,
+) {
+    errors::recent::RECENT_ERRORS.record(
+        errors::recent::ErrorRecord::new(error)
+            .with_label("client.addr", client_addr)
+            .with_label("server.addr", server_addr),
+    );
+}
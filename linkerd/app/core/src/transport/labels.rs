@@ -75,10 +75,19 @@ impl FmtLabels for Key {
 
 impl ServerLabels {
     fn inbound(
-        tls: tls::ConditionalServerTls,
+        mut tls: tls::ConditionalServerTls,
         target_addr: SocketAddr,
         policy: PolicyServerLabel,
     ) -> Self {
+        // Certificates are not labeled, so fingerprints must not distinguish
+        // metrics that are otherwise labeled identically.
+        if let Conditional::Some(tls::ServerTls::Established {
+            ref mut client_fingerprint,
+            ..
+        }) = tls
+        {
+            *client_fingerprint = None;
+        }
         ServerLabels {
             direction: Direction::In,
             tls,
@@ -196,6 +205,7 @@ mod tests {
         let labels = ServerLabels::inbound(
             tls::ConditionalServerTls::Some(tls::ServerTls::Established {
                 client_id: Some("foo.id.example.com".parse().unwrap()),
+                client_fingerprint: None,
                 negotiated_protocol: None,
             }),
             ([192, 0, 2, 4], 40000).into(),
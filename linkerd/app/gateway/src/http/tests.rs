@@ -78,6 +78,7 @@ async fn upgraded_request_remains_relative_form() {
         fn param(&self) -> tls::ConditionalServerTls {
             tls::ConditionalServerTls::Some(tls::ServerTls::Established {
                 client_id: Some(self.param()),
+                client_fingerprint: None,
                 negotiated_protocol: None,
             })
         }
@@ -129,8 +130,12 @@ async fn upgraded_request_remains_relative_form() {
                             kind: "authorizationpolicy".into(),
                             name: "testsaz".into(),
                         }),
+                        local_addrs: vec![],
+                        validity: vec![],
                     }]))]),
                 },
+                bandwidth_limit: None,
+                duplex: Default::default(),
             };
             let (policy, tx) = inbound::policy::AllowPolicy::for_test(self.param(), policy);
             tokio::spawn(async move {
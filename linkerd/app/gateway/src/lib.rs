@@ -88,7 +88,9 @@ impl Gateway {
                 .outbound
                 .to_tcp_connect()
                 .push_tcp_endpoint()
-                .push_http_tcp_client();
+                .push_http_tcp_client(outbound::http::PoolMetrics::register(
+                    registry.sub_registry_with_prefix("http_pool"),
+                ));
             let http = self.http(registry, http.into_inner(), resolve);
             self.inbound
                 .clone()
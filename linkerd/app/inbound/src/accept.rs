@@ -10,6 +10,11 @@ use linkerd_app_core::{
 use std::fmt::Debug;
 use tracing::info_span;
 
+mod bandwidth;
+mod limit;
+
+pub use self::limit::{ConnectionLimits, ShedMetrics};
+
 #[derive(Clone, Debug)]
 pub(crate) struct Accept {
     client_addr: Remote<ClientAddr>,
@@ -35,7 +40,7 @@ impl<N> Inbound<N> {
         I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr,
         I: Debug + Send + Sync + Unpin + 'static,
         N: svc::NewService<Accept, Service = NSvc> + Clone + Send + Sync + Unpin + 'static,
-        NSvc: svc::Service<I, Response = ()>,
+        NSvc: svc::Service<io::RateLimitedIo<I>, Response = ()>,
         NSvc: Send + Unpin + 'static,
         NSvc::Error: Into<Error>,
         NSvc::Future: Send,
@@ -46,6 +51,7 @@ impl<N> Inbound<N> {
     {
         self.map_stack(|cfg, rt, accept| {
             accept
+                .push(bandwidth::NewRateLimitIo::layer())
                 .push_switch(
                     // Switch to the `direct` stack when a connection's original destination is the
                     // proxy's inbound port. Otherwise, check that connections are allowed on the
@@ -69,6 +75,10 @@ impl<N> Inbound<N> {
                 .check_new_service::<T, I>()
                 .push_filter(cfg.allowed_ips.clone())
                 .push(rt.metrics.tcp_errors.to_layer())
+                .push(limit::NewLimitConnections::layer(
+                    cfg.connection_limits.clone(),
+                    rt.metrics.connections_shed.clone(),
+                ))
                 .check_new_service::<T, I>()
                 .instrument(|t: &T| {
                     let OrigDstAddr(addr) = t.param();
@@ -134,6 +144,8 @@ mod tests {
                             kind: "serverauthorization".into(),
                             name: "testsaz".into(),
                         }),
+                        local_addrs: vec![],
+                        validity: vec![],
                     },
                 ])),
                 meta: Arc::new(Meta::Resource {
@@ -141,6 +153,8 @@ mod tests {
                     kind: "server".into(),
                     name: "testsrv".into(),
                 }),
+                bandwidth_limit: None,
+                duplex: Default::default(),
             },
             None,
         );
@@ -187,11 +201,11 @@ mod tests {
         Inbound::new(test_util::default_config(), test_util::runtime().0)
     }
 
-    fn new_panic<T>(msg: &'static str) -> svc::ArcNewTcp<T, io::DuplexStream> {
+    fn new_panic<T, I: 'static>(msg: &'static str) -> svc::ArcNewTcp<T, I> {
         svc::ArcNewService::new(move |_| panic!("{msg}"))
     }
 
-    fn new_ok<T>() -> svc::ArcNewTcp<T, io::DuplexStream> {
+    fn new_ok<T, I: 'static>() -> svc::ArcNewTcp<T, I> {
         svc::ArcNewService::new(|_| svc::BoxService::new(svc::mk(|_| future::ok::<(), Error>(()))))
     }
 
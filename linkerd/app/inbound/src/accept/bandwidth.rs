@@ -0,0 +1,69 @@
+//! Limits the rate at which bytes are transferred on inbound connections.
+
+use crate::policy::AllowPolicy;
+use linkerd_app_core::{io, svc};
+use std::{
+    num::NonZeroU64,
+    task::{Context, Poll},
+};
+
+/// Applies the server's bandwidth limit to each accepted connection.
+#[derive(Clone, Debug)]
+pub(super) struct NewRateLimitIo<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct RateLimitIo<S> {
+    inner: S,
+    bandwidth_limit: Option<NonZeroU64>,
+}
+
+// === impl NewRateLimitIo ===
+
+impl<N> NewRateLimitIo<N> {
+    pub(super) fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRateLimitIo<N>
+where
+    T: svc::Param<AllowPolicy>,
+    N: svc::NewService<T>,
+{
+    type Service = RateLimitIo<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        // The limit is determined when the connection is accepted; policy
+        // updates only apply to subsequent connections.
+        let policy: AllowPolicy = target.param();
+        let bandwidth_limit = policy.borrow().bandwidth_limit;
+        RateLimitIo {
+            inner: self.inner.new_service(target),
+            bandwidth_limit,
+        }
+    }
+}
+
+// === impl RateLimitIo ===
+
+impl<S, I> svc::Service<I> for RateLimitIo<S>
+where
+    S: svc::Service<io::RateLimitedIo<I>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    #[inline]
+    fn call(&mut self, io: I) -> Self::Future {
+        self.inner
+            .call(io::RateLimitedIo::new(io, self.bandwidth_limit))
+    }
+}
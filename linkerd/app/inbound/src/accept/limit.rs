@@ -0,0 +1,380 @@
+//! Sheds inbound connections that exceed the proxy's connection limits.
+
+use linkerd_app_core::{
+    io,
+    metrics::{metrics, Counter, FmtLabels, FmtMetrics},
+    svc,
+    transport::addrs::{ClientAddr, Remote},
+    Error,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt,
+    net::IpAddr,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time::Instant;
+use tracing::debug;
+
+metrics! {
+    inbound_tcp_connections_shed_total: Counter {
+        "The total number of inbound TCP connections that were closed because they exceeded a connection limit."
+    }
+}
+
+/// Limits the connections accepted by the inbound proxy.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    /// The maximum number of inbound connections that may be open at once.
+    pub max_connections: Option<usize>,
+
+    /// The maximum number of inbound connections that may be open at once
+    /// from a single client IP address.
+    pub max_connections_per_client: Option<usize>,
+
+    /// The maximum number of inbound connections that may be accepted per
+    /// second.
+    pub max_accept_rate: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ShedMetrics(Arc<Mutex<HashMap<ShedReason, Counter>>>);
+
+/// Sheds connections that exceed the configured limits.
+#[derive(Clone, Debug)]
+pub struct NewLimitConnections<N> {
+    inner: N,
+    state: Arc<Mutex<State>>,
+    limits: ConnectionLimits,
+    metrics: ShedMetrics,
+}
+
+/// Holds a connection's place in the connection limits until the connection
+/// completes.
+#[derive(Debug)]
+pub struct Limited<S> {
+    inner: S,
+    _permit: Permit,
+}
+
+/// Closes connections that exceed a limit.
+#[derive(Copy, Clone, Debug)]
+pub struct Shed(());
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum ShedReason {
+    AcceptRate,
+    MaxConnections,
+    MaxConnectionsPerClient,
+}
+
+#[derive(Debug)]
+struct State {
+    open: usize,
+    by_client: HashMap<IpAddr, usize>,
+    accept_rate: Option<AcceptRate>,
+}
+
+/// A token bucket that limits the rate at which connections are accepted.
+#[derive(Debug)]
+struct AcceptRate {
+    per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+#[derive(Debug)]
+struct Permit {
+    client: IpAddr,
+    state: Arc<Mutex<State>>,
+}
+
+// === impl NewLimitConnections ===
+
+impl<N> NewLimitConnections<N> {
+    pub fn layer(
+        limits: ConnectionLimits,
+        metrics: ShedMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let state = Arc::new(Mutex::new(State {
+            open: 0,
+            by_client: HashMap::default(),
+            accept_rate: limits.max_accept_rate.map(AcceptRate::new),
+        }));
+        svc::layer::mk(move |inner| Self {
+            inner,
+            state: state.clone(),
+            limits: limits.clone(),
+            metrics: metrics.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewLimitConnections<N>
+where
+    T: svc::Param<Remote<ClientAddr>>,
+    N: svc::NewService<T>,
+{
+    type Service = svc::Either<Limited<N::Service>, Shed>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Remote(ClientAddr(client_addr)) = target.param();
+        let client = client_addr.ip();
+
+        let mut state = self.state.lock();
+        if let Some(reason) = state.check(client, &self.limits) {
+            drop(state);
+            debug!(%client, ?reason, "Shedding connection");
+            self.metrics.0.lock().entry(reason).or_default().incr();
+            return svc::Either::B(Shed(()));
+        }
+        state.open += 1;
+        *state.by_client.entry(client).or_default() += 1;
+        drop(state);
+
+        svc::Either::A(Limited {
+            inner: self.inner.new_service(target),
+            _permit: Permit {
+                client,
+                state: self.state.clone(),
+            },
+        })
+    }
+}
+
+// === impl Limited ===
+
+impl<S, I> svc::Service<I> for Limited<S>
+where
+    S: svc::Service<I>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    #[inline]
+    fn call(&mut self, io: I) -> Self::Future {
+        self.inner.call(io)
+    }
+}
+
+// === impl Shed ===
+
+impl<I: io::AsyncRead + io::AsyncWrite> svc::Service<I> for Shed {
+    type Response = ();
+    type Error = Error;
+    type Future = futures::future::Ready<Result<(), Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn call(&mut self, io: I) -> Self::Future {
+        // The connection is closed as soon as it is dropped.
+        drop(io);
+        futures::future::ok(())
+    }
+}
+
+// === impl State ===
+
+impl State {
+    fn check(&mut self, client: IpAddr, limits: &ConnectionLimits) -> Option<ShedReason> {
+        if let Some(max) = limits.max_connections {
+            if self.open >= max {
+                return Some(ShedReason::MaxConnections);
+            }
+        }
+
+        if let Some(max) = limits.max_connections_per_client {
+            if self.by_client.get(&client).copied().unwrap_or(0) >= max {
+                return Some(ShedReason::MaxConnectionsPerClient);
+            }
+        }
+
+        // The rate limit is checked last so that connections shed for other
+        // reasons do not consume the accept budget.
+        if let Some(rate) = self.accept_rate.as_mut() {
+            if !rate.acquire() {
+                return Some(ShedReason::AcceptRate);
+            }
+        }
+
+        None
+    }
+}
+
+// === impl AcceptRate ===
+
+impl AcceptRate {
+    fn new(per_second: u32) -> Self {
+        let per_second = f64::from(per_second);
+        Self {
+            per_second,
+            tokens: per_second,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn acquire(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.last_refill = now;
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.per_second).min(self.per_second);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+// === impl Permit ===
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock();
+        state.open = state.open.saturating_sub(1);
+        if let Some(count) = state.by_client.get_mut(&self.client) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                state.by_client.remove(&self.client);
+            }
+        }
+    }
+}
+
+// === impl ShedMetrics ===
+
+impl FmtMetrics for ShedMetrics {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metrics = self.0.lock();
+        if metrics.is_empty() {
+            return Ok(());
+        }
+        inbound_tcp_connections_shed_total.fmt_help(f)?;
+        inbound_tcp_connections_shed_total.fmt_scopes(f, metrics.iter(), |c| c)
+    }
+}
+
+// === impl ShedReason ===
+
+impl FmtLabels for ShedReason {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "reason=\"{}\"",
+            match self {
+                Self::AcceptRate => "accept_rate",
+                Self::MaxConnections => "max_connections",
+                Self::MaxConnectionsPerClient => "max_connections_per_client",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svc::NewService;
+    use tokio::time::Duration;
+
+    #[derive(Clone, Debug)]
+    struct Target(Remote<ClientAddr>);
+
+    impl svc::Param<Remote<ClientAddr>> for Target {
+        fn param(&self) -> Remote<ClientAddr> {
+            self.0
+        }
+    }
+
+    fn target(ip: [u8; 4]) -> Target {
+        Target(Remote(ClientAddr((ip, 40000).into())))
+    }
+
+    fn new_limit(
+        limits: ConnectionLimits,
+        metrics: ShedMetrics,
+    ) -> NewLimitConnections<fn(Target)> {
+        use svc::layer::Layer;
+        NewLimitConnections::layer(limits, metrics).layer((|_| ()) as fn(Target))
+    }
+
+    fn is_shed<S>(svc: &svc::Either<S, Shed>) -> bool {
+        matches!(svc, svc::Either::B(_))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn max_connections() {
+        let metrics = ShedMetrics::default();
+        let new_svc = new_limit(
+            ConnectionLimits {
+                max_connections: Some(2),
+                ..Default::default()
+            },
+            metrics.clone(),
+        );
+
+        let a = new_svc.new_service(target([192, 0, 2, 1]));
+        let b = new_svc.new_service(target([192, 0, 2, 2]));
+        assert!(!is_shed(&a) && !is_shed(&b));
+        assert!(is_shed(&new_svc.new_service(target([192, 0, 2, 3]))));
+
+        // Connections are permitted once others complete.
+        drop(a);
+        assert!(!is_shed(&new_svc.new_service(target([192, 0, 2, 3]))));
+
+        let counts = metrics.0.lock();
+        assert_eq!(
+            counts.get(&ShedReason::MaxConnections).unwrap().value(),
+            1.0
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn max_connections_per_client() {
+        let new_svc = new_limit(
+            ConnectionLimits {
+                max_connections_per_client: Some(1),
+                ..Default::default()
+            },
+            ShedMetrics::default(),
+        );
+
+        let a = new_svc.new_service(target([192, 0, 2, 1]));
+        assert!(!is_shed(&a));
+        assert!(is_shed(&new_svc.new_service(target([192, 0, 2, 1]))));
+        assert!(!is_shed(&new_svc.new_service(target([192, 0, 2, 2]))));
+
+        drop(a);
+        assert!(!is_shed(&new_svc.new_service(target([192, 0, 2, 1]))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn max_accept_rate() {
+        let new_svc = new_limit(
+            ConnectionLimits {
+                max_accept_rate: Some(2),
+                ..Default::default()
+            },
+            ShedMetrics::default(),
+        );
+
+        assert!(!is_shed(&new_svc.new_service(target([192, 0, 2, 1]))));
+        assert!(!is_shed(&new_svc.new_service(target([192, 0, 2, 1]))));
+        assert!(is_shed(&new_svc.new_service(target([192, 0, 2, 1]))));
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!is_shed(&new_svc.new_service(target([192, 0, 2, 1]))));
+        assert!(is_shed(&new_svc.new_service(target([192, 0, 2, 1]))));
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
 use linkerd_app_core::{
     detect, identity, io,
     metrics::ServerLabel,
-    proxy::http,
+    proxy::{http, tcp},
     svc, tls,
     transport::{
         self,
@@ -14,18 +14,24 @@ use linkerd_app_core::{
     },
     Error, Infallible,
 };
-use std::{fmt::Debug, time};
+use linkerd_proxy_server_policy::tls as sni_routes;
+use std::{fmt::Debug, net::SocketAddr, time};
 use tracing::info;
 
+mod hint;
 #[cfg(test)]
 mod tests;
 
+pub(crate) use self::hint::ProtocolHint;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct Forward {
     client_addr: Remote<ClientAddr>,
     orig_dst_addr: OrigDstAddr,
+    server_addr: Remote<ServerAddr>,
     tls: tls::ConditionalServerTls,
     permit: ServerPermit,
+    duplex: tcp::DuplexConfig,
 }
 
 #[derive(Clone, Debug)]
@@ -94,11 +100,17 @@ impl Inbound<svc::ArcNewTcp<Http, io::BoxedIo>> {
         self.map_stack(|cfg, rt, http| {
             let forward = svc::stack(forward)
                 .push_on_service(svc::MapTargetLayer::new(io::BoxedIo::new))
+                .push(hint::NewRecordHint::layer(
+                    rt.metrics.protocol_hints.clone(),
+                ))
                 .push(transport::metrics::NewServer::layer(
                     rt.metrics.proxy.transport.clone(),
                 ))
                 .push_map_target(Forward::from)
-                .push(policy::NewTcpPolicy::layer(rt.metrics.tcp_authz.clone()))
+                .push(policy::NewTcpPolicy::layer(
+                    rt.metrics.tcp_authz.clone(),
+                    cfg.revocation_grace_period,
+                ))
                 .arc_new_tcp();
 
             let detect_timeout = cfg.proxy.detect_protocol_timeout;
@@ -205,11 +217,17 @@ impl<I> Inbound<svc::ArcNewTcp<Tls, TlsIo<I>>> {
         self.map_stack(|cfg, rt, detect| {
             let forward = svc::stack(forward)
                 .push_on_service(svc::MapTargetLayer::new(io::BoxedIo::new))
+                .push(hint::NewRecordHint::layer(
+                    rt.metrics.protocol_hints.clone(),
+                ))
                 .push(transport::metrics::NewServer::layer(
                     rt.metrics.proxy.transport.clone(),
                 ))
                 .push_map_target(Forward::from)
-                .push(policy::NewTcpPolicy::layer(rt.metrics.tcp_authz.clone()))
+                .push(policy::NewTcpPolicy::layer(
+                    rt.metrics.tcp_authz.clone(),
+                    cfg.revocation_grace_period,
+                ))
                 .arc_new_tcp();
 
             let detect_timeout = cfg.proxy.detect_protocol_timeout;
@@ -281,18 +299,30 @@ impl<I> Inbound<svc::ArcNewTcp<Tls, TlsIo<I>>> {
 
 impl From<(ServerPermit, Tls)> for Forward {
     fn from((permit, tls): (ServerPermit, Tls)) -> Self {
+        let server_addr = tls
+            .sni_route_addr()
+            .unwrap_or_else(|| tls.orig_dst_addr.into());
+        let duplex = tls.policy.borrow().duplex;
         Self {
             client_addr: tls.client_addr,
             orig_dst_addr: tls.orig_dst_addr,
+            server_addr: Remote(ServerAddr(server_addr)),
             tls: tls.status,
             permit,
+            duplex,
         }
     }
 }
 
 impl svc::Param<Remote<ServerAddr>> for Forward {
     fn param(&self) -> Remote<ServerAddr> {
-        Remote(ServerAddr(self.orig_dst_addr.into()))
+        self.server_addr
+    }
+}
+
+impl svc::Param<tcp::DuplexConfig> for Forward {
+    fn param(&self) -> tcp::DuplexConfig {
+        self.duplex
     }
 }
 
@@ -308,6 +338,24 @@ impl svc::Param<transport::labels::Key> for Forward {
 
 // === impl Tls ===
 
+impl Tls {
+    /// Returns the backend address of the server's SNI route that matches a
+    /// passthrough TLS connection, if any.
+    fn sni_route_addr(&self) -> Option<SocketAddr> {
+        let Some(tls::ServerTls::Passthru { sni }) = self.status.value() else {
+            return None;
+        };
+        let policy = self.policy.borrow();
+        let Protocol::Tls { sni_routes, .. } = &policy.protocol else {
+            return None;
+        };
+        let backend = sni_routes::find(sni_routes, sni.as_str())?;
+        let addr = backend.addr(self.orig_dst_addr.into());
+        tracing::debug!(%sni, %addr, "Routing TLS connection by SNI");
+        Some(addr)
+    }
+}
+
 impl svc::Param<AllowPolicy> for Tls {
     fn param(&self) -> AllowPolicy {
         self.policy.clone()
@@ -0,0 +1,287 @@
+//! Fingerprints opaque connections so that operators can discover which
+//! protocols are served on opaque ports.
+//!
+//! Connections are never delayed or altered: the first bytes that the client
+//! sends (or, for server-first protocols, that the server sends) are
+//! inspected as they are proxied, and the resulting hint is only used to
+//! label metrics.
+
+use super::Forward;
+use crate::metrics::protocol_hints::ProtocolHintMetrics;
+use linkerd_app_core::{io, svc};
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// The protocol that an opaque connection's initial bytes resemble.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProtocolHint {
+    Tls,
+    Http,
+    Redis,
+    Postgres,
+    Mysql,
+    Unknown,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct NewRecordHint<N> {
+    metrics: ProtocolHintMetrics,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct RecordHint<S> {
+    metrics: ProtocolHintMetrics,
+    port: u16,
+    inner: S,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(super) struct HintIo<I> {
+    #[pin]
+    io: I,
+    recorder: Option<(ProtocolHintMetrics, u16)>,
+}
+
+const HTTP_METHODS: &[&[u8]] = &[
+    b"GET ",
+    b"POST ",
+    b"PUT ",
+    b"HEAD ",
+    b"DELETE ",
+    b"OPTIONS ",
+    b"PATCH ",
+    b"CONNECT ",
+    b"TRACE ",
+    b"PRI * HTTP/2",
+];
+
+// === impl ProtocolHint ===
+
+impl ProtocolHint {
+    /// Fingerprints the first bytes sent by a client.
+    pub(crate) fn from_client(buf: &[u8]) -> Self {
+        // A TLS handshake record, e.g. a ClientHello.
+        if let [0x16, 0x03, ..] = buf {
+            return Self::Tls;
+        }
+
+        if HTTP_METHODS.iter().any(|m| buf.starts_with(m)) {
+            return Self::Http;
+        }
+
+        // A RESP array of bulk strings, e.g. `*1\r\n$4\r\nPING\r\n`.
+        if let [b'*', d, ..] = buf {
+            if d.is_ascii_digit() {
+                return Self::Redis;
+            }
+        }
+
+        // A PostgreSQL StartupMessage (protocol 3.0), SSLRequest, or
+        // GSSENCRequest, each of which is prefixed by its length.
+        if let [_, _, _, _, a, b, c, d, ..] = *buf {
+            const POSTGRES_CODES: [u32; 3] = [0x0003_0000, 80877103, 80877104];
+            if POSTGRES_CODES.contains(&u32::from_be_bytes([a, b, c, d])) {
+                return Self::Postgres;
+            }
+        }
+
+        Self::Unknown
+    }
+
+    /// Fingerprints the first bytes sent by a server, before the client has
+    /// sent anything.
+    pub(crate) fn from_server(buf: &[u8]) -> Self {
+        // A MySQL handshake (protocol version 10) packet, with a 3-byte
+        // payload length and a sequence ID of 0.
+        if let [_, _, _, 0x00, 0x0a, ..] = buf {
+            return Self::Mysql;
+        }
+
+        Self::Unknown
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tls => "tls",
+            Self::Http => "http",
+            Self::Redis => "redis",
+            Self::Postgres => "postgres",
+            Self::Mysql => "mysql",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+// === impl NewRecordHint ===
+
+impl<N> NewRecordHint<N> {
+    pub(super) fn layer(
+        metrics: ProtocolHintMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<N: svc::NewService<Forward>> svc::NewService<Forward> for NewRecordHint<N> {
+    type Service = RecordHint<N::Service>;
+
+    fn new_service(&self, target: Forward) -> Self::Service {
+        RecordHint {
+            metrics: self.metrics.clone(),
+            port: target.orig_dst_addr.port(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl RecordHint ===
+
+impl<I, S> svc::Service<I> for RecordHint<S>
+where
+    S: svc::Service<HintIo<I>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, io: I) -> Self::Future {
+        self.inner.call(HintIo {
+            io,
+            recorder: Some((self.metrics.clone(), self.port)),
+        })
+    }
+}
+
+// === impl HintIo ===
+
+impl<I> HintIo<I> {
+    fn record(
+        recorder: &mut Option<(ProtocolHintMetrics, u16)>,
+        hint: impl FnOnce() -> ProtocolHint,
+    ) {
+        if let Some((metrics, port)) = recorder.take() {
+            let hint = hint();
+            tracing::debug!(
+                port,
+                hint = hint.as_str(),
+                "Fingerprinted opaque connection"
+            );
+            metrics.record(port, hint);
+        }
+    }
+}
+
+impl<I: io::AsyncRead> io::AsyncRead for HintIo<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> io::Poll<()> {
+        let this = self.project();
+        let prev_filled = buf.filled().len();
+        let res = this.io.poll_read(cx, buf);
+        let read = &buf.filled()[prev_filled..];
+        if this.recorder.is_some() && !read.is_empty() {
+            Self::record(this.recorder, || ProtocolHint::from_client(read));
+        }
+        res
+    }
+}
+
+impl<I: io::AsyncWrite> io::AsyncWrite for HintIo<I> {
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_shutdown(cx)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_flush(cx)
+    }
+
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+        let this = self.project();
+        if this.recorder.is_some() && !buf.is_empty() {
+            Self::record(this.recorder, || ProtocolHint::from_server(buf));
+        }
+        this.io.poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> io::Poll<usize> {
+        let this = self.project();
+        if this.recorder.is_some() {
+            if let Some(buf) = bufs.iter().find(|b| !b.is_empty()) {
+                Self::record(this.recorder, || ProtocolHint::from_server(buf));
+            }
+        }
+        this.io.poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+impl<I: io::PeerAddr> io::PeerAddr for HintIo<I> {
+    fn peer_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.io.peer_addr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProtocolHint;
+
+    #[test]
+    fn fingerprints() {
+        assert_eq!(
+            ProtocolHint::from_client(&[0x16, 0x03, 0x01, 0x02, 0x00]),
+            ProtocolHint::Tls
+        );
+        assert_eq!(
+            ProtocolHint::from_client(b"GET / HTTP/1.1\r\n"),
+            ProtocolHint::Http
+        );
+        assert_eq!(
+            ProtocolHint::from_client(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n"),
+            ProtocolHint::Http
+        );
+        assert_eq!(
+            ProtocolHint::from_client(b"*1\r\n$4\r\nPING\r\n"),
+            ProtocolHint::Redis
+        );
+        assert_eq!(
+            ProtocolHint::from_client(&[0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f]),
+            ProtocolHint::Postgres
+        );
+        assert_eq!(
+            ProtocolHint::from_client(&[0, 0, 0, 41, 0, 3, 0, 0, b'u', b's']),
+            ProtocolHint::Postgres
+        );
+        assert_eq!(ProtocolHint::from_client(b"hello"), ProtocolHint::Unknown);
+
+        assert_eq!(
+            ProtocolHint::from_server(&[0x4a, 0, 0, 0, 0x0a, b'8', b'.']),
+            ProtocolHint::Mysql
+        );
+        assert_eq!(
+            ProtocolHint::from_server(b"220 smtp.example.com ESMTP\r\n"),
+            ProtocolHint::Unknown
+        );
+    }
+}
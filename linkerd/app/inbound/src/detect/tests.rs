@@ -22,6 +22,8 @@ fn authzs() -> Arc<[Authorization]> {
             kind: "authorizationpolicy".into(),
             name: "testsaz".into(),
         }),
+        local_addrs: vec![],
+        validity: vec![],
     }])
 }
 
@@ -35,6 +37,8 @@ fn allow(protocol: Protocol) -> AllowPolicy {
                 kind: "server".into(),
                 name: "testsrv".into(),
             }),
+            bandwidth_limit: None,
+            duplex: Default::default(),
         },
     );
     allow
@@ -64,6 +68,7 @@ async fn detect_http_non_http() {
         orig_dst_addr: orig_dst_addr(),
         status: tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(client_id()),
+            client_fingerprint: None,
             negotiated_protocol: None,
         }),
         policy: allow(Protocol::Detect {
@@ -95,6 +100,7 @@ async fn detect_http() {
         orig_dst_addr: orig_dst_addr(),
         status: tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(client_id()),
+            client_fingerprint: None,
             negotiated_protocol: None,
         }),
         policy: allow(Protocol::Detect {
@@ -125,6 +131,7 @@ async fn hinted_http1() {
         orig_dst_addr: orig_dst_addr(),
         status: tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(client_id()),
+            client_fingerprint: None,
             negotiated_protocol: None,
         }),
         policy: allow(Protocol::Http1(vec![].into())),
@@ -151,6 +158,7 @@ async fn hinted_http1_supports_http2() {
         orig_dst_addr: orig_dst_addr(),
         status: tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(client_id()),
+            client_fingerprint: None,
             negotiated_protocol: None,
         }),
         policy: allow(Protocol::Http1(vec![].into())),
@@ -177,6 +185,7 @@ async fn hinted_http2() {
         orig_dst_addr: orig_dst_addr(),
         status: tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(client_id()),
+            client_fingerprint: None,
             negotiated_protocol: None,
         }),
         policy: allow(Protocol::Http2(vec![].into())),
@@ -194,6 +203,36 @@ async fn hinted_http2() {
         .expect("should succeed");
 }
 
+#[test]
+fn sni_routes() {
+    let routes: Arc<[sni_routes::Route]> = Arc::new([sni_routes::Route {
+        snis: vec!["*.example.com".parse().unwrap()],
+        backend: sni_routes::Backend::Port(8443),
+    }]);
+    let tls = || Protocol::Tls {
+        authorizations: authzs(),
+        sni_routes: routes.clone(),
+    };
+    let target = |sni: &str, protocol: Protocol| Tls {
+        client_addr: client_addr(),
+        orig_dst_addr: orig_dst_addr(),
+        status: tls::ConditionalServerTls::Some(tls::ServerTls::Passthru {
+            sni: sni.parse().unwrap(),
+        }),
+        policy: allow(protocol),
+    };
+
+    assert_eq!(
+        target("api.example.com", tls()).sni_route_addr(),
+        Some(([192, 0, 2, 2], 8443).into())
+    );
+    assert_eq!(target("api.example.org", tls()).sni_route_addr(), None);
+    assert_eq!(
+        target("api.example.com", Protocol::Opaque(authzs())).sni_route_addr(),
+        None
+    );
+}
+
 fn client_id() -> tls::ClientId {
     "testsa.testns.serviceaccount.identity.linkerd.cluster.local"
         .parse()
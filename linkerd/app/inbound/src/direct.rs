@@ -4,7 +4,7 @@ use crate::{
 };
 use linkerd_app_core::{
     identity, io, profiles,
-    proxy::http,
+    proxy::{http, tcp},
     svc::{self, ExtractParam, InsertParam, Param},
     tls,
     transport::{self, metrics::SensorIo, ClientAddr, OrigDstAddr, Remote, ServerAddr},
@@ -33,6 +33,7 @@ pub(crate) struct LocalTcp {
     client_addr: Remote<ClientAddr>,
     server_addr: Remote<ServerAddr>,
     client_id: tls::ClientId,
+    client_fingerprint: Option<tls::CertFingerprint>,
     policy: policy::AllowPolicy,
 }
 
@@ -41,6 +42,7 @@ pub(crate) struct AuthorizedLocalTcp {
     addr: Remote<ServerAddr>,
     client_id: tls::ClientId,
     permit: policy::ServerPermit,
+    duplex: tcp::DuplexConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -65,6 +67,7 @@ pub struct GatewayTransportHeader {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ClientInfo {
     pub client_id: tls::ClientId,
+    pub client_fingerprint: Option<tls::CertFingerprint>,
     pub alpn: Option<tls::NegotiatedProtocol>,
     pub client_addr: Remote<ClientAddr>,
     pub local_addr: OrigDstAddr,
@@ -123,12 +126,16 @@ impl<N> Inbound<N> {
                 .push_map_target(|(permit, tcp): (policy::ServerPermit, LocalTcp)| {
                     AuthorizedLocalTcp {
                         addr: tcp.server_addr,
+                        duplex: tcp.policy.borrow().duplex,
                         client_id: tcp.client_id,
                         permit,
                     }
                 })
                 .check_new_service::<(policy::ServerPermit, LocalTcp), _>()
-                .push(policy::NewTcpPolicy::layer(rt.metrics.tcp_authz.clone()))
+                .push(policy::NewTcpPolicy::layer(
+                    rt.metrics.tcp_authz.clone(),
+                    config.revocation_grace_period,
+                ))
                 .instrument(|_: &_| debug_span!("opaq"))
                 .check_new_service::<LocalTcp, _>()
                 // When the transport header is present, it may be used for either local TCP
@@ -156,6 +163,7 @@ impl<N> Inbound<N> {
                                             server_addr: Remote(ServerAddr(addr)),
                                             client_addr: client.client_addr,
                                             client_id: client.client_id,
+                                            client_fingerprint: client.client_fingerprint,
                                             policy,
                                         }),
                                         Some(protocol) => {
@@ -243,9 +251,11 @@ where
         match tls {
             Conditional::Some(tls::ServerTls::Established {
                 client_id: Some(client_id),
+                client_fingerprint,
                 negotiated_protocol,
             }) => Ok(Self {
                 client_id,
+                client_fingerprint,
                 alpn: negotiated_protocol,
                 client_addr: addrs.param(),
                 local_addr: addrs.param(),
@@ -282,6 +292,7 @@ impl Param<tls::ConditionalServerTls> for LocalTcp {
     fn param(&self) -> tls::ConditionalServerTls {
         tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(self.client_id.clone()),
+            client_fingerprint: self.client_fingerprint,
             negotiated_protocol: None,
         })
     }
@@ -295,11 +306,18 @@ impl Param<Remote<ServerAddr>> for AuthorizedLocalTcp {
     }
 }
 
+impl Param<tcp::DuplexConfig> for AuthorizedLocalTcp {
+    fn param(&self) -> tcp::DuplexConfig {
+        self.duplex
+    }
+}
+
 impl Param<transport::labels::Key> for AuthorizedLocalTcp {
     fn param(&self) -> transport::labels::Key {
         transport::labels::Key::inbound_server(
             tls::ConditionalServerTls::Some(tls::ServerTls::Established {
                 client_id: Some(self.client_id.clone()),
+                client_fingerprint: None,
                 negotiated_protocol: None,
             }),
             self.addr.into(),
@@ -333,6 +351,7 @@ impl Param<transport::labels::Key> for LocalHttp {
         transport::labels::Key::inbound_server(
             tls::ConditionalServerTls::Some(tls::ServerTls::Established {
                 client_id: Some(self.client.client_id.clone()),
+                client_fingerprint: None,
                 negotiated_protocol: None,
             }),
             self.addr.into(),
@@ -372,6 +391,7 @@ impl svc::Param<tls::ConditionalServerTls> for LocalHttp {
     fn param(&self) -> tls::ConditionalServerTls {
         tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(self.client.client_id.clone()),
+            client_fingerprint: self.client.client_fingerprint,
             negotiated_protocol: self.client.alpn.clone(),
         })
     }
@@ -417,6 +437,7 @@ impl Param<tls::ConditionalServerTls> for GatewayTransportHeader {
     fn param(&self) -> tls::ConditionalServerTls {
         tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(self.client.client_id.clone()),
+            client_fingerprint: self.client.client_fingerprint,
             negotiated_protocol: self.client.alpn.clone(),
         })
     }
@@ -1,8 +1,16 @@
+mod body_sizes;
+mod compress;
+mod drain;
+mod grpc_web;
 mod router;
 mod server;
 mod set_identity_header;
 #[cfg(test)]
 mod tests;
+mod transcode;
+mod websocket;
+
+pub use self::compress::{CompressionMetrics, ResponseCompression};
 
 fn trace_labels() -> std::collections::HashMap<String, String> {
     let mut l = std::collections::HashMap::new();
@@ -223,6 +231,8 @@ pub mod fuzz {
                                     kind: "server".into(),
                                     name: "testsaz".into(),
                                 }),
+                                local_addrs: vec![],
+                                validity: vec![],
                             },
                         ])),
                     ])),
@@ -231,6 +241,8 @@ pub mod fuzz {
                         kind: "server".into(),
                         name: "testsrv".into(),
                     }),
+                    bandwidth_limit: None,
+                    duplex: Default::default(),
                 },
             );
             policy
@@ -0,0 +1,250 @@
+//! Records the sizes of each route's request and response bodies.
+//!
+//! Sizes are recorded once a body completes, so that bodies that are dropped
+//! before they are fully streamed do not skew the distribution.
+
+use crate::{
+    metrics::body_sizes::{BodySizeMetrics, RouteBodySizes},
+    policy,
+};
+use futures::{ready, TryFuture};
+use linkerd_app_core::{
+    metrics::Histogram,
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use pin_project::{pin_project, pinned_drop};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+#[derive(Clone, Debug)]
+pub struct NewRecordBodySizes<N> {
+    metrics: BodySizeMetrics,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordBodySizes<S> {
+    sizes: Arc<RouteBodySizes>,
+    inner: S,
+}
+
+#[pin_project]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    sizes: Option<Arc<RouteBodySizes>>,
+}
+
+/// Counts the bytes in a body, recording its size once it completes.
+#[pin_project(PinnedDrop)]
+struct SizedBody {
+    #[pin]
+    inner: http::BoxBody,
+    bytes: u64,
+    /// Unset once the body's size has been recorded.
+    sizes: Option<Arc<RouteBodySizes>>,
+    histogram: fn(&RouteBodySizes) -> &Histogram<u64>,
+}
+
+// === impl NewRecordBodySizes ===
+
+impl<N> NewRecordBodySizes<N> {
+    pub fn layer(metrics: BodySizeMetrics) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<(policy::HttpRoutePermit, T)> for NewRecordBodySizes<N>
+where
+    N: svc::NewService<(policy::HttpRoutePermit, T)>,
+{
+    type Service = RecordBodySizes<N::Service>;
+
+    fn new_service(&self, (permit, target): (policy::HttpRoutePermit, T)) -> Self::Service {
+        let sizes = self.metrics.route(permit.labels.route.clone());
+        let inner = self.inner.new_service((permit, target));
+        RecordBodySizes { sizes, inner }
+    }
+}
+
+// === impl RecordBodySizes ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for RecordBodySizes<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let sizes = self.sizes.clone();
+        let req = req.map(|inner| http::BoxBody::new(SizedBody::request(inner, sizes)));
+        ResponseFuture {
+            inner: self.inner.call(req),
+            sizes: Some(self.sizes.clone()),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: TryFuture<Ok = http::Response<http::BoxBody>>,
+{
+    type Output = Result<http::Response<http::BoxBody>, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = ready!(this.inner.try_poll(cx))?;
+        let sizes = this
+            .sizes
+            .take()
+            .expect("future must not be polled after ready");
+        Poll::Ready(Ok(
+            rsp.map(|inner| http::BoxBody::new(SizedBody::response(inner, sizes)))
+        ))
+    }
+}
+
+// === impl SizedBody ===
+
+impl SizedBody {
+    fn request(inner: http::BoxBody, sizes: Arc<RouteBodySizes>) -> Self {
+        Self {
+            inner,
+            bytes: 0,
+            sizes: Some(sizes),
+            histogram: |s| &s.request,
+        }
+    }
+
+    fn response(inner: http::BoxBody, sizes: Arc<RouteBodySizes>) -> Self {
+        Self {
+            inner,
+            bytes: 0,
+            sizes: Some(sizes),
+            histogram: |s| &s.response,
+        }
+    }
+
+    fn record(
+        sizes: &mut Option<Arc<RouteBodySizes>>,
+        histogram: fn(&RouteBodySizes) -> &Histogram<u64>,
+        bytes: u64,
+    ) {
+        if let Some(sizes) = sizes.take() {
+            histogram(&sizes).add(bytes);
+        }
+    }
+}
+
+impl HttpBody for SizedBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        let data = ready!(this.inner.as_mut().poll_data(cx));
+        match data {
+            Some(Ok(ref data)) => {
+                *this.bytes += bytes::Buf::remaining(data) as u64;
+                // The body may not be polled again once it reports that it
+                // has ended.
+                if this.inner.is_end_stream() {
+                    Self::record(this.sizes, *this.histogram, *this.bytes);
+                }
+            }
+            // Failed bodies are not recorded.
+            Some(Err(_)) => *this.sizes = None,
+            None => Self::record(this.sizes, *this.histogram, *this.bytes),
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        let this = self.project();
+        let trailers = ready!(this.inner.poll_trailers(cx));
+        match trailers {
+            Ok(_) => Self::record(this.sizes, *this.histogram, *this.bytes),
+            Err(_) => *this.sizes = None,
+        }
+        Poll::Ready(trailers)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[pinned_drop]
+impl PinnedDrop for SizedBody {
+    fn drop(self: Pin<&mut Self>) {
+        // Bodies that end without being polled (e.g. empty bodies) are
+        // recorded when they are dropped.
+        let this = self.project();
+        if this.inner.is_end_stream() {
+            Self::record(this.sizes, *this.histogram, *this.bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn records_complete_bodies() {
+        let sizes = Arc::new(RouteBodySizes::default());
+
+        let mut body = SizedBody::request(
+            http::BoxBody::from(bytes::Bytes::from_static(b"hello")),
+            sizes.clone(),
+        );
+        while body.data().await.is_some() {}
+        drop(body);
+        sizes.request.assert_bucket_exactly(64.0, 1.0);
+
+        // Empty bodies are recorded even though they are never polled.
+        drop(SizedBody::response(http::BoxBody::default(), sizes.clone()));
+        sizes.response.assert_bucket_exactly(64.0, 1.0);
+        sizes.request.assert_bucket_exactly(64.0, 1.0);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn ignores_incomplete_bodies() {
+        let sizes = Arc::new(RouteBodySizes::default());
+        let (mut tx, rx) = hyper::Body::channel();
+        let mut body = SizedBody::request(http::BoxBody::new(rx), sizes.clone());
+        tx.send_data("hello".into()).await.unwrap();
+        assert!(body.data().await.is_some());
+        drop(body);
+        sizes.request.assert_bucket_exactly(16_777_216.0, 0.0);
+    }
+}
@@ -0,0 +1,597 @@
+//! Compresses inbound HTTP responses.
+//!
+//! Responses are compressed with gzip or brotli when the client advertises
+//! support for an encoding in its `accept-encoding` header, the response's
+//! content type is configured for compression, and the response is not known
+//! to be smaller than a configured minimum size. Bodies are compressed as they
+//! are streamed, so the compressed response is sent without a
+//! `content-length`.
+
+use bytes::{Buf, Bytes};
+use futures::{ready, TryFuture};
+use linkerd_app_core::{
+    metrics::{metrics, Counter, FmtLabels, FmtMetrics},
+    proxy::http::{self, header, HeaderValue, HttpBody},
+    svc, Error,
+};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    io::{self, Write},
+    mem,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+metrics! {
+    inbound_http_compressed_responses_total: Counter {
+        "The total number of inbound HTTP responses that were compressed, by encoding."
+    },
+    inbound_http_compression_input_bytes_total: Counter {
+        "The total number of response body bytes read by the inbound compressor, by encoding."
+    },
+    inbound_http_compression_output_bytes_total: Counter {
+        "The total number of compressed response body bytes written by the inbound compressor, by encoding."
+    }
+}
+
+/// Configures the compression of inbound HTTP responses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResponseCompression {
+    /// The media types of responses that may be compressed, e.g. `text/html`.
+    /// A type may be given as a wildcard, e.g. `text/*`.
+    pub content_types: Vec<String>,
+
+    /// Responses with a known length below this number of bytes are not
+    /// compressed.
+    pub min_bytes: usize,
+}
+
+/// Counts the responses compressed with each encoding and the number of bytes
+/// before and after compression, from which compression ratios may be
+/// derived.
+#[derive(Clone, Debug, Default)]
+pub struct CompressionMetrics(Arc<[EncodingMetrics; 2]>);
+
+/// Compresses the inner service's responses.
+#[derive(Clone, Debug)]
+pub struct Compress<S> {
+    inner: S,
+    config: Option<Arc<ResponseCompression>>,
+    metrics: CompressionMetrics,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    compress: Option<(Encoding, Arc<ResponseCompression>)>,
+    metrics: CompressionMetrics,
+}
+
+#[pin_project]
+struct CompressBody {
+    #[pin]
+    inner: http::BoxBody,
+    encoder: Option<Encoder>,
+    sink: Sink,
+    encoding: Encoding,
+    metrics: CompressionMetrics,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Encoding {
+    Gzip,
+    Brotli,
+}
+
+#[derive(Debug, Default)]
+struct EncodingMetrics {
+    responses: Counter,
+    input_bytes: Counter,
+    output_bytes: Counter,
+}
+
+enum Encoder {
+    Gzip(flate2::write::GzEncoder<Sink>),
+    Brotli(Box<brotli::CompressorWriter<Sink>>),
+}
+
+/// Collects the output of an encoder so that it may be sent as body data.
+#[derive(Clone, Debug, Default)]
+struct Sink(Arc<Mutex<Vec<u8>>>);
+
+/// Brotli's quality ranges from 0 to 11; lower levels trade compression ratio
+/// for speed, which suits responses that are compressed as they are served.
+const BROTLI_QUALITY: u32 = 4;
+const BROTLI_LG_WINDOW: u32 = 22;
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+// === impl CompressionMetrics ===
+
+impl CompressionMetrics {
+    fn get(&self, encoding: Encoding) -> &EncodingMetrics {
+        match encoding {
+            Encoding::Gzip => &self.0[0],
+            Encoding::Brotli => &self.0[1],
+        }
+    }
+
+    fn scopes(&self) -> impl Iterator<Item = (Encoding, &EncodingMetrics)> + '_ {
+        [Encoding::Gzip, Encoding::Brotli]
+            .into_iter()
+            .map(move |e| (e, self.get(e)))
+    }
+}
+
+impl FmtMetrics for CompressionMetrics {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.scopes().all(|(_, m)| m.responses.value() == 0.0) {
+            return Ok(());
+        }
+
+        inbound_http_compressed_responses_total.fmt_help(f)?;
+        inbound_http_compressed_responses_total.fmt_scopes(f, self.scopes(), |m| &m.responses)?;
+
+        inbound_http_compression_input_bytes_total.fmt_help(f)?;
+        inbound_http_compression_input_bytes_total
+            .fmt_scopes(f, self.scopes(), |m| &m.input_bytes)?;
+
+        inbound_http_compression_output_bytes_total.fmt_help(f)?;
+        inbound_http_compression_output_bytes_total
+            .fmt_scopes(f, self.scopes(), |m| &m.output_bytes)
+    }
+}
+
+// === impl Compress ===
+
+impl<S> Compress<S> {
+    pub fn layer(
+        config: Option<ResponseCompression>,
+        metrics: CompressionMetrics,
+    ) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        let config = config.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            inner,
+            config: config.clone(),
+            metrics: metrics.clone(),
+        })
+    }
+}
+
+impl<B, S> svc::Service<http::Request<B>> for Compress<S>
+where
+    S: svc::Service<http::Request<B>, Response = http::Response<http::BoxBody>>,
+    S::Error: Into<Error>,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let compress = self.config.as_ref().and_then(|config| {
+            if req.method() == http::Method::HEAD {
+                return None;
+            }
+            let encoding = Encoding::negotiate(req.headers())?;
+            Some((encoding, config.clone()))
+        });
+        ResponseFuture {
+            inner: self.inner.call(req),
+            compress,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: TryFuture<Ok = http::Response<http::BoxBody>>,
+    F::Error: Into<Error>,
+{
+    type Output = Result<http::Response<http::BoxBody>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut rsp = ready!(this.inner.try_poll(cx)).map_err(Into::into)?;
+
+        let encoding = match this.compress.take() {
+            Some((encoding, config)) if should_compress(&rsp, &config) => encoding,
+            _ => return Poll::Ready(Ok(rsp)),
+        };
+        tracing::trace!(?encoding, "Compressing response");
+
+        let headers = rsp.headers_mut();
+        headers.remove(header::CONTENT_LENGTH);
+        headers.insert(header::CONTENT_ENCODING, encoding.header_value());
+        if !varies_by_encoding(headers) {
+            headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        }
+        // The compressed representation is no longer byte-for-byte identical
+        // to the one a strong validator describes.
+        if let Some(etag) = headers.get(header::ETAG).and_then(weaken_etag) {
+            headers.insert(header::ETAG, etag);
+        }
+
+        this.metrics.get(encoding).responses.incr();
+        let metrics = this.metrics.clone();
+        let sink = Sink::default();
+        Poll::Ready(Ok(rsp.map(|inner| {
+            http::BoxBody::new(CompressBody {
+                inner,
+                encoder: Some(Encoder::new(encoding, sink.clone())),
+                sink,
+                encoding,
+                metrics,
+            })
+        })))
+    }
+}
+
+fn should_compress(rsp: &http::Response<http::BoxBody>, config: &ResponseCompression) -> bool {
+    let status = rsp.status();
+    if status.is_informational()
+        || status == http::StatusCode::NO_CONTENT
+        || status == http::StatusCode::NOT_MODIFIED
+    {
+        return false;
+    }
+
+    let headers = rsp.headers();
+    if headers.contains_key(header::CONTENT_ENCODING) {
+        return false;
+    }
+    let no_transform = headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|d| d.trim().eq_ignore_ascii_case("no-transform"));
+    if no_transform {
+        return false;
+    }
+
+    let content_type = match headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(ct) => ct,
+        None => return false,
+    };
+    if !matches_content_type(&config.content_types, content_type) {
+        return false;
+    }
+
+    let len = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .or_else(|| rsp.body().size_hint().exact());
+    match len {
+        Some(len) => len >= config.min_bytes as u64,
+        None => true,
+    }
+}
+
+fn matches_content_type(configured: &[String], content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    configured.iter().any(|c| match c.strip_suffix("/*") {
+        Some(ty) => media_type
+            .split('/')
+            .next()
+            .map_or(false, |t| t.eq_ignore_ascii_case(ty)),
+        None => media_type.eq_ignore_ascii_case(c),
+    })
+}
+
+fn varies_by_encoding(headers: &header::HeaderMap) -> bool {
+    headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|v| v == "*" || v.eq_ignore_ascii_case("accept-encoding"))
+}
+
+fn weaken_etag(etag: &HeaderValue) -> Option<HeaderValue> {
+    let etag = etag.to_str().ok()?;
+    if etag.starts_with("W/") {
+        return None;
+    }
+    HeaderValue::try_from(format!("W/{etag}")).ok()
+}
+
+// === impl Encoding ===
+
+impl Encoding {
+    /// Selects the encoding with the highest weight in the request's
+    /// `accept-encoding` headers, preferring brotli when weights are equal.
+    fn negotiate(headers: &header::HeaderMap) -> Option<Self> {
+        let mut selected: Option<(Self, f32)> = None;
+        let codings = headers
+            .get_all(header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','));
+        for coding in codings {
+            let mut params = coding.split(';');
+            let encoding = match params.next().unwrap_or_default().trim() {
+                c if c.eq_ignore_ascii_case("br") => Self::Brotli,
+                c if c.eq_ignore_ascii_case("gzip") || c.eq_ignore_ascii_case("x-gzip") => {
+                    Self::Gzip
+                }
+                _ => continue,
+            };
+            let weight = params
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(1.0, |q| q.trim().parse().unwrap_or(0.0));
+            if weight <= 0.0 {
+                continue;
+            }
+            let replace = match selected {
+                None => true,
+                Some((_, w)) if weight > w => true,
+                Some((_, w)) => weight >= w && encoding == Self::Brotli,
+            };
+            if replace {
+                selected = Some((encoding, weight));
+            }
+        }
+        selected.map(|(encoding, _)| encoding)
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Brotli => "br",
+        }
+    }
+
+    fn header_value(&self) -> HeaderValue {
+        HeaderValue::from_static(self.as_str())
+    }
+}
+
+impl FmtLabels for Encoding {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "encoding=\"{}\"", self.as_str())
+    }
+}
+
+// === impl Encoder ===
+
+impl Encoder {
+    fn new(encoding: Encoding, sink: Sink) -> Self {
+        match encoding {
+            Encoding::Gzip => Self::Gzip(flate2::write::GzEncoder::new(
+                sink,
+                flate2::Compression::fast(),
+            )),
+            Encoding::Brotli => Self::Brotli(Box::new(brotli::CompressorWriter::new(
+                sink,
+                BROTLI_BUFFER_SIZE,
+                BROTLI_QUALITY,
+                BROTLI_LG_WINDOW,
+            ))),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Gzip(w) => w.write_all(buf),
+            Self::Brotli(w) => w.write_all(buf),
+        }
+    }
+
+    /// Writes any buffered data and the end of the compressed stream to the
+    /// sink.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Gzip(w) => w.finish().map(drop),
+            Self::Brotli(w) => {
+                w.into_inner();
+                Ok(())
+            }
+        }
+    }
+}
+
+// === impl Sink ===
+
+impl Sink {
+    fn take(&self) -> Bytes {
+        Bytes::from(mem::take(&mut *self.0.lock()))
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// === impl CompressBody ===
+
+impl HttpBody for CompressBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn is_end_stream(&self) -> bool {
+        // The end of the compressed stream is only written once the inner
+        // body completes.
+        self.encoder.is_none()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        let metrics = this.metrics.get(*this.encoding);
+        loop {
+            let encoder = match this.encoder.as_mut() {
+                Some(encoder) => encoder,
+                None => return Poll::Ready(None),
+            };
+
+            match ready!(this.inner.as_mut().poll_data(cx)) {
+                Some(Ok(mut data)) => {
+                    metrics.input_bytes.add(data.remaining() as u64);
+                    while data.has_remaining() {
+                        let chunk = data.chunk();
+                        let len = chunk.len();
+                        encoder.write_all(chunk)?;
+                        data.advance(len);
+                    }
+                }
+                Some(Err(error)) => {
+                    *this.encoder = None;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                None => {
+                    if let Some(encoder) = this.encoder.take() {
+                        encoder.finish()?;
+                    }
+                }
+            }
+
+            // Encoders buffer their input, so a chunk of data may not produce
+            // any output until more data is written.
+            let out = this.sink.take();
+            if !out.is_empty() {
+                metrics.output_bytes.add(out.len() as u64);
+                return Poll::Ready(Some(Ok(out)));
+            }
+        }
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<header::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use svc::{Layer, Service, ServiceExt};
+
+    fn config() -> ResponseCompression {
+        ResponseCompression {
+            content_types: vec!["text/*".to_string(), "application/json".to_string()],
+            min_bytes: 16,
+        }
+    }
+
+    fn headers(accept: &str) -> header::HeaderMap {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, accept.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn negotiates_encodings() {
+        assert_eq!(Encoding::negotiate(&headers("gzip")), Some(Encoding::Gzip));
+        assert_eq!(
+            Encoding::negotiate(&headers("gzip, br")),
+            Some(Encoding::Brotli)
+        );
+        assert_eq!(
+            Encoding::negotiate(&headers("br;q=0.5, gzip")),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(Encoding::negotiate(&headers("br;q=0, deflate")), None);
+        assert_eq!(Encoding::negotiate(&header::HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn matches_content_types() {
+        let types = config().content_types;
+        assert!(matches_content_type(&types, "text/html; charset=utf-8"));
+        assert!(matches_content_type(&types, "Application/JSON"));
+        assert!(!matches_content_type(&types, "application/octet-stream"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn compresses_configured_responses() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let body = "hello world! ".repeat(64);
+        let metrics = CompressionMetrics::default();
+        let inner = svc::mk({
+            let body = body.clone();
+            move |_: http::Request<http::BoxBody>| {
+                let rsp = http::Response::builder()
+                    .header(header::CONTENT_TYPE, "text/plain")
+                    .header(header::CONTENT_LENGTH, body.len())
+                    .header(header::ETAG, "\"abc\"")
+                    .body(http::BoxBody::from(Bytes::from(body.clone())))
+                    .unwrap();
+                futures::future::ok::<_, Error>(rsp)
+            }
+        });
+        let mut svc = Compress::layer(Some(config()), metrics.clone()).layer(inner);
+
+        let req = http::Request::builder()
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(http::BoxBody::default())
+            .unwrap();
+        let rsp = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(rsp.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(rsp.headers()[header::VARY], "accept-encoding");
+        assert_eq!(rsp.headers()[header::ETAG], "W/\"abc\"");
+        assert!(rsp.headers().get(header::CONTENT_LENGTH).is_none());
+
+        let compressed = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body);
+
+        let m = metrics.get(Encoding::Gzip);
+        assert_eq!(m.responses.value(), 1.0);
+        assert_eq!(m.input_bytes.value(), body.len() as f64);
+        assert_eq!(m.output_bytes.value(), compressed.len() as f64);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn skips_small_responses() {
+        let inner = svc::mk(|_: http::Request<http::BoxBody>| {
+            let rsp = http::Response::builder()
+                .header(header::CONTENT_TYPE, "text/plain")
+                .body(http::BoxBody::from(Bytes::from_static(b"tiny")))
+                .unwrap();
+            futures::future::ok::<_, Error>(rsp)
+        });
+        let mut svc = Compress::layer(Some(config()), Default::default()).layer(inner);
+
+        let req = http::Request::builder()
+            .header(header::ACCEPT_ENCODING, "gzip, br")
+            .body(http::BoxBody::default())
+            .unwrap();
+        let rsp = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert!(rsp.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+}
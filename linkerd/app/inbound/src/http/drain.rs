@@ -0,0 +1,192 @@
+//! Drains HTTP connections when their port's policy no longer serves HTTP.
+//!
+//! A connection's protocol is determined by its port's policy when it is
+//! accepted. If the policy later changes so that the port is no longer served
+//! as HTTP (e.g. it becomes opaque), the connection's requests would fail to
+//! find routes. Instead, each HTTP connection on the port is shut down
+//! gracefully so that its client reconnects and is served according to the
+//! new policy. Connections on other ports are unaffected.
+
+use crate::{metrics::protocol_drains::ProtocolDrainMetrics, policy::AllowPolicy};
+use linkerd_app_core::{
+    proxy::http::ClientHandle,
+    svc::{self, Param},
+};
+use linkerd_proxy_server_policy::Protocol;
+use std::task::{Context, Poll};
+use tokio::sync::oneshot;
+use tracing::Instrument;
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewDrainOnProtocolChange<N> {
+    inner: N,
+    metrics: ProtocolDrainMetrics,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DrainOnProtocolChange<N> {
+    inner: N,
+    policy: AllowPolicy,
+    metrics: ProtocolDrainMetrics,
+}
+
+/// Serves a connection's requests, stopping the task that watches the
+/// connection's policy when the connection is dropped.
+#[derive(Debug)]
+pub(crate) struct Watched<S> {
+    inner: S,
+    _watch: oneshot::Receiver<()>,
+}
+
+// === impl NewDrainOnProtocolChange ===
+
+impl<N> NewDrainOnProtocolChange<N> {
+    pub(crate) fn layer(
+        metrics: ProtocolDrainMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            metrics: metrics.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewDrainOnProtocolChange<N>
+where
+    T: Param<AllowPolicy>,
+    N: svc::NewService<T>,
+{
+    type Service = DrainOnProtocolChange<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        DrainOnProtocolChange {
+            policy: target.param(),
+            inner: self.inner.new_service(target),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+// === impl DrainOnProtocolChange ===
+
+impl<N> svc::NewService<ClientHandle> for DrainOnProtocolChange<N>
+where
+    N: svc::NewService<ClientHandle>,
+{
+    type Service = Watched<N::Service>;
+
+    fn new_service(&self, handle: ClientHandle) -> Self::Service {
+        let (mut tx, rx) = oneshot::channel();
+        let mut policy = self.policy.clone();
+        let metrics = self.metrics.clone();
+        let close = handle.close.clone();
+        tokio::spawn(
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = tx.closed() => return,
+                        _ = policy.changed() => {}
+                    }
+                    if !is_http(&policy.protocol()) {
+                        let port = policy.dst_addr().port();
+                        tracing::debug!(port, "Draining connection after protocol change");
+                        metrics.record(port);
+                        close.close();
+                        return;
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+
+        Watched {
+            inner: self.inner.new_service(handle),
+            _watch: rx,
+        }
+    }
+}
+
+// === impl Watched ===
+
+impl<Req, S> svc::Service<Req> for Watched<S>
+where
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    #[inline]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+/// Indicates whether connections with the given protocol are served as HTTP.
+/// Connections continue to be served when the port's HTTP protocol changes
+/// (e.g. from HTTP/1 to gRPC), since requests are routed by the current
+/// policy.
+fn is_http(protocol: &Protocol) -> bool {
+    match protocol {
+        Protocol::Detect { .. } | Protocol::Http1(_) | Protocol::Http2(_) | Protocol::Grpc(_) => {
+            true
+        }
+        Protocol::Opaque(_) | Protocol::Tls { .. } => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Meta;
+    use linkerd_app_core::{metrics::FmtMetrics, svc::NewService, transport::OrigDstAddr};
+    use linkerd_proxy_server_policy::ServerPolicy;
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn drains_on_protocol_change() {
+        let policy = |protocol| ServerPolicy {
+            protocol,
+            meta: Arc::new(Meta::Resource {
+                group: "policy.linkerd.io".into(),
+                kind: "server".into(),
+                name: "test".into(),
+            }),
+            bandwidth_limit: None,
+            duplex: Default::default(),
+        };
+        let (allow, tx) = AllowPolicy::for_test(
+            OrigDstAddr(([192, 0, 2, 2], 8080).into()),
+            policy(Protocol::Http1(Arc::new([]))),
+        );
+
+        let metrics = ProtocolDrainMetrics::default();
+        let new_drain = NewDrainOnProtocolChange {
+            inner: |_: AllowPolicy| |_: ClientHandle| (),
+            metrics: metrics.clone(),
+        };
+        let (handle, closed) = ClientHandle::new(([192, 0, 2, 3], 54321).into());
+        let _svc = new_drain.new_service(allow).new_service(handle);
+        tokio::pin!(closed);
+
+        // Changes to the port's HTTP routes do not drain connections.
+        tx.send(policy(Protocol::Grpc(Arc::new([])))).unwrap();
+        tokio::time::timeout(Duration::from_millis(10), &mut closed)
+            .await
+            .expect_err("connection must not be drained");
+
+        tx.send(policy(Protocol::Opaque(Arc::new([])))).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), &mut closed)
+            .await
+            .expect("connection must be drained");
+        assert!(metrics
+            .as_display()
+            .to_string()
+            .contains("inbound_http_protocol_change_drains_total{target_port=\"8080\"} 1"));
+    }
+}
@@ -0,0 +1,429 @@
+//! Translates gRPC-web requests into gRPC so that browser clients may call
+//! meshed gRPC services.
+//!
+//! gRPC-web requests are sent as HTTP/2 gRPC requests to the application. The
+//! application's response trailers are then encoded into a final length-prefixed
+//! frame of the response body, since browsers cannot read HTTP trailers.
+//! Requests in the `application/grpc-web-text` format carry base64-encoded
+//! bodies, and their responses are encoded likewise.
+//!
+//! CORS preflight requests are not handled here, so browsers must call the
+//! service from the same origin or the application must answer preflights.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::{ready, TryFuture};
+use linkerd_app_core::{
+    proxy::http::{self, header, HeaderValue, HttpBody},
+    svc, Error,
+};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+const GRPC: &str = "application/grpc";
+const GRPC_WEB: &str = "application/grpc-web";
+const GRPC_WEB_TEXT: &str = "application/grpc-web-text";
+
+/// Marks a gRPC-web body frame that holds trailers rather than a message.
+const TRAILERS_FLAG: u8 = 0x80;
+
+/// Translates gRPC-web requests and responses, when enabled.
+#[derive(Clone, Debug)]
+pub struct GrpcWeb<S> {
+    inner: S,
+    enabled: bool,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    encoding: Option<Encoding>,
+}
+
+/// The body encoding of a gRPC-web request.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Encoding {
+    Binary,
+    Base64,
+}
+
+/// Decodes a base64-encoded gRPC-web request body.
+#[pin_project]
+struct DecodeText {
+    #[pin]
+    inner: http::BoxBody,
+    buf: BytesMut,
+}
+
+/// Encodes a gRPC response body as a gRPC-web response body.
+#[pin_project]
+struct EncodeResponse {
+    #[pin]
+    inner: http::BoxBody,
+    encoding: Encoding,
+    state: State,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    Data,
+    Trailers,
+    Done,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid grpc-web-text request body: {0}")]
+struct InvalidBase64(#[source] base64::DecodeError);
+
+// === impl GrpcWeb ===
+
+impl<S> GrpcWeb<S> {
+    pub fn layer(enabled: bool) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self { inner, enabled })
+    }
+}
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for GrpcWeb<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S::Error: Into<Error>,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        let encoding = if self.enabled {
+            translate_request(&mut req)
+        } else {
+            None
+        };
+        let req = match encoding {
+            Some(Encoding::Base64) => req.map(|inner| {
+                http::BoxBody::new(DecodeText {
+                    inner,
+                    buf: BytesMut::new(),
+                })
+            }),
+            _ => req,
+        };
+        ResponseFuture {
+            inner: self.inner.call(req),
+            encoding,
+        }
+    }
+}
+
+/// Rewrites a gRPC-web request's headers as those of a gRPC request,
+/// returning the encoding of its body. Returns `None`, leaving the request
+/// unmodified, if it is not a gRPC-web request.
+fn translate_request<B>(req: &mut http::Request<B>) -> Option<Encoding> {
+    let content_type = req.headers().get(header::CONTENT_TYPE)?.to_str().ok()?;
+    let (encoding, suffix) = if let Some(suffix) = content_type.strip_prefix(GRPC_WEB_TEXT) {
+        (Encoding::Base64, suffix)
+    } else if let Some(suffix) = content_type.strip_prefix(GRPC_WEB) {
+        (Encoding::Binary, suffix)
+    } else {
+        return None;
+    };
+    let content_type = HeaderValue::try_from(format!("{GRPC}{suffix}")).ok()?;
+    tracing::debug!(?encoding, "Translating gRPC-web request");
+
+    let headers = req.headers_mut();
+    headers.insert(header::CONTENT_TYPE, content_type);
+    headers.insert(header::TE, HeaderValue::from_static("trailers"));
+    if encoding == Encoding::Base64 {
+        headers.remove(header::CONTENT_LENGTH);
+    }
+    *req.version_mut() = ::http::Version::HTTP_2;
+    Some(encoding)
+}
+
+// === impl ResponseFuture ===
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: TryFuture<Ok = http::Response<http::BoxBody>>,
+    F::Error: Into<Error>,
+{
+    type Output = Result<http::Response<http::BoxBody>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut rsp = ready!(this.inner.try_poll(cx)).map_err(Into::into)?;
+        let encoding = match this.encoding.take() {
+            Some(encoding) => encoding,
+            None => return Poll::Ready(Ok(rsp)),
+        };
+
+        let suffix = rsp
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|ct| ct.strip_prefix(GRPC))
+            .unwrap_or_default();
+        let prefix = match encoding {
+            Encoding::Binary => GRPC_WEB,
+            Encoding::Base64 => GRPC_WEB_TEXT,
+        };
+        if let Ok(content_type) = HeaderValue::try_from(format!("{prefix}{suffix}")) {
+            rsp.headers_mut().insert(header::CONTENT_TYPE, content_type);
+        }
+        rsp.headers_mut().remove(header::CONTENT_LENGTH);
+
+        Poll::Ready(Ok(rsp.map(|inner| {
+            http::BoxBody::new(EncodeResponse {
+                inner,
+                encoding,
+                state: State::Data,
+            })
+        })))
+    }
+}
+
+// === impl DecodeText ===
+
+impl HttpBody for DecodeText {
+    type Data = Bytes;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream() && self.buf.is_empty()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        loop {
+            match ready!(this.inner.as_mut().poll_data(cx)) {
+                Some(Ok(mut data)) => {
+                    while data.has_remaining() {
+                        let chunk = data.chunk();
+                        let len = chunk.len();
+                        this.buf.put_slice(chunk);
+                        data.advance(len);
+                    }
+                    // Base64 is decoded in 4-byte quanta, so any partial
+                    // quantum is retained until more data is received.
+                    let len = this.buf.len() / 4 * 4;
+                    let decoded = decode_base64(&this.buf.split_to(len))?;
+                    if !decoded.is_empty() {
+                        return Poll::Ready(Some(Ok(decoded)));
+                    }
+                }
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => {
+                    let decoded = decode_base64(&this.buf.split())?;
+                    if decoded.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(Ok(decoded)));
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<header::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+}
+
+/// Decodes base64 data that may contain padding at the end of each message,
+/// since clients may encode each message separately.
+fn decode_base64(input: &[u8]) -> Result<Bytes, InvalidBase64> {
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let mut start = 0;
+    for (i, quantum) in input.chunks(4).enumerate() {
+        if quantum.contains(&b'=') {
+            let end = (i + 1) * 4;
+            STANDARD
+                .decode_vec(&input[start..end], &mut out)
+                .map_err(InvalidBase64)?;
+            start = end;
+        }
+    }
+    STANDARD
+        .decode_vec(&input[start..], &mut out)
+        .map_err(InvalidBase64)?;
+    Ok(out.into())
+}
+
+// === impl EncodeResponse ===
+
+impl HttpBody for EncodeResponse {
+    type Data = Bytes;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.state == State::Done
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        if *this.state == State::Data {
+            match ready!(this.inner.as_mut().poll_data(cx)) {
+                Some(Ok(mut data)) => {
+                    let data = data.copy_to_bytes(data.remaining());
+                    return Poll::Ready(Some(Ok(encode(*this.encoding, data))));
+                }
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => *this.state = State::Trailers,
+            }
+        }
+
+        if *this.state == State::Trailers {
+            let trailers = ready!(this.inner.as_mut().poll_trailers(cx))?;
+            *this.state = State::Done;
+            if let Some(trailers) = trailers {
+                let frame = trailers_frame(&trailers);
+                return Poll::Ready(Some(Ok(encode(*this.encoding, frame))));
+            }
+        }
+
+        Poll::Ready(None)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Result<Option<header::HeaderMap>, Self::Error>> {
+        // Trailers are sent in the body.
+        Poll::Ready(Ok(None))
+    }
+}
+
+fn encode(encoding: Encoding, data: Bytes) -> Bytes {
+    match encoding {
+        Encoding::Binary => data,
+        Encoding::Base64 => STANDARD.encode(data).into(),
+    }
+}
+
+/// Encodes trailers as a gRPC-web trailers frame: a flag byte, a 4-byte
+/// length, and the trailers in HTTP/1 header format.
+fn trailers_frame(trailers: &header::HeaderMap) -> Bytes {
+    let mut block = BytesMut::new();
+    for (name, value) in trailers {
+        block.put_slice(name.as_str().as_bytes());
+        block.put_u8(b':');
+        block.put_slice(value.as_bytes());
+        block.put_slice(b"\r\n");
+    }
+    let mut frame = BytesMut::with_capacity(5 + block.len());
+    frame.put_u8(TRAILERS_FLAG);
+    frame.put_u32(block.len() as u32);
+    frame.put(block);
+    frame.freeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svc::{Layer, Service, ServiceExt};
+
+    /// Returns a gRPC service that echoes the request body and responds with
+    /// `grpc-status: 0` trailers.
+    fn echo() -> svc::BoxHttp {
+        svc::BoxHttp::new(svc::mk(|req: http::Request<http::BoxBody>| async move {
+            assert_eq!(req.version(), ::http::Version::HTTP_2);
+            assert_eq!(
+                req.headers()[header::CONTENT_TYPE],
+                "application/grpc+proto"
+            );
+            assert_eq!(req.headers()[header::TE], "trailers");
+            let body = hyper::body::to_bytes(req.into_body()).await?;
+
+            let (mut tx, rx) = hyper::Body::channel();
+            tokio::spawn(async move {
+                tx.send_data(body).await.unwrap();
+                let mut trailers = header::HeaderMap::new();
+                trailers.insert("grpc-status", HeaderValue::from_static("0"));
+                tx.send_trailers(trailers).await.unwrap();
+            });
+            let rsp = http::Response::builder()
+                .header(header::CONTENT_TYPE, "application/grpc+proto")
+                .body(http::BoxBody::new(rx))
+                .unwrap();
+            Ok::<_, Error>(rsp)
+        }))
+    }
+
+    async fn call(content_type: &str, body: Bytes) -> http::Response<http::BoxBody> {
+        let mut svc = GrpcWeb::layer(true).layer(echo());
+        let req = http::Request::builder()
+            .version(::http::Version::HTTP_11)
+            .header(header::CONTENT_TYPE, content_type)
+            .body(http::BoxBody::from(body))
+            .unwrap();
+        svc.ready().await.unwrap().call(req).await.unwrap()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn translates_binary() {
+        let rsp = call("application/grpc-web+proto", Bytes::from_static(b"hello")).await;
+        assert_eq!(
+            rsp.headers()[header::CONTENT_TYPE],
+            "application/grpc-web+proto"
+        );
+
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        let mut expected = b"hello".to_vec();
+        expected.extend_from_slice(&trailers_frame(&{
+            let mut trailers = header::HeaderMap::new();
+            trailers.insert("grpc-status", HeaderValue::from_static("0"));
+            trailers
+        }));
+        assert_eq!(body, expected);
+        assert_eq!(&body[5..10], &[TRAILERS_FLAG, 0, 0, 0, 15]);
+        assert_eq!(&body[10..], b"grpc-status:0\r\n");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn translates_text() {
+        // Each message may be encoded, and padded, separately.
+        let req_body = format!("{}{}", STANDARD.encode("hi"), STANDARD.encode("there"));
+        let rsp = call("application/grpc-web-text+proto", req_body.into()).await;
+        assert_eq!(
+            rsp.headers()[header::CONTENT_TYPE],
+            "application/grpc-web-text+proto"
+        );
+
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        let decoded = decode_base64(&body).unwrap();
+        assert_eq!(&decoded[..7], b"hithere");
+        assert_eq!(decoded[7], TRAILERS_FLAG);
+    }
+
+    #[test]
+    fn ignores_other_requests() {
+        let mut req = http::Request::builder()
+            .header(header::CONTENT_TYPE, "application/grpc")
+            .body(())
+            .unwrap();
+        assert_eq!(translate_request(&mut req), None);
+        assert_eq!(req.version(), ::http::Version::HTTP_11);
+    }
+}
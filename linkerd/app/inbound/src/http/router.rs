@@ -107,6 +107,11 @@ impl<C> Inbound<C> {
                 // double-counted--i.e., endpoint metrics track these responses and error metrics
                 // track proxy errors that occur higher in the stack.
                 .push(ClientRescue::layer())
+                // Deducts the time spent in the proxy from the request's
+                // deadline header.
+                .push_on_service(http::PropagateDeadline::layer(
+                    config.proxy.deadline_header.clone(),
+                ))
                 // Registers the stack to be tapped.
                 .push(tap::NewTapHttp::layer(rt.tap.clone()))
                 // Records metrics for each `Logical`.
@@ -201,13 +206,16 @@ impl<C> Inbound<C> {
                 .instrument(|_: &Logical| debug_span!("profile"))
                 .arc_new_http();
 
+            let queue = rt.metrics.proxy.stack.queue(stack_labels("http", "logical"));
             discover
                 // Skip the profile stack if it takes too long to become ready.
                 .push_when_unready(config.profile_skip_timeout, http.into_inner())
                 .push_on_service(
                     rt.metrics.proxy.stack.layer(stack_labels("http", "logical")),
                 )
+                .push_on_service(queue.dequeue_layer())
                 .push(svc::NewQueue::layer_via(config.http_request_queue))
+                .push_on_service(queue.enqueue_layer())
                 .push_new_idle_cached(config.discovery_idle_timeout)
                 .push_on_service(http::Retain::layer())
                 .push_on_service(http::BoxResponse::layer())
@@ -231,8 +239,28 @@ impl<C> Inbound<C> {
                     LogicalPerRequest::from((permit.clone(), t.clone()))
                 }))
                 .check_new_service::<(policy::HttpRoutePermit, T), http::Request<http::BoxBody>>()
+                // Transcodes JSON requests on routes with a transcoding
+                // filter. This must be below the policy layer, which marks
+                // the requests to be transcoded.
+                .push_on_service(super::transcode::Transcode::layer())
+                // Serves WebSocket upgrades as sessions that are recorded by
+                // route and limited by the route's WebSocket filter, if any.
+                .push(super::websocket::NewWebSocket::layer(rt.metrics.websocket.clone()))
+                // Records the sizes of complete request and response bodies.
+                .push(super::body_sizes::NewRecordBodySizes::layer(
+                    rt.metrics.body_sizes.clone(),
+                ))
                 .push(svc::ArcNewService::layer())
-                .push(policy::NewHttpPolicy::layer(rt.metrics.http_authz.clone()))
+                .push(policy::NewHttpPolicy::layer_with_config(
+                    rt.metrics.http_authz.clone(),
+                    policy::NewHttpPolicyConfig {
+                        max_in_flight_requests: Some(config.proxy.max_in_flight_requests),
+                        hmac_secrets: config.hmac_secrets.clone(),
+                        grpc_stream_reauthorization: config.grpc_stream_reauthorization,
+                        rate_limits: rt.rate_limits.clone(),
+                    },
+                ))
+                .push_on_service(http::BoxResponse::layer())
                 // Used by tap.
                 .push_http_insert_target::<tls::ConditionalServerTls>()
                 .push_http_insert_target::<Remote<ClientAddr>>()
@@ -416,9 +444,13 @@ impl tap::Inspect for Logical {
     }
 
     fn route_labels<B>(&self, req: &http::Request<B>) -> Option<tap::Labels> {
-        req.extensions()
+        // Include the server, route, and authorization that permitted the
+        // request so that taps may match on policy route names.
+        let profile = req
+            .extensions()
             .get::<profiles::http::Route>()
-            .map(|r| r.labels().clone())
+            .map(|r| r.labels().clone());
+        tap::merge_labels(profile, Some(self.labels.clone()))
     }
 
     fn is_outbound<B>(&self, _: &http::Request<B>) -> bool {
@@ -491,10 +523,12 @@ impl ExtractParam<errors::respond::EmitHeaders, Logical> for ClientRescue {
 impl errors::HttpRescue<Error> for ClientRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if errors::is_caused_by::<std::io::Error>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(error)
+                .with_code(errors::ErrorCode::ConnectError));
         }
         if errors::is_caused_by::<errors::ConnectTimeout>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error)
+                .with_code(errors::ErrorCode::ConnectTimeout));
         }
 
         Err(error)
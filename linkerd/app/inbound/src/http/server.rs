@@ -1,4 +1,4 @@
-use super::set_identity_header::NewSetIdentityHeader;
+use super::{compress::Compress, grpc_web::GrpcWeb, set_identity_header::NewSetIdentityHeader};
 use crate::{policy, Inbound};
 pub use linkerd_app_core::proxy::http::{normalize_uri, Version};
 use linkerd_app_core::{
@@ -56,6 +56,10 @@ impl<H> Inbound<H> {
             } = config.proxy;
 
             http.check_new_service::<T, http::Request<_>>()
+                // Translates gRPC-web requests into HTTP/2 gRPC requests. This
+                // must be below the `NewNormalizeUri` layer so that HTTP/1
+                // requests have an authority before they are upgraded.
+                .push_on_service(GrpcWeb::layer(config.grpc_web))
                 // Convert origin form HTTP/1 URIs to absolute form for Hyper's
                 // `Client`. This must be below the `orig_proto::Downgrade` layer, since
                 // the request may have been downgraded from a HTTP/2 orig-proto request.
@@ -63,6 +67,12 @@ impl<H> Inbound<H> {
                 .push(NewSetIdentityHeader::layer(()))
                 // Downgrades the protocol if upgraded by an outbound proxy.
                 .push_on_service(http::orig_proto::Downgrade::layer())
+                // Compresses responses for clients that accept a compressed
+                // encoding.
+                .push_on_service(Compress::layer(
+                    config.response_compression.clone(),
+                    rt.metrics.http_compression.clone(),
+                ))
                 // Limit the number of in-flight inbound requests.
                 //
                 // TODO(ver) This concurrency limit applies only to
@@ -74,6 +84,19 @@ impl<H> Inbound<H> {
                 // Shed load by failing requests when the concurrency
                 // limit is reached.
                 .push_on_service(svc::LoadShed::layer())
+                // Convert panics into errors so that they fail only the
+                // request rather than the whole connection.
+                .push_on_service(errors::CatchPanic::layer())
+                // Fails requests that outlive the deadline in their deadline
+                // header.
+                .push_on_service(http::EnforceDeadline::layer(
+                    config.proxy.deadline_header.clone(),
+                ))
+                // Rejects requests with absurdly long targets before they
+                // are routed.
+                .push_on_service(http::LimitUri::layer(config.max_request_target_len))
+                // Records the error codes of reset streams.
+                .push_on_service(rt.metrics.http_stream_resets.layer())
                 .push(svc::NewMapErr::layer_from_target::<ServerError, _>())
                 .push_on_service(svc::MapErr::layer_boxed())
                 .push(rt.metrics.http_errors.to_layer())
@@ -92,10 +115,13 @@ impl<H> Inbound<H> {
 
     /// Uses the inner stack to serve HTTP requests for the given server-side
     /// socket.
+    ///
+    /// Connections are drained gracefully if their port's policy changes so
+    /// that it is no longer served as HTTP.
     pub fn push_http_tcp_server<T, I, HSvc>(self) -> Inbound<svc::ArcNewTcp<T, I>>
     where
         // Connection target.
-        T: Param<Version>,
+        T: Param<Version> + Param<policy::AllowPolicy>,
         T: Clone + Send + Unpin + 'static,
         // Server-side socket.
         I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + Send + Unpin + 'static,
@@ -113,15 +139,29 @@ impl<H> Inbound<H> {
     {
         self.map_stack(|config, rt, http| {
             let ProxyConfig {
-                server: ServerConfig { h2_settings, .. },
+                server:
+                    ServerConfig {
+                        h2_settings,
+                        max_connection_age,
+                        preserve_trailers,
+                        ..
+                    },
                 ..
             } = config.proxy;
 
             http.push_on_service(http::BoxRequest::layer())
                 .check_new_service::<T, http::Request<_>>()
                 .unlift_new()
+                .push(super::drain::NewDrainOnProtocolChange::layer(
+                    rt.metrics.protocol_drains.clone(),
+                ))
                 .check_new_new_service::<T, http::ClientHandle, http::Request<_>>()
-                .push(http::NewServeHttp::layer(h2_settings, rt.drain.clone()))
+                .push(http::NewServeHttp::layer(
+                    h2_settings,
+                    max_connection_age,
+                    preserve_trailers,
+                    rt.drain.clone(),
+                ))
                 .check_new_service::<T, I>()
                 .arc_new_tcp()
         })
@@ -187,6 +227,16 @@ impl errors::HttpRescue<Error> for ServerRescue {
             return Ok(errors::SyntheticHttpResponse::permission_denied(error));
         }
 
+        if errors::is_caused_by::<policy::HttpRouteConcurrencyExhausted>(&*error)
+            || errors::is_caused_by::<policy::HttpRoutePriorityShed>(&*error)
+        {
+            return Ok(errors::SyntheticHttpResponse::unavailable(error));
+        }
+
+        if errors::is_caused_by::<policy::HttpRouteRateLimited>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::rate_limited(error));
+        }
+
         if errors::is_caused_by::<policy::HttpRouteInvalidRedirect>(&*error) {
             tracing::warn!(%error);
             return Ok(errors::SyntheticHttpResponse::unexpected_error());
@@ -196,6 +246,24 @@ impl errors::HttpRescue<Error> for ServerRescue {
         {
             return Ok(errors::SyntheticHttpResponse::redirect(*status, location));
         }
+
+        // Policy-driven request failures.
+        if let Some(policy::HttpRouteInjectedFailure { status, message }) =
+            errors::cause_ref(&*error)
+        {
+            return Ok(errors::SyntheticHttpResponse::response(
+                *status,
+                message.to_string(),
+            ));
+        }
+        if let Some(policy::GrpcRouteInjectedFailure { code, message }) = errors::cause_ref(&*error)
+        {
+            return Ok(errors::SyntheticHttpResponse::grpc(
+                (*code as i32).into(),
+                message.to_string(),
+            ));
+        }
+
         if errors::is_caused_by::<policy::HttpInvalidPolicy>(&*error) {
             return Ok(errors::SyntheticHttpResponse::internal_error(
                 error.to_string(),
@@ -212,17 +280,35 @@ impl errors::HttpRescue<Error> for ServerRescue {
             return Ok(errors::SyntheticHttpResponse::loop_detected(error));
         }
         if errors::is_caused_by::<errors::FailFastError>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error)
+                .with_code(errors::ErrorCode::FailFast));
         }
         if errors::is_caused_by::<errors::LoadShedError>(&*error) {
             return Ok(errors::SyntheticHttpResponse::unavailable(error));
         }
+        if errors::is_caused_by::<http::ResponseTimeoutError>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::timeout(error));
+        }
+        if errors::is_caused_by::<http::UriTooLongError>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::uri_too_long(error));
+        }
+        if errors::is_caused_by::<http::DeadlineExceededError>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::timeout(error)
+                .with_code(errors::ErrorCode::DeadlineExceeded));
+        }
+
+        // Panics are logged and recorded when they are caught.
+        if errors::is_caused_by::<errors::Panicked>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::unexpected_error());
+        }
 
         if errors::is_caused_by::<errors::H2Error>(&*error) {
             return Err(error);
         }
 
         tracing::warn!(error, "Unexpected error");
+        errors::recent::RECENT_ERRORS
+            .record(errors::recent::ErrorRecord::new(&*error).with_label("direction", "inbound"));
         Ok(errors::SyntheticHttpResponse::unexpected_error())
     }
 }
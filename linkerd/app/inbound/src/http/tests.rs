@@ -672,6 +672,7 @@ impl Target {
                         .parse()
                         .unwrap(),
                 )),
+                client_fingerprint: None,
                 negotiated_protocol: None,
             }),
         )
@@ -686,6 +687,7 @@ impl Target {
                         .parse()
                         .unwrap(),
                 )),
+                client_fingerprint: None,
                 negotiated_protocol: None,
             }),
         )
@@ -736,6 +738,8 @@ impl svc::Param<policy::AllowPolicy> for Target {
                 kind: "serverauthorization".into(),
                 name: "testsaz".into(),
             }),
+            local_addrs: vec![],
+            validity: vec![],
         }]);
         let (policy, _) = policy::AllowPolicy::for_test(
             self.param(),
@@ -748,6 +752,8 @@ impl svc::Param<policy::AllowPolicy> for Target {
                     kind: "server".into(),
                     name: "testsrv".into(),
                 }),
+                bandwidth_limit: None,
+                duplex: Default::default(),
             },
         );
         policy
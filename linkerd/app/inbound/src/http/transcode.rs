@@ -0,0 +1,245 @@
+//! Transcodes JSON requests into unary gRPC requests.
+//!
+//! Routes opt into transcoding with a `Transcode` filter, which marks matching
+//! requests with the route's [`Transcoder`]. JSON requests posted to the path
+//! of a unary method described by the transcoder are buffered and sent to the
+//! application as HTTP/2 gRPC requests. The application's response is buffered
+//! and its message returned as JSON or, if the call failed, its gRPC status is
+//! described by a JSON error with a corresponding HTTP status.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{
+    proxy::http::{self, header, HeaderValue, HttpBody},
+    svc, Error,
+};
+use linkerd_http_transcode::{self as transcode, Method, Transcoder};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Bounds the size of buffered messages. gRPC servers accept messages of up
+/// to 4MiB by default.
+const MAX_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// The gRPC status code that indicates a message was too large.
+const GRPC_RESOURCE_EXHAUSTED: i32 = 8;
+
+#[derive(Clone, Debug)]
+pub struct Transcode<S> {
+    inner: S,
+}
+
+type ResponseFuture =
+    Pin<Box<dyn Future<Output = Result<http::Response<http::BoxBody>, Error>> + Send + 'static>>;
+
+// === impl Transcode ===
+
+impl<S> Transcode<S> {
+    pub fn layer() -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for Transcode<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S: Clone + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::Either<future::ErrInto<S::Future, Error>, ResponseFuture>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let method = match req
+            .extensions()
+            .get::<Transcoder>()
+            .filter(|_| is_json_post(&req))
+            .and_then(|t| t.method(req.uri().path()))
+        {
+            Some(method) => method,
+            None => return future::Either::Left(self.inner.call(req).err_into()),
+        };
+        tracing::debug!(method = %method.full_name(), "Transcoding JSON request");
+
+        // The request is dispatched once its body has been read, so the ready
+        // service is taken and replaced with a clone.
+        let clone = self.inner.clone();
+        let mut svc = std::mem::replace(&mut self.inner, clone);
+        future::Either::Right(Box::pin(async move {
+            let (mut parts, mut body) = req.into_parts();
+            let json = match read_body(&mut body).await? {
+                Some(json) => json,
+                None => {
+                    return Ok(error_response(
+                        http::StatusCode::PAYLOAD_TOO_LARGE,
+                        GRPC_RESOURCE_EXHAUSTED,
+                        "request message is too large",
+                    ))
+                }
+            };
+            let message = match method.encode_request(&json) {
+                Ok(message) => message,
+                Err(error) => {
+                    let code = transcode::GRPC_INVALID_ARGUMENT;
+                    let status = transcode::http_status(code);
+                    return Ok(error_response(status, code, &error.to_string()));
+                }
+            };
+
+            let version = parts.version;
+            parts.version = ::http::Version::HTTP_2;
+            parts.headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/grpc"),
+            );
+            parts
+                .headers
+                .insert(header::CONTENT_LENGTH, message.len().into());
+            parts
+                .headers
+                .insert(header::TE, HeaderValue::from_static("trailers"));
+            let req = http::Request::from_parts(parts, http::BoxBody::from(message));
+
+            let rsp = svc.call(req).await.map_err(Into::into)?;
+            let mut rsp = transcode_response(&method, rsp).await?;
+            *rsp.version_mut() = version;
+            Ok(rsp)
+        }))
+    }
+}
+
+fn is_json_post<B>(req: &http::Request<B>) -> bool {
+    req.method() == http::Method::POST
+        && req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map_or(false, |ct| {
+                ct.split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .eq_ignore_ascii_case("application/json")
+            })
+}
+
+/// Reads a body, returning `None` if it exceeds [`MAX_MESSAGE_BYTES`].
+async fn read_body(body: &mut http::BoxBody) -> Result<Option<Bytes>, Error> {
+    let mut buf = BytesMut::new();
+    while let Some(data) = body.data().await {
+        let data = data?;
+        if buf.len() + data.remaining() > MAX_MESSAGE_BYTES {
+            return Ok(None);
+        }
+        buf.put(data);
+    }
+    Ok(Some(buf.freeze()))
+}
+
+async fn transcode_response(
+    method: &Method,
+    rsp: http::Response<http::BoxBody>,
+) -> Result<http::Response<http::BoxBody>, Error> {
+    let is_grpc = rsp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |ct| ct.starts_with("application/grpc"));
+    if !is_grpc {
+        // The response was not produced by the application's gRPC server,
+        // e.g. because the proxy failed the request.
+        return Ok(rsp);
+    }
+
+    let (mut parts, mut body) = rsp.into_parts();
+    let message = match read_body(&mut body).await? {
+        Some(message) => message,
+        None => {
+            return Ok(error_response(
+                http::StatusCode::BAD_GATEWAY,
+                GRPC_RESOURCE_EXHAUSTED,
+                "response message is too large",
+            ))
+        }
+    };
+
+    // Responses that fail without a message may carry their status in their
+    // headers rather than their trailers.
+    let trailers = body.trailers().await?;
+    let (code, msg) = match trailers.as_ref().and_then(grpc_status) {
+        Some(status) => status,
+        None => grpc_status(&parts.headers).unwrap_or_else(|| {
+            (
+                transcode::GRPC_INTERNAL,
+                "response is missing a grpc-status".to_string(),
+            )
+        }),
+    };
+    if code != transcode::GRPC_OK {
+        return Ok(error_response(transcode::http_status(code), code, &msg));
+    }
+
+    let json = match method.decode_response(&message) {
+        Ok(json) => json,
+        Err(error) => {
+            return Ok(error_response(
+                http::StatusCode::BAD_GATEWAY,
+                transcode::GRPC_INTERNAL,
+                &error.to_string(),
+            ))
+        }
+    };
+
+    let grpc_headers = parts
+        .headers
+        .keys()
+        .filter(|name| name.as_str().starts_with("grpc-"))
+        .cloned()
+        .collect::<Vec<_>>();
+    for name in grpc_headers {
+        parts.headers.remove(name);
+    }
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    parts
+        .headers
+        .insert(header::CONTENT_LENGTH, json.len().into());
+    parts.status = http::StatusCode::OK;
+    Ok(http::Response::from_parts(parts, http::BoxBody::from(json)))
+}
+
+fn grpc_status(headers: &header::HeaderMap) -> Option<(i32, String)> {
+    let code = headers.get("grpc-status")?.to_str().ok()?.parse().ok()?;
+    let message = headers
+        .get("grpc-message")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    Some((code, message))
+}
+
+fn error_response(
+    status: http::StatusCode,
+    code: i32,
+    message: &str,
+) -> http::Response<http::BoxBody> {
+    let body = transcode::error_body(code, message);
+    http::Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(http::BoxBody::from(body))
+        .expect("error response must be valid")
+}
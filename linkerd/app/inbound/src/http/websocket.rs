@@ -0,0 +1,90 @@
+//! Configures the sessions served for WebSocket upgrades.
+//!
+//! Every WebSocket session is recorded in per-route metrics. Routes may limit
+//! the lifetime of their sessions with a `WebSocket` filter, which marks
+//! matching requests with the route's [`WebSocketPolicy`].
+
+use crate::{metrics::websocket::WebSocketMetrics, policy};
+use linkerd_app_core::{
+    metrics::RouteLabels,
+    proxy::http::{
+        self,
+        upgrade::{Http11Upgrade, WebSocketSession},
+    },
+    svc,
+};
+use linkerd_proxy_server_policy::http::{filter::WebSocketPolicy, r#match::upgrade};
+use std::task::{Context, Poll};
+
+#[derive(Clone, Debug)]
+pub struct NewWebSocket<N> {
+    metrics: WebSocketMetrics,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct WebSocket<S> {
+    metrics: WebSocketMetrics,
+    route: RouteLabels,
+    inner: S,
+}
+
+// === impl NewWebSocket ===
+
+impl<N> NewWebSocket<N> {
+    pub fn layer(metrics: WebSocketMetrics) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<(policy::HttpRoutePermit, T)> for NewWebSocket<N>
+where
+    N: svc::NewService<(policy::HttpRoutePermit, T)>,
+{
+    type Service = WebSocket<N::Service>;
+
+    fn new_service(&self, (permit, target): (policy::HttpRoutePermit, T)) -> Self::Service {
+        let route = permit.labels.route.clone();
+        let inner = self.inner.new_service((permit, target));
+        WebSocket {
+            metrics: self.metrics.clone(),
+            route,
+            inner,
+        }
+    }
+}
+
+// === impl WebSocket ===
+
+impl<B, S> svc::Service<http::Request<B>> for WebSocket<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let policy = req.extensions_mut().remove::<WebSocketPolicy>();
+        if upgrade::is_websocket(&req) {
+            if let Some(http11) = req.extensions().get::<Http11Upgrade>() {
+                let policy = policy.unwrap_or_default();
+                tracing::debug!(?policy, "Serving WebSocket session");
+                http11.set_websocket_session(WebSocketSession {
+                    max_duration: policy.max_session_duration,
+                    idle_timeout: policy.idle_timeout,
+                    metrics: Some(self.metrics.route(self.route.clone())),
+                });
+            }
+        }
+        self.inner.call(req)
+    }
+}
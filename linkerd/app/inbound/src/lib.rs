@@ -18,7 +18,12 @@ mod server;
 #[cfg(any(test, feature = "test-util", fuzzing))]
 pub mod test_util;
 
-pub use self::{metrics::InboundMetrics, policy::DefaultPolicy};
+pub use self::{
+    accept::ConnectionLimits,
+    http::ResponseCompression,
+    metrics::{policy_updates, top_clients, InboundMetrics},
+    policy::DefaultPolicy,
+};
 use linkerd_app_core::{
     config::{ConnectConfig, ProxyConfig, QueueConfig},
     drain,
@@ -29,7 +34,7 @@ use linkerd_app_core::{
     transport::{self, Remote, ServerAddr},
     Error, NameAddr, NameMatch, ProxyRuntime,
 };
-use std::{fmt::Debug, time::Duration};
+use std::{fmt::Debug, path::PathBuf, sync::Arc, time::Duration};
 use thiserror::Error;
 use tracing::debug_span;
 
@@ -53,6 +58,48 @@ pub struct Config {
 
     /// Configures how HTTP requests are buffered *for each inbound port*.
     pub http_request_queue: QueueConfig,
+
+    /// Limits the connections accepted on the inbound listener.
+    pub connection_limits: ConnectionLimits,
+
+    /// Configures the compression of HTTP responses. When unset, responses
+    /// are not compressed.
+    pub response_compression: Option<ResponseCompression>,
+
+    /// Enables the translation of gRPC-web requests into gRPC, so that
+    /// browser clients may call gRPC services.
+    pub grpc_web: bool,
+
+    /// Additional addresses on which inbound connections are accepted.
+    ///
+    /// Unlike the proxy port, connections on these listeners are handled as
+    /// if they targeted the listener's port, so each listener is governed by
+    /// the policy discovered for its port.
+    pub additional_listen_addrs: Vec<transport::ListenAddr>,
+
+    /// The secrets with which requests are signed for HMAC authorizations.
+    pub hmac_secrets: policy::HmacSecrets,
+
+    /// When set, the number of requests authorized for each of this many of
+    /// an authorization's most frequent clients is estimated and reported.
+    pub authz_top_clients: Option<usize>,
+
+    /// When set, requests whose path and query are longer than this many
+    /// bytes are rejected before they are routed.
+    pub max_request_target_len: Option<usize>,
+
+    /// When set, this file is watched for a list of additional ports on
+    /// which protocol detection is disabled by default.
+    pub opaque_ports_file: Option<PathBuf>,
+
+    /// How long connections remain open after their authorization is revoked
+    /// (by a policy change or the expiry of a time-limited authorization)
+    /// before they are closed.
+    pub revocation_grace_period: Duration,
+
+    /// When set, gRPC response streams are re-authorized as they are served,
+    /// so that streams do not outlive their clients' authorizations.
+    pub grpc_stream_reauthorization: Option<policy::GrpcStreamReauthorization>,
 }
 
 #[derive(Clone)]
@@ -69,6 +116,7 @@ struct Runtime {
     tap: tap::Registry,
     span_sink: OpenCensusSink,
     drain: drain::Watch,
+    rate_limits: policy::RateLimits,
 }
 
 /// Indicates the name to be used to route gateway connections.
@@ -116,14 +164,23 @@ impl<S> Inbound<S> {
     pub fn authorize_http<N>(
         &self,
     ) -> impl svc::layer::Layer<N, Service = policy::NewHttpPolicy<N>> + Clone {
-        policy::NewHttpPolicy::layer(self.runtime.metrics.http_authz.clone())
+        policy::NewHttpPolicy::layer_with_config(
+            self.runtime.metrics.http_authz.clone(),
+            policy::NewHttpPolicyConfig {
+                rate_limits: self.runtime.rate_limits.clone(),
+                ..Default::default()
+            },
+        )
     }
 
     /// A helper for gateways to instrument policy checks.
     pub fn authorize_tcp<N>(
         &self,
     ) -> impl svc::layer::Layer<N, Service = policy::NewTcpPolicy<N>> + Clone {
-        policy::NewTcpPolicy::layer(self.runtime.metrics.tcp_authz.clone())
+        policy::NewTcpPolicy::layer(
+            self.runtime.metrics.tcp_authz.clone(),
+            self.config.revocation_grace_period,
+        )
     }
 
     pub fn into_stack(self) -> svc::Stack<S> {
@@ -151,11 +208,12 @@ impl<S> Inbound<S> {
 impl Inbound<()> {
     pub fn new(config: Config, runtime: ProxyRuntime) -> Self {
         let runtime = Runtime {
-            metrics: InboundMetrics::new(runtime.metrics),
+            metrics: InboundMetrics::new(runtime.metrics, config.authz_top_clients),
             identity: runtime.identity,
             tap: runtime.tap,
             span_sink: runtime.span_sink,
             drain: runtime.drain,
+            rate_limits: Default::default(),
         };
         Self {
             config,
@@ -175,6 +233,18 @@ impl Inbound<()> {
         self.runtime.metrics.clone()
     }
 
+    /// Returns a handle for updating the set of opaque ports at runtime.
+    pub fn opaque_ports(&self) -> policy::OpaquePorts {
+        self.config.policy.opaque_ports().clone()
+    }
+
+    /// Shares route rate limits with the other replicas of this proxy's
+    /// servers by leasing their tokens from the given quota service.
+    pub fn with_rate_limit_quota(mut self, quota: policy::Quota) -> Self {
+        self.runtime.rate_limits = policy::RateLimits::with_quota(quota);
+        self
+    }
+
     pub fn with_stack<S>(self, stack: S) -> Inbound<S> {
         self.map_stack(move |_, _, _| svc::stack(stack))
     }
@@ -201,6 +271,7 @@ impl Inbound<()> {
             // forwarding and HTTP proxying).
             let ConnectConfig {
                 ref keepalive,
+                ref socket_options,
                 ref timeout,
                 ..
             } = config.proxy.connect;
@@ -209,14 +280,20 @@ impl Inbound<()> {
             #[error("inbound connection must not target port {0}")]
             struct Loop(u16);
 
-            svc::stack(transport::ConnectTcp::new(*keepalive))
+            // The proxy accepts inbound connections on its proxy port and on
+            // any additional listen addresses.
+            let listen_ports = std::iter::once(proxy_port)
+                .chain(config.additional_listen_addrs.iter().map(|a| a.port()))
+                .collect::<Arc<[u16]>>();
+
+            svc::stack(transport::ConnectTcp::new(*keepalive, *socket_options))
                 // Limits the time we wait for a connection to be established.
                 .push_connect_timeout(*timeout)
-                // Prevent connections that would target the inbound proxy port from looping.
+                // Prevent connections that would target an inbound listener from looping.
                 .push_filter(move |t: T| {
                     let addr = t.param();
                     let port = addr.port();
-                    if port == proxy_port {
+                    if listen_ports.contains(&port) {
                         return Err(Loop(port));
                     }
                     Ok(addr)
@@ -244,6 +321,7 @@ impl<S> Inbound<S> {
     where
         T: svc::Param<transport::labels::Key>
             + svc::Param<Remote<ServerAddr>>
+            + svc::Param<tcp::DuplexConfig>
             + Clone
             + Send
             + Sync
@@ -255,14 +333,16 @@ impl<S> Inbound<S> {
         S::Metadata: Send + Unpin,
         S::Future: Send,
     {
-        self.map_stack(|_, rt, connect| {
+        self.map_stack(|config, rt, connect| {
             connect
                 .push(transport::metrics::Client::layer(
                     rt.metrics.proxy.transport.clone(),
                 ))
                 .push(svc::stack::WithoutConnectionMetadata::layer())
                 .push_new_thunk()
-                .push_on_service(tcp::Forward::layer())
+                .push(tcp::NewForward::layer(
+                    config.proxy.server.max_connection_age,
+                ))
                 .push_on_service(drain::Retain::layer(rt.drain.clone()))
                 .instrument(|_: &_| debug_span!("tcp"))
                 .push(svc::NewMapErr::layer_from_target::<ForwardError, _>())
@@ -9,8 +9,15 @@
 //! `DashMap` as we migrate other metrics registries.
 
 pub(crate) mod authz;
+pub(crate) mod body_sizes;
 pub(crate) mod error;
+pub mod policy_updates;
+pub(crate) mod protocol_drains;
+pub(crate) mod protocol_hints;
+pub mod top_clients;
+pub(crate) mod websocket;
 
+use linkerd_app_core::errors;
 pub use linkerd_app_core::metrics::*;
 
 /// Holds outbound proxy metrics.
@@ -18,9 +25,17 @@ pub use linkerd_app_core::metrics::*;
 pub struct InboundMetrics {
     pub http_authz: authz::HttpAuthzMetrics,
     pub http_errors: error::HttpErrorMetrics,
+    pub http_stream_resets: errors::StreamResets,
+    pub http_compression: crate::http::CompressionMetrics,
+    pub websocket: websocket::WebSocketMetrics,
+    pub body_sizes: body_sizes::BodySizeMetrics,
+    pub policy_updates: policy_updates::PolicyUpdateMetrics,
 
     pub(crate) tcp_authz: authz::TcpAuthzMetrics,
     pub tcp_errors: error::TcpErrorMetrics,
+    pub(crate) connections_shed: crate::accept::ShedMetrics,
+    pub(crate) protocol_hints: protocol_hints::ProtocolHintMetrics,
+    pub(crate) protocol_drains: protocol_drains::ProtocolDrainMetrics,
 
     /// Holds metrics that are common to both inbound and outbound proxies. These metrics are
     /// reported separately
@@ -28,12 +43,22 @@ pub struct InboundMetrics {
 }
 
 impl InboundMetrics {
-    pub(crate) fn new(proxy: Proxy) -> Self {
+    pub(crate) fn new(proxy: Proxy, authz_top_clients: Option<usize>) -> Self {
         Self {
-            http_authz: authz::HttpAuthzMetrics::default(),
+            http_authz: authz_top_clients
+                .map(authz::HttpAuthzMetrics::with_authz_top_clients)
+                .unwrap_or_default(),
             http_errors: error::HttpErrorMetrics::default(),
+            http_stream_resets: errors::StreamResets::inbound(),
+            http_compression: crate::http::CompressionMetrics::default(),
+            websocket: websocket::WebSocketMetrics::default(),
+            body_sizes: body_sizes::BodySizeMetrics::default(),
+            policy_updates: policy_updates::PolicyUpdateMetrics::default(),
             tcp_authz: authz::TcpAuthzMetrics::default(),
             tcp_errors: error::TcpErrorMetrics::default(),
+            connections_shed: crate::accept::ShedMetrics::default(),
+            protocol_hints: protocol_hints::ProtocolHintMetrics::default(),
+            protocol_drains: protocol_drains::ProtocolDrainMetrics::default(),
             proxy,
         }
     }
@@ -43,9 +68,17 @@ impl FmtMetrics for InboundMetrics {
     fn fmt_metrics(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.http_authz.fmt_metrics(f)?;
         self.http_errors.fmt_metrics(f)?;
+        self.http_stream_resets.fmt_metrics(f)?;
+        self.http_compression.fmt_metrics(f)?;
+        self.websocket.fmt_metrics(f)?;
+        self.body_sizes.fmt_metrics(f)?;
+        self.policy_updates.fmt_metrics(f)?;
 
         self.tcp_authz.fmt_metrics(f)?;
         self.tcp_errors.fmt_metrics(f)?;
+        self.connections_shed.fmt_metrics(f)?;
+        self.protocol_hints.fmt_metrics(f)?;
+        self.protocol_drains.fmt_metrics(f)?;
 
         // XXX: Proxy metrics are reported elsewhere.
 
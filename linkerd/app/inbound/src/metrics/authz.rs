@@ -1,25 +1,29 @@
+use super::top_clients::{Client, SpaceSaving, TopClients};
 use crate::policy::{AllowPolicy, HttpRoutePermit, ServerPermit};
 use linkerd_app_core::{
     metrics::{
-        metrics, Counter, FmtLabels, FmtMetrics, RouteAuthzLabels, RouteLabels, ServerAuthzLabels,
-        ServerLabel, TargetAddr, TlsAccept,
+        metrics, Counter, CounterWithExemplar, FmtLabels, FmtMetrics, Gauge, RouteAuthzLabels,
+        RouteLabels, ServerAuthzLabels, ServerLabel, TargetAddr, TlsAccept,
     },
-    tls,
-    transport::OrigDstAddr,
+    tls, trace_context,
+    transport::{ClientAddr, OrigDstAddr, Remote},
 };
 use parking_lot::Mutex;
 use std::{collections::HashMap, sync::Arc};
 
 metrics! {
-    inbound_http_authz_allow_total: Counter {
+    inbound_http_authz_allow_total: CounterWithExemplar {
         "The total number of inbound HTTP requests that were authorized"
     },
-    inbound_http_authz_deny_total: Counter {
+    inbound_http_authz_deny_total: CounterWithExemplar {
         "The total number of inbound HTTP requests that could not be processed due to a proxy error."
     },
-    inbound_http_route_not_found_total: Counter {
+    inbound_http_route_not_found_total: CounterWithExemplar {
         "The total number of inbound HTTP requests that could not be associated with a route"
     },
+    inbound_http_authz_top_client_allow: Gauge {
+        "An estimate of the number of inbound HTTP requests authorized for each of an authorization's most frequent clients. Estimates may exceed the true count."
+    },
 
     inbound_tcp_authz_allow_total: Counter {
         "The total number of inbound TCP connections that were authorized"
@@ -40,11 +44,23 @@ pub(crate) struct TcpAuthzMetrics(Arc<TcpInner>);
 
 #[derive(Debug, Default)]
 struct HttpInner {
-    allow: Mutex<HashMap<RouteAuthzKey, Counter>>,
-    deny: Mutex<HashMap<RouteKey, Counter>>,
-    route_not_found: Mutex<HashMap<ServerKey, Counter>>,
+    allow: Mutex<HashMap<RouteAuthzKey, CounterWithExemplar>>,
+    deny: Mutex<HashMap<RouteKey, CounterWithExemplar>>,
+    route_not_found: Mutex<HashMap<ServerKey, CounterWithExemplar>>,
+    top_clients: TopClients,
+    authz_clients: Option<AuthzClients>,
+}
+
+/// Tracks the most frequent clients authorized by each authorization, so that
+/// operators can tell whether an authorization is still in use.
+#[derive(Debug)]
+struct AuthzClients {
+    capacity: usize,
+    by_authz: Mutex<HashMap<RouteAuthzLabels, SpaceSaving>>,
 }
 
+struct ClientLabel<'c>(&'c Client);
+
 #[derive(Debug, Default)]
 struct TcpInner {
     allow: Mutex<HashMap<ServerAuthzKey, Counter>>,
@@ -67,36 +83,89 @@ type RouteAuthzKey = Key<RouteAuthzLabels>;
 // === impl HttpAuthzMetrics ===
 
 impl HttpAuthzMetrics {
-    pub fn allow(&self, permit: &HttpRoutePermit, tls: tls::ConditionalServerTls) {
+    /// Returns metrics that also estimate the number of requests authorized
+    /// for each of an authorization's `capacity` most frequent clients.
+    pub(crate) fn with_authz_top_clients(capacity: usize) -> Self {
+        Self(Arc::new(HttpInner {
+            authz_clients: Some(AuthzClients {
+                capacity,
+                by_authz: Default::default(),
+            }),
+            ..Default::default()
+        }))
+    }
+
+    pub fn top_clients(&self) -> TopClients {
+        self.0.top_clients.clone()
+    }
+
+    pub fn allow(
+        &self,
+        permit: &HttpRoutePermit,
+        client: Remote<ClientAddr>,
+        tls: tls::ConditionalServerTls,
+        trace_id: Option<trace_context::Id>,
+    ) {
+        let client = Client::new(client, &tls);
+        if let Some(authz_clients) = &self.0.authz_clients {
+            authz_clients
+                .by_authz
+                .lock()
+                .entry(permit.labels.clone())
+                .or_insert_with(|| SpaceSaving::new(authz_clients.capacity))
+                .incr(client.clone());
+        }
         self.0
-            .allow
-            .lock()
+            .top_clients
+            .request(permit.labels.route.server.clone(), client);
+        let mut allow = self.0.allow.lock();
+        let counter = allow
             .entry(RouteAuthzKey::from_permit(permit, tls))
-            .or_default()
-            .incr();
+            .or_default();
+        incr(counter, trace_id);
     }
 
     pub fn route_not_found(
         &self,
         labels: ServerLabel,
         dst: OrigDstAddr,
+        client: Remote<ClientAddr>,
         tls: tls::ConditionalServerTls,
+        trace_id: Option<trace_context::Id>,
     ) {
         self.0
-            .route_not_found
-            .lock()
+            .top_clients
+            .request(labels.clone(), Client::new(client, &tls));
+        let mut route_not_found = self.0.route_not_found.lock();
+        let counter = route_not_found
             .entry(ServerKey::new(labels, dst, tls))
-            .or_default()
-            .incr();
+            .or_default();
+        incr(counter, trace_id);
     }
 
-    pub fn deny(&self, labels: RouteLabels, dst: OrigDstAddr, tls: tls::ConditionalServerTls) {
+    pub fn deny(
+        &self,
+        labels: RouteLabels,
+        dst: OrigDstAddr,
+        client: Remote<ClientAddr>,
+        tls: tls::ConditionalServerTls,
+        trace_id: Option<trace_context::Id>,
+    ) {
         self.0
-            .deny
-            .lock()
-            .entry(RouteKey::new(labels, dst, tls))
-            .or_default()
-            .incr();
+            .top_clients
+            .deny(labels.server.clone(), Client::new(client, &tls));
+        let mut deny = self.0.deny.lock();
+        let counter = deny.entry(RouteKey::new(labels, dst, tls)).or_default();
+        incr(counter, trace_id);
+    }
+}
+
+/// Increments a counter, recording the request's trace, if any, as an
+/// exemplar.
+fn incr(counter: &CounterWithExemplar, trace_id: Option<trace_context::Id>) {
+    match trace_id {
+        Some(id) => counter.incr_with_exemplar(vec![("trace_id", id.to_string())]),
+        None => counter.incr(),
     }
 }
 
@@ -140,10 +209,32 @@ impl FmtMetrics for HttpAuthzMetrics {
         }
         drop(route_not_found);
 
+        if let Some(authz_clients) = &self.0.authz_clients {
+            let by_authz = authz_clients.by_authz.lock();
+            if !by_authz.is_empty() {
+                inbound_http_authz_top_client_allow.fmt_help(f)?;
+                for (labels, clients) in by_authz.iter() {
+                    for top in clients.top() {
+                        inbound_http_authz_top_client_allow.fmt_metric_labeled(
+                            f,
+                            &Gauge::from(top.count),
+                            &(labels, ClientLabel(&top.client)),
+                        )?;
+                    }
+                }
+            }
+        }
+
         Ok(())
     }
 }
 
+impl FmtLabels for ClientLabel<'_> {
+    fn fmt_labels(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "client=\"{}\"", self.0)
+    }
+}
+
 // === impl TcpAuthzMetrics ===
 
 impl TcpAuthzMetrics {
@@ -237,3 +328,41 @@ impl ServerAuthzKey {
         Self::new(permit.labels.clone(), permit.dst, tls)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_proxy_server_policy::Meta;
+
+    #[test]
+    fn reports_authz_top_clients() {
+        let permit = HttpRoutePermit {
+            dst: OrigDstAddr(([192, 0, 2, 1], 8080).into()),
+            labels: RouteAuthzLabels {
+                route: RouteLabels {
+                    server: ServerLabel(Meta::new_default("server")),
+                    route: Meta::new_default("route"),
+                },
+                authz: Meta::new_default("authz"),
+            },
+        };
+        let tls = || tls::ConditionalServerTls::None(tls::NoServerTls::Loopback);
+        let client = |n: u8| Remote(ClientAddr(([192, 0, 2, n], 40000).into()));
+
+        let metrics = HttpAuthzMetrics::default();
+        metrics.allow(&permit, client(2), tls(), None);
+        assert!(!metrics
+            .as_display()
+            .to_string()
+            .contains("inbound_http_authz_top_client_allow"));
+
+        let metrics = HttpAuthzMetrics::with_authz_top_clients(2);
+        for _ in 0..3 {
+            metrics.allow(&permit, client(2), tls(), None);
+        }
+        metrics.allow(&permit, client(3), tls(), None);
+        let text = metrics.as_display().to_string();
+        assert!(text.contains("client=\"192.0.2.2\"} 3"), "{text}");
+        assert!(text.contains("client=\"192.0.2.3\"} 1"), "{text}");
+    }
+}
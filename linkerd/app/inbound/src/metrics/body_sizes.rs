@@ -0,0 +1,77 @@
+use linkerd_app_core::metrics::{metrics, Bounds, Bucket, FmtMetrics, Histogram, RouteLabels};
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt, sync::Arc};
+
+metrics! {
+    inbound_request_body_size_bytes: Histogram<u64> {
+        "The sizes of request bodies received on each inbound route"
+    },
+    inbound_response_body_size_bytes: Histogram<u64> {
+        "The sizes of response bodies sent on each inbound route"
+    }
+}
+
+/// Body sizes from 64B to 16MiB.
+const BOUNDS: &Bounds = &Bounds(&[
+    Bucket::Le(64.0),
+    Bucket::Le(256.0),
+    Bucket::Le(1024.0),
+    Bucket::Le(4096.0),
+    Bucket::Le(16_384.0),
+    Bucket::Le(65_536.0),
+    Bucket::Le(262_144.0),
+    Bucket::Le(1_048_576.0),
+    Bucket::Le(4_194_304.0),
+    Bucket::Le(16_777_216.0),
+    Bucket::Inf,
+]);
+
+/// Records the sizes of complete request and response bodies by route.
+///
+/// Histograms are labeled only by route, so their cardinality is bounded by
+/// the number of routes configured by policy.
+#[derive(Clone, Debug, Default)]
+pub struct BodySizeMetrics(Arc<Mutex<HashMap<RouteLabels, Arc<RouteBodySizes>>>>);
+
+#[derive(Debug)]
+pub(crate) struct RouteBodySizes {
+    pub(crate) request: Histogram<u64>,
+    pub(crate) response: Histogram<u64>,
+}
+
+// === impl BodySizeMetrics ===
+
+impl BodySizeMetrics {
+    pub(crate) fn route(&self, labels: RouteLabels) -> Arc<RouteBodySizes> {
+        self.0.lock().entry(labels).or_default().clone()
+    }
+}
+
+impl FmtMetrics for BodySizeMetrics {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let routes = self.0.lock();
+        if routes.is_empty() {
+            return Ok(());
+        }
+        let scopes = || routes.iter().map(|(labels, sizes)| (labels, &**sizes));
+
+        inbound_request_body_size_bytes.fmt_help(f)?;
+        inbound_request_body_size_bytes.fmt_scopes(f, scopes(), |s| &s.request)?;
+
+        inbound_response_body_size_bytes.fmt_help(f)?;
+        inbound_response_body_size_bytes.fmt_scopes(f, scopes(), |s| &s.response)?;
+
+        Ok(())
+    }
+}
+
+// === impl RouteBodySizes ===
+
+impl Default for RouteBodySizes {
+    fn default() -> Self {
+        Self {
+            request: Histogram::new(BOUNDS),
+            response: Histogram::new(BOUNDS),
+        }
+    }
+}
@@ -3,12 +3,17 @@ mod tcp;
 
 pub(crate) use self::{http::HttpErrorMetrics, tcp::TcpErrorMetrics};
 use crate::{
-    policy::{HttpRouteNotFound, HttpRouteUnauthorized, ServerUnauthorized},
+    policy::{
+        GrpcRouteInjectedFailure, HttpRouteConcurrencyExhausted, HttpRouteInjectedFailure,
+        HttpRouteNotFound, HttpRoutePriorityShed, HttpRouteRateLimited, HttpRouteUnauthorized,
+        ServerUnauthorized,
+    },
     GatewayDomainInvalid, GatewayIdentityRequired, GatewayLoop,
 };
 use linkerd_app_core::{
-    errors::{FailFastError, LoadShedError},
+    errors::{ErrorCode, ErrorSource, FailFastError, H2Error, LoadShedError},
     metrics::FmtLabels,
+    proxy::http::{h2::Reason, DeadlineExceededError, UriTooLongError},
     tls,
 };
 use std::fmt;
@@ -16,13 +21,18 @@ use std::fmt;
 /// Inbound proxy error types.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 enum ErrorKind {
+    Cancelled,
+    DeadlineExceeded,
     FailFast,
     LoadShed,
+    RateLimited,
     GatewayDomainInvalid,
     GatewayIdentityRequired,
     GatewayLoop,
     Io,
+    StreamReset,
     TlsDetectTimeout,
+    UriTooLong,
     Unexpected,
 }
 
@@ -38,6 +48,11 @@ impl ErrorKind {
             return None;
         }
 
+        // Failures injected by policy are intentional.
+        if err.is::<HttpRouteInjectedFailure>() || err.is::<GrpcRouteInjectedFailure>() {
+            return None;
+        }
+
         if err.is::<FailFastError>() {
             Some(ErrorKind::FailFast)
         } else if err.is::<std::io::Error>() {
@@ -50,31 +65,91 @@ impl ErrorKind {
             Some(ErrorKind::GatewayIdentityRequired)
         } else if err.is::<GatewayLoop>() {
             Some(ErrorKind::GatewayLoop)
-        } else if err.is::<LoadShedError>() {
+        } else if err.is::<LoadShedError>()
+            || err.is::<HttpRouteConcurrencyExhausted>()
+            || err.is::<HttpRoutePriorityShed>()
+        {
             Some(ErrorKind::LoadShed)
+        } else if err.is::<HttpRouteRateLimited>() {
+            Some(ErrorKind::RateLimited)
+        } else if err.is::<DeadlineExceededError>() {
+            Some(ErrorKind::DeadlineExceeded)
+        } else if err.is::<UriTooLongError>() {
+            Some(ErrorKind::UriTooLong)
+        } else if let Some(e) = err.downcast_ref::<H2Error>() {
+            // Clients cancel the streams they no longer need, so cancellations
+            // are attributed to the client and other resets to the upstream.
+            if e.reason() == Some(Reason::CANCEL) {
+                Some(ErrorKind::Cancelled)
+            } else {
+                Some(ErrorKind::StreamReset)
+            }
         } else if let Some(e) = err.source() {
             Self::mk(e)
         } else {
             Some(ErrorKind::Unexpected)
         }
     }
+
+    fn code(&self) -> ErrorCode {
+        match self {
+            ErrorKind::Cancelled => ErrorCode::Cancelled,
+            ErrorKind::DeadlineExceeded => ErrorCode::DeadlineExceeded,
+            ErrorKind::FailFast => ErrorCode::FailFast,
+            ErrorKind::LoadShed => ErrorCode::LoadShed,
+            ErrorKind::RateLimited => ErrorCode::RateLimited,
+            ErrorKind::GatewayDomainInvalid => ErrorCode::RouteNotFound,
+            ErrorKind::GatewayIdentityRequired => ErrorCode::Unauthenticated,
+            ErrorKind::GatewayLoop => ErrorCode::LoopDetected,
+            ErrorKind::Io => ErrorCode::ConnectError,
+            ErrorKind::StreamReset => ErrorCode::StreamReset,
+            ErrorKind::TlsDetectTimeout => ErrorCode::DetectTimeout,
+            ErrorKind::UriTooLong => ErrorCode::UriTooLong,
+            ErrorKind::Unexpected => ErrorCode::Internal,
+        }
+    }
+
+    /// Returns the party that caused the error.
+    fn source(&self) -> ErrorSource {
+        match self {
+            ErrorKind::Cancelled
+            | ErrorKind::GatewayDomainInvalid
+            | ErrorKind::GatewayIdentityRequired
+            | ErrorKind::TlsDetectTimeout
+            | ErrorKind::UriTooLong => ErrorSource::Client,
+            ErrorKind::Io | ErrorKind::StreamReset => ErrorSource::Upstream,
+            ErrorKind::DeadlineExceeded
+            | ErrorKind::FailFast
+            | ErrorKind::LoadShed
+            | ErrorKind::RateLimited
+            | ErrorKind::GatewayLoop
+            | ErrorKind::Unexpected => ErrorSource::Proxy,
+        }
+    }
 }
 
 impl FmtLabels for ErrorKind {
     fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "error=\"{}\"",
+            "error=\"{}\",code=\"{}\",source=\"{}\"",
             match self {
+                ErrorKind::Cancelled => "cancelled",
+                ErrorKind::DeadlineExceeded => "deadline exceeded",
                 ErrorKind::LoadShed => "loadshed",
+                ErrorKind::RateLimited => "rate limited",
                 ErrorKind::FailFast => "failfast",
                 ErrorKind::TlsDetectTimeout => "tls detection timeout",
                 ErrorKind::GatewayIdentityRequired => "gateway identity required",
                 ErrorKind::GatewayLoop => "gateway loop",
                 ErrorKind::GatewayDomainInvalid => "gateway domain invalid",
                 ErrorKind::Io => "i/o",
+                ErrorKind::StreamReset => "stream reset",
+                ErrorKind::UriTooLong => "uri too long",
                 ErrorKind::Unexpected => "unexpected",
-            }
+            },
+            self.code(),
+            self.source(),
         )
     }
 }
@@ -0,0 +1,229 @@
+use linkerd_app_core::metrics::{metrics, Counter, FmtLabels, FmtMetrics, Gauge};
+use linkerd_proxy_server_policy::ServerPolicy;
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt, sync::Arc};
+use tokio::time::{Duration, Instant};
+
+metrics! {
+    inbound_policy_updates_total: Counter {
+        "The total number of policy updates applied for each inbound port"
+    },
+    inbound_policy_update_age_seconds: Gauge {
+        "The number of seconds since the policy for each inbound port was last updated"
+    },
+    inbound_invalid_route_config_total: Counter {
+        "The total number of shadowed route rules found in policy updates for each inbound port"
+    }
+}
+
+/// Records the policy updates discovered from the control plane for each
+/// port, so that proxies enforcing stale policies can be identified.
+#[derive(Clone, Debug, Default)]
+pub struct PolicyUpdateMetrics(Arc<Mutex<HashMap<u16, PortUpdates>>>);
+
+/// Describes the policy enforced on a port.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortPolicy {
+    pub port: u16,
+    pub checksum: u64,
+    pub updates: u64,
+    pub age: Duration,
+}
+
+#[derive(Debug)]
+struct PortUpdates {
+    total: Counter,
+    last: Instant,
+    checksum: u64,
+    invalid: HashMap<&'static str, Counter>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct PortLabel(u16);
+
+#[derive(Copy, Clone, Debug)]
+struct ReasonLabel(&'static str);
+
+// === impl PolicyUpdateMetrics ===
+
+impl PolicyUpdateMetrics {
+    pub(crate) fn update(&self, port: u16, policy: &ServerPolicy) {
+        let now = Instant::now();
+        let checksum = policy.checksum();
+        let mut ports = self.0.lock();
+        let updates = ports.entry(port).or_insert_with(|| PortUpdates {
+            total: Counter::default(),
+            last: now,
+            checksum,
+            invalid: HashMap::new(),
+        });
+        updates.total.incr();
+        updates.last = now;
+        updates.checksum = checksum;
+    }
+
+    /// Records a problem with the route configuration of the port's most
+    /// recent update.
+    pub(crate) fn invalid_route_config(&self, port: u16, reason: &'static str) {
+        if let Some(updates) = self.0.lock().get_mut(&port) {
+            updates.invalid.entry(reason).or_default().incr();
+        }
+    }
+
+    /// Returns the policy enforced on each port, ordered by port.
+    pub fn snapshot(&self) -> Vec<PortPolicy> {
+        let now = Instant::now();
+        let mut ports = self
+            .0
+            .lock()
+            .iter()
+            .map(|(&port, updates)| PortPolicy {
+                port,
+                checksum: updates.checksum,
+                updates: updates.total.value() as u64,
+                age: now.saturating_duration_since(updates.last),
+            })
+            .collect::<Vec<_>>();
+        ports.sort_by_key(|p| p.port);
+        ports
+    }
+}
+
+impl FmtMetrics for PolicyUpdateMetrics {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ports = self.0.lock();
+        if ports.is_empty() {
+            return Ok(());
+        }
+
+        inbound_policy_updates_total.fmt_help(f)?;
+        inbound_policy_updates_total.fmt_scopes(
+            f,
+            ports.iter().map(|(p, u)| (PortLabel(*p), u)),
+            |u| &u.total,
+        )?;
+
+        let now = Instant::now();
+        inbound_policy_update_age_seconds.fmt_help(f)?;
+        for (port, updates) in ports.iter() {
+            let age = now.saturating_duration_since(updates.last);
+            inbound_policy_update_age_seconds.fmt_metric_labeled(
+                f,
+                &Gauge::from(age.as_secs()),
+                &PortLabel(*port),
+            )?;
+        }
+
+        if ports.values().any(|u| !u.invalid.is_empty()) {
+            inbound_invalid_route_config_total.fmt_help(f)?;
+            inbound_invalid_route_config_total.fmt_scopes(
+                f,
+                ports.iter().flat_map(|(p, u)| {
+                    u.invalid
+                        .iter()
+                        .map(move |(r, c)| ((PortLabel(*p), ReasonLabel(r)), c))
+                }),
+                |c| c,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// === impl PortLabel ===
+
+impl FmtLabels for PortLabel {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "port=\"{}\"", self.0)
+    }
+}
+
+// === impl ReasonLabel ===
+
+impl FmtLabels for ReasonLabel {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "reason=\"{}\"", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_updates_and_age() {
+        let metrics = PolicyUpdateMetrics::default();
+        assert_eq!(metrics.as_display().to_string(), "");
+
+        let policy = ServerPolicy::invalid(Duration::from_secs(10));
+        metrics.update(8080, &policy);
+        metrics.update(8080, &policy);
+        metrics.update(9090, &policy);
+        tokio::time::advance(Duration::from_secs(30)).await;
+        metrics.update(9090, &policy);
+
+        let text = metrics.as_display().to_string();
+        assert!(
+            text.contains("inbound_policy_updates_total{port=\"8080\"} 2"),
+            "{text}"
+        );
+        assert!(
+            text.contains("inbound_policy_update_age_seconds{port=\"8080\"} 30"),
+            "{text}"
+        );
+        assert!(
+            text.contains("inbound_policy_update_age_seconds{port=\"9090\"} 0"),
+            "{text}"
+        );
+    }
+    #[tokio::test(start_paused = true)]
+    async fn snapshots_checksums() {
+        let metrics = PolicyUpdateMetrics::default();
+        let invalid = ServerPolicy::invalid(Duration::from_secs(10));
+        let deny = ServerPolicy::from(crate::policy::DefaultPolicy::Deny);
+        metrics.update(9090, &invalid);
+        metrics.update(8080, &invalid);
+        tokio::time::advance(Duration::from_secs(5)).await;
+        metrics.update(8080, &deny);
+
+        assert_eq!(
+            metrics.snapshot(),
+            vec![
+                PortPolicy {
+                    port: 8080,
+                    checksum: deny.checksum(),
+                    updates: 2,
+                    age: Duration::ZERO,
+                },
+                PortPolicy {
+                    port: 9090,
+                    checksum: invalid.checksum(),
+                    updates: 1,
+                    age: Duration::from_secs(5),
+                },
+            ]
+        );
+        assert_ne!(deny.checksum(), invalid.checksum());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_invalid_route_config() {
+        let metrics = PolicyUpdateMetrics::default();
+        metrics.invalid_route_config(8080, "unreachable_rule");
+        assert_eq!(metrics.as_display().to_string(), "");
+
+        let policy = ServerPolicy::invalid(Duration::from_secs(10));
+        metrics.update(8080, &policy);
+        metrics.invalid_route_config(8080, "unreachable_rule");
+        metrics.invalid_route_config(8080, "unreachable_rule");
+
+        let text = metrics.as_display().to_string();
+        assert!(
+            text.contains(
+                "inbound_invalid_route_config_total{port=\"8080\",reason=\"unreachable_rule\"} 2"
+            ),
+            "{text}"
+        );
+    }
+}
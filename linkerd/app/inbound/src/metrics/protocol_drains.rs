@@ -0,0 +1,51 @@
+use linkerd_app_core::metrics::{metrics, Counter, FmtLabels, FmtMetrics};
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt, sync::Arc};
+
+metrics! {
+    inbound_http_protocol_change_drains_total: Counter {
+        "The total number of inbound HTTP connections drained because their port's protocol changed"
+    }
+}
+
+/// Counts the HTTP connections that are drained when their port's policy
+/// changes protocol, by target port.
+#[derive(Clone, Debug, Default)]
+pub struct ProtocolDrainMetrics(Arc<Mutex<HashMap<PortLabel, Counter>>>);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct PortLabel(u16);
+
+// === impl ProtocolDrainMetrics ===
+
+impl ProtocolDrainMetrics {
+    pub(crate) fn record(&self, target_port: u16) {
+        self.0
+            .lock()
+            .entry(PortLabel(target_port))
+            .or_default()
+            .incr();
+    }
+}
+
+impl FmtMetrics for ProtocolDrainMetrics {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let drains = self.0.lock();
+        if drains.is_empty() {
+            return Ok(());
+        }
+
+        inbound_http_protocol_change_drains_total.fmt_help(f)?;
+        inbound_http_protocol_change_drains_total.fmt_scopes(f, drains.iter(), |c| c)?;
+
+        Ok(())
+    }
+}
+
+// === impl PortLabel ===
+
+impl FmtLabels for PortLabel {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "target_port=\"{}\"", self.0)
+    }
+}
@@ -0,0 +1,59 @@
+use crate::detect::ProtocolHint;
+use linkerd_app_core::metrics::{metrics, Counter, FmtLabels, FmtMetrics};
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt, sync::Arc};
+
+metrics! {
+    inbound_opaque_protocol_hints_total: Counter {
+        "The total number of opaque inbound connections by the protocol that their initial bytes resemble"
+    }
+}
+
+/// Counts opaque connections by their target port and protocol hint.
+#[derive(Clone, Debug, Default)]
+pub struct ProtocolHintMetrics(Arc<Mutex<HashMap<Labels, Counter>>>);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct Labels {
+    target_port: u16,
+    hint: ProtocolHint,
+}
+
+// === impl ProtocolHintMetrics ===
+
+impl ProtocolHintMetrics {
+    pub(crate) fn record(&self, target_port: u16, hint: ProtocolHint) {
+        self.0
+            .lock()
+            .entry(Labels { target_port, hint })
+            .or_default()
+            .incr();
+    }
+}
+
+impl FmtMetrics for ProtocolHintMetrics {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hints = self.0.lock();
+        if hints.is_empty() {
+            return Ok(());
+        }
+
+        inbound_opaque_protocol_hints_total.fmt_help(f)?;
+        inbound_opaque_protocol_hints_total.fmt_scopes(f, hints.iter(), |c| c)?;
+
+        Ok(())
+    }
+}
+
+// === impl Labels ===
+
+impl FmtLabels for Labels {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "target_port=\"{}\",protocol_hint=\"{}\"",
+            self.target_port,
+            self.hint.as_str()
+        )
+    }
+}
@@ -0,0 +1,259 @@
+//! Space-bounded statistics describing the clients that send the most HTTP
+//! requests to each inbound server.
+//!
+//! Counts are estimated with the Space-Saving algorithm, which tracks a fixed
+//! number of clients per server. Clients that send few requests may be evicted
+//! by others, so each count is reported with an upper bound on its error.
+
+use linkerd_app_core::{
+    identity as id,
+    metrics::ServerLabel,
+    tls,
+    transport::{ClientAddr, Remote},
+};
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt, net::IpAddr, sync::Arc};
+
+/// The number of clients tracked for each server.
+const CAPACITY: usize = 32;
+
+#[derive(Clone, Debug, Default)]
+pub struct TopClients(Arc<Mutex<HashMap<ServerLabel, ServerClients>>>);
+
+/// Identifies a client by its mTLS identity, if one was established, or by
+/// its IP address otherwise.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Client {
+    Identity(id::Id),
+    Ip(IpAddr),
+}
+
+/// An estimate of the number of events for a client.
+///
+/// The true count is at least `count - error` and at most `count`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientCount {
+    pub client: Client,
+    pub count: u64,
+    pub error: u64,
+}
+
+/// The top clients for a single server, ordered by descending count.
+#[derive(Clone, Debug)]
+pub struct ServerTopClients {
+    pub server: ServerLabel,
+    pub requests: Vec<ClientCount>,
+    pub denials: Vec<ClientCount>,
+}
+
+#[derive(Debug, Default)]
+struct ServerClients {
+    requests: SpaceSaving,
+    denials: SpaceSaving,
+}
+
+/// Estimates the clients with the most events, tracking at most `capacity`
+/// clients.
+#[derive(Debug)]
+pub(crate) struct SpaceSaving {
+    capacity: usize,
+    counts: HashMap<Client, Estimate>,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Estimate {
+    count: u64,
+    error: u64,
+}
+
+// === impl TopClients ===
+
+impl TopClients {
+    /// Records a request from `client` to `server`.
+    pub(crate) fn request(&self, server: ServerLabel, client: Client) {
+        self.0
+            .lock()
+            .entry(server)
+            .or_default()
+            .requests
+            .incr(client);
+    }
+
+    /// Records a request from `client` to `server` that was denied.
+    pub(crate) fn deny(&self, server: ServerLabel, client: Client) {
+        let mut servers = self.0.lock();
+        let clients = servers.entry(server).or_default();
+        clients.requests.incr(client.clone());
+        clients.denials.incr(client);
+    }
+
+    /// Returns the top clients for each server.
+    pub fn snapshot(&self) -> Vec<ServerTopClients> {
+        self.0
+            .lock()
+            .iter()
+            .map(|(server, clients)| ServerTopClients {
+                server: server.clone(),
+                requests: clients.requests.top(),
+                denials: clients.denials.top(),
+            })
+            .collect()
+    }
+}
+
+// === impl Client ===
+
+impl Client {
+    pub(crate) fn new(client: Remote<ClientAddr>, tls: &tls::ConditionalServerTls) -> Self {
+        match tls {
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(tls::server::ClientId(id)),
+                ..
+            }) => Client::Identity(id.clone()),
+            _ => Client::Ip(client.ip()),
+        }
+    }
+}
+
+impl fmt::Display for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Client::Identity(id) => id.fmt(f),
+            Client::Ip(ip) => ip.fmt(f),
+        }
+    }
+}
+
+// === impl SpaceSaving ===
+
+impl Default for SpaceSaving {
+    fn default() -> Self {
+        Self::new(CAPACITY)
+    }
+}
+
+impl SpaceSaving {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            counts: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn incr(&mut self, client: Client) {
+        if let Some(est) = self.counts.get_mut(&client) {
+            est.count += 1;
+            return;
+        }
+
+        if self.counts.len() < self.capacity {
+            self.counts.insert(client, Estimate { count: 1, error: 0 });
+            return;
+        }
+
+        // Replace the client with the smallest count. The new client inherits
+        // that count, which bounds the error of its estimate.
+        let min = self
+            .counts
+            .iter()
+            .min_by_key(|(_, est)| est.count)
+            .map(|(c, est)| (c.clone(), est.count));
+        if let Some((evicted, min)) = min {
+            self.counts.remove(&evicted);
+            self.counts.insert(
+                client,
+                Estimate {
+                    count: min + 1,
+                    error: min,
+                },
+            );
+        }
+    }
+
+    pub(crate) fn top(&self) -> Vec<ClientCount> {
+        let mut top = self
+            .counts
+            .iter()
+            .map(|(client, est)| ClientCount {
+                client: client.clone(),
+                count: est.count,
+                error: est.error,
+            })
+            .collect::<Vec<_>>();
+        top.sort_by(|a, b| b.count.cmp(&a.count));
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(n: u8) -> Client {
+        Client::Ip([192, 0, 2, n].into())
+    }
+
+    #[test]
+    fn counts_exactly_under_capacity() {
+        let mut top = SpaceSaving::new(3);
+        for _ in 0..3 {
+            top.incr(ip(1));
+        }
+        top.incr(ip(2));
+
+        assert_eq!(
+            top.top(),
+            vec![
+                ClientCount {
+                    client: ip(1),
+                    count: 3,
+                    error: 0
+                },
+                ClientCount {
+                    client: ip(2),
+                    count: 1,
+                    error: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn evicts_smallest_count() {
+        let mut top = SpaceSaving::new(2);
+        for _ in 0..5 {
+            top.incr(ip(1));
+        }
+        top.incr(ip(2));
+        top.incr(ip(3));
+
+        assert_eq!(
+            top.top(),
+            vec![
+                ClientCount {
+                    client: ip(1),
+                    count: 5,
+                    error: 0
+                },
+                ClientCount {
+                    client: ip(3),
+                    count: 2,
+                    error: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn heavy_hitters_survive() {
+        let mut top = SpaceSaving::new(4);
+        for n in 0..100 {
+            top.incr(ip(1));
+            top.incr(ip(n));
+        }
+
+        let first = &top.top()[0];
+        assert_eq!(first.client, ip(1));
+        assert!(first.count - first.error >= 100);
+    }
+}
@@ -0,0 +1,123 @@
+use linkerd_app_core::{
+    metrics::{metrics, Counter, FmtLabels, FmtMetrics, Gauge, RouteLabels},
+    proxy::http::upgrade::{Direction, SessionMetrics},
+};
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt, sync::Arc};
+
+metrics! {
+    inbound_websocket_sessions_open: Gauge {
+        "The number of open inbound WebSocket sessions"
+    },
+    inbound_websocket_sessions_total: Counter {
+        "The total number of inbound WebSocket sessions"
+    },
+    inbound_websocket_messages_total: Counter {
+        "The total number of data messages sent on inbound WebSocket sessions"
+    },
+    inbound_websocket_bytes_total: Counter {
+        "The total number of bytes sent on inbound WebSocket sessions"
+    }
+}
+
+/// Records WebSocket sessions by route.
+#[derive(Clone, Debug, Default)]
+pub struct WebSocketMetrics(Arc<Mutex<HashMap<RouteLabels, Arc<RouteSessions>>>>);
+
+#[derive(Debug, Default)]
+pub(crate) struct RouteSessions {
+    open: Gauge,
+    total: Counter,
+    traffic: [Traffic; 2],
+}
+
+/// Records the data sent by one peer of a route's sessions.
+#[derive(Debug, Default)]
+struct Traffic {
+    messages: Counter,
+    bytes: Counter,
+}
+
+/// Labels the peer that sent WebSocket data.
+#[derive(Copy, Clone, Debug)]
+struct Sender(Direction);
+
+// === impl WebSocketMetrics ===
+
+impl WebSocketMetrics {
+    pub(crate) fn route(&self, labels: RouteLabels) -> Arc<RouteSessions> {
+        self.0.lock().entry(labels).or_default().clone()
+    }
+}
+
+impl FmtMetrics for WebSocketMetrics {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let routes = self.0.lock();
+        if routes.is_empty() {
+            return Ok(());
+        }
+        let scopes = || {
+            routes
+                .iter()
+                .map(|(labels, sessions)| (labels, &**sessions))
+        };
+        let traffic_scopes = || {
+            scopes().flat_map(|(labels, sessions)| {
+                [Direction::FromClient, Direction::FromServer]
+                    .into_iter()
+                    .map(move |d| ((labels, Sender(d)), &sessions.traffic[index(d)]))
+            })
+        };
+
+        inbound_websocket_sessions_open.fmt_help(f)?;
+        inbound_websocket_sessions_open.fmt_scopes(f, scopes(), |s| &s.open)?;
+
+        inbound_websocket_sessions_total.fmt_help(f)?;
+        inbound_websocket_sessions_total.fmt_scopes(f, scopes(), |s| &s.total)?;
+
+        inbound_websocket_messages_total.fmt_help(f)?;
+        inbound_websocket_messages_total.fmt_scopes(f, traffic_scopes(), |t| &t.messages)?;
+
+        inbound_websocket_bytes_total.fmt_help(f)?;
+        inbound_websocket_bytes_total.fmt_scopes(f, traffic_scopes(), |t| &t.bytes)?;
+
+        Ok(())
+    }
+}
+
+// === impl RouteSessions ===
+
+impl SessionMetrics for RouteSessions {
+    fn opened(&self) {
+        self.open.incr();
+        self.total.incr();
+    }
+
+    fn closed(&self) {
+        self.open.decr();
+    }
+
+    fn transferred(&self, direction: Direction, bytes: usize, messages: usize) {
+        let traffic = &self.traffic[index(direction)];
+        traffic.bytes.add(bytes as u64);
+        traffic.messages.add(messages as u64);
+    }
+}
+
+fn index(direction: Direction) -> usize {
+    match direction {
+        Direction::FromClient => 0,
+        Direction::FromServer => 1,
+    }
+}
+
+// === impl Sender ===
+
+impl FmtLabels for Sender {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Direction::FromClient => write!(f, "sender=\"client\""),
+            Direction::FromServer => write!(f, "sender=\"server\""),
+        }
+    }
+}
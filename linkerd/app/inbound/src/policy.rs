@@ -1,17 +1,24 @@
 mod api;
 mod config;
 pub mod defaults;
+mod hmac;
 mod http;
+mod opaque_ports;
 mod store;
 mod tcp;
 
 pub(crate) use self::store::Store;
 pub use self::{
     config::Config,
+    hmac::HmacSecrets,
     http::{
-        HttpInvalidPolicy, HttpRouteInvalidRedirect, HttpRouteNotFound, HttpRouteRedirect,
-        HttpRouteUnauthorized, NewHttpPolicy,
+        GrpcRouteInjectedFailure, GrpcStreamReauthorization, HttpInvalidPolicy,
+        HttpRouteConcurrencyExhausted, HttpRouteInjectedFailure, HttpRouteInvalidRedirect,
+        HttpRouteNotFound, HttpRoutePriorityShed, HttpRouteRateLimited, HttpRouteRedirect,
+        HttpRouteUnauthorized, NewHttpPolicy, NewHttpPolicyConfig, Quota, RateLimits,
+        ReauthorizeBody,
     },
+    opaque_ports::{InvalidPorts, OpaquePorts},
     tcp::NewTcpPolicy,
 };
 
@@ -24,12 +31,18 @@ use linkerd_app_core::{
 };
 use linkerd_idle_cache::Cached;
 pub use linkerd_proxy_server_policy::{
-    authz::Suffix,
+    authz::{
+        CertFingerprint, Hmac, IdentityPattern, LocalAddr, SignedComponent, Suffix, TimeWindow,
+    },
     grpc::Route as GrpcRoute,
-    http::{filter::Redirection, Route as HttpRoute},
-    route, Authentication, Authorization, Meta, Protocol, RoutePolicy, ServerPolicy,
+    http::{filter::Redirection, Route as HttpRoute, Transcoder},
+    overrides::{AuthzOverrides, IdentityScopes, Overrides, RouteOverrides, ServerOverrides},
+    route,
+    tls::Route as SniRoute,
+    Authentication, Authorization, DuplexConfig, Meta, Priority, Protocol, RateLimit, RoutePolicy,
+    ServerPolicy,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::SystemTime};
 use thiserror::Error;
 use tokio::sync::watch;
 
@@ -54,6 +67,7 @@ pub enum DefaultPolicy {
 pub struct AllowPolicy {
     dst: OrigDstAddr,
     server: Cached<watch::Receiver<ServerPolicy>>,
+    local_id: Option<id::Id>,
 }
 
 // Describes an authorized non-HTTP connection.
@@ -91,6 +105,8 @@ impl From<DefaultPolicy> for ServerPolicy {
             DefaultPolicy::Deny => ServerPolicy {
                 protocol: Protocol::Opaque(Arc::new([])),
                 meta: Meta::new_default("deny"),
+                bandwidth_limit: None,
+                duplex: Default::default(),
             },
         }
     }
@@ -103,7 +119,11 @@ impl AllowPolicy {
     pub fn for_test(dst: OrigDstAddr, server: ServerPolicy) -> (Self, watch::Sender<ServerPolicy>) {
         let (tx, server) = watch::channel(server);
         let server = Cached::uncached(server);
-        let p = Self { dst, server };
+        let p = Self {
+            dst,
+            server,
+            local_id: None,
+        };
         (p, tx)
     }
 
@@ -122,6 +142,13 @@ impl AllowPolicy {
         self.dst
     }
 
+    /// The proxy's own identity, against which identity patterns like
+    /// [`IdentityPattern::SameNamespace`] are matched.
+    #[inline]
+    pub fn local_id(&self) -> Option<&id::Id> {
+        self.local_id.as_ref()
+    }
+
     #[inline]
     pub fn meta(&self) -> Arc<Meta> {
         self.server.borrow().meta.clone()
@@ -132,7 +159,7 @@ impl AllowPolicy {
         ServerLabel(self.server.borrow().meta.clone())
     }
 
-    async fn changed(&mut self) {
+    pub(crate) async fn changed(&mut self) {
         if self.server.changed().await.is_err() {
             // If the sender was dropped, then there can be no further changes.
             futures::future::pending::<()>().await;
@@ -151,7 +178,11 @@ impl AllowPolicy {
     }
 }
 
-fn is_tls_authorized(tls: &tls::ConditionalServerTls, authz: &Authorization) -> bool {
+fn is_tls_authorized(
+    tls: &tls::ConditionalServerTls,
+    authz: &Authorization,
+    local_id: Option<&id::Id>,
+) -> bool {
     match authz.authentication {
         Authentication::Unauthenticated => true,
 
@@ -162,20 +193,38 @@ fn is_tls_authorized(tls: &tls::ConditionalServerTls, authz: &Authorization) ->
             )
         }
 
+        // Signatures authenticate individual requests, not connections.
+        Authentication::Hmac(_) => false,
+
+        Authentication::TlsFingerprints(ref fingerprints) => match tls {
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_fingerprint: Some(ref fingerprint),
+                ..
+            }) => fingerprints.contains(fingerprint),
+            _ => false,
+        },
+
         Authentication::TlsAuthenticated {
             ref identities,
             ref suffixes,
+            ref patterns,
         } => match tls {
             tls::ConditionalServerTls::Some(tls::ServerTls::Established {
                 client_id: Some(tls::server::ClientId(ref id)),
                 ..
-            }) => match id {
-                id::Id::Uri(_) => identities.contains(&*id.to_str()),
-                id::Id::Dns(_) => {
-                    identities.contains(&*id.to_str())
-                        || suffixes.iter().any(|s| s.contains(&id.to_str()))
+            }) => {
+                let name = id.to_str();
+                if identities.contains(&*name) {
+                    return true;
                 }
-            },
+                if matches!(id, id::Id::Dns(_)) && suffixes.iter().any(|s| s.contains(&name)) {
+                    return true;
+                }
+                let local_id = local_id.map(|id| id.to_str());
+                patterns
+                    .iter()
+                    .any(|p| p.matches(&name, local_id.as_deref()))
+            }
             _ => false,
         },
     }
@@ -183,6 +232,8 @@ fn is_tls_authorized(tls: &tls::ConditionalServerTls, authz: &Authorization) ->
 
 fn is_authorized(
     authz: &Authorization,
+    dst: OrigDstAddr,
+    local_id: Option<&id::Id>,
     client_addr: Remote<ClientAddr>,
     tls: &tls::ConditionalServerTls,
 ) -> bool {
@@ -190,7 +241,56 @@ fn is_authorized(
         return false;
     }
 
-    is_tls_authorized(tls, authz)
+    if !authz.matches_local_addr(dst.into()) {
+        return false;
+    }
+
+    if !authz.is_valid_at(SystemTime::now()) {
+        return false;
+    }
+
+    is_tls_authorized(tls, authz, local_id)
+}
+
+/// Like [`is_authorized`], but also authorizes requests that are signed as
+/// required by an HMAC authorization.
+fn is_http_authorized<B>(
+    authz: &Authorization,
+    dst: OrigDstAddr,
+    local_id: Option<&id::Id>,
+    client_addr: Remote<ClientAddr>,
+    tls: &tls::ConditionalServerTls,
+    secrets: &HmacSecrets,
+    req: &::http::Request<B>,
+) -> bool {
+    match authz.authentication {
+        Authentication::Hmac(ref hmac) => {
+            is_http_reauthorized(authz, dst, local_id, client_addr, tls)
+                && secrets.verify(hmac, req, SystemTime::now())
+        }
+        _ => is_authorized(authz, dst, local_id, client_addr, tls),
+    }
+}
+
+/// Checks whether a request that was already authorized remains authorized.
+///
+/// HMAC signatures are only valid shortly after a request is signed, so they
+/// are verified when the request is first authorized and not again.
+fn is_http_reauthorized(
+    authz: &Authorization,
+    dst: OrigDstAddr,
+    local_id: Option<&id::Id>,
+    client_addr: Remote<ClientAddr>,
+    tls: &tls::ConditionalServerTls,
+) -> bool {
+    match authz.authentication {
+        Authentication::Hmac(_) => {
+            authz.networks.iter().any(|n| n.contains(&client_addr.ip()))
+                && authz.matches_local_addr(dst.into())
+                && authz.is_valid_at(SystemTime::now())
+        }
+        _ => is_authorized(authz, dst, local_id, client_addr, tls),
+    }
 }
 
 // === impl Permit ===
@@ -213,8 +313,8 @@ mod tests {
     use super::is_tls_authorized;
     use super::Meta;
     use super::Suffix;
-    use super::{Authentication, Authorization};
-    use linkerd_app_core::tls;
+    use super::{Authentication, Authorization, IdentityPattern};
+    use linkerd_app_core::{identity as id, tls};
     use std::collections::BTreeSet;
     use std::str::FromStr;
     use std::sync::Arc;
@@ -228,7 +328,10 @@ mod tests {
             authentication: Authentication::TlsAuthenticated {
                 identities,
                 suffixes,
+                patterns: vec![],
             },
+            local_addrs: vec![],
+            validity: vec![],
         }
     }
 
@@ -236,6 +339,7 @@ mod tests {
         let client_id = tls::ClientId::from_str(identity).expect("should parse id");
         tls::ConditionalServerTls::Some(tls::ServerTls::Established {
             client_id: Some(client_id),
+            client_fingerprint: None,
             negotiated_protocol: None,
         })
     }
@@ -247,7 +351,7 @@ mod tests {
             BTreeSet::from(["spiffe://some-root/some-workload".into()]),
             vec![],
         );
-        assert!(is_tls_authorized(&tls, &authz))
+        assert!(is_tls_authorized(&tls, &authz, None))
     }
 
     #[test]
@@ -257,42 +361,42 @@ mod tests {
             BTreeSet::from(["spiffe://some-root/some-workload-2".into()]),
             vec![],
         );
-        assert!(!is_tls_authorized(&tls, &authz))
+        assert!(!is_tls_authorized(&tls, &authz, None))
     }
 
     #[test]
     fn is_authorized_for_matching_dns_ids() {
         let tls = server_tls("some.id.local");
         let authz = authorization(BTreeSet::from(["some.id.local".into()]), vec![]);
-        assert!(is_tls_authorized(&tls, &authz))
+        assert!(is_tls_authorized(&tls, &authz, None))
     }
 
     #[test]
     fn is_not_authorized_for_non_matching_dns_ids() {
         let tls = server_tls("some.id.local.one");
         let authz = authorization(BTreeSet::from(["some.id.local.two".into()]), vec![]);
-        assert!(!is_tls_authorized(&tls, &authz))
+        assert!(!is_tls_authorized(&tls, &authz, None))
     }
 
     #[test]
     fn is_authorized_for_matching_dns_suffixes_ids() {
         let tls = server_tls("some.id.local");
         let authz = authorization(BTreeSet::new(), vec![Suffix::new("id.local")]);
-        assert!(is_tls_authorized(&tls, &authz))
+        assert!(is_tls_authorized(&tls, &authz, None))
     }
 
     #[test]
     fn is_not_authorized_for_non_matching_suffixes_ids() {
         let tls = server_tls("some.id.local");
         let authz = authorization(BTreeSet::new(), vec![Suffix::new("another-id.local")]);
-        assert!(!is_tls_authorized(&tls, &authz))
+        assert!(!is_tls_authorized(&tls, &authz, None))
     }
 
     #[test]
     fn is_not_authorized_for_suffixes_and_spiffe_id() {
         let tls = server_tls("spiffe://some-root/some-workload-1");
         let authz = authorization(BTreeSet::new(), vec![Suffix::new("some-workload-1")]);
-        assert!(!is_tls_authorized(&tls, &authz))
+        assert!(!is_tls_authorized(&tls, &authz, None))
     }
 
     #[test]
@@ -306,7 +410,7 @@ mod tests {
             ]),
             vec![],
         );
-        assert!(is_tls_authorized(&tls, &authz))
+        assert!(is_tls_authorized(&tls, &authz, None))
     }
 
     #[test]
@@ -320,6 +424,108 @@ mod tests {
             ]),
             vec![],
         );
-        assert!(is_tls_authorized(&tls, &authz))
+        assert!(is_tls_authorized(&tls, &authz, None))
+    }
+
+    fn pattern_authorization(patterns: &[&str]) -> Authorization {
+        with_patterns(
+            patterns
+                .iter()
+                .map(|p| IdentityPattern::parse(p).expect("should parse pattern"))
+                .collect(),
+        )
+    }
+
+    fn with_patterns(patterns: Vec<IdentityPattern>) -> Authorization {
+        let mut authz = authorization(BTreeSet::new(), vec![]);
+        authz.authentication = Authentication::TlsAuthenticated {
+            identities: BTreeSet::new(),
+            suffixes: vec![],
+            patterns,
+        };
+        authz
+    }
+
+    #[test]
+    fn is_authorized_for_spiffe_prefix() {
+        let authz = pattern_authorization(&["spiffe://some-root/ns/prod/*"]);
+        assert!(is_tls_authorized(
+            &server_tls("spiffe://some-root/ns/prod/sa/web"),
+            &authz,
+            None
+        ));
+        assert!(!is_tls_authorized(
+            &server_tls("spiffe://some-root/ns/dev/sa/web"),
+            &authz,
+            None
+        ));
+        assert!(!is_tls_authorized(
+            &server_tls("spiffe://other-root/ns/prod/sa/web"),
+            &authz,
+            None
+        ));
+    }
+
+    #[test]
+    fn is_authorized_for_dns_wildcard() {
+        let authz = pattern_authorization(&["*.ns.serviceaccount.identity.linkerd.cluster.local"]);
+        assert!(is_tls_authorized(
+            &server_tls("web.ns.serviceaccount.identity.linkerd.cluster.local"),
+            &authz,
+            None
+        ));
+        // Wildcards match exactly one label.
+        assert!(!is_tls_authorized(
+            &server_tls("a.web.ns.serviceaccount.identity.linkerd.cluster.local"),
+            &authz,
+            None
+        ));
+        assert!(!is_tls_authorized(
+            &server_tls("web.other.serviceaccount.identity.linkerd.cluster.local"),
+            &authz,
+            None
+        ));
+    }
+    #[test]
+    fn is_authorized_for_pinned_fingerprints() {
+        let pinned = tls::CertFingerprint([1; 32]);
+        let mut authz = authorization(BTreeSet::new(), vec![]);
+        authz.authentication = Authentication::TlsFingerprints(Some(pinned).into_iter().collect());
+
+        let with_fingerprint = |fingerprint| {
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(
+                    "foo.ns1.serviceaccount.identity.linkerd.cluster.local"
+                        .parse()
+                        .unwrap(),
+                ),
+                client_fingerprint: Some(fingerprint),
+                negotiated_protocol: None,
+            })
+        };
+        assert!(is_tls_authorized(&with_fingerprint(pinned), &authz, None));
+        assert!(!is_tls_authorized(
+            &with_fingerprint(tls::CertFingerprint([2; 32])),
+            &authz,
+            None
+        ));
+        assert!(!is_tls_authorized(
+            &server_tls("foo.ns1.serviceaccount.identity.linkerd.cluster.local"),
+            &authz,
+            None
+        ));
+    }
+
+    #[test]
+    fn is_authorized_for_same_namespace() {
+        let authz = with_patterns(vec![IdentityPattern::SameNamespace]);
+        let local = id::Id::from_str("api.prod.serviceaccount.identity.linkerd.cluster.local")
+            .expect("should parse id");
+        let tls = server_tls("web.prod.serviceaccount.identity.linkerd.cluster.local");
+        assert!(is_tls_authorized(&tls, &authz, Some(&local)));
+        assert!(!is_tls_authorized(&tls, &authz, None));
+
+        let tls = server_tls("web.dev.serviceaccount.identity.linkerd.cluster.local");
+        assert!(!is_tls_authorized(&tls, &authz, Some(&local)));
     }
 }
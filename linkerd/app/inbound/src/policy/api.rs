@@ -1,3 +1,4 @@
+use crate::metrics::policy_updates::PolicyUpdateMetrics;
 use futures::prelude::*;
 use linkerd2_proxy_api::inbound::{
     self as api, inbound_server_policies_client::InboundServerPoliciesClient as Client,
@@ -8,24 +9,33 @@ use linkerd_app_core::{
     svc::Service,
     Error, Recover, Result,
 };
-use linkerd_proxy_server_policy::ServerPolicy;
+use linkerd_proxy_server_policy::{overrides::Overrides, ServerPolicy, ShadowedRule};
 use linkerd_tonic_stream::{LimitReceiveFuture, ReceiveLimits};
 use linkerd_tonic_watch::StreamWatch;
 use std::sync::Arc;
-use tokio::time;
+use tokio::{sync::watch, time};
+use tonic::codec::CompressionEncoding;
+use tracing::Instrument;
 
 #[derive(Clone, Debug)]
 pub(super) struct Api<S> {
     workload: Arc<str>,
     limits: ReceiveLimits,
     default_detect_timeout: time::Duration,
+    metrics: PolicyUpdateMetrics,
+    overrides: Arc<Overrides>,
     client: Client<S>,
 }
 
 #[derive(Clone)]
 pub(super) struct GrpcRecover(ExponentialBackoff);
 
-pub(super) type Watch<S> = StreamWatch<GrpcRecover, Api<S>>;
+/// Watches the policies of ports, publishing only policies that differ from
+/// a port's current policy.
+#[derive(Clone)]
+pub(super) struct Watch<S>(StreamWatch<GrpcRecover, Api<S>>);
+
+type Rx = watch::Receiver<ServerPolicy>;
 
 /// If an invalid policy is encountered, then this will be updated to hold a
 /// default, invalid policy.
@@ -41,18 +51,26 @@ where
         workload: Arc<str>,
         limits: ReceiveLimits,
         default_detect_timeout: time::Duration,
+        metrics: PolicyUpdateMetrics,
         client: S,
     ) -> Self {
         Self {
             workload,
             limits,
             default_detect_timeout,
-            client: Client::new(client),
+            metrics,
+            overrides: Default::default(),
+            client: Client::new(client).accept_compressed(CompressionEncoding::Gzip),
         }
     }
 
+    /// Applies locally-configured settings to each discovered policy.
+    pub(super) fn with_overrides(self, overrides: Arc<Overrides>) -> Self {
+        Self { overrides, ..self }
+    }
+
     pub(super) fn into_watch(self, backoff: ExponentialBackoff) -> Watch<S> {
-        StreamWatch::new(GrpcRecover(backoff), self)
+        Watch(StreamWatch::new(GrpcRecover(backoff), self))
     }
 }
 
@@ -84,6 +102,8 @@ where
 
         let detect_timeout = self.default_detect_timeout;
         let limits = self.limits;
+        let metrics = self.metrics.clone();
+        let overrides = self.overrides.clone();
         let mut client = self.client.clone();
         Box::pin(async move {
             let rsp = LimitReceiveFuture::new(limits, client.watch_port(tonic::Request::new(req)))
@@ -99,7 +119,27 @@ where
                             .get_or_init(|| ServerPolicy::invalid(detect_timeout))
                             .clone()
                     });
+                    let policy = overrides.apply(port, policy);
                     tracing::debug!(?policy);
+                    metrics.update(port, &policy);
+                    for ShadowedRule {
+                        lint,
+                        route,
+                        shadowed_by,
+                    } in policy.shadowed_rules()
+                    {
+                        tracing::warn!(
+                            reason = lint.as_str(),
+                            route.group = %route.group(),
+                            route.kind = %route.kind(),
+                            route.name = %route.name(),
+                            shadowed_by.group = %shadowed_by.group(),
+                            shadowed_by.kind = %shadowed_by.kind(),
+                            shadowed_by.name = %shadowed_by.name(),
+                            "Route rule is shadowed by an earlier rule",
+                        );
+                        metrics.invalid_route_config(port, lint.as_str());
+                    }
                     policy
                 })
                 .boxed()
@@ -108,6 +148,64 @@ where
     }
 }
 
+// === impl Watch ===
+
+impl<S> Watch<S>
+where
+    S: tonic::client::GrpcService<tonic::body::BoxBody, Error = Error>,
+    S: Clone + Send + Sync + 'static,
+    S::ResponseBody:
+        http::HttpBody<Data = tonic::codegen::Bytes, Error = Error> + Default + Send + 'static,
+    S::Future: Send + 'static,
+{
+    pub(super) fn spawn_with_init(self, port: u16, init: ServerPolicy) -> Rx {
+        skip_unchanged(self.0.spawn_with_init(port, init))
+    }
+}
+
+/// Publishes the policies from `rx`, skipping those that are identical to the
+/// current policy.
+///
+/// The policy controller may resend a policy on an established stream, and it
+/// sends the current policy whenever a stream is re-established. Stacks
+/// reauthorize connections whenever a port's policy changes, so these
+/// updates are skipped to avoid needless work on control plane resyncs.
+fn skip_unchanged(mut rx: Rx) -> Rx {
+    let (tx, policy_rx) = watch::channel(rx.borrow_and_update().clone());
+    tokio::spawn(
+        async move {
+            loop {
+                // Stop publishing when all receivers are dropped or when the
+                // discovery watch completes.
+                let changed = {
+                    let closed = tx.closed();
+                    let changed = rx.changed();
+                    futures::pin_mut!(closed, changed);
+                    match future::select(closed, changed).await {
+                        future::Either::Left(((), _)) => return,
+                        future::Either::Right((res, _)) => res,
+                    }
+                };
+                if changed.is_err() {
+                    return;
+                }
+
+                let policy = rx.borrow_and_update().clone();
+                tx.send_if_modified(|current| {
+                    if *current == policy {
+                        tracing::debug!("Policy unchanged");
+                        return false;
+                    }
+                    *current = policy;
+                    true
+                });
+            }
+        }
+        .in_current_span(),
+    );
+    policy_rx
+}
+
 // === impl GrpcRecover ===
 
 impl Recover<tonic::Status> for GrpcRecover {
@@ -134,3 +232,36 @@ impl Recover<tonic::Status> for GrpcRecover {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_proxy_server_policy::Meta;
+
+    fn policy(name: &'static str) -> ServerPolicy {
+        let mut policy = ServerPolicy::invalid(time::Duration::from_secs(10));
+        policy.meta = Meta::new_default(name);
+        policy
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn skips_unchanged_policies() {
+        let (tx, rx) = watch::channel(policy("a"));
+        let mut rx = skip_unchanged(rx);
+        assert_eq!(rx.borrow_and_update().meta.name(), "a");
+
+        // An identical policy, e.g. resent when a stream is re-established, is
+        // not published.
+        tx.send(policy("a")).unwrap();
+        tokio::task::yield_now().await;
+        assert!(!rx.has_changed().unwrap());
+
+        tx.send(policy("b")).unwrap();
+        rx.changed().await.unwrap();
+        assert_eq!(rx.borrow_and_update().meta.name(), "b");
+
+        // Receivers are notified when the discovery watch completes.
+        drop(tx);
+        assert!(rx.changed().await.is_err());
+    }
+}
@@ -1,7 +1,9 @@
-use super::{api::Api, DefaultPolicy, GetPolicy, Protocol, ServerPolicy, Store};
-use linkerd_app_core::{exp_backoff::ExponentialBackoff, proxy::http, Error};
+use super::{
+    api::Api, DefaultPolicy, GetPolicy, OpaquePorts, Overrides, Protocol, ServerPolicy, Store,
+};
+use crate::metrics::policy_updates::PolicyUpdateMetrics;
+use linkerd_app_core::{exp_backoff::ExponentialBackoff, identity as id, proxy::http, Error};
 use linkerd_tonic_stream::ReceiveLimits;
-use rangemap::RangeInclusiveSet;
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
@@ -17,24 +19,41 @@ use tokio::time::Duration;
 pub enum Config {
     Discover {
         default: DefaultPolicy,
+        /// Default policies for individual ports, used until their policies
+        /// are discovered.
+        port_defaults: HashMap<u16, DefaultPolicy>,
         cache_max_idle_age: Duration,
         ports: HashSet<u16>,
-        opaque_ports: RangeInclusiveSet<u16>,
+        opaque_ports: OpaquePorts,
+        /// Settings that are applied to discovered (and default) policies
+        /// because the control plane cannot yet express them.
+        overrides: Arc<Overrides>,
     },
     Fixed {
         default: DefaultPolicy,
+        port_defaults: HashMap<u16, DefaultPolicy>,
         cache_max_idle_age: Duration,
         ports: HashMap<u16, ServerPolicy>,
-        opaque_ports: RangeInclusiveSet<u16>,
+        opaque_ports: OpaquePorts,
     },
 }
 
 // === impl Config ===
 
 impl Config {
+    /// Returns the set of ports on which protocol detection is disabled by
+    /// default, which may be updated at runtime.
+    pub fn opaque_ports(&self) -> &OpaquePorts {
+        match self {
+            Self::Discover { opaque_ports, .. } | Self::Fixed { opaque_ports, .. } => opaque_ports,
+        }
+    }
+
     pub(crate) fn build<C>(
         self,
         workload: Arc<str>,
+        local_id: id::Id,
+        metrics: PolicyUpdateMetrics,
         client: C,
         backoff: ExponentialBackoff,
         limits: ReceiveLimits,
@@ -46,19 +65,28 @@ impl Config {
         C::ResponseBody: Default + Send + 'static,
         C::Future: Send,
     {
-        match self {
+        let store = match self {
             Self::Fixed {
                 default,
+                port_defaults,
                 ports,
                 cache_max_idle_age,
                 opaque_ports,
-            } => Store::spawn_fixed(default, cache_max_idle_age, ports, opaque_ports),
+            } => Store::spawn_fixed(
+                default,
+                port_defaults,
+                cache_max_idle_age,
+                ports,
+                opaque_ports,
+            ),
 
             Self::Discover {
                 default,
+                port_defaults,
                 ports,
                 cache_max_idle_age,
                 opaque_ports,
+                overrides,
             } => {
                 let watch = {
                     let detect_timeout = match default {
@@ -68,10 +96,21 @@ impl Config {
                         }) => timeout,
                         _ => Duration::from_secs(10),
                     };
-                    Api::new(workload, limits, detect_timeout, client).into_watch(backoff)
+                    Api::new(workload, limits, detect_timeout, metrics, client)
+                        .with_overrides(overrides.clone())
+                        .into_watch(backoff)
                 };
-                Store::spawn_discover(default, cache_max_idle_age, watch, ports, opaque_ports)
+                Store::spawn_discover(
+                    default,
+                    port_defaults,
+                    cache_max_idle_age,
+                    watch,
+                    ports,
+                    opaque_ports,
+                    &overrides,
+                )
             }
-        }
+        };
+        store.with_local_id(local_id)
     }
 }
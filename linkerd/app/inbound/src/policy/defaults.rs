@@ -53,6 +53,7 @@ fn authenticated() -> Authentication {
     Authentication::TlsAuthenticated {
         identities: Default::default(),
         suffixes: vec![Suffix::from(vec![])],
+        patterns: vec![],
     }
 }
 
@@ -66,6 +67,8 @@ fn mk(
         meta: Meta::new_default(name),
         networks: nets.into_iter().map(Into::into).collect(),
         authentication,
+        local_addrs: vec![],
+        validity: vec![],
     }]);
 
     // The default policy supports protocol detection and uses the default
@@ -79,5 +82,7 @@ fn mk(
     ServerPolicy {
         meta: Meta::new_default(name),
         protocol,
+        bandwidth_limit: None,
+        duplex: Default::default(),
     }
 }
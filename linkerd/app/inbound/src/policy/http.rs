@@ -1,4 +1,5 @@
-use super::{RoutePolicy, Routes};
+use self::reauthorize::Reauthorize;
+use super::{HmacSecrets, Priority, RoutePolicy, Routes};
 use crate::{
     metrics::authz::HttpAuthzMetrics,
     policy::{AllowPolicy, HttpRoutePermit},
@@ -7,16 +8,35 @@ use futures::{future, TryFutureExt};
 use linkerd_app_core::{
     metrics::{RouteAuthzLabels, RouteLabels},
     svc::{self, ServiceExt},
-    tls,
+    tls, trace_context,
     transport::{ClientAddr, OrigDstAddr, Remote},
     Error, Result,
 };
-use linkerd_proxy_server_policy::{grpc, http, route::RouteMatch};
-use std::{sync::Arc, task};
+use linkerd_http_access_log::PathParams;
+use linkerd_proxy_server_policy::{grpc, http, route::RouteMatch, Meta};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task,
+};
 
+mod rate_limit;
+mod reauthorize;
 #[cfg(test)]
 mod tests;
 
+pub use self::{
+    rate_limit::{Quota, RateLimits},
+    reauthorize::{GrpcStreamReauthorization, ReauthorizeBody},
+};
+
 /// A middleware that enforces policy on each HTTP request.
 ///
 /// This enforcement is done lazily on each request so that policy updates are
@@ -27,15 +47,40 @@ mod tests;
 #[derive(Clone, Debug)]
 pub struct NewHttpPolicy<N> {
     metrics: HttpAuthzMetrics,
+    in_flight: InFlight,
+    config: NewHttpPolicyConfig,
     inner: N,
 }
 
+/// Configures the enforcement of HTTP policies beyond route authorizations.
+#[derive(Clone, Debug, Default)]
+pub struct NewHttpPolicyConfig {
+    /// When set, requests on lower-priority routes are shed as a connection
+    /// approaches this many in-flight requests, so that the remaining
+    /// capacity is reserved for higher-priority routes.
+    pub max_in_flight_requests: Option<usize>,
+
+    /// The secrets with which HMAC-authenticated requests are signed.
+    pub hmac_secrets: HmacSecrets,
+
+    /// When set, gRPC response streams are re-authorized as they are served
+    /// and fail once their client is no longer authorized.
+    pub grpc_stream_reauthorization: Option<GrpcStreamReauthorization>,
+
+    /// Enforces the rate limits of the server's routes.
+    pub rate_limits: RateLimits,
+}
+
 #[derive(Clone, Debug)]
 pub struct HttpPolicyService<T, N> {
     target: T,
     connection: ConnectionMeta,
     policy: AllowPolicy,
     metrics: HttpAuthzMetrics,
+    in_flight: InFlight,
+    hmac_secrets: HmacSecrets,
+    grpc_stream_reauthorization: Option<GrpcStreamReauthorization>,
+    rate_limits: RateLimits,
     inner: N,
 }
 
@@ -46,9 +91,12 @@ struct ConnectionMeta {
     tls: tls::ConditionalServerTls,
 }
 
+/// Indicates that no route on the server matched the request.
 #[derive(Debug, thiserror::Error)]
-#[error("no route found for request")]
-pub struct HttpRouteNotFound(());
+#[error("no route found for request on {} {}", .server.kind(), .server.name())]
+pub struct HttpRouteNotFound {
+    server: Arc<Meta>,
+}
 
 #[derive(Debug, thiserror::Error)]
 #[error("invalid redirect: {0}")]
@@ -61,9 +109,78 @@ pub struct HttpRouteRedirect {
     pub location: ::http::Uri,
 }
 
+/// Indicates that a request was answered by a filter. [`HttpPolicyService`]
+/// replaces it with the filter's response.
+#[derive(Debug, thiserror::Error)]
+#[error("HTTP request answered directly with {}", .0.status)]
+struct HttpRouteDirectResponse(http::filter::DirectResponse);
+
+/// Indicates that the request's route does not authorize its client.
+#[derive(Debug, thiserror::Error)]
+#[error("unauthorized request on {} {}", .route.kind(), .route.name())]
+pub struct HttpRouteUnauthorized {
+    route: Arc<Meta>,
+}
+
+/// Counts the requests in flight on each route with a concurrency limit. The
+/// counts are shared by all connections to a server.
+#[derive(Clone, Debug, Default)]
+struct InFlight {
+    routes: Arc<Mutex<HashMap<RouteLabels, Arc<AtomicUsize>>>>,
+    connection: Option<ConnectionInFlight>,
+}
+
+/// Counts the requests in flight on a connection whose requests are shed by
+/// route priority.
+#[derive(Clone, Debug)]
+struct ConnectionInFlight {
+    requests: Arc<AtomicUsize>,
+    max: usize,
+}
+
+/// Holds one of a route's in-flight slots until it is dropped.
+#[derive(Debug)]
+struct InFlightGuard(Arc<AtomicUsize>);
+
+/// Holds a route's in-flight slot until the inner response future completes.
+///
+/// The path parameters captured by the request's route, if any, are set on
+/// the response so that they may be access logged. gRPC response streams may
+/// be re-authorized as they are served.
+#[pin_project]
+#[derive(Debug)]
+pub struct InFlightFuture<F> {
+    #[pin]
+    inner: F,
+    connection: Option<InFlightGuard>,
+    route: Option<InFlightGuard>,
+    path_params: Option<PathParams>,
+    reauthorize: Option<Box<Reauthorize>>,
+}
+
+/// Indicates that a route already has as many requests in flight as it
+/// permits.
+#[derive(Debug, thiserror::Error)]
+#[error("too many requests in flight on {} {}", .route.kind(), .route.name())]
+pub struct HttpRouteConcurrencyExhausted {
+    route: Arc<Meta>,
+}
+
+/// Indicates that a route's requests exceed its rate limit.
 #[derive(Debug, thiserror::Error)]
-#[error("unauthorized request on route")]
-pub struct HttpRouteUnauthorized(());
+#[error("rate limit exceeded on {} {}", .route.kind(), .route.name())]
+pub struct HttpRouteRateLimited {
+    route: Arc<Meta>,
+}
+
+/// Indicates that a request was shed to preserve its connection's capacity for
+/// requests on higher-priority routes.
+#[derive(Debug, thiserror::Error)]
+#[error("{priority:?} priority request shed on {} {}", .route.kind(), .route.name())]
+pub struct HttpRoutePriorityShed {
+    route: Arc<Meta>,
+    priority: Priority,
+}
 
 #[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
 #[error("HTTP request configured to fail with {status}: {message}")]
@@ -87,8 +204,18 @@ pub struct HttpInvalidPolicy(&'static str);
 
 impl<N> NewHttpPolicy<N> {
     pub fn layer(metrics: HttpAuthzMetrics) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        Self::layer_with_config(metrics, NewHttpPolicyConfig::default())
+    }
+
+    pub fn layer_with_config(
+        metrics: HttpAuthzMetrics,
+        config: NewHttpPolicyConfig,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let in_flight = InFlight::default();
         svc::layer::mk(move |inner| Self {
             metrics: metrics.clone(),
+            in_flight: in_flight.clone(),
+            config: config.clone(),
             inner,
         })
     }
@@ -113,11 +240,40 @@ where
             policy,
             connection: ConnectionMeta { client, dst, tls },
             metrics: self.metrics.clone(),
+            in_flight: self
+                .in_flight
+                .for_connection(self.config.max_in_flight_requests),
+            hmac_secrets: self.config.hmac_secrets.clone(),
+            grpc_stream_reauthorization: self.config.grpc_stream_reauthorization,
+            rate_limits: self.config.rate_limits.clone(),
             inner: self.inner.clone(),
         }
     }
 }
 
+// === impl ConnectionMeta ===
+
+impl ConnectionMeta {
+    fn inject_metadata(
+        &self,
+        filter: &http::filter::InjectMetadata,
+        route: &Meta,
+        headers: &mut ::http::HeaderMap,
+    ) {
+        let tls = self.tls.value();
+        let client_identity = tls.and_then(|tls| tls.client_id()).map(|id| id.to_str());
+        filter.apply(
+            headers,
+            &http::filter::Metadata {
+                client_addr: *self.client,
+                client_identity: client_identity.as_deref(),
+                tls: matches!(tls, Some(tls::ServerTls::Established { .. })),
+                route: route.name(),
+            },
+        );
+    }
+}
+
 // === impl HttpPolicyService ===
 
 macro_rules! err {
@@ -135,17 +291,18 @@ macro_rules! try_fut {
     };
 }
 
-impl<B, T, N, S> svc::Service<::http::Request<B>> for HttpPolicyService<T, N>
+impl<B, RspB, T, N, S> svc::Service<::http::Request<B>> for HttpPolicyService<T, N>
 where
     T: Clone,
     N: svc::NewService<(HttpRoutePermit, T), Service = S>,
-    S: svc::Service<::http::Request<B>>,
+    S: svc::Service<::http::Request<B>, Response = ::http::Response<RspB>>,
     S::Error: Into<Error>,
+    RspB: From<bytes::Bytes>,
 {
-    type Response = S::Response;
+    type Response = ::http::Response<ReauthorizeBody<RspB>>;
     type Error = Error;
     type Future = future::Either<
-        future::ErrInto<svc::stack::Oneshot<S, ::http::Request<B>>, Error>,
+        InFlightFuture<future::ErrInto<svc::stack::Oneshot<S, ::http::Request<B>>, Error>>,
         future::Ready<Result<Self::Response>>,
     >;
 
@@ -157,26 +314,79 @@ where
     fn call(&mut self, mut req: ::http::Request<B>) -> Self::Future {
         // Find an appropriate route for the request and ensure that it's
         // authorized.
-        let permit = match self.policy.routes() {
-            None => err!(self.mk_route_not_found()),
+        let (permit, priority, max_in_flight, path_params, reauthorize) = match self.policy.routes()
+        {
+            None => err!(self.mk_route_not_found(trace_context::sampled_trace_id(&req))),
             Some(Routes::Http(routes)) => {
                 let (permit, mtch, route) = try_fut!(self.authorize(&routes, &req));
-                try_fut!(apply_http_filters(mtch, route, &mut req));
-                permit
+                try_fut!(self.check_rate_limit(&permit, route));
+                let captures = mtch.route().captures();
+                let path_params = (!captures.is_empty()).then(|| PathParams(captures.to_string()));
+                if let Err(e) = apply_http_filters(mtch, route, &self.connection, &mut req) {
+                    // Direct responses are returned without calling the inner
+                    // service.
+                    let res = match e.downcast::<HttpRouteDirectResponse>() {
+                        Ok(direct) => Ok(direct
+                            .0
+                            .response()
+                            .map(|body| ReauthorizeBody::new(body, None))),
+                        Err(e) => Err(e),
+                    };
+                    return future::Either::Right(future::ready(res));
+                }
+                (
+                    permit,
+                    route.priority,
+                    route.max_in_flight,
+                    path_params,
+                    None,
+                )
             }
             Some(Routes::Grpc(routes)) => {
                 let (permit, _, route) = try_fut!(self.authorize(&routes, &req));
-                try_fut!(apply_grpc_filters(route, &mut req));
-                permit
+                try_fut!(self.check_rate_limit(&permit, route));
+                let reauthorize = self.grpc_stream_reauthorization.map(|config| {
+                    Box::new(Reauthorize::new(
+                        config,
+                        &req,
+                        permit.labels.route.clone(),
+                        self.policy.clone(),
+                        self.connection.clone(),
+                        self.metrics.clone(),
+                    ))
+                });
+                try_fut!(apply_grpc_filters(route, &self.connection, &mut req));
+                (
+                    permit,
+                    route.priority,
+                    route.max_in_flight,
+                    None,
+                    reauthorize,
+                )
             }
         };
 
-        future::Either::Left(
-            self.inner
+        let connection = try_fut!(self
+            .in_flight
+            .acquire_connection(&permit.labels.route, priority));
+        let route = match max_in_flight {
+            Some(max) => Some(try_fut!(self
+                .in_flight
+                .acquire_route(&permit.labels.route, max))),
+            None => None,
+        };
+
+        future::Either::Left(InFlightFuture {
+            inner: self
+                .inner
                 .new_service((permit, self.target.clone()))
                 .oneshot(req)
                 .err_into::<Error>(),
-        )
+            connection,
+            route,
+            path_params,
+            reauthorize,
+        })
     }
 }
 
@@ -189,19 +399,29 @@ impl<T, N> HttpPolicyService<T, N> {
         routes: &'m [super::route::Route<M, RoutePolicy<P>>],
         req: &::http::Request<B>,
     ) -> Result<(HttpRoutePermit, RouteMatch<M::Summary>, &'m RoutePolicy<P>)> {
+        // The request's trace, if any, is recorded as an exemplar of the
+        // authorization metrics.
+        let trace_id = || trace_context::sampled_trace_id(req);
+
         let (r#match, route) =
-            super::route::find(routes, req).ok_or_else(|| self.mk_route_not_found())?;
+            super::route::find(routes, req).ok_or_else(|| self.mk_route_not_found(trace_id()))?;
 
         let labels = RouteLabels {
             route: route.meta.clone(),
             server: self.policy.server_label(),
         };
 
-        let authz = match route
-            .authorizations
-            .iter()
-            .find(|a| super::is_authorized(a, self.connection.client, &self.connection.tls))
-        {
+        let authz = match route.authorizations.iter().find(|a| {
+            super::is_http_authorized(
+                a,
+                self.connection.dst,
+                self.policy.local_id(),
+                self.connection.client,
+                &self.connection.tls,
+                &self.hmac_secrets,
+                req,
+            )
+        }) {
             Some(authz) => authz,
             None => {
                 tracing::info!(
@@ -228,9 +448,15 @@ impl<T, N> HttpPolicyService<T, N> {
                         );
                     }
                 }
-                self.metrics
-                    .deny(labels, self.connection.dst, self.connection.tls.clone());
-                return Err(HttpRouteUnauthorized(()).into());
+                let route = labels.route.clone();
+                self.metrics.deny(
+                    labels,
+                    self.connection.dst,
+                    self.connection.client,
+                    self.connection.tls.clone(),
+                    trace_id(),
+                );
+                return Err(HttpRouteUnauthorized { route }.into());
             }
         };
 
@@ -259,21 +485,160 @@ impl<T, N> HttpPolicyService<T, N> {
             }
         };
 
-        self.metrics.allow(&permit, self.connection.tls.clone());
+        self.metrics.allow(
+            &permit,
+            self.connection.client,
+            self.connection.tls.clone(),
+            trace_id(),
+        );
         Ok((permit, r#match, route))
     }
 
-    fn mk_route_not_found(&self) -> Error {
+    /// Admits an authorized request if its route's rate limit permits it.
+    fn check_rate_limit<P>(&self, permit: &HttpRoutePermit, route: &RoutePolicy<P>) -> Result<()> {
+        match route.rate_limit {
+            Some(limit) => self.rate_limits.acquire(&permit.labels.route, limit),
+            None => Ok(()),
+        }
+    }
+
+    fn mk_route_not_found(&self, trace_id: Option<trace_context::Id>) -> Error {
         let labels = self.policy.server_label();
-        self.metrics
-            .route_not_found(labels, self.connection.dst, self.connection.tls.clone());
-        HttpRouteNotFound(()).into()
+        let server = labels.0.clone();
+        self.metrics.route_not_found(
+            labels,
+            self.connection.dst,
+            self.connection.client,
+            self.connection.tls.clone(),
+            trace_id,
+        );
+        HttpRouteNotFound { server }.into()
+    }
+}
+
+// === impl InFlight ===
+
+impl InFlight {
+    /// The percentage of a connection's request capacity that low-priority
+    /// requests may use.
+    const LOW_PRIORITY_SHARE: usize = 50;
+
+    /// The percentage of a connection's request capacity that normal-priority
+    /// requests may use.
+    const NORMAL_PRIORITY_SHARE: usize = 90;
+
+    fn for_connection(&self, max_in_flight_requests: Option<usize>) -> Self {
+        Self {
+            routes: self.routes.clone(),
+            connection: max_in_flight_requests.map(|max| ConnectionInFlight {
+                requests: Default::default(),
+                max,
+            }),
+        }
+    }
+
+    /// Takes one of the connection's in-flight slots, failing if the
+    /// connection's requests already use the share of its capacity permitted
+    /// for the route's priority.
+    fn acquire_connection(
+        &self,
+        route: &RouteLabels,
+        priority: Priority,
+    ) -> Result<Option<InFlightGuard>> {
+        let Some(ConnectionInFlight { requests, max }) = &self.connection else {
+            return Ok(None);
+        };
+        let limit = match priority {
+            Priority::Low => max.saturating_mul(Self::LOW_PRIORITY_SHARE) / 100,
+            Priority::Normal => max.saturating_mul(Self::NORMAL_PRIORITY_SHARE) / 100,
+            Priority::High => usize::MAX,
+        };
+        match InFlightGuard::try_acquire(requests, limit) {
+            Some(guard) => Ok(Some(guard)),
+            None => {
+                tracing::debug!(
+                    route.group = %route.route.group(),
+                    route.kind = %route.route.kind(),
+                    route.name = %route.route.name(),
+                    ?priority,
+                    limit,
+                    "Shedding request to preserve capacity for higher-priority routes",
+                );
+                Err(HttpRoutePriorityShed {
+                    route: route.route.clone(),
+                    priority,
+                }
+                .into())
+            }
+        }
+    }
+
+    /// Takes one of the route's in-flight slots, failing if all `max` slots
+    /// are taken.
+    fn acquire_route(&self, route: &RouteLabels, max: usize) -> Result<InFlightGuard> {
+        let count = self.routes.lock().entry(route.clone()).or_default().clone();
+        let Some(guard) = InFlightGuard::try_acquire(&count, max) else {
+            tracing::debug!(
+                route.group = %route.route.group(),
+                route.kind = %route.route.kind(),
+                route.name = %route.route.name(),
+                max,
+                "Route concurrency exhausted",
+            );
+            return Err(HttpRouteConcurrencyExhausted {
+                route: route.route.clone(),
+            }
+            .into());
+        };
+        Ok(guard)
+    }
+}
+
+// === impl InFlightGuard ===
+
+impl InFlightGuard {
+    fn try_acquire(count: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .ok()?;
+        Some(Self(count.clone()))
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Release);
+    }
+}
+
+// === impl InFlightFuture ===
+
+impl<F, B> Future for InFlightFuture<F>
+where
+    F: Future<Output = Result<::http::Response<B>>>,
+{
+    type Output = Result<::http::Response<ReauthorizeBody<B>>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+        let this = self.project();
+        let mut out = task::ready!(this.inner.poll(cx));
+        // Release the slots as soon as the response is available.
+        drop(this.route.take());
+        drop(this.connection.take());
+        if let (Ok(rsp), Some(params)) = (out.as_mut(), this.path_params.take()) {
+            rsp.extensions_mut().insert(params);
+        }
+        let reauthorize = this.reauthorize.take();
+        task::Poll::Ready(out.map(|rsp| rsp.map(|body| ReauthorizeBody::new(body, reauthorize))))
     }
 }
 
 fn apply_http_filters<B>(
     r#match: http::RouteMatch,
     route: &http::Policy,
+    connection: &ConnectionMeta,
     req: &mut ::http::Request<B>,
 ) -> Result<()> {
     // TODO Do any metrics apply here?
@@ -300,7 +665,27 @@ fn apply_http_filters<B>(
             },
 
             http::Filter::RequestHeaders(rh) => {
-                rh.apply(req.headers_mut());
+                rh.apply_with_captures(req.headers_mut(), r#match.route().captures());
+            }
+
+            http::Filter::InjectMetadata(im) => {
+                connection.inject_metadata(im, &route.meta, req.headers_mut());
+            }
+
+            http::Filter::Extension(ext) => {
+                if let Some(direct) = ext.apply_request(req.headers_mut())? {
+                    return Err(HttpRouteDirectResponse(direct).into());
+                }
+            }
+
+            http::Filter::Transcode(transcoder) => {
+                // Request bodies are transcoded by the inner stack.
+                req.extensions_mut().insert(transcoder.clone());
+            }
+
+            http::Filter::WebSocket(policy) => {
+                // Upgraded connections are limited by the inner stack.
+                req.extensions_mut().insert(policy.clone());
             }
 
             http::Filter::InternalError(msg) => {
@@ -312,7 +697,11 @@ fn apply_http_filters<B>(
     Ok(())
 }
 
-fn apply_grpc_filters<B>(route: &grpc::Policy, req: &mut ::http::Request<B>) -> Result<()> {
+fn apply_grpc_filters<B>(
+    route: &grpc::Policy,
+    connection: &ConnectionMeta,
+    req: &mut ::http::Request<B>,
+) -> Result<()> {
     for filter in &route.filters {
         match filter {
             grpc::Filter::InjectFailure(fail) => {
@@ -325,6 +714,10 @@ fn apply_grpc_filters<B>(route: &grpc::Policy, req: &mut ::http::Request<B>) ->
                 rh.apply(req.headers_mut());
             }
 
+            grpc::Filter::InjectMetadata(im) => {
+                connection.inject_metadata(im, &route.meta, req.headers_mut());
+            }
+
             grpc::Filter::InternalError(msg) => {
                 return Err(HttpInvalidPolicy(msg).into());
             }
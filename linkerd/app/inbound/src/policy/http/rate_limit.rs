@@ -0,0 +1,361 @@
+//! Enforces per-route request rate limits.
+//!
+//! Each route's limit is enforced with a token bucket. When a quota service is
+//! configured, tokens are instead leased from it in batches, so that a route's
+//! limit is shared by all of its server's replicas rather than applied by each
+//! proxy. While the quota service is unreachable, each proxy falls back to
+//! enforcing the limit with its own bucket.
+
+use super::HttpRouteRateLimited;
+use linkerd_app_core::{control, metrics::RouteLabels, Result};
+use linkerd_proxy_server_policy::RateLimit;
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::time::{self, Instant};
+
+/// Tracks the rate at which requests are admitted on each rate-limited route.
+/// Limits are shared by all connections to a server.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimits {
+    routes: Arc<Mutex<HashMap<RouteLabels, Arc<Limiter>>>>,
+    quota: Option<Quota>,
+}
+
+/// A client for the quota service from which rate limit tokens are leased.
+#[derive(Clone)]
+pub struct Quota {
+    client: control::Client,
+    timeout: Duration,
+}
+
+#[derive(Debug)]
+struct Limiter {
+    limit: RateLimit,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    /// Enforces the limit while the quota service is unavailable.
+    local: Bucket,
+
+    /// The number of tokens leased from the quota service that have not yet
+    /// been spent.
+    leased: u32,
+
+    /// Whether the quota service answered the most recent lease request.
+    available: bool,
+
+    /// Whether a lease request is in flight.
+    leasing: bool,
+
+    /// A new lease is not requested before this time.
+    retry_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct AcquireRequest {
+    #[prost(string, tag = "1")]
+    server_group: String,
+    #[prost(string, tag = "2")]
+    server_kind: String,
+    #[prost(string, tag = "3")]
+    server_name: String,
+    #[prost(string, tag = "4")]
+    route_group: String,
+    #[prost(string, tag = "5")]
+    route_kind: String,
+    #[prost(string, tag = "6")]
+    route_name: String,
+    /// The number of tokens requested.
+    #[prost(uint32, tag = "7")]
+    hits: u32,
+    #[prost(uint32, tag = "8")]
+    requests_per_second: u32,
+    #[prost(uint32, tag = "9")]
+    burst: u32,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct AcquireResponse {
+    /// The number of tokens granted, which may be fewer than were requested.
+    #[prost(uint32, tag = "1")]
+    granted: u32,
+}
+
+// === impl RateLimits ===
+
+impl RateLimits {
+    /// Returns limits whose tokens are leased from the given quota service.
+    pub fn with_quota(quota: Quota) -> Self {
+        Self {
+            routes: Default::default(),
+            quota: Some(quota),
+        }
+    }
+
+    /// Takes one of the route's tokens, failing if the route's limit has been
+    /// reached.
+    pub(super) fn acquire(&self, route: &RouteLabels, limit: RateLimit) -> Result<()> {
+        let limiter = {
+            let mut routes = self.routes.lock();
+            let limiter = routes
+                .entry(route.clone())
+                .or_insert_with(|| Arc::new(Limiter::new(limit)));
+            // Reset the route's limiter when its policy changes.
+            if limiter.limit != limit {
+                *limiter = Arc::new(Limiter::new(limit));
+            }
+            limiter.clone()
+        };
+
+        if limiter.try_acquire(route, self.quota.as_ref()) {
+            return Ok(());
+        }
+
+        tracing::debug!(
+            route.group = %route.route.group(),
+            route.kind = %route.route.kind(),
+            route.name = %route.route.name(),
+            requests_per_second = limit.requests_per_second.get(),
+            burst = limit.burst.get(),
+            "Route rate limit exceeded",
+        );
+        Err(HttpRouteRateLimited {
+            route: route.route.clone(),
+        }
+        .into())
+    }
+}
+
+// === impl Quota ===
+
+impl Quota {
+    const ACQUIRE_PATH: &'static str = "/io.linkerd.proxy.quota.Quota/Acquire";
+
+    /// How long a new lease is not requested after the quota service grants
+    /// no tokens.
+    const EXHAUSTED_RETRY: Duration = Duration::from_millis(100);
+
+    /// How long the limit is enforced locally after a lease request fails.
+    const UNAVAILABLE_RETRY: Duration = Duration::from_secs(1);
+
+    /// Builds a quota client whose lease requests fail after `timeout`.
+    pub fn new(client: control::Client, timeout: Duration) -> Self {
+        Self { client, timeout }
+    }
+
+    /// Leases up to a burst of tokens for the route, updating the limiter
+    /// once the quota service responds.
+    async fn lease(self, route: RouteLabels, limiter: Arc<Limiter>) {
+        let RateLimit {
+            requests_per_second,
+            burst,
+        } = limiter.limit;
+        let req = AcquireRequest {
+            server_group: route.server.0.group().to_string(),
+            server_kind: route.server.0.kind().to_string(),
+            server_name: route.server.0.name().to_string(),
+            route_group: route.route.group().to_string(),
+            route_kind: route.route.kind().to_string(),
+            route_name: route.route.name().to_string(),
+            hits: burst.get(),
+            requests_per_second: requests_per_second.get(),
+            burst: burst.get(),
+        };
+        let res = match time::timeout(self.timeout, self.acquire(req)).await {
+            Ok(res) => res,
+            Err(_) => Err("quota service request timed out".into()),
+        };
+
+        let now = Instant::now();
+        let mut state = limiter.state.lock();
+        state.leasing = false;
+        match res {
+            Ok(granted) => {
+                tracing::trace!(granted, "Leased rate limit tokens");
+                if !std::mem::replace(&mut state.available, true) {
+                    tracing::debug!("Quota service available");
+                }
+                state.leased = state.leased.saturating_add(granted);
+                state.retry_at = (granted == 0).then(|| now + Self::EXHAUSTED_RETRY);
+            }
+            Err(error) => {
+                if std::mem::replace(&mut state.available, false) {
+                    tracing::warn!(%error, "Quota service unavailable; enforcing rate limit locally");
+                } else {
+                    tracing::debug!(%error, "Quota service unavailable");
+                }
+                state.retry_at = Some(now + Self::UNAVAILABLE_RETRY);
+            }
+        }
+    }
+
+    async fn acquire(&self, req: AcquireRequest) -> Result<u32> {
+        let mut client = tonic::client::Grpc::new(self.client.clone());
+        client.ready().await?;
+        let rsp = client
+            .unary(
+                tonic::Request::new(req),
+                ::http::uri::PathAndQuery::from_static(Self::ACQUIRE_PATH),
+                tonic::codec::ProstCodec::<AcquireRequest, AcquireResponse>::default(),
+            )
+            .await?;
+        Ok(rsp.into_inner().granted)
+    }
+}
+
+impl std::fmt::Debug for Quota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Quota")
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+// === impl Limiter ===
+
+impl Limiter {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(State {
+                local: Bucket::full(limit),
+                leased: 0,
+                available: false,
+                leasing: false,
+                retry_at: None,
+            }),
+        }
+    }
+
+    fn try_acquire(self: &Arc<Self>, route: &RouteLabels, quota: Option<&Quota>) -> bool {
+        let now = Instant::now();
+        let mut state = self.state.lock();
+        let Some(quota) = quota else {
+            return state.local.take(self.limit, now);
+        };
+
+        let acquired = if state.leased > 0 {
+            state.leased -= 1;
+            true
+        } else if state.available {
+            // The route's shared limit has been reached.
+            false
+        } else {
+            state.local.take(self.limit, now)
+        };
+
+        // Lease more tokens once half of the lease has been spent.
+        let low = state.leased <= self.limit.burst.get() / 2;
+        let ready = state.retry_at.map_or(true, |at| at <= now);
+        if low && ready && !state.leasing {
+            state.leasing = true;
+            tokio::spawn(quota.clone().lease(route.clone(), self.clone()));
+        }
+
+        acquired
+    }
+}
+
+// === impl Bucket ===
+
+impl Bucket {
+    fn full(limit: RateLimit) -> Self {
+        Self {
+            tokens: limit.burst.get().into(),
+            updated: Instant::now(),
+        }
+    }
+
+    /// Refills the bucket for the time elapsed since it was last updated and
+    /// takes a token, if one is available.
+    fn take(&mut self, limit: RateLimit, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * f64::from(limit.requests_per_second.get()))
+            .min(limit.burst.get().into());
+        self.updated = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::{metrics::ServerLabel, svc, Error};
+    use linkerd_proxy_server_policy::Meta;
+    use std::num::NonZeroU32;
+
+    fn route() -> RouteLabels {
+        RouteLabels {
+            server: ServerLabel(Arc::new(Meta::Resource {
+                group: "policy.linkerd.io".into(),
+                kind: "server".into(),
+                name: "testsrv".into(),
+            })),
+            route: Arc::new(Meta::Resource {
+                group: "gateway.networking.k8s.io".into(),
+                kind: "HTTPRoute".into(),
+                name: "testrt".into(),
+            }),
+        }
+    }
+
+    fn limit(requests_per_second: u32, burst: u32) -> RateLimit {
+        RateLimit {
+            requests_per_second: NonZeroU32::new(requests_per_second).unwrap(),
+            burst: NonZeroU32::new(burst).unwrap(),
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn local_bucket() {
+        let limits = RateLimits::default();
+        let route = route();
+        let limit = limit(10, 2);
+
+        assert!(limits.acquire(&route, limit).is_ok());
+        assert!(limits.acquire(&route, limit).is_ok());
+        let err = limits
+            .acquire(&route, limit)
+            .expect_err("burst must be exhausted");
+        assert!(err.is::<HttpRouteRateLimited>(), "{err}");
+
+        // A token is added every 100ms.
+        time::advance(Duration::from_millis(100)).await;
+        assert!(limits.acquire(&route, limit).is_ok());
+        assert!(limits.acquire(&route, limit).is_err());
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn falls_back_when_quota_unavailable() {
+        // The quota service fails all requests.
+        let client = svc::BoxCloneSyncService::new(svc::mk(
+            |_: ::http::Request<tonic::body::BoxBody>| async move {
+                Err::<::http::Response<control::RspBody>, Error>("unreachable".into())
+            },
+        ));
+        let limits = RateLimits::with_quota(Quota::new(client, Duration::from_secs(1)));
+        let route = route();
+        let limit = limit(10, 2);
+
+        assert!(limits.acquire(&route, limit).is_ok());
+        // Let the lease request fail.
+        tokio::task::yield_now().await;
+        assert!(limits.acquire(&route, limit).is_ok());
+        let err = limits
+            .acquire(&route, limit)
+            .expect_err("burst must be exhausted");
+        assert!(err.is::<HttpRouteRateLimited>(), "{err}");
+    }
+}
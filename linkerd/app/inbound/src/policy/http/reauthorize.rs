@@ -0,0 +1,230 @@
+//! Re-authorizes gRPC response streams while they are served.
+//!
+//! Requests are authorized when they are received, but a server-streaming
+//! gRPC response may be served long after its client's authorization has been
+//! revoked. When enabled, a stream is authorized again whenever the server's
+//! policy changes (and, optionally, periodically) and it fails with
+//! `PERMISSION_DENIED` once its client is no longer authorized.
+
+use super::{ConnectionMeta, HttpRouteUnauthorized};
+use crate::{
+    metrics::authz::HttpAuthzMetrics,
+    policy::{route, AllowPolicy, Authorization, Routes},
+};
+use bytes::Bytes;
+use futures::FutureExt;
+use linkerd_app_core::{
+    metrics::RouteLabels,
+    proxy::http::{HeaderValue, HttpBody},
+    trace_context, Error,
+};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time;
+
+/// Configures the re-authorization of gRPC response streams.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GrpcStreamReauthorization {
+    /// When set, streams are also re-authorized at this interval, so that
+    /// authorizations that expire are enforced without a policy change.
+    pub interval: Option<Duration>,
+}
+
+/// A response body that fails once its request is no longer authorized.
+#[pin_project]
+#[derive(Debug)]
+pub struct ReauthorizeBody<B> {
+    #[pin]
+    inner: B,
+    reauthorize: Option<Box<Reauthorize>>,
+}
+
+pub(super) struct Reauthorize {
+    req: ::http::Request<()>,
+    labels: RouteLabels,
+    policy: AllowPolicy,
+    changed: Pin<Box<dyn Future<Output = AllowPolicy> + Send + 'static>>,
+    interval: Option<time::Interval>,
+    connection: ConnectionMeta,
+    metrics: HttpAuthzMetrics,
+}
+
+// === impl ReauthorizeBody ===
+
+impl<B> ReauthorizeBody<B> {
+    pub(super) fn new(inner: B, reauthorize: Option<Box<Reauthorize>>) -> Self {
+        Self { inner, reauthorize }
+    }
+}
+
+impl<B: Default> Default for ReauthorizeBody<B> {
+    fn default() -> Self {
+        Self::new(B::default(), None)
+    }
+}
+
+impl<B: From<Bytes>> From<Bytes> for ReauthorizeBody<B> {
+    fn from(bytes: Bytes) -> Self {
+        Self::new(B::from(bytes), None)
+    }
+}
+
+impl<B> HttpBody for ReauthorizeBody<B>
+where
+    B: HttpBody,
+    B::Error: Into<Error>,
+{
+    type Data = B::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        if let Some(reauthorize) = this.reauthorize.as_mut() {
+            if let Poll::Ready(error) = reauthorize.poll_revoked(cx) {
+                *this.reauthorize = None;
+                return Poll::Ready(Some(Err(error)));
+            }
+        }
+        this.inner.poll_data(cx).map_err(Into::into)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<::http::HeaderMap<HeaderValue>>, Self::Error>> {
+        self.project().inner.poll_trailers(cx).map_err(Into::into)
+    }
+}
+
+// === impl Reauthorize ===
+
+impl Reauthorize {
+    pub(super) fn new<B>(
+        config: GrpcStreamReauthorization,
+        req: &::http::Request<B>,
+        labels: RouteLabels,
+        policy: AllowPolicy,
+        connection: ConnectionMeta,
+        metrics: HttpAuthzMetrics,
+    ) -> Self {
+        // Only the parts of the request that routes match are retained.
+        let mut head = ::http::Request::new(());
+        *head.method_mut() = req.method().clone();
+        *head.uri_mut() = req.uri().clone();
+        *head.version_mut() = req.version();
+        *head.headers_mut() = req.headers().clone();
+
+        let interval = config.interval.map(|period| {
+            let mut interval = time::interval_at(time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+            interval
+        });
+
+        Self {
+            req: head,
+            labels,
+            changed: Box::pin(Self::changed(policy.clone())),
+            policy,
+            interval,
+            connection,
+            metrics,
+        }
+    }
+
+    async fn changed(mut policy: AllowPolicy) -> AllowPolicy {
+        policy.changed().await;
+        policy
+    }
+
+    /// Polls for the stream's authorization to be revoked, returning the
+    /// error with which the stream fails.
+    fn poll_revoked(&mut self, cx: &mut Context<'_>) -> Poll<Error> {
+        loop {
+            let mut recheck = false;
+            if let Poll::Ready(policy) = self.changed.poll_unpin(cx) {
+                self.changed = Box::pin(Self::changed(policy.clone()));
+                self.policy = policy;
+                recheck = true;
+            }
+            if let Some(interval) = self.interval.as_mut() {
+                while interval.poll_tick(cx).is_ready() {
+                    recheck = true;
+                }
+            }
+            if !recheck {
+                return Poll::Pending;
+            }
+
+            if !self.is_authorized() {
+                return Poll::Ready(self.revoke());
+            }
+            tracing::trace!("Stream remains authorized");
+        }
+    }
+
+    /// Checks whether the request is authorized by the current policy. The
+    /// request need not match the route on which it was first authorized.
+    fn is_authorized(&self) -> bool {
+        let ConnectionMeta { dst, client, tls } = &self.connection;
+        let authorized = |authzs: &[Authorization]| {
+            authzs.iter().any(|a| {
+                crate::policy::is_http_reauthorized(a, *dst, self.policy.local_id(), *client, tls)
+            })
+        };
+        match self.policy.routes() {
+            Some(Routes::Grpc(routes)) => route::find(&routes, &self.req)
+                .map_or(false, |(_, route)| authorized(&route.authorizations)),
+            Some(Routes::Http(routes)) => route::find(&routes, &self.req)
+                .map_or(false, |(_, route)| authorized(&route.authorizations)),
+            None => false,
+        }
+    }
+
+    fn revoke(&self) -> Error {
+        let labels = self.labels.clone();
+        tracing::info!(
+            server.group = %labels.server.0.group(),
+            server.kind = %labels.server.0.kind(),
+            server.name = %labels.server.0.name(),
+            route.group = %labels.route.group(),
+            route.kind = %labels.route.kind(),
+            route.name = %labels.route.name(),
+            client.tls = ?self.connection.tls,
+            client.ip = %self.connection.client.ip(),
+            "Stream no longer authorized",
+        );
+        let route = labels.route.clone();
+        self.metrics.deny(
+            labels,
+            self.connection.dst,
+            self.connection.client,
+            self.connection.tls.clone(),
+            trace_context::sampled_trace_id(&self.req),
+        );
+        HttpRouteUnauthorized { route }.into()
+    }
+}
+
+impl std::fmt::Debug for Reauthorize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reauthorize")
+            .field("req", &self.req)
+            .field("labels", &self.labels)
+            .field("interval", &self.interval.as_ref().map(|i| i.period()))
+            .finish_non_exhaustive()
+    }
+}
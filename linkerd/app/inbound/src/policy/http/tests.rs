@@ -1,5 +1,7 @@
 use super::*;
-use crate::policy::{Authentication, Authorization, Meta, Protocol, ServerPolicy};
+use crate::policy::{
+    Authentication, Authorization, Meta, Priority, Protocol, RateLimit, ServerPolicy,
+};
 use linkerd_app_core::{svc::Service, Infallible};
 use std::sync::Arc;
 
@@ -10,6 +12,7 @@ macro_rules! conn {
             client: Remote(ClientAddr(($client, 30120).into())),
             tls: tls::ConditionalServerTls::Some(tls::ServerTls::Established {
                 client_id: Some("foo.bar.bah".parse().unwrap()),
+                client_fingerprint: None,
                 negotiated_protocol: None,
             }),
         }
@@ -30,6 +33,8 @@ macro_rules! new_svc {
                     kind: "Server".into(),
                     name: "testsrv".into(),
                 }),
+                bandwidth_limit: None,
+                duplex: Default::default(),
             },
         );
         let svc = HttpPolicyService {
@@ -37,6 +42,10 @@ macro_rules! new_svc {
             policy,
             connection: $conn,
             metrics: HttpAuthzMetrics::default(),
+            in_flight: InFlight::default(),
+            hmac_secrets: HmacSecrets::default(),
+            grpc_stream_reauthorization: None,
+            rate_limits: RateLimits::default(),
             inner: |(permit, _): (HttpRoutePermit, ())| {
                 let f = $rsp;
                 svc::mk(move |req: ::http::Request<hyper::Body>| {
@@ -88,9 +97,14 @@ async fn http_route() {
                             kind: "AuthorizationPolicy".into(),
                             name: "test".into(),
                         }),
+                        local_addrs: vec![],
+                        validity: vec![],
                     }]),
                     filters: vec![],
                     meta: rmeta.clone(),
+                    max_in_flight: None,
+                    rate_limit: None,
+                    priority: Priority::Normal,
                 },
             },
             Rule {
@@ -102,6 +116,9 @@ async fn http_route() {
                     authorizations: Arc::new([]),
                     filters: vec![],
                     meta: rmeta.clone(),
+                    max_in_flight: None,
+                    rate_limit: None,
+                    priority: Priority::Normal,
                 },
             }
         ],
@@ -172,9 +189,14 @@ async fn http_route() {
                                 kind: "AuthorizationPolicy".into(),
                                 name: "other".into(),
                             }),
+                            local_addrs: vec![],
+                            validity: vec![],
                         }]),
                         filters: vec![],
                         meta: rmeta.clone(),
+                        max_in_flight: None,
+                        rate_limit: None,
+                        priority: Priority::Normal,
                     },
                 },
                 Rule {
@@ -191,13 +213,20 @@ async fn http_route() {
                                 kind: "AuthorizationPolicy".into(),
                                 name: "test".into(),
                             }),
+                            local_addrs: vec![],
+                            validity: vec![],
                         }]),
                         filters: vec![],
                         meta: rmeta.clone(),
+                        max_in_flight: None,
+                        rate_limit: None,
+                        priority: Priority::Normal,
                     },
                 },
             ],
         }])),
+        bandwidth_limit: None,
+        duplex: Default::default(),
     })
     .expect("must send");
 
@@ -265,12 +294,17 @@ async fn http_filter_header() {
                         kind: "AuthorizatoinPolicy".into(),
                         name: "test".into(),
                     }),
+                    local_addrs: vec![],
+                    validity: vec![],
                 }]),
                 filters: vec![Filter::RequestHeaders(filter::ModifyHeader {
                     add: vec![("testkey".parse().unwrap(), "testval".parse().unwrap())],
                     ..filter::ModifyHeader::default()
                 })],
                 meta: rmeta.clone(),
+                max_in_flight: None,
+                rate_limit: None,
+                priority: Priority::Normal,
             },
         }],
     }]));
@@ -303,6 +337,156 @@ async fn http_filter_header() {
     assert_eq!(permit.labels.route.route, rmeta);
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn http_filter_inject_metadata() {
+    use linkerd_proxy_server_policy::http::{
+        filter, r#match::MatchRequest, Filter, Policy, Route, Rule,
+    };
+
+    let rmeta = Arc::new(Meta::Resource {
+        group: "gateway.networking.k8s.io".into(),
+        kind: "httproute".into(),
+        name: "testrt".into(),
+    });
+    let proto = Protocol::Http1(Arc::new([Route {
+        hosts: vec![],
+        rules: vec![Rule {
+            matches: vec![MatchRequest::default()],
+            policy: Policy {
+                authorizations: Arc::new([Authorization {
+                    authentication: Authentication::Unauthenticated,
+                    networks: vec![std::net::IpAddr::from([192, 168, 3, 3]).into()],
+                    meta: Arc::new(Meta::Resource {
+                        group: "policy.linkerd.io".into(),
+                        kind: "AuthorizatoinPolicy".into(),
+                        name: "test".into(),
+                    }),
+                    local_addrs: vec![],
+                    validity: vec![],
+                }]),
+                filters: vec![Filter::InjectMetadata(filter::InjectMetadata {
+                    client_addr: Some("x-client-ip".parse().unwrap()),
+                    client_identity: Some("x-client-id".parse().unwrap()),
+                    tls: Some("x-client-tls".parse().unwrap()),
+                    route: Some("x-route".parse().unwrap()),
+                })],
+                meta: rmeta.clone(),
+                max_in_flight: None,
+                rate_limit: None,
+                priority: Priority::Normal,
+            },
+        }],
+    }]));
+    let inner = |_: HttpRoutePermit, req: ::http::Request<hyper::Body>| -> Result<_> {
+        assert_eq!(req.headers()["x-client-ip"], "192.168.3.3");
+        assert_eq!(req.headers()["x-client-id"], "foo.bar.bah");
+        assert_eq!(req.headers()["x-client-tls"], "true");
+        assert_eq!(req.headers()["x-route"], "testrt");
+        Ok(::http::Response::builder()
+            .body(hyper::Body::default())
+            .unwrap())
+    };
+    let (mut svc, _tx) = new_svc!(proto, conn!(), inner);
+
+    svc.call(
+        ::http::Request::builder()
+            .header("x-client-id", "spoofed")
+            .body(hyper::Body::default())
+            .unwrap(),
+    )
+    .await
+    .expect("serves");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn http_filter_extension() {
+    use linkerd_proxy_server_policy::http::{
+        filter, r#match::MatchRequest, Filter, Policy, Route, Rule,
+    };
+
+    /// Answers requests without an `x-allow` header directly.
+    #[derive(Debug)]
+    struct Gate;
+
+    impl filter::ExtensionFilter for Gate {
+        fn name(&self) -> &str {
+            "gate"
+        }
+
+        fn apply_request(
+            &self,
+            headers: &mut ::http::HeaderMap,
+        ) -> Result<Option<filter::DirectResponse>> {
+            if headers.contains_key("x-allow") {
+                headers.insert("x-gated", "true".parse().unwrap());
+                return Ok(None);
+            }
+            Ok(Some(filter::DirectResponse {
+                status: ::http::StatusCode::FORBIDDEN,
+                headers: vec![],
+                body: "denied".into(),
+            }))
+        }
+    }
+
+    let proto = Protocol::Http1(Arc::new([Route {
+        hosts: vec![],
+        rules: vec![Rule {
+            matches: vec![MatchRequest::default()],
+            policy: Policy {
+                authorizations: Arc::new([Authorization {
+                    authentication: Authentication::Unauthenticated,
+                    networks: vec![std::net::IpAddr::from([192, 168, 3, 3]).into()],
+                    meta: Arc::new(Meta::Resource {
+                        group: "policy.linkerd.io".into(),
+                        kind: "AuthorizatoinPolicy".into(),
+                        name: "test".into(),
+                    }),
+                    local_addrs: vec![],
+                    validity: vec![],
+                }]),
+                filters: vec![Filter::Extension(filter::Extension::new(Gate))],
+                meta: Arc::new(Meta::Resource {
+                    group: "gateway.networking.k8s.io".into(),
+                    kind: "httproute".into(),
+                    name: "testrt".into(),
+                }),
+                max_in_flight: None,
+                rate_limit: None,
+                priority: Priority::Normal,
+            },
+        }],
+    }]));
+    let inner = |_: HttpRoutePermit, req: ::http::Request<hyper::Body>| -> Result<_> {
+        assert_eq!(req.headers()["x-gated"], "true");
+        Ok(::http::Response::builder()
+            .body(hyper::Body::default())
+            .unwrap())
+    };
+    let (mut svc, _tx) = new_svc!(proto, conn!(), inner);
+
+    let rsp = svc
+        .call(
+            ::http::Request::builder()
+                .header("x-allow", "1")
+                .body(hyper::Body::default())
+                .unwrap(),
+        )
+        .await
+        .expect("serves");
+    assert_eq!(rsp.status(), ::http::StatusCode::OK);
+
+    let rsp = svc
+        .call(
+            ::http::Request::builder()
+                .body(hyper::Body::default())
+                .unwrap(),
+        )
+        .await
+        .expect("responds directly");
+    assert_eq!(rsp.status(), ::http::StatusCode::FORBIDDEN);
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn http_filter_inject_failure() {
     use linkerd_proxy_server_policy::http::{
@@ -330,6 +514,8 @@ async fn http_filter_inject_failure() {
                         kind: "AuthorizatoinPolicy".into(),
                         name: "test".into(),
                     }),
+                    local_addrs: vec![],
+                    validity: vec![],
                 }]),
                 filters: vec![Filter::InjectFailure(filter::InjectFailure {
                     distribution: filter::Distribution::from_ratio(1, 1).unwrap(),
@@ -339,6 +525,9 @@ async fn http_filter_inject_failure() {
                     },
                 })],
                 meta: rmeta.clone(),
+                max_in_flight: None,
+                rate_limit: None,
+                priority: Priority::Normal,
             },
         }],
     }]));
@@ -396,9 +585,14 @@ async fn grpc_route() {
                             kind: "AuthorizationPolicy".into(),
                             name: "test".into(),
                         }),
+                        local_addrs: vec![],
+                        validity: vec![],
                     }]),
                     filters: vec![],
                     meta: rmeta.clone(),
+                    max_in_flight: None,
+                    rate_limit: None,
+                    priority: Priority::Normal,
                 },
             },
             Rule {
@@ -413,6 +607,9 @@ async fn grpc_route() {
                     authorizations: Arc::new([]),
                     filters: vec![],
                     meta: rmeta.clone(),
+                    max_in_flight: None,
+                    rate_limit: None,
+                    priority: Priority::Normal,
                 },
             }
         ],
@@ -459,6 +656,83 @@ async fn grpc_route() {
         .is::<HttpRouteNotFound>());
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn grpc_stream_reauthorization() {
+    use futures::FutureExt;
+    use linkerd_app_core::proxy::http::HttpBody;
+    use linkerd_proxy_server_policy::grpc::{r#match::MatchRoute, Policy, Route, Rule};
+
+    let rmeta = Arc::new(Meta::Resource {
+        group: "gateway.networking.k8s.io".into(),
+        kind: "grpcproute".into(),
+        name: "testrt".into(),
+    });
+    let proto = |authorizations: Arc<[Authorization]>| {
+        Protocol::Grpc(Arc::new([Route {
+            hosts: vec![],
+            rules: vec![Rule {
+                matches: vec![MatchRoute::default()],
+                policy: Policy {
+                    authorizations,
+                    filters: vec![],
+                    meta: rmeta.clone(),
+                    max_in_flight: None,
+                    rate_limit: None,
+                    priority: Priority::Normal,
+                },
+            }],
+        }]))
+    };
+    let authorized = proto(Arc::new([Authorization {
+        authentication: Authentication::Unauthenticated,
+        networks: vec![std::net::IpAddr::from([192, 168, 3, 3]).into()],
+        meta: Arc::new(Meta::Resource {
+            group: "policy.linkerd.io".into(),
+            kind: "AuthorizationPolicy".into(),
+            name: "test".into(),
+        }),
+        local_addrs: vec![],
+        validity: vec![],
+    }]));
+
+    // Streams are served until the response body's sender is dropped.
+    let senders = Arc::new(Mutex::new(Vec::new()));
+    let (mut svc, tx) = new_svc!(authorized.clone(), conn!(), {
+        let senders = senders.clone();
+        move |_: HttpRoutePermit, _: ::http::Request<hyper::Body>| {
+            let (tx, body) = hyper::Body::channel();
+            senders.lock().push(tx);
+            Ok::<_, Infallible>(::http::Response::new(body))
+        }
+    });
+    svc.grpc_stream_reauthorization = Some(GrpcStreamReauthorization::default());
+
+    let mut rsp = svc
+        .call(
+            ::http::Request::builder()
+                .uri("/foo.bar.bah/baz")
+                .method(::http::Method::POST)
+                .body(hyper::Body::default())
+                .unwrap(),
+        )
+        .await
+        .expect("serves");
+    let body = rsp.body_mut();
+    assert!(body.data().now_or_never().is_none());
+
+    // Policy changes that continue to authorize the stream do not end it.
+    tx.send_modify(|p| p.protocol = authorized.clone());
+    assert!(body.data().now_or_never().is_none());
+
+    tx.send_modify(|p| p.protocol = proto(Arc::new([])));
+    let error = body
+        .data()
+        .await
+        .expect("stream must end")
+        .expect_err("stream must fail");
+    assert!(error.is::<HttpRouteUnauthorized>());
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn grpc_filter_header() {
     use linkerd_proxy_server_policy::{
@@ -494,12 +768,17 @@ async fn grpc_filter_header() {
                         kind: "AuthorizatoinPolicy".into(),
                         name: "test".into(),
                     }),
+                    local_addrs: vec![],
+                    validity: vec![],
                 }]),
                 filters: vec![Filter::RequestHeaders(http::filter::ModifyHeader {
                     add: vec![("testkey".parse().unwrap(), "testval".parse().unwrap())],
                     ..http::filter::ModifyHeader::default()
                 })],
                 meta: rmeta.clone(),
+                max_in_flight: None,
+                rate_limit: None,
+                priority: Priority::Normal,
             },
         }],
     }]));
@@ -567,6 +846,8 @@ async fn grpc_filter_inject_failure() {
                         kind: "AuthorizatoinPolicy".into(),
                         name: "test".into(),
                     }),
+                    local_addrs: vec![],
+                    validity: vec![],
                 }]),
                 filters: vec![Filter::InjectFailure(filter::InjectFailure {
                     distribution: filter::Distribution::from_ratio(1, 1).unwrap(),
@@ -576,6 +857,9 @@ async fn grpc_filter_inject_failure() {
                     },
                 })],
                 meta: rmeta.clone(),
+                max_in_flight: None,
+                rate_limit: None,
+                priority: Priority::Normal,
             },
         }],
     }]));
@@ -602,3 +886,191 @@ async fn grpc_filter_inject_failure() {
         }
     );
 }
+
+#[tokio::test(flavor = "current_thread")]
+async fn http_route_max_in_flight() {
+    use linkerd_proxy_server_policy::http::{r#match::MatchRequest, Policy, Route, Rule};
+
+    let rmeta = Arc::new(Meta::Resource {
+        group: "gateway.networking.k8s.io".into(),
+        kind: "httproute".into(),
+        name: "testrt".into(),
+    });
+    let (mut svc, _tx) = new_svc!(Protocol::Http1(Arc::new([Route {
+        hosts: vec![],
+        rules: vec![Rule {
+            matches: vec![MatchRequest::default()],
+            policy: Policy {
+                authorizations: Arc::new([Authorization {
+                    authentication: Authentication::Unauthenticated,
+                    networks: vec![std::net::IpAddr::from([192, 168, 3, 3]).into()],
+                    meta: Arc::new(Meta::Resource {
+                        group: "policy.linkerd.io".into(),
+                        kind: "AuthorizationPolicy".into(),
+                        name: "test".into(),
+                    }),
+                    local_addrs: vec![],
+                    validity: vec![],
+                }]),
+                filters: vec![],
+                meta: rmeta.clone(),
+                max_in_flight: Some(1),
+                rate_limit: None,
+                priority: Priority::Normal,
+            },
+        }],
+    }])));
+    let req = || {
+        ::http::Request::builder()
+            .body(hyper::Body::default())
+            .unwrap()
+    };
+
+    // The first request takes the route's only slot until its response
+    // completes.
+    let first = svc.call(req());
+    let err = svc.call(req()).await.expect_err("fails");
+    assert!(err.is::<HttpRouteConcurrencyExhausted>(), "{err}");
+
+    first.await.expect("serves");
+    svc.call(req()).await.expect("serves");
+
+    // Dropping a pending response releases its slot.
+    drop(svc.call(req()));
+    svc.call(req()).await.expect("serves");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_route_rate_limit() {
+    use linkerd_proxy_server_policy::http::{r#match::MatchRequest, Policy, Route, Rule};
+    use std::num::NonZeroU32;
+
+    let rmeta = Arc::new(Meta::Resource {
+        group: "gateway.networking.k8s.io".into(),
+        kind: "httproute".into(),
+        name: "testrt".into(),
+    });
+    let (mut svc, _tx) = new_svc!(Protocol::Http1(Arc::new([Route {
+        hosts: vec![],
+        rules: vec![Rule {
+            matches: vec![MatchRequest::default()],
+            policy: Policy {
+                authorizations: Arc::new([Authorization {
+                    authentication: Authentication::Unauthenticated,
+                    networks: vec![std::net::IpAddr::from([192, 168, 3, 3]).into()],
+                    meta: Arc::new(Meta::Resource {
+                        group: "policy.linkerd.io".into(),
+                        kind: "AuthorizationPolicy".into(),
+                        name: "test".into(),
+                    }),
+                    local_addrs: vec![],
+                    validity: vec![],
+                }]),
+                filters: vec![],
+                meta: rmeta.clone(),
+                max_in_flight: None,
+                rate_limit: Some(RateLimit {
+                    requests_per_second: NonZeroU32::new(1).unwrap(),
+                    burst: NonZeroU32::new(2).unwrap(),
+                }),
+                priority: Priority::Normal,
+            },
+        }],
+    }])));
+    let req = || {
+        ::http::Request::builder()
+            .body(hyper::Body::default())
+            .unwrap()
+    };
+
+    svc.call(req()).await.expect("serves");
+    svc.call(req()).await.expect("serves");
+    let err = svc.call(req()).await.expect_err("fails");
+    assert!(err.is::<HttpRouteRateLimited>(), "{err}");
+
+    // The route's bucket is refilled at one request per second.
+    tokio::time::advance(std::time::Duration::from_secs(1)).await;
+    svc.call(req()).await.expect("serves");
+    let err = svc.call(req()).await.expect_err("fails");
+    assert!(err.is::<HttpRouteRateLimited>(), "{err}");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn http_route_priority() {
+    use linkerd_proxy_server_policy::http::{r#match::MatchRequest, Policy, Route, Rule};
+
+    let rule = |method, name: &str, priority| Rule {
+        matches: vec![MatchRequest {
+            method: Some(method),
+            ..MatchRequest::default()
+        }],
+        policy: Policy {
+            authorizations: Arc::new([Authorization {
+                authentication: Authentication::Unauthenticated,
+                networks: vec![std::net::IpAddr::from([192, 168, 3, 3]).into()],
+                meta: Arc::new(Meta::Resource {
+                    group: "policy.linkerd.io".into(),
+                    kind: "AuthorizationPolicy".into(),
+                    name: "test".into(),
+                }),
+                local_addrs: vec![],
+                validity: vec![],
+            }]),
+            filters: vec![],
+            meta: Arc::new(Meta::Resource {
+                group: "gateway.networking.k8s.io".into(),
+                kind: "httproute".into(),
+                name: name.into(),
+            }),
+            max_in_flight: None,
+            rate_limit: None,
+            priority,
+        },
+    };
+    let (mut svc, _tx) = new_svc!(Protocol::Http1(Arc::new([Route {
+        hosts: vec![],
+        rules: vec![
+            rule(::http::Method::POST, "batch", Priority::Low),
+            rule(::http::Method::PUT, "api", Priority::Normal),
+            rule(::http::Method::GET, "health", Priority::High),
+        ],
+    }])));
+    svc.in_flight = InFlight::default().for_connection(Some(10));
+    let req = |method| {
+        ::http::Request::builder()
+            .method(method)
+            .body(hyper::Body::default())
+            .unwrap()
+    };
+
+    // Low-priority requests may use half of the connection's capacity.
+    let low = (0..5)
+        .map(|_| svc.call(req(::http::Method::POST)))
+        .collect::<Vec<_>>();
+    let err = svc
+        .call(req(::http::Method::POST))
+        .await
+        .expect_err("fails");
+    assert!(err.is::<HttpRoutePriorityShed>(), "{err}");
+
+    // Normal-priority requests may use 90% of it.
+    let normal = (0..4)
+        .map(|_| svc.call(req(::http::Method::PUT)))
+        .collect::<Vec<_>>();
+    let err = svc.call(req(::http::Method::PUT)).await.expect_err("fails");
+    assert!(err.is::<HttpRoutePriorityShed>(), "{err}");
+
+    // High-priority requests are not shed.
+    let high = (0..5)
+        .map(|_| svc.call(req(::http::Method::GET)))
+        .collect::<Vec<_>>();
+    for rsp in high {
+        rsp.await.expect("serves");
+    }
+
+    // Completed requests release their slots.
+    for rsp in low.into_iter().chain(normal) {
+        rsp.await.expect("serves");
+    }
+    svc.call(req(::http::Method::POST)).await.expect("serves");
+}
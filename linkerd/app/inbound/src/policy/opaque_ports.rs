@@ -0,0 +1,145 @@
+//! Tracks the inbound ports on which protocol detection is disabled by default.
+//!
+//! Opaque ports are configured at startup, but workloads may open new ports
+//! after the proxy starts. The set may be updated at runtime (via the admin
+//! server or a watched file) so that a port's proxying mode can be fixed
+//! without restarting the pod. Updates apply to connections on ports whose
+//! policies are subsequently looked up; policies that have already been
+//! discovered from the control plane are not changed.
+
+use parking_lot::RwLock;
+use rangemap::RangeInclusiveSet;
+use std::{ops::RangeInclusive, path::PathBuf, sync::Arc};
+use tokio::time;
+
+#[derive(Clone, Debug, Default)]
+pub struct OpaquePorts(Arc<RwLock<RangeInclusiveSet<u16>>>);
+
+#[derive(Clone, Debug, thiserror::Error)]
+#[error("invalid port range: {0}")]
+pub struct InvalidPorts(String);
+
+// === impl OpaquePorts ===
+
+impl OpaquePorts {
+    pub fn new(ports: RangeInclusiveSet<u16>) -> Self {
+        Self(Arc::new(RwLock::new(ports)))
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        self.0.read().contains(&port)
+    }
+
+    pub fn ranges(&self) -> Vec<RangeInclusive<u16>> {
+        self.0.read().iter().cloned().collect()
+    }
+
+    pub fn insert(&self, ports: &RangeInclusiveSet<u16>) {
+        let mut set = self.0.write();
+        for range in ports.iter() {
+            set.insert(range.clone());
+        }
+    }
+
+    pub fn remove(&self, ports: &RangeInclusiveSet<u16>) {
+        let mut set = self.0.write();
+        for range in ports.iter() {
+            set.remove(range.clone());
+        }
+    }
+
+    /// Parses a comma-separated list of ports and port ranges (e.g.
+    /// `25,8000-8080`).
+    pub fn parse(s: &str) -> Result<RangeInclusiveSet<u16>, InvalidPorts> {
+        let mut set = RangeInclusiveSet::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let invalid = || InvalidPorts(part.to_string());
+            let range = match part.split_once('-') {
+                Some((low, high)) => {
+                    let low = low.trim().parse::<u16>().map_err(|_| invalid())?;
+                    let high = high.trim().parse::<u16>().map_err(|_| invalid())?;
+                    if low > high {
+                        return Err(invalid());
+                    }
+                    low..=high
+                }
+                None => {
+                    let port = part.parse::<u16>().map_err(|_| invalid())?;
+                    port..=port
+                }
+            };
+            set.insert(range);
+        }
+        Ok(set)
+    }
+
+    /// Polls `path` for a list of ports, marking them opaque in addition to
+    /// the statically configured ports.
+    ///
+    /// When the file changes, ports that were listed previously but are no
+    /// longer listed are no longer opaque. If the file cannot be read or
+    /// parsed, the set is left unchanged.
+    pub async fn watch_file(self, path: PathBuf, interval: time::Duration) {
+        let mut listed = RangeInclusiveSet::new();
+        let mut contents = None;
+        let mut interval = time::interval(interval);
+        interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            let read = match tokio::fs::read_to_string(&path).await {
+                Ok(read) => read,
+                Err(error) => {
+                    tracing::debug!(path = %path.display(), %error, "Failed to read opaque ports");
+                    continue;
+                }
+            };
+            if contents.as_ref() == Some(&read) {
+                continue;
+            }
+
+            match Self::parse(&read) {
+                Ok(ports) => {
+                    tracing::info!(
+                        path = %path.display(),
+                        ports = ?ports.iter().collect::<Vec<_>>(),
+                        "Updating opaque ports",
+                    );
+                    self.remove(&listed);
+                    self.insert(&ports);
+                    listed = ports;
+                }
+                Err(error) => {
+                    tracing::warn!(path = %path.display(), %error, "Invalid opaque ports");
+                }
+            }
+            contents = Some(read);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_remove() {
+        let ports = OpaquePorts::new(OpaquePorts::parse("25, 3306").unwrap());
+        assert!(ports.contains(25));
+        assert!(!ports.contains(8080));
+
+        ports.insert(&OpaquePorts::parse("8000-8080").unwrap());
+        assert!(ports.contains(8080));
+        assert_eq!(ports.ranges(), vec![25..=25, 3306..=3306, 8000..=8080]);
+
+        ports.remove(&OpaquePorts::parse("25,8080").unwrap());
+        assert!(!ports.contains(25));
+        assert!(!ports.contains(8080));
+        assert_eq!(ports.ranges(), vec![3306..=3306, 8000..=8079]);
+
+        assert!(OpaquePorts::parse("").unwrap().is_empty());
+        assert!(OpaquePorts::parse("http").is_err());
+        assert!(OpaquePorts::parse("90-80").is_err());
+        assert!(OpaquePorts::parse("70000").is_err());
+    }
+}
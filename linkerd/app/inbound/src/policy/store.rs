@@ -1,10 +1,9 @@
-use super::{api, AllowPolicy, DefaultPolicy, GetPolicy};
-use linkerd_app_core::{proxy::http, transport::OrigDstAddr, Error};
-use linkerd_idle_cache::IdleCache;
+use super::{api, AllowPolicy, DefaultPolicy, GetPolicy, OpaquePorts, Overrides};
+use linkerd_app_core::{identity as id, proxy::http, transport::OrigDstAddr, Error};
+use linkerd_idle_cache::{Cached, IdleCache};
 pub use linkerd_proxy_server_policy::{Protocol, ServerPolicy};
-use rangemap::RangeInclusiveSet;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     hash::{BuildHasherDefault, Hasher},
     sync::Arc,
 };
@@ -14,14 +13,30 @@ use tracing::info_span;
 #[derive(Clone)]
 pub struct Store<S> {
     cache: IdleCache<u16, Rx, BuildHasherDefault<PortHasher>>,
-    default_rx: Rx,
-    opaque_ports: Arc<RangeInclusiveSet<u16>>,
-    opaque_default_rx: Rx,
+    defaults: Defaults,
     discover: Option<api::Watch<S>>,
+    local_id: Option<id::Id>,
 }
 
 type Rx = watch::Receiver<ServerPolicy>;
 
+/// The policies used for ports whose policies are not fixed or not (yet)
+/// discovered.
+#[derive(Clone)]
+struct Defaults {
+    default: DefaultRxs,
+    ports: Arc<HashMap<u16, DefaultRxs>>,
+    opaque_ports: OpaquePorts,
+}
+
+/// A default policy, and its variant for ports on which protocol detection is
+/// disabled.
+#[derive(Clone)]
+struct DefaultRxs {
+    default: Rx,
+    opaque: Rx,
+}
+
 /// A hasher for ports.
 ///
 /// Because ports are single `u16` values, we don't have to hash them; we can just use
@@ -34,15 +49,14 @@ struct PortHasher(u16);
 impl<S> Store<S> {
     pub(crate) fn spawn_fixed(
         default: DefaultPolicy,
+        port_defaults: HashMap<u16, DefaultPolicy>,
         idle_timeout: Duration,
         ports: impl IntoIterator<Item = (u16, ServerPolicy)>,
-        opaque_ports: RangeInclusiveSet<u16>,
+        opaque_ports: OpaquePorts,
     ) -> Self {
-        let opaque_default_rx = Self::spawn_default(Self::make_opaque(default.clone()));
+        // Ports without a fixed policy are not cached, so that they use the
+        // opaque default policy if the set of opaque ports changes.
         let cache = {
-            let opaque_rxs = opaque_ports
-                .iter()
-                .flat_map(|range| range.clone().map(|port| (port, opaque_default_rx.clone())));
             let rxs = ports.into_iter().map(|(p, s)| {
                 // When using a fixed policy, we don't need to watch for changes. It's
                 // safe to discard the sender, as the receiver will continue to let us
@@ -50,15 +64,14 @@ impl<S> Store<S> {
                 let (_, rx) = watch::channel(s);
                 (p, rx)
             });
-            IdleCache::with_permanent_from_iter(idle_timeout, opaque_rxs.chain(rxs))
+            IdleCache::with_permanent_from_iter(idle_timeout, rxs)
         };
 
         Self {
             cache,
             discover: None,
-            default_rx: Self::spawn_default(default),
-            opaque_ports: Arc::new(opaque_ports),
-            opaque_default_rx,
+            defaults: Defaults::spawn(default, port_defaults, opaque_ports),
+            local_id: None,
         }
     }
 
@@ -68,10 +81,12 @@ impl<S> Store<S> {
     /// result is cached for as long as the `Store` is held. The `Store` may be used to
     pub(super) fn spawn_discover(
         default: DefaultPolicy,
+        port_defaults: HashMap<u16, DefaultPolicy>,
         idle_timeout: Duration,
         discover: api::Watch<S>,
         ports: HashSet<u16>,
-        opaque_ports: RangeInclusiveSet<u16>,
+        opaque_ports: OpaquePorts,
+        overrides: &Overrides,
     ) -> Self
     where
         S: tonic::client::GrpcService<tonic::body::BoxBody, Error = Error>,
@@ -80,7 +95,8 @@ impl<S> Store<S> {
         S::ResponseBody:
             http::HttpBody<Data = tonic::codegen::Bytes, Error = Error> + Default + Send + 'static,
     {
-        let opaque_default = Self::make_opaque(default.clone());
+        let port_defaults = Defaults::with_overrides(&default, port_defaults, overrides);
+        let defaults = Defaults::spawn(default, port_defaults, opaque_ports);
         // The initial set of policies never expire from the cache.
         //
         // Policies that are dynamically discovered at runtime will expire after
@@ -89,13 +105,9 @@ impl<S> Store<S> {
         let cache = {
             let rxs = ports.into_iter().map(|port| {
                 let discover = discover.clone();
-                let default = if opaque_ports.contains(&port) {
-                    opaque_default.clone()
-                } else {
-                    default.clone()
-                };
-                let rx = info_span!("watch", port)
-                    .in_scope(|| discover.spawn_with_init(port, default.into()));
+                let init = defaults.rx(port).borrow().clone();
+                let rx =
+                    info_span!("watch", port).in_scope(|| discover.spawn_with_init(port, init));
                 (port, rx)
             });
             IdleCache::with_permanent_from_iter(idle_timeout, rxs)
@@ -104,9 +116,86 @@ impl<S> Store<S> {
         Self {
             cache,
             discover: Some(discover),
-            default_rx: Self::spawn_default(default),
-            opaque_ports: Arc::new(opaque_ports),
-            opaque_default_rx: Self::spawn_default(opaque_default),
+            defaults,
+            local_id: None,
+        }
+    }
+
+    /// Sets the proxy's own identity, against which policies' identity
+    /// patterns may be matched.
+    pub(super) fn with_local_id(self, local_id: id::Id) -> Self {
+        Self {
+            local_id: Some(local_id),
+            ..self
+        }
+    }
+}
+
+// === impl Defaults ===
+
+impl Defaults {
+    fn spawn(
+        default: DefaultPolicy,
+        port_defaults: HashMap<u16, DefaultPolicy>,
+        opaque_ports: OpaquePorts,
+    ) -> Self {
+        // Default policies configured for individual ports apply to those
+        // ports in place of the proxy's default policy.
+        let ports = port_defaults
+            .into_iter()
+            .map(|(port, default)| (port, DefaultRxs::spawn(default)))
+            .collect();
+        Self {
+            default: DefaultRxs::spawn(default),
+            ports: Arc::new(ports),
+            opaque_ports,
+        }
+    }
+
+    /// Applies the settings configured for each port to the port's default
+    /// policy, so that they apply before the port's policy is discovered.
+    fn with_overrides(
+        default: &DefaultPolicy,
+        mut port_defaults: HashMap<u16, DefaultPolicy>,
+        overrides: &Overrides,
+    ) -> HashMap<u16, DefaultPolicy> {
+        for port in overrides.ports.keys() {
+            let policy = match port_defaults
+                .remove(port)
+                .unwrap_or_else(|| default.clone())
+            {
+                DefaultPolicy::Allow(policy) => {
+                    DefaultPolicy::Allow(overrides.apply(*port, policy))
+                }
+                DefaultPolicy::Deny => DefaultPolicy::Deny,
+            };
+            port_defaults.insert(*port, policy);
+        }
+        port_defaults
+    }
+
+    /// Returns the default policy for the given port.
+    ///
+    /// If the port has its own default policy, it is used. If the port is
+    /// currently marked as opaque, the opaque variant of the default policy
+    /// is used.
+    fn rx(&self, port: u16) -> &Rx {
+        let rxs = self.ports.get(&port).unwrap_or(&self.default);
+        if self.opaque_ports.contains(port) {
+            &rxs.opaque
+        } else {
+            &rxs.default
+        }
+    }
+}
+
+// === impl DefaultRxs ===
+
+impl DefaultRxs {
+    fn spawn(default: DefaultPolicy) -> Self {
+        Self {
+            opaque: Self::spawn_rx(Self::make_opaque(default.clone())),
+            default: Self::spawn_rx(default),
         }
     }
 
@@ -118,6 +207,8 @@ impl<S> Store<S> {
                         Protocol::Opaque(tcp_authorizations)
                     }
                     opaq @ Protocol::Opaque(_) => opaq,
+                    // TLS is passed through without protocol detection.
+                    tls @ Protocol::Tls { .. } => tls,
                     _ => unreachable!("default policy must have been configured to detect prior to marking it opaque"),
                 };
                 DefaultPolicy::Allow(policy)
@@ -126,7 +217,7 @@ impl<S> Store<S> {
         }
     }
 
-    fn spawn_default(default: DefaultPolicy) -> Rx {
+    fn spawn_rx(default: DefaultPolicy) -> Rx {
         let (tx, rx) = watch::channel(ServerPolicy::from(default));
         // Hold the sender until all of the receivers are dropped. This ensures
         // that receivers can be watched like any other policy.
@@ -149,33 +240,34 @@ where
         // Lookup the policy for the target port in the cache. If it doesn't
         // already exist, we spawn a watch on the API (if it is configured). If
         // no discovery API is configured we use the default policy.
-        let server =
-            self.cache
-                .get_or_insert_with(dst.port(), |port| match self.discover.clone() {
-                    Some(disco) => info_span!("watch", port).in_scope(|| {
-                        let is_default_opaque = self.opaque_ports.contains(port);
-                        tracing::trace!(%port, is_default_opaque, "spawning policy discovery");
-                        // If the port is in the range of ports marked as
-                        // opaque, use the opaque default policy instead.
-                        let init = if is_default_opaque {
-                            self.opaque_default_rx.borrow().clone()
-                        } else {
-                            self.default_rx.borrow().clone()
-                        };
-                        disco.spawn_with_init(*port, init)
-                    }),
-
-                    // If no discovery API is configured, then we use the
-                    // default policy. Whlie it's a little wasteful to cache
-                    // these results separately, this case isn't expected to be
-                    // used outside of testing.
-                    None => {
-                        tracing::trace!(%port, "using the default policy");
-                        self.default_rx.clone()
-                    }
-                });
+        let port = dst.port();
+        let server = match self.discover.clone() {
+            Some(disco) => self.cache.get_or_insert_with(port, |port| {
+                info_span!("watch", port).in_scope(|| {
+                    let is_default_opaque = self.defaults.opaque_ports.contains(*port);
+                    tracing::trace!(%port, is_default_opaque, "spawning policy discovery");
+                    // If the port has its own default policy, use it. If the
+                    // port is in the range of ports marked as opaque, use the
+                    // opaque variant of the default policy.
+                    let init = self.defaults.rx(*port).borrow().clone();
+                    disco.spawn_with_init(*port, init)
+                })
+            }),
+
+            // If no discovery API is configured, then we use the fixed policy
+            // or, if there is none, the default policy. Default policies are
+            // not cached so that changes to the set of opaque ports apply.
+            None => self.cache.get(&port).unwrap_or_else(|| {
+                tracing::trace!(%port, "using the default policy");
+                Cached::uncached(self.defaults.rx(port).clone())
+            }),
+        };
 
-        AllowPolicy { dst, server }
+        AllowPolicy {
+            dst,
+            server,
+            local_id: self.local_id.clone(),
+        }
     }
 }
 
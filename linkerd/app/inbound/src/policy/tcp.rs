@@ -4,24 +4,32 @@ use crate::{
 };
 use futures::future;
 use linkerd_app_core::{
-    svc, tls,
+    identity as id, svc, tls,
     transport::{ClientAddr, OrigDstAddr, Remote},
     Error, Result,
 };
 use linkerd_proxy_server_policy::{Protocol, ServerPolicy};
-use std::{future::Future, pin::Pin, task};
+use std::{
+    future::Future,
+    pin::Pin,
+    task,
+    time::{Duration, SystemTime},
+};
 #[cfg(test)]
 mod tests;
 
 /// A middleware that enforces policy on each TCP connection. When connection is
-/// authorized, we continue to monitor the policy for changes and, if the
-/// connection is no longer authorized, it is dropped/closed.
+/// authorized, we continue to monitor the policy for changes (and the
+/// expiry of time-limited authorizations) and, if the connection is no longer
+/// authorized, it is dropped/closed once the configured grace period elapses
+/// (unless it is authorized again in the meantime).
 ///
 /// Metrics are reported to the `TcpAuthzMetrics` struct.
 #[derive(Clone, Debug)]
 pub struct NewTcpPolicy<N> {
     inner: N,
     metrics: TcpAuthzMetrics,
+    grace_period: Duration,
 }
 
 #[derive(Clone, Debug)]
@@ -37,17 +45,26 @@ pub struct Authorized<S> {
     client: Remote<ClientAddr>,
     tls: tls::ConditionalServerTls,
     metrics: TcpAuthzMetrics,
+    grace_period: Duration,
 }
 
+/// The reason for which a connection's authorization was revoked, and the end
+/// of its grace period.
+type Revoked = (&'static str, Pin<Box<tokio::time::Sleep>>);
+
 // === impl NewTcpPolicy ===
 
 impl<N> NewTcpPolicy<N> {
+    /// Builds a layer that authorizes connections. Connections whose
+    /// authorization is revoked are closed after `grace_period`.
     pub(crate) fn layer(
         metrics: TcpAuthzMetrics,
+        grace_period: Duration,
     ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
         svc::layer::mk(move |inner| Self {
             inner,
             metrics: metrics.clone(),
+            grace_period,
         })
     }
 }
@@ -68,7 +85,7 @@ where
         let authorized = {
             let p = policy.server.borrow();
             tracing::trace!(policy = ?p, "Authorizing connection");
-            check_authorized(&p, policy.dst, client, &tls)
+            check_authorized(&p, policy.dst, policy.local_id(), client, &tls)
         };
         match authorized {
             Ok(permit) => {
@@ -86,6 +103,7 @@ where
                     client,
                     tls,
                     metrics: self.metrics.clone(),
+                    grace_period: self.grace_period,
                 })
             }
             Err(deny) => {
@@ -138,6 +156,7 @@ where
             tls,
             policy,
             metrics,
+            grace_period,
         } = match self {
             Self::Authorized(a) => a,
             Self::Unauthorized(_deny) => unreachable!("poll_ready must be called"),
@@ -149,39 +168,124 @@ where
         let tls = tls.clone();
         let mut policy = policy.clone();
         let metrics = metrics.clone();
+        let grace_period = *grace_period;
 
         let call = inner.call(io);
         future::Either::Left(Box::pin(async move {
             tokio::pin!(call);
+            // Set when the connection is no longer authorized, so that it may
+            // be terminated once its grace period elapses.
+            let mut revoked: Option<Revoked> = None;
             loop {
-                tokio::select! {
+                let expiry = next_expiry(&policy.server.borrow(), SystemTime::now());
+                let reason = tokio::select! {
                     res = &mut call => return res.map_err(Into::into),
-                    _ = policy.changed() => {
-                        if let Err(denied) = check_authorized(&policy.server.borrow(), policy.dst, client, &tls) {
-                            let meta = policy.meta();
-                            tracing::info!(
-                                server.group = %meta.group(),
-                                server.kind = %meta.kind(),
-                                server.name = %meta.name(),
-                                ?tls,
-                                %client,
-                                "Connection terminated due to policy change",
-                            );
-                            metrics.terminate(&policy, tls);
-                            return Err(denied.into());
+                    _ = policy.changed() => "policy change",
+                    _ = expired(expiry) => "authorization expiry",
+                    reason = grace_elapsed(&mut revoked) => reason,
+                };
+                let authorized = check_authorized(
+                    &policy.server.borrow(),
+                    policy.dst,
+                    policy.local_id(),
+                    client,
+                    &tls,
+                );
+                let denied = match authorized {
+                    Ok(_) => {
+                        if revoked.take().is_some() {
+                            tracing::debug!(?tls, %client, "Connection authorized again");
                         }
+                        continue;
                     }
+                    Err(denied) => denied,
                 };
+
+                match revoked {
+                    // Allow the connection to continue for the grace period so
+                    // that the application may close it gracefully.
+                    None if !grace_period.is_zero() => {
+                        tracing::info!(
+                            ?grace_period,
+                            ?tls,
+                            %client,
+                            "Connection no longer authorized due to {reason}",
+                        );
+                        revoked = Some((reason, Box::pin(tokio::time::sleep(grace_period))));
+                        continue;
+                    }
+                    Some((_, ref sleep)) if !sleep.is_elapsed() => continue,
+                    _ => {}
+                }
+
+                let meta = policy.meta();
+                tracing::info!(
+                    server.group = %meta.group(),
+                    server.kind = %meta.kind(),
+                    server.name = %meta.name(),
+                    ?tls,
+                    %client,
+                    "Connection terminated due to {reason}",
+                );
+                metrics.terminate(&policy, tls);
+                return Err(denied.into());
             }
         }))
     }
 }
 
+/// Returns the earliest time after `now` at which one of the server's
+/// connection authorizations expires, so that connections can be
+/// re-authorized when it does.
+fn next_expiry(server: &ServerPolicy, now: SystemTime) -> Option<SystemTime> {
+    let authzs = match &server.protocol {
+        Protocol::Detect {
+            tcp_authorizations: authzs,
+            ..
+        }
+        | Protocol::Tls {
+            authorizations: authzs,
+            ..
+        }
+        | Protocol::Opaque(authzs) => authzs,
+        _ => return None,
+    };
+    authzs
+        .iter()
+        .flat_map(|authz| authz.validity.iter().filter_map(|w| w.not_after))
+        .filter(|&end| end > now)
+        .min()
+}
+
+/// Completes with the reason for a connection's revocation when its grace
+/// period elapses, or never if the connection has not been revoked.
+async fn grace_elapsed(revoked: &mut Option<Revoked>) -> &'static str {
+    match revoked {
+        Some((reason, sleep)) => {
+            sleep.await;
+            *reason
+        }
+        None => future::pending().await,
+    }
+}
+
+/// Completes at the given time, or never if no time is given.
+async fn expired(at: Option<SystemTime>) {
+    match at {
+        Some(at) => {
+            let timeout = at.duration_since(SystemTime::now()).unwrap_or_default();
+            tokio::time::sleep(timeout).await
+        }
+        None => future::pending().await,
+    }
+}
+
 /// Checks whether the destination port's `AllowPolicy` is authorized to
 /// accept connections given the provided TLS state.
 fn check_authorized(
     server: &ServerPolicy,
     dst: OrigDstAddr,
+    local_id: Option<&id::Id>,
     client_addr: Remote<ClientAddr>,
     tls: &tls::ConditionalServerTls,
 ) -> Result<ServerPermit, ServerUnauthorized> {
@@ -189,11 +293,14 @@ fn check_authorized(
         tcp_authorizations: authzs,
         ..
     }
-    | Protocol::Tls(authzs)
+    | Protocol::Tls {
+        authorizations: authzs,
+        ..
+    }
     | Protocol::Opaque(authzs) = &server.protocol
     {
         for authz in &**authzs {
-            if super::is_authorized(authz, client_addr, tls) {
+            if super::is_authorized(authz, dst, local_id, client_addr, tls) {
                 return Ok(ServerPermit::new(dst, server, authz));
             }
         }
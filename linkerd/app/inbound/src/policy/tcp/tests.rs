@@ -21,6 +21,8 @@ async fn unauthenticated_allowed() {
                     kind: "serverauthorization".into(),
                     name: "unauth".into(),
                 }),
+                local_addrs: vec![],
+                validity: vec![],
             }]
             .into(),
         ),
@@ -29,10 +31,12 @@ async fn unauthenticated_allowed() {
             kind: "server".into(),
             name: "test".into(),
         }),
+        bandwidth_limit: None,
+        duplex: Default::default(),
     };
 
     let tls = tls::ConditionalServerTls::None(tls::NoServerTls::NoClientHello);
-    let permitted = check_authorized(&policy, orig_dst_addr(), client_addr(), &tls)
+    let permitted = check_authorized(&policy, orig_dst_addr(), None, client_addr(), &tls)
         .expect("unauthenticated connection must be permitted");
     assert_eq!(
         permitted,
@@ -63,6 +67,7 @@ async fn authenticated_identity() {
                 authentication: Authentication::TlsAuthenticated {
                     suffixes: vec![],
                     identities: vec![client_id().to_string()].into_iter().collect(),
+                    patterns: vec![],
                 },
                 networks: vec!["192.0.2.0/24".parse().unwrap()],
                 meta: Arc::new(Meta::Resource {
@@ -70,6 +75,8 @@ async fn authenticated_identity() {
                     kind: "serverauthorization".into(),
                     name: "tls-auth".into(),
                 }),
+                local_addrs: vec![],
+                validity: vec![],
             }]
             .into(),
         ),
@@ -78,13 +85,16 @@ async fn authenticated_identity() {
             kind: "server".into(),
             name: "test".into(),
         }),
+        bandwidth_limit: None,
+        duplex: Default::default(),
     };
 
     let tls = tls::ConditionalServerTls::Some(tls::ServerTls::Established {
         client_id: Some(client_id()),
+        client_fingerprint: None,
         negotiated_protocol: None,
     });
-    let permitted = check_authorized(&policy, orig_dst_addr(), client_addr(), &tls)
+    let permitted = check_authorized(&policy, orig_dst_addr(), None, client_addr(), &tls)
         .expect("unauthenticated connection must be permitted");
     assert_eq!(
         permitted,
@@ -112,9 +122,10 @@ async fn authenticated_identity() {
                 .parse()
                 .unwrap(),
         )),
+        client_fingerprint: None,
         negotiated_protocol: None,
     });
-    check_authorized(&policy, orig_dst_addr(), client_addr(), &tls)
+    check_authorized(&policy, orig_dst_addr(), None, client_addr(), &tls)
         .expect_err("policy must require a client identity");
 }
 
@@ -126,6 +137,7 @@ async fn authenticated_suffix() {
                 authentication: Authentication::TlsAuthenticated {
                     identities: BTreeSet::default(),
                     suffixes: vec![Suffix::from(vec!["cluster".into(), "local".into()])],
+                    patterns: vec![],
                 },
                 networks: vec!["192.0.2.0/24".parse().unwrap()],
                 meta: Arc::new(Meta::Resource {
@@ -133,6 +145,8 @@ async fn authenticated_suffix() {
                     kind: "serverauthorization".into(),
                     name: "tls-auth".into(),
                 }),
+                local_addrs: vec![],
+                validity: vec![],
             }]
             .into(),
         ),
@@ -141,14 +155,17 @@ async fn authenticated_suffix() {
             kind: "server".into(),
             name: "test".into(),
         }),
+        bandwidth_limit: None,
+        duplex: Default::default(),
     };
 
     let tls = tls::ConditionalServerTls::Some(tls::ServerTls::Established {
         client_id: Some(client_id()),
+        client_fingerprint: None,
         negotiated_protocol: None,
     });
     assert_eq!(
-        check_authorized(&policy, orig_dst_addr(), client_addr(), &tls)
+        check_authorized(&policy, orig_dst_addr(), None, client_addr(), &tls)
             .expect("unauthenticated connection must be permitted"),
         ServerPermit {
             dst: orig_dst_addr(),
@@ -174,9 +191,10 @@ async fn authenticated_suffix() {
                 .parse()
                 .unwrap(),
         ),
+        client_fingerprint: None,
         negotiated_protocol: None,
     });
-    check_authorized(&policy, orig_dst_addr(), client_addr(), &tls)
+    check_authorized(&policy, orig_dst_addr(), None, client_addr(), &tls)
         .expect_err("policy must require a client identity");
 }
 
@@ -192,6 +210,8 @@ async fn tls_unauthenticated() {
                     kind: "serverauthorization".into(),
                     name: "tls-unauth".into(),
                 }),
+                local_addrs: vec![],
+                validity: vec![],
             }]
             .into(),
         ),
@@ -200,14 +220,17 @@ async fn tls_unauthenticated() {
             kind: "server".into(),
             name: "test".into(),
         }),
+        bandwidth_limit: None,
+        duplex: Default::default(),
     };
 
     let tls = tls::ConditionalServerTls::Some(tls::ServerTls::Established {
         client_id: None,
+        client_fingerprint: None,
         negotiated_protocol: None,
     });
     assert_eq!(
-        check_authorized(&policy, orig_dst_addr(), client_addr(), &tls)
+        check_authorized(&policy, orig_dst_addr(), None, client_addr(), &tls)
             .expect("unauthenticated connection must be permitted"),
         ServerPermit {
             dst: orig_dst_addr(),
@@ -232,10 +255,120 @@ async fn tls_unauthenticated() {
             .parse()
             .unwrap(),
     });
-    check_authorized(&policy, orig_dst_addr(), client_addr(), &tls)
+    check_authorized(&policy, orig_dst_addr(), None, client_addr(), &tls)
         .expect_err("policy must require a TLS termination identity");
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn local_addr_restricted() {
+    use linkerd_proxy_server_policy::authz::LocalAddr;
+
+    let policy = ServerPolicy {
+        protocol: Protocol::Opaque(
+            vec![Authorization {
+                authentication: Authentication::Unauthenticated,
+                networks: vec!["192.0.2.0/24".parse().unwrap()],
+                meta: Arc::new(Meta::Resource {
+                    group: "policy.linkerd.io".into(),
+                    kind: "serverauthorization".into(),
+                    name: "node-local".into(),
+                }),
+                local_addrs: vec![LocalAddr {
+                    net: Some("192.0.2.2/32".parse().unwrap()),
+                    port: Some(1000),
+                }],
+                validity: vec![],
+            }]
+            .into(),
+        ),
+        meta: Arc::new(Meta::Resource {
+            group: "policy.linkerd.io".into(),
+            kind: "server".into(),
+            name: "test".into(),
+        }),
+        bandwidth_limit: None,
+        duplex: Default::default(),
+    };
+
+    let tls = tls::ConditionalServerTls::None(tls::NoServerTls::NoClientHello);
+    check_authorized(&policy, orig_dst_addr(), None, client_addr(), &tls)
+        .expect("connection on the local address must be permitted");
+    check_authorized(
+        &policy,
+        OrigDstAddr(([192, 0, 2, 2], 1001).into()),
+        None,
+        client_addr(),
+        &tls,
+    )
+    .expect_err("connection on another port must not be permitted");
+    check_authorized(
+        &policy,
+        OrigDstAddr(([192, 0, 2, 4], 1000).into()),
+        None,
+        client_addr(),
+        &tls,
+    )
+    .expect_err("connection on another address must not be permitted");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn time_limited() {
+    use linkerd_proxy_server_policy::authz::TimeWindow;
+    use std::time::{Duration, SystemTime};
+
+    let now = SystemTime::now();
+    let policy = |validity| ServerPolicy {
+        protocol: Protocol::Opaque(
+            vec![Authorization {
+                authentication: Authentication::Unauthenticated,
+                networks: vec!["192.0.2.0/24".parse().unwrap()],
+                meta: Arc::new(Meta::Resource {
+                    group: "policy.linkerd.io".into(),
+                    kind: "serverauthorization".into(),
+                    name: "break-glass".into(),
+                }),
+                local_addrs: vec![],
+                validity,
+            }]
+            .into(),
+        ),
+        meta: Arc::new(Meta::Resource {
+            group: "policy.linkerd.io".into(),
+            kind: "server".into(),
+            name: "test".into(),
+        }),
+        bandwidth_limit: None,
+        duplex: Default::default(),
+    };
+
+    let tls = tls::ConditionalServerTls::None(tls::NoServerTls::NoClientHello);
+    let current = policy(vec![TimeWindow {
+        not_before: Some(now - Duration::from_secs(60)),
+        not_after: Some(now + Duration::from_secs(60)),
+    }]);
+    check_authorized(&current, orig_dst_addr(), None, client_addr(), &tls)
+        .expect("connection must be permitted during the window");
+    assert_eq!(
+        next_expiry(&current, now),
+        Some(now + Duration::from_secs(60))
+    );
+
+    let expired = policy(vec![TimeWindow {
+        not_before: None,
+        not_after: Some(now - Duration::from_secs(1)),
+    }]);
+    check_authorized(&expired, orig_dst_addr(), None, client_addr(), &tls)
+        .expect_err("connection must not be permitted after the window");
+    assert_eq!(next_expiry(&expired, now), None);
+
+    let pending = policy(vec![TimeWindow {
+        not_before: Some(now + Duration::from_secs(60)),
+        not_after: None,
+    }]);
+    check_authorized(&pending, orig_dst_addr(), None, client_addr(), &tls)
+        .expect_err("connection must not be permitted before the window");
+}
+
 fn client_id() -> tls::ClientId {
     "testsa.testns.serviceaccount.identity.linkerd.cluster.local"
         .parse()
@@ -274,6 +407,7 @@ impl Store<MockSvc> {
     ) -> Self {
         Self::spawn_fixed(
             default.into(),
+            Default::default(),
             std::time::Duration::MAX,
             ports,
             Default::default(),
@@ -2,20 +2,24 @@ use crate::{direct, policy, Inbound};
 use linkerd_app_core::{
     exp_backoff::ExponentialBackoff,
     io, profiles,
-    proxy::http,
+    proxy::{http, tcp},
     svc,
     transport::{self, addrs::*},
     Error,
 };
 use linkerd_tonic_stream::ReceiveLimits;
-use std::{fmt::Debug, sync::Arc};
-use tracing::debug_span;
+use std::{fmt::Debug, sync::Arc, time::Duration};
+use tracing::{debug_span, info_span, Instrument};
 
 #[derive(Copy, Clone, Debug)]
 struct TcpEndpoint {
     addr: Remote<ServerAddr>,
+    duplex: tcp::DuplexConfig,
 }
 
+/// How often the opaque ports file is checked for changes.
+const OPAQUE_PORTS_FILE_INTERVAL: Duration = Duration::from_secs(5);
+
 // === impl Inbound ===
 
 impl Inbound<()> {
@@ -33,10 +37,23 @@ impl Inbound<()> {
         C::ResponseBody: Default + Send + 'static,
         C::Future: Send,
     {
-        self.config
-            .policy
-            .clone()
-            .build(workload, client, backoff, limits)
+        if let Some(path) = self.config.opaque_ports_file.clone() {
+            let opaque_ports = self.config.policy.opaque_ports().clone();
+            tokio::spawn(
+                opaque_ports
+                    .watch_file(path, OPAQUE_PORTS_FILE_INTERVAL)
+                    .instrument(info_span!("opaque_ports")),
+            );
+        }
+
+        self.config.policy.clone().build(
+            workload,
+            self.runtime.identity.local_id().clone(),
+            self.runtime.metrics.policy_updates.clone(),
+            client,
+            backoff,
+            limits,
+        )
     }
 
     pub fn mk<A, I, P>(
@@ -106,8 +123,14 @@ impl Inbound<()> {
 // === impl TcpEndpoint ===
 
 impl TcpEndpoint {
-    pub fn from_param<T: svc::Param<Remote<ServerAddr>>>(t: T) -> Self {
-        Self { addr: t.param() }
+    pub fn from_param<T>(t: T) -> Self
+    where
+        T: svc::Param<Remote<ServerAddr>> + svc::Param<tcp::DuplexConfig>,
+    {
+        Self {
+            addr: t.param(),
+            duplex: t.param(),
+        }
     }
 }
 
@@ -117,6 +140,12 @@ impl svc::Param<Remote<ServerAddr>> for TcpEndpoint {
     }
 }
 
+impl svc::Param<tcp::DuplexConfig> for TcpEndpoint {
+    fn param(&self) -> tcp::DuplexConfig {
+        self.duplex
+    }
+}
+
 impl svc::Param<transport::labels::Key> for TcpEndpoint {
     fn param(&self) -> transport::labels::Key {
         transport::labels::Key::InboundClient
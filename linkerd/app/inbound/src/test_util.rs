@@ -30,6 +30,8 @@ pub fn default_config() -> Config {
             kind: "serverauthorization".into(),
             name: "testsaz".into(),
         }),
+        local_addrs: vec![],
+        validity: vec![],
     }]);
     let policy = policy::Config::Fixed {
         cache_max_idle_age: Duration::from_secs(20),
@@ -46,8 +48,11 @@ pub fn default_config() -> Config {
                 kind: "server".into(),
                 name: "testsrv".into(),
             }),
+            bandwidth_limit: None,
+            duplex: Default::default(),
         }
         .into(),
+        port_defaults: Default::default(),
         ports: Default::default(),
         opaque_ports: Default::default(),
     };
@@ -59,10 +64,16 @@ pub fn default_config() -> Config {
             server: config::ServerConfig {
                 addr: ListenAddr(([0, 0, 0, 0], 0).into()),
                 keepalive: Keepalive(None),
+                socket_options: Default::default(),
+                accept_shards: Default::default(),
+                reuse_port: Default::default(),
                 h2_settings: h2::Settings::default(),
+                max_connection_age: None,
+                preserve_trailers: true,
             },
             connect: config::ConnectConfig {
                 keepalive: Keepalive(None),
+                socket_options: Default::default(),
                 timeout: Duration::from_secs(1),
                 backoff: exp_backoff::ExponentialBackoff::try_new(
                     Duration::from_millis(100),
@@ -73,11 +84,14 @@ pub fn default_config() -> Config {
                 h1_settings: h1::PoolSettings {
                     max_idle: 1,
                     idle_timeout: Duration::from_secs(1),
+                    max_requests_per_connection: None,
+                    warm_connections: 0,
                 },
                 h2_settings: h2::Settings::default(),
             },
             max_in_flight_requests: 10_000,
             detect_protocol_timeout: Duration::from_secs(10),
+            deadline_header: None,
         },
         allowed_ips: Default::default(),
         http_request_queue: config::QueueConfig {
@@ -86,12 +100,22 @@ pub fn default_config() -> Config {
         },
         discovery_idle_timeout: Duration::from_secs(20),
         profile_skip_timeout: Duration::from_secs(1),
+        connection_limits: Default::default(),
+        response_compression: None,
+        grpc_web: false,
+        additional_listen_addrs: Vec::new(),
+        hmac_secrets: Default::default(),
+        authz_top_clients: None,
+        max_request_target_len: None,
+        opaque_ports_file: None,
+        revocation_grace_period: Duration::ZERO,
+        grpc_stream_reauthorization: None,
     }
 }
 
 pub fn runtime() -> (ProxyRuntime, drain::Signal) {
     let (drain_tx, drain) = drain::channel();
-    let (tap, _) = tap::new();
+    let (tap, _) = tap::new(Default::default());
     let (metrics, _) = metrics::Metrics::new(std::time::Duration::from_secs(10));
     let runtime = ProxyRuntime {
         identity: rustls::creds::default_for_test().1.into(),
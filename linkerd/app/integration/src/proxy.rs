@@ -2,7 +2,7 @@ use super::*;
 use linkerd_app_core::{
     svc::Param,
     transport::OrigDstAddr,
-    transport::{listen, orig_dst, Keepalive, ListenAddr},
+    transport::{listen, orig_dst, AcceptShards, Keepalive, ListenAddr, ReusePort, SocketOptions},
     Result,
 };
 use std::{
@@ -67,7 +67,8 @@ enum MockOrigDst {
 
 impl<T> listen::Bind<T> for MockOrigDst
 where
-    T: Param<Keepalive> + Param<ListenAddr>,
+    T: Param<Keepalive> + Param<SocketOptions> + Param<AcceptShards> + Param<ListenAddr>,
+    T: Param<ReusePort>,
 {
     type Addrs = orig_dst::Addrs;
     type Io = tokio::net::TcpStream;
@@ -1544,6 +1544,47 @@ async fn http1_requests_without_host_have_unique_connections() {
     proxy.join_servers().await;
 }
 
+#[tokio::test]
+async fn outbound_http2_rotates_connections() {
+    let _trace = trace_init();
+
+    let srv = server::http2().route("/", "hello rotation").run().await;
+    let dstctl = controller::new();
+    let _profile = dstctl.profile_tx_default(srv.addr, "transparency.test.svc.cluster.local");
+    let dest = dstctl.destination_tx(format!(
+        "transparency.test.svc.cluster.local:{}",
+        srv.addr.port()
+    ));
+    dest.send_addr(srv.addr);
+
+    let mut env = TestEnv::default();
+    env.put(
+        app::env::ENV_OUTBOUND_HTTP2_MAX_REQUESTS_PER_CONNECTION,
+        "1".to_owned(),
+    );
+    let proxy = proxy::new()
+        .controller(dstctl.run().await)
+        .outbound(srv)
+        .run_with_test_env(env)
+        .await;
+    let client = client::http2(proxy.outbound, "transparency.test.svc.cluster.local");
+
+    // Every request must succeed while connections are rotated underneath
+    // them.
+    for _ in 0..10 {
+        assert_eq!(client.get("/").await, "hello rotation");
+    }
+
+    let outbound = proxy.outbound_server.as_ref().expect("no outbound server");
+    assert!(
+        outbound.connections() > 1,
+        "connections must be rotated; got {}",
+        outbound.connections()
+    );
+
+    proxy.join_servers().await;
+}
+
 #[tokio::test]
 async fn retry_reconnect_errors() {
     let _trace = trace_init();
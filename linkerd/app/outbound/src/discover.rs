@@ -238,6 +238,13 @@ fn policy_for_backend(
             meta: meta.clone(),
             filters: NO_OPAQ_FILTERS.clone(),
             failure_policy: Default::default(),
+            cache: None,
+            request_decompression: None,
+            request_body_limit: None,
+            streaming: None,
+            sticky: None,
+            mirror: None,
+            success_objective: None,
             request_timeout: None,
             distribution: policy::RouteDistribution::FirstAvailable(Arc::new([
                 policy::RouteBackend {
@@ -257,6 +264,13 @@ fn policy_for_backend(
                 meta: meta.clone(),
                 filters: NO_HTTP_FILTERS.clone(),
                 failure_policy: Default::default(),
+                cache: None,
+                request_decompression: None,
+                request_body_limit: None,
+                streaming: None,
+                sticky: None,
+                mirror: None,
+                success_objective: None,
                 request_timeout: None,
                 distribution: policy::RouteDistribution::FirstAvailable(Arc::new([
                     policy::RouteBackend {
@@ -16,14 +16,20 @@ use tokio::sync::watch;
 mod breaker;
 pub mod concrete;
 mod endpoint;
+mod expect_continue;
 mod handle_proxy_error_headers;
 pub mod logical;
 mod require_id_header;
 mod retry;
 mod server;
 
-pub use self::logical::{policy, profile, LogicalAddr, Routes};
 pub(crate) use self::require_id_header::IdentityRequired;
+pub use self::{
+    endpoint::PoolMetrics,
+    expect_continue::ExpectContinue,
+    logical::{policy, profile, LogicalAddr, Routes},
+    retry::RetryConfig,
+};
 pub use linkerd_app_core::proxy::http::{self as http, *};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -32,6 +38,8 @@ pub struct Http<T>(T);
 #[derive(Clone, Debug, Default)]
 pub struct HttpMetrics {
     balancer: concrete::BalancerMetrics,
+    pinning: concrete::PinMetricFamilies,
+    failover: concrete::FailoverMetricFamilies,
     http_route: policy::RouteMetrics,
     grpc_route: policy::RouteMetrics,
 }
@@ -61,10 +69,60 @@ where
             }
 
             if let Some(routes) = (mk)(&*route_rx.borrow_and_update()) {
-                if tx.send(routes).is_err() {
-                    // Drop the `tx` sender when all of its receivers are dropped.
-                    return;
-                }
+                // Routes are only published when they change so that the
+                // router is not rebuilt when unrelated configuration changes.
+                tx.send_if_modified(|current| {
+                    if *current == routes {
+                        return false;
+                    }
+                    *current = routes;
+                    true
+                });
+            }
+        }
+    });
+
+    rx
+}
+
+/// Like [`spawn_routes`], but builds routes from two discovery watches,
+/// updating whenever either changes.
+pub fn spawn_merged_routes<A, B>(
+    mut a_rx: watch::Receiver<A>,
+    mut b_rx: watch::Receiver<B>,
+    init: Routes,
+    mut mk: impl FnMut(&A, &B) -> Option<Routes> + Send + Sync + 'static,
+) -> watch::Receiver<Routes>
+where
+    A: Send + Sync + 'static,
+    B: Send + Sync + 'static,
+{
+    let (tx, rx) = watch::channel(init);
+
+    tokio::spawn(async move {
+        loop {
+            let res = tokio::select! {
+                biased;
+                _ = tx.closed() => return,
+                res = a_rx.changed() => res,
+                res = b_rx.changed() => res,
+            };
+
+            if res.is_err() {
+                // Drop the `tx` sender when either discovery sender is
+                // dropped.
+                return;
+            }
+
+            let routes = (mk)(&*a_rx.borrow_and_update(), &*b_rx.borrow_and_update());
+            if let Some(routes) = routes {
+                tx.send_if_modified(|current| {
+                    if *current == routes {
+                        return false;
+                    }
+                    *current = routes;
+                    true
+                });
             }
         }
     });
@@ -101,7 +159,7 @@ impl<T> Outbound<svc::ArcNewHttp<concrete::Endpoint<logical::Concrete<Http<T>>>>
         R::Resolution: Unpin,
     {
         self.push_http_endpoint()
-            .push_http_concrete(metrics.balancer, resolve)
+            .push_http_concrete(metrics.balancer, metrics.pinning, metrics.failover, resolve)
             .push_http_logical(metrics.http_route, metrics.grpc_route)
             .map_stack(move |config, _, stk| {
                 stk.push_new_idle_cached(config.discovery_idle_timeout)
@@ -137,14 +195,19 @@ impl HttpMetrics {
     pub fn register(registry: &mut prom::Registry) -> Self {
         let http = registry.sub_registry_with_prefix("http");
         let http_route = policy::RouteMetrics::register(http.sub_registry_with_prefix("route"));
-        let balancer =
-            concrete::BalancerMetrics::register(http.sub_registry_with_prefix("balancer"));
+        let balancer_registry = http.sub_registry_with_prefix("balancer");
+        let balancer = concrete::BalancerMetrics::register(balancer_registry);
+        let pinning = concrete::PinMetricFamilies::register(balancer_registry);
+        let failover = concrete::FailoverMetricFamilies::register(balancer_registry);
 
         let grpc = registry.sub_registry_with_prefix("grpc");
-        let grpc_route = policy::RouteMetrics::register(grpc.sub_registry_with_prefix("route"));
+        let grpc_route =
+            policy::RouteMetrics::register_grpc(grpc.sub_registry_with_prefix("route"));
 
         Self {
             balancer,
+            pinning,
+            failover,
             http_route,
             grpc_route,
         }
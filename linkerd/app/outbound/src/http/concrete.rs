@@ -21,8 +21,14 @@ use std::{fmt::Debug, net::SocketAddr, sync::Arc};
 use tracing::info_span;
 
 mod balance;
+mod failover;
+mod pin;
 
-pub use self::balance::BalancerMetrics;
+pub use self::{
+    balance::BalancerMetrics,
+    failover::{EndpointFailover, FailoverCluster, FailoverMetricFamilies},
+    pin::{EndpointPinning, InvalidPinnedEndpoint, PinMetricFamilies, PinnedEndpointNotFound},
+};
 
 /// Parameter configuring dispatcher behavior.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -60,9 +66,17 @@ impl<N> Outbound<N> {
     /// 'failfast'. While in failfast, buffered requests are failed and the
     /// service becomes unavailable so callers may choose alternate concrete
     /// services.
+    ///
+    /// When endpoint pinning is configured, authorized clients may bypass a
+    /// balancer to send requests to one of its endpoints.
+    ///
+    /// When endpoint failover is configured, balancers prefer local endpoints
+    /// and only use remote-cluster gateways while local endpoints are scarce.
     pub fn push_http_concrete<T, NSvc, R>(
         self,
         balancer_metrics: balance::BalancerMetrics,
+        pin_metrics: pin::PinMetricFamilies,
+        failover_metrics: failover::FailoverMetricFamilies,
         resolve: R,
     ) -> Outbound<svc::ArcNewCloneHttp<T>>
     where
@@ -89,6 +103,11 @@ impl<N> Outbound<N> {
             // TODO(ver) Configure this from discovery.
             let queue = config.http_request_queue;
 
+            let forward_queue = rt
+                .metrics
+                .proxy
+                .stack
+                .queue(stack_labels("http", "forward"));
             let forward = inner
                 .clone()
                 .push_on_service(
@@ -97,9 +116,20 @@ impl<N> Outbound<N> {
                         .stack
                         .layer(stack_labels("http", "forward")),
                 )
+                .push_on_service(forward_queue.dequeue_layer())
                 .push(svc::NewQueue::layer())
+                .push_on_service(forward_queue.enqueue_layer())
                 .instrument(|e: &Endpoint<T>| info_span!("forward", addr = %e.addr));
 
+            let pinned_queue = rt.metrics.proxy.stack.queue(stack_labels("http", "pinned"));
+            let pinned = inner
+                .clone()
+                .push_on_service(rt.metrics.proxy.stack.layer(stack_labels("http", "pinned")))
+                .push_on_service(pinned_queue.dequeue_layer())
+                .push(svc::NewQueue::layer())
+                .push_on_service(pinned_queue.enqueue_layer())
+                .instrument(|e: &Endpoint<T>| info_span!("pinned", addr = %e.addr));
+
             let fail = svc::ArcNewService::new(|message: Arc<str>| {
                 svc::mk(move |_| futures::future::ready(Err(DispatcherFailed(message.clone()))))
             });
@@ -109,8 +139,15 @@ impl<N> Outbound<N> {
                     config,
                     rt,
                     balancer_metrics,
+                    failover_metrics,
                     resolve,
                 ))
+                .push(pin::NewPinEndpoint::layer(
+                    config.endpoint_pinning.clone(),
+                    pin_metrics,
+                    inbound_ips.clone(),
+                    pinned.into_inner(),
+                ))
                 .check_new_clone()
                 .push_switch(Ok::<_, Infallible>, forward.into_inner())
                 .push_switch(
@@ -122,6 +159,7 @@ impl<N> Outbound<N> {
                                     ewma,
                                     parent,
                                     queue,
+                                    endpoints: Default::default(),
                                 }))
                             }
                             Dispatch::Forward(addr, metadata) => svc::Either::A(svc::Either::B({
@@ -153,6 +191,18 @@ impl<T> svc::Param<Remote<ServerAddr>> for Endpoint<T> {
     }
 }
 
+impl<T: svc::Param<ParentRef>> svc::Param<ParentRef> for Endpoint<T> {
+    fn param(&self) -> ParentRef {
+        self.parent.param()
+    }
+}
+
+impl<T: svc::Param<BackendRef>> svc::Param<BackendRef> for Endpoint<T> {
+    fn param(&self) -> BackendRef {
+        self.parent.param()
+    }
+}
+
 impl<T> svc::Param<svc::queue::Capacity> for Endpoint<T> {
     fn param(&self) -> svc::queue::Capacity {
         svc::queue::Capacity(self.queue.capacity)
@@ -321,10 +371,16 @@ impl<T> tap::Inspect for Endpoint<T> {
     }
 
     fn route_labels<B>(&self, req: &http::Request<B>) -> Option<tap::Labels> {
-        // FIXME(ver) create a dedicated extension type for route labels.
-        req.extensions()
+        // FIXME(ver) create a dedicated extension type for profile route labels.
+        let profile = req
+            .extensions()
             .get::<profiles::http::Route>()
-            .map(|r| r.labels().clone())
+            .map(|r| r.labels().clone());
+        let policy = req
+            .extensions()
+            .get::<tap::PolicyRouteLabels>()
+            .map(|tap::PolicyRouteLabels(labels)| labels.clone());
+        tap::merge_labels(profile, policy)
     }
 
     fn is_outbound<B>(&self, _: &http::Request<B>) -> bool {
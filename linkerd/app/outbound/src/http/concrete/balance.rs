@@ -1,4 +1,4 @@
-use super::Endpoint;
+use super::{failover, pin, Endpoint};
 use crate::{
     http::{self, balance, breaker},
     metrics::{BalancerMetricsParams, ConcreteLabels},
@@ -26,6 +26,7 @@ pub struct Balance<T> {
     pub ewma: balance::EwmaConfig,
     pub queue: QueueConfig,
     pub parent: T,
+    pub endpoints: pin::Endpoints,
 }
 
 /// Wraps errors encountered in this module.
@@ -83,6 +84,7 @@ where
         config: &crate::Config,
         rt: &crate::Runtime,
         metrics: BalancerMetrics,
+        failover_metrics: failover::FailoverMetricFamilies,
         resolve: R,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
@@ -104,10 +106,22 @@ where
 
         let resolve = svc::stack(resolve.into_service())
             .push_map_target(|t: Self| ConcreteAddr(t.addr))
+            .push(failover::FailoverResolve::layer(
+                config.endpoint_failover.clone(),
+            ))
+            .push(pin::RecordEndpoints::layer(
+                config.endpoint_pinning.is_some(),
+            ))
             .into_inner();
 
+        let endpoint_failover = config.endpoint_failover.clone();
+
         svc::layer::mk(move |inner: N| {
             let endpoint = svc::stack(inner)
+                .push(failover::NewClusterRequests::layer(
+                    endpoint_failover.clone(),
+                    failover_metrics.clone(),
+                ))
                 .push_map_target({
                     let inbound_ips = inbound_ips.clone();
                     move |((addr, metadata), target): ((SocketAddr, Metadata), Self)| {
@@ -0,0 +1,464 @@
+//! Fails over from local-cluster endpoints to remote-cluster gateways.
+//!
+//! A multicluster backend's resolution may include both the endpoints of the
+//! local cluster and the gateway endpoints of remote clusters, which are
+//! identified by an endpoint label. When failover is configured, a balancer
+//! only sees the local endpoints until fewer than a remote cluster's
+//! threshold of local endpoints remain, at which point that cluster's
+//! gateway endpoints are added to the balancer. They are removed again once
+//! the local endpoints recover.
+
+use super::Endpoint;
+use crate::{http, metrics::ConcreteLabels, BackendRef, ParentRef};
+use futures::{prelude::*, ready};
+use linkerd_app_core::{
+    metrics::prom::{self, encoding::*, EncodeLabelSetMut},
+    proxy::{api_resolve::Metadata, core::Update},
+    svc,
+};
+use pin_project::pin_project;
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// Configures failover from local endpoints to remote clusters.
+#[derive(Clone, Debug)]
+pub struct EndpointFailover {
+    /// The endpoint label that names a remote endpoint's cluster. Endpoints
+    /// without this label are in the local cluster.
+    pub cluster_label: String,
+
+    /// The remote clusters to which backends may fail over. Endpoints in
+    /// other remote clusters are never used.
+    pub clusters: Arc<[FailoverCluster]>,
+}
+
+/// Configures failover to a remote cluster.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailoverCluster {
+    pub name: String,
+
+    /// The cluster's endpoints are used while fewer than this many local
+    /// endpoints are available.
+    pub min_local_endpoints: usize,
+}
+
+/// Filters a balancer's resolution according to its failover policy.
+#[derive(Clone, Debug)]
+pub struct FailoverResolve<R> {
+    failover: Option<Arc<EndpointFailover>>,
+    inner: R,
+}
+
+#[pin_project]
+pub struct FailoverFuture<F> {
+    #[pin]
+    inner: F,
+    failover: Option<Arc<EndpointFailover>>,
+}
+
+#[pin_project]
+pub struct Failover<S> {
+    #[pin]
+    inner: S,
+    state: Option<State>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FailoverMetricFamilies {
+    requests: prom::Family<ClusterLabels, prom::Counter>,
+}
+
+/// Counts requests dispatched to each cluster's endpoints.
+#[derive(Clone, Debug)]
+pub struct NewClusterRequests<N> {
+    failover: Option<Arc<EndpointFailover>>,
+    metrics: FailoverMetricFamilies,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClusterRequests<S> {
+    requests: Option<prom::Counter>,
+    inner: S,
+}
+
+#[derive(Debug)]
+struct State {
+    failover: Arc<EndpointFailover>,
+    /// All endpoints in the resolution.
+    resolved: HashMap<SocketAddr, Metadata>,
+    /// The endpoints that have been published to the balancer.
+    active: HashMap<SocketAddr, Metadata>,
+    pending: VecDeque<Update<Metadata>>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct ClusterLabels {
+    concrete: ConcreteLabels,
+    cluster: Arc<str>,
+}
+
+/// The `cluster` label value of local endpoints.
+const LOCAL_CLUSTER: &str = "local";
+
+// === impl EndpointFailover ===
+
+impl EndpointFailover {
+    /// Returns the name of an endpoint's remote cluster, if it has one.
+    fn cluster(&self, metadata: &Metadata) -> Option<String> {
+        metadata.labels().get(&self.cluster_label).cloned()
+    }
+
+    /// Selects the endpoints that should be balanced: all local endpoints and
+    /// the endpoints of each remote cluster whose threshold is not met.
+    fn select(&self, resolved: &HashMap<SocketAddr, Metadata>) -> HashMap<SocketAddr, Metadata> {
+        let local = resolved
+            .values()
+            .filter(|m| self.cluster(m).is_none())
+            .count();
+        resolved
+            .iter()
+            .filter(|(_, m)| match self.cluster(m) {
+                None => true,
+                Some(cluster) => self
+                    .clusters
+                    .iter()
+                    .any(|c| c.name == cluster && local < c.min_local_endpoints),
+            })
+            .map(|(addr, m)| (*addr, m.clone()))
+            .collect()
+    }
+}
+
+// === impl FailoverResolve ===
+
+impl<R> FailoverResolve<R> {
+    pub fn layer(
+        failover: Option<EndpointFailover>,
+    ) -> impl svc::layer::Layer<R, Service = Self> + Clone {
+        let failover = failover.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            failover: failover.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, R> svc::Service<T> for FailoverResolve<R>
+where
+    R: svc::Service<T>,
+{
+    type Response = Failover<R::Response>;
+    type Error = R::Error;
+    type Future = FailoverFuture<R::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        FailoverFuture {
+            inner: self.inner.call(target),
+            failover: self.failover.clone(),
+        }
+    }
+}
+
+impl<F: TryFuture> Future for FailoverFuture<F> {
+    type Output = Result<Failover<F::Ok>, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let inner = ready!(this.inner.try_poll(cx))?;
+        let state = this.failover.take().map(|failover| State {
+            failover,
+            resolved: HashMap::default(),
+            active: HashMap::default(),
+            pending: VecDeque::default(),
+        });
+        Poll::Ready(Ok(Failover { inner, state }))
+    }
+}
+
+// === impl Failover ===
+
+impl<S, E> Stream for Failover<S>
+where
+    S: Stream<Item = Result<Update<Metadata>, E>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        let Some(state) = this.state.as_mut() else {
+            return this.inner.poll_next(cx);
+        };
+
+        loop {
+            if let Some(update) = state.pending.pop_front() {
+                return Poll::Ready(Some(Ok(update)));
+            }
+            match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Ok(update)) => state.update(update),
+                item => return Poll::Ready(item),
+            }
+        }
+    }
+}
+
+// === impl State ===
+
+impl State {
+    /// Applies a resolution update and enqueues the updates that bring the
+    /// balancer's endpoints in line with the failover policy.
+    fn update(&mut self, update: Update<Metadata>) {
+        let reset = match update {
+            Update::Reset(eps) => {
+                self.resolved = eps.into_iter().collect();
+                true
+            }
+            Update::Add(eps) => {
+                self.resolved.extend(eps);
+                false
+            }
+            Update::Remove(addrs) => {
+                for addr in &addrs {
+                    self.resolved.remove(addr);
+                }
+                false
+            }
+            Update::DoesNotExist => {
+                self.resolved.clear();
+                self.active.clear();
+                self.pending.push_back(Update::DoesNotExist);
+                return;
+            }
+        };
+
+        let selected = self.failover.select(&self.resolved);
+        if reset {
+            self.pending
+                .push_back(Update::Reset(selected.clone().into_iter().collect()));
+        } else {
+            let removed = self
+                .active
+                .keys()
+                .filter(|addr| !selected.contains_key(addr))
+                .copied()
+                .collect::<Vec<_>>();
+            if !removed.is_empty() {
+                self.pending.push_back(Update::Remove(removed));
+            }
+            let added = selected
+                .iter()
+                .filter(|(addr, m)| self.active.get(addr) != Some(m))
+                .map(|(addr, m)| (*addr, m.clone()))
+                .collect::<Vec<_>>();
+            if !added.is_empty() {
+                self.pending.push_back(Update::Add(added));
+            }
+        }
+
+        let remote = selected
+            .values()
+            .filter(|m| self.failover.cluster(m).is_some())
+            .count();
+        let was_remote = self
+            .active
+            .values()
+            .filter(|m| self.failover.cluster(m).is_some())
+            .count();
+        if remote > 0 && was_remote == 0 {
+            tracing::info!(remote_endpoints = remote, "Failing over to remote clusters");
+        } else if remote == 0 && was_remote > 0 {
+            tracing::info!("Local endpoints recovered; failed back from remote clusters");
+        }
+        self.active = selected;
+    }
+}
+
+// === impl FailoverMetricFamilies ===
+
+impl FailoverMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let requests = prom::Family::default();
+        reg.register(
+            "cluster_requests",
+            "The total number of requests dispatched to each cluster's endpoints by balancers with a failover policy",
+            requests.clone(),
+        );
+        Self { requests }
+    }
+}
+
+// === impl ClusterLabels ===
+
+impl EncodeLabelSet for ClusterLabels {
+    fn encode(&self, mut enc: LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.concrete.encode_label_set(&mut enc)?;
+        ("cluster", &*self.cluster).encode(enc.encode_label())
+    }
+}
+
+// === impl NewClusterRequests ===
+
+impl<N> NewClusterRequests<N> {
+    pub fn layer(
+        failover: Option<EndpointFailover>,
+        metrics: FailoverMetricFamilies,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let failover = failover.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            failover: failover.clone(),
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<Endpoint<T>> for NewClusterRequests<N>
+where
+    T: svc::Param<ParentRef> + svc::Param<BackendRef>,
+    N: svc::NewService<Endpoint<T>>,
+{
+    type Service = ClusterRequests<N::Service>;
+
+    fn new_service(&self, target: Endpoint<T>) -> Self::Service {
+        let requests = self.failover.as_ref().map(|failover| {
+            let cluster = failover
+                .cluster(&target.metadata)
+                .unwrap_or_else(|| LOCAL_CLUSTER.to_string());
+            self.metrics
+                .requests
+                .get_or_create(&ClusterLabels {
+                    concrete: ConcreteLabels(target.parent.param(), target.parent.param()),
+                    cluster: cluster.into(),
+                })
+                .clone()
+        });
+        ClusterRequests {
+            requests,
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl ClusterRequests ===
+
+impl<B, S> svc::Service<http::Request<B>> for ClusterRequests<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(requests) = self.requests.as_ref() {
+            requests.inc();
+        }
+        self.inner.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::proxy::api_resolve::ProtocolHint;
+
+    fn failover() -> Arc<EndpointFailover> {
+        Arc::new(EndpointFailover {
+            cluster_label: "cluster".to_string(),
+            clusters: Arc::new([
+                FailoverCluster {
+                    name: "east".to_string(),
+                    min_local_endpoints: 2,
+                },
+                FailoverCluster {
+                    name: "west".to_string(),
+                    min_local_endpoints: 1,
+                },
+            ]),
+        })
+    }
+
+    fn ep(addr: &str, cluster: Option<&str>) -> (SocketAddr, Metadata) {
+        let labels = cluster.map(|c| ("cluster".to_string(), c.to_string()));
+        let metadata = Metadata::new(labels, ProtocolHint::Unknown, None, None, None);
+        (addr.parse().unwrap(), metadata)
+    }
+
+    fn addrs(update: &Update<Metadata>) -> Vec<String> {
+        let mut addrs = match update {
+            Update::Reset(eps) | Update::Add(eps) => {
+                eps.iter().map(|(a, _)| a.to_string()).collect::<Vec<_>>()
+            }
+            Update::Remove(addrs) => addrs.iter().map(ToString::to_string).collect(),
+            Update::DoesNotExist => vec![],
+        };
+        addrs.sort();
+        addrs
+    }
+
+    #[test]
+    fn fails_over_by_cluster() {
+        let mut state = State {
+            failover: failover(),
+            resolved: HashMap::default(),
+            active: HashMap::default(),
+            pending: VecDeque::default(),
+        };
+
+        state.update(Update::Reset(vec![
+            ep("10.0.0.1:8080", None),
+            ep("10.0.0.2:8080", None),
+            ep("192.168.0.1:4143", Some("east")),
+            ep("192.168.1.1:4143", Some("west")),
+            ep("192.168.2.1:4143", Some("north")),
+        ]));
+        let update = state.pending.pop_front().unwrap();
+        assert!(matches!(update, Update::Reset(_)));
+        assert_eq!(addrs(&update), ["10.0.0.1:8080", "10.0.0.2:8080"]);
+
+        // With a single local endpoint, the east cluster is added.
+        state.update(Update::Remove(vec!["10.0.0.2:8080".parse().unwrap()]));
+        let update = state.pending.pop_front().unwrap();
+        assert!(matches!(update, Update::Remove(_)));
+        assert_eq!(addrs(&update), ["10.0.0.2:8080"]);
+        let update = state.pending.pop_front().unwrap();
+        assert!(matches!(update, Update::Add(_)));
+        assert_eq!(addrs(&update), ["192.168.0.1:4143"]);
+
+        // Without local endpoints, the west cluster is also added.
+        state.update(Update::Remove(vec!["10.0.0.1:8080".parse().unwrap()]));
+        let update = state.pending.pop_front().unwrap();
+        assert_eq!(addrs(&update), ["10.0.0.1:8080"]);
+        let update = state.pending.pop_front().unwrap();
+        assert_eq!(addrs(&update), ["192.168.1.1:4143"]);
+        assert!(state.pending.is_empty());
+
+        // Once the local endpoints recover, the remote clusters are removed.
+        state.update(Update::Add(vec![
+            ep("10.0.0.3:8080", None),
+            ep("10.0.0.4:8080", None),
+        ]));
+        let update = state.pending.pop_front().unwrap();
+        assert!(matches!(update, Update::Remove(_)));
+        assert_eq!(addrs(&update), ["192.168.0.1:4143", "192.168.1.1:4143"]);
+        let update = state.pending.pop_front().unwrap();
+        assert!(matches!(update, Update::Add(_)));
+        assert_eq!(addrs(&update), ["10.0.0.3:8080", "10.0.0.4:8080"]);
+        assert!(state.pending.is_empty());
+    }
+}
@@ -0,0 +1,549 @@
+//! Routes requests to an endpoint named in a trusted request header.
+//!
+//! Endpoint pinning supports debugging and canary verification workflows, in
+//! which a client needs to reach a specific replica of a service. Only clients
+//! permitted by the configured authorization may pin requests, and requests may
+//! only be pinned
+//! to endpoints in the balancer's current resolution, so the header cannot be
+//! used to reach arbitrary addresses. Pinned requests bypass the balancer and
+//! are dispatched through a dedicated endpoint stack.
+
+use super::{balance::Balance, Endpoint};
+use crate::{http, metrics::ConcreteLabels, BackendRef, ParentRef};
+use futures::{future, prelude::*, ready};
+use linkerd_app_core::{
+    metrics::prom::{self, encoding::*, EncodeLabelSetMut},
+    proxy::{api_resolve::Metadata, core::Update},
+    svc::{self, ServiceExt},
+    transport::addrs::*,
+    Error,
+};
+use linkerd_proxy_server_policy::{Authentication, Authorization};
+use parking_lot::{Mutex, RwLock};
+use pin_project::pin_project;
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::SystemTime,
+};
+
+/// Configures header-driven endpoint pinning.
+#[derive(Clone, Debug)]
+pub struct EndpointPinning {
+    /// The request header that names an endpoint, either as an IP address or
+    /// as a socket address.
+    pub header: http::HeaderName,
+
+    /// Authorizes the clients that may pin requests. The header is ignored on
+    /// requests from other clients, which are load balanced.
+    ///
+    /// Outbound clients are not authenticated, so only unauthenticated
+    /// authorizations permit pinning.
+    pub authorization: Authorization,
+}
+
+/// The endpoints in a balancer's most recent resolution.
+#[derive(Clone, Debug, Default)]
+pub struct Endpoints(Arc<RwLock<HashMap<SocketAddr, Metadata>>>);
+
+/// Records a balancer's resolution updates into its [`Endpoints`].
+#[derive(Clone, Debug)]
+pub struct RecordEndpoints<R> {
+    enabled: bool,
+    inner: R,
+}
+
+#[pin_project]
+pub struct RecordFuture<F> {
+    #[pin]
+    inner: F,
+    endpoints: Option<Endpoints>,
+}
+
+#[pin_project]
+pub struct Recorded<S> {
+    #[pin]
+    inner: S,
+    endpoints: Option<Endpoints>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PinMetricFamilies {
+    requests: prom::Family<PinLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewPinEndpoint<E, N> {
+    pinning: Option<Arc<EndpointPinning>>,
+    metrics: PinMetricFamilies,
+    inbound_ips: Arc<HashSet<IpAddr>>,
+    new_endpoint: E,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct PinEndpoint<T, E, ES, S> {
+    pinning: Option<Pinning>,
+    endpoints: Endpoints,
+    target: Balance<T>,
+    inbound_ips: Arc<HashSet<IpAddr>>,
+    new_endpoint: E,
+    pinned: Arc<Mutex<HashMap<SocketAddr, (Metadata, ES)>>>,
+    inner: S,
+}
+
+#[derive(Clone, Debug)]
+struct Pinning {
+    config: Arc<EndpointPinning>,
+    metrics: PinMetrics,
+}
+
+#[derive(Clone, Debug)]
+struct PinMetrics {
+    pinned: prom::Counter,
+    denied: prom::Counter,
+    invalid: prom::Counter,
+    not_found: prom::Counter,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct PinLabels {
+    concrete: ConcreteLabels,
+    result: PinResult,
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum PinResult {
+    Pinned,
+    Denied,
+    Invalid,
+    NotFound,
+}
+
+/// Identifies the endpoint to which a request is pinned.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PinnedAddr {
+    Ip(IpAddr),
+    Addr(SocketAddr),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid pinned endpoint")]
+pub struct InvalidPinnedEndpoint(());
+
+#[derive(Debug, thiserror::Error)]
+#[error("pinned endpoint {0} is not a resolved endpoint of the backend")]
+pub struct PinnedEndpointNotFound(String);
+
+type BoxFuture = Pin<Box<dyn Future<Output = Result<http::Response<http::BoxBody>, Error>> + Send>>;
+
+// === impl EndpointPinning ===
+
+impl EndpointPinning {
+    /// Returns true if the client is authorized to pin requests at `now`.
+    fn is_authorized(&self, client: Option<SocketAddr>, now: SystemTime) -> bool {
+        let Some(client) = client else {
+            return false;
+        };
+        let authz = &self.authorization;
+        matches!(authz.authentication, Authentication::Unauthenticated)
+            && authz.networks.iter().any(|n| n.contains(&client.ip()))
+            && authz.is_valid_at(now)
+    }
+}
+
+// === impl Endpoints ===
+
+impl Endpoints {
+    fn update(&self, update: &Update<Metadata>) {
+        let mut endpoints = self.0.write();
+        match update {
+            Update::Reset(eps) => *endpoints = eps.iter().cloned().collect(),
+            Update::Add(eps) => endpoints.extend(eps.iter().cloned()),
+            Update::Remove(addrs) => {
+                for addr in addrs {
+                    endpoints.remove(addr);
+                }
+            }
+            Update::DoesNotExist => endpoints.clear(),
+        }
+    }
+
+    /// Finds a resolved endpoint. When only an IP address is specified, the
+    /// endpoint with the lowest port on that address is selected.
+    fn find(&self, pin: PinnedAddr) -> Option<(SocketAddr, Metadata)> {
+        let endpoints = self.0.read();
+        match pin {
+            PinnedAddr::Addr(addr) => endpoints.get(&addr).map(|m| (addr, m.clone())),
+            PinnedAddr::Ip(ip) => endpoints
+                .iter()
+                .filter(|(addr, _)| addr.ip() == ip)
+                .min_by_key(|(addr, _)| addr.port())
+                .map(|(addr, m)| (*addr, m.clone())),
+        }
+    }
+}
+
+/// Balancer targets are compared by their other fields; each is created with
+/// its own set of endpoints.
+impl PartialEq for Endpoints {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Endpoints {}
+
+// === impl RecordEndpoints ===
+
+impl<R> RecordEndpoints<R> {
+    pub fn layer(enabled: bool) -> impl svc::layer::Layer<R, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self { enabled, inner })
+    }
+}
+
+impl<T, R> svc::Service<Balance<T>> for RecordEndpoints<R>
+where
+    R: svc::Service<Balance<T>>,
+{
+    type Response = Recorded<R::Response>;
+    type Error = R::Error;
+    type Future = RecordFuture<R::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: Balance<T>) -> Self::Future {
+        let endpoints = self.enabled.then(|| target.endpoints.clone());
+        RecordFuture {
+            inner: self.inner.call(target),
+            endpoints,
+        }
+    }
+}
+
+impl<F: TryFuture> Future for RecordFuture<F> {
+    type Output = Result<Recorded<F::Ok>, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let inner = ready!(this.inner.try_poll(cx))?;
+        Poll::Ready(Ok(Recorded {
+            inner,
+            endpoints: this.endpoints.take(),
+        }))
+    }
+}
+
+impl<S, E> Stream for Recorded<S>
+where
+    S: Stream<Item = Result<Update<Metadata>, E>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = ready!(this.inner.poll_next(cx));
+        if let (Some(endpoints), Some(Ok(update))) = (this.endpoints.as_ref(), item.as_ref()) {
+            endpoints.update(update);
+        }
+        Poll::Ready(item)
+    }
+}
+
+// === impl PinMetricFamilies ===
+
+impl PinMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let requests = prom::Family::default();
+        reg.register(
+            "pinned_requests",
+            "The total number of requests that named an endpoint in the endpoint pinning header",
+            requests.clone(),
+        );
+        Self { requests }
+    }
+
+    fn metrics(&self, concrete: ConcreteLabels) -> PinMetrics {
+        let counter = |result| {
+            self.requests
+                .get_or_create(&PinLabels {
+                    concrete: concrete.clone(),
+                    result,
+                })
+                .clone()
+        };
+        PinMetrics {
+            pinned: counter(PinResult::Pinned),
+            denied: counter(PinResult::Denied),
+            invalid: counter(PinResult::Invalid),
+            not_found: counter(PinResult::NotFound),
+        }
+    }
+}
+
+// === impl PinLabels ===
+
+impl EncodeLabelSet for PinLabels {
+    fn encode(&self, mut enc: LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.concrete.encode_label_set(&mut enc)?;
+        ("result", self.result).encode(enc.encode_label())
+    }
+}
+
+// === impl NewPinEndpoint ===
+
+impl<E, N> NewPinEndpoint<E, N> {
+    pub fn layer(
+        pinning: Option<EndpointPinning>,
+        metrics: PinMetricFamilies,
+        inbound_ips: Arc<HashSet<IpAddr>>,
+        new_endpoint: E,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone
+    where
+        E: Clone,
+    {
+        let pinning = pinning.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            pinning: pinning.clone(),
+            metrics: metrics.clone(),
+            inbound_ips: inbound_ips.clone(),
+            new_endpoint: new_endpoint.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, E, N> svc::NewService<Balance<T>> for NewPinEndpoint<E, N>
+where
+    T: svc::Param<ParentRef> + svc::Param<BackendRef> + Clone,
+    E: svc::NewService<Endpoint<T>> + Clone,
+    N: svc::NewService<Balance<T>>,
+{
+    type Service = PinEndpoint<T, E, E::Service, N::Service>;
+
+    fn new_service(&self, target: Balance<T>) -> Self::Service {
+        let pinning = self.pinning.clone().map(|config| Pinning {
+            config,
+            metrics: self
+                .metrics
+                .metrics(ConcreteLabels(target.parent.param(), target.parent.param())),
+        });
+        PinEndpoint {
+            pinning,
+            endpoints: target.endpoints.clone(),
+            inbound_ips: self.inbound_ips.clone(),
+            new_endpoint: self.new_endpoint.clone(),
+            pinned: Default::default(),
+            inner: self.inner.new_service(target.clone()),
+            target,
+        }
+    }
+}
+
+// === impl PinEndpoint ===
+
+impl<T, E, ES, S> PinEndpoint<T, E, ES, S>
+where
+    T: Clone,
+    E: svc::NewService<Endpoint<T>, Service = ES>,
+    ES: Clone,
+{
+    /// Returns the service for a pinned endpoint, building it if necessary.
+    fn endpoint(&self, addr: SocketAddr, metadata: Metadata) -> ES {
+        let mut pinned = self.pinned.lock();
+
+        // Drop the services of endpoints that are no longer resolved or have
+        // been updated.
+        {
+            let endpoints = self.endpoints.0.read();
+            pinned.retain(|addr, (metadata, _)| endpoints.get(addr) == Some(metadata));
+        }
+
+        let (_, svc) = pinned.entry(addr).or_insert_with(|| {
+            let endpoint = Endpoint {
+                addr: Remote(ServerAddr(addr)),
+                is_local: self.inbound_ips.contains(&addr.ip()),
+                metadata: metadata.clone(),
+                parent: self.target.parent.clone(),
+                queue: self.target.queue,
+                close_server_connection_on_remote_proxy_error: false,
+            };
+            (metadata, self.new_endpoint.new_service(endpoint))
+        });
+        svc.clone()
+    }
+}
+
+impl<T, E, ES, S> svc::Service<http::Request<http::BoxBody>> for PinEndpoint<T, E, ES, S>
+where
+    T: Clone,
+    E: svc::NewService<Endpoint<T>, Service = ES>,
+    ES: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    ES: Clone + Send + 'static,
+    ES::Error: Into<Error>,
+    ES::Future: Send,
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::Either<S::Future, BoxFuture>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        let Some(Pinning { config, metrics }) = self.pinning.as_ref() else {
+            return future::Either::Left(self.inner.call(req));
+        };
+        let Some(value) = req.headers_mut().remove(&config.header) else {
+            return future::Either::Left(self.inner.call(req));
+        };
+
+        let client = req.extensions().get::<http::ClientHandle>().map(|c| c.addr);
+        if !config.is_authorized(client, SystemTime::now()) {
+            tracing::debug!(
+                ?client,
+                header = %config.header,
+                authz = %config.authorization.meta.name(),
+                "Client is not authorized to pin endpoints",
+            );
+            metrics.denied.inc();
+            return future::Either::Left(self.inner.call(req));
+        }
+
+        let Some(pin) = parse_pinned_addr(&value) else {
+            tracing::debug!(?value, "Invalid pinned endpoint");
+            metrics.invalid.inc();
+            return future::Either::Right(fail(InvalidPinnedEndpoint(())));
+        };
+        let Some((addr, metadata)) = self.endpoints.find(pin) else {
+            tracing::debug!(?pin, "Pinned endpoint not found");
+            metrics.not_found.inc();
+            return future::Either::Right(fail(PinnedEndpointNotFound(pin.to_string())));
+        };
+
+        tracing::debug!(%addr, "Pinning request to endpoint");
+        metrics.pinned.inc();
+        let svc = self.endpoint(addr, metadata);
+        future::Either::Right(Box::pin(svc.oneshot(req).err_into::<Error>()))
+    }
+}
+
+fn fail(error: impl Into<Error>) -> BoxFuture {
+    let rsp: Result<http::Response<http::BoxBody>, Error> = Err(error.into());
+    Box::pin(future::ready(rsp))
+}
+
+// === impl PinnedAddr ===
+
+fn parse_pinned_addr(value: &http::HeaderValue) -> Option<PinnedAddr> {
+    let value = value.to_str().ok()?.trim();
+    if let Ok(addr) = value.parse() {
+        return Some(PinnedAddr::Addr(addr));
+    }
+    value.parse().ok().map(PinnedAddr::Ip)
+}
+
+impl std::fmt::Display for PinnedAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ip(ip) => ip.fmt(f),
+            Self::Addr(addr) => addr.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(value: &'static str) -> Option<PinnedAddr> {
+        parse_pinned_addr(&http::HeaderValue::from_static(value))
+    }
+
+    #[test]
+    fn parses_pins() {
+        assert_eq!(pin("10.1.2.3"), Some(PinnedAddr::Ip([10, 1, 2, 3].into())));
+        assert_eq!(
+            pin(" 10.1.2.3:8080 "),
+            Some(PinnedAddr::Addr(([10, 1, 2, 3], 8080).into()))
+        );
+        assert_eq!(
+            pin("[fd00::1]:8080").map(|p| p.to_string()).as_deref(),
+            Some("[fd00::1]:8080")
+        );
+        assert_eq!(pin("pod-0"), None);
+    }
+
+    #[test]
+    fn finds_resolved_endpoints() {
+        let endpoints = Endpoints::default();
+        let ep = |addr: &str| (addr.parse::<SocketAddr>().unwrap(), Metadata::default());
+        endpoints.update(&Update::Reset(vec![
+            ep("10.1.2.3:8080"),
+            ep("10.1.2.3:9090"),
+            ep("10.1.2.4:8080"),
+        ]));
+        endpoints.update(&Update::Remove(vec!["10.1.2.4:8080".parse().unwrap()]));
+
+        let find = |p| {
+            endpoints
+                .find(pin(p).unwrap())
+                .map(|(addr, _)| addr.to_string())
+        };
+        assert_eq!(find("10.1.2.3").as_deref(), Some("10.1.2.3:8080"));
+        assert_eq!(find("10.1.2.3:9090").as_deref(), Some("10.1.2.3:9090"));
+        assert_eq!(find("10.1.2.3:7070"), None);
+        assert_eq!(find("10.1.2.4"), None);
+    }
+
+    #[test]
+    fn authorizes_clients() {
+        use linkerd_proxy_server_policy::{authz::TimeWindow, Meta};
+        use std::time::Duration;
+
+        let now = SystemTime::now();
+        let mut pinning = EndpointPinning {
+            header: http::HeaderName::from_static("l5d-pin-endpoint"),
+            authorization: Authorization {
+                networks: vec!["10.0.0.0/8"
+                    .parse::<linkerd_app_core::IpNet>()
+                    .unwrap()
+                    .into()],
+                authentication: Authentication::Unauthenticated,
+                meta: Arc::new(Meta::Default {
+                    name: "endpoint-pinning".into(),
+                }),
+                local_addrs: vec![],
+                validity: vec![],
+            },
+        };
+        let client = Some(([10, 1, 2, 3], 5000).into());
+        assert!(pinning.is_authorized(client, now));
+        assert!(!pinning.is_authorized(Some(([192, 168, 1, 1], 5000).into()), now));
+        assert!(!pinning.is_authorized(None, now));
+
+        pinning.authorization.validity = vec![TimeWindow {
+            not_before: None,
+            not_after: Some(now),
+        }];
+        assert!(!pinning.is_authorized(client, now));
+        assert!(pinning.is_authorized(client, now - Duration::from_secs(1)));
+
+        pinning.authorization.validity = vec![];
+        pinning.authorization.authentication = Authentication::TlsUnauthenticated;
+        assert!(!pinning.is_authorized(client, now));
+    }
+}
@@ -4,7 +4,7 @@ use super::{
     handle_proxy_error_headers::{self, NewHandleProxyErrorHeaders},
     NewRequireIdentity,
 };
-use crate::{tcp::tagged_transport, Outbound};
+use crate::{tcp::tagged_transport, BackendRef, Outbound, ParentRef};
 use linkerd_app_core::{
     classify, config, errors, http_tracing, metrics,
     proxy::{api_resolve::ProtocolHint, http, tap},
@@ -15,9 +15,12 @@ use linkerd_app_core::{
     Error, Result, CANONICAL_DST_HEADER,
 };
 
+mod pool;
 #[cfg(test)]
 mod tests;
 
+pub use self::pool::PoolMetrics;
+
 #[derive(Clone, Debug)]
 pub struct Connect<T> {
     version: http::Version,
@@ -38,10 +41,12 @@ struct ClientRescue {
 }
 
 impl<C> Outbound<C> {
-    pub fn push_http_tcp_client<T, B>(self) -> Outbound<svc::ArcNewHttp<T, B>>
+    pub fn push_http_tcp_client<T, B>(self, pool: PoolMetrics) -> Outbound<svc::ArcNewHttp<T, B>>
     where
         // Http endpoint target.
         T: svc::Param<http::client::Settings>,
+        T: svc::Param<ParentRef>,
+        T: svc::Param<BackendRef>,
         T: Clone + Send + Sync + 'static,
         // Http endpoint body.
         B: http::HttpBody<Error = Error> + std::fmt::Debug + Default + Send + 'static,
@@ -64,6 +69,7 @@ impl<C> Outbound<C> {
             // HTTP/1.x fallback is supported as needed.
             svc::stack(inner.into_inner().into_service())
                 .check_service::<Connect<T>>()
+                .push(pool::TrackPool::layer(pool))
                 .push_map_target(|(version, inner)| Connect { version, inner })
                 .push(http::client::layer(h1_settings, h2_settings))
                 .push_on_service(svc::MapErr::layer_boxed())
@@ -114,6 +120,11 @@ impl<T> Outbound<svc::ArcNewHttp<T, http::BoxBody>> {
                 // double-counted--i.e., endpoint metrics track these responses and error metrics
                 // track proxy errors that occur higher in the stack.
                 .push(ClientRescue::layer(config.emit_headers))
+                // Deducts the time spent in the proxy from the request's
+                // deadline header.
+                .push_on_service(http::PropagateDeadline::layer(
+                    config.proxy.deadline_header.clone(),
+                ))
                 .push_on_service(http::BoxRequest::layer())
                 .push(tap::NewTapHttp::layer(rt.tap.clone()))
                 .push(
@@ -170,10 +181,12 @@ impl errors::HttpRescue<Error> for ClientRescue {
             return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
         }
         if errors::is_caused_by::<std::io::Error>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(error)
+                .with_code(errors::ErrorCode::ConnectError));
         }
         if errors::is_caused_by::<errors::ConnectTimeout>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error)
+                .with_code(errors::ErrorCode::ConnectTimeout));
         }
 
         Err(error)
@@ -0,0 +1,125 @@
+//! Tracks the connections held by HTTP/1 client connection pools.
+
+use super::Connect;
+use crate::{http, metrics::ConcreteLabels, BackendRef, ParentRef};
+use futures::prelude::*;
+use linkerd_app_core::{
+    io,
+    metrics::prom,
+    svc::{self, layer},
+    Error,
+};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Gauges the number of HTTP/1 connections held in each backend's client
+/// connection pools.
+#[derive(Clone, Debug, Default)]
+pub struct PoolMetrics(prom::Family<ConcreteLabels, prom::Gauge>);
+
+/// Wraps a connector so that the HTTP/1 connections it establishes are
+/// counted while they remain open.
+#[derive(Clone, Debug)]
+pub(super) struct TrackPool<C> {
+    inner: C,
+    metrics: PoolMetrics,
+}
+
+/// Decrements a backend's pool gauge when its connection is closed.
+#[derive(Debug)]
+pub(super) struct PooledConnection(Option<prom::Gauge>);
+
+// === impl PoolMetrics ===
+
+impl PoolMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let connections = prom::Family::default();
+        registry.register(
+            "connections",
+            "The number of HTTP/1 connections held in client connection pools",
+            connections.clone(),
+        );
+        Self(connections)
+    }
+
+    fn connection<T>(&self, target: &T) -> PooledConnection
+    where
+        T: svc::Param<ParentRef> + svc::Param<BackendRef>,
+    {
+        let labels = ConcreteLabels(target.param(), target.param());
+        let gauge = self.0.get_or_create(&labels).clone();
+        gauge.inc();
+        PooledConnection(Some(gauge))
+    }
+}
+
+// === impl TrackPool ===
+
+impl<C> TrackPool<C> {
+    pub(super) fn layer(metrics: PoolMetrics) -> impl layer::Layer<C, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            metrics: metrics.clone(),
+        })
+    }
+}
+
+impl<C, T> svc::Service<Connect<T>> for TrackPool<C>
+where
+    T: svc::Param<ParentRef> + svc::Param<BackendRef>,
+    C: svc::MakeConnection<Connect<T>>,
+    C::Connection: Send + 'static,
+    C::Metadata: Send + 'static,
+    C::Error: Into<Error>,
+    C::Future: Send + 'static,
+{
+    type Response = (io::SensorIo<C::Connection, PooledConnection>, C::Metadata);
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Error>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, target: Connect<T>) -> Self::Future {
+        // Only HTTP/1 connections are pooled; HTTP/2 connections are
+        // multiplexed by a single client.
+        let pooled = match target.version {
+            http::Version::Http1 => self.metrics.connection(&target.inner),
+            http::Version::H2 => PooledConnection(None),
+        };
+        Box::pin(
+            self.inner
+                .connect(target)
+                .err_into::<Error>()
+                .map_ok(move |(io, meta)| (io::SensorIo::new(io, pooled), meta)),
+        )
+    }
+}
+
+// === impl PooledConnection ===
+
+impl io::Sensor for PooledConnection {
+    fn record_read(&mut self, _: usize) {}
+
+    fn record_write(&mut self, _: usize) {}
+
+    fn record_close(&mut self, _: Option<io::Errno>) {
+        if let Some(gauge) = self.0.take() {
+            gauge.dec();
+        }
+    }
+
+    fn record_error<T>(&mut self, op: io::Poll<T>) -> io::Poll<T> {
+        op
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        io::Sensor::record_close(self, None);
+    }
+}
@@ -1,13 +1,20 @@
 use super::*;
-use crate::{http, tcp, test_util::*};
+use crate::{http, policy, tcp, test_util::*};
 use ::http::header::{CONNECTION, UPGRADE};
 use linkerd_app_core::{
     io,
+    metrics::prom,
     proxy::api_resolve::ProtocolHint,
-    svc::{NewService, ServiceExt},
+    svc::{NewService, Service, ServiceExt},
     Infallible,
 };
-use std::net::SocketAddr;
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 static WAS_ORIG_PROTO: &str = "request-orig-proto";
 
@@ -25,7 +32,7 @@ async fn http11_forward() {
     let (rt, _shutdown) = runtime();
     let stack = Outbound::new(default_config(), rt)
         .with_stack(connect)
-        .push_http_tcp_client()
+        .push_http_tcp_client(Default::default())
         .push_http_endpoint()
         .into_stack()
         .push(classify::NewClassify::layer_default())
@@ -48,6 +55,127 @@ async fn http11_forward() {
     assert!(rsp.headers().get(WAS_ORIG_PROTO).is_none());
 }
 
+/// Tests that HTTP/1 connections are retired from the pool once they have
+/// served the configured maximum number of requests.
+#[tokio::test(flavor = "current_thread")]
+async fn http11_max_requests_per_connection() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), 2041);
+
+    let connects = Arc::new(AtomicUsize::new(0));
+    let connect = support::connect().endpoint_fn_boxed(addr, {
+        let connects = connects.clone();
+        move |_: _| {
+            connects.fetch_add(1, Ordering::SeqCst);
+            serve(::http::Version::HTTP_11)
+        }
+    });
+
+    let mut registry = prom::Registry::default();
+    let pool = http::PoolMetrics::register(&mut registry);
+
+    let mut config = default_config();
+    config.proxy.connect.h1_settings.max_requests_per_connection = Some(1);
+
+    // Build the outbound server
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(connect)
+        .push_http_tcp_client(pool)
+        .push_http_endpoint()
+        .into_stack()
+        .push(classify::NewClassify::layer_default())
+        .into_inner();
+
+    let mut svc = stack.new_service(Endpoint {
+        addr: Remote(ServerAddr(addr)),
+        version: http::Version::Http1,
+        hint: ProtocolHint::Unknown,
+    });
+
+    for _ in 0..2 {
+        let req = http::Request::builder()
+            .version(::http::Version::HTTP_11)
+            .uri("http://foo.example.com")
+            .header(::http::header::HOST, "foo.example.com")
+            .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+            .body(http::BoxBody::default())
+            .unwrap();
+        let rsp = svc.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(rsp.status(), http::StatusCode::NO_CONTENT);
+        drop(rsp);
+        // Let the connection be returned to the pool.
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+
+    let mut metrics = String::new();
+    prom::encoding::text::encode(&mut metrics, &registry).unwrap();
+    // The retired connection has been closed, so only the second connection
+    // remains in the pool.
+    assert!(
+        metrics
+            .lines()
+            .any(|l| l.starts_with("connections{parent_group") && l.ends_with(" 1")),
+        "pooled connections must be gauged by backend: {metrics}"
+    );
+}
+
+/// Tests that HTTP/1 connections are established before requests are
+/// dispatched and that warm connections are replaced once they are used.
+#[tokio::test(flavor = "current_thread")]
+async fn http11_warm_connections() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), 2041);
+
+    let connects = Arc::new(AtomicUsize::new(0));
+    let connect = support::connect().endpoint_fn_boxed(addr, {
+        let connects = connects.clone();
+        move |_: _| {
+            connects.fetch_add(1, Ordering::SeqCst);
+            serve(::http::Version::HTTP_11)
+        }
+    });
+
+    let mut config = default_config();
+    config.proxy.connect.h1_settings.warm_connections = 2;
+
+    // Build the outbound server
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(connect)
+        .push_http_tcp_client(Default::default())
+        .push_http_endpoint()
+        .into_stack()
+        .push(classify::NewClassify::layer_default())
+        .into_inner();
+
+    let mut svc = stack.new_service(Endpoint {
+        addr: Remote(ServerAddr(addr)),
+        version: http::Version::Http1,
+        hint: ProtocolHint::Unknown,
+    });
+    svc.ready().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+
+    let req = http::Request::builder()
+        .version(::http::Version::HTTP_11)
+        .uri("http://foo.example.com")
+        .header(::http::header::HOST, "foo.example.com")
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+        .body(http::BoxBody::default())
+        .unwrap();
+    let rsp = svc.call(req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::NO_CONTENT);
+
+    // The warm connection that served the request is replaced.
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    assert_eq!(connects.load(Ordering::SeqCst), 3);
+}
+
 /// Tests that the the HTTP endpoint stack forwards connections without HTTP upgrading.
 #[tokio::test(flavor = "current_thread")]
 async fn http2_forward() {
@@ -61,7 +189,7 @@ async fn http2_forward() {
     let (rt, _shutdown) = runtime();
     let stack = Outbound::new(default_config(), rt)
         .with_stack(connect)
-        .push_http_tcp_client()
+        .push_http_tcp_client(Default::default())
         .push_http_endpoint::<http::BoxBody>()
         .into_stack()
         .push(classify::NewClassify::layer_default())
@@ -99,7 +227,7 @@ async fn orig_proto_upgrade() {
     let (rt, _shutdown) = runtime();
     let stack = Outbound::new(default_config(), rt)
         .with_stack(connect)
-        .push_http_tcp_client()
+        .push_http_tcp_client(Default::default())
         .push_http_endpoint::<http::BoxBody>()
         .into_stack()
         .push(classify::NewClassify::layer_default())
@@ -149,7 +277,7 @@ async fn orig_proto_skipped_on_http_upgrade() {
     let drain = rt.drain.clone();
     let stack = Outbound::new(default_config(), rt)
         .with_stack(connect)
-        .push_http_tcp_client()
+        .push_http_tcp_client(Default::default())
         .push_http_endpoint::<http::BoxBody>()
         .into_stack()
         .push(classify::NewClassify::layer_default())
@@ -197,7 +325,7 @@ async fn orig_proto_http2_noop() {
     let (rt, _shutdown) = runtime();
     let stack = Outbound::new(default_config(), rt)
         .with_stack(connect)
-        .push_http_tcp_client()
+        .push_http_tcp_client(Default::default())
         .push_http_endpoint::<http::BoxBody>()
         .into_stack()
         .push(classify::NewClassify::layer_default())
@@ -330,6 +458,18 @@ impl svc::Param<http::client::Settings> for Endpoint {
     }
 }
 
+impl svc::Param<ParentRef> for Endpoint {
+    fn param(&self) -> ParentRef {
+        ParentRef(policy::Meta::new_default("parent"))
+    }
+}
+
+impl svc::Param<BackendRef> for Endpoint {
+    fn param(&self) -> BackendRef {
+        BackendRef(policy::Meta::new_default("backend"))
+    }
+}
+
 impl svc::Param<ProtocolHint> for Endpoint {
     fn param(&self) -> ProtocolHint {
         self.hint
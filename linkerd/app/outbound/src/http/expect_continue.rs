@@ -0,0 +1,209 @@
+//! Handles requests that carry an `Expect: 100-continue` header.
+//!
+//! Such a client waits for a `100 Continue` response before it sends its
+//! request body. The proxy's HTTP/1 server sends this response once the
+//! request body is first read, which ordinarily happens when the request is
+//! forwarded to its server. Requests that the proxy rejects before forwarding
+//! them, e.g. because they exceed a route's body size limit, are therefore
+//! answered before their clients send their bodies.
+
+use linkerd_app_core::{
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Configures how the proxy treats requests that expect a `100 Continue`
+/// response.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ExpectContinue {
+    /// Forwards the expectation to the server. The client is told to continue
+    /// once the proxy begins to forward its request body.
+    #[default]
+    PassThrough,
+
+    /// Answers the expectation as soon as the request is received, without
+    /// forwarding it to the server. Requests that exceed route limits fail
+    /// only after their clients have begun to send their bodies.
+    Answer,
+
+    /// Removes the expectation from requests before they are forwarded. The
+    /// client is told to continue once the proxy begins to forward its request
+    /// body.
+    Strip,
+}
+
+#[derive(Clone, Debug)]
+pub struct HandleExpectContinue<S> {
+    mode: ExpectContinue,
+    inner: S,
+}
+
+/// A request body that was read once when the request was received, so that
+/// its client was told to continue.
+#[pin_project]
+struct ContinuedBody {
+    #[pin]
+    inner: http::BoxBody,
+    first: Option<Option<Result<<http::BoxBody as HttpBody>::Data, Error>>>,
+}
+
+// === impl HandleExpectContinue ===
+
+impl<S> HandleExpectContinue<S> {
+    pub fn layer(mode: ExpectContinue) -> impl svc::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self { mode, inner })
+    }
+}
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for HandleExpectContinue<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        if self.mode == ExpectContinue::PassThrough || !expects_continue(req.headers()) {
+            return self.inner.call(req);
+        }
+
+        req.headers_mut().remove(http::header::EXPECT);
+        if self.mode == ExpectContinue::Answer {
+            tracing::trace!("Answering 100-continue expectation");
+            req = req.map(|inner| http::BoxBody::new(ContinuedBody::new(inner)));
+        }
+        self.inner.call(req)
+    }
+}
+
+fn expects_continue(headers: &::http::HeaderMap) -> bool {
+    headers
+        .get(http::header::EXPECT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().eq_ignore_ascii_case("100-continue"))
+        .unwrap_or(false)
+}
+
+// === impl ContinuedBody ===
+
+impl ContinuedBody {
+    /// Reads the body once, which prompts the server to tell the client to
+    /// continue. Any data that is read is buffered until the body is polled.
+    fn new(mut inner: http::BoxBody) -> Self {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let first = match Pin::new(&mut inner).poll_data(&mut cx) {
+            Poll::Ready(first) => Some(first),
+            Poll::Pending => None,
+        };
+        Self { inner, first }
+    }
+}
+
+impl HttpBody for ContinuedBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    fn is_end_stream(&self) -> bool {
+        match self.first {
+            Some(Some(_)) => false,
+            Some(None) => true,
+            None => self.inner.is_end_stream(),
+        }
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        if let Some(first) = this.first.take() {
+            return Poll::Ready(first);
+        }
+        this.inner.poll_data(cx)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<::http::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        let hint = self.inner.size_hint();
+        let buffered = match self.first {
+            Some(Some(Ok(ref data))) => bytes::Buf::remaining(data) as u64,
+            _ => return hint,
+        };
+        let mut sized = http_body::SizeHint::new();
+        sized.set_lower(hint.lower() + buffered);
+        if let Some(upper) = hint.upper() {
+            sized.set_upper(upper + buffered);
+        }
+        sized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::svc::ServiceExt;
+
+    async fn handle(
+        mode: ExpectContinue,
+        req: http::Request<http::BoxBody>,
+    ) -> http::Request<http::BoxBody> {
+        let inner =
+            svc::mk(|req: http::Request<http::BoxBody>| futures::future::ok::<_, Error>(req));
+        HandleExpectContinue { mode, inner }
+            .oneshot(req)
+            .await
+            .unwrap()
+    }
+
+    fn request(body: http::BoxBody) -> http::Request<http::BoxBody> {
+        http::Request::builder()
+            .header(http::header::EXPECT, "100-continue")
+            .body(body)
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn pass_through() {
+        let req = handle(ExpectContinue::PassThrough, request(Default::default())).await;
+        assert_eq!(
+            req.headers().get(http::header::EXPECT).unwrap(),
+            "100-continue"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn strip() {
+        let req = handle(ExpectContinue::Strip, request(Default::default())).await;
+        assert!(req.headers().get(http::header::EXPECT).is_none());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn answer_reads_body() {
+        let (mut tx, body) = hyper::Body::channel();
+        tx.send_data("hello".into()).await.unwrap();
+        drop(tx);
+
+        let req = handle(ExpectContinue::Answer, request(http::BoxBody::new(body))).await;
+        assert!(req.headers().get(http::header::EXPECT).is_none());
+        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        assert_eq!(body, "hello");
+    }
+}
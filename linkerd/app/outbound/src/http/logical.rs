@@ -1,6 +1,6 @@
 //! A stack that routes HTTP requests to concrete backends.
 
-use super::concrete;
+use super::{concrete, retry};
 use crate::{BackendRef, EndpointRef, Outbound, OutboundMetrics, ParentRef};
 use linkerd_app_core::{
     proxy::{api_resolve::Metadata, http},
@@ -100,7 +100,13 @@ impl<N> Outbound<N> {
         NSvc: Clone + Send + Sync + 'static,
         NSvc::Future: Send,
     {
-        self.map_stack(|_config, rt, concrete| {
+        self.map_stack(|config, rt, concrete| {
+            // Retries of legacy ServiceProfile routes that are applied to
+            // policy routes are reported with the profile's retry metrics.
+            let retries = retry::NewRetryPolicy::new(
+                rt.metrics.proxy.http_profile_route_retry.clone(),
+                config.http_retry.clone(),
+            );
             // For each `T` target, watch its `Profile`, rebuilding a
             // router stack.
             concrete
@@ -108,8 +114,11 @@ impl<N> Outbound<N> {
                 .lift_new()
                 .push_on_service(RouterParams::layer(
                     rt.metrics.clone(),
-                    http_metrics,
-                    grpc_metrics,
+                    http_metrics.with_profile_retries(retries.clone()),
+                    grpc_metrics.with_profile_retries(retries.clone()),
+                    rt.chaos.clone(),
+                    config.egress_policy.clone(),
+                    retries,
                 ))
                 // Rebuild the inner router stack every time the watch changes.
                 .push(svc::NewSpawnWatch::<Routes, _>::layer_into::<RouterParams<T>>())
@@ -128,6 +137,9 @@ where
         metrics: OutboundMetrics,
         http_metrics: policy::RouteMetrics,
         grpc_metrics: policy::RouteMetrics,
+        chaos: policy::Chaos,
+        egress: policy::EgressPolicy,
+        retries: retry::NewRetryPolicy,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<RouterParams<T>>> + Clone
     where
         N: svc::NewService<Concrete<T>, Service = S>,
@@ -144,9 +156,13 @@ where
             let policy = svc::stack(concrete.clone()).push(policy::Policy::layer(
                 http_metrics.clone(),
                 grpc_metrics.clone(),
+                chaos.clone(),
+                egress.clone(),
+            ));
+            let profile = svc::stack(concrete.clone()).push(profile::Params::layer(
+                metrics.proxy.clone(),
+                retries.clone(),
             ));
-            let profile =
-                svc::stack(concrete.clone()).push(profile::Params::layer(metrics.proxy.clone()));
             svc::stack(concrete)
                 .push_switch(
                     |prms: Self| {
@@ -176,6 +192,15 @@ where
                         .push_switch(Ok::<_, Infallible>, policy.into_inner())
                         .into_inner(),
                 )
+                // Deny traffic to logical services that the egress policy
+                // denies, whether or not they are routed by policy routes.
+                .push(policy::NewDenyLogical::layer_via(
+                    egress.clone(),
+                    |prms: &Self| {
+                        let LogicalAddr(addr) = svc::Param::param(prms);
+                        addr.into_name_addr()
+                    },
+                ))
                 .push(svc::NewMapErr::layer_from_target::<LogicalError, _>())
                 .arc_new_clone_http()
                 .into_inner()
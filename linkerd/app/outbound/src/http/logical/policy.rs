@@ -1,4 +1,4 @@
-use super::{Concrete, LogicalAddr};
+use super::{profile, Concrete, LogicalAddr};
 use linkerd_app_core::{proxy::http, svc, Addr, Error, Infallible};
 use std::{fmt::Debug, hash::Hash};
 
@@ -7,11 +7,18 @@ mod router;
 #[cfg(test)]
 mod tests;
 
+pub(crate) use self::route::authz::NewDenyLogical;
 pub use self::{
-    route::{errors, RouteMetrics},
+    route::{
+        authz::{EgressPolicy, EgressUnauthorized},
+        chaos::{Chaos, Fault},
+        errors, RouteMetrics,
+    },
     router::{GrpcParams, HttpParams},
 };
+use linkerd_http_route::Route;
 pub use linkerd_proxy_client_policy::{ClientPolicy, FailureAccrual};
+use linkerd_proxy_client_policy::{Meta, RoutePolicy};
 
 /// HTTP or gRPC policy route parameters.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -36,6 +43,37 @@ impl Params {
             | Params::Grpc(router::Params { ref addr, .. }) => addr,
         }
     }
+
+    /// Returns true if any route is configured by a policy resource (e.g. an
+    /// HTTPRoute), rather than synthesized by the control plane.
+    pub fn has_resource_routes(&self) -> bool {
+        fn any_resource<M, F, E>(routes: &[Route<M, RoutePolicy<F, E>>]) -> bool {
+            routes
+                .iter()
+                .flat_map(|route| route.rules.iter())
+                .any(|rule| matches!(*rule.policy.meta, Meta::Resource { .. }))
+        }
+
+        match self {
+            Params::Http(http) => any_resource(&http.routes),
+            Params::Grpc(grpc) => any_resource(&grpc.routes),
+        }
+    }
+
+    /// Applies the retries and timeouts of legacy ServiceProfile routes to
+    /// requests on these routes.
+    pub fn with_profile_routes(self, routes: profile::Routes) -> Self {
+        match self {
+            Params::Http(http) => Params::Http(router::HttpParams {
+                profile_routes: Some(routes),
+                ..http
+            }),
+            Params::Grpc(grpc) => Params::Grpc(router::GrpcParams {
+                profile_routes: Some(routes),
+                ..grpc
+            }),
+        }
+    }
 }
 
 // === impl Policy ===
@@ -52,6 +90,8 @@ where
     pub(super) fn layer<N, S>(
         http_metrics: route::RouteMetrics,
         grpc_metrics: route::RouteMetrics,
+        chaos: Chaos,
+        egress: EgressPolicy,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         // Inner stack.
@@ -66,8 +106,16 @@ where
         S::Future: Send,
     {
         svc::layer::mk(move |inner: N| {
-            let http = svc::stack(inner.clone()).push(router::Http::layer(http_metrics.clone()));
-            let grpc = svc::stack(inner).push(router::Grpc::layer(grpc_metrics.clone()));
+            let http = svc::stack(inner.clone()).push(router::Http::layer(
+                http_metrics.clone(),
+                chaos.clone(),
+                egress.clone(),
+            ));
+            let grpc = svc::stack(inner).push(router::Grpc::layer(
+                grpc_metrics.clone(),
+                chaos.clone(),
+                egress.clone(),
+            ));
 
             http.push_switch(
                 |pp: Policy<T>| {
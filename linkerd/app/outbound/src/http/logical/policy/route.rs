@@ -1,22 +1,53 @@
 use super::super::Concrete;
-use crate::RouteRef;
-use linkerd_app_core::{classify, metrics::prom, proxy::http, svc, Addr, Error, Result};
+use crate::{http::retry, ParentRef, RouteRef};
+use linkerd_app_core::{
+    classify,
+    metrics::prom::{self, encoding::*, EncodeLabelSetMut},
+    proxy::{http, tap},
+    svc, Addr, Error, Result,
+};
 use linkerd_distribute as distribute;
 use linkerd_http_route as http_route;
 use linkerd_proxy_client_policy as policy;
 use std::{fmt::Debug, hash::Hash, sync::Arc};
 
+pub(crate) mod authz;
 pub(crate) mod backend;
+mod body_limit;
+mod body_sizes;
+mod cache;
+mod cancel;
+pub(crate) mod chaos;
+mod decompress;
 pub(crate) mod filters;
+mod messages;
+mod mirror;
+pub(crate) mod profile_compat;
+mod slo;
+mod sticky;
+mod streaming;
 
 pub(crate) use self::backend::{Backend, MatchedBackend};
 pub use self::filters::errors;
+pub(crate) use self::mirror::Mirror;
 
 #[derive(Clone, Debug, Default)]
 pub struct RouteMetrics {
+    authz: authz::AuthzMetricFamilies,
     backend: backend::RouteBackendMetrics,
+    messages: Option<messages::MessageMetricFamilies>,
+    body_sizes: body_sizes::BodySizeMetricFamilies,
+    cache: cache::CacheMetricFamilies,
+    cancel: cancel::CancelMetricFamilies,
+    mirror: mirror::MirrorMetricFamilies,
+    profile: profile_compat::ProfileCompatMetricFamilies,
+    slo: slo::SloMetricFamilies,
 }
 
+/// Labels that identify a route's metrics.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct RouteLabels(ParentRef, RouteRef);
+
 /// A target type that includes a summary of exactly how a request was matched.
 /// This match state is required to apply route filters.
 ///
@@ -31,11 +62,22 @@ pub(crate) struct Matched<M, P> {
 pub(crate) struct Route<T, F, E> {
     pub(super) parent: T,
     pub(super) addr: Addr,
+    pub(super) parent_ref: ParentRef,
     pub(super) route_ref: RouteRef,
     pub(super) filters: Arc<[F]>,
     pub(super) distribution: BackendDistribution<T, F>,
     pub(super) failure_policy: E,
     pub(super) request_timeout: Option<std::time::Duration>,
+    pub(super) cache: Option<policy::http::Cache>,
+    pub(super) request_decompression: Option<policy::http::Decompression>,
+    pub(super) request_body_limit: Option<policy::http::RequestBodyLimit>,
+    pub(super) streaming: Option<policy::http::Streaming>,
+    pub(super) sticky: Option<policy::http::StickyKey>,
+    pub(super) mirror: Option<Mirror<T>>,
+    pub(super) success_objective: Option<policy::http::SuccessObjective>,
+    /// The legacy ServiceProfile route that matches the request, if the
+    /// ServiceProfile is being migrated to policy routes.
+    pub(super) profile: Option<profile_compat::ProfileRoute>,
 }
 
 pub(crate) type MatchedRoute<T, M, F, E> = Matched<M, Route<T, F, E>>;
@@ -53,7 +95,6 @@ pub(crate) type Grpc<T> = MatchedRoute<
 >;
 
 pub(crate) type BackendDistribution<T, F> = distribute::Distribution<Backend<T, F>>;
-pub(crate) type NewDistribute<T, F, N> = distribute::NewDistribute<Backend<T, F>, (), N>;
 
 /// Wraps errors with route metadata.
 #[derive(Debug, thiserror::Error)]
@@ -69,9 +110,39 @@ struct RouteError {
 impl RouteMetrics {
     pub fn register(reg: &mut prom::Registry) -> Self {
         Self {
+            authz: authz::AuthzMetricFamilies::register(reg.sub_registry_with_prefix("authz")),
             backend: backend::RouteBackendMetrics::register(
                 reg.sub_registry_with_prefix("backend"),
             ),
+            messages: None,
+            body_sizes: body_sizes::BodySizeMetricFamilies::register(reg),
+            cache: cache::CacheMetricFamilies::register(reg.sub_registry_with_prefix("cache")),
+            cancel: cancel::CancelMetricFamilies::register(reg),
+            mirror: mirror::MirrorMetricFamilies::register(reg.sub_registry_with_prefix("mirror")),
+            profile: profile_compat::ProfileCompatMetricFamilies::register(
+                reg.sub_registry_with_prefix("profile"),
+            ),
+            slo: slo::SloMetricFamilies::register(reg.sub_registry_with_prefix("slo")),
+        }
+    }
+
+    /// Like [`RouteMetrics::register`], but also records the messages sent
+    /// and received on each gRPC stream.
+    pub fn register_grpc(reg: &mut prom::Registry) -> Self {
+        Self {
+            messages: Some(messages::MessageMetricFamilies::register(
+                reg.sub_registry_with_prefix("stream"),
+            )),
+            ..Self::register(reg)
+        }
+    }
+
+    /// Configures the retries of ServiceProfile routes applied to policy
+    /// routes, which are reported with the legacy profile route retry metrics.
+    pub(crate) fn with_profile_retries(self, retries: retry::NewRetryPolicy) -> Self {
+        Self {
+            profile: self.profile.with_retries(retries),
+            ..self
         }
     }
 
@@ -84,6 +155,34 @@ impl RouteMetrics {
     ) -> backend::RequestCount {
         self.backend.request_count(p, r, b)
     }
+
+    #[cfg(test)]
+    pub(crate) fn response_count(
+        &self,
+        p: crate::ParentRef,
+        r: RouteRef,
+        b: crate::BackendRef,
+        status: Option<http::StatusCode>,
+    ) -> u64 {
+        self.backend.response_count(p, r, b, status)
+    }
+}
+
+// === impl RouteLabels ===
+
+impl EncodeLabelSetMut for RouteLabels {
+    fn encode_label_set(&self, enc: &mut LabelSetEncoder<'_>) -> std::fmt::Result {
+        let Self(parent, route) = self;
+        parent.encode_label_set(enc)?;
+        route.encode_label_set(enc)?;
+        Ok(())
+    }
+}
+
+impl EncodeLabelSet for RouteLabels {
+    fn encode(&self, mut enc: LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.encode_label_set(&mut enc)
+    }
 }
 
 // === impl MatchedRoute ===
@@ -110,6 +209,8 @@ where
     /// backends are expected to be cached/shared by the inner stack.
     pub(crate) fn layer<N, S>(
         metrics: RouteMetrics,
+        chaos: chaos::Chaos,
+        egress: authz::EgressPolicy,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         // Inner stack.
@@ -123,22 +224,76 @@ where
         S: Clone + Send + Sync + 'static,
         S::Future: Send,
     {
-        svc::layer::mk(move |inner| {
+        svc::layer::mk(move |inner: N| {
+            let concrete = inner.clone();
             svc::stack(inner)
                 // Distribute requests across route backends, applying policies
                 // and filters for each of the route-backends.
                 .push(MatchedBackend::layer(metrics.backend.clone()))
                 .lift_new_with_target()
-                .push(NewDistribute::layer())
+                // Sticky routes assign requests to backends by a request key.
+                .push(sticky::NewStickyDistribute::<Backend<T, F>, _>::layer())
                 // The router does not take the backend's availability into
                 // consideration, so we must eagerly fail requests to prevent
                 // leaking tasks onto the runtime.
                 .push_on_service(svc::LoadShed::layer())
+                // Mirrors requests to the route's shadow backend, if it has
+                // one, and compares its responses with the route's.
+                .push(mirror::NewMirror::layer(concrete, metrics.mirror.clone()))
                 // TODO(ver) attach the `E` typed failure policy to requests.
                 .push(filters::NewApplyFilters::<Self, _, _>::layer())
+                // Limits the size of request bodies, rejecting requests that
+                // declare oversized bodies before their bodies are read.
+                .push(body_limit::NewLimitRequestBody::layer())
+                // Decompresses request bodies, if the route is configured to,
+                // so that body limits apply to their decompressed size.
+                .push(decompress::NewDecompress::layer())
+                // Describes the route in tap events.
+                .push(http::insert::NewInsert::<tap::PolicyRouteLabels, _>::layer())
+                // Serves cached responses, if the route is configured with a
+                // cache.
+                .push(cache::NewCache::layer(metrics.cache.clone()))
+                // Retries requests as configured by a legacy ServiceProfile
+                // route, if one matches the request. Depending on whether or
+                // not the request can be retried, it may have one of two
+                // `Body` types. This layer unifies any `Body` type into
+                // `BoxBody`.
+                .push_on_service(http::BoxRequest::erased())
+                .push(metrics.profile.retry_layer())
+                // Injects faults enabled for chaos experiments, so that
+                // injected delays count against the request timeout.
+                .push(chaos::NewInjectFaults::layer(chaos.clone()))
                 // Sets an optional request timeout.
                 .push(http::NewTimeout::layer())
+                // Extends the request timeout to the first byte of streamed
+                // response bodies and terminates idle streams.
+                .push(streaming::NewStreamingTimeout::layer())
+                // Counts messages on gRPC streams.
+                .push(messages::NewRecordMessages::layer(metrics.messages.clone()))
+                // Records the sizes of complete request and response bodies.
+                .push(body_sizes::NewRecordBodySizes::layer(
+                    metrics.body_sizes.clone(),
+                ))
+                // Reports how quickly routes with a success-rate objective
+                // consume their error budgets.
+                .push(slo::NewRecordSlo::layer(metrics.slo.clone()))
                 .push(classify::NewClassify::layer())
+                // Counts requests on which the policy route's configuration
+                // overrides a legacy ServiceProfile route's.
+                .push(profile_compat::NewRecordConflicts::layer(
+                    metrics.profile.clone(),
+                ))
+                // Denies requests that this workload may not send, before any
+                // other route policy is applied.
+                .push(authz::NewAuthorizeEgress::layer(
+                    egress.clone(),
+                    metrics.authz.clone(),
+                ))
+                // Cancels requests as soon as their clients go away and
+                // counts these cancellations.
+                .push(cancel::NewRecordCancellations::layer(
+                    metrics.cancel.clone(),
+                ))
                 .push(svc::NewMapErr::layer_with(|rt: &Self| {
                     let route = rt.params.route_ref.clone();
                     move |source| RouteError {
@@ -158,9 +313,83 @@ impl<T: Clone, M, F, E> svc::Param<BackendDistribution<T, F>> for MatchedRoute<T
     }
 }
 
+impl<T, M, F, E> svc::Param<Option<policy::http::StickyKey>> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> Option<policy::http::StickyKey> {
+        self.params.sticky.clone()
+    }
+}
+
+impl<T, M, F, E> svc::Param<Option<policy::http::SuccessObjective>> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> Option<policy::http::SuccessObjective> {
+        self.params.success_objective.clone()
+    }
+}
+
+impl<T, M, F, E> svc::Param<ParentRef> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> ParentRef {
+        self.params.parent_ref.clone()
+    }
+}
+
+impl<T, M, F, E> svc::Param<RouteRef> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> RouteRef {
+        self.params.route_ref.clone()
+    }
+}
+
+impl<T, M, F, E> svc::Param<Option<policy::http::Cache>> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> Option<policy::http::Cache> {
+        self.params.cache.clone()
+    }
+}
+
+impl<T, M, F, E> svc::Param<Option<policy::http::Decompression>> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> Option<policy::http::Decompression> {
+        self.params.request_decompression.clone()
+    }
+}
+
+impl<T, M, F, E> svc::Param<Option<policy::http::RequestBodyLimit>> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> Option<policy::http::RequestBodyLimit> {
+        self.params.request_body_limit.clone()
+    }
+}
+
+impl<T, M, F, E> svc::Param<Option<streaming::Timeouts>> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> Option<streaming::Timeouts> {
+        let streaming = self.params.streaming.as_ref()?;
+        Some(streaming::Timeouts {
+            first_byte: self.params.request_timeout,
+            idle: streaming.idle_timeout,
+        })
+    }
+}
+
+impl<T, M, F, E> svc::Param<tap::PolicyRouteLabels> for MatchedRoute<T, M, F, E> {
+    fn param(&self) -> tap::PolicyRouteLabels {
+        let RouteRef(meta) = &self.params.route_ref;
+        let labels = [
+            ("route_group", meta.group()),
+            ("route_kind", meta.kind()),
+            ("route_name", meta.name()),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<std::collections::BTreeMap<_, _>>();
+        tap::PolicyRouteLabels(labels.into())
+    }
+}
+
 impl<T, M, F, E> svc::Param<http::timeout::ResponseTimeout> for MatchedRoute<T, M, F, E> {
     fn param(&self) -> http::timeout::ResponseTimeout {
-        http::timeout::ResponseTimeout(self.params.request_timeout)
+        // A policy route's timeout takes precedence over a legacy
+        // ServiceProfile route's.
+        let profile = self.params.profile.as_ref();
+        http::timeout::ResponseTimeout(
+            self.params
+                .request_timeout
+                .or_else(|| profile?.route.timeout()),
+        )
     }
 }
 
@@ -0,0 +1,353 @@
+//! Enforces egress authorization on a route's requests.
+//!
+//! The outbound policy API does not describe client-side authorizations, so
+//! egress restrictions are configured locally: requests may be denied by the
+//! name of the route that handles them or by their authority. Every denial is
+//! logged and counted so that restrictions can be audited.
+//!
+//! Denied authorities are also enforced on logical services, so that traffic
+//! that is not routed by policy routes (i.e. ServiceProfile routes and opaque
+//! connections) can't bypass them.
+
+use super::RouteLabels;
+use crate::{ParentRef, RouteRef};
+use futures::future;
+use linkerd_app_core::{metrics::prom, proxy::http, svc, Error, NameAddr};
+use linkerd_http_route::http::MatchHost;
+use std::{
+    collections::HashSet,
+    marker::PhantomData,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// Describes the requests that this workload may not send.
+#[derive(Clone, Debug)]
+pub struct EgressPolicy {
+    deny_authorities: Arc<[MatchHost]>,
+    deny_routes: Arc<HashSet<String>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AuthzMetricFamilies {
+    denied: prom::Family<RouteLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewAuthorizeEgress<N> {
+    policy: EgressPolicy,
+    metrics: AuthzMetricFamilies,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AuthorizeEgress<S> {
+    policy: EgressPolicy,
+    parent: ParentRef,
+    route: RouteRef,
+    denied: prom::Counter,
+    inner: S,
+}
+
+/// Denies all traffic to logical services whose names match the egress
+/// policy's denied authorities.
+#[derive(Clone, Debug)]
+pub(crate) struct NewDenyLogical<X, N> {
+    policy: EgressPolicy,
+    extract: X,
+    inner: N,
+}
+
+/// Fails all requests (or connections) to a denied logical service.
+pub(crate) struct DenyLogical<S> {
+    addr: NameAddr,
+    _marker: PhantomData<fn() -> S>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("egress denied to {target}")]
+pub struct EgressUnauthorized {
+    target: String,
+}
+
+// === impl EgressPolicy ===
+
+impl Default for EgressPolicy {
+    fn default() -> Self {
+        Self {
+            deny_authorities: Arc::new([]),
+            deny_routes: Default::default(),
+        }
+    }
+}
+
+impl EgressPolicy {
+    pub fn new(
+        deny_authorities: impl IntoIterator<Item = MatchHost>,
+        deny_routes: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Self {
+            deny_authorities: deny_authorities.into_iter().collect(),
+            deny_routes: Arc::new(deny_routes.into_iter().collect()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.deny_authorities.is_empty() && self.deny_routes.is_empty()
+    }
+
+    /// Returns true if traffic may not be sent to the logical service.
+    fn denies_logical(&self, addr: &NameAddr) -> bool {
+        let Ok(uri) = format!("http://{addr}/").parse::<http::uri::Uri>() else {
+            return false;
+        };
+        self.deny_authorities
+            .iter()
+            .any(|host| host.summarize_match(&uri).is_some())
+    }
+
+    /// Returns true if the request may not be sent on the given route.
+    fn denies<B>(&self, RouteRef(route): &RouteRef, req: &http::Request<B>) -> bool {
+        self.deny_routes.contains(route.name())
+            || self
+                .deny_authorities
+                .iter()
+                .any(|host| host.summarize_match(req.uri()).is_some())
+    }
+}
+
+// === impl AuthzMetricFamilies ===
+
+impl AuthzMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let denied = prom::Family::default();
+        reg.register(
+            "denied",
+            "The total number of requests denied by the egress policy",
+            denied.clone(),
+        );
+        Self { denied }
+    }
+}
+
+// === impl NewAuthorizeEgress ===
+
+impl<N> NewAuthorizeEgress<N> {
+    pub(crate) fn layer(
+        policy: EgressPolicy,
+        metrics: AuthzMetricFamilies,
+    ) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            policy: policy.clone(),
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewAuthorizeEgress<N>
+where
+    T: svc::Param<ParentRef> + svc::Param<RouteRef>,
+    N: svc::NewService<T>,
+{
+    type Service = svc::Either<AuthorizeEgress<N::Service>, N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        // Most workloads have no egress restrictions, so avoid inspecting
+        // their requests.
+        if self.policy.is_empty() {
+            return svc::Either::B(self.inner.new_service(target));
+        }
+
+        let parent: ParentRef = target.param();
+        let route: RouteRef = target.param();
+        let denied = self
+            .metrics
+            .denied
+            .get_or_create(&RouteLabels(parent.clone(), route.clone()))
+            .clone();
+        svc::Either::A(AuthorizeEgress {
+            policy: self.policy.clone(),
+            parent,
+            route,
+            denied,
+            inner: self.inner.new_service(target),
+        })
+    }
+}
+
+// === impl AuthorizeEgress ===
+
+impl<B, S> svc::Service<http::Request<B>> for AuthorizeEgress<S>
+where
+    S: svc::Service<http::Request<B>, Error = Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<S::Future, future::Ready<Result<S::Response, Error>>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if !self.policy.denies(&self.route, &req) {
+            return future::Either::Left(self.inner.call(req));
+        }
+
+        let ParentRef(parent) = &self.parent;
+        let RouteRef(route) = &self.route;
+        tracing::info!(
+            parent.group = %parent.group(),
+            parent.kind = %parent.kind(),
+            parent.name = %parent.name(),
+            route.group = %route.group(),
+            route.kind = %route.kind(),
+            route.name = %route.name(),
+            authority = ?req.uri().authority(),
+            "Request denied by egress policy",
+        );
+        self.denied.inc();
+        future::Either::Right(future::err(
+            EgressUnauthorized {
+                target: format!("{} {}", route.kind(), route.name()),
+            }
+            .into(),
+        ))
+    }
+}
+
+// === impl NewDenyLogical ===
+
+impl<X: Clone, N> NewDenyLogical<X, N> {
+    pub(crate) fn layer_via(
+        policy: EgressPolicy,
+        extract: X,
+    ) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            policy: policy.clone(),
+            extract: extract.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, X, N> svc::NewService<T> for NewDenyLogical<X, N>
+where
+    X: svc::ExtractParam<Option<NameAddr>, T>,
+    N: svc::NewService<T>,
+{
+    type Service = svc::Either<N::Service, DenyLogical<N::Service>>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        match self.extract.extract_param(&target) {
+            Some(addr) if self.policy.denies_logical(&addr) => {
+                tracing::debug!(%addr, "Logical service denied by egress policy");
+                svc::Either::B(DenyLogical {
+                    addr,
+                    _marker: PhantomData,
+                })
+            }
+            _ => svc::Either::A(self.inner.new_service(target)),
+        }
+    }
+}
+
+// === impl DenyLogical ===
+
+impl<Req, S> svc::Service<Req> for DenyLogical<S>
+where
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Ready<Result<S::Response, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Req) -> Self::Future {
+        tracing::info!(addr = %self.addr, "Traffic denied by egress policy");
+        future::err(
+            EgressUnauthorized {
+                target: self.addr.to_string(),
+            }
+            .into(),
+        )
+    }
+}
+
+impl<S> Clone for DenyLogical<S> {
+    fn clone(&self) -> Self {
+        Self {
+            addr: self.addr.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_http_route::http::r#match::MatchPort;
+    use linkerd_proxy_client_policy::Meta;
+    use svc::{Layer, NewService};
+    use tower::ServiceExt;
+
+    fn route(name: &str) -> RouteRef {
+        RouteRef(Arc::new(Meta::Resource {
+            group: "policy.linkerd.io".into(),
+            kind: "HTTPRoute".into(),
+            namespace: "ns".into(),
+            name: name.into(),
+            port: None,
+            section: None,
+        }))
+    }
+
+    fn req(uri: &str) -> http::Request<()> {
+        http::Request::builder().uri(uri).body(()).unwrap()
+    }
+
+    #[test]
+    fn denies_routes_and_authorities() {
+        let policy = EgressPolicy::new(
+            [
+                "*.example.com".parse().unwrap(),
+                "api.test"
+                    .parse::<MatchHost>()
+                    .unwrap()
+                    .with_port(MatchPort::Exact(8443)),
+            ],
+            Some("payments".to_string()),
+        );
+
+        assert!(policy.denies(&route("payments"), &req("http://svc.ns:8080/")));
+        assert!(policy.denies(&route("default"), &req("https://www.example.com/")));
+        assert!(policy.denies(&route("default"), &req("http://api.test:8443/v1")));
+        assert!(!policy.denies(&route("default"), &req("http://api.test:8080/v1")));
+        assert!(!policy.denies(&route("default"), &req("http://example.com/")));
+        assert!(!EgressPolicy::default().denies(&route("payments"), &req("http://example.com/")));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn denies_logical_services() {
+        let policy = EgressPolicy::new(
+            ["*.example.com".parse().unwrap()],
+            Some("payments".to_string()),
+        );
+        let new_svc = NewDenyLogical::layer_via(policy, |addr: &NameAddr| Some(addr.clone()))
+            .layer(|_: NameAddr| tower::service_fn(|()| future::ok::<_, Error>(())));
+
+        let denied = new_svc.new_service("www.example.com:443".parse().unwrap());
+        let err = denied.oneshot(()).await.expect_err("must be denied");
+        assert!(err.is::<EgressUnauthorized>());
+
+        // Logical services are only denied by authority.
+        let allowed = new_svc.new_service("payments.ns.svc.cluster.local:80".parse().unwrap());
+        allowed.oneshot(()).await.expect("must be allowed");
+    }
+}
@@ -6,10 +6,11 @@ use linkerd_proxy_client_policy as policy;
 use std::{fmt::Debug, hash::Hash, sync::Arc};
 
 mod count_reqs;
+mod count_rsps;
 mod metrics;
 
 pub use self::count_reqs::RequestCount;
-pub use self::metrics::RouteBackendMetrics;
+pub use self::metrics::{ResponseStatuses, RouteBackendMetrics};
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub(crate) struct Backend<T, F> {
@@ -72,6 +73,7 @@ where
     // Assert that filters can be applied.
     Self: filters::Apply,
     RouteBackendMetrics: svc::ExtractParam<RequestCount, Self>,
+    RouteBackendMetrics: svc::ExtractParam<ResponseStatuses, Self>,
 {
     /// Builds a stack that applies per-route-backend policy filters over an
     /// inner [`Concrete`] stack.
@@ -103,6 +105,7 @@ where
                 )
                 .push(filters::NewApplyFilters::<Self, _, _>::layer())
                 .push(http::NewTimeout::layer())
+                .push(count_rsps::NewCountResponses::layer_via(metrics.clone()))
                 .push(count_reqs::NewCountRequests::layer_via(metrics.clone()))
                 .push(svc::NewMapErr::layer_with(|t: &Self| {
                     let backend = t.params.concrete.backend_ref.clone();
@@ -0,0 +1,95 @@
+use super::metrics::ResponseStatuses;
+use futures::ready;
+use linkerd_app_core::svc;
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[derive(Clone, Debug)]
+pub struct NewCountResponses<X, N> {
+    inner: N,
+    extract: X,
+}
+
+#[derive(Clone, Debug)]
+pub struct CountResponses<S> {
+    inner: S,
+    statuses: ResponseStatuses,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    statuses: ResponseStatuses,
+}
+
+// === impl NewCountResponses ===
+
+impl<X: Clone, N> NewCountResponses<X, N> {
+    pub fn new(extract: X, inner: N) -> Self {
+        Self { extract, inner }
+    }
+
+    pub fn layer_via(extract: X) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self::new(extract.clone(), inner))
+    }
+}
+
+impl<T, X, N> svc::NewService<T> for NewCountResponses<X, N>
+where
+    X: svc::ExtractParam<ResponseStatuses, T>,
+    N: svc::NewService<T>,
+{
+    type Service = CountResponses<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let statuses = self.extract.extract_param(&target);
+        let inner = self.inner.new_service(target);
+        CountResponses { inner, statuses }
+    }
+}
+
+// === impl CountResponses ===
+
+impl<A, B, S> svc::Service<http::Request<A>> for CountResponses<S>
+where
+    S: svc::Service<http::Request<A>, Response = http::Response<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<A>) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(req),
+            statuses: self.statuses.clone(),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<B, E, F> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.inner.poll(cx));
+        this.statuses
+            .record(res.as_ref().ok().map(|rsp| rsp.status()));
+        Poll::Ready(res)
+    }
+}
@@ -7,17 +7,39 @@ use linkerd_app_core::{
 #[derive(Clone, Debug, Default)]
 pub struct RouteBackendMetrics {
     metrics: super::count_reqs::RequestCountFamilies<RouteBackendLabels>,
+    statuses: prom::Family<StatusLabels, prom::Counter>,
+}
+
+/// Counts the responses from a route backend by status.
+#[derive(Clone, Debug)]
+pub struct ResponseStatuses {
+    family: prom::Family<StatusLabels, prom::Counter>,
+    labels: RouteBackendLabels,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 struct RouteBackendLabels(ParentRef, RouteRef, BackendRef);
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct StatusLabels {
+    backend: RouteBackendLabels,
+    /// Unset when the backend fails to produce a response.
+    status: Option<http::StatusCode>,
+}
+
 // === impl RouteBackendMetrics ===
 
 impl RouteBackendMetrics {
     pub fn register(reg: &mut prom::Registry) -> Self {
+        let statuses = prom::Family::default();
+        reg.register(
+            "response_statuses",
+            "The total number of responses received, by HTTP status",
+            statuses.clone(),
+        );
         Self {
             metrics: super::count_reqs::RequestCountFamilies::register(reg),
+            statuses,
         }
     }
 
@@ -30,6 +52,21 @@ impl RouteBackendMetrics {
     ) -> super::count_reqs::RequestCount {
         self.metrics.metrics(&RouteBackendLabels(p, r, b))
     }
+
+    #[cfg(test)]
+    pub(crate) fn response_count(
+        &self,
+        p: ParentRef,
+        r: RouteRef,
+        b: BackendRef,
+        status: Option<http::StatusCode>,
+    ) -> u64 {
+        let labels = StatusLabels {
+            backend: RouteBackendLabels(p, r, b),
+            status,
+        };
+        self.statuses.get_or_create(&labels).get()
+    }
 }
 
 impl<T> svc::ExtractParam<super::count_reqs::RequestCount, T> for RouteBackendMetrics
@@ -42,6 +79,30 @@ where
     }
 }
 
+impl<T> svc::ExtractParam<ResponseStatuses, T> for RouteBackendMetrics
+where
+    T: svc::Param<ParentRef> + svc::Param<RouteRef> + svc::Param<BackendRef>,
+{
+    fn extract_param(&self, t: &T) -> ResponseStatuses {
+        ResponseStatuses {
+            family: self.statuses.clone(),
+            labels: RouteBackendLabels(t.param(), t.param(), t.param()),
+        }
+    }
+}
+
+// === impl ResponseStatuses ===
+
+impl ResponseStatuses {
+    pub(super) fn record(&self, status: Option<http::StatusCode>) {
+        let labels = StatusLabels {
+            backend: self.labels.clone(),
+            status,
+        };
+        self.family.get_or_create(&labels).inc();
+    }
+}
+
 // === impl RouteBackendLabels ===
 
 impl EncodeLabelSetMut for RouteBackendLabels {
@@ -59,3 +120,29 @@ impl EncodeLabelSet for RouteBackendLabels {
         self.encode_label_set(&mut enc)
     }
 }
+
+// === impl StatusLabels ===
+
+impl EncodeLabelSetMut for StatusLabels {
+    fn encode_label_set(&self, enc: &mut LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.backend.encode_label_set(enc)?;
+        (
+            "http_status",
+            self.status.map(|s| s.as_u16()).unwrap_or_default(),
+        )
+            .encode(enc.encode_label())?;
+        let error = if self.status.is_none() {
+            "true"
+        } else {
+            "false"
+        };
+        ("error", error).encode(enc.encode_label())?;
+        Ok(())
+    }
+}
+
+impl EncodeLabelSet for StatusLabels {
+    fn encode(&self, mut enc: LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.encode_label_set(&mut enc)
+    }
+}
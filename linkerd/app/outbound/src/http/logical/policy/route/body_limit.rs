@@ -0,0 +1,208 @@
+//! Limits the size of a route's request bodies.
+//!
+//! Requests that declare a larger `content-length` than their route permits
+//! are rejected before their bodies are read. When such a client sends an
+//! `Expect: 100-continue` header, it is never told to continue, so a large
+//! upload fails before its body is sent (unless the proxy is configured to
+//! answer expectations as soon as requests are received). Bodies of unknown
+//! length fail once they are streamed beyond the limit.
+
+use super::errors::HttpRouteBodyTooLarge;
+use futures::future;
+use linkerd_app_core::{
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use linkerd_proxy_client_policy as policy;
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[derive(Clone, Debug)]
+pub struct NewLimitRequestBody<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct LimitRequestBody<S> {
+    inner: S,
+    limit: Option<policy::http::RequestBodyLimit>,
+}
+
+#[pin_project]
+struct LimitedBody {
+    #[pin]
+    inner: http::BoxBody,
+    max_bytes: u64,
+    bytes: u64,
+}
+
+// === impl NewLimitRequestBody ===
+
+impl<N> NewLimitRequestBody<N> {
+    pub fn layer() -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewLimitRequestBody<N>
+where
+    T: svc::Param<Option<policy::http::RequestBodyLimit>>,
+    N: svc::NewService<T>,
+{
+    type Service = LimitRequestBody<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let limit = target.param();
+        let inner = self.inner.new_service(target);
+        LimitRequestBody { inner, limit }
+    }
+}
+
+// === impl LimitRequestBody ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for LimitRequestBody<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Error = Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<S::Future, future::Ready<Result<S::Response, Error>>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let max_bytes = match self.limit.as_ref() {
+            Some(limit) => limit.max_bytes,
+            None => return future::Either::Left(self.inner.call(req)),
+        };
+
+        if let Some(len) = content_length(req.headers()).filter(|len| *len > max_bytes) {
+            tracing::debug!(content_length = len, max_bytes, "Request body is too large");
+            return future::Either::Right(future::err(HttpRouteBodyTooLarge { max_bytes }.into()));
+        }
+
+        let req = req.map(|inner| {
+            http::BoxBody::new(LimitedBody {
+                inner,
+                max_bytes,
+                bytes: 0,
+            })
+        });
+        future::Either::Left(self.inner.call(req))
+    }
+}
+
+fn content_length(headers: &::http::HeaderMap) -> Option<u64> {
+    headers
+        .get(http::header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+// === impl LimitedBody ===
+
+impl HttpBody for LimitedBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let data = futures::ready!(this.inner.poll_data(cx));
+        if let Some(Ok(ref data)) = data {
+            *this.bytes += bytes::Buf::remaining(data) as u64;
+            if *this.bytes > *this.max_bytes {
+                let max_bytes = *this.max_bytes;
+                return Poll::Ready(Some(Err(HttpRouteBodyTooLarge { max_bytes }.into())));
+            }
+        }
+        Poll::Ready(data)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<::http::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::svc::ServiceExt;
+
+    fn limit(
+        max_bytes: u64,
+    ) -> LimitRequestBody<
+        impl svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+        >,
+    > {
+        // Reads the request body before responding.
+        let inner = svc::mk(|req: http::Request<http::BoxBody>| async move {
+            hyper::body::to_bytes(req.into_body()).await?;
+            Ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+        });
+        LimitRequestBody {
+            inner,
+            limit: Some(policy::http::RequestBodyLimit { max_bytes }),
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn rejects_declared_lengths_before_reading() {
+        // The body is never sent, so it must not be read.
+        let (_tx, body) = hyper::Body::channel();
+        let req = http::Request::builder()
+            .header(http::header::CONTENT_LENGTH, "2000")
+            .header(http::header::EXPECT, "100-continue")
+            .body(http::BoxBody::new(body))
+            .unwrap();
+        let err = limit(1000)
+            .oneshot(req)
+            .await
+            .expect_err("request must be rejected");
+        assert!(err.is::<HttpRouteBodyTooLarge>(), "{err}");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn fails_streamed_bodies() {
+        // The body's length is not declared in a header.
+        let req = http::Request::new(http::BoxBody::from(bytes::Bytes::from(vec![0; 2000])));
+        let err = limit(1000)
+            .oneshot(req)
+            .await
+            .expect_err("body must exceed limit");
+        assert!(err.is::<HttpRouteBodyTooLarge>(), "{err}");
+
+        let req = http::Request::new(http::BoxBody::from(bytes::Bytes::from(vec![0; 1000])));
+        limit(1000)
+            .oneshot(req)
+            .await
+            .expect("body must not exceed limit");
+    }
+}
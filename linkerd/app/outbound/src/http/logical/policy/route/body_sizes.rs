@@ -0,0 +1,296 @@
+//! Records the sizes of each route's request and response bodies.
+//!
+//! Sizes are recorded once a body completes, so that bodies that are dropped
+//! before they are fully streamed do not skew the distribution. Histograms
+//! are labeled only by route, so their cardinality is bounded by the number
+//! of routes configured by policy.
+
+use super::RouteLabels;
+use crate::{ParentRef, RouteRef};
+use futures::{ready, TryFuture};
+use linkerd_app_core::{
+    metrics::prom,
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use pin_project::{pin_project, pinned_drop};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[derive(Clone, Debug)]
+pub struct BodySizeMetricFamilies {
+    request_bytes: prom::Family<RouteLabels, prom::Histogram, fn() -> prom::Histogram>,
+    response_bytes: prom::Family<RouteLabels, prom::Histogram, fn() -> prom::Histogram>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewRecordBodySizes<N> {
+    inner: N,
+    families: BodySizeMetricFamilies,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordBodySizes<S> {
+    inner: S,
+    request_bytes: prom::Histogram,
+    response_bytes: prom::Histogram,
+}
+
+#[pin_project]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    response_bytes: Option<prom::Histogram>,
+}
+
+/// Counts the bytes in a body, recording its size once it completes.
+#[pin_project(PinnedDrop)]
+struct SizedBody {
+    #[pin]
+    inner: http::BoxBody,
+    bytes: u64,
+    /// Unset once the body's size has been recorded.
+    histogram: Option<prom::Histogram>,
+}
+
+// === impl BodySizeMetricFamilies ===
+
+impl Default for BodySizeMetricFamilies {
+    fn default() -> Self {
+        Self {
+            request_bytes: prom::Family::new_with_constructor(Self::histogram),
+            response_bytes: prom::Family::new_with_constructor(Self::histogram),
+        }
+    }
+}
+
+impl BodySizeMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let families = Self::default();
+        reg.register_with_unit(
+            "request_body_size",
+            "The sizes of request bodies sent on each route",
+            prom::Unit::Bytes,
+            families.request_bytes.clone(),
+        );
+        reg.register_with_unit(
+            "response_body_size",
+            "The sizes of response bodies received on each route",
+            prom::Unit::Bytes,
+            families.response_bytes.clone(),
+        );
+        families
+    }
+
+    fn histogram() -> prom::Histogram {
+        // 64B to 16MiB.
+        prom::Histogram::new(prom::metrics::histogram::exponential_buckets(64.0, 4.0, 10))
+    }
+}
+
+// === impl NewRecordBodySizes ===
+
+impl<N> NewRecordBodySizes<N> {
+    pub fn layer(families: BodySizeMetricFamilies) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            families: families.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRecordBodySizes<N>
+where
+    T: svc::Param<ParentRef> + svc::Param<RouteRef>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordBodySizes<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let labels = RouteLabels(target.param(), target.param());
+        RecordBodySizes {
+            request_bytes: self.families.request_bytes.get_or_create(&labels).clone(),
+            response_bytes: self.families.response_bytes.get_or_create(&labels).clone(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl RecordBodySizes ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for RecordBodySizes<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let histogram = self.request_bytes.clone();
+        let req = req.map(|inner| http::BoxBody::new(SizedBody::new(inner, histogram)));
+        ResponseFuture {
+            inner: self.inner.call(req),
+            response_bytes: Some(self.response_bytes.clone()),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: TryFuture<Ok = http::Response<http::BoxBody>>,
+{
+    type Output = Result<http::Response<http::BoxBody>, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = ready!(this.inner.try_poll(cx))?;
+        let histogram = this
+            .response_bytes
+            .take()
+            .expect("future must not be polled after ready");
+        Poll::Ready(Ok(
+            rsp.map(|inner| http::BoxBody::new(SizedBody::new(inner, histogram)))
+        ))
+    }
+}
+
+// === impl SizedBody ===
+
+impl SizedBody {
+    fn new(inner: http::BoxBody, histogram: prom::Histogram) -> Self {
+        Self {
+            inner,
+            bytes: 0,
+            histogram: Some(histogram),
+        }
+    }
+}
+
+impl HttpBody for SizedBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        let data = ready!(this.inner.as_mut().poll_data(cx));
+        match data {
+            Some(Ok(ref data)) => {
+                *this.bytes += bytes::Buf::remaining(data) as u64;
+                // The body may not be polled again once it reports that it
+                // has ended.
+                if this.inner.is_end_stream() {
+                    record(this.histogram, *this.bytes);
+                }
+            }
+            // Failed bodies are not recorded.
+            Some(Err(_)) => *this.histogram = None,
+            None => record(this.histogram, *this.bytes),
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<::http::HeaderMap>, Self::Error>> {
+        let this = self.project();
+        let trailers = ready!(this.inner.poll_trailers(cx));
+        match trailers {
+            Ok(_) => record(this.histogram, *this.bytes),
+            Err(_) => *this.histogram = None,
+        }
+        Poll::Ready(trailers)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[pinned_drop]
+impl PinnedDrop for SizedBody {
+    fn drop(self: Pin<&mut Self>) {
+        // Bodies that end without being polled (e.g. empty bodies) are
+        // recorded when they are dropped.
+        let this = self.project();
+        if this.inner.is_end_stream() {
+            record(this.histogram, *this.bytes);
+        }
+    }
+}
+
+fn record(histogram: &mut Option<prom::Histogram>, bytes: u64) {
+    if let Some(histogram) = histogram.take() {
+        histogram.observe(bytes as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the histogram's sum and count, as encoded for Prometheus.
+    fn observed(histogram: &prom::Histogram) -> (String, String) {
+        let mut reg = prom::Registry::default();
+        reg.register("size", "Body sizes", histogram.clone());
+        let mut out = String::new();
+        prom::encoding::text::encode(&mut out, &reg).unwrap();
+        let find = |name: &str| {
+            out.lines()
+                .find_map(|l| l.strip_prefix(name))
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        (find("size_sum"), find("size_count"))
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn records_complete_bodies() {
+        let histogram = BodySizeMetricFamilies::histogram();
+
+        let mut body = SizedBody::new(
+            http::BoxBody::from(bytes::Bytes::from_static(b"hello")),
+            histogram.clone(),
+        );
+        while body.data().await.is_some() {}
+        drop(body);
+        assert_eq!(observed(&histogram), ("5.0".into(), "1".into()));
+
+        // Empty bodies are recorded even though they are never polled.
+        let body = SizedBody::new(http::BoxBody::default(), histogram.clone());
+        drop(body);
+        assert_eq!(observed(&histogram), ("5.0".into(), "2".into()));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn ignores_incomplete_bodies() {
+        let histogram = BodySizeMetricFamilies::histogram();
+        let (mut tx, rx) = hyper::Body::channel();
+        let mut body = SizedBody::new(http::BoxBody::new(rx), histogram.clone());
+        tx.send_data("hello".into()).await.unwrap();
+        assert!(body.data().await.is_some());
+        drop(body);
+        assert_eq!(observed(&histogram), ("0.0".into(), "0".into()));
+    }
+}
@@ -0,0 +1,647 @@
+//! An in-memory cache of a route's responses.
+//!
+//! Only responses to `GET` requests are cached, and only when their
+//! `Cache-Control` headers permit a shared cache to store them. Cached
+//! responses are served until their `max-age` (or `s-maxage`) expires; stale
+//! responses with an `ETag` are then revalidated with a conditional request.
+//! Responses must have a known length to be cached, and entries are evicted
+//! oldest-first once the route's cache exceeds its configured size.
+
+use super::RouteLabels;
+use crate::{ParentRef, RouteRef};
+use ahash::AHashMap;
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{
+    metrics::prom,
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use linkerd_proxy_client_policy as policy;
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+#[derive(Clone, Debug, Default)]
+pub struct CacheMetricFamilies {
+    hits: prom::Family<RouteLabels, prom::Counter>,
+    misses: prom::Family<RouteLabels, prom::Counter>,
+    revalidations: prom::Family<RouteLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug)]
+struct CacheMetrics {
+    hits: prom::Counter,
+    misses: prom::Counter,
+    revalidations: prom::Counter,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewCache<N> {
+    inner: N,
+    families: CacheMetricFamilies,
+}
+
+#[derive(Clone, Debug)]
+pub struct Cache<S> {
+    inner: S,
+    store: Option<Arc<Store>>,
+}
+
+#[derive(Debug)]
+struct Store {
+    config: policy::http::Cache,
+    metrics: CacheMetrics,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    by_key: AHashMap<Key, Arc<Entry>>,
+    bytes: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    uri: http::uri::Uri,
+    vary: Vec<Option<http::HeaderValue>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    status: http::StatusCode,
+    version: ::http::Version,
+    headers: http::header::HeaderMap,
+    body: Bytes,
+    /// The request headers named by the response's `Vary` header, with the
+    /// values they had on the request that populated the entry.
+    vary: Vec<(http::HeaderName, Option<http::HeaderValue>)>,
+    etag: Option<http::HeaderValue>,
+    stored_at: Instant,
+    max_age: Duration,
+}
+
+/// The `Cache-Control` directives that are relevant to a shared cache.
+#[derive(Debug, Default)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    private: bool,
+    max_age: Option<Duration>,
+    s_maxage: Option<Duration>,
+}
+
+type ResponseFuture =
+    Pin<Box<dyn Future<Output = Result<http::Response<http::BoxBody>, Error>> + Send + 'static>>;
+
+// === impl CacheMetricFamilies ===
+
+impl CacheMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let hits = prom::Family::default();
+        reg.register(
+            "hits",
+            "The total number of requests answered from a route's response cache",
+            hits.clone(),
+        );
+
+        let misses = prom::Family::default();
+        reg.register(
+            "misses",
+            "The total number of cacheable requests that could not be answered from a route's response cache",
+            misses.clone(),
+        );
+
+        let revalidations = prom::Family::default();
+        reg.register(
+            "revalidations",
+            "The total number of stale cached responses that were revalidated by a route's backends",
+            revalidations.clone(),
+        );
+
+        Self {
+            hits,
+            misses,
+            revalidations,
+        }
+    }
+
+    fn metrics(&self, labels: &RouteLabels) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.hits.get_or_create(labels).clone(),
+            misses: self.misses.get_or_create(labels).clone(),
+            revalidations: self.revalidations.get_or_create(labels).clone(),
+        }
+    }
+}
+
+// === impl NewCache ===
+
+impl<N> NewCache<N> {
+    pub fn layer(families: CacheMetricFamilies) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            families: families.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewCache<N>
+where
+    T: svc::Param<Option<policy::http::Cache>>,
+    T: svc::Param<ParentRef> + svc::Param<RouteRef>,
+    N: svc::NewService<T>,
+{
+    type Service = Cache<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let store = svc::Param::<Option<policy::http::Cache>>::param(&target).map(|config| {
+            let labels = RouteLabels(target.param(), target.param());
+            Arc::new(Store {
+                config,
+                metrics: self.families.metrics(&labels),
+                entries: Default::default(),
+            })
+        });
+        let inner = self.inner.new_service(target);
+        Cache { inner, store }
+    }
+}
+
+// === impl Cache ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for Cache<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::Either<future::ErrInto<S::Future, Error>, ResponseFuture>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        let store = match self.store.as_ref() {
+            Some(store) if is_cacheable(&req) => store.clone(),
+            _ => return future::Either::Left(self.inner.call(req).err_into()),
+        };
+
+        let key = store.key(&req);
+        // The client's headers are retained to describe the response, since
+        // revalidation modifies the request.
+        let req_headers = req.headers().clone();
+        let now = Instant::now();
+        let mut revalidating = None;
+        if !CacheControl::parse(req.headers()).no_cache {
+            if let Some(entry) = store.get(&key, req.headers()) {
+                if entry.is_fresh(now) {
+                    tracing::trace!(uri = %req.uri(), "Serving cached response");
+                    store.metrics.hits.inc();
+                    let rsp = entry.response(req.headers(), now);
+                    return future::Either::Right(Box::pin(future::ok(rsp)));
+                }
+
+                // Stale responses are revalidated unless the client is making
+                // its own conditional request.
+                if let Some(etag) = entry.etag.clone() {
+                    if !req.headers().contains_key(http::header::IF_NONE_MATCH) {
+                        req.headers_mut().insert(http::header::IF_NONE_MATCH, etag);
+                        revalidating = Some(entry);
+                    }
+                }
+            }
+        }
+
+        let rsp = self.inner.call(req).err_into::<Error>();
+        future::Either::Right(Box::pin(async move {
+            let rsp = rsp.await?;
+            if let Some(entry) = revalidating {
+                if rsp.status() == http::StatusCode::NOT_MODIFIED {
+                    store.metrics.revalidations.inc();
+                    let entry = store.refresh(key, &entry, rsp.headers());
+                    return Ok(entry.response(&req_headers, Instant::now()));
+                }
+            }
+            store.metrics.misses.inc();
+            store.insert(key, &req_headers, rsp, now).await
+        }))
+    }
+}
+
+fn is_cacheable<B>(req: &http::Request<B>) -> bool {
+    req.method() == http::Method::GET
+        && !req.headers().contains_key(http::header::AUTHORIZATION)
+        && !CacheControl::parse(req.headers()).no_store
+}
+
+// === impl Store ===
+
+impl Store {
+    fn key<B>(&self, req: &http::Request<B>) -> Key {
+        Key {
+            uri: req.uri().clone(),
+            vary: self
+                .config
+                .vary
+                .iter()
+                .map(|name| req.headers().get(name).cloned())
+                .collect(),
+        }
+    }
+
+    fn get(&self, key: &Key, headers: &http::header::HeaderMap) -> Option<Arc<Entry>> {
+        let entry = self.entries.lock().by_key.get(key).cloned()?;
+        entry
+            .vary
+            .iter()
+            .all(|(name, value)| headers.get(name) == value.as_ref())
+            .then_some(entry)
+    }
+
+    /// Caches a response if it may be stored, returning a response to the
+    /// request.
+    async fn insert(
+        &self,
+        key: Key,
+        req_headers: &http::header::HeaderMap,
+        rsp: http::Response<http::BoxBody>,
+        now: Instant,
+    ) -> Result<http::Response<http::BoxBody>, Error> {
+        let (max_age, vary) = match self.storable(&rsp, req_headers) {
+            Some(storable) => storable,
+            None => return Ok(rsp),
+        };
+
+        let (parts, mut body) = rsp.into_parts();
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = body.data().await {
+            buf.put(chunk?);
+        }
+        let body = buf.freeze();
+
+        let entry = Entry {
+            status: parts.status,
+            version: parts.version,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+            vary,
+            etag: parts.headers.get(http::header::ETAG).cloned(),
+            stored_at: now,
+            max_age,
+        };
+        self.entries
+            .lock()
+            .insert(key, entry, self.config.max_bytes);
+
+        Ok(http::Response::from_parts(parts, http::BoxBody::from(body)))
+    }
+
+    /// Returns the response's freshness lifetime and the request headers that
+    /// it varies by, if it may be cached.
+    #[allow(clippy::type_complexity)]
+    fn storable(
+        &self,
+        rsp: &http::Response<http::BoxBody>,
+        req_headers: &http::header::HeaderMap,
+    ) -> Option<(Duration, Vec<(http::HeaderName, Option<http::HeaderValue>)>)> {
+        if !matches!(rsp.status().as_u16(), 200 | 203 | 300 | 301 | 404 | 410) {
+            return None;
+        }
+
+        let headers = rsp.headers();
+        if headers.contains_key(http::header::SET_COOKIE)
+            || headers.contains_key(http::header::TRAILER)
+        {
+            return None;
+        }
+
+        let cc = CacheControl::parse(headers);
+        if cc.no_store || cc.private {
+            return None;
+        }
+        let max_age = max_age(&cc, headers)?;
+
+        let len = rsp.body().size_hint().upper()?;
+        if len > self.config.max_entry_bytes as u64 || len > self.config.max_bytes as u64 {
+            return None;
+        }
+
+        let mut vary = Vec::new();
+        for value in headers.get_all(http::header::VARY) {
+            for name in value.to_str().ok()?.split(',') {
+                let name = name.trim();
+                if name == "*" {
+                    return None;
+                }
+                let name = http::HeaderName::from_bytes(name.as_bytes()).ok()?;
+                let value = req_headers.get(&name).cloned();
+                vary.push((name, value));
+            }
+        }
+
+        Some((max_age, vary))
+    }
+
+    /// Updates a revalidated entry with the headers of a `304 Not Modified`
+    /// response.
+    fn refresh(&self, key: Key, entry: &Entry, headers: &http::header::HeaderMap) -> Arc<Entry> {
+        let mut updated = entry.headers.clone();
+        for name in headers.keys() {
+            if name == http::header::CONTENT_LENGTH {
+                continue;
+            }
+            updated.remove(name);
+            for value in headers.get_all(name) {
+                updated.append(name.clone(), value.clone());
+            }
+        }
+
+        let max_age = max_age(&CacheControl::parse(&updated), &updated).unwrap_or_default();
+        let entry = Entry {
+            status: entry.status,
+            version: entry.version,
+            etag: updated.get(http::header::ETAG).cloned(),
+            headers: updated,
+            body: entry.body.clone(),
+            vary: entry.vary.clone(),
+            stored_at: Instant::now(),
+            max_age,
+        };
+        self.entries
+            .lock()
+            .insert(key, entry, self.config.max_bytes)
+    }
+}
+
+/// Returns how long a response may be served from the cache. Responses
+/// without an explicit lifetime are only cached if they can be revalidated.
+fn max_age(cc: &CacheControl, headers: &http::header::HeaderMap) -> Option<Duration> {
+    let can_revalidate = headers.contains_key(http::header::ETAG);
+    if cc.no_cache {
+        return can_revalidate.then_some(Duration::ZERO);
+    }
+    match cc.s_maxage.or(cc.max_age) {
+        Some(max_age) if max_age > Duration::ZERO || can_revalidate => Some(max_age),
+        Some(_) => None,
+        None => can_revalidate.then_some(Duration::ZERO),
+    }
+}
+
+// === impl Entries ===
+
+impl Entries {
+    fn insert(&mut self, key: Key, entry: Entry, max_bytes: usize) -> Arc<Entry> {
+        let entry = Arc::new(entry);
+        self.bytes += entry.body.len();
+        if let Some(prior) = self.by_key.insert(key, entry.clone()) {
+            self.bytes -= prior.body.len();
+        }
+
+        while self.bytes > max_bytes {
+            let oldest = self
+                .by_key
+                .iter()
+                .min_by_key(|(_, e)| e.stored_at)
+                .map(|(k, _)| k.clone());
+            match oldest.and_then(|k| self.by_key.remove(&k)) {
+                Some(evicted) => self.bytes -= evicted.body.len(),
+                None => break,
+            }
+        }
+
+        entry
+    }
+}
+
+// === impl Entry ===
+
+impl Entry {
+    fn is_fresh(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.stored_at) < self.max_age
+    }
+
+    fn response(
+        &self,
+        req_headers: &http::header::HeaderMap,
+        now: Instant,
+    ) -> http::Response<http::BoxBody> {
+        let not_modified = match (&self.etag, req_headers.get(http::header::IF_NONE_MATCH)) {
+            (Some(etag), Some(inm)) => etag_matches(inm, etag),
+            _ => false,
+        };
+
+        let mut rsp = if not_modified {
+            let mut rsp = http::Response::new(http::BoxBody::default());
+            *rsp.status_mut() = http::StatusCode::NOT_MODIFIED;
+            *rsp.headers_mut() = self.headers.clone();
+            rsp.headers_mut().remove(http::header::CONTENT_LENGTH);
+            rsp
+        } else {
+            let mut rsp = http::Response::new(http::BoxBody::from(self.body.clone()));
+            *rsp.status_mut() = self.status;
+            *rsp.headers_mut() = self.headers.clone();
+            rsp
+        };
+        *rsp.version_mut() = self.version;
+
+        let age = now.saturating_duration_since(self.stored_at).as_secs();
+        rsp.headers_mut()
+            .insert(http::header::AGE, http::HeaderValue::from(age));
+        rsp
+    }
+}
+
+/// Compares entity tags as described by RFC 9110 for `If-None-Match`, i.e.
+/// weakly.
+fn etag_matches(if_none_match: &http::HeaderValue, etag: &http::HeaderValue) -> bool {
+    fn weak(tag: &str) -> &str {
+        tag.trim().trim_start_matches("W/")
+    }
+
+    let (inm, etag) = match (if_none_match.to_str(), etag.to_str()) {
+        (Ok(inm), Ok(etag)) => (inm, weak(etag)),
+        _ => return false,
+    };
+    inm.trim() == "*" || inm.split(',').any(|tag| weak(tag) == etag)
+}
+
+// === impl CacheControl ===
+
+impl CacheControl {
+    fn parse(headers: &http::header::HeaderMap) -> Self {
+        let mut cc = Self::default();
+        let directives = headers
+            .get_all(http::header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','));
+        for directive in directives {
+            let (name, arg) = match directive.split_once('=') {
+                Some((name, arg)) => (name.trim(), Some(arg.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            let seconds = || arg.and_then(|a| a.parse().ok()).map(Duration::from_secs);
+            match name.to_ascii_lowercase().as_str() {
+                "no-store" => cc.no_store = true,
+                "no-cache" => cc.no_cache = true,
+                "private" => cc.private = true,
+                "max-age" => cc.max_age = seconds(),
+                "s-maxage" => cc.s_maxage = seconds(),
+                _ => {}
+            }
+        }
+        cc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::svc::{Service, ServiceExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn store() -> Arc<Store> {
+        Arc::new(Store {
+            config: policy::http::Cache {
+                max_bytes: 1024,
+                max_entry_bytes: 512,
+                vary: vec![],
+            },
+            metrics: CacheMetricFamilies::default().metrics(&RouteLabels(
+                ParentRef(policy::Meta::new_default("parent")),
+                RouteRef(policy::Meta::new_default("route")),
+            )),
+            entries: Default::default(),
+        })
+    }
+
+    /// Returns a cache over a backend that serves `rsp` and counts the
+    /// requests it receives.
+    fn cache(
+        store: Arc<Store>,
+        rsp: impl Fn(&http::Request<http::BoxBody>) -> http::Response<http::BoxBody> + Send + 'static,
+    ) -> (Cache<svc::BoxHttp>, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let inner = {
+            let calls = calls.clone();
+            svc::mk(move |req: http::Request<http::BoxBody>| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                future::ok::<_, Error>(rsp(&req))
+            })
+        };
+        let cache = Cache {
+            inner: svc::BoxHttp::new(inner),
+            store: Some(store),
+        };
+        (cache, calls)
+    }
+
+    fn response(cache_control: &'static str) -> http::Response<http::BoxBody> {
+        http::Response::builder()
+            .header(http::header::CACHE_CONTROL, cache_control)
+            .header(http::header::ETAG, "\"v1\"")
+            .body(http::BoxBody::from(Bytes::from_static(b"hello")))
+            .unwrap()
+    }
+
+    async fn get(svc: &mut Cache<svc::BoxHttp>) -> http::Response<http::BoxBody> {
+        let req = http::Request::get("http://example.com/")
+            .body(http::BoxBody::default())
+            .unwrap();
+        svc.ready().await.unwrap().call(req).await.unwrap()
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn serves_fresh_responses() {
+        let (mut svc, calls) = cache(store(), |_| response("max-age=10"));
+
+        let rsp = get(&mut svc).await;
+        assert_eq!(rsp.status(), http::StatusCode::OK);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        let rsp = get(&mut svc).await;
+        assert_eq!(rsp.status(), http::StatusCode::OK);
+        assert_eq!(rsp.headers()[http::header::AGE], "5");
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        assert_eq!(body, "hello");
+        assert_eq!(
+            calls.load(Ordering::Relaxed),
+            1,
+            "must be served from cache"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn revalidates_stale_responses() {
+        let (mut svc, calls) = cache(store(), |req| {
+            if req.headers().get(http::header::IF_NONE_MATCH).is_some() {
+                return http::Response::builder()
+                    .status(http::StatusCode::NOT_MODIFIED)
+                    .body(http::BoxBody::default())
+                    .unwrap();
+            }
+            response("max-age=1")
+        });
+
+        get(&mut svc).await;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let rsp = get(&mut svc).await;
+        assert_eq!(rsp.status(), http::StatusCode::OK);
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        assert_eq!(body, "hello");
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        // The revalidated response is fresh again.
+        get(&mut svc).await;
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn does_not_store_private_responses() {
+        let (mut svc, calls) = cache(store(), |_| response("private, max-age=10"));
+
+        get(&mut svc).await;
+        get(&mut svc).await;
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn evicts_oldest_entries() {
+        let mut entries = Entries::default();
+        let now = Instant::now();
+        let entry = |body: &'static [u8], stored_at| Entry {
+            status: http::StatusCode::OK,
+            version: ::http::Version::HTTP_11,
+            headers: Default::default(),
+            body: Bytes::from_static(body),
+            vary: vec![],
+            etag: None,
+            stored_at,
+            max_age: Duration::from_secs(10),
+        };
+        let key = |path: &str| Key {
+            uri: path.parse().unwrap(),
+            vary: vec![],
+        };
+
+        entries.insert(key("/a"), entry(b"aaaa", now), 8);
+        entries.insert(key("/b"), entry(b"bbbb", now + Duration::from_secs(1)), 8);
+        entries.insert(key("/c"), entry(b"cc", now + Duration::from_secs(2)), 8);
+        assert!(!entries.by_key.contains_key(&key("/a")));
+        assert!(entries.by_key.contains_key(&key("/b")));
+        assert!(entries.by_key.contains_key(&key("/c")));
+        assert_eq!(entries.bytes, 6);
+    }
+}
@@ -0,0 +1,326 @@
+//! Records requests that are cancelled by their clients.
+//!
+//! A client may go away while its request is in flight, either by dropping
+//! the request's response future or by resetting the request's body stream.
+//! When the request body fails because its client went away, the outbound
+//! request is cancelled immediately rather than once the upstream notices the
+//! reset. Each cancellation is counted on the request's route so that it is
+//! not mistaken for a failure of the server.
+
+use super::RouteLabels;
+use crate::{ParentRef, RouteRef};
+use linkerd_app_core::{
+    errors::{self, ClientCancelled},
+    metrics::prom,
+    proxy::http::{self, h2::Reason, HasH2Reason, HttpBody},
+    svc, Error,
+};
+use pin_project::{pin_project, pinned_drop};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::oneshot;
+
+#[derive(Clone, Debug, Default)]
+pub struct CancelMetricFamilies {
+    cancelled: prom::Family<RouteLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewRecordCancellations<N> {
+    metrics: CancelMetricFamilies,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordCancellations<S> {
+    cancelled: prom::Counter,
+    inner: S,
+}
+
+#[pin_project(PinnedDrop)]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    /// Notified when the request body fails because the client went away.
+    /// Unset once the response completes.
+    reset: Option<oneshot::Receiver<()>>,
+    cancelled: prom::Counter,
+    done: bool,
+}
+
+#[pin_project]
+struct CancelBody {
+    #[pin]
+    inner: http::BoxBody,
+    reset: Option<oneshot::Sender<()>>,
+}
+
+// === impl CancelMetricFamilies ===
+
+impl CancelMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let cancelled = prom::Family::default();
+        reg.register(
+            "client_cancelled",
+            "The total number of requests cancelled by their clients before a response was received",
+            cancelled.clone(),
+        );
+        Self { cancelled }
+    }
+}
+
+// === impl NewRecordCancellations ===
+
+impl<N> NewRecordCancellations<N> {
+    pub fn layer(metrics: CancelMetricFamilies) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRecordCancellations<N>
+where
+    T: svc::Param<ParentRef> + svc::Param<RouteRef>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordCancellations<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let cancelled = self
+            .metrics
+            .cancelled
+            .get_or_create(&RouteLabels(target.param(), target.param()))
+            .clone();
+        RecordCancellations {
+            cancelled,
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl RecordCancellations ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for RecordCancellations<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Error = Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let (tx, rx) = oneshot::channel();
+        let req = req.map(|inner| {
+            http::BoxBody::new(CancelBody {
+                inner,
+                reset: Some(tx),
+            })
+        });
+        ResponseFuture {
+            inner: self.inner.call(req),
+            reset: Some(rx),
+            cancelled: self.cancelled.clone(),
+            done: false,
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<T, F> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Some(reset) = this.reset.as_mut() {
+            match Pin::new(reset).poll(cx) {
+                Poll::Ready(Ok(())) => {
+                    tracing::debug!("Client cancelled the request");
+                    *this.reset = None;
+                    *this.done = true;
+                    this.cancelled.inc();
+                    return Poll::Ready(Err(ClientCancelled.into()));
+                }
+                // The request body completed or was dropped.
+                Poll::Ready(Err(_)) => *this.reset = None,
+                Poll::Pending => {}
+            }
+        }
+
+        let res = futures::ready!(this.inner.poll(cx));
+        *this.done = true;
+        if let Err(error) = res.as_ref() {
+            if errors::is_caused_by::<ClientCancelled>(&**error) {
+                this.cancelled.inc();
+            }
+        }
+        Poll::Ready(res)
+    }
+}
+
+#[pinned_drop]
+impl<F> PinnedDrop for ResponseFuture<F> {
+    fn drop(self: Pin<&mut Self>) {
+        // The client stopped waiting for the response.
+        if !self.done {
+            self.cancelled.inc();
+        }
+    }
+}
+
+// === impl CancelBody ===
+
+impl HttpBody for CancelBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.poll_data(cx));
+        Poll::Ready(res.map(|res| res.map_err(|e| cancel_on_reset(this.reset, e))))
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<::http::HeaderMap>, Self::Error>> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.poll_trailers(cx));
+        Poll::Ready(res.map_err(|e| cancel_on_reset(this.reset, e)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Cancels the response if the request body failed because its client went
+/// away, so that the outbound request is not left waiting on the upstream.
+fn cancel_on_reset(reset: &mut Option<oneshot::Sender<()>>, error: Error) -> Error {
+    if !is_client_reset(&error) {
+        return error;
+    }
+    if let Some(tx) = reset.take() {
+        let _ = tx.send(());
+    }
+    ClientCancelled.into()
+}
+
+/// Returns true if a request body error indicates that the client reset the
+/// stream or closed its connection before the body was complete.
+fn is_client_reset(error: &Error) -> bool {
+    if error.h2_reason() == Some(Reason::CANCEL) {
+        return true;
+    }
+    errors::cause_ref::<std::io::Error>(&**error)
+        .map(|e| e.kind() == std::io::ErrorKind::UnexpectedEof)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use linkerd_app_core::{errors::H2Error, svc::ServiceExt};
+    use linkerd_proxy_client_policy::Meta;
+
+    /// A request body that is reset by its client.
+    struct ResetBody;
+
+    impl HttpBody for ResetBody {
+        type Data = Bytes;
+        type Error = H2Error;
+
+        fn poll_data(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Option<Result<Bytes, H2Error>>> {
+            Poll::Ready(Some(Err(Reason::CANCEL.into())))
+        }
+
+        fn poll_trailers(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Result<Option<::http::HeaderMap>, H2Error>> {
+            Poll::Ready(Ok(None))
+        }
+    }
+
+    fn record<S>(metrics: &CancelMetricFamilies, inner: S) -> RecordCancellations<S> {
+        RecordCancellations {
+            cancelled: metrics.cancelled.get_or_create(&labels()).clone(),
+            inner,
+        }
+    }
+
+    fn labels() -> RouteLabels {
+        RouteLabels(
+            ParentRef(Meta::new_default("parent")),
+            RouteRef(Meta::new_default("route")),
+        )
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn request_body_reset() {
+        let metrics = CancelMetricFamilies::default();
+        // The upstream reads the request body in the background and never
+        // responds.
+        let svc = record(
+            &metrics,
+            svc::mk(|req: http::Request<http::BoxBody>| {
+                tokio::spawn(async move {
+                    let mut body = req.into_body();
+                    while let Some(Ok(_)) = body.data().await {}
+                });
+                futures::future::pending::<Result<http::Response<http::BoxBody>, Error>>()
+            }),
+        );
+
+        let req = http::Request::new(http::BoxBody::new(ResetBody));
+        let err = svc
+            .oneshot(req)
+            .await
+            .expect_err("request must be cancelled");
+        assert!(err.is::<ClientCancelled>(), "{err}");
+        assert_eq!(metrics.cancelled.get_or_create(&labels()).get(), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn response_dropped() {
+        let metrics = CancelMetricFamilies::default();
+        let mut svc = record(
+            &metrics,
+            svc::mk(|_: http::Request<http::BoxBody>| {
+                futures::future::pending::<Result<http::Response<http::BoxBody>, Error>>()
+            }),
+        );
+
+        let rsp = svc::Service::call(&mut svc, http::Request::new(http::BoxBody::default()));
+        assert_eq!(metrics.cancelled.get_or_create(&labels()).get(), 0);
+        drop(rsp);
+        assert_eq!(metrics.cancelled.get_or_create(&labels()).get(), 1);
+    }
+}
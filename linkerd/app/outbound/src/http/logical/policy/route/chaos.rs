@@ -0,0 +1,245 @@
+//! Injects faults into a route's requests during chaos experiments.
+//!
+//! Faults are enabled for a route by name, typically through the admin
+//! server, without a policy update from the control plane. Every fault has an
+//! expiry so that an abandoned experiment cannot degrade a route indefinitely.
+
+use crate::RouteRef;
+use futures::{future, prelude::*};
+use linkerd_app_core::{proxy::http, svc, Error};
+use parking_lot::RwLock;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time;
+
+/// The faults injected into routes' requests, by route name.
+#[derive(Clone, Debug, Default)]
+pub struct Chaos(Arc<RwLock<HashMap<String, Arc<Active>>>>);
+
+/// Describes a fault injected into a route's requests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fault {
+    /// Delays requests before they are dispatched.
+    pub delay: Option<Duration>,
+
+    /// Fails requests with a synthesized response with this status, after
+    /// any delay.
+    pub abort: Option<http::StatusCode>,
+
+    /// The percentage of the route's requests to which the fault applies.
+    pub percent: u32,
+
+    /// When the fault is disabled.
+    pub expires_at: time::Instant,
+}
+
+#[derive(Debug)]
+struct Active {
+    fault: Fault,
+    requests: AtomicU64,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewInjectFaults<N> {
+    chaos: Chaos,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct InjectFaults<S> {
+    chaos: Chaos,
+    route: RouteRef,
+    inner: S,
+}
+
+type ResponseFuture =
+    Pin<Box<dyn Future<Output = Result<http::Response<http::BoxBody>, Error>> + Send + 'static>>;
+
+// === impl Chaos ===
+
+impl Chaos {
+    /// Injects a fault into the named route's requests until it expires,
+    /// replacing any fault already enabled for the route.
+    pub fn enable(&self, route: impl Into<String>, fault: Fault) {
+        let now = time::Instant::now();
+        let mut routes = self.0.write();
+        routes.retain(|_, active| active.fault.expires_at > now);
+        routes.insert(
+            route.into(),
+            Arc::new(Active {
+                fault,
+                requests: AtomicU64::new(0),
+            }),
+        );
+    }
+
+    /// Stops injecting faults into the named route's requests, returning
+    /// whether a fault was enabled.
+    pub fn disable(&self, route: &str) -> bool {
+        let now = time::Instant::now();
+        self.0
+            .write()
+            .remove(route)
+            .map_or(false, |active| active.fault.expires_at > now)
+    }
+
+    /// Lists the faults that have not yet expired, by route name.
+    pub fn faults(&self) -> Vec<(String, Fault)> {
+        let now = time::Instant::now();
+        let mut faults = self
+            .0
+            .read()
+            .iter()
+            .filter(|(_, active)| active.fault.expires_at > now)
+            .map(|(route, active)| (route.clone(), active.fault.clone()))
+            .collect::<Vec<_>>();
+        faults.sort_by(|(a, _), (b, _)| a.cmp(b));
+        faults
+    }
+
+    /// Returns the fault to inject into a request on the named route, if any.
+    fn get(&self, route: &str) -> Option<Fault> {
+        let active = self.0.read().get(route).cloned()?;
+        if active.fault.expires_at <= time::Instant::now() {
+            return None;
+        }
+        active.applies().then(|| active.fault.clone())
+    }
+}
+
+// === impl Active ===
+
+impl Active {
+    /// Spreads faults evenly over the route's requests, so that exactly
+    /// `percent` of every hundred requests are affected.
+    fn applies(&self) -> bool {
+        let n = self.requests.fetch_add(1, Ordering::Relaxed);
+        let percent = u64::from(self.fault.percent.min(100));
+        (n + 1) * percent / 100 > n * percent / 100
+    }
+}
+
+// === impl NewInjectFaults ===
+
+impl<N> NewInjectFaults<N> {
+    pub(crate) fn layer(chaos: Chaos) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            chaos: chaos.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewInjectFaults<N>
+where
+    T: svc::Param<RouteRef>,
+    N: svc::NewService<T>,
+{
+    type Service = InjectFaults<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        InjectFaults {
+            chaos: self.chaos.clone(),
+            route: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl InjectFaults ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for InjectFaults<S>
+where
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+    S: Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::Either<S::Future, ResponseFuture>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let RouteRef(meta) = &self.route;
+        let Some(fault) = self.chaos.get(meta.name()) else {
+            return future::Either::Left(self.inner.call(req));
+        };
+        tracing::debug!(route = %meta.name(), ?fault.delay, ?fault.abort, "Injecting fault");
+
+        if fault.delay.is_none() && fault.abort.is_none() {
+            return future::Either::Left(self.inner.call(req));
+        }
+
+        // Take the service that was driven to readiness.
+        let svc = self.inner.clone();
+        let mut svc = std::mem::replace(&mut self.inner, svc);
+        future::Either::Right(Box::pin(async move {
+            if let Some(delay) = fault.delay {
+                time::sleep(delay).await;
+            }
+            if let Some(status) = fault.abort {
+                let mut rsp = http::Response::new(http::BoxBody::default());
+                *rsp.status_mut() = status;
+                return Ok(rsp);
+            }
+            svc.call(req).await
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fault(percent: u32) -> Fault {
+        Fault {
+            delay: None,
+            abort: Some(http::StatusCode::SERVICE_UNAVAILABLE),
+            percent,
+            expires_at: time::Instant::now() + Duration::from_secs(60),
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn faults_expire() {
+        let chaos = Chaos::default();
+        chaos.enable("route", fault(100));
+        assert!(chaos.get("route").is_some());
+        assert!(chaos.get("other").is_none());
+        assert_eq!(chaos.faults().len(), 1);
+
+        time::advance(Duration::from_secs(61)).await;
+        assert!(chaos.get("route").is_none());
+        assert!(chaos.faults().is_empty());
+        assert!(!chaos.disable("route"));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn faults_apply_to_a_percentage_of_requests() {
+        let chaos = Chaos::default();
+        for percent in [0, 10, 33, 100] {
+            chaos.enable("route", fault(percent));
+            let injected = (0..300).filter(|_| chaos.get("route").is_some()).count();
+            assert_eq!(injected, 3 * percent as usize, "percent={percent}");
+        }
+
+        assert!(chaos.disable("route"));
+        assert!(chaos.get("route").is_none());
+    }
+}
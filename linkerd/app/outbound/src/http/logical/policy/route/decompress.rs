@@ -0,0 +1,303 @@
+//! Decompresses a route's gzip-encoded request bodies.
+//!
+//! Requests are forwarded with their decompressed bodies, so that body size
+//! limits and body-dependent filters operate on the decompressed content.
+//! Bodies are decompressed as they are streamed and fail once they expand
+//! beyond the route's configured limits.
+
+use bytes::{Buf, Bytes};
+use linkerd_app_core::{
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use linkerd_proxy_client_policy as policy;
+use pin_project::pin_project;
+use std::{
+    io::Write,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Compressed data is written to the decoder in slices of this size, so that
+/// the expansion of each slice is bounded before limits are checked.
+const WRITE_SIZE: usize = 1024;
+
+/// The expansion ratio is not enforced until the decompressed body exceeds
+/// this size, since small bodies may legitimately expand far beyond the
+/// ratio.
+const MIN_RATIO_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct NewDecompress<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct Decompress<S> {
+    inner: S,
+    config: Option<policy::http::Decompression>,
+}
+
+#[pin_project]
+struct DecompressBody {
+    #[pin]
+    inner: http::BoxBody,
+    decoder: Option<flate2::write::GzDecoder<Vec<u8>>>,
+    config: policy::http::Decompression,
+    compressed_bytes: usize,
+    decompressed_bytes: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("decompressed request body exceeds {0} bytes")]
+pub struct DecompressedBodyTooLarge(usize);
+
+#[derive(Debug, thiserror::Error)]
+#[error("request body expanded more than {0} times its compressed size")]
+pub struct ExpansionRatioExceeded(u32);
+
+// === impl NewDecompress ===
+
+impl<N> NewDecompress<N> {
+    pub fn layer() -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewDecompress<N>
+where
+    T: svc::Param<Option<policy::http::Decompression>>,
+    N: svc::NewService<T>,
+{
+    type Service = Decompress<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let config = target.param();
+        let inner = self.inner.new_service(target);
+        Decompress { inner, config }
+    }
+}
+
+// === impl Decompress ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for Decompress<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        let config = match self.config.as_ref() {
+            Some(config) if is_gzip(req.headers()) => config.clone(),
+            _ => return self.inner.call(req),
+        };
+
+        tracing::trace!(uri = %req.uri(), "Decompressing request body");
+        req.headers_mut().remove(http::header::CONTENT_ENCODING);
+        req.headers_mut().remove(http::header::CONTENT_LENGTH);
+        let req = req.map(|inner| {
+            http::BoxBody::new(DecompressBody {
+                inner,
+                decoder: Some(flate2::write::GzDecoder::new(Vec::new())),
+                config,
+                compressed_bytes: 0,
+                decompressed_bytes: 0,
+            })
+        });
+        self.inner.call(req)
+    }
+}
+
+/// Returns true if the request's body is encoded only with gzip. Bodies with
+/// other or multiple encodings are forwarded unmodified.
+fn is_gzip(headers: &http::header::HeaderMap) -> bool {
+    let mut encodings = headers.get_all(http::header::CONTENT_ENCODING).iter();
+    match (encodings.next(), encodings.next()) {
+        (Some(enc), None) => enc
+            .to_str()
+            .map(|e| {
+                let e = e.trim();
+                e.eq_ignore_ascii_case("gzip") || e.eq_ignore_ascii_case("x-gzip")
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+// === impl DecompressBody ===
+
+impl HttpBody for DecompressBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn is_end_stream(&self) -> bool {
+        self.decoder.is_none()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        loop {
+            let decoder = match this.decoder.as_mut() {
+                Some(decoder) => decoder,
+                None => return Poll::Ready(None),
+            };
+
+            match futures::ready!(this.inner.as_mut().poll_data(cx)) {
+                Some(Ok(mut data)) => {
+                    while data.has_remaining() {
+                        let chunk = data.chunk();
+                        let len = chunk.len().min(WRITE_SIZE);
+                        decoder.write_all(&chunk[..len])?;
+                        data.advance(len);
+                        *this.compressed_bytes += len;
+                        let decompressed = *this.decompressed_bytes + decoder.get_ref().len();
+                        check_limits(this.config, *this.compressed_bytes, decompressed)?;
+                    }
+                }
+                Some(Err(error)) => {
+                    *this.decoder = None;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                None => {
+                    if let Some(decoder) = this.decoder.take() {
+                        let out = decoder.finish()?;
+                        *this.decompressed_bytes += out.len();
+                        check_limits(
+                            this.config,
+                            *this.compressed_bytes,
+                            *this.decompressed_bytes,
+                        )?;
+                        if !out.is_empty() {
+                            return Poll::Ready(Some(Ok(out.into())));
+                        }
+                    }
+                    return Poll::Ready(None);
+                }
+            }
+
+            // The decoder may need more input before it produces any output.
+            let out = mem::take(decoder.get_mut());
+            if !out.is_empty() {
+                *this.decompressed_bytes += out.len();
+                return Poll::Ready(Some(Ok(out.into())));
+            }
+        }
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+}
+
+fn check_limits(
+    config: &policy::http::Decompression,
+    compressed: usize,
+    decompressed: usize,
+) -> Result<(), Error> {
+    if let Some(max) = config.max_bytes {
+        if decompressed > max {
+            return Err(DecompressedBodyTooLarge(max).into());
+        }
+    }
+    let ratio = config.max_expansion_ratio as usize;
+    if decompressed > MIN_RATIO_BYTES && decompressed > compressed.saturating_mul(ratio) {
+        return Err(ExpansionRatioExceeded(config.max_expansion_ratio).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::svc::{Service, ServiceExt};
+
+    fn gzip(data: &[u8]) -> Bytes {
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap().into()
+    }
+
+    /// Sends a gzip-encoded request with `body` through a decompressing
+    /// service and returns the body received by the backend.
+    async fn decompress(config: policy::http::Decompression, body: &[u8]) -> Result<Bytes, Error> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let mut tx = Some(tx);
+        let inner = svc::mk(move |req: http::Request<http::BoxBody>| {
+            assert!(req.headers().get(http::header::CONTENT_ENCODING).is_none());
+            if let Some(tx) = tx.take() {
+                let _ = tx.send(req.into_body());
+            }
+            futures::future::ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+        });
+        let mut svc = Decompress {
+            inner,
+            config: Some(config),
+        };
+
+        let req = http::Request::builder()
+            .header(http::header::CONTENT_ENCODING, "gzip")
+            .body(http::BoxBody::from(gzip(body)))
+            .unwrap();
+        svc.ready().await.unwrap().call(req).await.unwrap();
+        let body = rx.await.unwrap();
+        hyper::body::to_bytes(body).await
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn decompresses_gzip_bodies() {
+        let body = b"hello world! ".repeat(100);
+        let decompressed = decompress(
+            policy::http::Decompression {
+                max_bytes: None,
+                max_expansion_ratio: 100,
+            },
+            &body,
+        )
+        .await
+        .expect("body must be decompressed");
+        assert_eq!(decompressed, body);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn fails_bodies_that_exceed_max_bytes() {
+        let err = decompress(
+            policy::http::Decompression {
+                max_bytes: Some(1000),
+                max_expansion_ratio: 100,
+            },
+            &[b'a'; 2000],
+        )
+        .await
+        .expect_err("body must exceed limit");
+        assert!(err.is::<DecompressedBodyTooLarge>());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn fails_bodies_that_exceed_expansion_ratio() {
+        let err = decompress(
+            policy::http::Decompression {
+                max_bytes: None,
+                max_expansion_ratio: 10,
+            },
+            &vec![0; 1024 * 1024],
+        )
+        .await
+        .expect_err("body must exceed expansion ratio");
+        assert!(err.is::<ExpansionRatioExceeded>());
+    }
+}
@@ -69,6 +69,21 @@ pub mod errors {
         pub message: Arc<str>,
     }
 
+    /// Indicates that a request was answered by a direct-response filter.
+    ///
+    /// This is not a failure: [`ApplyFilters`] replaces it with the filter's
+    /// response.
+    #[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+    #[error("HTTP request answered directly with {}", .0.status)]
+    pub struct HttpRouteDirectResponse(pub http::filter::DirectResponse);
+
+    /// Indicates that a request's body exceeds its route's size limit.
+    #[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+    #[error("request body exceeds {max_bytes} bytes")]
+    pub struct HttpRouteBodyTooLarge {
+        pub max_bytes: u64,
+    }
+
     #[derive(Debug, thiserror::Error)]
     #[error("invalid client policy: {0}")]
     pub struct HttpInvalidPolicy(pub &'static str);
@@ -108,7 +123,17 @@ pub fn apply_http_request<B>(
             },
 
             http::Filter::RequestHeaders(rh) => {
-                rh.apply(req.headers_mut());
+                rh.apply_with_captures(req.headers_mut(), r#match.route().captures());
+            }
+
+            http::Filter::DirectResponse(direct) => {
+                return Err(errors::HttpRouteDirectResponse(direct.clone()).into());
+            }
+
+            http::Filter::Extension(ext) => {
+                if let Some(direct) = ext.apply_request(req.headers_mut())? {
+                    return Err(errors::HttpRouteDirectResponse(direct).into());
+                }
             }
 
             http::Filter::InternalError(msg) => {
@@ -131,6 +156,8 @@ pub fn apply_http_response<B>(
             http::Filter::InjectFailure(_) => {} // InjectFailure filter does not apply to responses.
             http::Filter::Redirect(_) => {}      // Redirect filter does not apply to responses.
             http::Filter::RequestHeaders(_) => {} // RequestHeaders filter does not apply to responses.
+            http::Filter::DirectResponse(_) => {} // DirectResponse filter does not apply to responses.
+            http::Filter::Extension(_) => {}      // Extension filters do not apply to responses.
             http::Filter::InternalError(_) => {} // InternalError filter does not apply to responses.
             http::Filter::ResponseHeaders(rh) => rh.apply(rsp.headers_mut()),
         }
@@ -215,6 +242,7 @@ where
 
 impl<B, A, S> svc::Service<::http::Request<B>> for ApplyFilters<A, S>
 where
+    B: From<bytes::Bytes>,
     A: Apply + Clone,
     S: svc::Service<::http::Request<B>, Response = ::http::Response<B>>,
     S::Error: Into<Error>,
@@ -233,7 +261,16 @@ where
 
     fn call(&mut self, mut req: ::http::Request<B>) -> Self::Future {
         if let Err(e) = self.apply.apply_request(&mut req) {
-            return future::Either::Left(future::err(e));
+            // Direct responses are returned in place of the inner service's
+            // response, so response filters still apply to them.
+            let res = match e.downcast::<errors::HttpRouteDirectResponse>() {
+                Ok(direct) => {
+                    let mut rsp = direct.0.response();
+                    self.apply.apply_response(&mut rsp).map(move |()| rsp)
+                }
+                Err(e) => Err(e),
+            };
+            return future::Either::Left(future::ready(res));
         }
         let rsp = ResponseFuture {
             apply: self.apply.clone(),
@@ -0,0 +1,415 @@
+//! Message-level metrics for gRPC streams.
+//!
+//! Request and response counts describe when streams open and close, but not
+//! whether a long-lived streaming RPC is making progress. These metrics count
+//! the length-prefixed gRPC messages sent and received on each stream, and
+//! record how long each stream waits for its first response message.
+
+use super::RouteLabels;
+use crate::{ParentRef, RouteRef};
+use bytes::Buf;
+use futures::{future, ready, TryFuture};
+use linkerd_app_core::{
+    metrics::prom,
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    io::IoSlice,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::time::Instant;
+
+/// The maximum number of buffer chunks inspected when decoding message frames.
+const MAX_CHUNKS: usize = 64;
+
+/// The length of a gRPC message's prefix: a compression flag followed by a
+/// 32-bit big-endian length.
+const PREFIX_LEN: usize = 5;
+
+#[derive(Clone, Debug)]
+pub struct MessageMetricFamilies {
+    request_messages: prom::Family<RouteLabels, prom::Counter>,
+    response_messages: prom::Family<RouteLabels, prom::Counter>,
+    first_message: prom::Family<RouteLabels, prom::Histogram, fn() -> prom::Histogram>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MessageMetrics {
+    request_messages: prom::Counter,
+    response_messages: prom::Counter,
+    first_message: prom::Histogram,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewRecordMessages<N> {
+    inner: N,
+    families: Option<MessageMetricFamilies>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordMessages<S> {
+    inner: S,
+    metrics: Option<MessageMetrics>,
+}
+
+#[pin_project]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    metrics: Option<MessageMetrics>,
+    started_at: Instant,
+}
+
+/// Counts the messages in a request body.
+#[pin_project]
+struct RequestBody {
+    #[pin]
+    inner: http::BoxBody,
+    messages: prom::Counter,
+    frames: Frames,
+}
+
+/// Counts the messages in a response body, recording the time at which the
+/// first message completes.
+#[pin_project]
+struct ResponseBody {
+    #[pin]
+    inner: http::BoxBody,
+    metrics: MessageMetrics,
+    started_at: Option<Instant>,
+    frames: Frames,
+}
+
+/// Decodes gRPC message boundaries from a stream of bytes.
+#[derive(Debug, Default)]
+struct Frames {
+    prefix: [u8; PREFIX_LEN],
+    prefix_read: usize,
+    message_remaining: usize,
+}
+
+// === impl MessageMetricFamilies ===
+
+impl MessageMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let request_messages = prom::Family::default();
+        reg.register(
+            "request_messages",
+            "The total number of gRPC messages sent by clients on route streams",
+            request_messages.clone(),
+        );
+
+        let response_messages = prom::Family::default();
+        reg.register(
+            "response_messages",
+            "The total number of gRPC messages received from servers on route streams",
+            response_messages.clone(),
+        );
+
+        let first_message =
+            prom::Family::<_, _, fn() -> prom::Histogram>::new_with_constructor(|| {
+                prom::Histogram::new(
+                    [
+                        0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+                    ]
+                    .iter()
+                    .copied(),
+                )
+            });
+        reg.register_with_unit(
+            "first_message",
+            "The time from the start of a route stream until its first response message",
+            prom::Unit::Seconds,
+            first_message.clone(),
+        );
+
+        Self {
+            request_messages,
+            response_messages,
+            first_message,
+        }
+    }
+
+    fn metrics(&self, labels: &RouteLabels) -> MessageMetrics {
+        MessageMetrics {
+            request_messages: self.request_messages.get_or_create(labels).clone(),
+            response_messages: self.response_messages.get_or_create(labels).clone(),
+            first_message: self.first_message.get_or_create(labels).clone(),
+        }
+    }
+}
+
+// === impl NewRecordMessages ===
+
+impl<N> NewRecordMessages<N> {
+    /// Records message metrics for each route, if `families` is set.
+    pub fn layer(
+        families: Option<MessageMetricFamilies>,
+    ) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            families: families.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRecordMessages<N>
+where
+    T: svc::Param<ParentRef> + svc::Param<RouteRef>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordMessages<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let metrics = self
+            .families
+            .as_ref()
+            .map(|f| f.metrics(&RouteLabels(target.param(), target.param())));
+        let inner = self.inner.new_service(target);
+        RecordMessages { inner, metrics }
+    }
+}
+
+// === impl RecordMessages ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for RecordMessages<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = future::Either<S::Future, ResponseFuture<S::Future>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let metrics = match self.metrics.clone() {
+            Some(metrics) => metrics,
+            None => return future::Either::Left(self.inner.call(req)),
+        };
+
+        let messages = metrics.request_messages.clone();
+        let req = req.map(|inner| {
+            http::BoxBody::new(RequestBody {
+                inner,
+                messages,
+                frames: Frames::default(),
+            })
+        });
+        future::Either::Right(ResponseFuture {
+            inner: self.inner.call(req),
+            metrics: Some(metrics),
+            started_at: Instant::now(),
+        })
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: TryFuture<Ok = http::Response<http::BoxBody>>,
+{
+    type Output = Result<http::Response<http::BoxBody>, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = ready!(this.inner.try_poll(cx))?;
+        let metrics = this
+            .metrics
+            .take()
+            .expect("future must not be polled after ready");
+        let started_at = *this.started_at;
+        Poll::Ready(Ok(rsp.map(|inner| {
+            http::BoxBody::new(ResponseBody {
+                inner,
+                metrics,
+                started_at: Some(started_at),
+                frames: Frames::default(),
+            })
+        })))
+    }
+}
+
+// === impl RequestBody ===
+
+impl HttpBody for RequestBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let data = ready!(this.inner.poll_data(cx));
+        if let Some(Ok(ref data)) = data {
+            let messages = this.frames.decode(data);
+            if messages > 0 {
+                this.messages.inc_by(messages);
+            }
+        }
+        Poll::Ready(data)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<::http::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl ResponseBody ===
+
+impl HttpBody for ResponseBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let data = ready!(this.inner.poll_data(cx));
+        if let Some(Ok(ref data)) = data {
+            let messages = this.frames.decode(data);
+            if messages > 0 {
+                this.metrics.response_messages.inc_by(messages);
+                if let Some(started_at) = this.started_at.take() {
+                    this.metrics.first_message.observe(
+                        Instant::now()
+                            .saturating_duration_since(started_at)
+                            .as_secs_f64(),
+                    );
+                }
+            }
+        }
+        Poll::Ready(data)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<::http::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl Frames ===
+
+impl Frames {
+    /// Advances the decoder over a buffer without consuming it, returning the
+    /// number of messages that were completed.
+    fn decode(&mut self, buf: &impl Buf) -> u64 {
+        let mut chunks = [IoSlice::new(&[]); MAX_CHUNKS];
+        let n = buf.chunks_vectored(&mut chunks);
+        chunks[..n]
+            .iter()
+            .map(|chunk| self.decode_chunk(chunk))
+            .sum()
+    }
+
+    fn decode_chunk(&mut self, mut chunk: &[u8]) -> u64 {
+        let mut messages = 0;
+        while !chunk.is_empty() {
+            if self.message_remaining > 0 {
+                let n = self.message_remaining.min(chunk.len());
+                self.message_remaining -= n;
+                chunk = &chunk[n..];
+                if self.message_remaining == 0 {
+                    messages += 1;
+                }
+                continue;
+            }
+
+            let n = (PREFIX_LEN - self.prefix_read).min(chunk.len());
+            self.prefix[self.prefix_read..self.prefix_read + n].copy_from_slice(&chunk[..n]);
+            self.prefix_read += n;
+            chunk = &chunk[n..];
+            if self.prefix_read == PREFIX_LEN {
+                self.prefix_read = 0;
+                let [_, a, b, c, d] = self.prefix;
+                self.message_remaining = u32::from_be_bytes([a, b, c, d]) as usize;
+                if self.message_remaining == 0 {
+                    messages += 1;
+                }
+            }
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(len: u32) -> Vec<u8> {
+        let mut msg = vec![0];
+        msg.extend_from_slice(&len.to_be_bytes());
+        msg.resize(PREFIX_LEN + len as usize, 0xff);
+        msg
+    }
+
+    #[test]
+    fn decodes_whole_messages() {
+        let mut frames = Frames::default();
+        let buf = [message(3), message(0), message(10)].concat();
+        assert_eq!(frames.decode(&bytes::Bytes::from(buf)), 3);
+    }
+
+    #[test]
+    fn decodes_split_messages() {
+        let mut frames = Frames::default();
+        let buf = [message(4), message(2)].concat();
+        // Split within the first message's prefix and within the second
+        // message's body.
+        assert_eq!(frames.decode(&bytes::Bytes::copy_from_slice(&buf[..2])), 0);
+        assert_eq!(
+            frames.decode(&bytes::Bytes::copy_from_slice(&buf[2..10])),
+            1
+        );
+        assert_eq!(
+            frames.decode(&bytes::Bytes::copy_from_slice(&buf[10..14])),
+            0
+        );
+        assert_eq!(frames.decode(&bytes::Bytes::copy_from_slice(&buf[14..])), 1);
+    }
+
+    #[test]
+    fn decodes_chained_buffers() {
+        let mut frames = Frames::default();
+        let msg = message(6);
+        let buf = bytes::Bytes::copy_from_slice(&msg[..3]).chain(&msg[3..]);
+        assert_eq!(frames.decode(&buf), 1);
+    }
+}
@@ -0,0 +1,449 @@
+//! Mirrors a route's requests to a shadow backend and compares responses.
+//!
+//! Once the route's response headers are received, a copy of the request is
+//! sent to the mirror's shadow backend in the background. When both response
+//! bodies have completed, the responses are compared by status, by a
+//! configured set of headers, and by a hash of their bodies, and the result is
+//! recorded. Shadow responses are never returned to clients.
+//!
+//! Request bodies are buffered so that they can be replayed to the shadow
+//! backend; requests with bodies too large to buffer are not mirrored.
+
+use super::{super::super::Concrete, RouteLabels};
+use bytes::{Buf, Bytes};
+use futures::{future, prelude::*};
+use linkerd_app_core::{
+    metrics::prom::{self, encoding::*, EncodeLabelSetMut},
+    proxy::http::{self, HttpBody},
+    svc::{self, ServiceExt},
+    Error,
+};
+use linkerd_http_retry::ReplayBody;
+use pin_project::pin_project;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::Hasher,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::oneshot;
+use tracing::Instrument;
+
+/// Configures a route's mirror.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mirror<T> {
+    pub(crate) concrete: Concrete<T>,
+    pub(crate) compare_headers: Arc<[http::HeaderName]>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MirrorMetricFamilies {
+    comparisons: prom::Family<ComparisonLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewMirror<C, N> {
+    concrete: C,
+    families: MirrorMetricFamilies,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct MirrorService<S, M> {
+    shadow: Option<Shadow<M>>,
+    inner: S,
+}
+
+#[derive(Clone, Debug)]
+struct Shadow<M> {
+    svc: M,
+    compare_headers: Arc<[http::HeaderName]>,
+    metrics: ComparisonMetrics,
+}
+
+#[derive(Clone, Debug)]
+struct ComparisonMetrics {
+    family: prom::Family<ComparisonLabels, prom::Counter>,
+    route: RouteLabels,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct ComparisonLabels {
+    route: RouteLabels,
+    result: Comparison,
+}
+
+/// The result of comparing a route's response with its shadow's.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum Comparison {
+    Match,
+    Status,
+    Headers,
+    Body,
+    Error,
+}
+
+/// Describes a response for comparison.
+#[derive(Debug, PartialEq, Eq)]
+struct Summary {
+    status: http::StatusCode,
+    headers: Vec<Option<http::HeaderValue>>,
+    body: u64,
+}
+
+/// Hashes a response body as it is read, sending the hash once the body
+/// completes.
+#[pin_project]
+struct HashBody {
+    #[pin]
+    inner: http::BoxBody,
+    hasher: DefaultHasher,
+    tx: Option<oneshot::Sender<u64>>,
+}
+
+type ResponseFuture =
+    Pin<Box<dyn Future<Output = Result<http::Response<http::BoxBody>, Error>> + Send + 'static>>;
+
+/// Allow buffering request bodies up to 64 kb
+const MAX_BUFFERED_BYTES: usize = 64 * 1024;
+
+// === impl MirrorMetricFamilies ===
+
+impl MirrorMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let comparisons = prom::Family::default();
+        reg.register(
+            "comparisons",
+            "The total number of a route's responses compared with its mirror's responses, by result",
+            comparisons.clone(),
+        );
+        Self { comparisons }
+    }
+}
+
+// === impl ComparisonMetrics ===
+
+impl ComparisonMetrics {
+    fn record(&self, result: Comparison) {
+        self.family
+            .get_or_create(&ComparisonLabels {
+                route: self.route.clone(),
+                result,
+            })
+            .inc();
+    }
+}
+
+// === impl ComparisonLabels ===
+
+impl EncodeLabelSet for ComparisonLabels {
+    fn encode(&self, mut enc: LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.route.encode_label_set(&mut enc)?;
+        ("result", self.result).encode(enc.encode_label())
+    }
+}
+
+// === impl NewMirror ===
+
+impl<C: Clone, N> NewMirror<C, N> {
+    pub fn layer(
+        concrete: C,
+        families: MirrorMetricFamilies,
+    ) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            concrete: concrete.clone(),
+            families: families.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, M, F, E, C, N> svc::NewService<super::MatchedRoute<T, M, F, E>> for NewMirror<C, N>
+where
+    T: Clone,
+    C: svc::NewService<Concrete<T>>,
+    N: svc::NewService<super::MatchedRoute<T, M, F, E>>,
+{
+    type Service = MirrorService<N::Service, C::Service>;
+
+    fn new_service(&self, target: super::MatchedRoute<T, M, F, E>) -> Self::Service {
+        let shadow = target.params.mirror.clone().map(|mirror| {
+            tracing::debug!(backend = ?mirror.concrete.backend_ref, "Mirroring route");
+            Shadow {
+                svc: self.concrete.new_service(mirror.concrete),
+                compare_headers: mirror.compare_headers,
+                metrics: ComparisonMetrics {
+                    family: self.families.comparisons.clone(),
+                    route: RouteLabels(
+                        target.params.parent_ref.clone(),
+                        target.params.route_ref.clone(),
+                    ),
+                },
+            }
+        });
+        let inner = self.inner.new_service(target);
+        MirrorService { shadow, inner }
+    }
+}
+
+// === impl MirrorService ===
+
+impl<S, M> svc::Service<http::Request<http::BoxBody>> for MirrorService<S, M>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+    M: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+    M: Clone + Send + 'static,
+    M::Future: Send,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::Either<future::ErrInto<S::Future, Error>, ResponseFuture>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let Some(shadow) = self.shadow.clone() else {
+            return future::Either::Left(self.inner.call(req).err_into());
+        };
+
+        let (parts, body) = req.into_parts();
+        let body = match ReplayBody::try_new(body, MAX_BUFFERED_BYTES) {
+            Ok(body) => body,
+            Err(body) => {
+                tracing::debug!("Request body too large to mirror");
+                let req = http::Request::from_parts(parts, body);
+                return future::Either::Left(self.inner.call(req).err_into());
+            }
+        };
+
+        let mut shadow_req = http::Request::new(http::BoxBody::new(body.clone()));
+        *shadow_req.method_mut() = parts.method.clone();
+        *shadow_req.uri_mut() = parts.uri.clone();
+        *shadow_req.version_mut() = parts.version;
+        *shadow_req.headers_mut() = parts.headers.clone();
+
+        let req = http::Request::from_parts(parts, http::BoxBody::new(body));
+        let rsp = self.inner.call(req).err_into::<Error>();
+        future::Either::Right(Box::pin(async move {
+            // The shadow request is only sent once the route's response is
+            // received, so that the route's request body is replayed to it
+            // rather than contended for.
+            let rsp = rsp.await?;
+            Ok(shadow.compare(rsp, shadow_req))
+        }))
+    }
+}
+
+// === impl Shadow ===
+
+impl<M> Shadow<M>
+where
+    M: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+    M: Send + 'static,
+    M::Future: Send,
+{
+    /// Sends the request to the shadow backend and compares its response
+    /// with the route's response once both have completed.
+    fn compare(
+        self,
+        rsp: http::Response<http::BoxBody>,
+        req: http::Request<http::BoxBody>,
+    ) -> http::Response<http::BoxBody> {
+        let Self {
+            svc,
+            compare_headers,
+            metrics,
+        } = self;
+
+        let status = rsp.status();
+        let headers = select(rsp.headers(), &compare_headers);
+        let (tx, rx) = oneshot::channel();
+        let rsp = rsp.map(|inner| {
+            http::BoxBody::new(HashBody {
+                inner,
+                hasher: DefaultHasher::new(),
+                tx: Some(tx),
+            })
+        });
+
+        tokio::spawn(
+            async move {
+                let shadow = async {
+                    let rsp = svc.oneshot(req).await?;
+                    let status = rsp.status();
+                    let headers = select(rsp.headers(), &compare_headers);
+                    let mut body = rsp.into_body();
+                    let mut hasher = DefaultHasher::new();
+                    while let Some(data) = body.data().await {
+                        hasher.write(data?.chunk());
+                    }
+                    Ok::<_, Error>(Summary {
+                        status,
+                        headers,
+                        body: hasher.finish(),
+                    })
+                };
+                let (shadow, primary) = future::join(shadow, rx).await;
+
+                // If the route's response body did not complete, e.g. because
+                // the client canceled it, there is nothing to compare.
+                let Ok(body) = primary else { return };
+                let primary = Summary {
+                    status,
+                    headers,
+                    body,
+                };
+                let result = match shadow {
+                    Ok(shadow) => primary.compare(&shadow),
+                    Err(error) => {
+                        tracing::debug!(%error, "Mirrored request failed");
+                        Comparison::Error
+                    }
+                };
+                tracing::trace!(?result, "Compared mirrored response");
+                metrics.record(result);
+            }
+            .in_current_span(),
+        );
+
+        rsp
+    }
+}
+
+fn select(
+    headers: &http::header::HeaderMap,
+    names: &[http::HeaderName],
+) -> Vec<Option<http::HeaderValue>> {
+    names.iter().map(|n| headers.get(n).cloned()).collect()
+}
+
+// === impl Summary ===
+
+impl Summary {
+    fn compare(&self, shadow: &Self) -> Comparison {
+        if self.status != shadow.status {
+            Comparison::Status
+        } else if self.headers != shadow.headers {
+            Comparison::Headers
+        } else if self.body != shadow.body {
+            Comparison::Body
+        } else {
+            Comparison::Match
+        }
+    }
+}
+
+// === impl HashBody ===
+
+impl HttpBody for HashBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let data = match futures::ready!(this.inner.poll_data(cx)) {
+            Some(Ok(mut data)) => data.copy_to_bytes(data.remaining()),
+            Some(Err(error)) => {
+                // Drop the sender so that the response isn't compared.
+                this.tx.take();
+                return Poll::Ready(Some(Err(error)));
+            }
+            None => {
+                if let Some(tx) = this.tx.take() {
+                    let _ = tx.send(this.hasher.finish());
+                }
+                return Poll::Ready(None);
+            }
+        };
+        this.hasher.write(&data);
+        Poll::Ready(Some(Ok(data)))
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(status: u16, header: Option<&'static str>, body: u64) -> Summary {
+        Summary {
+            status: http::StatusCode::from_u16(status).unwrap(),
+            headers: vec![header.map(http::HeaderValue::from_static)],
+            body,
+        }
+    }
+
+    #[test]
+    fn compares_summaries() {
+        let primary = summary(200, Some("a"), 1);
+        assert_eq!(
+            primary.compare(&summary(200, Some("a"), 1)),
+            Comparison::Match
+        );
+        assert_eq!(
+            primary.compare(&summary(500, Some("b"), 2)),
+            Comparison::Status
+        );
+        assert_eq!(primary.compare(&summary(200, None, 2)), Comparison::Headers);
+        assert_eq!(
+            primary.compare(&summary(200, Some("a"), 2)),
+            Comparison::Body
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn hashes_bodies_across_chunks() {
+        let (mut tx, body) = hyper::Body::channel();
+        let (hash_tx, hash_rx) = oneshot::channel();
+        let mut body = HashBody {
+            inner: http::BoxBody::new(body),
+            hasher: DefaultHasher::new(),
+            tx: Some(hash_tx),
+        };
+        tokio::spawn(async move {
+            tx.send_data(Bytes::from_static(b"hello, ")).await.unwrap();
+            tx.send_data(Bytes::from_static(b"world")).await.unwrap();
+        });
+        while let Some(data) = body.data().await {
+            data.unwrap();
+        }
+
+        let mut hasher = DefaultHasher::new();
+        hasher.write(b"hello, world");
+        assert_eq!(hash_rx.await.unwrap(), hasher.finish());
+    }
+}
@@ -0,0 +1,209 @@
+//! Applies legacy ServiceProfile retries and timeouts to policy routes.
+//!
+//! While a workload migrates from ServiceProfiles to HTTPRoutes (or
+//! GRPCRoutes), both may describe the same requests. Rather than choosing one
+//! configuration wholesale, policy routes select the request's backends, and
+//! the ServiceProfile route that matches the request contributes its retry
+//! and timeout configuration:
+//!
+//! - A policy route's request timeout takes precedence over the profile
+//!   route's timeout. When both are configured, the conflict is counted.
+//! - The profile route's retries, classified by its response classes, are
+//!   applied once, on the policy route. The profile's own route stack is not
+//!   built, so requests are never retried by both stacks.
+
+use super::{MatchedRoute, RouteLabels};
+use crate::http::{logical::profile, retry};
+use linkerd_app_core::{
+    metrics::{
+        prom::{self, encoding::*, EncodeLabelSetMut},
+        ProfileRouteLabels,
+    },
+    proxy::http::{self, EraseResponse},
+    svc,
+};
+use linkerd_retry::NewPolicy;
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// The ServiceProfile route that matches a request on a policy route.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ProfileRoute {
+    pub(crate) addr: profile::LogicalAddr,
+    pub(crate) route: profile::Route,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProfileCompatMetricFamilies {
+    conflicts: prom::Family<ConflictLabels, prom::Counter>,
+    retries: retry::NewRetryPolicy,
+}
+
+/// Builds retry policies from the profile routes that match requests.
+#[derive(Clone, Debug)]
+pub(crate) struct NewProfileRetryPolicy(retry::NewRetryPolicy);
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewRecordConflicts<N> {
+    metrics: ProfileCompatMetricFamilies,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RecordConflicts<S> {
+    conflicts: Arc<[prom::Counter]>,
+    inner: S,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct ConflictLabels {
+    route: RouteLabels,
+    field: &'static str,
+}
+
+// === impl ProfileRoute ===
+
+impl svc::Param<profile::Route> for ProfileRoute {
+    fn param(&self) -> profile::Route {
+        self.route.clone()
+    }
+}
+
+impl svc::Param<ProfileRouteLabels> for ProfileRoute {
+    fn param(&self) -> ProfileRouteLabels {
+        ProfileRouteLabels::outbound(self.addr.clone(), &self.route)
+    }
+}
+
+// === impl ProfileCompatMetricFamilies ===
+
+impl ProfileCompatMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let conflicts = prom::Family::default();
+        reg.register(
+            "conflicts",
+            "The total number of requests on which a policy route's configuration took precedence over a conflicting ServiceProfile route",
+            conflicts.clone(),
+        );
+        Self {
+            conflicts,
+            retries: Default::default(),
+        }
+    }
+
+    /// Configures the retries of ServiceProfile routes applied to policy
+    /// routes, which are reported with the legacy profile route retry metrics.
+    pub(crate) fn with_retries(self, retries: retry::NewRetryPolicy) -> Self {
+        Self { retries, ..self }
+    }
+
+    pub(crate) fn retry_layer<N>(
+        &self,
+    ) -> impl svc::Layer<
+        N,
+        Service = linkerd_retry::NewRetry<NewProfileRetryPolicy, N, EraseResponse<()>>,
+    > + Clone {
+        let new_policy = NewProfileRetryPolicy(self.retries.clone());
+        // As in the profile stack, retried and non-retried responses are
+        // unified into a single body type.
+        linkerd_retry::layer(new_policy).with_proxy(EraseResponse::new(()))
+    }
+}
+
+// === impl NewProfileRetryPolicy ===
+
+impl<T, M, F, E> NewPolicy<MatchedRoute<T, M, F, E>> for NewProfileRetryPolicy {
+    type Policy = retry::RetryPolicy;
+
+    fn new_policy(&self, target: &MatchedRoute<T, M, F, E>) -> Option<Self::Policy> {
+        let profile = target.params.profile.as_ref()?;
+        self.0.new_policy(profile)
+    }
+}
+
+// === impl NewRecordConflicts ===
+
+impl<N> NewRecordConflicts<N> {
+    pub(crate) fn layer(
+        metrics: ProfileCompatMetricFamilies,
+    ) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, M, F, E, N> svc::NewService<MatchedRoute<T, M, F, E>> for NewRecordConflicts<N>
+where
+    N: svc::NewService<MatchedRoute<T, M, F, E>>,
+{
+    type Service = RecordConflicts<N::Service>;
+
+    fn new_service(&self, target: MatchedRoute<T, M, F, E>) -> Self::Service {
+        let mut conflicts = Vec::new();
+        if let Some(profile) = target.params.profile.as_ref() {
+            if target.params.request_timeout.is_some() && profile.route.timeout().is_some() {
+                tracing::debug!(
+                    route.timeout = ?target.params.request_timeout,
+                    profile.timeout = ?profile.route.timeout(),
+                    "Policy route timeout overrides ServiceProfile route timeout",
+                );
+                let labels = ConflictLabels {
+                    route: RouteLabels(
+                        target.params.parent_ref.clone(),
+                        target.params.route_ref.clone(),
+                    ),
+                    field: "timeout",
+                };
+                conflicts.push(self.metrics.conflicts.get_or_create(&labels).clone());
+            }
+        }
+
+        RecordConflicts {
+            conflicts: conflicts.into(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl RecordConflicts ===
+
+impl<B, S> svc::Service<http::Request<B>> for RecordConflicts<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        for conflict in self.conflicts.iter() {
+            conflict.inc();
+        }
+        self.inner.call(req)
+    }
+}
+
+// === impl ConflictLabels ===
+
+impl EncodeLabelSetMut for ConflictLabels {
+    fn encode_label_set(&self, enc: &mut LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.route.encode_label_set(enc)?;
+        ("field", self.field).encode(enc.encode_label())?;
+        Ok(())
+    }
+}
+
+impl EncodeLabelSet for ConflictLabels {
+    fn encode(&self, mut enc: LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.encode_label_set(&mut enc)
+    }
+}
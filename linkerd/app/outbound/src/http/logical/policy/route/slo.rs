@@ -0,0 +1,367 @@
+//! Reports how quickly routes consume the error budgets of their success-rate
+//! objectives.
+//!
+//! A route's burn rate over a window is the fraction of its requests that
+//! failed during the window divided by its error budget. At a burn rate of 1,
+//! the route fails exactly as often as its objective allows, so alerts can be
+//! defined on burn-rate gauges directly (e.g. paging when the budget burns at
+//! 14.4x over an hour).
+
+use super::RouteLabels;
+use crate::{ParentRef, RouteRef};
+use linkerd_app_core::{
+    classify,
+    metrics::prom::{
+        self,
+        encoding::{EncodeLabel, EncodeLabelSet, EncodeMetric, LabelSetEncoder, MetricEncoder},
+        metrics::{MetricType, TypedMetric},
+        EncodeLabelSetMut,
+    },
+    proxy::http,
+    svc::{self, Layer},
+};
+use linkerd_proxy_client_policy::http::SuccessObjective;
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    sync::{atomic::AtomicU64, Arc},
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::Instant};
+
+/// The number of buckets into which each window is divided.
+const BUCKETS: u32 = 10;
+
+/// The number of classified responses that may be buffered before they are
+/// recorded. Classifications are dropped when the buffer is full.
+const CHANNEL_CAPACITY: usize = 1_000;
+
+#[derive(Clone, Debug, Default)]
+pub struct SloMetricFamilies {
+    objective: prom::Family<RouteLabels, prom::Gauge<f64, AtomicU64>>,
+    requests: prom::Family<RouteLabels, prom::Counter>,
+    failures: prom::Family<RouteLabels, prom::Counter>,
+    burn_rate: prom::Family<WindowLabels, BurnRate>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewRecordSlo<N> {
+    families: SloMetricFamilies,
+    inner: N,
+}
+
+pub type RecordSlo<S> = svc::Either<
+    http::BoxResponse<http::classify::BroadcastClassification<classify::Response, S>>,
+    S,
+>;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct WindowLabels {
+    route: RouteLabels,
+    window: String,
+}
+
+/// Counts a route's requests and failures over a sliding window to report
+/// its burn rate when scraped.
+#[derive(Clone, Debug, Default)]
+struct BurnRate(Arc<Mutex<Window>>);
+
+#[derive(Debug, Default)]
+struct Window {
+    /// The fraction of requests that may fail.
+    budget: f64,
+    width: Duration,
+    buckets: VecDeque<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    start: Instant,
+    requests: u64,
+    failures: u64,
+}
+
+#[derive(Debug)]
+struct Recorder {
+    requests: prom::Counter,
+    failures: prom::Counter,
+    windows: Vec<BurnRate>,
+}
+
+// === impl SloMetricFamilies ===
+
+impl SloMetricFamilies {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let objective = prom::Family::default();
+        reg.register(
+            "objective",
+            "The fraction of a route's requests that must succeed",
+            objective.clone(),
+        );
+
+        let requests = prom::Family::default();
+        reg.register(
+            "requests",
+            "The total number of classified responses on routes with a success-rate objective",
+            requests.clone(),
+        );
+
+        let failures = prom::Family::default();
+        reg.register(
+            "failures",
+            "The total number of failed responses on routes with a success-rate objective",
+            failures.clone(),
+        );
+
+        let burn_rate = prom::Family::default();
+        reg.register(
+            "burn_rate",
+            "The rate at which a route's failures consumed its error budget over a window",
+            burn_rate.clone(),
+        );
+
+        Self {
+            objective,
+            requests,
+            failures,
+            burn_rate,
+        }
+    }
+
+    fn recorder(&self, route: RouteLabels, objective: &SuccessObjective) -> Recorder {
+        let target = f64::from(objective.target_ppm.min(1_000_000)) / 1_000_000.0;
+        self.objective.get_or_create(&route).set(target);
+
+        let windows = objective
+            .windows
+            .iter()
+            .filter(|w| !w.is_zero())
+            .map(|window| {
+                let labels = WindowLabels {
+                    route: route.clone(),
+                    window: format!("{}s", window.as_secs_f64()),
+                };
+                let burn_rate = self.burn_rate.get_or_create(&labels).clone();
+                burn_rate.configure(1.0 - target, *window);
+                burn_rate
+            })
+            .collect();
+
+        Recorder {
+            requests: self.requests.get_or_create(&route).clone(),
+            failures: self.failures.get_or_create(&route).clone(),
+            windows,
+        }
+    }
+}
+
+// === impl NewRecordSlo ===
+
+impl<N> NewRecordSlo<N> {
+    pub fn layer(families: SloMetricFamilies) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            families: families.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRecordSlo<N>
+where
+    T: svc::Param<Option<SuccessObjective>>,
+    T: svc::Param<ParentRef> + svc::Param<RouteRef>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordSlo<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let objective: Option<SuccessObjective> = target.param();
+        let Some(objective) = objective else {
+            return svc::Either::B(self.inner.new_service(target));
+        };
+
+        let route = RouteLabels(target.param(), target.param());
+        let recorder = self.families.recorder(route, &objective);
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(recorder.run(rx));
+
+        let inner = self.inner.new_service(target);
+        svc::Either::A(
+            http::BoxResponse::layer()
+                .layer(http::classify::BroadcastClassification::new(tx, inner)),
+        )
+    }
+}
+
+// === impl Recorder ===
+
+impl Recorder {
+    /// Records classified responses until all of the route's services are
+    /// dropped.
+    async fn run(self, mut rx: mpsc::Receiver<classify::Class>) {
+        while let Some(class) = rx.recv().await {
+            let failure = class.is_failure();
+            self.requests.inc();
+            if failure {
+                self.failures.inc();
+            }
+            let now = Instant::now();
+            for window in &self.windows {
+                window.record(now, failure);
+            }
+        }
+    }
+}
+
+// === impl WindowLabels ===
+
+impl EncodeLabelSet for WindowLabels {
+    fn encode(&self, mut enc: LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.route.encode_label_set(&mut enc)?;
+        ("window", self.window.as_str()).encode(enc.encode_label())
+    }
+}
+
+// === impl BurnRate ===
+
+impl BurnRate {
+    fn configure(&self, budget: f64, window: Duration) {
+        let mut w = self.0.lock();
+        w.budget = budget;
+        w.width = window / BUCKETS;
+    }
+
+    fn record(&self, now: Instant, failure: bool) {
+        let mut w = self.0.lock();
+        w.expire(now);
+        let width = w.width;
+        if !matches!(w.buckets.back(), Some(b) if now < b.start + width) {
+            w.buckets.push_back(Bucket {
+                start: now,
+                requests: 0,
+                failures: 0,
+            });
+        }
+        let bucket = w.buckets.back_mut().expect("window must have a bucket");
+        bucket.requests += 1;
+        if failure {
+            bucket.failures += 1;
+        }
+    }
+
+    fn get(&self) -> f64 {
+        let mut w = self.0.lock();
+        w.expire(Instant::now());
+        let (requests, failures) = w
+            .buckets
+            .iter()
+            .fold((0, 0), |(r, f), b| (r + b.requests, f + b.failures));
+        if requests == 0 {
+            return 0.0;
+        }
+        let failure_rate = failures as f64 / requests as f64;
+        if w.budget <= 0.0 {
+            // An objective of 100% has no budget: any failure exhausts it.
+            return if failures > 0 { f64::INFINITY } else { 0.0 };
+        }
+        failure_rate / w.budget
+    }
+}
+
+impl TypedMetric for BurnRate {
+    const TYPE: MetricType = MetricType::Gauge;
+}
+
+impl EncodeMetric for BurnRate {
+    fn encode(&self, mut encoder: MetricEncoder<'_>) -> std::fmt::Result {
+        encoder.encode_gauge(&self.get())
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
+// === impl Window ===
+
+impl Window {
+    /// Drops buckets that started more than a window ago.
+    fn expire(&mut self, now: Instant) {
+        let window = self.width * BUCKETS;
+        while let Some(b) = self.buckets.front() {
+            if b.start + window > now {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn burn_rate_over_window() {
+        let rate = BurnRate::default();
+        // A 99% objective over a 100s window.
+        rate.configure(0.01, Duration::from_secs(100));
+        assert_eq!(rate.get(), 0.0);
+
+        for i in 0..100 {
+            rate.record(Instant::now(), i < 2);
+        }
+        // 2% of requests failed, burning the 1% budget at twice the
+        // sustainable rate.
+        assert!((rate.get() - 2.0).abs() < 1e-9, "{}", rate.get());
+
+        tokio::time::advance(Duration::from_secs(50)).await;
+        for _ in 0..100 {
+            rate.record(Instant::now(), false);
+        }
+        assert!((rate.get() - 1.0).abs() < 1e-9, "{}", rate.get());
+
+        // The failures are no longer in the window.
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert_eq!(rate.get(), 0.0);
+
+        tokio::time::advance(Duration::from_secs(50)).await;
+        assert_eq!(rate.get(), 0.0);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn records_classifications() {
+        let families = SloMetricFamilies::default();
+        let objective = SuccessObjective {
+            target_ppm: 990_000,
+            windows: vec![Duration::from_secs(60), Duration::from_secs(3600)],
+        };
+        let route = RouteLabels(
+            ParentRef(crate::policy::Meta::new_default("parent")),
+            RouteRef(crate::policy::Meta::new_default("route")),
+        );
+        let recorder = families.recorder(route.clone(), &objective);
+        assert_eq!(recorder.windows.len(), 2);
+
+        let (tx, rx) = mpsc::channel(10);
+        tx.send(classify::Class::Http(Ok(http::StatusCode::OK)))
+            .await
+            .unwrap();
+        tx.send(classify::Class::Http(Err(
+            http::StatusCode::INTERNAL_SERVER_ERROR,
+        )))
+        .await
+        .unwrap();
+        drop(tx);
+        recorder.run(rx).await;
+
+        assert_eq!(families.requests.get_or_create(&route).get(), 2);
+        assert_eq!(families.failures.get_or_create(&route).get(), 1);
+        assert!((families.objective.get_or_create(&route).get() - 0.99).abs() < 1e-9);
+        let labels = WindowLabels {
+            route,
+            window: "60s".to_string(),
+        };
+        assert!((families.burn_rate.get_or_create(&labels).get() - 50.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,259 @@
+//! Sticky assignment of requests to a route's weighted backends.
+//!
+//! A sticky route hashes a key from each request onto its distribution's
+//! weights, so that, e.g., a given user is consistently assigned the same arm
+//! of an A/B test. Requests are dispatched to their assigned backend if it is
+//! available and otherwise to the next available backend in the
+//! distribution. Requests without a key are distributed at random.
+
+use linkerd_app_core::{
+    proxy::http,
+    svc::{self, ServiceExt},
+};
+use linkerd_distribute as distribute;
+use linkerd_proxy_client_policy::http::StickyKey;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// Builds [`distribute::Distribute`] services for routes, or a
+/// [`StickyDistribute`] for routes with a sticky, weighted distribution.
+#[derive(Debug)]
+pub struct NewStickyDistribute<K, N> {
+    inner: N,
+    _marker: PhantomData<fn() -> K>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StickyDistribute<S> {
+    key: StickyKey,
+    /// The cumulative weight of each backend in the distribution.
+    bounds: Arc<[u64]>,
+    /// For each backend in the distribution, a service that prefers that
+    /// backend and otherwise uses the first available of the others.
+    arms: Arc<[S]>,
+    /// Distributes requests that have no key.
+    random: S,
+}
+
+// === impl NewStickyDistribute ===
+
+impl<K, N> NewStickyDistribute<K, N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self {
+            inner,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<K, N: Clone> Clone for NewStickyDistribute<K, N> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, K, N, KNew> svc::NewService<T> for NewStickyDistribute<K, N>
+where
+    T: svc::Param<distribute::Distribution<K>>,
+    T: svc::Param<Option<StickyKey>>,
+    K: Debug + Hash + Eq + Clone,
+    N: svc::NewService<T, Service = KNew>,
+    KNew: svc::NewService<K> + Clone,
+{
+    type Service = svc::Either<
+        distribute::Distribute<K, KNew::Service>,
+        StickyDistribute<distribute::Distribute<K, KNew::Service>>,
+    >;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let dist: distribute::Distribution<K> = target.param();
+        let sticky: Option<StickyKey> = target.param();
+        let newk = self.inner.new_service(target);
+        let mk = |dist: distribute::Distribution<K>| {
+            svc::stack(svc::NewCloneService::from(newk.clone()))
+                .push(distribute::NewDistribute::layer_via(svc::CloneParam::from(
+                    dist,
+                )))
+                .into_inner()
+                .new_service(())
+        };
+
+        let (key, weighted) = match (sticky, &dist) {
+            (Some(key), distribute::Distribution::RandomAvailable(weighted)) => {
+                (key, weighted.clone())
+            }
+            // Sticky assignment only applies to weighted distributions.
+            _ => return svc::Either::A(mk(dist)),
+        };
+
+        tracing::debug!(?key, backends = ?weighted.keys(), "New sticky distribution");
+        let keys = weighted.keys();
+        let arms = (0..keys.len())
+            .map(|i| {
+                let ordered = keys[i..].iter().chain(&keys[..i]).cloned();
+                mk(distribute::Distribution::first_available(ordered))
+            })
+            .collect();
+        let bounds = weighted
+            .weights()
+            .iter()
+            .scan(0u64, |sum, w| {
+                *sum += u64::from(*w);
+                Some(*sum)
+            })
+            .collect();
+        svc::Either::B(StickyDistribute {
+            key,
+            bounds,
+            arms,
+            random: mk(dist),
+        })
+    }
+}
+
+// === impl StickyDistribute ===
+
+impl<S> StickyDistribute<S> {
+    /// Returns the index of the backend to which a request is assigned, if
+    /// the request has a key.
+    fn assign<B>(&self, req: &http::Request<B>) -> Option<usize> {
+        let mut hasher = DefaultHasher::new();
+        match self.key {
+            StickyKey::Header(ref name) => req.headers().get(name)?.as_bytes().hash(&mut hasher),
+            StickyKey::Cookie(ref name) => cookie(req.headers(), name)?.hash(&mut hasher),
+            StickyKey::ClientIp => req
+                .extensions()
+                .get::<http::ClientHandle>()?
+                .addr
+                .ip()
+                .hash(&mut hasher),
+        }
+        let total = *self.bounds.last()?;
+        let point = hasher.finish() % total;
+        self.bounds.iter().position(|b| point < *b)
+    }
+}
+
+impl<B, S> svc::Service<http::Request<B>> for StickyDistribute<S>
+where
+    S: svc::Service<http::Request<B>> + Clone,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = svc::Oneshot<S, http::Request<B>>;
+
+    /// Always ready, since a request's backend is only known once it is
+    /// called. Backends are buffered, so requests wait for their backend to
+    /// become ready until it fails fast.
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let svc = match self.assign(&req) {
+            Some(idx) => {
+                tracing::trace!(backend = idx, "Assigned sticky backend");
+                self.arms[idx].clone()
+            }
+            None => self.random.clone(),
+        };
+        svc.oneshot(req)
+    }
+}
+
+/// Finds the value of a named cookie.
+fn cookie<'h>(headers: &'h http::header::HeaderMap, name: &str) -> Option<&'h [u8]> {
+    headers
+        .get_all(http::header::COOKIE)
+        .iter()
+        .flat_map(|v| v.as_bytes().split(|b| *b == b';'))
+        .find_map(|pair| {
+            let eq = pair.iter().position(|b| *b == b'=')?;
+            let (k, v) = pair.split_at(eq);
+            (trim(k) == name.as_bytes()).then(|| trim(&v[1..]))
+        })
+}
+
+fn trim(mut bytes: &[u8]) -> &[u8] {
+    while let [b' ', rest @ ..] = bytes {
+        bytes = rest;
+    }
+    while let [rest @ .., b' '] = bytes {
+        bytes = rest;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sticky(key: StickyKey, weights: &[u64]) -> StickyDistribute<()> {
+        StickyDistribute {
+            key,
+            bounds: weights
+                .iter()
+                .scan(0, |sum, w| {
+                    *sum += w;
+                    Some(*sum)
+                })
+                .collect(),
+            arms: weights.iter().map(|_| ()).collect(),
+            random: (),
+        }
+    }
+
+    fn req(header: &str, value: &str) -> http::Request<()> {
+        http::Request::builder()
+            .header(header, value)
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_cookies() {
+        let mut headers = http::header::HeaderMap::new();
+        headers.append(http::header::COOKIE, "a=1; session = abc ".parse().unwrap());
+        headers.append(http::header::COOKIE, "user=xyz".parse().unwrap());
+        assert_eq!(cookie(&headers, "session"), Some(&b"abc"[..]));
+        assert_eq!(cookie(&headers, "user"), Some(&b"xyz"[..]));
+        assert_eq!(cookie(&headers, "b"), None);
+    }
+
+    #[test]
+    fn assigns_keys_consistently() {
+        let dist = sticky(StickyKey::Cookie("user".to_string()), &[9, 1]);
+        let mut counts = [0usize; 2];
+        for i in 0..1000 {
+            let req = req("cookie", &format!("user={i}"));
+            let idx = dist.assign(&req).expect("request must be assigned");
+            assert_eq!(dist.assign(&req), Some(idx), "assignment must be stable");
+            counts[idx] += 1;
+        }
+        // Keys are assigned roughly in proportion to the weights.
+        assert!(counts[0] > 800 && counts[1] > 50, "{counts:?}");
+
+        let dist = sticky(
+            StickyKey::Header(http::HeaderName::from_static("x-user")),
+            &[1, 0, 1],
+        );
+        for i in 0..100 {
+            assert_ne!(dist.assign(&req("x-user", &i.to_string())), Some(1));
+        }
+        assert_eq!(dist.assign(&req("x-other", "1")), None);
+        assert_eq!(
+            sticky(StickyKey::ClientIp, &[1, 1]).assign(&req("x-user", "1")),
+            None
+        );
+    }
+}
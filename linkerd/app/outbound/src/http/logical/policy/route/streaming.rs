@@ -0,0 +1,279 @@
+//! Timeouts for routes that stream their responses.
+//!
+//! A route's request timeout ordinarily bounds the time until its response
+//! headers are received. Streaming endpoints, e.g. server-sent events, often
+//! send headers immediately and then hold the response open, so a streaming
+//! route's request timeout instead extends to the first byte of the response
+//! body. Thereafter, the stream is terminated only once it has been idle for
+//! the route's idle timeout.
+
+use futures::{future, ready, TryFuture};
+use linkerd_app_core::{
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{self, Instant};
+
+/// Configures a streaming route's timeouts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Timeouts {
+    /// Bounds the time from the start of the request until the first byte of
+    /// the response body.
+    pub first_byte: Option<Duration>,
+
+    /// Bounds the time between each frame of the response body.
+    pub idle: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewStreamingTimeout<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct StreamingTimeout<S> {
+    inner: S,
+    timeouts: Option<Timeouts>,
+}
+
+#[pin_project]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    timeouts: Timeouts,
+    started_at: Instant,
+}
+
+#[pin_project]
+struct ResponseBody {
+    #[pin]
+    inner: http::BoxBody,
+    #[pin]
+    sleep: time::Sleep,
+    idle: Duration,
+    /// Set until the first byte of the body is received, if the route has a
+    /// request timeout.
+    first_byte: Option<Duration>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("response body did not begin within {0:?}")]
+pub struct FirstByteTimeoutError(Duration);
+
+#[derive(Debug, thiserror::Error)]
+#[error("response stream idle for {0:?}")]
+pub struct StreamIdleError(Duration);
+
+// === impl NewStreamingTimeout ===
+
+impl<N> NewStreamingTimeout<N> {
+    pub fn layer() -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewStreamingTimeout<N>
+where
+    T: svc::Param<Option<Timeouts>>,
+    N: svc::NewService<T>,
+{
+    type Service = StreamingTimeout<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let timeouts = target.param();
+        let inner = self.inner.new_service(target);
+        StreamingTimeout { inner, timeouts }
+    }
+}
+
+// === impl StreamingTimeout ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for StreamingTimeout<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = future::Either<S::Future, ResponseFuture<S::Future>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let timeouts = match self.timeouts {
+            Some(timeouts) => timeouts,
+            None => return future::Either::Left(self.inner.call(req)),
+        };
+
+        future::Either::Right(ResponseFuture {
+            inner: self.inner.call(req),
+            timeouts,
+            started_at: Instant::now(),
+        })
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: TryFuture<Ok = http::Response<http::BoxBody>>,
+{
+    type Output = Result<http::Response<http::BoxBody>, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = ready!(this.inner.try_poll(cx))?;
+        let Timeouts { first_byte, idle } = *this.timeouts;
+        // Without a request timeout, the idle timeout also bounds the time
+        // until the first byte.
+        let deadline = match first_byte {
+            Some(timeout) => *this.started_at + timeout,
+            None => Instant::now() + idle,
+        };
+        Poll::Ready(Ok(rsp.map(|inner| {
+            http::BoxBody::new(ResponseBody {
+                inner,
+                sleep: time::sleep_until(deadline),
+                idle,
+                first_byte,
+            })
+        })))
+    }
+}
+
+// === impl ResponseBody ===
+
+impl ResponseBody {
+    /// Fails the body if its timeout has elapsed.
+    fn poll_timeout(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Error> {
+        let this = self.project();
+        ready!(this.sleep.poll(cx));
+        let error = match *this.first_byte {
+            Some(timeout) => FirstByteTimeoutError(timeout).into(),
+            None => StreamIdleError(*this.idle).into(),
+        };
+        tracing::debug!(%error, "Terminating response stream");
+        Poll::Ready(error)
+    }
+}
+
+impl HttpBody for ResponseBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.as_mut().project();
+        if let Poll::Ready(data) = this.inner.poll_data(cx) {
+            if let Some(Ok(_)) = data {
+                *this.first_byte = None;
+                this.sleep.reset(Instant::now() + *this.idle);
+            }
+            return Poll::Ready(data);
+        }
+        self.poll_timeout(cx).map(|e| Some(Err(e)))
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        if let Poll::Ready(trailers) = self.as_mut().project().inner.poll_trailers(cx) {
+            return Poll::Ready(trailers);
+        }
+        self.poll_timeout(cx).map(Err)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use linkerd_app_core::svc::{Service, ServiceExt};
+
+    /// Returns a service that responds with a body that is fed by the returned
+    /// sender.
+    async fn stream(timeouts: Timeouts) -> (hyper::body::Sender, http::BoxBody) {
+        let (tx, body) = hyper::Body::channel();
+        let mut body = Some(body);
+        let inner = svc::mk(move |_: http::Request<http::BoxBody>| {
+            let body = body.take().expect("must only be called once");
+            future::ok::<_, Error>(http::Response::new(http::BoxBody::new(body)))
+        });
+        let mut svc = StreamingTimeout {
+            inner,
+            timeouts: Some(timeouts),
+        };
+        let rsp = svc
+            .ready()
+            .await
+            .unwrap()
+            .call(http::Request::new(http::BoxBody::default()))
+            .await
+            .unwrap();
+        (tx, rsp.into_body())
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn times_out_before_first_byte() {
+        let (_tx, mut body) = stream(Timeouts {
+            first_byte: Some(Duration::from_secs(1)),
+            idle: Duration::from_secs(10),
+        })
+        .await;
+        match body.data().await.unwrap() {
+            Err(err) => assert!(err.is::<FirstByteTimeoutError>()),
+            Ok(_) => panic!("body must time out"),
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn streams_until_idle() {
+        let (mut tx, mut body) = stream(Timeouts {
+            first_byte: Some(Duration::from_secs(1)),
+            idle: Duration::from_secs(10),
+        })
+        .await;
+
+        // The stream outlives its request timeout as long as it is active.
+        for _ in 0..5 {
+            tx.send_data(Bytes::from_static(b"data: hi\n\n"))
+                .await
+                .unwrap();
+            assert!(body.data().await.unwrap().is_ok(), "body must not time out");
+            time::sleep(Duration::from_secs(5)).await;
+        }
+
+        let started = Instant::now();
+        match body.data().await.unwrap() {
+            Err(err) => assert!(err.is::<StreamIdleError>()),
+            Ok(_) => panic!("body must time out"),
+        }
+        assert_eq!(
+            Instant::now().saturating_duration_since(started),
+            Duration::from_secs(5)
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use super::{
-    super::{concrete, Concrete, LogicalAddr, NoRoute},
+    super::{concrete, profile, Concrete, LogicalAddr, NoRoute},
     route,
 };
 use crate::{BackendRef, EndpointRef, ParentRef, RouteRef};
@@ -18,6 +18,9 @@ pub struct Params<M, F, E> {
     pub routes: Arc<[http_route::Route<M, policy::RoutePolicy<F, E>>]>,
     pub backends: Arc<[policy::Backend]>,
     pub failure_accrual: policy::FailureAccrual,
+    /// Legacy ServiceProfile routes whose retries and timeouts apply to
+    /// requests on these routes while the ServiceProfile is migrated.
+    pub profile_routes: Option<profile::Routes>,
 }
 
 pub type HttpParams =
@@ -31,6 +34,7 @@ pub(crate) struct Router<T: Clone + Debug + Eq + Hash, M, F, E> {
     pub(super) addr: Addr,
     pub(super) routes: Arc<[http_route::Route<M, route::Route<T, F, E>>]>,
     pub(super) backends: distribute::Backends<Concrete<T>>,
+    pub(super) profile_routes: Option<profile::Routes>,
 }
 
 pub(super) type Http<T> =
@@ -66,11 +70,17 @@ where
     route::MatchedBackend<T, M::Summary, F>: route::filters::Apply,
     route::backend::RouteBackendMetrics:
         svc::ExtractParam<route::backend::RequestCount, route::MatchedBackend<T, M::Summary, F>>,
+    route::backend::RouteBackendMetrics: svc::ExtractParam<
+        route::backend::ResponseStatuses,
+        route::MatchedBackend<T, M::Summary, F>,
+    >,
 {
     /// Builds a stack that applies routes to distribute requests over a cached
     /// set of inner services so that.
     pub(super) fn layer<N, S>(
         metrics: route::RouteMetrics,
+        chaos: route::chaos::Chaos,
+        egress: route::authz::EgressPolicy,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         // Inner stack.
@@ -92,7 +102,11 @@ where
                 .push(NewBackendCache::layer())
                 // Lazily cache a service for each `RouteParams` returned from the
                 // `SelectRoute` impl.
-                .push_on_service(route::MatchedRoute::layer(metrics.clone()))
+                .push_on_service(route::MatchedRoute::layer(
+                    metrics.clone(),
+                    chaos.clone(),
+                    egress.clone(),
+                ))
                 .push(svc::NewOneshotRoute::<Self, (), _>::layer_cached())
                 .arc_new_clone_http()
                 .into_inner()
@@ -114,10 +128,12 @@ where
             routes,
             backends,
             failure_accrual,
+            profile_routes,
         } = rts;
 
         let mk_concrete = {
             let parent = parent.clone();
+            let parent_ref = parent_ref.clone();
             move |backend_ref: BackendRef, target: concrete::Dispatch| {
                 // XXX With policies we don't have a top-level authority name at
                 // the moment. So, instead, we use the concrete addr used for
@@ -195,21 +211,41 @@ where
                              distribution,
                              failure_policy,
                              request_timeout,
+                             cache,
+                             request_decompression,
+                             request_body_limit,
+                             streaming,
+                             sticky,
+                             mirror,
+                             success_objective,
                          }| {
             let route_ref = RouteRef(meta);
             let distribution = mk_distribution(&route_ref, &distribution);
+            let mirror = mirror.map(|m| route::Mirror {
+                concrete: mk_dispatch(&m.backend),
+                compare_headers: m.compare_headers.into(),
+            });
             route::Route {
                 addr: addr.clone(),
                 parent: parent.clone(),
+                parent_ref: parent_ref.clone(),
                 route_ref,
                 filters,
                 failure_policy,
                 distribution,
                 request_timeout,
+                cache,
+                request_decompression,
+                request_body_limit,
+                streaming,
+                sticky,
+                mirror,
+                success_objective,
+                profile: None,
             }
         };
 
-        let routes = routes
+        let routes: Arc<[_]> = routes
             .iter()
             .map(|route| http_route::Route {
                 hosts: route.hosts.clone(),
@@ -225,17 +261,50 @@ where
             })
             .collect();
 
-        let backends = backends.iter().map(mk_dispatch).collect();
+        // Mirrored routes' shadow backends are cached with the other backends.
+        let mirrors = routes
+            .iter()
+            .flat_map(|route| route.rules.iter())
+            .filter_map(|rule| rule.policy.mirror.as_ref())
+            .map(|mirror| mirror.concrete.clone());
+        let backends = backends.iter().map(mk_dispatch).chain(mirrors).collect();
 
         Self {
             routes,
             backends,
             addr,
             parent,
+            profile_routes,
         }
     }
 }
 
+impl<T, M, F, E> Router<T, M, F, E>
+where
+    T: Eq + Hash + Clone + Debug,
+    F: Clone,
+    E: Clone,
+{
+    /// Attaches the legacy ServiceProfile route that matches the request, if
+    /// any, to the selected policy route.
+    fn with_profile_route<B>(
+        &self,
+        params: &route::Route<T, F, E>,
+        req: &http::Request<B>,
+    ) -> route::Route<T, F, E> {
+        let mut params = params.clone();
+        params.profile = self.profile_routes.as_ref().and_then(|profile| {
+            let route = profile::route_for_request(&profile.routes, req)?;
+            tracing::debug!(labels = ?route.labels(), "Selected ServiceProfile route");
+            Some(route::profile_compat::ProfileRoute {
+                addr: profile.addr.clone(),
+                route: route.clone(),
+            })
+        });
+        params
+    }
+}
+
 impl<B, T> svc::router::SelectRoute<http::Request<B>> for Http<T>
 where
     T: Eq + Hash + Clone + Debug,
@@ -250,7 +319,7 @@ where
         tracing::trace!(?r#match);
         Ok(route::Matched {
             r#match,
-            params: params.clone(),
+            params: self.with_profile_route(params, req),
         })
     }
 }
@@ -269,7 +338,7 @@ where
         tracing::trace!(?r#match);
         Ok(route::Matched {
             r#match,
-            params: params.clone(),
+            params: self.with_profile_route(params, req),
         })
     }
 }
@@ -48,6 +48,13 @@ async fn header_based_route() {
         }),
         filters: Arc::new([]),
         failure_policy: Default::default(),
+        cache: None,
+        request_decompression: None,
+        request_body_limit: None,
+        streaming: None,
+        sticky: None,
+        mirror: None,
+        success_objective: None,
         request_timeout: None,
         distribution: policy::RouteDistribution::FirstAvailable(Arc::new([policy::RouteBackend {
             filters: Arc::new([]),
@@ -123,12 +130,18 @@ async fn header_based_route() {
             .chain(Some(special_backend.clone()))
             .collect(),
         failure_accrual: Default::default(),
+        profile_routes: None,
     });
 
     let metrics = RouteMetrics::default();
-    let router = Policy::layer(metrics.clone(), Default::default())
-        .layer(inner)
-        .new_service(Policy::from((routes, ())));
+    let router = Policy::layer(
+        metrics.clone(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+    )
+    .layer(inner)
+    .new_service(Policy::from((routes, ())));
 
     let default_reqs = metrics.request_count(
         parent_ref.clone(),
@@ -148,9 +161,9 @@ async fn header_based_route() {
     let req = http::Request::builder()
         .body(http::BoxBody::default())
         .unwrap();
-    let _ = tokio::select! {
+    let rsp = tokio::spawn(router.clone().oneshot(req));
+    let (_, tx) = tokio::select! {
         biased;
-        _ = router.clone().oneshot(req) => panic!("unexpected response"),
         _ = special.next_request() => panic!("unexpected request to special service"),
         _ = time::sleep(time::Duration::from_secs(1)) => panic!("timed out"),
         reqrsp = default.next_request() => reqrsp.expect("request"),
@@ -158,6 +171,33 @@ async fn header_based_route() {
     assert_eq!(default_reqs.get(), 1);
     assert_eq!(special_reqs.get(), 0);
 
+    tx.send_response(
+        http::Response::builder()
+            .status(http::StatusCode::SERVICE_UNAVAILABLE)
+            .body(http::BoxBody::default())
+            .unwrap(),
+    );
+    let rsp = rsp.await.unwrap().expect("response");
+    assert_eq!(rsp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        metrics.response_count(
+            parent_ref.clone(),
+            default_route_ref.clone(),
+            default_backend_ref.clone(),
+            Some(http::StatusCode::SERVICE_UNAVAILABLE),
+        ),
+        1
+    );
+    assert_eq!(
+        metrics.response_count(
+            parent_ref.clone(),
+            special_route_ref.clone(),
+            special_backend_ref.clone(),
+            Some(http::StatusCode::SERVICE_UNAVAILABLE),
+        ),
+        0
+    );
+
     default.allow(1);
     special.allow(1);
     let req = http::Request::builder()
@@ -212,6 +252,13 @@ async fn http_filter_request_headers() {
                     policy: policy::RoutePolicy {
                         meta: policy::Meta::new_default("turtles"),
                         failure_policy: Default::default(),
+                        cache: None,
+                        request_decompression: None,
+                        request_body_limit: None,
+                        streaming: None,
+                        sticky: None,
+                        mirror: None,
+                        success_objective: None,
                         request_timeout: None,
                         filters: Arc::new([policy::http::Filter::RequestHeaders(
                             policy::http::filter::ModifyHeader {
@@ -236,12 +283,18 @@ async fn http_filter_request_headers() {
             }]),
             backends: std::iter::once(backend).collect(),
             failure_accrual: Default::default(),
+            profile_routes: None,
         }
     });
 
-    let router = Policy::layer(Default::default(), Default::default())
-        .layer(inner)
-        .new_service(Policy::from((routes, ())));
+    let router = Policy::layer(
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+    )
+    .layer(inner)
+    .new_service(Policy::from((routes, ())));
 
     handle.allow(1);
     let req = http::Request::builder()
@@ -263,3 +316,135 @@ async fn http_filter_request_headers() {
     // Hold the router to prevent inner services from being dropped.
     drop(router);
 }
+
+#[tokio::test(flavor = "current_thread")]
+async fn http_split_backend_request_headers() {
+    let _trace = trace::test::trace_init();
+
+    let mk_backend = |name: &'static str, port: u16| policy::Backend {
+        meta: policy::Meta::new_default(name),
+        queue: policy::Queue {
+            capacity: 10,
+            failfast_timeout: time::Duration::from_secs(1),
+        },
+        dispatcher: policy::BackendDispatcher::Forward(
+            ([127, 0, 0, 1], port).into(),
+            Default::default(),
+        ),
+    };
+    let stable = mk_backend("stable", 18080);
+    let canary = mk_backend("canary", 18081);
+
+    // Stack that produces mock services.
+    let (inner_stable, mut stable_handle) = tower_test::mock::pair();
+    let (inner_canary, mut canary_handle) = tower_test::mock::pair();
+    let inner = move |concrete: Concrete<()>| {
+        if let concrete::Dispatch::Forward(addr, ..) = concrete.target {
+            match addr.port() {
+                18080 => return inner_stable.clone(),
+                18081 => return inner_canary.clone(),
+                _ => {}
+            }
+        }
+        panic!("unexpected target: {:?}", concrete.target);
+    };
+
+    // Only requests split to the canary backend are marked.
+    static CANARY: http::HeaderName = http::HeaderName::from_static("x-canary");
+    static TRUE: http::HeaderValue = http::HeaderValue::from_static("true");
+    let routes = Params::Http(router::HttpParams {
+        addr: Addr::Socket(([127, 0, 0, 1], 8080).into()),
+        meta: ParentRef(policy::Meta::new_default("splitter")),
+        routes: Arc::new([policy::http::Route {
+            hosts: Default::default(),
+            rules: vec![policy::http::Rule {
+                matches: vec![route::http::MatchRequest::default()],
+                policy: policy::RoutePolicy {
+                    meta: policy::Meta::new_default("split"),
+                    failure_policy: Default::default(),
+                    cache: None,
+                    request_decompression: None,
+                    request_body_limit: None,
+                    streaming: None,
+                    sticky: None,
+                    mirror: None,
+                    success_objective: None,
+                    request_timeout: None,
+                    filters: Arc::new([]),
+                    distribution: policy::RouteDistribution::RandomAvailable(Arc::new([
+                        (
+                            policy::RouteBackend {
+                                backend: stable.clone(),
+                                filters: Arc::new([]),
+                                request_timeout: None,
+                            },
+                            1,
+                        ),
+                        (
+                            policy::RouteBackend {
+                                backend: canary.clone(),
+                                filters: Arc::new([policy::http::Filter::RequestHeaders(
+                                    policy::http::filter::ModifyHeader {
+                                        add: vec![(CANARY.clone(), TRUE.clone())],
+                                        ..Default::default()
+                                    },
+                                )]),
+                                request_timeout: None,
+                            },
+                            1,
+                        ),
+                    ])),
+                },
+            }],
+        }]),
+        backends: [stable, canary].into_iter().collect(),
+        failure_accrual: Default::default(),
+        profile_routes: None,
+    });
+
+    let router = Policy::layer(
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+    )
+    .layer(inner)
+    .new_service(Policy::from((routes, ())));
+
+    stable_handle.allow(100);
+    canary_handle.allow(100);
+    let (mut stable_reqs, mut canary_reqs) = (0, 0);
+    for _ in 0..100 {
+        let rsp = router
+            .clone()
+            .oneshot(http::Request::new(http::BoxBody::default()));
+        tokio::pin!(rsp);
+        let (req, is_canary) = tokio::select! {
+            biased;
+            _ = &mut rsp => panic!("unexpected response"),
+            _ = time::sleep(time::Duration::from_secs(1)) => panic!("timed out"),
+            reqrsp = stable_handle.next_request() => (reqrsp.expect("request").0, false),
+            reqrsp = canary_handle.next_request() => (reqrsp.expect("request").0, true),
+        };
+        assert_eq!(req.headers().get(&CANARY), is_canary.then_some(&TRUE));
+        if is_canary {
+            canary_reqs += 1;
+        } else {
+            stable_reqs += 1;
+        }
+        if stable_reqs > 0 && canary_reqs > 0 {
+            break;
+        }
+    }
+    assert!(
+        stable_reqs > 0,
+        "no requests were sent to the stable backend"
+    );
+    assert!(
+        canary_reqs > 0,
+        "no requests were sent to the canary backend"
+    );
+
+    // Hold the router to prevent inner services from being dropped.
+    drop(router);
+}
@@ -69,6 +69,7 @@ where
     /// we can reuse inner services.
     pub(super) fn layer<N, S>(
         metrics: metrics::Proxy,
+        retries: retry::NewRetryPolicy,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         N: svc::NewService<Concrete<T>, Service = S> + Clone + Send + Sync + 'static,
@@ -88,7 +89,7 @@ where
                 .push(NewBackendCache::layer())
                 // Lazily cache a service for each `RouteParams`
                 // returned from the `SelectRoute` impl.
-                .push_on_service(RouteParams::layer(metrics.clone()))
+                .push_on_service(RouteParams::layer(metrics.clone(), retries.clone()))
                 .push(svc::NewOneshotRoute::<Params<T>, _, _>::layer_cached())
                 .arc_new_clone_http()
                 .into_inner()
@@ -246,6 +247,7 @@ where
 impl<T> RouteParams<T> {
     fn layer<N, S>(
         metrics: metrics::Proxy,
+        retries: retry::NewRetryPolicy,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         T: Clone + Debug + Eq + Hash + Send + Sync + 'static,
@@ -282,7 +284,7 @@ impl<T> RouteParams<T> {
                 // layer unifies any `Body` type into `BoxBody`.
                 .push_on_service(http::BoxRequest::erased())
                 // Sets an optional retry policy.
-                .push(retry::layer(metrics.http_profile_route_retry.clone()))
+                .push(retry::layer(retries.clone()))
                 // Sets an optional request timeout.
                 .push(http::NewTimeout::layer())
                 // Records per-route metrics.
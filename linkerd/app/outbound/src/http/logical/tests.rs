@@ -41,6 +41,7 @@ async fn routes() {
             backends: Arc::new([backend.clone()]),
             routes: Arc::new([default_route(backend)]),
             failure_accrual: client_policy::FailureAccrual::None,
+            profile_routes: None,
         })));
     let target = Target {
         num: 1,
@@ -99,6 +100,7 @@ async fn consecutive_failures_accrue() {
                 max_failures: 3,
                 backoff,
             },
+            profile_routes: None,
         })));
     let target = Target {
         num: 1,
@@ -261,6 +263,7 @@ async fn balancer_doesnt_select_tripped_breakers() {
                 max_failures: 3,
                 backoff,
             },
+            profile_routes: None,
         })));
     let target = Target {
         num: 1,
@@ -338,6 +341,7 @@ async fn route_request_timeout() {
             backends: Arc::new([backend]),
             routes: Arc::new([route]),
             failure_accrual: client_policy::FailureAccrual::None,
+            profile_routes: None,
         })))
     };
     let target = Target {
@@ -404,6 +408,7 @@ async fn backend_request_timeout() {
             backends: Arc::new([backend]),
             routes: Arc::new([route]),
             failure_accrual: client_policy::FailureAccrual::None,
+            profile_routes: None,
         })))
     };
     let target = Target {
@@ -616,6 +621,13 @@ fn default_route(backend: client_policy::Backend) -> client_policy::http::Route
                 meta: Meta::new_default("test_route"),
                 filters: NO_FILTERS.clone(),
                 failure_policy: Default::default(),
+                cache: None,
+                request_decompression: None,
+                request_body_limit: None,
+                streaming: None,
+                sticky: None,
+                mirror: None,
+                success_objective: None,
                 request_timeout: None,
                 distribution: RouteDistribution::FirstAvailable(Arc::new([RouteBackend {
                     filters: NO_FILTERS.clone(),
@@ -646,6 +658,13 @@ fn timeout_route(
                 meta: Meta::new_default("test_route"),
                 filters: NO_FILTERS.clone(),
                 failure_policy: Default::default(),
+                cache: None,
+                request_decompression: None,
+                request_body_limit: None,
+                streaming: None,
+                sticky: None,
+                mirror: None,
+                success_objective: None,
                 request_timeout: route_timeout,
                 distribution: RouteDistribution::FirstAvailable(Arc::new([RouteBackend {
                     filters: NO_FILTERS.clone(),
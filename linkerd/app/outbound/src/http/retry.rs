@@ -17,18 +17,33 @@ use linkerd_retry as retry;
 use std::sync::Arc;
 
 pub fn layer<N>(
-    metrics: metrics::HttpProfileRouteRetry,
+    new_policy: NewRetryPolicy,
 ) -> impl layer::Layer<N, Service = retry::NewRetry<NewRetryPolicy, N, EraseResponse<()>>> + Clone {
-    retry::layer(NewRetryPolicy::new(metrics))
+    retry::layer(new_policy)
         // Because we wrap the response body type on retries, we must include a
         // `Proxy` middleware for unifying the response body types of the retry
         // and non-retry services.
         .with_proxy(EraseResponse::new(()))
 }
 
-#[derive(Clone, Debug)]
+/// Configures how requests on retryable ServiceProfile routes are buffered
+/// so that they may be retried.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// The maximum number of request body bytes that are buffered for
+    /// retries. Requests with larger bodies are not retried.
+    pub max_buffered_bytes: usize,
+
+    /// When set, requests with non-idempotent methods (e.g. POSTs) are only
+    /// retried when they are marked idempotent with this header, and only
+    /// once their bodies have been completely buffered.
+    pub idempotency_header: Option<http::HeaderName>,
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct NewRetryPolicy {
     metrics: metrics::HttpProfileRouteRetry,
+    config: RetryConfig,
 }
 
 #[derive(Clone, Debug)]
@@ -36,16 +51,26 @@ pub struct RetryPolicy {
     metrics: Handle,
     budget: Arc<retry::Budget>,
     response_classes: profiles::http::ResponseClasses,
+    config: RetryConfig,
 }
 
-/// Allow buffering requests up to 64 kb
-const MAX_BUFFERED_BYTES: usize = 64 * 1024;
+// === impl RetryConfig ===
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            // Allow buffering requests up to 64 kb
+            max_buffered_bytes: 64 * 1024,
+            idempotency_header: None,
+        }
+    }
+}
 
 // === impl NewRetryPolicy ===
 
 impl NewRetryPolicy {
-    pub fn new(metrics: metrics::HttpProfileRouteRetry) -> Self {
-        Self { metrics }
+    pub fn new(metrics: metrics::HttpProfileRouteRetry, config: RetryConfig) -> Self {
+        Self { metrics, config }
     }
 }
 
@@ -62,12 +87,25 @@ where
             metrics: self.metrics.get_handle(labels),
             budget: route.retries()?.budget().clone(),
             response_classes: route.response_classes().clone(),
+            config: self.config.clone(),
         })
     }
 }
 
 // === impl Retry ===
 
+impl RetryPolicy {
+    /// Returns `true` unless the request must be marked idempotent to be
+    /// retried and it is not (or its body has not been completely buffered).
+    fn is_replayable<A: HttpBody>(&self, req: &http::Request<ReplayBody<A>>) -> bool {
+        let Some(header) = self.config.idempotency_header.as_ref() else {
+            return true;
+        };
+        req.method().is_idempotent()
+            || (req.headers().contains_key(header) && req.body().is_fully_buffered())
+    }
+}
+
 impl<A, B, E> retry::Policy<http::Request<ReplayBody<A>>, http::Response<WithTrailers<B>>, E>
     for RetryPolicy
 where
@@ -93,8 +131,12 @@ where
                     .is_failure();
                 // did the body exceed the maximum length limit?
                 let exceeded_max_len = req.body().is_capped();
-                let retryable = is_failure && !exceeded_max_len;
-                tracing::trace!(is_failure, exceeded_max_len, retryable);
+                if is_failure && exceeded_max_len {
+                    self.metrics.incr_body_too_large();
+                }
+                let replayable = self.is_replayable(req);
+                let retryable = is_failure && !exceeded_max_len && replayable;
+                tracing::trace!(is_failure, exceeded_max_len, replayable, retryable);
                 retryable
             }
         };
@@ -159,7 +201,7 @@ where
         req: http::Request<A>,
     ) -> Either<Self::RetryRequest, http::Request<A>> {
         let (head, body) = req.into_parts();
-        let replay_body = match ReplayBody::try_new(body, MAX_BUFFERED_BYTES) {
+        let replay_body = match ReplayBody::try_new(body, self.config.max_buffered_bytes) {
             Ok(body) => body,
             Err(body) => {
                 tracing::debug!(
@@ -40,6 +40,16 @@ impl<T> Outbound<svc::ArcNewCloneHttp<T>> {
                 // reached or the inner service is otherwise not ready for
                 // requests.
                 .push_on_service(svc::LoadShed::layer())
+                // Convert panics into errors so that they fail only the
+                // request rather than the whole connection.
+                .push_on_service(errors::CatchPanic::layer())
+                // Fails requests that outlive the deadline in their deadline
+                // header.
+                .push_on_service(http::EnforceDeadline::layer(
+                    config.proxy.deadline_header.clone(),
+                ))
+                // Records the error codes of reset streams.
+                .push_on_service(rt.metrics.http_stream_resets.layer())
                 .push_on_service(rt.metrics.http_errors.to_layer())
                 // Synthesizes responses for proxy errors.
                 .check_new_service::<T, http::Request<_>>()
@@ -48,6 +58,10 @@ impl<T> Outbound<svc::ArcNewCloneHttp<T>> {
                 // Initiates OpenCensus tracing.
                 .push_on_service(http_tracing::server(rt.span_sink.clone(), trace_labels()))
                 .push_on_service(http::BoxResponse::layer())
+                // Handles `Expect: 100-continue` headers as configured.
+                .push_on_service(super::expect_continue::HandleExpectContinue::layer(
+                    config.http_expect_continue,
+                ))
                 // Convert origin form HTTP/1 URIs to absolute form for Hyper's
                 // `Client`.
                 .push(http::NewNormalizeUri::layer())
@@ -83,6 +97,8 @@ impl<N> Outbound<N> {
                 .push(svc::ArcNewService::layer())
                 .push(http::NewServeHttp::layer(
                     config.proxy.server.h2_settings,
+                    config.proxy.server.max_connection_age,
+                    config.proxy.server.preserve_trailers,
                     rt.drain.clone(),
                 ))
         })
@@ -117,15 +133,27 @@ impl errors::HttpRescue<Error> for ServerRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         use super::logical::policy::errors as policy;
 
+        // The client went away before the request was answered.
+        if errors::is_caused_by::<errors::ClientCancelled>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::cancelled(error));
+        }
+
         // A profile configured request timeout was encountered.
         if errors::is_caused_by::<http::ResponseTimeoutError>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::timeout(error));
+        }
+
+        // A request's deadline header expired before it was answered.
+        if errors::is_caused_by::<http::DeadlineExceededError>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::timeout(error)
+                .with_code(errors::ErrorCode::DeadlineExceeded));
         }
 
         // A request with a `l5d-require-id` header are dispatched to endpoints
         // with a different identity.
         if errors::is_caused_by::<IdentityRequired>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(error)
+                .with_code(errors::ErrorCode::IdentityRequired));
         }
 
         // No available backend can be found for a request.
@@ -133,7 +161,8 @@ impl errors::HttpRescue<Error> for ServerRescue {
             // XXX(ver) This should probably be SERVICE_UNAVAILABLE, because
             // this is basically no different from a LoadShedError, but that
             // would be a change in behavior.
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error)
+                .with_code(errors::ErrorCode::FailFast));
         }
         if errors::is_caused_by::<errors::LoadShedError>(&*error) {
             return Ok(errors::SyntheticHttpResponse::unavailable(error));
@@ -141,12 +170,28 @@ impl errors::HttpRescue<Error> for ServerRescue {
         if errors::is_caused_by::<super::concrete::DispatcherFailed>(&*error) {
             return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
         }
+        if errors::is_caused_by::<super::concrete::InvalidPinnedEndpoint>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+        }
+        if errors::is_caused_by::<super::concrete::PinnedEndpointNotFound>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::unavailable(error));
+        }
+
+        // The egress policy denies the request.
+        if errors::is_caused_by::<crate::EgressUnauthorized>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::permission_denied(error));
+        }
 
         // No routes configured for a request.
         if errors::is_caused_by::<super::logical::NoRoute>(&*error) {
             return Ok(errors::SyntheticHttpResponse::not_found(error));
         }
 
+        // The request's body exceeds its route's size limit.
+        if errors::is_caused_by::<policy::HttpRouteBodyTooLarge>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::payload_too_large(error));
+        }
+
         // Policy-driven request redirection.
         if let Some(policy::HttpRouteRedirect { status, location }) = errors::cause_ref(&*error) {
             return Ok(errors::SyntheticHttpResponse::redirect(*status, location));
@@ -169,6 +214,11 @@ impl errors::HttpRescue<Error> for ServerRescue {
             ));
         }
 
+        // Panics are logged and recorded when they are caught.
+        if errors::is_caused_by::<errors::Panicked>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::unexpected_error());
+        }
+
         // HTTP/2 errors.
         if errors::is_caused_by::<errors::H2Error>(&*error) {
             return Err(error);
@@ -176,6 +226,8 @@ impl errors::HttpRescue<Error> for ServerRescue {
 
         // Everything else...
         tracing::warn!(error, "Unexpected error");
+        errors::recent::RECENT_ERRORS
+            .record(errors::recent::ErrorRecord::new(&*error).with_label("direction", "outbound"));
         Ok(errors::SyntheticHttpResponse::unexpected_error())
     }
 }
@@ -1,7 +1,7 @@
 use crate::{http, opaq, policy, Config, Discovery, Outbound, ParentRef};
 use linkerd_app_core::{
     config::{ProxyConfig, ServerConfig},
-    detect, errors, io,
+    detect, dns, errors, io,
     metrics::prom,
     profiles,
     proxy::{
@@ -19,6 +19,15 @@ use thiserror::Error;
 use tokio::sync::watch;
 use tracing::Instrument;
 
+mod authority;
+mod fallback;
+
+use self::fallback::{DnsFallback, Fallback, FallbackMetrics, IngressRescue};
+pub use self::{
+    authority::{AuthorityOverrides, AuthorityRewrite},
+    fallback::{DnsFallbackTtl, IngressFallback},
+};
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Http<T> {
     parent: T,
@@ -29,16 +38,24 @@ struct Http<T> {
 struct Opaq<T>(Discovery<T>);
 
 #[derive(Clone, Debug)]
-struct SelectTarget<T>(Http<T>);
+struct SelectTarget<T> {
+    target: Http<T>,
+    overrides: AuthorityOverrides,
+    fallback: IngressFallback,
+}
 
+/// The destination of an ingress request.
+///
+/// Requests named by an override header also carry their original
+/// destination address when it is used as a fallback.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 enum RequestTarget {
-    Named(NameAddr),
+    Named(NameAddr, Option<OrigDstAddr>),
     Orig(OrigDstAddr),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct DiscoverAddr(Addr);
+struct DiscoverAddr(Addr, Option<OrigDstAddr>);
 
 #[derive(Clone, Debug)]
 struct Logical {
@@ -74,6 +91,7 @@ impl Outbound<()> {
         profiles: impl profiles::GetProfile<Error = Error>,
         policies: impl policy::GetPolicy,
         resolve: R,
+        dns: dns::Resolver,
     ) -> svc::ArcNewTcp<T, I>
     where
         // Target type for outbund ingress-mode connections.
@@ -88,7 +106,9 @@ impl Outbound<()> {
     {
         let registry = registry.sub_registry_with_prefix("outbound");
 
-        let discover = self.ingress_resolver(profiles, policies);
+        let fallback_metrics =
+            FallbackMetrics::register(registry.sub_registry_with_prefix("ingress"));
+        let discover = self.ingress_resolver(profiles, policies, dns, fallback_metrics);
 
         // The fallback stack is the same thing as the normal proxy stack, but
         // it doesn't include TCP metrics, since they are already instrumented
@@ -99,7 +119,7 @@ impl Outbound<()> {
                 .push_opaq_cached(registry, resolve.clone())
                 .map_stack(|_, _, stk| stk.push_map_target(Opaq))
                 .push_discover(svc::mk(move |OrigDstAddr(addr)| {
-                    discover.clone().oneshot(DiscoverAddr(addr.into()))
+                    discover.clone().oneshot(DiscoverAddr(addr.into(), None))
                 }))
                 .into_inner()
         };
@@ -107,7 +127,9 @@ impl Outbound<()> {
         let http = self
             .to_tcp_connect()
             .push_tcp_endpoint()
-            .push_http_tcp_client()
+            .push_http_tcp_client(http::PoolMetrics::register(
+                registry.sub_registry_with_prefix("http_pool"),
+            ))
             .push_http_cached(http::HttpMetrics::register(registry), resolve)
             .push_http_server()
             .map_stack(|_, _, stk| {
@@ -125,6 +147,8 @@ impl Outbound<()> {
         &self,
         profiles: impl profiles::GetProfile<Error = Error>,
         policies: impl policy::GetPolicy,
+        dns: dns::Resolver,
+        fallback_metrics: FallbackMetrics,
     ) -> impl svc::Service<
         DiscoverAddr,
         Error = Error,
@@ -147,7 +171,18 @@ impl Outbound<()> {
                 crate::http::logical::profile::DEFAULT_EWMA;
             policy::Load::PeakEwma(policy::PeakEwma { decay, default_rtt })
         };
-        svc::mk(move |DiscoverAddr(addr)| {
+        let fallback = Fallback {
+            mode: self.config.ingress_fallback,
+            dns: DnsFallback {
+                resolver: dns,
+                ttl: self.config.ingress_dns_fallback_ttl,
+                detect_timeout,
+                queue,
+            },
+            orig_dst: self.resolver(profiles.clone(), policies.clone()),
+            metrics: fallback_metrics,
+        };
+        svc::mk(move |DiscoverAddr(addr, orig_dst)| {
             tracing::debug!(%addr, "Discover");
 
             let profile = profiles
@@ -155,8 +190,9 @@ impl Outbound<()> {
                 .get_profile(profiles::LookupAddr(addr.clone()))
                 .instrument(tracing::debug_span!("profiles").or_current());
             let policy = policies
-                .get_policy(addr)
+                .get_policy(addr.clone())
                 .instrument(tracing::debug_span!("policy").or_current());
+            let fallback = fallback.clone();
 
             Box::pin(async move {
                 let (profile, policy) = tokio::join!(profile, policy);
@@ -190,11 +226,11 @@ impl Outbound<()> {
                 };
 
                 // If there was a profile resolution, try to use it to synthesize a
-                // policy. Otherwise, return an error, as we have neither a
-                // policy nor a ServiceProfile and can't synthesize a policy.
+                // policy. Otherwise, we have neither a policy nor a
+                // ServiceProfile, so the configured fallback applies.
                 let profile = match profile {
                     Some(profile) => profile,
-                    None => return Err(policy_error),
+                    None => return fallback.resolve(addr, orig_dst, policy_error).await,
                 };
 
                 let policy = crate::discover::spawn_synthesized_profile_policy(
@@ -278,10 +314,18 @@ impl<N> Outbound<N> {
     {
         self.map_stack(|config, rt, inner| {
             let detect_http = config.proxy.detect_http();
+            let overrides = config.ingress_authority_overrides.clone();
+            let ingress_fallback = config.ingress_fallback;
             let Config {
                 proxy:
                     ProxyConfig {
-                        server: ServerConfig { h2_settings, .. },
+                        server:
+                            ServerConfig {
+                                h2_settings,
+                                max_connection_age,
+                                preserve_trailers,
+                                ..
+                            },
                         ..
                     },
                 ..
@@ -306,7 +350,13 @@ impl<N> Outbound<N> {
                         .push(http::strip_header::request::layer(DST_OVERRIDE_HEADER))
                 )
                 .lift_new()
-                .push(svc::NewOneshotRoute::layer_via(|t: &Http<T>| SelectTarget(t.clone())))
+                .push(svc::NewOneshotRoute::layer_via(move |t: &Http<T>| SelectTarget {
+                    target: t.clone(),
+                    overrides: overrides.clone(),
+                    fallback: ingress_fallback,
+                }))
+                .push(IngressRescue::layer(config.emit_headers))
+                .push_on_service(http::BoxResponse::layer())
                 .check_new_service::<Http<T>, http::Request<_>>();
 
             // HTTP detection is **always** performed. If detection fails, then we
@@ -314,7 +364,12 @@ impl<N> Outbound<N> {
             // destination address.
             http.check_new_service::<Http<T>, http::Request<_>>()
                 .unlift_new()
-                .push(http::NewServeHttp::layer(*h2_settings, rt.drain.clone()))
+                .push(http::NewServeHttp::layer(
+                    *h2_settings,
+                    *max_connection_age,
+                    *preserve_trailers,
+                    rt.drain.clone(),
+                ))
                 .check_new_service::<Http<T>, I>()
                 .push_switch(
                     |(detected, target): (detect::Result<http::Version>, T)| -> Result<_, Infallible> {
@@ -345,15 +400,25 @@ where
     type Error = InvalidOverrideHeader;
 
     fn select(&self, req: &http::Request<B>) -> Result<Self::Key, Self::Error> {
-        // Use either the override header or the original destination address.
+        // Use either the override header, if the client may set it, or the
+        // original destination address.
+        let client = req.extensions().get::<http::ClientHandle>().map(|c| c.addr);
         let target = http::authority_from_header(req, DST_OVERRIDE_HEADER)
-            .map(|a| {
-                NameAddr::from_authority_with_default_port(&a, 80)
-                    .map(RequestTarget::Named)
-                    .map_err(|_| InvalidOverrideHeader)
+            .filter(|_| {
+                let allowed = self.overrides.allows(client);
+                if !allowed {
+                    tracing::debug!(?client, "Client may not set {DST_OVERRIDE_HEADER}");
+                }
+                allowed
+            })
+            .map(|a| -> Result<_, InvalidOverrideHeader> {
+                let addr = self.overrides.rewrite(&a)?;
+                let orig_dst =
+                    (self.fallback == IngressFallback::OrigDst).then(|| (*self.target).param());
+                Ok(RequestTarget::Named(addr, orig_dst))
             })
             .transpose()?
-            .unwrap_or_else(|| RequestTarget::Orig((*self.0).param()));
+            .unwrap_or_else(|| RequestTarget::Orig((*self.target).param()));
 
         // Use the request's version.
         let version = match req.version() {
@@ -396,7 +461,11 @@ impl<T> std::ops::Deref for Http<T> {
 
 impl svc::Param<DiscoverAddr> for Http<RequestTarget> {
     fn param(&self) -> DiscoverAddr {
-        DiscoverAddr(self.parent.clone().into())
+        let orig_dst = match self.parent {
+            RequestTarget::Named(_, orig_dst) => orig_dst,
+            RequestTarget::Orig(_) => None,
+        };
+        DiscoverAddr(self.parent.clone().into(), orig_dst)
     }
 }
 
@@ -428,7 +497,7 @@ impl TryFrom<Discovery<Http<RequestTarget>>> for Http<Logical> {
         let mut policy = svc::Param::<policy::Receiver>::param(&parent);
 
         match (**parent).clone() {
-            RequestTarget::Named(addr) => {
+            RequestTarget::Named(addr, _) => {
                 // Only use service profiles if there are novel routes/target
                 // overrides.
                 if let Some(mut profile) = profile {
@@ -544,6 +613,7 @@ fn policy_routes(
                     backends: policy.backends.clone(),
                     routes,
                     failure_accrual,
+                    profile_routes: None,
                 },
             )))
         }
@@ -560,6 +630,7 @@ fn policy_routes(
                 backends: policy.backends.clone(),
                 routes: routes.clone(),
                 failure_accrual,
+                profile_routes: None,
             },
         ))),
         policy::Protocol::Http2(policy::http::Http2 {
@@ -572,6 +643,7 @@ fn policy_routes(
                 backends: policy.backends.clone(),
                 routes: routes.clone(),
                 failure_accrual,
+                profile_routes: None,
             },
         ))),
         policy::Protocol::Grpc(policy::grpc::Grpc {
@@ -584,6 +656,7 @@ fn policy_routes(
                 backends: policy.backends.clone(),
                 routes: routes.clone(),
                 failure_accrual,
+                profile_routes: None,
             },
         ))),
         _ => None,
@@ -650,7 +723,7 @@ where
 impl From<RequestTarget> for Addr {
     fn from(tgt: RequestTarget) -> Self {
         match tgt {
-            RequestTarget::Named(n) => n.into(),
+            RequestTarget::Named(n, _) => n.into(),
             RequestTarget::Orig(OrigDstAddr(a)) => a.into(),
         }
     }
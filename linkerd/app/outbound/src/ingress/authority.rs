@@ -0,0 +1,141 @@
+use super::InvalidOverrideHeader;
+use linkerd_app_core::{IpNet, NameAddr};
+use std::{net::SocketAddr, sync::Arc};
+
+/// Configures how ingress-mode proxies interpret the `l5d-dst-override`
+/// header.
+#[derive(Clone, Debug)]
+pub struct AuthorityOverrides {
+    /// Rewrites override authorities before they are discovered. The first
+    /// rule that matches an authority's host is applied.
+    pub rewrites: Arc<[AuthorityRewrite]>,
+
+    /// The port used when neither an override authority nor its rewrite rule
+    /// specifies one.
+    pub default_port: u16,
+
+    /// The networks of clients that may set the override header. When unset,
+    /// all clients may set it. Requests from other clients are routed to their
+    /// original destination.
+    pub allowed_clients: Option<Arc<[IpNet]>>,
+}
+
+/// Rewrites an override authority's host and, if it has none, its port.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthorityRewrite {
+    /// The canonical host that the rule matches.
+    pub host: String,
+
+    /// Replaces the matched host.
+    pub to_host: Option<String>,
+
+    /// Sets a port on authorities that do not specify one.
+    pub to_port: Option<u16>,
+}
+
+// === impl AuthorityOverrides ===
+
+impl Default for AuthorityOverrides {
+    fn default() -> Self {
+        Self {
+            rewrites: Arc::new([]),
+            default_port: 80,
+            allowed_clients: None,
+        }
+    }
+}
+
+impl AuthorityOverrides {
+    /// Returns true if the client may set the override header.
+    pub(super) fn allows(&self, client: Option<SocketAddr>) -> bool {
+        match self.allowed_clients.as_deref() {
+            None => true,
+            Some(nets) => client.map_or(false, |c| nets.iter().any(|n| n.contains(&c.ip()))),
+        }
+    }
+
+    /// Canonicalizes an override authority and applies the first matching
+    /// rewrite rule, returning the address to be discovered.
+    pub(super) fn rewrite(
+        &self,
+        authority: &::http::uri::Authority,
+    ) -> Result<NameAddr, InvalidOverrideHeader> {
+        let mut host = canonicalize(authority.host());
+        let mut port = authority.port_u16();
+
+        if let Some(rule) = self.rewrites.iter().find(|r| r.host == host) {
+            tracing::debug!(%authority, ?rule, "Rewriting override authority");
+            if let Some(to_host) = rule.to_host.as_deref() {
+                host = canonicalize(to_host);
+            }
+            port = port.or(rule.to_port);
+        }
+
+        NameAddr::from_str_and_port(&host, port.unwrap_or(self.default_port))
+            .map_err(|_| InvalidOverrideHeader)
+    }
+}
+
+/// Lowercases a host and removes any trailing dot, so that equivalent names
+/// are discovered and matched identically.
+fn canonicalize(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides() -> AuthorityOverrides {
+        AuthorityOverrides {
+            rewrites: Arc::new([
+                AuthorityRewrite {
+                    host: "web.example.com".to_string(),
+                    to_host: Some("web.prod.svc.cluster.local".to_string()),
+                    to_port: Some(8080),
+                },
+                AuthorityRewrite {
+                    host: "api.prod.svc.cluster.local".to_string(),
+                    to_host: None,
+                    to_port: Some(8443),
+                },
+            ]),
+            default_port: 80,
+            allowed_clients: Some(Arc::new(["10.0.0.0/8".parse().unwrap()])),
+        }
+    }
+
+    fn rewrite(authority: &'static str) -> String {
+        let authority = ::http::uri::Authority::from_static(authority);
+        overrides().rewrite(&authority).unwrap().to_string()
+    }
+
+    #[test]
+    fn rewrites_authorities() {
+        assert_eq!(
+            rewrite("web.example.com"),
+            "web.prod.svc.cluster.local:8080"
+        );
+        assert_eq!(
+            rewrite("WEB.example.com.:9090"),
+            "web.prod.svc.cluster.local:9090"
+        );
+        assert_eq!(
+            rewrite("api.prod.svc.cluster.local"),
+            "api.prod.svc.cluster.local:8443"
+        );
+        assert_eq!(
+            rewrite("other.prod.svc.cluster.local."),
+            "other.prod.svc.cluster.local:80"
+        );
+    }
+
+    #[test]
+    fn allows_clients() {
+        let overrides = overrides();
+        assert!(overrides.allows(Some(([10, 1, 2, 3], 5000).into())));
+        assert!(!overrides.allows(Some(([192, 168, 1, 1], 5000).into())));
+        assert!(!overrides.allows(None));
+        assert!(AuthorityOverrides::default().allows(None));
+    }
+}
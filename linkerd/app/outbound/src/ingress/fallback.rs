@@ -0,0 +1,368 @@
+use crate::{discover::synthesize_forward_policy, policy};
+use linkerd_app_core::{
+    dns, errors,
+    metrics::prom,
+    profiles,
+    svc::{self, ServiceExt},
+    transport::OrigDstAddr,
+    Addr, Error, NameAddr, Result,
+};
+use once_cell::sync::Lazy;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use thiserror::Error;
+use tokio::{sync::watch, time};
+use tracing::Instrument;
+
+/// Configures how ingress-mode proxies route requests when the authority in
+/// their `l5d-dst-override` header has no discovery result.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+pub enum IngressFallback {
+    /// Fails requests with a 502 Bad Gateway response.
+    #[default]
+    Fail,
+
+    /// Routes requests to their connection's original destination address,
+    /// as if they had no override header.
+    OrigDst,
+
+    /// Forwards requests to an address resolved from the authority via DNS.
+    Dns,
+}
+
+/// Bounds how long a DNS fallback resolution is used before it is refreshed,
+/// regardless of the TTLs of the resolved records. When the bounds conflict,
+/// the maximum takes precedence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DnsFallbackTtl {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+/// Resolves a routing policy for authorities that have no discovery result.
+#[derive(Clone)]
+pub(super) struct Fallback<R> {
+    pub(super) mode: IngressFallback,
+    pub(super) dns: DnsFallback,
+    pub(super) orig_dst: R,
+    pub(super) metrics: FallbackMetrics,
+}
+
+/// Resolves fallback authorities via DNS, refreshing each resolution in the
+/// background for as long as its policy is in use.
+#[derive(Clone)]
+pub(super) struct DnsFallback {
+    pub(super) resolver: dns::Resolver,
+    pub(super) ttl: DnsFallbackTtl,
+    pub(super) detect_timeout: Duration,
+    pub(super) queue: policy::Queue,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct FallbackMetrics {
+    fallbacks: prom::Family<FallbackLabels, prom::Counter>,
+    dns_duration: prom::Family<DnsLabels, prom::Histogram, fn() -> prom::Histogram>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, prom::encoding::EncodeLabelSet)]
+struct FallbackLabels {
+    fallback: IngressFallback,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, prom::encoding::EncodeLabelSet)]
+struct DnsLabels {
+    result: &'static str,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(super) struct IngressRescue {
+    emit_headers: bool,
+}
+
+#[derive(Debug, Error)]
+#[error("no discovery result for {addr}")]
+pub(crate) struct NoDiscoveryResult {
+    addr: NameAddr,
+    #[source]
+    source: Error,
+}
+
+// === impl Fallback ===
+
+impl<R> Fallback<R>
+where
+    R: svc::Service<
+        OrigDstAddr,
+        Response = (Option<profiles::Receiver>, policy::Receiver),
+        Error = Error,
+    >,
+{
+    /// Resolves a policy for an address that could not be discovered.
+    ///
+    /// Only override authorities fall back; failures to discover original
+    /// destination addresses are returned as-is.
+    pub(super) async fn resolve(
+        self,
+        addr: Addr,
+        orig_dst: Option<OrigDstAddr>,
+        error: Error,
+    ) -> Result<(Option<profiles::Receiver>, policy::Receiver)> {
+        let addr = match addr {
+            Addr::Name(addr) => addr,
+            Addr::Socket(_) => return Err(error),
+        };
+
+        tracing::debug!(%addr, fallback = ?self.mode, "No discovery result");
+        self.metrics.inc(self.mode);
+        match (self.mode, orig_dst) {
+            (IngressFallback::OrigDst, Some(orig_dst)) => self.orig_dst.oneshot(orig_dst).await,
+            (IngressFallback::Dns, _) => {
+                let policy = self
+                    .dns
+                    .resolve(addr.clone(), &self.metrics)
+                    .await
+                    .map_err(|source| {
+                        tracing::debug!(%addr, error = %source, "DNS fallback failed");
+                        NoDiscoveryResult { addr, source }
+                    })?;
+                Ok((None, policy))
+            }
+            _ => Err(NoDiscoveryResult {
+                addr,
+                source: error,
+            }
+            .into()),
+        }
+    }
+}
+
+// === impl DnsFallbackTtl ===
+
+impl Default for DnsFallbackTtl {
+    fn default() -> Self {
+        Self {
+            min: Duration::from_secs(5),
+            max: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl DnsFallbackTtl {
+    fn clamp(&self, ttl: Duration) -> Duration {
+        ttl.max(self.min).min(self.max)
+    }
+}
+
+// === impl DnsFallback ===
+
+impl DnsFallback {
+    /// Resolves an authority via DNS and synthesizes a policy that forwards
+    /// requests to the first resolved address.
+    ///
+    /// The resolution is refreshed in the background once its (clamped) TTL
+    /// elapses, so that the policy may be cached for as long as it is in use
+    /// without forwarding requests to a stale address.
+    async fn resolve(self, addr: NameAddr, metrics: &FallbackMetrics) -> Result<policy::Receiver> {
+        let (endpoint, ttl) = self.lookup(&addr, metrics).await?;
+        tracing::debug!(%addr, %endpoint, ?ttl, "Forwarding to DNS-resolved address");
+
+        let (tx, rx) = watch::channel(self.mk_policy(endpoint));
+        let metrics = metrics.clone();
+        let span = tracing::debug_span!("dns_fallback", %addr);
+        tokio::spawn(
+            async move {
+                self.refresh(addr, endpoint, ttl, tx, metrics).await;
+            }
+            .instrument(span.or_current()),
+        );
+        Ok(rx)
+    }
+
+    /// Refreshes a resolution each time its TTL elapses, until the policy is
+    /// no longer in use. If a refresh fails, the prior address continues to
+    /// be used and the refresh is retried after the minimum TTL.
+    async fn refresh(
+        self,
+        addr: NameAddr,
+        mut endpoint: SocketAddr,
+        mut ttl: Duration,
+        tx: watch::Sender<policy::ClientPolicy>,
+        metrics: FallbackMetrics,
+    ) {
+        loop {
+            tokio::select! {
+                biased;
+                _ = tx.closed() => return,
+                _ = time::sleep(ttl) => {}
+            }
+
+            ttl = match self.lookup(&addr, &metrics).await {
+                Ok((ep, ttl)) => {
+                    if ep != endpoint {
+                        tracing::debug!(%ep, "DNS-resolved address changed");
+                        endpoint = ep;
+                        if tx.send(self.mk_policy(endpoint)).is_err() {
+                            return;
+                        }
+                    }
+                    ttl
+                }
+                Err(error) => {
+                    tracing::debug!(%error, "Failed to refresh DNS fallback");
+                    self.ttl.min
+                }
+            };
+        }
+    }
+
+    /// Resolves the first address for an authority, along with the clamped
+    /// TTL of the resolution.
+    async fn lookup(
+        &self,
+        addr: &NameAddr,
+        metrics: &FallbackMetrics,
+    ) -> Result<(SocketAddr, Duration)> {
+        let t0 = time::Instant::now();
+        let res = self
+            .resolver
+            .resolve_addrs(addr.name().as_ref(), addr.port())
+            .await;
+        metrics.observe_dns(
+            time::Instant::now().saturating_duration_since(t0),
+            res.is_ok(),
+        );
+
+        let (addrs, expiry) = res?;
+        let endpoint = addrs
+            .into_iter()
+            .next()
+            .ok_or("DNS resolution returned no addresses")?;
+        let ttl = expiry
+            .deadline()
+            .saturating_duration_since(time::Instant::now());
+        Ok((endpoint, self.ttl.clamp(ttl)))
+    }
+
+    fn mk_policy(&self, endpoint: SocketAddr) -> policy::ClientPolicy {
+        static META: Lazy<Arc<policy::Meta>> = Lazy::new(|| {
+            Arc::new(policy::Meta::Default {
+                name: "dns-fallback".into(),
+            })
+        });
+
+        synthesize_forward_policy(
+            &META,
+            self.detect_timeout,
+            self.queue,
+            endpoint,
+            Default::default(),
+        )
+    }
+}
+
+// === impl FallbackMetrics ===
+
+impl Default for FallbackMetrics {
+    fn default() -> Self {
+        Self {
+            fallbacks: prom::Family::default(),
+            dns_duration: prom::Family::new_with_constructor(|| {
+                // 1ms to ~4s.
+                prom::Histogram::new(prom::metrics::histogram::exponential_buckets(
+                    0.001, 2.0, 13,
+                ))
+            }),
+        }
+    }
+}
+
+impl FallbackMetrics {
+    pub(super) fn register(reg: &mut prom::Registry) -> Self {
+        let metrics = Self::default();
+        reg.register(
+            "fallbacks",
+            "The total number of ingress override authorities that fell back because they could not be discovered",
+            metrics.fallbacks.clone(),
+        );
+        reg.register_with_unit(
+            "dns_fallback_duration",
+            "The time taken to resolve fallback authorities via DNS",
+            prom::Unit::Seconds,
+            metrics.dns_duration.clone(),
+        );
+        metrics
+    }
+
+    fn inc(&self, fallback: IngressFallback) {
+        self.fallbacks
+            .get_or_create(&FallbackLabels { fallback })
+            .inc();
+    }
+
+    fn observe_dns(&self, elapsed: Duration, ok: bool) {
+        let result = if ok { "ok" } else { "error" };
+        self.dns_duration
+            .get_or_create(&DnsLabels { result })
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+// === impl IngressRescue ===
+
+impl IngressRescue {
+    /// Synthesizes 502 responses for requests whose override authority could
+    /// not be discovered. Other errors are not handled.
+    pub(super) fn layer<N>(
+        emit_headers: bool,
+    ) -> impl svc::layer::Layer<N, Service = errors::NewRespondService<Self, Self, N>> + Clone {
+        errors::respond::layer(Self { emit_headers })
+    }
+}
+
+impl<T> svc::ExtractParam<Self, T> for IngressRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> Self {
+        *self
+    }
+}
+
+impl<T> svc::ExtractParam<errors::respond::EmitHeaders, T> for IngressRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitHeaders {
+        errors::respond::EmitHeaders(self.emit_headers)
+    }
+}
+
+impl errors::HttpRescue<Error> for IngressRescue {
+    fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
+        if errors::is_caused_by::<NoDiscoveryResult>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+        }
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_ttls() {
+        let ttl = DnsFallbackTtl {
+            min: Duration::from_secs(5),
+            max: Duration::from_secs(60),
+        };
+        assert_eq!(ttl.clamp(Duration::ZERO), Duration::from_secs(5));
+        assert_eq!(ttl.clamp(Duration::from_secs(30)), Duration::from_secs(30));
+        assert_eq!(
+            ttl.clamp(Duration::from_secs(3600)),
+            Duration::from_secs(60)
+        );
+
+        // The maximum takes precedence over a conflicting minimum.
+        let ttl = DnsFallbackTtl {
+            min: Duration::from_secs(60),
+            max: Duration::from_secs(5),
+        };
+        assert_eq!(ttl.clamp(Duration::ZERO), Duration::from_secs(5));
+    }
+}
@@ -8,7 +8,7 @@
 
 use linkerd_app_core::{
     config::{ProxyConfig, QueueConfig},
-    drain,
+    dns, drain,
     exp_backoff::ExponentialBackoff,
     http_tracing::OpenCensusSink,
     identity, io,
@@ -49,6 +49,9 @@ pub mod test_util;
 
 pub use self::{
     discover::{spawn_synthesized_profile_policy, synthesize_forward_policy, Discovery},
+    http::concrete::{EndpointFailover, EndpointPinning, FailoverCluster},
+    http::logical::policy::{Chaos, EgressPolicy, EgressUnauthorized, Fault},
+    ingress::{AuthorityOverrides, AuthorityRewrite, DnsFallbackTtl, IngressFallback},
     metrics::OutboundMetrics,
 };
 
@@ -79,6 +82,42 @@ pub struct Config {
     // not perform per-target-address discovery. Non-HTTP connections are
     // forwarded without discovery/routing/mTLS.
     pub ingress_mode: bool,
+
+    /// Configures how ingress-mode proxies interpret the `l5d-dst-override`
+    /// header.
+    pub ingress_authority_overrides: AuthorityOverrides,
+
+    /// Configures how ingress-mode proxies route requests when the
+    /// `l5d-dst-override` authority has no discovery result.
+    pub ingress_fallback: IngressFallback,
+
+    /// Bounds how long ingress-mode proxies use an address resolved by the
+    /// DNS fallback before resolving it again.
+    pub ingress_dns_fallback_ttl: DnsFallbackTtl,
+
+    /// Configures whether clients may pin requests to specific endpoints.
+    /// Pinning is disabled when unset.
+    pub endpoint_pinning: Option<EndpointPinning>,
+
+    /// Configures whether balancers fail over from local endpoints to
+    /// remote-cluster gateways. All resolved endpoints are balanced when unset.
+    pub endpoint_failover: Option<EndpointFailover>,
+
+    /// Configures the requests that this workload may not send. Denied
+    /// requests fail with a `403 Forbidden` response.
+    pub egress_policy: EgressPolicy,
+
+    /// Configures route settings that are applied to each discovered client
+    /// policy.
+    pub policy_overrides: Arc<policy::overrides::Overrides>,
+
+    /// Configures how requests are buffered so that they may be retried.
+    pub http_retry: http::RetryConfig,
+
+    /// Configures how requests with an `Expect: 100-continue` header are
+    /// handled.
+    pub http_expect_continue: http::ExpectContinue,
+
     pub inbound_ips: Arc<HashSet<IpAddr>>,
 
     // Whether the proxy may include informational headers on HTTP responses.
@@ -99,6 +138,7 @@ struct Runtime {
     tap: tap::Registry,
     span_sink: OpenCensusSink,
     drain: drain::Watch,
+    chaos: Chaos,
 }
 
 pub type ConnectMeta = tls::ConnectMeta<Local<ClientAddr>>;
@@ -129,6 +169,7 @@ impl Outbound<()> {
             tap: runtime.tap,
             span_sink: runtime.span_sink,
             drain: runtime.drain,
+            chaos: Default::default(),
         };
         Self {
             config,
@@ -152,6 +193,7 @@ impl Outbound<()> {
         C::Future: Send,
     {
         policy::Api::new(workload, limits, Duration::from_secs(10), client)
+            .with_overrides(self.config.policy_overrides.clone())
             .into_watch(backoff)
             .map_result(|response| match response {
                 Err(e) => Err(e.into()),
@@ -180,6 +222,11 @@ impl<S> Outbound<S> {
         self.runtime.metrics.clone()
     }
 
+    /// Returns a handle for injecting faults into routes' requests.
+    pub fn chaos(&self) -> Chaos {
+        self.runtime.chaos.clone()
+    }
+
     pub fn stack_metrics(&self) -> metrics::Stack {
         self.runtime.metrics.proxy.stack.clone()
     }
@@ -230,6 +277,7 @@ impl Outbound<()> {
         profiles: impl profiles::GetProfile<Error = Error>,
         policies: impl policy::GetPolicy,
         resolve: R,
+        dns: dns::Resolver,
     ) -> svc::ArcNewTcp<T, I>
     where
         // Target describing a server-side connection.
@@ -245,7 +293,7 @@ impl Outbound<()> {
         let profiles = profiles::WithAllowlist::new(profiles, self.config.allow_discovery.clone());
         if self.config.ingress_mode {
             tracing::info!("Outbound routing in ingress-mode");
-            self.mk_ingress(registry, profiles, policies, resolve)
+            self.mk_ingress(registry, profiles, policies, resolve, dns)
         } else {
             self.mk_sidecar(registry, profiles, policies, resolve)
         }
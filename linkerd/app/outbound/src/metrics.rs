@@ -10,6 +10,7 @@
 
 use crate::{policy, BackendRef, ParentRef, RouteRef};
 use linkerd_app_core::{
+    errors,
     metrics::prom::{encoding::*, EncodeLabelSetMut},
     svc,
 };
@@ -22,6 +23,7 @@ pub use linkerd_app_core::{metrics::*, proxy::balance};
 #[derive(Clone, Debug)]
 pub struct OutboundMetrics {
     pub(crate) http_errors: error::Http,
+    pub(crate) http_stream_resets: errors::StreamResets,
     pub(crate) tcp_errors: error::Tcp,
 
     // pub(crate) http_route_backends: RouteBackendMetrics,
@@ -80,6 +82,7 @@ impl OutboundMetrics {
         Self {
             proxy,
             http_errors: error::Http::default(),
+            http_stream_resets: errors::StreamResets::outbound(),
             tcp_errors: error::Tcp::default(),
         }
     }
@@ -88,6 +91,7 @@ impl OutboundMetrics {
 impl FmtMetrics for OutboundMetrics {
     fn fmt_metrics(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.http_errors.fmt_metrics(f)?;
+        self.http_stream_resets.fmt_metrics(f)?;
         self.tcp_errors.fmt_metrics(f)?;
 
         // XXX: Proxy and Route Backend metrics are reported elsewhere.
@@ -2,21 +2,29 @@ mod http;
 mod tcp;
 
 pub(crate) use self::{http::Http, tcp::Tcp};
-use crate::http::IdentityRequired;
+use crate::{
+    http::{policy::errors::HttpRouteBodyTooLarge, IdentityRequired},
+    EgressUnauthorized,
+};
 use linkerd_app_core::{
-    errors::{FailFastError, LoadShedError},
+    errors::{ClientCancelled, ErrorCode, ErrorSource, FailFastError, H2Error, LoadShedError},
     metrics::FmtLabels,
-    proxy::http::ResponseTimeoutError,
+    proxy::http::{h2::Reason, DeadlineExceededError, ResponseTimeoutError},
 };
 use std::fmt;
 
 /// Outbound proxy error types.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 enum ErrorKind {
+    Cancelled,
+    DeadlineExceeded,
+    EgressUnauthorized,
     FailFast,
     IdentityRequired,
     Io,
+    PayloadTooLarge,
     ResponseTimeout,
+    StreamReset,
     Unexpected,
     LoadShed,
 }
@@ -33,29 +41,106 @@ impl ErrorKind {
             ErrorKind::FailFast
         } else if err.is::<ResponseTimeoutError>() {
             ErrorKind::ResponseTimeout
+        } else if err.is::<DeadlineExceededError>() {
+            ErrorKind::DeadlineExceeded
         } else if err.is::<LoadShedError>() {
             ErrorKind::LoadShed
+        } else if err.is::<ClientCancelled>() {
+            ErrorKind::Cancelled
+        } else if err.is::<EgressUnauthorized>() {
+            ErrorKind::EgressUnauthorized
+        } else if err.is::<HttpRouteBodyTooLarge>() {
+            ErrorKind::PayloadTooLarge
+        } else if let Some(e) = err.downcast_ref::<H2Error>() {
+            // Clients cancel the streams they no longer need, so cancellations
+            // are attributed to the client and other resets to the upstream.
+            if e.reason() == Some(Reason::CANCEL) {
+                ErrorKind::Cancelled
+            } else {
+                ErrorKind::StreamReset
+            }
         } else if let Some(e) = err.source() {
             Self::mk(e)
         } else {
             ErrorKind::Unexpected
         }
     }
+
+    fn code(&self) -> ErrorCode {
+        match self {
+            ErrorKind::Cancelled => ErrorCode::Cancelled,
+            ErrorKind::DeadlineExceeded => ErrorCode::DeadlineExceeded,
+            ErrorKind::EgressUnauthorized => ErrorCode::Unauthorized,
+            ErrorKind::FailFast => ErrorCode::FailFast,
+            ErrorKind::IdentityRequired => ErrorCode::IdentityRequired,
+            ErrorKind::Io => ErrorCode::ConnectError,
+            ErrorKind::PayloadTooLarge => ErrorCode::PayloadTooLarge,
+            ErrorKind::ResponseTimeout => ErrorCode::ResponseTimeout,
+            ErrorKind::StreamReset => ErrorCode::StreamReset,
+            ErrorKind::Unexpected => ErrorCode::Internal,
+            ErrorKind::LoadShed => ErrorCode::LoadShed,
+        }
+    }
+
+    /// Returns the party that caused the error.
+    fn source(&self) -> ErrorSource {
+        match self {
+            ErrorKind::Cancelled | ErrorKind::PayloadTooLarge => ErrorSource::Client,
+            ErrorKind::Io | ErrorKind::StreamReset => ErrorSource::Upstream,
+            ErrorKind::DeadlineExceeded
+            | ErrorKind::EgressUnauthorized
+            | ErrorKind::FailFast
+            | ErrorKind::IdentityRequired
+            | ErrorKind::ResponseTimeout
+            | ErrorKind::Unexpected
+            | ErrorKind::LoadShed => ErrorSource::Proxy,
+        }
+    }
 }
 
 impl FmtLabels for ErrorKind {
     fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "error=\"{}\"",
+            "error=\"{}\",code=\"{}\",source=\"{}\"",
             match self {
+                ErrorKind::Cancelled => "cancelled",
+                ErrorKind::DeadlineExceeded => "deadline exceeded",
+                ErrorKind::EgressUnauthorized => "egress unauthorized",
                 ErrorKind::LoadShed => "loadshed",
                 ErrorKind::FailFast => "failfast",
                 ErrorKind::IdentityRequired => "identity required",
                 ErrorKind::Io => "i/o",
+                ErrorKind::PayloadTooLarge => "payload too large",
                 ErrorKind::ResponseTimeout => "response timeout",
+                ErrorKind::StreamReset => "stream reset",
                 ErrorKind::Unexpected => "unexpected",
-            }
+            },
+            self.code(),
+            self.source(),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::Error;
+
+    fn source(err: impl Into<Error>) -> ErrorSource {
+        let err = err.into();
+        ErrorKind::mk(&*err).source()
+    }
+
+    #[test]
+    fn classifies_sources() {
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert_eq!(source(refused), ErrorSource::Upstream);
+        assert_eq!(
+            source(H2Error::from(Reason::REFUSED_STREAM)),
+            ErrorSource::Upstream
+        );
+        assert_eq!(source(H2Error::from(Reason::CANCEL)), ErrorSource::Client);
+        assert_eq!(source("unexpected"), ErrorSource::Proxy);
+    }
+}
@@ -205,7 +205,7 @@ impl<C> Outbound<C> {
                     },
                     forward.into_inner(),
                 )
-                .push_on_service(tcp::Forward::layer())
+                .push_on_service(tcp::Forward::layer(config.proxy.server.max_connection_age))
                 .push_on_service(drain::Retain::layer(rt.drain.clone()))
                 .push(svc::ArcNewService::layer())
         })
@@ -87,7 +87,7 @@ impl<N> Outbound<N> {
         NSvc::Future: Send,
         NSvc::Error: Into<Error>,
     {
-        self.map_stack(|_, _, concrete| {
+        self.map_stack(|config, _, concrete| {
             let route = svc::layers()
                 .lift_new()
                 .push(NewDistribute::layer())
@@ -135,6 +135,15 @@ impl<N> Outbound<N> {
                     },
                     concrete.into_inner(),
                 )
+                // Deny connections to logical services that the egress policy
+                // denies.
+                .push(crate::http::logical::policy::NewDenyLogical::layer_via(
+                    config.egress_policy.clone(),
+                    |parent: &T| match parent.param() {
+                        Logical::Route(addr, _) => Some(addr),
+                        Logical::Forward(..) => None,
+                    },
+                ))
                 .arc_new_clone_tcp()
         })
     }
@@ -8,17 +8,19 @@ use linkerd_app_core::{
     svc::Service,
     Addr, Error, Recover, Result,
 };
-use linkerd_proxy_client_policy::ClientPolicy;
+use linkerd_proxy_client_policy::{overrides::Overrides, ClientPolicy};
 use linkerd_tonic_stream::{LimitReceiveFuture, ReceiveLimits};
 use linkerd_tonic_watch::StreamWatch;
 use std::sync::Arc;
 use tokio::time;
+use tonic::codec::CompressionEncoding;
 
 #[derive(Clone, Debug)]
 pub(crate) struct Api<S> {
     workload: Arc<str>,
     limits: ReceiveLimits,
     default_detect_timeout: time::Duration,
+    overrides: Arc<Overrides>,
     client: Client<S>,
 }
 
@@ -46,10 +48,17 @@ where
             workload,
             limits,
             default_detect_timeout,
-            client: Client::new(client),
+            overrides: Default::default(),
+            client: Client::new(client).accept_compressed(CompressionEncoding::Gzip),
         }
     }
 
+    /// Applies locally-configured settings to each policy returned by the
+    /// control plane.
+    pub(crate) fn with_overrides(self, overrides: Arc<Overrides>) -> Self {
+        Self { overrides, ..self }
+    }
+
     pub(crate) fn into_watch(self, backoff: ExponentialBackoff) -> Watch<S> {
         StreamWatch::new(GrpcRecover(backoff), self)
     }
@@ -89,12 +98,17 @@ where
 
         let detect_timeout = self.default_detect_timeout;
         let limits = self.limits;
+        let overrides = self.overrides.clone();
         let mut client = self.client.clone();
         Box::pin(async move {
             let rsp =
                 LimitReceiveFuture::new(limits, client.watch(tonic::Request::new(req))).await?;
             Ok(rsp.map(move |s| {
-                s.map_ok(move |up| {
+                // The policy controller may send updates that are identical to
+                // the prior update. These are skipped so that stacks are not
+                // rebuilt needlessly.
+                let mut prior = None;
+                s.try_filter_map(move |up| {
                     // If the server returned an invalid client policy, we
                     // default to using an invalid policy that causes all
                     // requests to report an internal error.
@@ -104,8 +118,14 @@ where
                             .get_or_init(|| ClientPolicy::invalid(detect_timeout))
                             .clone()
                     });
+                    let policy = overrides.apply(policy);
+                    if prior.as_ref() == Some(&policy) {
+                        tracing::debug!("Policy unchanged");
+                        return future::ok(None);
+                    }
                     tracing::debug!(?policy);
-                    policy
+                    prior = Some(policy.clone());
+                    future::ok(Some(policy))
                 })
                 .boxed()
             }))
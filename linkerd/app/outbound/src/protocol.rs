@@ -51,6 +51,8 @@ impl<N> Outbound<N> {
                 .unlift_new()
                 .push(http::NewServeHttp::layer(
                     config.proxy.server.h2_settings,
+                    config.proxy.server.max_connection_age,
+                    config.proxy.server.preserve_trailers,
                     rt.drain.clone(),
                 ))
                 .arc_new_tcp()
@@ -15,7 +15,7 @@ use linkerd_app_core::{
     transport::addrs::*,
     Error,
 };
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 use tokio::sync::watch;
 use tracing::info_span;
 
@@ -62,7 +62,9 @@ impl Outbound<()> {
         let http = self
             .to_tcp_connect()
             .push_tcp_endpoint()
-            .push_http_tcp_client()
+            .push_http_tcp_client(http::PoolMetrics::register(
+                registry.sub_registry_with_prefix("http_pool"),
+            ))
             .push_http_cached(http::HttpMetrics::register(registry), resolve)
             .push_http_server()
             .into_stack()
@@ -185,6 +187,37 @@ impl From<protocol::Http<Sidecar>> for HttpSidecar {
             // Only use service profiles if there are novel routes/target
             // overrides.
             if let Some(addr) = http::profile::should_override_policy(&profile) {
+                // While a workload migrates from ServiceProfiles to policy
+                // routes, a profile that only configures routes is applied to
+                // the policy routes, so that its retries and timeouts are not
+                // lost. Traffic splits can't be merged, so they continue to
+                // override policy routes.
+                let init = Self::mk_merged_routes(
+                    orig_dst,
+                    version,
+                    &policy.borrow_and_update(),
+                    addr.clone(),
+                    &profile.borrow(),
+                );
+                if let Some(init) = init {
+                    tracing::debug!(
+                        "Using ClientPolicy routes with ServiceProfile retries and timeouts"
+                    );
+                    let routes = http::spawn_merged_routes(
+                        policy,
+                        profile,
+                        init,
+                        move |policy: &policy::ClientPolicy, profile: &profiles::Profile| {
+                            Self::mk_merged_routes(orig_dst, version, policy, addr.clone(), profile)
+                        },
+                    );
+                    return HttpSidecar {
+                        orig_dst,
+                        version,
+                        routes,
+                    };
+                }
+
                 tracing::debug!("Using ServiceProfile");
                 let init = Self::mk_profile_routes(addr.clone(), &profile.borrow_and_update());
                 let routes =
@@ -254,6 +287,7 @@ impl HttpSidecar {
                         backends: policy.backends.clone(),
                         routes: routes.clone(),
                         failure_accrual,
+                        profile_routes: None,
                     },
                 )))
             }
@@ -272,10 +306,35 @@ impl HttpSidecar {
                 routes,
                 backends: policy.backends.clone(),
                 failure_accrual,
+                profile_routes: None,
             },
         )))
     }
 
+    /// Builds policy routes that apply the profile's routes, if the profile
+    /// configures no traffic split and the policy has resource routes.
+    fn mk_merged_routes(
+        orig_dst: OrigDstAddr,
+        version: http::Version,
+        policy: &policy::ClientPolicy,
+        addr: profiles::LogicalAddr,
+        profile: &profiles::Profile,
+    ) -> Option<http::Routes> {
+        if !profile.targets.is_empty() {
+            return None;
+        }
+        match Self::mk_policy_routes(orig_dst, version, policy)? {
+            http::Routes::Policy(params) if params.has_resource_routes() => Some(
+                http::Routes::Policy(params.with_profile_routes(http::profile::Routes {
+                    addr,
+                    routes: profile.http_routes.clone(),
+                    targets: Arc::new([]),
+                })),
+            ),
+            _ => None,
+        }
+    }
+
     fn mk_profile_routes(addr: profiles::LogicalAddr, profile: &profiles::Profile) -> http::Routes {
         http::Routes::Profile(http::profile::Routes {
             addr,
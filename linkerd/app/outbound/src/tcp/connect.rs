@@ -2,7 +2,11 @@ use crate::Outbound;
 use futures::future;
 use linkerd_app_core::{
     io, svc, tls,
-    transport::{addrs::*, ConnectTcp},
+    transport::{
+        addrs::*,
+        metrics::{health, peer},
+        ConnectTcp,
+    },
 };
 use std::task::{Context, Poll};
 
@@ -20,9 +24,23 @@ pub struct PreventLoopback<S>(S);
 // === impl Outbound ===
 
 impl Outbound<()> {
-    pub fn to_tcp_connect(&self) -> Outbound<PreventLoopback<ConnectTcp>> {
-        let connect = PreventLoopback(ConnectTcp::new(self.config.proxy.connect.keepalive));
-        self.clone().with_stack(connect)
+    pub fn to_tcp_connect(
+        &self,
+    ) -> Outbound<PreventLoopback<peer::Connect<health::Client<ConnectTcp>>>> {
+        let connect = svc::stack(ConnectTcp::new(
+            self.config.proxy.connect.keepalive,
+            self.config.proxy.connect.socket_options,
+        ))
+        // Samples the health of long-lived connections, if enabled.
+        .push(health::Client::layer(
+            self.runtime.metrics.proxy.socket_health.clone(),
+        ))
+        // Records per-peer metrics, if enabled.
+        .push(peer::Connect::layer(
+            self.runtime.metrics.proxy.peers.clone(),
+        ))
+        .into_inner();
+        self.clone().with_stack(PreventLoopback(connect))
     }
 }
 
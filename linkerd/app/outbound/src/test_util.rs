@@ -20,16 +20,31 @@ pub(crate) fn default_config() -> Config {
     };
     Config {
         ingress_mode: false,
+        ingress_authority_overrides: Default::default(),
+        ingress_fallback: Default::default(),
+        ingress_dns_fallback_ttl: Default::default(),
+        endpoint_pinning: None,
+        endpoint_failover: None,
+        egress_policy: Default::default(),
+        policy_overrides: Default::default(),
+        http_retry: Default::default(),
+        http_expect_continue: Default::default(),
         emit_headers: true,
         allow_discovery: IpMatch::new(Some(IpNet::from_str("0.0.0.0/0").unwrap())).into(),
         proxy: config::ProxyConfig {
             server: config::ServerConfig {
                 addr: ListenAddr(([0, 0, 0, 0], 0).into()),
                 keepalive: Keepalive(None),
+                socket_options: Default::default(),
+                accept_shards: Default::default(),
+                reuse_port: Default::default(),
                 h2_settings: h2::Settings::default(),
+                max_connection_age: None,
+                preserve_trailers: true,
             },
             connect: config::ConnectConfig {
                 keepalive: Keepalive(None),
+                socket_options: Default::default(),
                 timeout: Duration::from_secs(1),
                 backoff: exp_backoff::ExponentialBackoff::try_new(
                     Duration::from_millis(100),
@@ -40,11 +55,14 @@ pub(crate) fn default_config() -> Config {
                 h1_settings: h1::PoolSettings {
                     max_idle: 1,
                     idle_timeout: Duration::from_secs(1),
+                    max_requests_per_connection: None,
+                    warm_connections: 0,
                 },
                 h2_settings: h2::Settings::default(),
             },
             max_in_flight_requests: 10_000,
             detect_protocol_timeout: Duration::from_secs(3),
+            deadline_header: None,
         },
         inbound_ips: Default::default(),
         discovery_idle_timeout: Duration::from_secs(60),
@@ -55,7 +73,7 @@ pub(crate) fn default_config() -> Config {
 
 pub(crate) fn runtime() -> (ProxyRuntime, drain::Signal) {
     let (drain_tx, drain) = drain::channel();
-    let (tap, _) = tap::new();
+    let (tap, _) = tap::new(Default::default());
     let (metrics, _) = metrics::Metrics::new(std::time::Duration::from_secs(10));
     let runtime = ProxyRuntime {
         identity: linkerd_meshtls_rustls::creds::default_for_test().1.into(),
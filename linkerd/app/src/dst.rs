@@ -1,11 +1,9 @@
 use linkerd_app_core::{
-    control, dns,
+    control,
     exp_backoff::{ExponentialBackoff, ExponentialBackoffStream},
-    identity,
-    metrics::{self, prom},
     profiles::{self, DiscoveryRejected},
     proxy::{api_resolve as api, http, resolve::recover},
-    svc::{self, NewService, ServiceExt},
+    svc::{self, ServiceExt},
     Error, Recover,
 };
 use linkerd_tonic_stream::ReceiveLimits;
@@ -35,12 +33,11 @@ pub struct BackoffUnlessInvalidArgument(ExponentialBackoff);
 // === impl Config ===
 
 impl Config {
+    /// Builds clients that send their requests over the given control plane
+    /// client, so that their watches share its connections.
     pub fn build(
         self,
-        dns: dns::Resolver,
-        metrics: metrics::ControlHttp,
-        registry: &mut prom::Registry,
-        identity: identity::NewClient,
+        client: control::Client,
     ) -> Result<
         Dst<
             impl svc::Service<
@@ -54,11 +51,7 @@ impl Config {
     > {
         let addr = self.control.addr.clone();
         let backoff = BackoffUnlessInvalidArgument(self.control.connect.backoff);
-        let svc = self
-            .control
-            .build(dns, metrics, registry, identity)
-            .new_service(())
-            .map_err(Error::from);
+        let svc = client.map_err(Error::from);
 
         let profiles = profiles::Client::new_recover_default(
             backoff,
@@ -1,11 +1,18 @@
-use crate::{dns, gateway, identity, inbound, oc_collector, outbound, policy};
+use crate::{
+    dns, gateway, identity, inbound, metrics_push, oc_collector, otlp_metrics, outbound, policy,
+    quota,
+};
 use linkerd_app_core::{
     addr,
     config::*,
     control::{Config as ControlConfig, ControlAddr},
-    proxy::http::{h1, h2},
+    metrics,
+    proxy::{
+        http::{self, h1, h2},
+        tap,
+    },
     tls,
-    transport::{Keepalive, ListenAddr},
+    transport::{self, AcceptShards, Keepalive, ListenAddr, ReusePort, SocketOptions},
     Addr, AddrMatch, Conditional, IpNet,
 };
 use linkerd_tonic_stream::ReceiveLimits;
@@ -14,8 +21,10 @@ use std::{
     collections::{HashMap, HashSet},
     fs,
     net::{IpAddr, SocketAddr},
+    num::{NonZeroU32, NonZeroU64},
     path::PathBuf,
     str::FromStr,
+    sync::Arc,
     time::Duration,
 };
 use thiserror::Error;
@@ -89,18 +98,251 @@ pub enum ParseError {
     InvalidTrustAnchors,
     #[error("not a valid port policy: {0}")]
     InvalidPortPolicy(String),
+    #[error("not a valid histogram growth factor")]
+    NotAGrowthFactor,
+    #[error("not a valid list of histogram buckets")]
+    NotHistogramBuckets,
+    #[error("not a valid aggregation temporality")]
+    NotATemporality,
+    #[error("not a valid key=value attribute")]
+    NotAnAttribute,
+    #[error("not an http:// URL")]
+    NotAPushUrl,
+    #[error("not a valid metrics push format")]
+    NotAPushFormat,
+    #[error("not a valid peer metrics granularity")]
+    NotAPeerGranularity,
+    #[error("not a valid HTTP header name")]
+    NotAHeaderName,
+    #[error("not a valid authority rewrite rule")]
+    NotAnAuthorityRewrite,
+    #[error("not a valid ingress fallback")]
+    NotAnIngressFallback,
+    #[error("not a valid 100-continue mode")]
+    NotAnExpectContinueMode,
+    #[error("not a valid failover cluster")]
+    NotAFailoverCluster,
+    #[error("not a valid host match: {0}")]
+    NotAHostMatch(String),
+    #[error("not a valid public key pin")]
+    NotAPublicKeyPin,
+    #[error("could not read HMAC secrets")]
+    InvalidHmacSecrets,
+    #[error("not a valid SNI route: {0}")]
+    NotAnSniRoute(String),
+    #[error("not a valid TCP setting: {0}")]
+    NotATcpSetting(String),
+    #[error("not a valid route setting: {0}")]
+    NotARouteSetting(String),
+    #[error("not a valid authorization setting: {0}")]
+    NotAnAuthzSetting(String),
+    #[error("not a valid DNS suffix route: {0}")]
+    NotADnsSuffixRoute(String),
 }
 
 // Environment variables to look at when loading the configuration
 pub const ENV_OUTBOUND_LISTEN_ADDR: &str = "LINKERD2_PROXY_OUTBOUND_LISTEN_ADDR";
 pub const ENV_INBOUND_LISTEN_ADDR: &str = "LINKERD2_PROXY_INBOUND_LISTEN_ADDR";
+/// A comma-separated list of `IP:PORT` addresses on which inbound connections
+/// are accepted in addition to `ENV_INBOUND_LISTEN_ADDR`. Policy is discovered
+/// for each listener's port.
+pub const ENV_INBOUND_ADDITIONAL_LISTEN_ADDRS: &str =
+    "LINKERD2_PROXY_INBOUND_ADDITIONAL_LISTEN_ADDRS";
 pub const ENV_CONTROL_LISTEN_ADDR: &str = "LINKERD2_PROXY_CONTROL_LISTEN_ADDR";
+/// The time after which an unresponsive destination or policy controller
+/// connection is considered unhealthy, so that the discovery watches
+/// multiplexed over it are re-established on a new connection.
+pub const ENV_CONTROL_HTTP2_KEEPALIVE_TIMEOUT: &str =
+    "LINKERD2_PROXY_CONTROL_HTTP2_KEEPALIVE_TIMEOUT";
+/// A comma-separated list of `audience=path` pairs naming files that hold
+/// bound service account tokens. A controller's `{base}_TOKEN_AUDIENCE`
+/// selects the token with which the proxy authenticates to it.
+pub const ENV_CONTROL_TOKEN_SOURCES: &str = "LINKERD2_PROXY_CONTROL_TOKEN_SOURCES";
 pub const ENV_ADMIN_LISTEN_ADDR: &str = "LINKERD2_PROXY_ADMIN_LISTEN_ADDR";
 
 pub const ENV_METRICS_RETAIN_IDLE: &str = "LINKERD2_PROXY_METRICS_RETAIN_IDLE";
 
+/// When set, latency histograms use sparse, exponentially-sized buckets whose
+/// upper bounds grow by this factor (e.g. `1.1`) instead of fixed buckets.
+pub const ENV_METRICS_HISTOGRAM_GROWTH_FACTOR: &str =
+    "LINKERD2_PROXY_METRICS_HISTOGRAM_GROWTH_FACTOR";
+
+/// Overrides the buckets of latency histograms by metric family, e.g.
+/// `response_latency_ms=0.1,0.5,1,5;route_response_latency_ms=1,10,100`.
+/// Bucket bounds are expressed in milliseconds.
+pub const ENV_METRICS_LATENCY_BUCKETS: &str = "LINKERD2_PROXY_METRICS_LATENCY_BUCKETS";
+
+/// Enables per-peer TCP metrics for outbound connections, grouped by the
+/// peer's `ip` or by its `endpoint` (IP and port). Disabled by default.
+pub const ENV_METRICS_PEERS: &str = "LINKERD2_PROXY_METRICS_PEERS";
+
+/// The maximum number of peers tracked individually by per-peer TCP metrics.
+/// Connections to additional peers are aggregated under `peer="other"`.
+pub const ENV_METRICS_PEERS_MAX: &str = "LINKERD2_PROXY_METRICS_PEERS_MAX";
+
+/// The interval at which the `TCP_INFO` of long-lived connections is sampled to
+/// record socket health metrics. Set to `0s` to disable sampling.
+pub const ENV_METRICS_TCP_INFO_INTERVAL: &str = "LINKERD2_PROXY_METRICS_TCP_INFO_INTERVAL";
+
+/// The directory to which captures triggered via the admin server are written.
+/// Defaults to the system's temporary directory.
+pub const ENV_ADMIN_CAPTURE_DIR: &str = "LINKERD2_PROXY_ADMIN_CAPTURE_DIR";
+
+/// The path of a Unix socket to which inbound requests may be mirrored for
+/// inspection by node tooling. Mirroring is only enabled when tap is enabled.
+pub const ENV_ADMIN_MIRROR_SOCKET: &str = "LINKERD2_PROXY_ADMIN_MIRROR_SOCKET";
+
+/// A comma-separated list of client identities that may inject faults into
+/// outbound routes via the admin server. Fault injection is disabled if unset.
+pub const ENV_ADMIN_CHAOS_IDENTITIES: &str = "LINKERD2_PROXY_ADMIN_CHAOS_IDENTITIES";
+
 const ENV_INGRESS_MODE: &str = "LINKERD2_PROXY_INGRESS_MODE";
 
+/// A comma-separated list of `host=rewrite` rules that rewrite the authorities
+/// in ingress-mode `l5d-dst-override` headers before they are discovered. A
+/// rewrite is a `host`, a `host:port`, or a `:port`, which only sets a port on
+/// authorities that lack one.
+pub const ENV_INGRESS_AUTHORITY_REWRITES: &str = "LINKERD2_PROXY_INGRESS_AUTHORITY_REWRITES";
+
+/// The port used for `l5d-dst-override` authorities that do not specify one.
+/// Defaults to 80.
+pub const ENV_INGRESS_AUTHORITY_DEFAULT_PORT: &str =
+    "LINKERD2_PROXY_INGRESS_AUTHORITY_DEFAULT_PORT";
+
+/// A comma-separated list of networks from which clients may set the
+/// `l5d-dst-override` header. When unset, all clients may set it.
+pub const ENV_INGRESS_OVERRIDE_ALLOWED_CLIENTS: &str =
+    "LINKERD2_PROXY_INGRESS_OVERRIDE_ALLOWED_CLIENTS";
+
+/// How ingress-mode proxies route requests when the `l5d-dst-override`
+/// authority has no discovery result: `fail` responds with a 502, `orig-dst`
+/// routes to the connection's original destination, and `dns` forwards to an
+/// address resolved via DNS. Defaults to `fail`.
+pub const ENV_INGRESS_FALLBACK: &str = "LINKERD2_PROXY_INGRESS_FALLBACK";
+
+/// Bound how long an address resolved by the `dns` ingress fallback is used
+/// before it is resolved again in the background, regardless of the TTLs of
+/// its records. Default to 5s and 5m, respectively.
+pub const ENV_INGRESS_DNS_FALLBACK_MIN_TTL: &str = "LINKERD2_PROXY_INGRESS_DNS_FALLBACK_MIN_TTL";
+pub const ENV_INGRESS_DNS_FALLBACK_MAX_TTL: &str = "LINKERD2_PROXY_INGRESS_DNS_FALLBACK_MAX_TTL";
+
+/// The request header with which outbound clients may pin requests to an
+/// endpoint of a load balanced backend, named by its IP address or socket
+/// address. Endpoint pinning is disabled unless this is set.
+pub const ENV_OUTBOUND_ENDPOINT_PIN_HEADER: &str = "LINKERD2_PROXY_OUTBOUND_ENDPOINT_PIN_HEADER";
+
+/// Authorizes the clients that may pin requests to endpoints, as
+/// comma-separated settings:
+///
+/// - `name=<name>` names the authorization in logs, defaulting to
+///   `endpoint-pinning`;
+/// - `networks=<net>+<net>` lists the client networks that are authorized;
+/// - `not-before=<time>` and `not-after=<time>` bound when the authorization
+///   is valid, as RFC 3339 timestamps.
+///
+/// Requests from unauthorized clients are load balanced. No clients are
+/// authorized unless networks are configured.
+pub const ENV_OUTBOUND_ENDPOINT_PIN_AUTHZ: &str = "LINKERD2_PROXY_OUTBOUND_ENDPOINT_PIN_AUTHZ";
+
+/// A comma-separated list of remote clusters to which outbound balancers may
+/// fail over, each as `name=min-local-endpoints`. A cluster's gateway endpoints
+/// are balanced while fewer than its number of local endpoints are available.
+/// Endpoint failover is disabled unless this is set.
+pub const ENV_OUTBOUND_FAILOVER_CLUSTERS: &str = "LINKERD2_PROXY_OUTBOUND_FAILOVER_CLUSTERS";
+
+/// The endpoint label that names a remote endpoint's cluster. Defaults to
+/// `cluster`.
+pub const ENV_OUTBOUND_FAILOVER_CLUSTER_LABEL: &str =
+    "LINKERD2_PROXY_OUTBOUND_FAILOVER_CLUSTER_LABEL";
+
+/// A comma-separated list of authorities to which this workload may not send
+/// HTTP requests, e.g. `*.example.com` or `api.example.com:443`. Requests and
+/// opaque connections to logical services with these names are denied, as
+/// are HTTP requests whose authority matches on policy routes.
+pub const ENV_OUTBOUND_EGRESS_DENY_AUTHORITIES: &str =
+    "LINKERD2_PROXY_OUTBOUND_EGRESS_DENY_AUTHORITIES";
+
+/// A comma-separated list of the names of outbound routes on which this
+/// workload may not send requests.
+pub const ENV_OUTBOUND_EGRESS_DENY_ROUTES: &str = "LINKERD2_PROXY_OUTBOUND_EGRESS_DENY_ROUTES";
+
+/// Configures outbound HTTP and gRPC routes with settings that the policy
+/// controller cannot express. Settings apply to every route whose resource
+/// has the given name.
+///
+/// This is a comma-separated list of `route/setting=value` pairs, where the
+/// settings are:
+///
+/// - `direct-response`: answers the route's HTTP requests with a response
+///   with the given status code, rather than forwarding them;
+/// - `direct-response-body`: the body of the direct response;
+/// - `direct-response-headers`: the `+`-separated `name:value` headers of the
+///   direct response;
+/// - `cache`: caches the route's responses to `GET` requests in memory, up to
+///   the given total number of bytes;
+/// - `cache-max-entry-bytes`: the size of the largest response body that is
+///   cached (1MiB by default);
+/// - `cache-vary`: the `+`-separated request headers that distinguish cached
+///   responses, in addition to those named by responses' `Vary` headers;
+/// - `decompress`: decompresses the route's gzip-encoded request bodies,
+///   failing requests whose bodies expand beyond the given multiple of their
+///   compressed size;
+/// - `decompress-max-bytes`: the size of the largest decompressed request
+///   body;
+/// - `path-template`: restricts the route's HTTP rules to requests whose paths
+///   match a template like `/users/{id}/orders/{order}`. Captured segments
+///   may be referenced by the route's filters;
+/// - `header-present` and `header-absent`: restricts the route's rules to
+///   requests that set, or do not set, each of the `+`-separated headers;
+/// - `header-exact-ignore-case`: restricts the route's rules to requests with
+///   each of the `+`-separated `name:value` headers, ignoring the case of
+///   values;
+/// - `content-type`: restricts the route's HTTP rules to requests with the
+///   given media type, e.g. `application/json`, or any subtype of a media
+///   type, e.g. `multipart/*`;
+/// - `max-request-body-bytes`: the size of the largest request body that the
+///   route forwards. Larger requests fail with a `413 Payload Too Large`
+///   response;
+/// - `streaming-idle-timeout`: treats the route's responses as streams, e.g.
+///   server-sent events, that are terminated once no data has been received
+///   for the given duration. The route's request timeout then bounds the time
+///   until the first byte of the response body;
+/// - `sticky`: consistently assigns requests to the route's weighted backends
+///   by `header:<name>`, `cookie:<name>`, or `client-ip`;
+/// - `mirror`: mirrors the route's requests to the given `host:port`, which is
+///   resolved by the destination controller, and compares the responses;
+/// - `mirror-compare-headers`: the `+`-separated response headers whose values
+///   are compared with those of the shadow backend's responses;
+/// - `success-objective`: the percentage of the route's requests that must
+///   succeed, e.g. `99.9`, so that the proxy reports how quickly failures
+///   consume the route's error budget;
+/// - `success-objective-windows`: the `+`-separated windows over which the
+///   error budget's burn rate is reported (`5m+1h` by default).
+///
+/// For example, `maintenance/direct-response=503,maintenance/direct-response-body=down`.
+pub const ENV_OUTBOUND_ROUTE_SETTINGS: &str = "LINKERD2_PROXY_OUTBOUND_ROUTE_SETTINGS";
+
+/// The maximum number of request body bytes that are buffered so that
+/// requests on retryable routes may be retried. Requests with larger bodies
+/// are not retried.
+pub const ENV_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES";
+
+/// When set, requests with non-idempotent methods (e.g. POSTs) on retryable
+/// routes are only retried when they carry this header (e.g.
+/// `idempotency-key`), and only once their bodies have been completely
+/// buffered.
+pub const ENV_OUTBOUND_HTTP_RETRY_IDEMPOTENCY_HEADER: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_IDEMPOTENCY_HEADER";
+
+/// How outbound requests with an `Expect: 100-continue` header are handled:
+/// `pass-through` forwards the expectation to the server, `answer` tells the
+/// client to continue as soon as the request is received, and `strip` removes
+/// the expectation before the request is forwarded. Unless the expectation is
+/// answered, requests that exceed a route's body size limit fail before their
+/// clients send their bodies. Defaults to `pass-through`.
+pub const ENV_OUTBOUND_HTTP_EXPECT_CONTINUE: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_EXPECT_CONTINUE";
+
 const ENV_INBOUND_HTTP_QUEUE_CAPACITY: &str = "LINKERD2_PROXY_INBOUND_HTTP_QUEUE_CAPACITY";
 const ENV_INBOUND_HTTP_FAILFAST_TIMEOUT: &str = "LINKERD2_PROXY_INBOUND_HTTP_FAILFAST_TIMEOUT";
 
@@ -118,16 +360,142 @@ const ENV_OUTBOUND_CONNECT_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_CONNECT_TIME
 const ENV_INBOUND_ACCEPT_KEEPALIVE: &str = "LINKERD2_PROXY_INBOUND_ACCEPT_KEEPALIVE";
 const ENV_OUTBOUND_ACCEPT_KEEPALIVE: &str = "LINKERD2_PROXY_OUTBOUND_ACCEPT_KEEPALIVE";
 
+/// Limits how long an accepted connection is served before it is closed, so
+/// that clients periodically reconnect (and are rebalanced and reauthorized).
+/// HTTP connections are closed gracefully, with a GOAWAY for HTTP/2 and a
+/// `connection: close` response for HTTP/1; opaque TCP connections are closed.
+///
+/// By default, connections may be served indefinitely.
+pub const ENV_INBOUND_MAX_CONNECTION_AGE: &str = "LINKERD2_PROXY_INBOUND_MAX_CONNECTION_AGE";
+pub const ENV_OUTBOUND_MAX_CONNECTION_AGE: &str = "LINKERD2_PROXY_OUTBOUND_MAX_CONNECTION_AGE";
+
+/// Configures whether HTTP/1 requests retain the client's acceptance of
+/// trailers (`te: trailers`), so that trailers are preserved when requests are
+/// bridged to HTTP/2 (and back).
+///
+/// Informational (1xx) responses such as `103 Early Hints` are not forwarded,
+/// regardless of this setting: the proxy's HTTP library can neither receive
+/// them from servers nor send them to clients.
+///
+/// Enabled by default.
+pub const ENV_HTTP_PRESERVE_TRAILERS: &str = "LINKERD2_PROXY_HTTP_PRESERVE_TRAILERS";
+
+/// Delays closing an inbound connection after its authorization is revoked
+/// (e.g. by a policy update or an expired authorization). If the connection
+/// is authorized again before the grace period elapses, it is not closed.
+///
+/// By default, connections are closed as soon as they are no longer
+/// authorized.
+pub const ENV_INBOUND_AUTHORIZATION_REVOCATION_GRACE_PERIOD: &str =
+    "LINKERD2_PROXY_INBOUND_AUTHORIZATION_REVOCATION_GRACE_PERIOD";
+
 const ENV_INBOUND_CONNECT_KEEPALIVE: &str = "LINKERD2_PROXY_INBOUND_CONNECT_KEEPALIVE";
 const ENV_OUTBOUND_CONNECT_KEEPALIVE: &str = "LINKERD2_PROXY_OUTBOUND_CONNECT_KEEPALIVE";
 
+/// Configures the number of sockets that are bound (with `SO_REUSEPORT`) to
+/// each proxy listener, so that connections may be accepted concurrently.
+///
+/// Defaults to a single socket.
+pub const ENV_INBOUND_ACCEPT_SHARDS: &str = "LINKERD2_PROXY_INBOUND_ACCEPT_SHARDS";
+pub const ENV_OUTBOUND_ACCEPT_SHARDS: &str = "LINKERD2_PROXY_OUTBOUND_ACCEPT_SHARDS";
+
+/// Configures all of the proxy's listeners to be bound with `SO_REUSEPORT`.
+///
+/// This enables in-place upgrades: a new proxy process may bind the same
+/// addresses while the prior process is still running. Once the new process is
+/// ready, the prior process should be sent a shutdown signal so that it stops
+/// accepting connections and drains its existing connections within
+/// `LINKERD2_PROXY_SHUTDOWN_GRACE_PERIOD`. On Linux, the
+/// `net.ipv4.tcp_migrate_req` sysctl should be enabled so that connections that
+/// are still queued on the prior process's listeners are migrated to the new
+/// process's listeners rather than being reset.
+///
+/// Defaults to false.
+pub const ENV_LISTEN_REUSE_PORT: &str = "LINKERD2_PROXY_LISTEN_REUSE_PORT";
+
 const ENV_INBOUND_MAX_IDLE_CONNS_PER_ENDPOINT: &str = "LINKERD2_PROXY_MAX_IDLE_CONNS_PER_ENDPOINT";
 const ENV_OUTBOUND_MAX_IDLE_CONNS_PER_ENDPOINT: &str =
     "LINKERD2_PROXY_OUTBOUND_MAX_IDLE_CONNS_PER_ENDPOINT";
 
+/// Configures the number of HTTP/1 connections that are established to each
+/// outbound endpoint as soon as it is discovered, and kept ready for use.
+///
+/// Warm connections are replaced as they are used, when they are closed by the
+/// endpoint, and when they are idle for longer than the HTTP/1 connection pool
+/// idle timeout. Failed connection attempts are retried with a backoff. HTTP/2
+/// endpoints always establish a single connection eagerly. By default, no
+/// HTTP/1 connections are warmed.
+pub const ENV_OUTBOUND_WARM_CONNS_PER_ENDPOINT: &str =
+    "LINKERD2_PROXY_OUTBOUND_WARM_CONNS_PER_ENDPOINT";
+
 pub const ENV_INBOUND_MAX_IN_FLIGHT: &str = "LINKERD2_PROXY_INBOUND_MAX_IN_FLIGHT";
 pub const ENV_OUTBOUND_MAX_IN_FLIGHT: &str = "LINKERD2_PROXY_OUTBOUND_MAX_IN_FLIGHT";
 
+/// Names a request header (e.g. `x-deadline-ms`) that carries the number of
+/// milliseconds a client will wait for a response. When set, the inbound and
+/// outbound proxies fail requests that exceed their deadline and rewrite the
+/// header with the time remaining before forwarding requests. By default, no
+/// deadline header is honored.
+pub const ENV_HTTP_DEADLINE_HEADER: &str = "LINKERD2_PROXY_HTTP_DEADLINE_HEADER";
+
+/// Limits the number of connections that may be open on the inbound listener
+/// at once. Connections that exceed the limit are closed immediately. By
+/// default, connections are not limited.
+pub const ENV_INBOUND_MAX_CONNECTIONS: &str = "LINKERD2_PROXY_INBOUND_MAX_CONNECTIONS";
+
+/// Limits the number of inbound connections that a single client IP address
+/// may hold open at once. By default, connections are not limited.
+pub const ENV_INBOUND_MAX_CONNECTIONS_PER_CLIENT: &str =
+    "LINKERD2_PROXY_INBOUND_MAX_CONNECTIONS_PER_CLIENT";
+
+/// Limits the number of connections that the inbound listener accepts per
+/// second. By default, the accept rate is not limited.
+pub const ENV_INBOUND_MAX_ACCEPT_RATE: &str = "LINKERD2_PROXY_INBOUND_MAX_ACCEPT_RATE";
+
+/// A comma-separated list of media types, e.g. `text/html,application/json`,
+/// of inbound HTTP responses that are compressed for clients that accept gzip
+/// or brotli encodings. Wildcards such as `text/*` match all subtypes. By
+/// default, responses are not compressed.
+pub const ENV_INBOUND_COMPRESSION_CONTENT_TYPES: &str =
+    "LINKERD2_PROXY_INBOUND_COMPRESSION_CONTENT_TYPES";
+
+/// The minimum size, in bytes, of inbound HTTP responses that are compressed.
+/// Responses of unknown length are always eligible for compression.
+pub const ENV_INBOUND_COMPRESSION_MIN_BYTES: &str = "LINKERD2_PROXY_INBOUND_COMPRESSION_MIN_BYTES";
+
+/// Enables the translation of inbound gRPC-web requests into gRPC, so that
+/// browser clients may call meshed gRPC services without a separate gRPC-web
+/// proxy. Defaults to false.
+pub const ENV_INBOUND_GRPC_WEB: &str = "LINKERD2_PROXY_INBOUND_GRPC_WEB";
+
+/// A directory of secrets with which clients sign requests for HMAC
+/// authorizations. Each file holds one secret, named by the file's name, so
+/// that a Kubernetes secret volume may be mounted directly.
+pub const ENV_INBOUND_HMAC_SECRETS_DIR: &str = "LINKERD2_PROXY_INBOUND_HMAC_SECRETS_DIR";
+
+/// Enables the re-authorization of inbound gRPC response streams whenever the
+/// server's policy changes, so that streams are failed with
+/// `PERMISSION_DENIED` once their client is no longer authorized. Defaults to
+/// false.
+pub const ENV_INBOUND_GRPC_STREAM_REAUTHORIZATION: &str =
+    "LINKERD2_PROXY_INBOUND_GRPC_STREAM_REAUTHORIZATION";
+
+/// When set, inbound gRPC response streams are also re-authorized at this
+/// interval (e.g. to enforce the expiry of time-limited authorizations). This
+/// implies `LINKERD2_PROXY_INBOUND_GRPC_STREAM_REAUTHORIZATION`.
+pub const ENV_INBOUND_GRPC_STREAM_REAUTHORIZATION_INTERVAL: &str =
+    "LINKERD2_PROXY_INBOUND_GRPC_STREAM_REAUTHORIZATION_INTERVAL";
+
+/// When set, the inbound proxy reports estimated request counts for this many
+/// of each authorization's most frequent clients. Disabled by default.
+pub const ENV_INBOUND_AUTHZ_TOP_CLIENTS: &str = "LINKERD2_PROXY_INBOUND_AUTHZ_TOP_CLIENTS";
+
+/// The maximum length, in bytes, of an inbound request's path and query.
+/// Longer requests are rejected with a 414 before they are routed. A limit of
+/// 0 disables the check.
+pub const ENV_INBOUND_MAX_REQUEST_TARGET_LENGTH: &str =
+    "LINKERD2_PROXY_INBOUND_MAX_REQUEST_TARGET_LENGTH";
+
 const ENV_OUTBOUND_DISABLE_INFORMATIONAL_HEADERS: &str =
     "LINKERD2_PROXY_OUTBOUND_DISABLE_INFORMATIONAL_HEADERS";
 
@@ -164,6 +532,11 @@ pub const ENV_INBOUND_GATEWAY_SUFFIXES: &str = "LINKERD2_PROXY_INBOUND_GATEWAY_S
 pub const ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION: &str =
     "LINKERD2_PROXY_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION";
 
+/// A file that is watched for a list of ports on which protocol detection is
+/// disabled, in addition to `LINKERD2_PROXY_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION`.
+pub const ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION_FILE: &str =
+    "LINKERD2_PROXY_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION_FILE";
+
 pub const ENV_INBOUND_PORTS_REQUIRE_IDENTITY: &str =
     "LINKERD2_PROXY_INBOUND_PORTS_REQUIRE_IDENTITY";
 
@@ -177,11 +550,136 @@ pub const ENV_INBOUND_PORTS_REQUIRE_TLS: &str = "LINKERD2_PROXY_INBOUND_PORTS_RE
 /// By default, this is `unauthenticated`.
 pub const ENV_INBOUND_DEFAULT_POLICY: &str = "LINKERD2_PROXY_INBOUND_DEFAULT_POLICY";
 
+/// Configures default policies for individual inbound ports, overriding
+/// `LINKERD2_PROXY_INBOUND_DEFAULT_POLICY` on those ports until (or, without a
+/// policy controller, instead of) their policies are discovered.
+///
+/// This is a comma-separated list of `port=policy` pairs, e.g.
+/// `25=deny,8080=cluster-authenticated`.
+pub const ENV_INBOUND_PORT_DEFAULT_POLICIES: &str = "LINKERD2_PROXY_INBOUND_PORT_DEFAULT_POLICIES";
+
+/// Configures inbound ports to pass TLS connections that are not addressed to
+/// the proxy through to backends selected by the connection's SNI. Routes
+/// apply to the port's default and discovered policies, and they retain the
+/// policy's connection authorizations.
+///
+/// This is a comma-separated list of `port/sni=backend` routes, where the SNI
+/// may be a wildcard (e.g. `*.example.com`) and the backend is either a port
+/// on the connection's original destination IP or a socket address, e.g.
+/// `443/api.example.com=8443,443/*.example.com=10.0.0.2:443`.
+pub const ENV_INBOUND_PORT_SNI_ROUTES: &str = "LINKERD2_PROXY_INBOUND_PORT_SNI_ROUTES";
+
+/// Configures how opaque TCP connections to inbound ports are forwarded. The
+/// settings apply to the port's default and discovered policies.
+///
+/// This is a comma-separated list of `port/setting=value` pairs, where the
+/// settings are:
+///
+/// - `half-close`: whether a peer's half-close is forwarded (`true`, the
+///   default) or closes the connection (`false`);
+/// - `linger`: how long a half-closed connection may remain open;
+/// - `idle-timeout`: how long a connection may remain open without data being
+///   sent in either direction.
+///
+/// For example, `5432/idle-timeout=1h,9092/half-close=false`.
+pub const ENV_INBOUND_PORT_TCP_SETTINGS: &str = "LINKERD2_PROXY_INBOUND_PORT_TCP_SETTINGS";
+
+/// Limits the bandwidth of each connection to inbound ports. Unlike
+/// `LINKERD2_PROXY_INBOUND_PORT_DEFAULT_POLICIES`, limits also apply to the
+/// policies that are discovered for these ports.
+///
+/// This is a comma-separated list of `port=bytes-per-second` pairs, e.g.
+/// `8080=1048576`.
+pub const ENV_INBOUND_PORT_BANDWIDTH_LIMITS: &str = "LINKERD2_PROXY_INBOUND_PORT_BANDWIDTH_LIMITS";
+
+/// Configures inbound HTTP and gRPC routes with settings that the policy
+/// controller cannot express. Settings apply to every route (on any port)
+/// whose resource has the given name.
+///
+/// This is a comma-separated list of `route/setting=value` pairs, where the
+/// settings are:
+///
+/// - `max-in-flight`: the number of requests that may be in flight on the
+///   route at once;
+/// - `rate-limit`: the number of requests per second admitted on the route,
+///   optionally followed by the size of bursts, e.g. `100:200`. Bursts are
+///   limited to one second of requests by default;
+/// - `priority`: how soon the route's requests are shed as the server
+///   saturates, one of `low`, `normal` (the default), or `high`;
+/// - `inject-client-addr`, `inject-client-identity`, `inject-tls`, and
+///   `inject-route`: name the request headers that describe the client's IP
+///   address, the client's identity, whether the connection is secured by
+///   TLS, and the route's name, respectively. Clients' values for these
+///   headers are always replaced;
+/// - `transcode`: the path of a protobuf `FileDescriptorSet` that describes
+///   the gRPC services to which the route's JSON requests are transcoded;
+/// - `path-template`: restricts the route's HTTP rules to requests whose paths
+///   match a template like `/users/{id}/orders/{order}`. Captured segments
+///   may be referenced by the route's filters;
+/// - `header-present` and `header-absent`: restricts the route's rules to
+///   requests that set, or do not set, each of the `+`-separated headers;
+/// - `header-exact-ignore-case`: restricts the route's rules to requests with
+///   each of the `+`-separated `name:value` headers, ignoring the case of
+///   values;
+/// - `content-type`: restricts the route's HTTP rules to requests with the
+///   given media type, e.g. `application/json`, or any subtype of a media
+///   type, e.g. `multipart/*`.
+///
+/// For example, `api/max-in-flight=100,api/rate-limit=50,batch/priority=low`.
+pub const ENV_INBOUND_ROUTE_SETTINGS: &str = "LINKERD2_PROXY_INBOUND_ROUTE_SETTINGS";
+
+/// Configures inbound authorizations with settings that the policy controller
+/// cannot express. Settings apply to every authorization whose resource has
+/// the given name.
+///
+/// This is a comma-separated list of `authorization/setting=value` pairs,
+/// where the settings are:
+///
+/// - `hmac-secret`: authenticates requests by their HMAC-SHA256 signatures,
+///   made with the named secret from `LINKERD2_PROXY_INBOUND_HMAC_SECRETS_DIR`,
+///   instead of by the client's identity;
+/// - `hmac-signature-header`: the header that carries a request's signature
+///   (`x-signature` by default);
+/// - `hmac-timestamp-header`: the header that carries the time at which a
+///   request was signed (`x-signature-timestamp` by default);
+/// - `hmac-max-skew`: how far a request's timestamp may be from the proxy's
+///   clock (`5m` by default);
+/// - `hmac-signed`: the `+`-separated request components covered by the
+///   signature, each of `method`, `authority`, `path`, or `header:<name>`
+///   (`method+authority+path` by default);
+/// - `local-addrs`: restricts the authorization to connections received on
+///   one of the `+`-separated local addresses, each a network, a port, or
+///   both, e.g. `10.0.0.0/8`, `:4191`, `10.0.0.0/8:4143`, or `[fd00::/8]:4143`;
+/// - `not-before` and `not-after`: restricts the authorization to a window of
+///   time, given as RFC 3339 timestamps like `2024-01-01T00:00:00Z`;
+/// - `trust-domains`: authenticates clients with identities issued in one of
+///   the `+`-separated trust domains instead of by the authorization's
+///   authentication;
+/// - `same-namespace`: when `true`, authenticates clients in the same trust
+///   domain and namespace as the proxy instead of by the authorization's
+///   authentication;
+/// - `cert-fingerprints`: authenticates clients whose certificates have one of
+///   the `+`-separated SHA-256 fingerprints, each as hexadecimal digits that
+///   may be separated by colons, instead of by the authorization's
+///   authentication.
+///
+/// For example, `webhooks/hmac-secret=github,webhooks/hmac-signed=path+header:x-event`.
+pub const ENV_INBOUND_AUTHZ_SETTINGS: &str = "LINKERD2_PROXY_INBOUND_AUTHZ_SETTINGS";
+
 pub const ENV_INBOUND_PORTS: &str = "LINKERD2_PROXY_INBOUND_PORTS";
 pub const ENV_POLICY_SVC_BASE: &str = "LINKERD2_PROXY_POLICY_SVC";
 pub const ENV_POLICY_WORKLOAD: &str = "LINKERD2_PROXY_POLICY_WORKLOAD";
 pub const ENV_POLICY_CLUSTER_NETWORKS: &str = "LINKERD2_PROXY_POLICY_CLUSTER_NETWORKS";
 
+/// Configures a quota service from which the tokens of inbound route rate
+/// limits are leased, so that each limit is shared by all of a server's
+/// replicas. When unset, each proxy enforces rate limits on its own.
+pub const ENV_RATE_LIMIT_QUOTA_SVC_BASE: &str = "LINKERD2_PROXY_RATE_LIMIT_QUOTA_SVC";
+
+/// How long the proxy waits for the quota service to lease tokens before it
+/// enforces rate limits on its own.
+pub const ENV_RATE_LIMIT_QUOTA_TIMEOUT: &str = "LINKERD2_PROXY_RATE_LIMIT_QUOTA_TIMEOUT";
+
 pub const ENV_INBOUND_IPS: &str = "LINKERD2_PROXY_INBOUND_IPS";
 
 pub const ENV_IDENTITY_DISABLED: &str = "LINKERD2_PROXY_IDENTITY_DISABLED";
@@ -200,11 +698,56 @@ pub const ENV_HOSTNAME: &str = "HOSTNAME";
 
 pub const ENV_TRACE_COLLECTOR_SVC_BASE: &str = "LINKERD2_PROXY_TRACE_COLLECTOR_SVC";
 
+/// Configures an OpenTelemetry collector to which metrics are pushed via
+/// OTLP/gRPC. Metrics are still served from the admin server.
+pub const ENV_METRICS_OTLP_COLLECTOR_SVC_BASE: &str = "LINKERD2_PROXY_METRICS_OTLP_COLLECTOR_SVC";
+
+/// How often metrics are pushed to the OTLP collector.
+pub const ENV_METRICS_OTLP_EXPORT_INTERVAL: &str = "LINKERD2_PROXY_METRICS_OTLP_EXPORT_INTERVAL";
+
+/// Either `cumulative` (the default) or `delta`. Determines whether counters
+/// and histograms are reported as totals or as the change since the previous
+/// export.
+pub const ENV_METRICS_OTLP_TEMPORALITY: &str = "LINKERD2_PROXY_METRICS_OTLP_TEMPORALITY";
+
+/// A comma-separated list of `key=value` pairs that are exported as resource
+/// attributes, e.g. `k8s.pod.name=web-0,k8s.namespace.name=emojivoto`.
+pub const ENV_METRICS_OTLP_RESOURCE_ATTRIBUTES: &str =
+    "LINKERD2_PROXY_METRICS_OTLP_RESOURCE_ATTRIBUTES";
+
+/// An `http://` URL to which metrics are pushed, e.g. a Prometheus
+/// remote-write receiver or a Pushgateway grouping key
+/// (`http://pushgateway:9091/metrics/job/linkerd-proxy/instance/web-0`).
+pub const ENV_METRICS_PUSH_URL: &str = "LINKERD2_PROXY_METRICS_PUSH_URL";
+
+/// Either `remote-write` (the default) or `pushgateway`.
+pub const ENV_METRICS_PUSH_FORMAT: &str = "LINKERD2_PROXY_METRICS_PUSH_FORMAT";
+
+/// How often metrics are pushed. Metrics are also pushed once when the proxy
+/// shuts down.
+pub const ENV_METRICS_PUSH_INTERVAL: &str = "LINKERD2_PROXY_METRICS_PUSH_INTERVAL";
+
+/// A comma-separated list of `key=value` labels that are added to each
+/// remote-write series, e.g. `job=linkerd-proxy,pod=web-0`.
+pub const ENV_METRICS_PUSH_LABELS: &str = "LINKERD2_PROXY_METRICS_PUSH_LABELS";
+
 pub const ENV_DESTINATION_CONTEXT: &str = "LINKERD2_PROXY_DESTINATION_CONTEXT";
 pub const ENV_DESTINATION_PROFILE_INITIAL_TIMEOUT: &str =
     "LINKERD2_PROXY_DESTINATION_PROFILE_INITIAL_TIMEOUT";
 
 pub const ENV_TAP_SVC_NAME: &str = "LINKERD2_PROXY_TAP_SVC_NAME";
+
+/// Configures the maximum number of bytes of each request and response body
+/// that is included in tap events. Body capture is disabled when this is 0
+/// (the default).
+///
+/// Bodies are only captured for taps that are authorized to extract headers.
+pub const ENV_TAP_BODY_CAPTURE_MAX_BYTES: &str = "LINKERD2_PROXY_TAP_BODY_CAPTURE_MAX_BYTES";
+
+/// A comma-separated list of content types for which bodies may be captured by
+/// tap. An entry that ends with `/` (e.g. `text/`) allows all subtypes.
+pub const ENV_TAP_BODY_CAPTURE_CONTENT_TYPES: &str =
+    "LINKERD2_PROXY_TAP_BODY_CAPTURE_CONTENT_TYPES";
 const ENV_RESOLV_CONF: &str = "LINKERD2_PROXY_RESOLV_CONF";
 
 /// Configures a minimum value for the TTL of DNS lookups.
@@ -215,6 +758,11 @@ const ENV_DNS_MIN_TTL: &str = "LINKERD2_PROXY_DNS_MIN_TTL";
 ///
 /// Lookups with TTLs above this value will use this value instead.
 const ENV_DNS_MAX_TTL: &str = "LINKERD2_PROXY_DNS_MAX_TTL";
+/// A comma-separated list of `SUFFIX=IP:PORT` pairs that resolve names with a
+/// suffix using a specific name server instead of the system configuration,
+/// e.g. `.consul=127.0.0.1:8600`. When several suffixes match a name, the most
+/// specific one is used.
+const ENV_DNS_SUFFIX_RESOLVERS: &str = "LINKERD2_PROXY_DNS_SUFFIX_RESOLVERS";
 
 /// Configure the stream or connection level flow control setting for HTTP2.
 ///
@@ -223,13 +771,42 @@ const ENV_INITIAL_STREAM_WINDOW_SIZE: &str = "LINKERD2_PROXY_HTTP2_INITIAL_STREA
 const ENV_INITIAL_CONNECTION_WINDOW_SIZE: &str =
     "LINKERD2_PROXY_HTTP2_INITIAL_CONNECTION_WINDOW_SIZE";
 
+/// Configures the maximum age of outbound HTTP/2 client connections.
+///
+/// Once a connection exceeds this age, a replacement connection is established
+/// and the prior connection is closed gracefully after its in-flight requests
+/// complete. By default, connections are not rotated.
+pub const ENV_OUTBOUND_HTTP2_MAX_CONNECTION_AGE: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP2_MAX_CONNECTION_AGE";
+
+/// Configures the maximum number of requests an outbound HTTP/2 client
+/// connection may dispatch before it is replaced by a new connection.
+///
+/// By default, connections are not rotated.
+pub const ENV_OUTBOUND_HTTP2_MAX_REQUESTS_PER_CONNECTION: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP2_MAX_REQUESTS_PER_CONNECTION";
+
 const ENV_INBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT: &str =
     "LINKERD2_PROXY_INBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT";
 const ENV_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT: &str =
     "LINKERD2_PROXY_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT";
 
+/// Configures the maximum number of requests an outbound HTTP/1 client
+/// connection may serve before it is removed from its connection pool.
+///
+/// By default, connections are reused without limit.
+pub const ENV_OUTBOUND_HTTP1_MAX_REQUESTS_PER_CONNECTION: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP1_MAX_REQUESTS_PER_CONNECTION";
+
 const ENV_SHUTDOWN_GRACE_PERIOD: &str = "LINKERD2_PROXY_SHUTDOWN_GRACE_PERIOD";
 
+/// Configures the maximum number of idle 8KiB buffers that are retained for
+/// copying data between opaque TCP connections. Setting this to 0 disables
+/// buffer reuse.
+///
+/// By default, up to 1024 buffers (8MiB) are retained.
+pub const ENV_TCP_COPY_BUFFERS_MAX_IDLE: &str = "LINKERD2_PROXY_TCP_COPY_BUFFERS_MAX_IDLE";
+
 // Default values for various configuration fields
 const DEFAULT_OUTBOUND_LISTEN_ADDR: &str = "127.0.0.1:4140";
 pub const DEFAULT_INBOUND_LISTEN_ADDR: &str = "0.0.0.0:4143";
@@ -237,6 +814,11 @@ pub const DEFAULT_CONTROL_LISTEN_ADDR: &str = "0.0.0.0:4190";
 
 const DEFAULT_ADMIN_LISTEN_ADDR: &str = "127.0.0.1:4191";
 const DEFAULT_METRICS_RETAIN_IDLE: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_METRICS_PEERS_MAX: usize = 100;
+const DEFAULT_METRICS_TCP_INFO_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_METRICS_OTLP_EXPORT_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_METRICS_PUSH_INTERVAL: Duration = Duration::from_secs(15);
+const DEFAULT_RATE_LIMIT_QUOTA_TIMEOUT: Duration = Duration::from_millis(500);
 
 const DEFAULT_INBOUND_HTTP_QUEUE_CAPACITY: usize = 10_000;
 const DEFAULT_INBOUND_HTTP_FAILFAST_TIMEOUT: Duration = Duration::from_secs(1);
@@ -254,16 +836,22 @@ const DEFAULT_OUTBOUND_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
 const DEFAULT_OUTBOUND_CONNECT_BACKOFF: ExponentialBackoff =
     ExponentialBackoff::new_unchecked(Duration::from_millis(100), Duration::from_millis(500), 0.1);
 
+const DEFAULT_HTTP_PRESERVE_TRAILERS: bool = true;
+
 const DEFAULT_CONTROL_QUEUE_CAPACITY: usize = 100;
 const DEFAULT_CONTROL_FAILFAST_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_CONTROL_HTTP2_KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(40);
 
 const DEFAULT_RESOLV_CONF: &str = "/etc/resolv.conf";
 
+const DEFAULT_TAP_BODY_CAPTURE_CONTENT_TYPES: &str = "application/json,text/";
+
 const DEFAULT_INITIAL_STREAM_WINDOW_SIZE: u32 = 65_535; // Protocol default
 const DEFAULT_INITIAL_CONNECTION_WINDOW_SIZE: u32 = 1048576; // 1MB ~ 16 streams at capacity
 
 // 2 minutes seems like a reasonable amount of time to wait for connections to close...
 const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2 * 60);
+const DEFAULT_TCP_COPY_BUFFERS_MAX_IDLE: usize = 1024;
 
 // This configuration limits the amount of time Linkerd retains cached clients &
 // connections for a given destination ip:port, as referenced by the application
@@ -275,7 +863,19 @@ const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2 * 60);
 // stale clients/connections can have a severe memory impact, especially when
 // the application communicates with many destinations.
 const ENV_OUTBOUND_DISCOVERY_IDLE_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_DISCOVERY_IDLE_TIMEOUT";
+// The largest response body that a route's cache stores, unless configured
+// otherwise.
+const DEFAULT_OUTBOUND_ROUTE_CACHE_MAX_ENTRY_BYTES: usize = 1024 * 1024;
+
+const DEFAULT_OUTBOUND_SUCCESS_OBJECTIVE_WINDOWS: [Duration; 2] =
+    [Duration::from_secs(5 * 60), Duration::from_secs(60 * 60)];
+
+// Mirrored requests are balanced like those of ServiceProfile backends.
+const DEFAULT_OUTBOUND_MIRROR_EWMA_DECAY: Duration = Duration::from_secs(10);
+const DEFAULT_OUTBOUND_MIRROR_EWMA_DEFAULT_RTT: Duration = Duration::from_millis(30);
+
 const DEFAULT_OUTBOUND_DISCOVERY_IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_OUTBOUND_FAILOVER_CLUSTER_LABEL: &str = "cluster";
 
 // On the inbound side, we may lookup per-port policy or per-service profile
 // configuration. We are more permissive in retaining inbound configuration,
@@ -307,6 +907,12 @@ const DEFAULT_OUTBOUND_MAX_IDLE_CONNS_PER_ENDPOINT: usize = usize::MAX;
 const DEFAULT_INBOUND_MAX_IN_FLIGHT: usize = 100_000;
 const DEFAULT_OUTBOUND_MAX_IN_FLIGHT: usize = 100_000;
 
+// Compressing small responses costs more than it saves.
+const DEFAULT_INBOUND_COMPRESSION_MIN_BYTES: usize = 1024;
+
+// Matches the request line limits commonly enforced by HTTP servers.
+const DEFAULT_INBOUND_MAX_REQUEST_TARGET_LENGTH: usize = 8 * 1024;
+
 const DEFAULT_DESTINATION_PROFILE_SUFFIXES: &str = "svc.cluster.local.";
 const DEFAULT_DESTINATION_PROFILE_SKIP_TIMEOUT: Duration = Duration::from_millis(500);
 
@@ -315,6 +921,8 @@ const DEFAULT_IDENTITY_MAX_REFRESH: Duration = Duration::from_secs(60 * 60 * 24)
 
 const INBOUND_CONNECT_BASE: &str = "INBOUND_CONNECT";
 const OUTBOUND_CONNECT_BASE: &str = "OUTBOUND_CONNECT";
+const INBOUND_ACCEPT_BASE: &str = "INBOUND_ACCEPT";
+const OUTBOUND_ACCEPT_BASE: &str = "OUTBOUND_ACCEPT";
 
 /// Load a `App` by reading ENV variables.
 pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError> {
@@ -322,6 +930,11 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     // defer returning any errors until all of them have been parsed.
     let outbound_listener_addr = parse(strings, ENV_OUTBOUND_LISTEN_ADDR, parse_socket_addr);
     let inbound_listener_addr = parse(strings, ENV_INBOUND_LISTEN_ADDR, parse_socket_addr);
+    let inbound_additional_listener_addrs = parse(
+        strings,
+        ENV_INBOUND_ADDITIONAL_LISTEN_ADDRS,
+        parse_socket_addrs,
+    );
     let admin_listener_addr = parse(strings, ENV_ADMIN_LISTEN_ADDR, parse_socket_addr);
 
     let inbound_detect_timeout = parse(strings, ENV_INBOUND_DETECT_TIMEOUT, parse_duration);
@@ -339,14 +952,50 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     let outbound_http_failfast_timeout =
         parse(strings, ENV_OUTBOUND_HTTP_FAILFAST_TIMEOUT, parse_duration);
     let outbound_connect_timeout = parse(strings, ENV_OUTBOUND_CONNECT_TIMEOUT, parse_duration);
+    let outbound_http_retry_max_buffered_bytes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES,
+        parse_number,
+    );
+    let outbound_http_retry_idempotency_header = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RETRY_IDEMPOTENCY_HEADER,
+        parse_header_name,
+    );
+    let outbound_http_expect_continue = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_EXPECT_CONTINUE,
+        parse_expect_continue,
+    );
 
     let inbound_accept_keepalive = parse(strings, ENV_INBOUND_ACCEPT_KEEPALIVE, parse_duration);
     let outbound_accept_keepalive = parse(strings, ENV_OUTBOUND_ACCEPT_KEEPALIVE, parse_duration);
 
+    let inbound_max_connection_age = parse(strings, ENV_INBOUND_MAX_CONNECTION_AGE, parse_duration);
+    let inbound_revocation_grace_period = parse(
+        strings,
+        ENV_INBOUND_AUTHORIZATION_REVOCATION_GRACE_PERIOD,
+        parse_duration,
+    );
+    let outbound_max_connection_age =
+        parse(strings, ENV_OUTBOUND_MAX_CONNECTION_AGE, parse_duration);
+    let http_preserve_trailers = parse(strings, ENV_HTTP_PRESERVE_TRAILERS, parse_bool);
+
     let inbound_connect_keepalive = parse(strings, ENV_INBOUND_CONNECT_KEEPALIVE, parse_duration);
     let outbound_connect_keepalive = parse(strings, ENV_OUTBOUND_CONNECT_KEEPALIVE, parse_duration);
 
+    let inbound_accept_socket_options = parse_socket_options(strings, INBOUND_ACCEPT_BASE);
+    let outbound_accept_socket_options = parse_socket_options(strings, OUTBOUND_ACCEPT_BASE);
+    let inbound_connect_socket_options = parse_socket_options(strings, INBOUND_CONNECT_BASE);
+    let outbound_connect_socket_options = parse_socket_options(strings, OUTBOUND_CONNECT_BASE);
+
+    let inbound_accept_shards = parse(strings, ENV_INBOUND_ACCEPT_SHARDS, parse_number);
+    let outbound_accept_shards = parse(strings, ENV_OUTBOUND_ACCEPT_SHARDS, parse_number);
+    let listen_reuse_port = parse(strings, ENV_LISTEN_REUSE_PORT, parse_bool)
+        .map(|reuse| ReusePort(reuse.unwrap_or_default()));
+
     let shutdown_grace_period = parse(strings, ENV_SHUTDOWN_GRACE_PERIOD, parse_duration);
+    let tcp_copy_buffers_max_idle = parse(strings, ENV_TCP_COPY_BUFFERS_MAX_IDLE, parse_number);
 
     let inbound_discovery_idle_timeout =
         parse(strings, ENV_INBOUND_DISCOVERY_IDLE_TIMEOUT, parse_duration);
@@ -365,11 +1014,55 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     );
 
     let inbound_max_in_flight = parse(strings, ENV_INBOUND_MAX_IN_FLIGHT, parse_number);
+    let inbound_max_connections = parse(strings, ENV_INBOUND_MAX_CONNECTIONS, parse_number);
+    let inbound_max_connections_per_client = parse(
+        strings,
+        ENV_INBOUND_MAX_CONNECTIONS_PER_CLIENT,
+        parse_number,
+    );
+    let inbound_max_accept_rate = parse(strings, ENV_INBOUND_MAX_ACCEPT_RATE, parse_number);
+    let inbound_compression_content_types = parse(
+        strings,
+        ENV_INBOUND_COMPRESSION_CONTENT_TYPES,
+        parse_strings,
+    );
+    let inbound_compression_min_bytes =
+        parse(strings, ENV_INBOUND_COMPRESSION_MIN_BYTES, parse_number);
+    let inbound_grpc_web = parse(strings, ENV_INBOUND_GRPC_WEB, parse_bool);
+    let inbound_hmac_secrets = parse(strings, ENV_INBOUND_HMAC_SECRETS_DIR, read_hmac_secrets);
+    let inbound_grpc_stream_reauthorization =
+        parse(strings, ENV_INBOUND_GRPC_STREAM_REAUTHORIZATION, parse_bool);
+    let inbound_grpc_stream_reauthorization_interval = parse(
+        strings,
+        ENV_INBOUND_GRPC_STREAM_REAUTHORIZATION_INTERVAL,
+        parse_duration,
+    );
+    let inbound_authz_top_clients = parse(strings, ENV_INBOUND_AUTHZ_TOP_CLIENTS, parse_number);
+    let inbound_max_request_target_len =
+        parse(strings, ENV_INBOUND_MAX_REQUEST_TARGET_LENGTH, parse_number);
     let outbound_max_in_flight = parse(strings, ENV_OUTBOUND_MAX_IN_FLIGHT, parse_number);
+    let http_deadline_header = parse(strings, ENV_HTTP_DEADLINE_HEADER, parse_header_name)?;
 
     let metrics_retain_idle = parse(strings, ENV_METRICS_RETAIN_IDLE, parse_duration);
+    let metrics_histogram_growth_factor = parse(
+        strings,
+        ENV_METRICS_HISTOGRAM_GROWTH_FACTOR,
+        parse_growth_factor,
+    );
+    let metrics_latency_buckets = parse(strings, ENV_METRICS_LATENCY_BUCKETS, parse_buckets);
+    let metrics_peers = parse(strings, ENV_METRICS_PEERS, parse_peer_granularity);
+    let metrics_peers_max = parse(strings, ENV_METRICS_PEERS_MAX, parse_number);
+    let metrics_tcp_info_interval = parse(strings, ENV_METRICS_TCP_INFO_INTERVAL, parse_duration);
+    let admin_capture_dir = strings.get(ENV_ADMIN_CAPTURE_DIR);
+    let admin_mirror_socket = strings.get(ENV_ADMIN_MIRROR_SOCKET);
+    let admin_chaos_identities = parse(strings, ENV_ADMIN_CHAOS_IDENTITIES, parse_identities);
 
     let control_receive_limits = mk_control_receive_limits(strings)?;
+    let control_keepalive_timeout =
+        parse(strings, ENV_CONTROL_HTTP2_KEEPALIVE_TIMEOUT, parse_duration)?
+            .unwrap_or(DEFAULT_CONTROL_HTTP2_KEEPALIVE_TIMEOUT);
+    let control_tokens =
+        parse(strings, ENV_CONTROL_TOKEN_SOURCES, parse_token_sources)?.unwrap_or_default();
 
     // DNS
 
@@ -377,8 +1070,9 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
     let dns_min_ttl = parse(strings, ENV_DNS_MIN_TTL, parse_duration);
     let dns_max_ttl = parse(strings, ENV_DNS_MAX_TTL, parse_duration);
+    let dns_suffix_routes = parse(strings, ENV_DNS_SUFFIX_RESOLVERS, parse_dns_suffix_routes);
 
-    let identity_config = parse_identity_config(strings);
+    let identity_config = parse_identity_config(strings, &control_tokens);
 
     let hostname = strings.get(ENV_HOSTNAME);
 
@@ -386,6 +1080,23 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
     let trace_collector_addr = parse_control_addr(strings, ENV_TRACE_COLLECTOR_SVC_BASE);
 
+    let quota_addr = parse_control_addr(strings, ENV_RATE_LIMIT_QUOTA_SVC_BASE);
+    let quota_timeout = parse(strings, ENV_RATE_LIMIT_QUOTA_TIMEOUT, parse_duration);
+
+    let otlp_metrics_addr = parse_control_addr(strings, ENV_METRICS_OTLP_COLLECTOR_SVC_BASE);
+    let otlp_metrics_interval = parse(strings, ENV_METRICS_OTLP_EXPORT_INTERVAL, parse_duration);
+    let otlp_metrics_temporality = parse(strings, ENV_METRICS_OTLP_TEMPORALITY, parse_temporality);
+    let otlp_metrics_attributes = parse(
+        strings,
+        ENV_METRICS_OTLP_RESOURCE_ATTRIBUTES,
+        parse_key_values,
+    );
+
+    let metrics_push_url = parse(strings, ENV_METRICS_PUSH_URL, parse_push_url);
+    let metrics_push_format = parse(strings, ENV_METRICS_PUSH_FORMAT, parse_push_format);
+    let metrics_push_interval = parse(strings, ENV_METRICS_PUSH_INTERVAL, parse_duration);
+    let metrics_push_labels = parse(strings, ENV_METRICS_PUSH_LABELS, parse_key_values);
+
     let gateway_suffixes = parse(strings, ENV_INBOUND_GATEWAY_SUFFIXES, parse_dns_suffixes);
 
     let dst_addr = parse_control_addr(strings, ENV_DESTINATION_SVC_BASE);
@@ -407,6 +1118,9 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         parse(strings, ENV_INITIAL_CONNECTION_WINDOW_SIZE, parse_number);
 
     let tap = parse_tap_config(strings);
+    let tap_body_capture_max_bytes = parse(strings, ENV_TAP_BODY_CAPTURE_MAX_BYTES, parse_number);
+    let tap_body_capture_content_types =
+        parse(strings, ENV_TAP_BODY_CAPTURE_CONTENT_TYPES, parse_strings);
 
     let h2_settings = h2::Settings {
         initial_stream_window_size: Some(
@@ -432,11 +1146,84 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         } else {
             debug!(allowed = ?ips, "Only allowing connections targeting `{}`", ENV_INBOUND_IPS);
         }
-        std::sync::Arc::new(ips)
+        Arc::new(ips)
     };
 
     let outbound = {
         let ingress_mode = parse(strings, ENV_INGRESS_MODE, parse_bool)?.unwrap_or(false);
+        let ingress_authority_overrides = {
+            let defaults = outbound::AuthorityOverrides::default();
+            outbound::AuthorityOverrides {
+                rewrites: parse(
+                    strings,
+                    ENV_INGRESS_AUTHORITY_REWRITES,
+                    parse_authority_rewrites,
+                )?
+                .map(Into::into)
+                .unwrap_or(defaults.rewrites),
+                default_port: parse(strings, ENV_INGRESS_AUTHORITY_DEFAULT_PORT, parse_number)?
+                    .unwrap_or(defaults.default_port),
+                allowed_clients: parse(
+                    strings,
+                    ENV_INGRESS_OVERRIDE_ALLOWED_CLIENTS,
+                    parse_networks,
+                )?
+                .map(|nets| nets.into_iter().collect()),
+            }
+        };
+        let ingress_fallback =
+            parse(strings, ENV_INGRESS_FALLBACK, parse_ingress_fallback)?.unwrap_or_default();
+        let ingress_dns_fallback_ttl = {
+            let defaults = outbound::DnsFallbackTtl::default();
+            outbound::DnsFallbackTtl {
+                min: parse(strings, ENV_INGRESS_DNS_FALLBACK_MIN_TTL, parse_duration)?
+                    .unwrap_or(defaults.min),
+                max: parse(strings, ENV_INGRESS_DNS_FALLBACK_MAX_TTL, parse_duration)?
+                    .unwrap_or(defaults.max),
+            }
+        };
+
+        let endpoint_pinning =
+            match parse(strings, ENV_OUTBOUND_ENDPOINT_PIN_HEADER, parse_header_name)? {
+                None => None,
+                Some(header) => {
+                    let authorization =
+                        parse(strings, ENV_OUTBOUND_ENDPOINT_PIN_AUTHZ, parse_pin_authz)?
+                            .unwrap_or_else(default_pin_authz);
+                    if authorization.networks.is_empty() {
+                        warn!(
+                            "`{}` authorizes no networks; no clients may pin endpoints",
+                            ENV_OUTBOUND_ENDPOINT_PIN_AUTHZ
+                        );
+                    }
+                    Some(outbound::EndpointPinning {
+                        header,
+                        authorization,
+                    })
+                }
+            };
+
+        let failover_cluster_label = strings.get(ENV_OUTBOUND_FAILOVER_CLUSTER_LABEL)?;
+        let endpoint_failover = parse(
+            strings,
+            ENV_OUTBOUND_FAILOVER_CLUSTERS,
+            parse_failover_clusters,
+        )?
+        .map(|clusters| outbound::EndpointFailover {
+            cluster_label: failover_cluster_label
+                .unwrap_or_else(|| DEFAULT_OUTBOUND_FAILOVER_CLUSTER_LABEL.to_string()),
+            clusters: clusters.into(),
+        });
+
+        let egress_policy = outbound::EgressPolicy::new(
+            parse(
+                strings,
+                ENV_OUTBOUND_EGRESS_DENY_AUTHORITIES,
+                parse_host_matches,
+            )?
+            .unwrap_or_default(),
+            parse(strings, ENV_OUTBOUND_EGRESS_DENY_ROUTES, parse_strings)?.unwrap_or_default(),
+        );
 
         // Instances can opt out of receiving informational headers by setting this configuration.
         // These headers are also omitted by default if ingress-mode is enabled.
@@ -455,7 +1242,16 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         let server = ServerConfig {
             addr,
             keepalive,
+            socket_options: outbound_accept_socket_options?,
+            accept_shards: outbound_accept_shards?
+                .map(AcceptShards)
+                .unwrap_or_default(),
+            reuse_port: listen_reuse_port.clone()?,
             h2_settings,
+            max_connection_age: outbound_max_connection_age?,
+            preserve_trailers: http_preserve_trailers
+                .clone()?
+                .unwrap_or(DEFAULT_HTTP_PRESERVE_TRAILERS),
         };
         let discovery_idle_timeout =
             outbound_discovery_idle_timeout?.unwrap_or(DEFAULT_OUTBOUND_DISCOVERY_IDLE_TIMEOUT);
@@ -467,20 +1263,44 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             ENV_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT,
             parse_duration,
         )?;
+        let h1_max_requests_per_connection = parse(
+            strings,
+            ENV_OUTBOUND_HTTP1_MAX_REQUESTS_PER_CONNECTION,
+            parse_number,
+        )?;
+        let h1_warm_connections =
+            parse(strings, ENV_OUTBOUND_WARM_CONNS_PER_ENDPOINT, parse_number)?;
+        let h2_max_connection_age = parse(
+            strings,
+            ENV_OUTBOUND_HTTP2_MAX_CONNECTION_AGE,
+            parse_duration,
+        )?;
+        let h2_max_requests_per_connection = parse(
+            strings,
+            ENV_OUTBOUND_HTTP2_MAX_REQUESTS_PER_CONNECTION,
+            parse_number,
+        )?;
 
         let connect = ConnectConfig {
             keepalive,
+            socket_options: outbound_connect_socket_options?,
             timeout: outbound_connect_timeout?.unwrap_or(DEFAULT_OUTBOUND_CONNECT_TIMEOUT),
             backoff: parse_backoff(
                 strings,
                 OUTBOUND_CONNECT_BASE,
                 DEFAULT_OUTBOUND_CONNECT_BACKOFF,
             )?,
-            h2_settings,
+            h2_settings: h2::Settings {
+                max_connection_age: h2_max_connection_age,
+                max_requests_per_connection: h2_max_requests_per_connection,
+                ..h2_settings
+            },
             h1_settings: h1::PoolSettings {
                 max_idle,
                 idle_timeout: connection_pool_timeout
                     .unwrap_or(DEFAULT_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT),
+                max_requests_per_connection: h1_max_requests_per_connection,
+                warm_connections: h1_warm_connections.unwrap_or(0),
             },
         };
 
@@ -496,8 +1316,38 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         let http_failfast_timeout =
             outbound_http_failfast_timeout?.unwrap_or(DEFAULT_OUTBOUND_HTTP_FAILFAST_TIMEOUT);
 
+        // Mirrored requests are buffered like those of the routes' other
+        // backends.
+        let outbound_route_settings = parse(strings, ENV_OUTBOUND_ROUTE_SETTINGS, |s| {
+            parse_outbound_route_settings(
+                s,
+                outbound::policy::Queue {
+                    capacity: http_queue_capacity,
+                    failfast_timeout: http_failfast_timeout,
+                },
+            )
+        });
+
         outbound::Config {
             ingress_mode,
+            ingress_authority_overrides,
+            ingress_fallback,
+            ingress_dns_fallback_ttl,
+            endpoint_pinning,
+            endpoint_failover,
+            egress_policy,
+            policy_overrides: Arc::new(outbound::policy::overrides::Overrides {
+                routes: outbound_route_settings?.unwrap_or_default(),
+            }),
+            http_retry: {
+                let defaults = outbound::http::RetryConfig::default();
+                outbound::http::RetryConfig {
+                    max_buffered_bytes: outbound_http_retry_max_buffered_bytes?
+                        .unwrap_or(defaults.max_buffered_bytes),
+                    idempotency_header: outbound_http_retry_idempotency_header?,
+                }
+            },
+            http_expect_continue: outbound_http_expect_continue?.unwrap_or_default(),
             emit_headers: !disable_headers,
             allow_discovery: AddrMatch::new(dst_profile_suffixes.clone(), dst_profile_networks),
             proxy: ProxyConfig {
@@ -506,6 +1356,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 max_in_flight_requests: outbound_max_in_flight?
                     .unwrap_or(DEFAULT_OUTBOUND_MAX_IN_FLIGHT),
                 detect_protocol_timeout,
+                deadline_header: http_deadline_header.clone(),
             },
             inbound_ips: inbound_ips.clone(),
             discovery_idle_timeout,
@@ -532,11 +1383,21 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             inbound_listener_addr?
                 .unwrap_or_else(|| parse_socket_addr(DEFAULT_INBOUND_LISTEN_ADDR).unwrap()),
         );
+        let additional_listen_addrs = inbound_additional_listener_addrs?
+            .unwrap_or_default()
+            .into_iter()
+            .map(ListenAddr)
+            .collect::<Vec<_>>();
         let keepalive = Keepalive(inbound_accept_keepalive?);
         let server = ServerConfig {
             addr,
             keepalive,
+            socket_options: inbound_accept_socket_options?,
+            accept_shards: inbound_accept_shards?.map(AcceptShards).unwrap_or_default(),
+            reuse_port: listen_reuse_port?,
             h2_settings,
+            max_connection_age: inbound_max_connection_age?,
+            preserve_trailers: http_preserve_trailers?.unwrap_or(DEFAULT_HTTP_PRESERVE_TRAILERS),
         };
         let discovery_idle_timeout =
             inbound_discovery_idle_timeout?.unwrap_or(DEFAULT_INBOUND_DISCOVERY_IDLE_TIMEOUT);
@@ -551,6 +1412,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         let keepalive = Keepalive(inbound_connect_keepalive?);
         let connect = ConnectConfig {
             keepalive,
+            socket_options: inbound_connect_socket_options?,
             timeout: inbound_connect_timeout?.unwrap_or(DEFAULT_INBOUND_CONNECT_TIMEOUT),
             backoff: parse_backoff(
                 strings,
@@ -561,6 +1423,8 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             h1_settings: h1::PoolSettings {
                 max_idle,
                 idle_timeout: connection_pool_timeout,
+                max_requests_per_connection: None,
+                warm_connections: 0,
             },
         };
 
@@ -583,6 +1447,11 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
             // We always configure a default policy. This policy applies when no other policy is
             // configured, especially when the port is not documented in via `ENV_INBOUND_PORTS`.
+            let port_defaults = parse(strings, ENV_INBOUND_PORT_DEFAULT_POLICIES, |s| {
+                parse_port_default_policies(s, &cluster_nets, detect_protocol_timeout)
+            })?
+            .unwrap_or_default();
+
             let default = parse(strings, ENV_INBOUND_DEFAULT_POLICY, |s| {
                 parse_default_policy(s, cluster_nets, detect_protocol_timeout)
             })?
@@ -594,6 +1463,49 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 inbound::policy::defaults::all_unauthenticated(detect_protocol_timeout).into()
             });
 
+            // Settings that the control plane cannot (yet) express are applied
+            // to both the default and discovered policies of each port.
+            let mut overrides = inbound::policy::Overrides::default();
+            let bandwidth_limits = parse(
+                strings,
+                ENV_INBOUND_PORT_BANDWIDTH_LIMITS,
+                parse_port_bandwidth_limits,
+            )?
+            .unwrap_or_default();
+            for (port, limit) in bandwidth_limits {
+                overrides.ports.entry(port).or_default().bandwidth_limit = Some(limit);
+            }
+
+            // Ports with SNI routes pass TLS through to the application.
+            let sni_routes = parse(strings, ENV_INBOUND_PORT_SNI_ROUTES, parse_port_sni_routes)?
+                .unwrap_or_default();
+            for (port, routes) in sni_routes {
+                overrides.ports.entry(port).or_default().sni_routes = Some(routes.into());
+            }
+
+            let tcp_settings = parse(
+                strings,
+                ENV_INBOUND_PORT_TCP_SETTINGS,
+                parse_port_tcp_settings,
+            )?
+            .unwrap_or_default();
+            for (port, duplex) in tcp_settings {
+                overrides.ports.entry(port).or_default().duplex = Some(duplex);
+            }
+
+            overrides.routes = parse(
+                strings,
+                ENV_INBOUND_ROUTE_SETTINGS,
+                parse_inbound_route_settings,
+            )?
+            .unwrap_or_default();
+            overrides.authorizations = parse(
+                strings,
+                ENV_INBOUND_AUTHZ_SETTINGS,
+                parse_inbound_authz_settings,
+            )?
+            .unwrap_or_default();
+
             // Load the the set of all known inbound ports to be discovered
             // eagerly during initialization.
             let mut ports = match parse(strings, ENV_INBOUND_PORTS, parse_port_range_set)? {
@@ -614,6 +1526,9 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             // Ensure that the admin server port is included in policy discovery.
             ports.insert(admin_listener_addr.port());
 
+            // Each additional inbound listener is governed by its port's policy.
+            ports.extend(additional_listen_addrs.iter().map(|a| a.port()));
+
             // Determine any pre-configured opaque ports.
             let opaque_ports = parse(
                 strings,
@@ -627,9 +1542,11 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
             inbound::policy::Config::Discover {
                 default,
+                port_defaults,
                 ports,
                 cache_max_idle_age: discovery_idle_timeout,
-                opaque_ports,
+                opaque_ports: inbound::policy::OpaquePorts::new(opaque_ports),
+                overrides: Arc::new(overrides),
             }
         };
 
@@ -641,6 +1558,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 max_in_flight_requests: inbound_max_in_flight?
                     .unwrap_or(DEFAULT_INBOUND_MAX_IN_FLIGHT),
                 detect_protocol_timeout,
+                deadline_header: http_deadline_header,
             },
             policy,
             profile_skip_timeout: dst_profile_skip_timeout?
@@ -654,16 +1572,56 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 failfast_timeout: inbound_http_failfast_timeout?
                     .unwrap_or(DEFAULT_INBOUND_HTTP_FAILFAST_TIMEOUT),
             },
+            connection_limits: inbound::ConnectionLimits {
+                max_connections: inbound_max_connections?,
+                max_connections_per_client: inbound_max_connections_per_client?,
+                max_accept_rate: inbound_max_accept_rate?,
+            },
+            response_compression: {
+                let min_bytes =
+                    inbound_compression_min_bytes?.unwrap_or(DEFAULT_INBOUND_COMPRESSION_MIN_BYTES);
+                inbound_compression_content_types?
+                    .filter(|types| !types.is_empty())
+                    .map(|content_types| inbound::ResponseCompression {
+                        content_types,
+                        min_bytes,
+                    })
+            },
+            grpc_web: inbound_grpc_web?.unwrap_or(false),
+            additional_listen_addrs,
+            hmac_secrets: inbound_hmac_secrets?.unwrap_or_default(),
+            authz_top_clients: inbound_authz_top_clients?.filter(|&n| n > 0),
+            max_request_target_len: Some(
+                inbound_max_request_target_len?
+                    .unwrap_or(DEFAULT_INBOUND_MAX_REQUEST_TARGET_LENGTH),
+            )
+            .filter(|&n| n > 0),
+            opaque_ports_file: strings
+                .get(ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION_FILE)?
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from),
+            revocation_grace_period: inbound_revocation_grace_period?.unwrap_or_default(),
+            grpc_stream_reauthorization: {
+                let enabled = inbound_grpc_stream_reauthorization?.unwrap_or(false);
+                let interval = inbound_grpc_stream_reauthorization_interval?;
+                (enabled || interval.is_some())
+                    .then_some(inbound::policy::GrpcStreamReauthorization { interval })
+            },
         }
     };
 
     let dst = {
         let addr = dst_addr?.ok_or(EnvError::NoDestinationAddress)?;
-        let connect = if addr.addr.is_loopback() {
-            inbound.proxy.connect.clone()
-        } else {
-            outbound.proxy.connect.clone()
-        };
+        let trust = parse_control_trust(strings, ENV_DESTINATION_SVC_BASE)?;
+        let token = parse_control_token(strings, ENV_DESTINATION_SVC_BASE, &control_tokens)?;
+        let connect = with_control_keepalive(
+            if addr.addr.is_loopback() {
+                inbound.proxy.connect.clone()
+            } else {
+                outbound.proxy.connect.clone()
+            },
+            control_keepalive_timeout,
+        );
         let failfast_timeout = if addr.addr.is_loopback() {
             inbound.http_request_queue.failfast_timeout
         } else {
@@ -683,6 +1641,8 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
                     failfast_timeout,
                 },
+                trust,
+                token,
             },
             limits,
         }
@@ -691,6 +1651,8 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     let policy = {
         let addr =
             parse_control_addr(strings, ENV_POLICY_SVC_BASE)?.ok_or(EnvError::NoPolicyAddress)?;
+        let trust = parse_control_trust(strings, ENV_POLICY_SVC_BASE)?;
+        let token = parse_control_token(strings, ENV_POLICY_SVC_BASE, &control_tokens)?;
         // The workload, which is opaque from the proxy's point-of-view, is sent to the
         // policy controller to support policy discovery.
         let workload = strings.get(ENV_POLICY_WORKLOAD)?.ok_or_else(|| {
@@ -708,11 +1670,14 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             .unwrap_or(control_receive_limits);
 
         let control = {
-            let connect = if addr.addr.is_loopback() {
-                inbound.proxy.connect.clone()
-            } else {
-                outbound.proxy.connect.clone()
-            };
+            let connect = with_control_keepalive(
+                if addr.addr.is_loopback() {
+                    inbound.proxy.connect.clone()
+                } else {
+                    outbound.proxy.connect.clone()
+                },
+                control_keepalive_timeout,
+            );
             ControlConfig {
                 addr,
                 connect,
@@ -720,6 +1685,8 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
                     failfast_timeout: DEFAULT_CONTROL_FAILFAST_TIMEOUT,
                 },
+                trust,
+                token,
             }
         };
 
@@ -730,13 +1697,76 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         }
     };
 
+    let quota = match quota_addr? {
+        None => quota::Config::Disabled,
+        Some(addr) => {
+            let trust = parse_control_trust(strings, ENV_RATE_LIMIT_QUOTA_SVC_BASE)?;
+            let token =
+                parse_control_token(strings, ENV_RATE_LIMIT_QUOTA_SVC_BASE, &control_tokens)?;
+            let connect = with_control_keepalive(
+                if addr.addr.is_loopback() {
+                    inbound.proxy.connect.clone()
+                } else {
+                    outbound.proxy.connect.clone()
+                },
+                control_keepalive_timeout,
+            );
+            quota::Config::Enabled(Box::new(quota::EnabledConfig {
+                timeout: quota_timeout?.unwrap_or(DEFAULT_RATE_LIMIT_QUOTA_TIMEOUT),
+                control: ControlConfig {
+                    addr,
+                    connect,
+                    buffer: QueueConfig {
+                        capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
+                        failfast_timeout: DEFAULT_CONTROL_FAILFAST_TIMEOUT,
+                    },
+                    trust,
+                    token,
+                },
+            }))
+        }
+    };
+
     let admin = super::admin::Config {
         metrics_retain_idle: metrics_retain_idle?.unwrap_or(DEFAULT_METRICS_RETAIN_IDLE),
+        metrics_histogram_growth_factor: metrics_histogram_growth_factor?,
+        metrics_latency_buckets: metrics_latency_buckets?.unwrap_or_default(),
+        metrics_peers: {
+            let max_peers = metrics_peers_max?.unwrap_or(DEFAULT_METRICS_PEERS_MAX);
+            metrics_peers?.map(|granularity| transport::metrics::peer::Config {
+                granularity,
+                max_peers,
+            })
+        },
+        metrics_socket_health: Some(
+            metrics_tcp_info_interval?.unwrap_or(DEFAULT_METRICS_TCP_INFO_INTERVAL),
+        )
+        .filter(|interval| !interval.is_zero())
+        .map(|interval| transport::metrics::health::Config { interval }),
         server: ServerConfig {
             addr: ListenAddr(admin_listener_addr),
             keepalive: inbound.proxy.server.keepalive,
+            socket_options: inbound.proxy.server.socket_options,
+            accept_shards: AcceptShards::default(),
+            reuse_port: inbound.proxy.server.reuse_port,
             h2_settings,
+            max_connection_age: None,
+            preserve_trailers: false,
+        },
+        capture_dir: admin_capture_dir?
+            .map(Into::into)
+            .unwrap_or_else(std::env::temp_dir),
+        // Mirrored requests are observed via tap, so they are only served
+        // when tap clients are authorized.
+        mirror_socket: match (admin_mirror_socket?, tap.as_ref()) {
+            (Some(path), Ok(Some(_))) => Some(path.into()),
+            (Some(_), _) => {
+                warn!("{ENV_ADMIN_MIRROR_SOCKET} is ignored because tap is disabled");
+                None
+            }
+            (None, _) => None,
         },
+        chaos_identities: admin_chaos_identities?.unwrap_or_default(),
 
         // TODO(ver) Currently we always enable profiling when the pprof feature
         // is enabled. In the future, this should be driven by runtime
@@ -751,6 +1781,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         resolv_conf_path: resolv_conf_path?
             .unwrap_or_else(|| DEFAULT_RESOLV_CONF.into())
             .into(),
+        suffix_routes: dns_suffix_routes?.unwrap_or_default(),
     };
 
     let oc_collector = match trace_collector_addr? {
@@ -775,6 +1806,39 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
             oc_collector::Config::Enabled(Box::new(oc_collector::EnabledConfig {
                 attributes,
+                hostname: hostname.clone()?,
+                control: ControlConfig {
+                    addr,
+                    connect,
+                    buffer: QueueConfig {
+                        capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
+                        failfast_timeout,
+                    },
+                    trust: None,
+                    token: None,
+                },
+            }))
+        }
+    };
+
+    let otlp_metrics = match otlp_metrics_addr? {
+        None => otlp_metrics::Config::Disabled,
+        Some(addr) => {
+            let connect = if addr.addr.is_loopback() {
+                inbound.proxy.connect.clone()
+            } else {
+                outbound.proxy.connect.clone()
+            };
+            let failfast_timeout = if addr.addr.is_loopback() {
+                inbound.http_request_queue.failfast_timeout
+            } else {
+                outbound.http_request_queue.failfast_timeout
+            };
+
+            otlp_metrics::Config::Enabled(Box::new(otlp_metrics::EnabledConfig {
+                interval: otlp_metrics_interval?.unwrap_or(DEFAULT_METRICS_OTLP_EXPORT_INTERVAL),
+                temporality: otlp_metrics_temporality?.unwrap_or_default(),
+                attributes: otlp_metrics_attributes?.unwrap_or_default(),
                 hostname: hostname?,
                 control: ControlConfig {
                     addr,
@@ -783,24 +1847,55 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                         capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
                         failfast_timeout,
                     },
+                    trust: None,
+                    token: None,
                 },
             }))
         }
     };
 
+    let metrics_push = match metrics_push_url? {
+        None => metrics_push::Config::Disabled,
+        Some(uri) => {
+            let mut labels = metrics_push_labels?
+                .unwrap_or_default()
+                .into_iter()
+                .collect::<Vec<_>>();
+            labels.sort();
+            metrics_push::Config::Enabled(Box::new(metrics_push::EnabledConfig {
+                uri,
+                format: metrics_push_format?.unwrap_or_default(),
+                interval: metrics_push_interval?.unwrap_or(DEFAULT_METRICS_PUSH_INTERVAL),
+                labels,
+            }))
+        }
+    };
+
+    let body_capture = tap::BodyCapture::new(
+        tap_body_capture_max_bytes?.unwrap_or(0),
+        tap_body_capture_content_types?
+            .unwrap_or_else(|| parse_strings(DEFAULT_TAP_BODY_CAPTURE_CONTENT_TYPES).unwrap()),
+    );
     let tap = tap?
         .map(|(addr, ids)| super::tap::Config::Enabled {
             permitted_client_ids: ids,
-            config: ServerConfig {
+            body_capture,
+            config: Box::new(ServerConfig {
                 addr: ListenAddr(addr),
                 keepalive: inbound.proxy.server.keepalive,
+                socket_options: inbound.proxy.server.socket_options,
+                accept_shards: AcceptShards::default(),
+                reuse_port: inbound.proxy.server.reuse_port,
                 h2_settings,
-            },
+                max_connection_age: None,
+                preserve_trailers: false,
+            }),
         })
         .unwrap_or(super::tap::Config::Disabled);
 
     let identity = {
         let (addr, certify, params) = identity_config?;
+        let trust = parse_control_trust(strings, ENV_IDENTITY_SVC_BASE)?;
         // If the address doesn't have a server identity, then we're on localhost.
         let connect = if addr.addr.is_loopback() {
             inbound.proxy.connect.clone()
@@ -821,6 +1916,10 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
                     failfast_timeout,
                 },
+                trust,
+                // The identity controller authenticates the token sent with
+                // each certificate signing request.
+                token: None,
             },
             params,
         }
@@ -832,12 +1931,17 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         dst,
         tap,
         oc_collector,
+        otlp_metrics,
+        metrics_push,
         policy,
+        quota,
         identity,
         outbound,
         gateway,
         inbound,
         shutdown_grace_period: shutdown_grace_period?.unwrap_or(DEFAULT_SHUTDOWN_GRACE_PERIOD),
+        tcp_copy_buffers_max_idle: tcp_copy_buffers_max_idle?
+            .unwrap_or(DEFAULT_TCP_COPY_BUFFERS_MAX_IDLE),
     })
 }
 
@@ -957,42 +2061,231 @@ where
     s.parse().map_err(Into::into)
 }
 
-fn parse_duration_opt(s: &str) -> Result<Option<Duration>, ParseError> {
-    if s.is_empty() {
-        return Ok(None);
-    }
-    parse_duration(s).map(Some)
+fn parse_growth_factor(s: &str) -> Result<metrics::GrowthFactor, ParseError> {
+    s.parse().map_err(|_| ParseError::NotAGrowthFactor)
 }
 
-fn parse_duration(s: &str) -> Result<Duration, ParseError> {
-    use regex::Regex;
-
-    let re = Regex::new(r"^\s*(\d+)(ms|s|m|h|d)?\s*$").expect("duration regex");
-
-    let cap = re.captures(s).ok_or(ParseError::NotADuration)?;
-
-    let magnitude = parse_number(&cap[1])?;
-    match cap.get(2).map(|m| m.as_str()) {
-        None if magnitude == 0 => Ok(Duration::from_secs(0)),
-        Some("ms") => Ok(Duration::from_millis(magnitude)),
-        Some("s") => Ok(Duration::from_secs(magnitude)),
-        Some("m") => Ok(Duration::from_secs(magnitude * 60)),
-        Some("h") => Ok(Duration::from_secs(magnitude * 60 * 60)),
-        Some("d") => Ok(Duration::from_secs(magnitude * 60 * 60 * 24)),
-        _ => Err(ParseError::NotADuration),
+fn parse_buckets(s: &str) -> Result<HashMap<String, &'static metrics::Bounds>, ParseError> {
+    let mut buckets = HashMap::new();
+    for family in s.split(';').map(str::trim).filter(|f| !f.is_empty()) {
+        let (name, bounds) = family
+            .split_once('=')
+            .ok_or(ParseError::NotHistogramBuckets)?;
+        let bounds = bounds
+            .split(',')
+            .map(|b| b.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let bounds = metrics::Bounds::leak(&bounds).map_err(|_| ParseError::NotHistogramBuckets)?;
+        buckets.insert(name.trim().to_string(), bounds);
     }
+    Ok(buckets)
 }
 
-fn parse_socket_addr(s: &str) -> Result<SocketAddr, ParseError> {
-    match parse_addr(s)? {
-        Addr::Socket(a) => Ok(a),
-        _ => {
-            error!("Expected IP:PORT; found: {}", s);
-            Err(ParseError::HostIsNotAnIpAddress)
-        }
+fn parse_temporality(s: &str) -> Result<otlp_metrics::Temporality, ParseError> {
+    match s.trim() {
+        "cumulative" => Ok(otlp_metrics::Temporality::Cumulative),
+        "delta" => Ok(otlp_metrics::Temporality::Delta),
+        _ => Err(ParseError::NotATemporality),
     }
 }
 
+fn parse_key_values(s: &str) -> Result<HashMap<String, String>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|kv| !kv.is_empty())
+        .map(|kv| match kv.split_once('=') {
+            Some((k, v)) if !k.trim().is_empty() => {
+                Ok((k.trim().to_string(), v.trim().to_string()))
+            }
+            _ => Err(ParseError::NotAnAttribute),
+        })
+        .collect()
+}
+
+fn parse_authority_rewrites(s: &str) -> Result<Vec<outbound::AuthorityRewrite>, ParseError> {
+    parse_key_values(s)
+        .map_err(|_| ParseError::NotAnAuthorityRewrite)?
+        .into_iter()
+        .map(|(host, rewrite)| {
+            let (to_host, to_port) = match rewrite.rsplit_once(':') {
+                Some((h, p)) => (h, Some(p.parse::<u16>()?)),
+                None => (rewrite.as_str(), None),
+            };
+            if to_host.is_empty() && to_port.is_none() {
+                return Err(ParseError::NotAnAuthorityRewrite);
+            }
+            Ok(outbound::AuthorityRewrite {
+                host: host.trim_end_matches('.').to_ascii_lowercase(),
+                to_host: Some(to_host.to_string()).filter(|h| !h.is_empty()),
+                to_port,
+            })
+        })
+        .collect()
+}
+
+fn parse_failover_clusters(s: &str) -> Result<Vec<outbound::FailoverCluster>, ParseError> {
+    parse_key_values(s)
+        .map_err(|_| ParseError::NotAFailoverCluster)?
+        .into_iter()
+        .map(|(name, min)| {
+            let min_local_endpoints = min
+                .parse::<usize>()
+                .map_err(|_| ParseError::NotAFailoverCluster)?;
+            Ok(outbound::FailoverCluster {
+                name,
+                min_local_endpoints,
+            })
+        })
+        .collect()
+}
+
+fn parse_host_matches(
+    s: &str,
+) -> Result<Vec<outbound::policy::http::r#match::MatchHost>, ParseError> {
+    use outbound::policy::http::r#match::{MatchHost, MatchPort};
+
+    parse_strings(s)?
+        .into_iter()
+        .map(|authority| {
+            let invalid =
+                |e: &dyn std::fmt::Display| ParseError::NotAHostMatch(format!("{authority}: {e}"));
+            // An authority may specify a port (or port range) after its host.
+            let (host, port) = match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port.parse::<MatchPort>())),
+                None => (authority.as_str(), None),
+            };
+            let host = host.parse::<MatchHost>().map_err(|e| invalid(&e))?;
+            match port {
+                Some(port) => Ok(host.with_port(port.map_err(|e| invalid(&e))?)),
+                None => Ok(host),
+            }
+        })
+        .collect()
+}
+
+fn parse_token_sources(s: &str) -> Result<HashMap<String, identity::TokenSource>, ParseError> {
+    parse_key_values(s)?
+        .into_iter()
+        .map(|(audience, path)| {
+            let token = identity::TokenSource::if_nonempty_file(path).map_err(|error| {
+                error!(%audience, %error, "Could not read token");
+                ParseError::InvalidTokenSource
+            })?;
+            Ok((audience.clone(), token.with_audience(audience)))
+        })
+        .collect()
+}
+
+fn parse_spki_pins(s: &str) -> Result<Vec<linkerd_app_core::identity::SpkiPin>, ParseError> {
+    parse_strings(s)?
+        .into_iter()
+        .map(|pin| pin.parse().map_err(|_| ParseError::NotAPublicKeyPin))
+        .collect()
+}
+
+fn parse_push_url(s: &str) -> Result<http::uri::Uri, ParseError> {
+    let uri = s
+        .trim()
+        .parse::<http::uri::Uri>()
+        .map_err(|_| ParseError::NotAPushUrl)?;
+    if uri.scheme() != Some(&http::uri::Scheme::HTTP) || uri.authority().is_none() {
+        return Err(ParseError::NotAPushUrl);
+    }
+    Ok(uri)
+}
+
+fn parse_push_format(s: &str) -> Result<metrics_push::Format, ParseError> {
+    match s.trim() {
+        "remote-write" => Ok(metrics_push::Format::RemoteWrite),
+        "pushgateway" => Ok(metrics_push::Format::Pushgateway),
+        _ => Err(ParseError::NotAPushFormat),
+    }
+}
+
+fn parse_peer_granularity(s: &str) -> Result<transport::metrics::peer::Granularity, ParseError> {
+    match s.trim() {
+        "ip" => Ok(transport::metrics::peer::Granularity::Ip),
+        "endpoint" => Ok(transport::metrics::peer::Granularity::Endpoint),
+        _ => Err(ParseError::NotAPeerGranularity),
+    }
+}
+
+fn parse_ingress_fallback(s: &str) -> Result<outbound::IngressFallback, ParseError> {
+    match s.trim() {
+        "fail" => Ok(outbound::IngressFallback::Fail),
+        "orig-dst" => Ok(outbound::IngressFallback::OrigDst),
+        "dns" => Ok(outbound::IngressFallback::Dns),
+        _ => Err(ParseError::NotAnIngressFallback),
+    }
+}
+
+fn parse_expect_continue(s: &str) -> Result<outbound::http::ExpectContinue, ParseError> {
+    match s.trim() {
+        "pass-through" => Ok(outbound::http::ExpectContinue::PassThrough),
+        "answer" => Ok(outbound::http::ExpectContinue::Answer),
+        "strip" => Ok(outbound::http::ExpectContinue::Strip),
+        _ => Err(ParseError::NotAnExpectContinueMode),
+    }
+}
+
+fn parse_header_name(s: &str) -> Result<http::HeaderName, ParseError> {
+    s.trim()
+        .parse::<http::HeaderName>()
+        .map_err(|_| ParseError::NotAHeaderName)
+}
+
+fn parse_duration_opt(s: &str) -> Result<Option<Duration>, ParseError> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    parse_duration(s).map(Some)
+}
+
+fn parse_duration(s: &str) -> Result<Duration, ParseError> {
+    use regex::Regex;
+
+    let re = Regex::new(r"^\s*(\d+)(ms|s|m|h|d)?\s*$").expect("duration regex");
+
+    let cap = re.captures(s).ok_or(ParseError::NotADuration)?;
+
+    let magnitude = parse_number(&cap[1])?;
+    match cap.get(2).map(|m| m.as_str()) {
+        None if magnitude == 0 => Ok(Duration::from_secs(0)),
+        Some("ms") => Ok(Duration::from_millis(magnitude)),
+        Some("s") => Ok(Duration::from_secs(magnitude)),
+        Some("m") => Ok(Duration::from_secs(magnitude * 60)),
+        Some("h") => Ok(Duration::from_secs(magnitude * 60 * 60)),
+        Some("d") => Ok(Duration::from_secs(magnitude * 60 * 60 * 24)),
+        _ => Err(ParseError::NotADuration),
+    }
+}
+
+fn parse_socket_addr(s: &str) -> Result<SocketAddr, ParseError> {
+    match parse_addr(s)? {
+        Addr::Socket(a) => Ok(a),
+        _ => {
+            error!("Expected IP:PORT; found: {}", s);
+            Err(ParseError::HostIsNotAnIpAddress)
+        }
+    }
+}
+
+fn parse_socket_addrs(s: &str) -> Result<Vec<SocketAddr>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(parse_socket_addr)
+        .collect()
+}
+
+fn parse_strings(s: &str) -> Result<Vec<String>, ParseError> {
+    Ok(s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect())
+}
+
 fn parse_ip_set(s: &str) -> Result<HashSet<IpAddr>, ParseError> {
     s.split(',')
         .map(|s| s.parse::<IpAddr>().map_err(Into::into))
@@ -1101,6 +2394,41 @@ where
     }
 }
 
+fn read_hmac_secrets(dir: &str) -> Result<inbound::policy::HmacSecrets, ParseError> {
+    let entries = fs::read_dir(dir).map_err(|error| {
+        error!(%dir, %error, "Could not read HMAC secrets");
+        ParseError::InvalidHmacSecrets
+    })?;
+
+    let mut secrets = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|_| ParseError::InvalidHmacSecrets)?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        // Skip the hidden files and directories that Kubernetes uses to
+        // update secret volumes atomically.
+        if name.starts_with('.') || !path.is_file() {
+            continue;
+        }
+        let secret = fs::read(&path).map_err(|error| {
+            error!(path = %path.display(), %error, "Could not read HMAC secret");
+            ParseError::InvalidHmacSecrets
+        })?;
+        secrets.push((name.to_string(), secret));
+    }
+    info!(secrets = secrets.len(), "Loaded HMAC secrets");
+    Ok(inbound::policy::HmacSecrets::new(secrets))
+}
+
+fn parse_identities(list: &str) -> Result<HashSet<identity::Id>, ParseError> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(parse_identity)
+        .collect()
+}
+
 fn parse_dns_suffixes(list: &str) -> Result<HashSet<dns::Suffix>, ParseError> {
     let mut suffixes = HashSet::new();
     for item in list.split(',') {
@@ -1122,6 +2450,26 @@ fn parse_dns_suffix(s: &str) -> Result<dns::Suffix, ParseError> {
     dns::Suffix::from_str(s).map_err(|_| ParseError::NotADomainSuffix)
 }
 
+fn parse_dns_suffix_routes(s: &str) -> Result<Vec<dns::SuffixRoute>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|route| !route.is_empty())
+        .map(|route| {
+            let invalid = || ParseError::NotADnsSuffixRoute(route.to_string());
+            let (suffix, server) = route.split_once('=').ok_or_else(invalid)?;
+            // Suffixes may be written with a leading dot, e.g. `.consul`.
+            let suffix = match suffix.trim() {
+                "." => ".",
+                suffix => suffix.strip_prefix('.').unwrap_or(suffix),
+            };
+            Ok(dns::SuffixRoute {
+                suffix: parse_dns_suffix(suffix).map_err(|_| invalid())?,
+                server: server.trim().parse().map_err(|_| invalid())?,
+            })
+        })
+        .collect()
+}
+
 fn parse_networks(list: &str) -> Result<HashSet<IpNet>, ParseError> {
     let mut nets = HashSet::new();
     for input in list.split(',') {
@@ -1137,6 +2485,563 @@ fn parse_networks(list: &str) -> Result<HashSet<IpNet>, ParseError> {
     Ok(nets)
 }
 
+fn parse_port_default_policies(
+    s: &str,
+    cluster_nets: &HashSet<IpNet>,
+    detect_timeout: Duration,
+) -> Result<HashMap<u16, inbound::policy::DefaultPolicy>, ParseError> {
+    parse_key_values(s)?
+        .into_iter()
+        .map(|(port, policy)| {
+            let port = port.parse::<u16>()?;
+            let policy = parse_default_policy(&policy, cluster_nets.clone(), detect_timeout)?;
+            Ok((port, policy))
+        })
+        .collect()
+}
+
+fn parse_port_sni_routes(
+    s: &str,
+) -> Result<HashMap<u16, Vec<inbound::policy::SniRoute>>, ParseError> {
+    let mut routes = HashMap::<_, Vec<_>>::new();
+    for (key, backend) in parse_key_values(s)? {
+        let invalid = || ParseError::NotAnSniRoute(format!("{key}={backend}"));
+        let (port, sni) = key.split_once('/').ok_or_else(invalid)?;
+        let route = inbound::policy::SniRoute {
+            snis: vec![sni.parse().map_err(|_| invalid())?],
+            backend: backend.parse().map_err(|_| invalid())?,
+        };
+        routes.entry(port.parse::<u16>()?).or_default().push(route);
+    }
+    Ok(routes)
+}
+
+fn parse_port_tcp_settings(
+    s: &str,
+) -> Result<HashMap<u16, inbound::policy::DuplexConfig>, ParseError> {
+    let mut settings = HashMap::<_, inbound::policy::DuplexConfig>::new();
+    for (key, value) in parse_key_values(s)? {
+        let invalid = || ParseError::NotATcpSetting(format!("{key}={value}"));
+        let (port, setting) = key.split_once('/').ok_or_else(invalid)?;
+        let duplex = settings.entry(port.parse::<u16>()?).or_default();
+        match setting {
+            "half-close" => duplex.half_close = parse_bool(&value)?,
+            "linger" => duplex.linger = Some(parse_duration(&value)?),
+            "idle-timeout" => duplex.idle_timeout = Some(parse_duration(&value)?),
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(settings)
+}
+
+fn parse_port_bandwidth_limits(s: &str) -> Result<HashMap<u16, NonZeroU64>, ParseError> {
+    parse_key_values(s)?
+        .into_iter()
+        .map(|(port, limit)| Ok((port.parse()?, limit.parse()?)))
+        .collect()
+}
+
+fn parse_inbound_route_settings(
+    s: &str,
+) -> Result<HashMap<String, inbound::policy::RouteOverrides>, ParseError> {
+    let mut settings = HashMap::<_, inbound::policy::RouteOverrides>::new();
+    for (key, value) in parse_key_values(s)? {
+        let invalid = || ParseError::NotARouteSetting(format!("{key}={value}"));
+        let (route, setting) = key.split_once('/').ok_or_else(invalid)?;
+        let route = settings.entry(route.to_string()).or_default();
+        match setting {
+            "max-in-flight" => route.max_in_flight = Some(parse_number(&value)?),
+            "rate-limit" => route.rate_limit = Some(parse_rate_limit(&value)?),
+            "transcode" => {
+                let descriptor_set = fs::read(&value).map_err(|error| {
+                    error!(path = %value, %error, "Could not read descriptor set");
+                    invalid()
+                })?;
+                let transcoder =
+                    inbound::policy::Transcoder::from_descriptor_set(descriptor_set.into())
+                        .map_err(|error| {
+                            error!(path = %value, %error, "Invalid descriptor set");
+                            invalid()
+                        })?;
+                route.transcode = Some(transcoder);
+            }
+            "inject-client-addr" => {
+                route
+                    .inject_metadata
+                    .get_or_insert_with(Default::default)
+                    .client_addr = Some(parse_header_name(&value)?)
+            }
+            "inject-client-identity" => {
+                route
+                    .inject_metadata
+                    .get_or_insert_with(Default::default)
+                    .client_identity = Some(parse_header_name(&value)?)
+            }
+            "inject-tls" => {
+                route
+                    .inject_metadata
+                    .get_or_insert_with(Default::default)
+                    .tls = Some(parse_header_name(&value)?)
+            }
+            "inject-route" => {
+                route
+                    .inject_metadata
+                    .get_or_insert_with(Default::default)
+                    .route = Some(parse_header_name(&value)?)
+            }
+            "path-template"
+            | "header-present"
+            | "header-absent"
+            | "header-exact-ignore-case"
+            | "content-type" => {
+                let matches = route.matches.get_or_insert_with(Default::default);
+                parse_route_match(matches, setting, &value).ok_or_else(invalid)?;
+            }
+            "priority" => {
+                route.priority = Some(match value.as_str() {
+                    "low" => inbound::policy::Priority::Low,
+                    "normal" => inbound::policy::Priority::Normal,
+                    "high" => inbound::policy::Priority::High,
+                    _ => return Err(invalid()),
+                })
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(settings)
+}
+
+fn parse_inbound_authz_settings(
+    s: &str,
+) -> Result<HashMap<String, inbound::policy::AuthzOverrides>, ParseError> {
+    use inbound::policy::{Hmac, SignedComponent};
+
+    let mut settings = HashMap::<_, inbound::policy::AuthzOverrides>::new();
+    let mut hmacs = HashMap::<_, (Option<String>, Hmac)>::new();
+    for (key, value) in parse_key_values(s)? {
+        let invalid = || ParseError::NotAnAuthzSetting(format!("{key}={value}"));
+        let (authz, setting) = key.split_once('/').ok_or_else(invalid)?;
+        match setting {
+            "local-addrs" => {
+                settings.entry(authz.to_string()).or_default().local_addrs = value
+                    .split('+')
+                    .map(|addr| parse_local_addr(addr).ok_or_else(invalid))
+                    .collect::<Result<_, _>>()?;
+                continue;
+            }
+            "not-before" | "not-after" => {
+                let time = humantime::parse_rfc3339(&value).map_err(|_| invalid())?;
+                let window = settings
+                    .entry(authz.to_string())
+                    .or_default()
+                    .validity
+                    .get_or_insert_with(Default::default);
+                if setting == "not-before" {
+                    window.not_before = Some(time);
+                } else {
+                    window.not_after = Some(time);
+                }
+                continue;
+            }
+            "trust-domains" => {
+                let domains = value
+                    .split('+')
+                    .map(|domain| {
+                        let domain = domain.trim();
+                        if domain.is_empty() || domain.contains(['*', '/']) {
+                            return Err(invalid());
+                        }
+                        Ok(domain.to_string())
+                    })
+                    .collect::<Result<_, _>>()?;
+                settings
+                    .entry(authz.to_string())
+                    .or_default()
+                    .identity_scopes
+                    .get_or_insert_with(Default::default)
+                    .trust_domains = domains;
+                continue;
+            }
+            "cert-fingerprints" => {
+                settings
+                    .entry(authz.to_string())
+                    .or_default()
+                    .cert_fingerprints = value
+                    .split('+')
+                    .map(|fp| fp.trim().parse().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?;
+                continue;
+            }
+            "same-namespace" => {
+                settings
+                    .entry(authz.to_string())
+                    .or_default()
+                    .identity_scopes
+                    .get_or_insert_with(Default::default)
+                    .same_namespace = parse_bool(&value)?;
+                continue;
+            }
+            _ => {}
+        }
+
+        let (secret, hmac) = hmacs.entry(authz.to_string()).or_insert_with(|| {
+            let hmac = Hmac {
+                secret: String::new(),
+                signature_header: http::HeaderName::from_static("x-signature"),
+                timestamp_header: http::HeaderName::from_static("x-signature-timestamp"),
+                max_skew: Duration::from_secs(5 * 60),
+                components: vec![
+                    SignedComponent::Method,
+                    SignedComponent::Authority,
+                    SignedComponent::Path,
+                ],
+            };
+            (None, hmac)
+        });
+        match setting {
+            "hmac-secret" => *secret = Some(value),
+            "hmac-signature-header" => hmac.signature_header = parse_header_name(&value)?,
+            "hmac-timestamp-header" => hmac.timestamp_header = parse_header_name(&value)?,
+            "hmac-max-skew" => hmac.max_skew = parse_duration(&value)?,
+            "hmac-signed" => {
+                hmac.components = value
+                    .split('+')
+                    .map(|c| match c {
+                        "method" => Ok(SignedComponent::Method),
+                        "authority" => Ok(SignedComponent::Authority),
+                        "path" => Ok(SignedComponent::Path),
+                        c => match c.strip_prefix("header:") {
+                            Some(name) => Ok(SignedComponent::Header(parse_header_name(name)?)),
+                            None => Err(invalid()),
+                        },
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    for (authz, (secret, hmac)) in hmacs {
+        // HMAC settings are only meaningful with a secret.
+        let secret = secret.ok_or_else(|| {
+            ParseError::NotAnAuthzSetting(format!("{authz}/hmac-secret is required"))
+        })?;
+        settings.entry(authz).or_default().hmac = Some(Hmac { secret, ..hmac });
+    }
+
+    // Each of these settings replaces the authorization's authentication, so
+    // at most one may be configured for an authorization.
+    if let Some(authz) = settings.iter().find_map(|(authz, s)| {
+        let authns = [
+            s.hmac.is_some(),
+            s.identity_scopes.is_some(),
+            !s.cert_fingerprints.is_empty(),
+        ];
+        (authns.into_iter().filter(|a| *a).count() > 1).then_some(authz)
+    }) {
+        return Err(ParseError::NotAnAuthzSetting(format!(
+            "{authz} may only configure one of HMAC settings, identity scopes, or certificate fingerprints"
+        )));
+    }
+    Ok(settings)
+}
+
+fn default_pin_authz() -> inbound::policy::Authorization {
+    inbound::policy::Authorization {
+        networks: vec![],
+        authentication: inbound::policy::Authentication::Unauthenticated,
+        meta: Arc::new(inbound::policy::Meta::Default {
+            name: "endpoint-pinning".into(),
+        }),
+        local_addrs: vec![],
+        validity: vec![],
+    }
+}
+
+fn parse_pin_authz(s: &str) -> Result<inbound::policy::Authorization, ParseError> {
+    use inbound::policy::{Meta, TimeWindow};
+
+    let mut authz = default_pin_authz();
+    let mut window = TimeWindow::default();
+    for (key, value) in parse_key_values(s)? {
+        let invalid = || ParseError::NotAnAuthzSetting(format!("{key}={value}"));
+        match key.as_str() {
+            "name" if !value.is_empty() => {
+                authz.meta = Arc::new(Meta::Default { name: value.into() })
+            }
+            "networks" => {
+                authz.networks = parse_networks(&value.replace('+', ","))?
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+            }
+            "not-before" => {
+                window.not_before = Some(humantime::parse_rfc3339(&value).map_err(|_| invalid())?)
+            }
+            "not-after" => {
+                window.not_after = Some(humantime::parse_rfc3339(&value).map_err(|_| invalid())?)
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    if window != TimeWindow::default() {
+        authz.validity.push(window);
+    }
+    Ok(authz)
+}
+
+fn parse_outbound_route_settings(
+    s: &str,
+    queue: outbound::policy::Queue,
+) -> Result<HashMap<String, outbound::policy::overrides::RouteOverrides>, ParseError> {
+    let mut settings = HashMap::<_, outbound::policy::overrides::RouteOverrides>::new();
+    // Some settings refine another setting, which must also be configured,
+    // e.g. `direct-response-body` requires `direct-response`.
+    let mut required = HashSet::new();
+    let mut configured = HashSet::new();
+    for (key, value) in parse_key_values(s)? {
+        let invalid = || ParseError::NotARouteSetting(format!("{key}={value}"));
+        let (name, setting) = key.split_once('/').ok_or_else(invalid)?;
+        let route = settings.entry(name.to_string()).or_default();
+        match setting {
+            "direct-response" | "direct-response-body" | "direct-response-headers" => {
+                let direct = route.direct_response.get_or_insert_with(|| {
+                    outbound::policy::http::filter::DirectResponse {
+                        status: http::StatusCode::OK,
+                        headers: vec![],
+                        body: Default::default(),
+                    }
+                });
+                match setting {
+                    "direct-response" => {
+                        direct.status = value.parse().map_err(|_| invalid())?;
+                    }
+                    "direct-response-body" => direct.body = value.clone().into(),
+                    _ => {
+                        direct.headers = value
+                            .split('+')
+                            .map(|header| {
+                                let (name, value) = header.split_once(':').ok_or_else(invalid)?;
+                                let value = value.trim().parse().map_err(|_| invalid())?;
+                                Ok((parse_header_name(name)?, value))
+                            })
+                            .collect::<Result<_, ParseError>>()?;
+                    }
+                }
+                required.insert(format!("{name}/direct-response"));
+            }
+            "cache" | "cache-max-entry-bytes" | "cache-vary" => {
+                let cache = route
+                    .cache
+                    .get_or_insert_with(|| outbound::policy::http::Cache {
+                        max_bytes: 0,
+                        max_entry_bytes: DEFAULT_OUTBOUND_ROUTE_CACHE_MAX_ENTRY_BYTES,
+                        vary: vec![],
+                    });
+                match setting {
+                    "cache" => cache.max_bytes = parse_number(&value)?,
+                    "cache-max-entry-bytes" => cache.max_entry_bytes = parse_number(&value)?,
+                    _ => {
+                        cache.vary = value
+                            .split('+')
+                            .map(parse_header_name)
+                            .collect::<Result<_, _>>()?;
+                    }
+                }
+                required.insert(format!("{name}/cache"));
+            }
+            "decompress" | "decompress-max-bytes" => {
+                let decompression = route.request_decompression.get_or_insert(
+                    outbound::policy::http::Decompression {
+                        max_bytes: None,
+                        max_expansion_ratio: 0,
+                    },
+                );
+                if setting == "decompress" {
+                    decompression.max_expansion_ratio = parse_number::<NonZeroU32>(&value)?.get();
+                } else {
+                    decompression.max_bytes = Some(parse_number(&value)?);
+                }
+                required.insert(format!("{name}/decompress"));
+            }
+            "sticky" => {
+                route.sticky = Some(match value.split_once(':') {
+                    Some(("header", name)) => {
+                        outbound::policy::http::StickyKey::Header(parse_header_name(name)?)
+                    }
+                    Some(("cookie", name)) if !name.is_empty() => {
+                        outbound::policy::http::StickyKey::Cookie(name.to_string())
+                    }
+                    None if value == "client-ip" => outbound::policy::http::StickyKey::ClientIp,
+                    _ => return Err(invalid()),
+                });
+            }
+            "mirror" | "mirror-compare-headers" => {
+                let mirror = route
+                    .mirror
+                    .get_or_insert_with(|| outbound::policy::http::Mirror {
+                        backend: outbound::policy::Backend {
+                            meta: outbound::policy::Meta::new_default("mirror"),
+                            queue,
+                            dispatcher: outbound::policy::BackendDispatcher::Fail {
+                                message: "mirror backend not configured".into(),
+                            },
+                        },
+                        compare_headers: vec![],
+                    });
+                if setting == "mirror" {
+                    let addr = addr::NameAddr::from_str(&value).map_err(ParseError::AddrError)?;
+                    mirror.backend.meta = outbound::policy::Meta::new_default(addr.to_string());
+                    mirror.backend.dispatcher = outbound::policy::BackendDispatcher::BalanceP2c(
+                        outbound::policy::Load::PeakEwma(outbound::policy::PeakEwma {
+                            decay: DEFAULT_OUTBOUND_MIRROR_EWMA_DECAY,
+                            default_rtt: DEFAULT_OUTBOUND_MIRROR_EWMA_DEFAULT_RTT,
+                        }),
+                        outbound::policy::EndpointDiscovery::DestinationGet {
+                            path: addr.to_string(),
+                        },
+                    );
+                } else {
+                    mirror.compare_headers = value
+                        .split('+')
+                        .map(parse_header_name)
+                        .collect::<Result<_, _>>()?;
+                }
+                required.insert(format!("{name}/mirror"));
+            }
+            "success-objective" | "success-objective-windows" => {
+                let objective = route.success_objective.get_or_insert_with(|| {
+                    outbound::policy::http::SuccessObjective {
+                        target_ppm: 0,
+                        windows: DEFAULT_OUTBOUND_SUCCESS_OBJECTIVE_WINDOWS.to_vec(),
+                    }
+                });
+                if setting == "success-objective" {
+                    let percent = value.parse::<f64>().map_err(|_| invalid())?;
+                    if !(percent > 0.0 && percent <= 100.0) {
+                        return Err(invalid());
+                    }
+                    objective.target_ppm = (percent * 10_000.0).round() as u32;
+                } else {
+                    objective.windows = value
+                        .split('+')
+                        .map(parse_duration)
+                        .collect::<Result<_, _>>()?;
+                }
+                required.insert(format!("{name}/success-objective"));
+            }
+            "path-template"
+            | "header-present"
+            | "header-absent"
+            | "header-exact-ignore-case"
+            | "content-type" => {
+                let matches = route.matches.get_or_insert_with(Default::default);
+                parse_route_match(matches, setting, &value).ok_or_else(invalid)?;
+            }
+            "max-request-body-bytes" => {
+                route.request_body_limit = Some(outbound::policy::http::RequestBodyLimit {
+                    max_bytes: parse_number(&value)?,
+                });
+            }
+            "streaming-idle-timeout" => {
+                route.streaming = Some(outbound::policy::http::Streaming {
+                    idle_timeout: parse_duration(&value)?,
+                });
+            }
+            _ => return Err(invalid()),
+        }
+        configured.insert(key);
+    }
+
+    if let Some(missing) = required.difference(&configured).next() {
+        return Err(ParseError::NotARouteSetting(format!(
+            "{missing} is required"
+        )));
+    }
+    Ok(settings)
+}
+
+/// Narrows a route's matches by a `path-template`, header, or `content-type`
+/// setting. Inbound and outbound policies share these match types.
+fn parse_route_match(
+    matches: &mut inbound::policy::route::http::MatchRequest,
+    setting: &str,
+    value: &str,
+) -> Option<()> {
+    use inbound::policy::route::{http::r#match::MatchPath, MatchHeader};
+
+    match setting {
+        "path-template" => {
+            matches.path = Some(MatchPath::Template(value.parse().ok()?));
+            return Some(());
+        }
+        "content-type" => {
+            matches.content_type = Some(value.parse().ok()?);
+            return Some(());
+        }
+        _ => {}
+    }
+    for header in value.split('+') {
+        let header = match setting {
+            "header-present" => MatchHeader::Present(parse_header_name(header).ok()?),
+            "header-absent" => MatchHeader::Absent(parse_header_name(header).ok()?),
+            _ => {
+                let (name, value) = header.split_once(':')?;
+                MatchHeader::ExactIgnoreCase(
+                    parse_header_name(name).ok()?,
+                    value.trim().parse().ok()?,
+                )
+            }
+        };
+        matches.headers.push(header);
+    }
+    Some(())
+}
+
+/// Parses a local address match like `10.0.0.0/8:4143`, where either the
+/// network or the port may be omitted. IPv6 networks with ports are bracketed,
+/// e.g. `[fd00::/8]:4143`.
+fn parse_local_addr(s: &str) -> Option<inbound::policy::LocalAddr> {
+    let (net, port) = match s.strip_prefix('[') {
+        Some(s) => {
+            let (net, port) = s.split_once(']')?;
+            (net, Some(port.strip_prefix(':')?))
+        }
+        None => match s.rsplit_once(':') {
+            Some((net, port)) if !net.contains(':') => (net, Some(port)),
+            _ => (s, None),
+        },
+    };
+    let net = match net {
+        "" => None,
+        net => Some(
+            net.parse::<IpNet>()
+                .or_else(|_| net.parse::<IpAddr>().map(IpNet::from))
+                .ok()?,
+        ),
+    };
+    let port = port.map(str::parse).transpose().ok()?;
+    if net.is_none() && port.is_none() {
+        return None;
+    }
+    Some(inbound::policy::LocalAddr { net, port })
+}
+
+fn parse_rate_limit(s: &str) -> Result<inbound::policy::RateLimit, ParseError> {
+    let (rate, burst) = match s.split_once(':') {
+        Some((rate, burst)) => (rate.parse()?, burst.parse()?),
+        None => {
+            let rate = s.parse()?;
+            (rate, rate)
+        }
+    };
+    Ok(inbound::policy::RateLimit {
+        requests_per_second: rate,
+        burst,
+    })
+}
+
 fn parse_default_policy(
     s: &str,
     cluster_nets: HashSet<IpNet>,
@@ -1194,12 +3099,65 @@ pub fn parse_backoff<S: Strings>(
     }
 }
 
+/// Parses the TCP socket options configured for `base`, e.g.
+/// `LINKERD2_PROXY_INBOUND_ACCEPT_KEEPALIVE_INTERVAL`,
+/// `LINKERD2_PROXY_INBOUND_ACCEPT_KEEPALIVE_COUNT`,
+/// `LINKERD2_PROXY_INBOUND_ACCEPT_USER_TIMEOUT`, and
+/// `LINKERD2_PROXY_INBOUND_ACCEPT_NODELAY`.
+pub fn parse_socket_options<S: Strings>(
+    strings: &S,
+    base: &str,
+) -> Result<SocketOptions, EnvError> {
+    let keepalive_interval = parse(
+        strings,
+        &format!("LINKERD2_PROXY_{}_KEEPALIVE_INTERVAL", base),
+        parse_duration,
+    );
+    let keepalive_count = parse(
+        strings,
+        &format!("LINKERD2_PROXY_{}_KEEPALIVE_COUNT", base),
+        parse_number,
+    );
+    let user_timeout = parse(
+        strings,
+        &format!("LINKERD2_PROXY_{}_USER_TIMEOUT", base),
+        parse_duration,
+    );
+    let nodelay = parse(
+        strings,
+        &format!("LINKERD2_PROXY_{}_NODELAY", base),
+        parse_bool,
+    );
+
+    let default = SocketOptions::default();
+    Ok(SocketOptions {
+        nodelay: nodelay?.unwrap_or(default.nodelay),
+        keepalive_interval: keepalive_interval?,
+        keepalive_count: keepalive_count?,
+        user_timeout: user_timeout?,
+    })
+}
+
+/// Configures HTTP/2 PINGs on a discovery controller's connections, so that
+/// unhealthy connections are detected even while their watches are idle.
+fn with_control_keepalive(connect: ConnectConfig, timeout: Duration) -> ConnectConfig {
+    ConnectConfig {
+        h2_settings: h2::Settings {
+            keepalive_timeout: Some(timeout),
+            ..connect.h2_settings
+        },
+        ..connect
+    }
+}
+
 pub fn parse_control_addr<S: Strings>(
     strings: &S,
     base: &str,
 ) -> Result<Option<ControlAddr>, EnvError> {
     let a = parse(strings, &format!("{}_ADDR", base), parse_addr)?;
     let n = parse(strings, &format!("{}_NAME", base), parse_dns_name)?;
+    // The SNI sent to the controller defaults to its identity name.
+    let sni = parse(strings, &format!("{}_SNI", base), parse_dns_name)?;
     match (a, n) {
         (None, None) => Ok(None),
         (Some(ref addr), _) if addr.is_loopback() => Ok(Some(ControlAddr {
@@ -1210,7 +3168,7 @@ pub fn parse_control_addr<S: Strings>(
             addr,
             identity: Conditional::Some(tls::ClientTls::new(
                 tls::ServerId(name.clone().into()),
-                tls::ServerName(name),
+                tls::ServerName(sni.unwrap_or(name)),
             )),
         })),
         _ => {
@@ -1220,8 +3178,63 @@ pub fn parse_control_addr<S: Strings>(
     }
 }
 
+/// Parses the trust anchors and public key pins with which a controller's
+/// certificate is verified, if they are configured separately from the
+/// workload's trust anchors.
+///
+/// `{base}_TRUST_ANCHORS` holds PEM-encoded trust anchors and
+/// `{base}_PINNED_KEYS` holds a comma-separated list of hex-encoded SHA-256
+/// digests of SubjectPublicKeyInfos, at least one of which must match a
+/// certificate in the controller's chain.
+pub fn parse_control_trust<S: Strings>(
+    strings: &S,
+    base: &str,
+) -> Result<Option<linkerd_app_core::identity::ServerTrust>, EnvError> {
+    let roots_env = format!("{}_TRUST_ANCHORS", base);
+    let roots = strings.get(&roots_env)?.filter(|s| !s.is_empty());
+    let pins = parse(strings, &format!("{}_PINNED_KEYS", base), parse_spki_pins)?;
+    if roots.is_none() && pins.is_none() {
+        return Ok(None);
+    }
+
+    linkerd_app_core::identity::ServerTrust::new(
+        Default::default(),
+        roots.as_deref(),
+        pins.unwrap_or_default(),
+    )
+    .map(Some)
+    .map_err(|error| {
+        error!(%error, "Invalid {}", roots_env);
+        EnvError::InvalidEnvVar
+    })
+}
+
+/// Selects the token with which a controller's requests are authenticated by
+/// the audience named in `{base}_TOKEN_AUDIENCE`.
+pub fn parse_control_token<S: Strings>(
+    strings: &S,
+    base: &str,
+    tokens: &HashMap<String, identity::TokenSource>,
+) -> Result<Option<identity::TokenSource>, EnvError> {
+    let env = format!("{}_TOKEN_AUDIENCE", base);
+    match strings.get(&env)?.filter(|s| !s.is_empty()) {
+        None => Ok(None),
+        Some(audience) => match tokens.get(audience.trim()) {
+            Some(token) => Ok(Some(token.clone())),
+            None => {
+                error!(
+                    "{} names an audience without a token in {}",
+                    env, ENV_CONTROL_TOKEN_SOURCES
+                );
+                Err(EnvError::InvalidEnvVar)
+            }
+        },
+    }
+}
+
 pub fn parse_identity_config<S: Strings>(
     strings: &S,
+    tokens: &HashMap<String, identity::TokenSource>,
 ) -> Result<(ControlAddr, identity::certify::Config, identity::TlsParams), EnvError> {
     let control = parse_control_addr(strings, ENV_IDENTITY_SVC_BASE);
     let ta = parse(strings, ENV_IDENTITY_TRUST_ANCHORS, |s| {
@@ -1231,12 +3244,16 @@ pub fn parse_identity_config<S: Strings>(
         Ok(s.to_string())
     });
     let dir = parse(strings, ENV_IDENTITY_DIR, |ref s| Ok(PathBuf::from(s)));
-    let tok = parse(strings, ENV_IDENTITY_TOKEN_FILE, |ref s| {
-        identity::TokenSource::if_nonempty_file(s.to_string()).map_err(|e| {
-            error!("Could not read {}: {}", ENV_IDENTITY_TOKEN_FILE, e);
-            ParseError::InvalidTokenSource
-        })
-    });
+    // A token selected by audience takes precedence over the token file.
+    let tok = match parse_control_token(strings, ENV_IDENTITY_SVC_BASE, tokens) {
+        Ok(None) => parse(strings, ENV_IDENTITY_TOKEN_FILE, |ref s| {
+            identity::TokenSource::if_nonempty_file(s.to_string()).map_err(|e| {
+                error!("Could not read {}: {}", ENV_IDENTITY_TOKEN_FILE, e);
+                ParseError::InvalidTokenSource
+            })
+        }),
+        tok => tok,
+    };
     let li = parse(strings, ENV_IDENTITY_IDENTITY_LOCAL_NAME, parse_dns_name);
     let min_refresh = parse(strings, ENV_IDENTITY_MIN_REFRESH, parse_duration);
     let max_refresh = parse(strings, ENV_IDENTITY_MAX_REFRESH, parse_duration);
@@ -1313,6 +3330,154 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_latency_buckets() {
+        let buckets = parse_buckets("response_latency_ms=0.1, 0.5,1;route_response_latency_ms=10")
+            .expect("buckets must parse");
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets["response_latency_ms"].0.len(), 4);
+        assert_eq!(buckets["route_response_latency_ms"].0.len(), 2);
+
+        assert!(parse_buckets("").unwrap().is_empty());
+        assert!(parse_buckets("response_latency_ms").is_err());
+        assert!(parse_buckets("response_latency_ms=5,1").is_err());
+        assert!(parse_buckets("response_latency_ms=0,1").is_err());
+        assert!(parse_buckets("response_latency_ms=one").is_err());
+    }
+
+    #[test]
+    fn parse_otlp_metrics_config() {
+        assert_eq!(
+            parse_temporality("delta"),
+            Ok(otlp_metrics::Temporality::Delta)
+        );
+        assert!(parse_temporality("gauge").is_err());
+
+        let attrs =
+            parse_key_values("k8s.pod.name=web-0, env = prod,").expect("attributes must parse");
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs["k8s.pod.name"], "web-0");
+        assert_eq!(attrs["env"], "prod");
+        assert!(parse_key_values("nope").is_err());
+        assert!(parse_key_values("=value").is_err());
+    }
+
+    #[test]
+    fn parse_metrics_push_config() {
+        assert!(parse_push_url("http://prometheus.monitoring:9090/api/v1/write").is_ok());
+        assert!(parse_push_url("https://prometheus.monitoring:9090/api/v1/write").is_err());
+        assert!(parse_push_url("/api/v1/write").is_err());
+        assert_eq!(
+            parse_push_format("pushgateway"),
+            Ok(metrics_push::Format::Pushgateway)
+        );
+        assert!(parse_push_format("graphite").is_err());
+    }
+
+    #[test]
+    fn parse_ingress_authority_rewrites() {
+        let mut rewrites = parse_authority_rewrites(
+            "Web.example.com.=web.prod.svc.cluster.local:8080, api.prod=:8443, a=b",
+        )
+        .expect("rewrites must parse");
+        rewrites.sort_by(|a, b| a.host.cmp(&b.host));
+        assert_eq!(
+            rewrites,
+            vec![
+                outbound::AuthorityRewrite {
+                    host: "a".to_string(),
+                    to_host: Some("b".to_string()),
+                    to_port: None,
+                },
+                outbound::AuthorityRewrite {
+                    host: "api.prod".to_string(),
+                    to_host: None,
+                    to_port: Some(8443),
+                },
+                outbound::AuthorityRewrite {
+                    host: "web.example.com".to_string(),
+                    to_host: Some("web.prod.svc.cluster.local".to_string()),
+                    to_port: Some(8080),
+                },
+            ]
+        );
+        assert!(parse_authority_rewrites("a=").is_err());
+        assert!(parse_authority_rewrites("a=b:http").is_err());
+        assert!(parse_authority_rewrites("a").is_err());
+    }
+
+    #[test]
+    fn parse_failover_clusters_by_name() {
+        let mut clusters = parse_failover_clusters("east=2, west=1").unwrap();
+        clusters.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(
+            clusters,
+            vec![
+                outbound::FailoverCluster {
+                    name: "east".to_string(),
+                    min_local_endpoints: 2,
+                },
+                outbound::FailoverCluster {
+                    name: "west".to_string(),
+                    min_local_endpoints: 1,
+                },
+            ]
+        );
+        assert!(parse_failover_clusters("east").is_err());
+        assert!(parse_failover_clusters("east=many").is_err());
+    }
+
+    #[test]
+    fn parse_egress_host_matches() {
+        assert_eq!(
+            parse_host_matches("*.example.com, api.test:8443")
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(parse_host_matches("10.0.0.1").is_err());
+        assert!(parse_host_matches("api.test:http").is_err());
+    }
+
+    #[test]
+    fn parse_ingress_fallbacks() {
+        assert_eq!(
+            parse_ingress_fallback(" orig-dst").unwrap(),
+            outbound::IngressFallback::OrigDst
+        );
+        assert_eq!(
+            parse_ingress_fallback("dns").unwrap(),
+            outbound::IngressFallback::Dns
+        );
+        assert!(parse_ingress_fallback("forward").is_err());
+    }
+
+    #[test]
+    fn parse_expect_continue_modes() {
+        assert_eq!(
+            parse_expect_continue("answer").unwrap(),
+            outbound::http::ExpectContinue::Answer
+        );
+        assert_eq!(
+            parse_expect_continue(" strip ").unwrap(),
+            outbound::http::ExpectContinue::Strip
+        );
+        assert!(parse_expect_continue("reject").is_err());
+    }
+
+    #[test]
+    fn parse_metrics_peers_config() {
+        assert_eq!(
+            parse_peer_granularity("ip"),
+            Ok(transport::metrics::peer::Granularity::Ip)
+        );
+        assert_eq!(
+            parse_peer_granularity(" endpoint "),
+            Ok(transport::metrics::peer::Granularity::Endpoint)
+        );
+        assert!(parse_peer_granularity("pod").is_err());
+    }
+
     #[test]
     fn parse_duration_unit_ms() {
         test_unit("ms", Duration::from_millis);
@@ -1484,6 +3649,22 @@ mod tests {
         assert!(parse_ip_set("10.0.1.1/24").is_err());
     }
 
+    #[test]
+    fn socket_addrs() {
+        let addrs = &[
+            SocketAddr::from(([0, 0, 0, 0], 4144)),
+            SocketAddr::from(([127, 0, 0, 1], 4145)),
+        ];
+        assert_eq!(parse_socket_addrs(""), Ok(vec![]));
+        assert_eq!(parse_socket_addrs("0.0.0.0:4144"), Ok(addrs[..1].to_vec()));
+        assert_eq!(
+            parse_socket_addrs(" 0.0.0.0:4144, 127.0.0.1:4145 ,"),
+            Ok(addrs.to_vec())
+        );
+        assert!(parse_socket_addrs("0.0.0.0").is_err());
+        assert!(parse_socket_addrs("localhost:4144").is_err());
+    }
+
     #[test]
     fn ranges() {
         fn set(
@@ -1576,4 +3757,610 @@ mod tests {
         env.insert("LINKERD2_PROXY_CONTROL_STREAM_LIFETIME", "1s");
         assert!(mk_control_receive_limits(&env).is_err());
     }
+
+    #[test]
+    fn control_sni_and_pins() {
+        let mut env = HashMap::<&'static str, &'static str>::default();
+        env.insert(
+            "LINKERD2_PROXY_DESTINATION_SVC_ADDR",
+            "linkerd-dst-headless.linkerd.svc.cluster.local:8086",
+        );
+        env.insert(
+            "LINKERD2_PROXY_DESTINATION_SVC_NAME",
+            "linkerd-destination.linkerd.serviceaccount.identity.linkerd.cluster.local",
+        );
+        env.insert("LINKERD2_PROXY_DESTINATION_SVC_SNI", "dst.example.com");
+        let addr = parse_control_addr(&env, ENV_DESTINATION_SVC_BASE)
+            .unwrap()
+            .expect("address must be configured");
+        match addr.identity {
+            Conditional::Some(tls) => assert_eq!(
+                tls.server_name,
+                tls::ServerName("dst.example.com".parse().unwrap())
+            ),
+            Conditional::None(_) => panic!("identity must be configured"),
+        }
+        assert!(parse_control_trust(&env, ENV_DESTINATION_SVC_BASE)
+            .unwrap()
+            .is_none());
+
+        env.insert(
+            "LINKERD2_PROXY_DESTINATION_SVC_PINNED_KEYS",
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+        );
+        assert!(parse_control_trust(&env, ENV_DESTINATION_SVC_BASE)
+            .unwrap()
+            .is_some());
+
+        env.insert("LINKERD2_PROXY_DESTINATION_SVC_PINNED_KEYS", "sha256/abc");
+        assert!(parse_control_trust(&env, ENV_DESTINATION_SVC_BASE).is_err());
+    }
+
+    #[test]
+    fn port_default_policies() {
+        let timeout = Duration::from_secs(10);
+        let nets = ["10.0.0.0/8".parse().unwrap()].into_iter().collect();
+        let defaults =
+            parse_port_default_policies("25=deny, 8080=cluster-authenticated", &nets, timeout)
+                .expect("port defaults must parse");
+        assert_eq!(defaults.len(), 2);
+        assert_eq!(defaults[&25], inbound::policy::DefaultPolicy::Deny);
+        assert_eq!(
+            defaults[&8080],
+            inbound::policy::defaults::cluster_authenticated(nets.clone(), timeout).into()
+        );
+
+        assert!(parse_port_default_policies("", &nets, timeout)
+            .unwrap()
+            .is_empty());
+        assert!(parse_port_default_policies("http=deny", &nets, timeout).is_err());
+        assert!(parse_port_default_policies("25=permissive", &nets, timeout).is_err());
+        assert!(
+            parse_port_default_policies("25=cluster-authenticated", &HashSet::new(), timeout)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn port_sni_routes() {
+        let routes =
+            parse_port_sni_routes("443/api.example.com=8443, 443/*.example.com=10.0.0.2:443")
+                .expect("SNI routes must parse");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[&443].len(), 2);
+
+        assert!(parse_port_sni_routes("").unwrap().is_empty());
+        assert!(parse_port_sni_routes("api.example.com=8443").is_err());
+        assert!(parse_port_sni_routes("https/api.example.com=8443").is_err());
+        assert!(parse_port_sni_routes("443/api.example.com=backend").is_err());
+        assert!(parse_port_sni_routes("443/api..com=8443").is_err());
+    }
+
+    #[test]
+    fn dns_suffix_routes() {
+        assert_eq!(
+            parse_dns_suffix_routes(".consul=127.0.0.1:8600, corp.example.com.=10.0.0.2:53")
+                .unwrap(),
+            vec![
+                dns::SuffixRoute {
+                    suffix: "consul".parse().unwrap(),
+                    server: SocketAddr::from(([127, 0, 0, 1], 8600)),
+                },
+                dns::SuffixRoute {
+                    suffix: "corp.example.com.".parse().unwrap(),
+                    server: SocketAddr::from(([10, 0, 0, 2], 53)),
+                },
+            ]
+        );
+        assert!(parse_dns_suffix_routes("").unwrap().is_empty());
+        assert!(parse_dns_suffix_routes(".consul").is_err());
+        assert!(parse_dns_suffix_routes(".consul=127.0.0.1").is_err());
+        assert!(parse_dns_suffix_routes(".consul=localhost:8600").is_err());
+        assert!(parse_dns_suffix_routes("a..b=127.0.0.1:53").is_err());
+    }
+
+    #[test]
+    fn port_tcp_settings() {
+        let settings =
+            parse_port_tcp_settings("5432/idle-timeout=1h, 9092/half-close=false, 9092/linger=30s")
+                .expect("TCP settings must parse");
+        assert_eq!(
+            settings[&5432],
+            inbound::policy::DuplexConfig {
+                idle_timeout: Some(Duration::from_secs(60 * 60)),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            settings[&9092],
+            inbound::policy::DuplexConfig {
+                half_close: false,
+                linger: Some(Duration::from_secs(30)),
+                idle_timeout: None,
+            }
+        );
+
+        assert!(parse_port_tcp_settings("").unwrap().is_empty());
+        assert!(parse_port_tcp_settings("idle-timeout=1h").is_err());
+        assert!(parse_port_tcp_settings("5432/keepalive=1h").is_err());
+        assert!(parse_port_tcp_settings("5432/half-close=maybe").is_err());
+    }
+
+    #[test]
+    fn inbound_route_settings() {
+        let settings = parse_inbound_route_settings(
+            "api/max-in-flight=100, api/rate-limit=50, web/rate-limit=10:20, web/priority=low, \
+             web/inject-client-identity=x-client-id, web/inject-tls=x-client-tls",
+        )
+        .expect("route settings must parse");
+        assert_eq!(settings["api"].max_in_flight, Some(100));
+        let rate_limit = |rate, burst| {
+            Some(inbound::policy::RateLimit {
+                requests_per_second: std::num::NonZeroU32::new(rate).unwrap(),
+                burst: std::num::NonZeroU32::new(burst).unwrap(),
+            })
+        };
+        assert_eq!(settings["api"].rate_limit, rate_limit(50, 50));
+        assert_eq!(settings["web"].rate_limit, rate_limit(10, 20));
+        assert_eq!(settings["api"].priority, None);
+        assert_eq!(
+            settings["web"].priority,
+            Some(inbound::policy::Priority::Low)
+        );
+        assert_eq!(settings["api"].inject_metadata, None);
+        let inject = settings["web"].inject_metadata.as_ref().unwrap();
+        assert_eq!(inject.client_addr, None);
+        assert_eq!(inject.client_identity.as_ref().unwrap(), "x-client-id");
+        assert_eq!(inject.tls.as_ref().unwrap(), "x-client-tls");
+        assert_eq!(inject.route, None);
+
+        assert!(parse_inbound_route_settings("").unwrap().is_empty());
+        assert!(parse_inbound_route_settings("max-in-flight=100").is_err());
+        assert!(parse_inbound_route_settings("api/max-in-flight=lots").is_err());
+        assert!(parse_inbound_route_settings("api/timeout=1s").is_err());
+        assert!(parse_inbound_route_settings("api/rate-limit=0").is_err());
+        assert!(parse_inbound_route_settings("api/rate-limit=10:").is_err());
+        assert!(parse_inbound_route_settings("api/priority=urgent").is_err());
+        assert!(parse_inbound_route_settings("api/inject-route=x route").is_err());
+        assert!(parse_inbound_route_settings("api/transcode=/dev/null/descriptors").is_err());
+
+        let settings = parse_inbound_route_settings("users/path-template=/users/{id}")
+            .expect("route settings must parse");
+        assert_eq!(
+            settings["users"].matches.as_ref().unwrap().path,
+            Some(inbound::policy::route::http::r#match::MatchPath::Template(
+                "/users/{id}".parse().unwrap()
+            ))
+        );
+        assert!(parse_inbound_route_settings("users/path-template=users/{id}").is_err());
+
+        let settings = parse_inbound_route_settings(
+            "beta/header-present=x-beta+x-user, beta/header-absent=x-legacy, \
+             beta/header-exact-ignore-case=x-env:Staging",
+        )
+        .expect("route settings must parse");
+        let headers = &settings["beta"].matches.as_ref().unwrap().headers;
+        assert_eq!(headers.len(), 4);
+        assert!(
+            headers.contains(&inbound::policy::route::MatchHeader::Present(
+                http::HeaderName::from_static("x-user")
+            ))
+        );
+        assert!(
+            headers.contains(&inbound::policy::route::MatchHeader::Absent(
+                http::HeaderName::from_static("x-legacy")
+            ))
+        );
+        assert!(
+            headers.contains(&inbound::policy::route::MatchHeader::ExactIgnoreCase(
+                http::HeaderName::from_static("x-env"),
+                http::HeaderValue::from_static("Staging"),
+            ))
+        );
+        assert!(parse_inbound_route_settings("beta/header-present=x beta").is_err());
+        assert!(parse_inbound_route_settings("beta/header-exact-ignore-case=x-env").is_err());
+
+        let settings = parse_inbound_route_settings("uploads/content-type=multipart/*")
+            .expect("route settings must parse");
+        assert_eq!(
+            settings["uploads"].matches.as_ref().unwrap().content_type,
+            Some("multipart/*".parse().unwrap())
+        );
+        assert!(parse_inbound_route_settings("uploads/content-type=multipart").is_err());
+
+        // An empty descriptor set describes no services.
+        let path = std::env::temp_dir().join(format!("linkerd-descriptors-{}", std::process::id()));
+        std::fs::write(&path, b"").unwrap();
+        let settings = parse_inbound_route_settings(&format!("api/transcode={}", path.display()))
+            .expect("route settings must parse");
+        assert!(settings["api"].transcode.is_some());
+        std::fs::write(&path, b"not a descriptor set").unwrap();
+        assert!(
+            parse_inbound_route_settings(&format!("api/transcode={}", path.display())).is_err()
+        );
+    }
+
+    #[test]
+    fn outbound_route_settings() {
+        let queue = outbound::policy::Queue {
+            capacity: 100,
+            failfast_timeout: Duration::from_secs(3),
+        };
+        let parse_settings = |s: &str| parse_outbound_route_settings(s, queue);
+
+        let settings = parse_settings(
+            "maintenance/direct-response=503, maintenance/direct-response-body=down, \
+             maintenance/direct-response-headers=retry-after:60+content-type:text/plain",
+        )
+        .expect("route settings must parse");
+        let direct = settings["maintenance"].direct_response.as_ref().unwrap();
+        assert_eq!(direct.status, http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(direct.body, "down");
+        assert_eq!(
+            direct.headers,
+            [
+                (
+                    http::HeaderName::from_static("retry-after"),
+                    http::HeaderValue::from_static("60")
+                ),
+                (
+                    http::header::CONTENT_TYPE,
+                    http::HeaderValue::from_static("text/plain")
+                ),
+            ]
+        );
+
+        assert!(parse_settings("").unwrap().is_empty());
+        assert!(parse_settings("direct-response=503").is_err());
+        assert!(parse_settings("api/direct-response=5000").is_err());
+        assert!(parse_settings("api/direct-response-body=down").is_err());
+        assert!(parse_settings("api/direct-response-headers=x").is_err());
+        assert!(parse_settings("api/timeout=1s").is_err());
+
+        let settings = parse_settings(
+            "api/cache=10485760, api/cache-vary=accept+accept-language, web/cache=1024",
+        )
+        .expect("route settings must parse");
+        assert_eq!(
+            settings["api"].cache,
+            Some(outbound::policy::http::Cache {
+                max_bytes: 10 * 1024 * 1024,
+                max_entry_bytes: DEFAULT_OUTBOUND_ROUTE_CACHE_MAX_ENTRY_BYTES,
+                vary: vec![http::header::ACCEPT, http::header::ACCEPT_LANGUAGE],
+            })
+        );
+        assert_eq!(settings["web"].cache.as_ref().unwrap().max_bytes, 1024);
+        assert!(parse_settings("api/cache=lots").is_err());
+        assert!(parse_settings("api/cache-max-entry-bytes=1024").is_err());
+
+        let settings = parse_settings("api/decompress=10, api/decompress-max-bytes=65536")
+            .expect("route settings must parse");
+        assert_eq!(
+            settings["api"].request_decompression,
+            Some(outbound::policy::http::Decompression {
+                max_bytes: Some(65536),
+                max_expansion_ratio: 10,
+            })
+        );
+        assert!(parse_settings("api/decompress=0").is_err());
+        assert!(parse_settings("api/decompress-max-bytes=1024").is_err());
+
+        let settings =
+            parse_settings("events/streaming-idle-timeout=30s").expect("route settings must parse");
+        assert_eq!(
+            settings["events"].streaming,
+            Some(outbound::policy::http::Streaming {
+                idle_timeout: Duration::from_secs(30),
+            })
+        );
+        assert!(parse_settings("events/streaming-idle-timeout=soon").is_err());
+
+        let settings = parse_settings(
+            "a/sticky=header:x-user-id, b/sticky=cookie:session, c/sticky=client-ip",
+        )
+        .expect("route settings must parse");
+        assert_eq!(
+            settings["a"].sticky,
+            Some(outbound::policy::http::StickyKey::Header(
+                http::HeaderName::from_static("x-user-id")
+            ))
+        );
+        assert_eq!(
+            settings["b"].sticky,
+            Some(outbound::policy::http::StickyKey::Cookie(
+                "session".to_string()
+            ))
+        );
+        assert_eq!(
+            settings["c"].sticky,
+            Some(outbound::policy::http::StickyKey::ClientIp)
+        );
+        assert!(parse_settings("a/sticky=cookie:").is_err());
+        assert!(parse_settings("a/sticky=query:user").is_err());
+
+        let settings = parse_settings(
+            "api/mirror=api-v2.ns.svc.cluster.local:8080, api/mirror-compare-headers=etag",
+        )
+        .expect("route settings must parse");
+        let mirror = settings["api"].mirror.as_ref().unwrap();
+        assert_eq!(mirror.backend.queue, queue);
+        assert!(matches!(
+            mirror.backend.dispatcher,
+            outbound::policy::BackendDispatcher::BalanceP2c(
+                _,
+                outbound::policy::EndpointDiscovery::DestinationGet { ref path },
+            ) if path == "api-v2.ns.svc.cluster.local:8080"
+        ));
+        assert_eq!(mirror.compare_headers, [http::header::ETAG]);
+        assert!(parse_settings("api/mirror=api-v2").is_err());
+        assert!(parse_settings("api/mirror-compare-headers=etag").is_err());
+
+        let settings = parse_settings(
+            "api/success-objective=99.9, web/success-objective=95, \
+             web/success-objective-windows=1m+1d",
+        )
+        .expect("route settings must parse");
+        assert_eq!(
+            settings["api"].success_objective,
+            Some(outbound::policy::http::SuccessObjective {
+                target_ppm: 999_000,
+                windows: DEFAULT_OUTBOUND_SUCCESS_OBJECTIVE_WINDOWS.to_vec(),
+            })
+        );
+        assert_eq!(
+            settings["web"].success_objective,
+            Some(outbound::policy::http::SuccessObjective {
+                target_ppm: 950_000,
+                windows: vec![Duration::from_secs(60), Duration::from_secs(24 * 60 * 60)],
+            })
+        );
+        assert!(parse_settings("api/success-objective=0").is_err());
+        assert!(parse_settings("api/success-objective=100.1").is_err());
+        assert!(parse_settings("api/success-objective=most").is_err());
+        assert!(parse_settings("api/success-objective-windows=1m").is_err());
+
+        let settings = parse_settings("upload/max-request-body-bytes=1048576")
+            .expect("route settings must parse");
+        assert_eq!(
+            settings["upload"].request_body_limit,
+            Some(outbound::policy::http::RequestBodyLimit {
+                max_bytes: 1024 * 1024
+            })
+        );
+        assert!(parse_settings("upload/max-request-body-bytes=1MiB").is_err());
+
+        let settings =
+            parse_settings("users/path-template=/users/{id}").expect("route settings must parse");
+        assert_eq!(
+            settings["users"].matches.as_ref().unwrap().path,
+            Some(outbound::policy::route::http::r#match::MatchPath::Template(
+                "/users/{id}".parse().unwrap()
+            ))
+        );
+        assert!(parse_settings("users/path-template=/users/{}").is_err());
+
+        let settings = parse_settings("beta/header-present=x-beta, beta/header-absent=x-legacy")
+            .expect("route settings must parse");
+        assert_eq!(settings["beta"].matches.as_ref().unwrap().headers.len(), 2);
+        assert!(parse_settings("beta/header-absent=").is_err());
+
+        let settings = parse_settings("json/content-type=application/json")
+            .expect("route settings must parse");
+        assert_eq!(
+            settings["json"].matches.as_ref().unwrap().content_type,
+            Some("application/json".parse().unwrap())
+        );
+        assert!(parse_settings("json/content-type=*/json").is_err());
+    }
+
+    #[test]
+    fn pin_authz() {
+        let authz = parse_pin_authz(
+            "name=debug, networks=10.0.0.0/8+fd00::/8, not-after=2030-01-01T00:00:00Z",
+        )
+        .unwrap();
+        assert_eq!(authz.meta.name(), "debug");
+        assert_eq!(authz.networks.len(), 2);
+        assert_eq!(
+            authz.validity,
+            vec![inbound::policy::TimeWindow {
+                not_before: None,
+                not_after: Some(humantime::parse_rfc3339("2030-01-01T00:00:00Z").unwrap()),
+            }]
+        );
+
+        let authz = parse_pin_authz("").unwrap();
+        assert_eq!(authz.meta.name(), "endpoint-pinning");
+        assert!(authz.networks.is_empty());
+        assert!(authz.validity.is_empty());
+
+        assert!(parse_pin_authz("networks=pods").is_err());
+        assert!(parse_pin_authz("not-before=yesterday").is_err());
+        assert!(parse_pin_authz("identities=web").is_err());
+    }
+
+    #[test]
+    fn inbound_authz_settings() {
+        use inbound::policy::SignedComponent;
+
+        let settings = parse_inbound_authz_settings(
+            "webhooks/hmac-secret=github, webhooks/hmac-signed=path+header:x-event, \
+             webhooks/hmac-max-skew=1m, other/hmac-secret=other, \
+             node/local-addrs=10.0.0.0/8:4143+:4191+[fd00::/8]:4143+fd00::1, \
+             temp/not-before=2024-01-01T00:00:00Z, temp/not-after=2024-01-02T00:00:00Z, \
+             mesh/trust-domains=cluster.local+example.org, mesh/same-namespace=true, \
+             local/same-namespace=true, \
+             pinned/cert-fingerprints=01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01:01",
+        )
+        .expect("authorization settings must parse");
+        let hmac = settings["webhooks"].hmac.as_ref().unwrap();
+        assert_eq!(hmac.secret, "github");
+        assert_eq!(hmac.signature_header, "x-signature");
+        assert_eq!(hmac.max_skew, Duration::from_secs(60));
+        assert_eq!(
+            hmac.components,
+            vec![
+                SignedComponent::Path,
+                SignedComponent::Header(http::HeaderName::from_static("x-event")),
+            ]
+        );
+        let hmac = settings["other"].hmac.as_ref().unwrap();
+        assert_eq!(hmac.secret, "other");
+        assert_eq!(
+            hmac.components,
+            vec![
+                SignedComponent::Method,
+                SignedComponent::Authority,
+                SignedComponent::Path,
+            ]
+        );
+
+        let addr = |net: Option<&str>, port| inbound::policy::LocalAddr {
+            net: net.map(|n| n.parse().unwrap()),
+            port,
+        };
+        assert_eq!(settings["node"].hmac, None);
+        assert_eq!(
+            settings["node"].local_addrs,
+            vec![
+                addr(Some("10.0.0.0/8"), Some(4143)),
+                addr(None, Some(4191)),
+                addr(Some("fd00::/8"), Some(4143)),
+                addr(Some("fd00::1/128"), None),
+            ]
+        );
+
+        let day = |d: u64| {
+            Some(std::time::UNIX_EPOCH + Duration::from_secs(1_704_067_200 + (d - 1) * 86_400))
+        };
+        assert_eq!(
+            settings["temp"].validity,
+            Some(inbound::policy::TimeWindow {
+                not_before: day(1),
+                not_after: day(2),
+            })
+        );
+        assert_eq!(settings["node"].validity, None);
+
+        assert_eq!(
+            settings["mesh"].identity_scopes,
+            Some(inbound::policy::IdentityScopes {
+                trust_domains: vec!["cluster.local".to_string(), "example.org".to_string()],
+                same_namespace: true,
+            })
+        );
+        assert_eq!(
+            settings["local"].identity_scopes,
+            Some(inbound::policy::IdentityScopes {
+                trust_domains: vec![],
+                same_namespace: true,
+            })
+        );
+        assert_eq!(settings["node"].identity_scopes, None);
+        assert_eq!(
+            settings["pinned"].cert_fingerprints,
+            Some(inbound::policy::CertFingerprint([1; 32]))
+                .into_iter()
+                .collect()
+        );
+        assert!(settings["node"].cert_fingerprints.is_empty());
+
+        assert!(parse_inbound_authz_settings("").unwrap().is_empty());
+        assert!(parse_inbound_authz_settings("hmac-secret=github").is_err());
+        assert!(parse_inbound_authz_settings("temp/not-after=tomorrow").is_err());
+        assert!(parse_inbound_authz_settings("node/local-addrs=").is_err());
+        assert!(parse_inbound_authz_settings("node/local-addrs=10.0.0.0/8:http").is_err());
+        assert!(parse_inbound_authz_settings("node/local-addrs=[fd00::/8]4143").is_err());
+        assert!(parse_inbound_authz_settings("webhooks/hmac-max-skew=1m").is_err());
+        assert!(parse_inbound_authz_settings("webhooks/hmac-signed=body").is_err());
+        assert!(parse_inbound_authz_settings("webhooks/identity=foo").is_err());
+        assert!(parse_inbound_authz_settings("mesh/trust-domains=*.local").is_err());
+        assert!(parse_inbound_authz_settings("mesh/same-namespace=yes").is_err());
+        assert!(parse_inbound_authz_settings("pinned/cert-fingerprints=0011").is_err());
+        assert!(parse_inbound_authz_settings(&format!(
+            "pinned/cert-fingerprints={}, pinned/trust-domains=cluster.local",
+            "00".repeat(32)
+        ))
+        .is_err());
+        assert!(
+            parse_inbound_authz_settings("mesh/same-namespace=true, mesh/hmac-secret=github")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn port_bandwidth_limits() {
+        let limits = parse_port_bandwidth_limits("8080=1048576, 9090=1024")
+            .expect("bandwidth limits must parse");
+        assert_eq!(limits[&8080].get(), 1048576);
+        assert_eq!(limits[&9090].get(), 1024);
+
+        assert!(parse_port_bandwidth_limits("").unwrap().is_empty());
+        assert!(parse_port_bandwidth_limits("8080=0").is_err());
+        assert!(parse_port_bandwidth_limits("8080=1MiB").is_err());
+        assert!(parse_port_bandwidth_limits("http=1024").is_err());
+    }
+
+    #[test]
+    fn control_token_audiences() {
+        let path = std::env::temp_dir().join(format!("linkerd-token-{}", std::process::id()));
+        std::fs::write(&path, "dst-token").unwrap();
+        let sources = format!("linkerd-destination={}", path.display());
+        let tokens = parse_token_sources(&sources).expect("token sources must parse");
+        assert!(parse_token_sources("linkerd-destination=/does/not/exist").is_err());
+
+        let mut env = HashMap::<&'static str, &'static str>::default();
+        assert_eq!(
+            parse_control_token(&env, ENV_DESTINATION_SVC_BASE, &tokens).unwrap(),
+            None
+        );
+
+        env.insert(
+            "LINKERD2_PROXY_DESTINATION_SVC_TOKEN_AUDIENCE",
+            "linkerd-destination",
+        );
+        let token = parse_control_token(&env, ENV_DESTINATION_SVC_BASE, &tokens)
+            .unwrap()
+            .expect("token must be configured");
+        assert_eq!(token.audience(), Some("linkerd-destination"));
+        assert_eq!(token.load().unwrap(), b"dst-token");
+
+        // Rotated tokens are read when they are next used.
+        std::fs::write(&path, "rotated-token").unwrap();
+        assert_eq!(token.load().unwrap(), b"rotated-token");
+
+        env.insert("LINKERD2_PROXY_POLICY_SVC_TOKEN_AUDIENCE", "linkerd-policy");
+        assert!(parse_control_token(&env, ENV_POLICY_SVC_BASE, &tokens).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn socket_options() {
+        let mut env = HashMap::default();
+        let opts = parse_socket_options(&env, INBOUND_ACCEPT_BASE).unwrap();
+        assert_eq!(opts, SocketOptions::default());
+
+        env.insert("LINKERD2_PROXY_INBOUND_ACCEPT_KEEPALIVE_INTERVAL", "10s");
+        env.insert("LINKERD2_PROXY_INBOUND_ACCEPT_KEEPALIVE_COUNT", "3");
+        env.insert("LINKERD2_PROXY_INBOUND_ACCEPT_USER_TIMEOUT", "30s");
+        env.insert("LINKERD2_PROXY_INBOUND_ACCEPT_NODELAY", "false");
+        let opts = parse_socket_options(&env, INBOUND_ACCEPT_BASE).unwrap();
+        assert_eq!(
+            opts,
+            SocketOptions {
+                nodelay: false,
+                keepalive_interval: Some(Duration::from_secs(10)),
+                keepalive_count: Some(3),
+                user_timeout: Some(Duration::from_secs(30)),
+            }
+        );
+
+        // Options are configured independently for each base.
+        let opts = parse_socket_options(&env, OUTBOUND_ACCEPT_BASE).unwrap();
+        assert_eq!(opts, SocketOptions::default());
+
+        env.insert("LINKERD2_PROXY_INBOUND_ACCEPT_KEEPALIVE_COUNT", "-1");
+        assert!(parse_socket_options(&env, INBOUND_ACCEPT_BASE).is_err());
+    }
 }
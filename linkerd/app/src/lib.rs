@@ -7,25 +7,28 @@
 pub mod dst;
 pub mod env;
 pub mod identity;
+pub mod metrics_push;
 pub mod oc_collector;
+pub mod otlp_metrics;
 pub mod policy;
+pub mod quota;
 pub mod tap;
 
 pub use self::metrics::Metrics;
-use futures::{future, Future, FutureExt};
+use futures::{future, Future, FutureExt, TryStreamExt};
 use linkerd_app_admin as admin;
+pub use linkerd_app_core::{self as core, metrics, trace, transport::BindTcp, BUILD_INFO};
 use linkerd_app_core::{
     config::ServerConfig,
     control::ControlAddr,
     dns, drain,
     metrics::prom,
     metrics::FmtMetrics,
-    serve,
-    svc::Param,
-    transport::{addrs::*, listen::Bind},
+    proxy, serve,
+    svc::{NewService, Param},
+    transport::{addrs::*, listen::Bind, AcceptShards, GetTcpInfo, ListenAddr},
     Error, ProxyRuntime,
 };
-pub use linkerd_app_core::{metrics, trace, transport::BindTcp, BUILD_INFO};
 use linkerd_app_gateway as gateway;
 use linkerd_app_inbound::{self as inbound, Inbound};
 use linkerd_app_outbound::{self as outbound, Outbound};
@@ -58,15 +61,22 @@ pub struct Config {
     pub identity: identity::Config,
     pub dst: dst::Config,
     pub policy: policy::Config,
+    pub quota: quota::Config,
     pub admin: admin::Config,
     pub tap: tap::Config,
     pub oc_collector: oc_collector::Config,
+    pub otlp_metrics: otlp_metrics::Config,
+    pub metrics_push: metrics_push::Config,
 
     /// Grace period for graceful shutdowns.
     ///
     /// If the proxy does not shut down gracefully within this timeout, it will
     /// terminate forcefully, closing any remaining connections.
     pub shutdown_grace_period: time::Duration,
+
+    /// The maximum number of idle buffers retained for copying data between
+    /// opaque TCP connections.
+    pub tcp_copy_buffers_max_idle: usize,
 }
 
 pub struct App {
@@ -75,7 +85,10 @@ pub struct App {
     dst: ControlAddr,
     identity: identity::Identity,
     inbound_addr: Local<ServerAddr>,
+    inbound_additional_addrs: Vec<Local<ServerAddr>>,
+    metrics_push: metrics_push::MetricsPush,
     oc_collector: oc_collector::OcCollector,
+    otlp_metrics: otlp_metrics::OtlpMetrics,
     outbound_addr: Local<ServerAddr>,
     start_proxy: Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>>,
     tap: tap::Tap,
@@ -101,12 +114,13 @@ impl Config {
         log_level: trace::Handle,
     ) -> Result<App, Error>
     where
-        BIn: Bind<ServerConfig> + 'static,
+        BIn: Bind<ServerConfig> + Clone + 'static,
+        BIn::Io: GetTcpInfo,
         BIn::Addrs: Param<Remote<ClientAddr>>
             + Param<Local<ServerAddr>>
             + Param<OrigDstAddr>
             + Param<AddrPair>,
-        BOut: Bind<ServerConfig> + 'static,
+        BOut: Bind<ServerConfig> + Clone + 'static,
         BOut::Addrs: Param<Remote<ClientAddr>>
             + Param<Local<ServerAddr>>
             + Param<OrigDstAddr>
@@ -119,16 +133,31 @@ impl Config {
             dns,
             dst,
             policy,
+            quota,
             identity,
             inbound,
             oc_collector,
+            otlp_metrics,
+            metrics_push,
             outbound,
             gateway,
             tap,
+            tcp_copy_buffers_max_idle,
             ..
         } = self;
         debug!("Building app");
-        let (metrics, report) = Metrics::new(admin.metrics_retain_idle);
+        linkerd_app_core::duplex::COPY_BUFFERS.set_max_idle(tcp_copy_buffers_max_idle);
+        if let Some(growth_factor) = admin.metrics_histogram_growth_factor {
+            if linkerd_app_core::metrics::latency::use_sparse_buckets(growth_factor).is_err() {
+                tracing::warn!("Latency histogram buckets were already configured");
+            }
+        }
+        let (metrics, report) = Metrics::with_latency_bounds(
+            admin.metrics_retain_idle,
+            &admin.metrics_latency_buckets,
+            admin.metrics_peers,
+            admin.metrics_socket_health,
+        );
 
         let mut registry = prom::Registry::default();
 
@@ -153,34 +182,78 @@ impl Config {
         };
 
         debug!("Building Destination client");
-        let dst = {
+        let dst_client = {
             let registry = registry.sub_registry_with_prefix("control_destination");
             let metrics = metrics.control.clone();
             let dns = dns.resolver.clone();
-            info_span!("dst")
-                .in_scope(|| dst.build(dns, metrics, registry, identity.receiver().new_client()))
-        }?;
+            info_span!("dst").in_scope(|| {
+                dst.control
+                    .clone()
+                    .build(dns, metrics, registry, identity.receiver().new_client())
+                    .new_service(())
+            })
+        };
+        let dst_token = dst.control.token.clone();
+        let dst = dst.build(dst_client.clone())?;
 
         debug!("Building Policy client");
         let policies = {
-            let registry = registry.sub_registry_with_prefix("control_policy");
-            let dns = dns.resolver.clone();
-            let metrics = metrics.control.clone();
-            info_span!("policy")
-                .in_scope(|| policy.build(dns, metrics, registry, identity.receiver().new_client()))
+            // When the destination and policy controllers are served at the
+            // same address and authenticate with the same token, their
+            // watches are multiplexed over the same connections rather than
+            // each opening their own.
+            let client = if policy.control.addr == dst.addr && policy.control.token == dst_token {
+                debug!("Sharing Destination client connections");
+                dst_client
+            } else {
+                let registry = registry.sub_registry_with_prefix("control_policy");
+                let dns = dns.resolver.clone();
+                let metrics = metrics.control.clone();
+                info_span!("policy").in_scope(|| {
+                    policy
+                        .control
+                        .clone()
+                        .build(dns, metrics, registry, identity.receiver().new_client())
+                        .new_service(())
+                })
+            };
+            policy.build(client)
         }?;
 
+        debug!(config = ?quota, "Building Quota client");
+        let quota = {
+            let registry = registry.sub_registry_with_prefix("control_quota");
+            let identity = identity.receiver().new_client();
+            let dns = dns.resolver.clone();
+            let client_metrics = metrics.control.clone();
+            info_span!("quota").in_scope(|| quota.build(identity, dns, registry, client_metrics))
+        };
+
         debug!(config = ?oc_collector, "Building client");
         let oc_collector = {
             let registry = registry.sub_registry_with_prefix("opencensus");
             let identity = identity.receiver().new_client();
-            let dns = dns.resolver;
+            let dns = dns.resolver.clone();
             let client_metrics = metrics.control.clone();
             let metrics = metrics.opencensus;
             info_span!("opencensus")
                 .in_scope(|| oc_collector.build(identity, dns, metrics, registry, client_metrics))
         }?;
 
+        debug!(config = ?otlp_metrics, "Building OTLP metrics client");
+        let otlp_metrics = {
+            let registry = registry.sub_registry_with_prefix("otlp_metrics");
+            let identity = identity.receiver().new_client();
+            let dns = dns.resolver.clone();
+            let client_metrics = metrics.control.clone();
+            info_span!("otlp_metrics")
+                .in_scope(|| otlp_metrics.build(identity, dns, registry, client_metrics))
+        }?;
+
+        // Inbound connections are accepted from the network, so their socket
+        // health is sampled as they are served.
+        let socket_health = metrics.proxy.socket_health.clone();
+
         let runtime = ProxyRuntime {
             identity: identity.receiver(),
             metrics: metrics.proxy,
@@ -188,7 +261,10 @@ impl Config {
             span_sink: oc_collector.span_sink(),
             drain: drain_rx.clone(),
         };
-        let inbound = Inbound::new(inbound, runtime.clone());
+        let mut inbound = Inbound::new(inbound, runtime.clone());
+        if let Some(quota) = quota {
+            inbound = inbound.with_rate_limit_quota(quota);
+        }
         let outbound = Outbound::new(outbound, runtime);
 
         let inbound_policies = inbound.build_policies(
@@ -215,10 +291,23 @@ impl Config {
 
         // Bind the proxy sockets eagerly (so they're reserved and known) but defer building the
         // stacks until the proxy starts running.
-        let (inbound_addr, inbound_listen) = bind_in
-            .bind(&inbound.config().proxy.server)
-            .expect("Failed to bind inbound listener");
+        let (inbound_addr, mut inbound_listens) =
+            bind_shards(bind_in.clone(), &inbound.config().proxy.server)
+                .expect("Failed to bind inbound listener");
+        let mut inbound_additional_addrs =
+            Vec::with_capacity(inbound.config().additional_listen_addrs.len());
+        for &addr in &inbound.config().additional_listen_addrs {
+            let config = ServerConfig {
+                addr,
+                ..inbound.config().proxy.server.clone()
+            };
+            let (addr, listens) = bind_shards(bind_in.clone(), &config)
+                .expect("Failed to bind additional inbound listener");
+            inbound_additional_addrs.push(addr);
+            inbound_listens.extend(listens);
+        }
         let inbound_metrics = inbound.metrics();
+        let inbound_opaque_ports = inbound.opaque_ports();
         let inbound = inbound.mk(
             inbound_addr,
             inbound_policies.clone(),
@@ -226,15 +315,17 @@ impl Config {
             gateway.into_inner(),
         );
 
-        let (outbound_addr, outbound_listen) = bind_out
-            .bind(&outbound.config().proxy.server)
-            .expect("Failed to bind outbound listener");
+        let (outbound_addr, outbound_listens) =
+            bind_shards(bind_out, &outbound.config().proxy.server)
+                .expect("Failed to bind outbound listener");
         let outbound_metrics = outbound.metrics();
+        let outbound_chaos = outbound.chaos();
         let outbound = outbound.mk(
             registry.sub_registry_with_prefix("outbound"),
             dst.profiles.clone(),
             outbound_policies,
             dst.resolve.clone(),
+            dns.resolver,
         );
 
         // Build a task that initializes and runs the proxy stacks.
@@ -245,30 +336,48 @@ impl Config {
             Box::pin(async move {
                 Self::await_identity(identity_ready).await;
 
-                tokio::spawn(
-                    serve::serve(outbound_listen, outbound, drain_rx.clone().signaled())
-                        .instrument(info_span!("outbound").or_current()),
-                );
+                for listen in outbound_listens {
+                    tokio::spawn(
+                        serve::serve(listen, outbound.clone(), drain_rx.clone().signaled())
+                            .instrument(info_span!("outbound").or_current()),
+                    );
+                }
 
-                tokio::spawn(
-                    serve::serve(inbound_listen, inbound, drain_rx.signaled())
-                        .instrument(info_span!("inbound").or_current()),
-                );
+                for listen in inbound_listens {
+                    let socket_health = socket_health.clone();
+                    let listen =
+                        listen.map_ok(move |(addrs, io)| (addrs, socket_health.inbound(io)));
+                    tokio::spawn(
+                        serve::serve(listen, inbound.clone(), drain_rx.clone().signaled())
+                            .instrument(info_span!("inbound").or_current()),
+                    );
+                }
             })
         };
 
         metrics::process::register(registry.sub_registry_with_prefix("process"));
         registry.register("proxy_build_info", "Proxy build info", BUILD_INFO.metric());
 
+        let report = inbound_metrics
+            .clone()
+            .and_report(outbound_metrics)
+            .and_report(report)
+            // The prom registry reports an "# EOF" at the end of its export, so
+            // it should be emitted last.
+            .and_report(prom::Report::from(registry));
+
+        let otlp_metrics = otlp_metrics.export(report.clone());
+        let metrics_push = metrics_push.build(report.clone(), drain_rx.clone());
+
+        // Logs summaries of suppressed warnings, if enabled.
+        if let Some(summarize) = log_level.summarize_warnings() {
+            tokio::spawn(summarize.instrument(info_span!("log").or_current()));
+        }
+
         let admin = {
             let identity = identity.receiver().server();
-            let metrics = inbound_metrics.clone();
-            let report = inbound_metrics
-                .and_report(outbound_metrics)
-                .and_report(report)
-                // The prom registry reports an "# EOF" at the end of its export, so
-                // it should be emitted last.
-                .and_report(prom::Report::from(registry));
+            let metrics = inbound_metrics;
+            let tap = tap.server();
             info_span!("admin").in_scope(move || {
                 admin.build(
                     bind_admin,
@@ -277,6 +386,9 @@ impl Config {
                     report,
                     metrics,
                     log_level,
+                    tap,
+                    outbound_chaos,
+                    inbound_opaque_ports,
                     drain_rx,
                     shutdown_tx,
                 )
@@ -289,7 +401,10 @@ impl Config {
             drain: drain_tx,
             identity,
             inbound_addr,
+            inbound_additional_addrs,
+            metrics_push,
             oc_collector,
+            otlp_metrics,
             outbound_addr,
             start_proxy,
             tap,
@@ -312,6 +427,33 @@ impl Config {
     }
 }
 
+/// Binds each of a server's accept shards, so that connections may be accepted
+/// by multiple tasks concurrently.
+fn bind_shards<B: Bind<ServerConfig> + Clone>(
+    bind: B,
+    config: &ServerConfig,
+) -> Result<(Local<ServerAddr>, Vec<B::Incoming>), Error> {
+    let (addr, listen) = bind.clone().bind(config)?;
+    let AcceptShards(shards) = config.accept_shards;
+    let mut listens = Vec::with_capacity(shards.get());
+    listens.push(listen);
+
+    // If the configuration did not specify a port, the remaining shards must
+    // bind the port chosen for the first shard.
+    let Local(ServerAddr(bound)) = addr;
+    let config = ServerConfig {
+        addr: ListenAddr(bound),
+        ..config.clone()
+    };
+    for _ in 1..shards.get() {
+        let (_, listen) = bind.clone().bind(&config)?;
+        listens.push(listen);
+    }
+    debug!(%bound, shards, "Bound listener");
+
+    Ok((addr, listens))
+}
+
 impl App {
     pub fn admin_addr(&self) -> Local<ServerAddr> {
         self.admin.listen_addr
@@ -321,6 +463,10 @@ impl App {
         self.inbound_addr
     }
 
+    pub fn inbound_additional_addrs(&self) -> &[Local<ServerAddr>] {
+        &self.inbound_additional_addrs
+    }
+
     pub fn outbound_addr(&self) -> Local<ServerAddr> {
         self.outbound_addr
     }
@@ -351,12 +497,28 @@ impl App {
         }
     }
 
+    pub fn otlp_metrics_addr(&self) -> Option<&ControlAddr> {
+        match self.otlp_metrics {
+            otlp_metrics::OtlpMetrics::Disabled => None,
+            otlp_metrics::OtlpMetrics::Enabled(ref otlp) => Some(&otlp.addr),
+        }
+    }
+
+    pub fn metrics_push_uri(&self) -> Option<&proxy::http::uri::Uri> {
+        match self.metrics_push {
+            metrics_push::MetricsPush::Disabled => None,
+            metrics_push::MetricsPush::Enabled(ref push) => Some(&push.uri),
+        }
+    }
+
     pub fn spawn(self) -> drain::Signal {
         let App {
             admin,
             drain,
             identity,
+            metrics_push,
             oc_collector,
+            otlp_metrics,
             start_proxy,
             tap,
             ..
@@ -425,6 +587,20 @@ impl App {
                             tokio::spawn(oc.task.instrument(info_span!("opencensus").or_current()));
                         }
 
+                        if let metrics_push::MetricsPush::Enabled(push) = metrics_push {
+                            tokio::spawn(
+                                push.task
+                                    .instrument(info_span!("metrics_push").or_current()),
+                            );
+                        }
+
+                        if let otlp_metrics::OtlpMetrics::Enabled(otlp) = otlp_metrics {
+                            tokio::spawn(
+                                otlp.task
+                                    .instrument(info_span!("otlp_metrics").or_current()),
+                            );
+                        }
+
                         // we don't care if the admin shutdown channel is
                         // dropped or actually triggered.
                         let _ = admin_shutdown_rx.await;
@@ -0,0 +1,57 @@
+use linkerd_app_core::{drain, metrics::FmtMetrics, proxy::http};
+pub use linkerd_metrics_push::Format;
+use linkerd_metrics_push::Pusher;
+use std::{future::Future, pin::Pin, time::Duration};
+use tracing::Instrument;
+
+#[derive(Clone, Debug)]
+pub enum Config {
+    Disabled,
+    Enabled(Box<EnabledConfig>),
+}
+
+#[derive(Clone, Debug)]
+pub struct EnabledConfig {
+    pub uri: http::uri::Uri,
+    pub format: Format,
+    pub interval: Duration,
+    pub labels: Vec<(String, String)>,
+}
+
+pub type Task = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+pub enum MetricsPush {
+    Disabled,
+    Enabled(Box<EnabledPush>),
+}
+
+pub struct EnabledPush {
+    pub uri: http::uri::Uri,
+    pub task: Task,
+}
+
+impl Config {
+    pub fn build<R>(self, report: R, drain: drain::Watch) -> MetricsPush
+    where
+        R: FmtMetrics + Send + Sync + 'static,
+    {
+        match self {
+            Config::Disabled => MetricsPush::Disabled,
+            Config::Enabled(inner) => {
+                let EnabledConfig {
+                    uri,
+                    format,
+                    interval,
+                    labels,
+                } = *inner;
+                let pusher = Pusher::new(uri.clone(), format, labels);
+                let task = Box::pin(
+                    pusher
+                        .run(report, interval, drain)
+                        .instrument(tracing::debug_span!("metrics_push", %uri).or_current()),
+                );
+                MetricsPush::Enabled(Box::new(EnabledPush { uri, task }))
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use linkerd_app_core::{
+    control, dns, identity,
+    metrics::{prom, ControlHttp as HttpMetrics, FmtMetrics},
+    proxy::http,
+    svc::{self, NewService},
+    Error,
+};
+pub use linkerd_otlp_metrics::Temporality;
+use linkerd_otlp_metrics::{self as otlp, proto};
+use std::{collections::HashMap, future::Future, pin::Pin, time::Duration};
+use tracing::Instrument;
+
+#[derive(Clone, Debug)]
+pub enum Config {
+    Disabled,
+    Enabled(Box<EnabledConfig>),
+}
+
+#[derive(Clone, Debug)]
+pub struct EnabledConfig {
+    pub control: control::Config,
+    pub interval: Duration,
+    pub temporality: Temporality,
+    pub attributes: HashMap<String, String>,
+    pub hostname: Option<String>,
+}
+
+pub type Task = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+type Client =
+    svc::BoxCloneSyncService<http::Request<tonic::body::BoxBody>, http::Response<control::RspBody>>;
+
+/// An exporter whose client has been built but which has not yet been given
+/// the metrics it exports.
+///
+/// The client's own metrics are registered in the proxy's registry, so it
+/// must be built before the report is complete.
+pub enum NewExporter {
+    Disabled,
+    Enabled(Box<NewEnabledExporter>),
+}
+
+pub struct NewEnabledExporter {
+    addr: control::ControlAddr,
+    client: Client,
+    resource: proto::resource::v1::Resource,
+    interval: Duration,
+    temporality: Temporality,
+}
+
+pub enum OtlpMetrics {
+    Disabled,
+    Enabled(Box<EnabledExporter>),
+}
+
+pub struct EnabledExporter {
+    pub addr: control::ControlAddr,
+    pub task: Task,
+}
+
+impl Config {
+    const SERVICE_NAME: &'static str = "linkerd-proxy";
+
+    pub fn build(
+        self,
+        identity: identity::NewClient,
+        dns: dns::Resolver,
+        registry: &mut prom::Registry,
+        client_metrics: HttpMetrics,
+    ) -> Result<NewExporter, Error> {
+        match self {
+            Config::Disabled => Ok(NewExporter::Disabled),
+            Config::Enabled(inner) => {
+                let addr = inner.control.addr.clone();
+                let client = inner
+                    .control
+                    .build(dns, client_metrics, registry, identity)
+                    .new_service(());
+
+                let resource = {
+                    use self::proto::common::v1::{any_value, AnyValue, KeyValue};
+
+                    let kv = |key: &str, value: any_value::Value| KeyValue {
+                        key: key.to_string(),
+                        value: Some(AnyValue { value: Some(value) }),
+                    };
+                    let mut attributes = vec![
+                        kv(
+                            "service.name",
+                            any_value::Value::StringValue(Self::SERVICE_NAME.to_string()),
+                        ),
+                        kv(
+                            "process.pid",
+                            any_value::Value::IntValue(std::process::id().into()),
+                        ),
+                    ];
+                    if let Some(hostname) = inner.hostname {
+                        attributes.push(kv("host.name", any_value::Value::StringValue(hostname)));
+                    }
+                    // Configured attributes may override the defaults.
+                    let mut configured = inner.attributes.into_iter().collect::<Vec<_>>();
+                    configured.sort();
+                    for (key, value) in configured {
+                        attributes.retain(|kv| kv.key != key);
+                        attributes.push(kv(&key, any_value::Value::StringValue(value)));
+                    }
+                    proto::resource::v1::Resource {
+                        attributes,
+                        dropped_attributes_count: 0,
+                    }
+                };
+
+                Ok(NewExporter::Enabled(Box::new(NewEnabledExporter {
+                    addr,
+                    client,
+                    resource,
+                    interval: inner.interval,
+                    temporality: inner.temporality,
+                })))
+            }
+        }
+    }
+}
+
+impl NewExporter {
+    /// Builds a task that exports the metrics in `report`.
+    pub fn export<R>(self, report: R) -> OtlpMetrics
+    where
+        R: FmtMetrics + Send + Sync + 'static,
+    {
+        match self {
+            NewExporter::Disabled => OtlpMetrics::Disabled,
+            NewExporter::Enabled(inner) => {
+                let NewEnabledExporter {
+                    addr,
+                    client,
+                    resource,
+                    interval,
+                    temporality,
+                } = *inner;
+                let task = Box::pin(
+                    otlp::export_metrics(client, resource, report, interval, temporality)
+                        .instrument(
+                            tracing::debug_span!("otlp_metrics", peer.addr = %addr).or_current(),
+                        ),
+                );
+                OtlpMetrics::Enabled(Box::new(EnabledExporter { addr, task }))
+            }
+        }
+    }
+}
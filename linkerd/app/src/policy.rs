@@ -1,10 +1,8 @@
 use linkerd_app_core::{
-    control, dns,
+    control,
     exp_backoff::ExponentialBackoff,
-    identity,
-    metrics::{self, prom},
     proxy::http,
-    svc::{self, NewService, ServiceExt},
+    svc::{self, ServiceExt},
     Error,
 };
 use linkerd_tonic_stream::ReceiveLimits;
@@ -37,12 +35,11 @@ pub struct Policy<S> {
 // === impl Config ===
 
 impl Config {
+    /// Builds clients that send their requests over the given control plane
+    /// client, so that their watches share its connections.
     pub fn build(
         self,
-        dns: dns::Resolver,
-        metrics: metrics::ControlHttp,
-        registry: &mut prom::Registry,
-        identity: identity::NewClient,
+        client: control::Client,
     ) -> Result<
         Policy<
             impl svc::Service<
@@ -57,11 +54,7 @@ impl Config {
         let addr = self.control.addr.clone();
         let workload = self.workload.into();
         let backoff = self.control.connect.backoff;
-        let client = self
-            .control
-            .build(dns, metrics, registry, identity)
-            .new_service(())
-            .map_err(Error::from);
+        let client = client.map_err(Error::from);
 
         Ok(Policy {
             addr,
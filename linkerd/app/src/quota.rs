@@ -0,0 +1,47 @@
+use linkerd_app_core::{
+    control, dns, identity,
+    metrics::{prom, ControlHttp as HttpMetrics},
+    svc::NewService,
+};
+use linkerd_app_inbound::policy::Quota;
+use std::time::Duration;
+
+/// Configures the quota service from which inbound route rate limits are
+/// leased, so that they are shared by all replicas of a server.
+#[derive(Clone, Debug)]
+pub enum Config {
+    Disabled,
+    Enabled(Box<EnabledConfig>),
+}
+
+#[derive(Clone, Debug)]
+pub struct EnabledConfig {
+    pub control: control::Config,
+
+    /// How long to wait for the quota service before rate limits are
+    /// enforced locally.
+    pub timeout: Duration,
+}
+
+// === impl Config ===
+
+impl Config {
+    pub fn build(
+        self,
+        identity: identity::NewClient,
+        dns: dns::Resolver,
+        registry: &mut prom::Registry,
+        client_metrics: HttpMetrics,
+    ) -> Option<Quota> {
+        match self {
+            Config::Disabled => None,
+            Config::Enabled(inner) => {
+                let client = inner
+                    .control
+                    .build(dns, client_metrics, registry, identity)
+                    .new_service(());
+                Some(Quota::new(client, inner.timeout))
+            }
+        }
+    }
+}
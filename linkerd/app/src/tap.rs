@@ -16,18 +16,21 @@ use tower::util::{service_fn, ServiceExt};
 pub enum Config {
     Disabled,
     Enabled {
-        config: ServerConfig,
+        config: Box<ServerConfig>,
         permitted_client_ids: HashSet<tls::server::ClientId>,
+        body_capture: tap::BodyCapture,
     },
 }
 
 pub enum Tap {
     Disabled {
         registry: tap::Registry,
+        server: tap::Server,
     },
     Enabled {
         listen_addr: Local<ServerAddr>,
         registry: tap::Registry,
+        server: tap::Server,
         serve: Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>>,
     },
 }
@@ -49,18 +52,19 @@ impl Config {
         B::Addrs: Param<Remote<ClientAddr>>,
         B::Addrs: Param<AddrPair>,
     {
-        let (registry, server) = tap::new();
         match self {
             Config::Disabled => {
-                drop(server);
-                Ok(Tap::Disabled { registry })
+                let (registry, server) = tap::new(tap::BodyCapture::default());
+                Ok(Tap::Disabled { registry, server })
             }
             Config::Enabled {
                 config,
                 permitted_client_ids,
+                body_capture,
             } => {
+                let (registry, server) = tap::new(body_capture);
                 let (listen_addr, listen) = bind.bind(&config)?;
-                let accept = svc::stack(server)
+                let accept = svc::stack(server.clone())
                     .push(svc::layer::mk(move |service| {
                         tap::AcceptPermittedClients::new(
                             permitted_client_ids.clone().into(),
@@ -90,6 +94,7 @@ impl Config {
                 Ok(Tap::Enabled {
                     listen_addr,
                     registry,
+                    server,
                     serve,
                 })
             }
@@ -100,10 +105,19 @@ impl Config {
 impl Tap {
     pub fn registry(&self) -> tap::Registry {
         match self {
-            Tap::Disabled { ref registry } => registry.clone(),
+            Tap::Disabled { ref registry, .. } => registry.clone(),
             Tap::Enabled { ref registry, .. } => registry.clone(),
         }
     }
+
+    /// Returns a tap server that may be used to observe requests locally,
+    /// even when the tap server is not exposed to remote clients.
+    pub fn server(&self) -> tap::Server {
+        match self {
+            Tap::Disabled { ref server, .. } => server.clone(),
+            Tap::Enabled { ref server, .. } => server.clone(),
+        }
+    }
 }
 
 // === TlsParams ===
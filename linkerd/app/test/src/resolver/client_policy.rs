@@ -73,6 +73,13 @@ impl ClientPolicies {
                     meta: Meta::new_default("default"),
                     filters: Arc::new([]),
                     failure_policy: Default::default(),
+                    cache: None,
+                    request_decompression: None,
+                    request_body_limit: None,
+                    streaming: None,
+                    sticky: None,
+                    mirror: None,
+                    success_objective: None,
                     request_timeout: None,
                     distribution: RouteDistribution::FirstAvailable(Arc::new([RouteBackend {
                         filters: Arc::new([]),
@@ -98,6 +105,13 @@ impl ClientPolicies {
                     meta: Meta::new_default("default"),
                     filters: Arc::new([]),
                     failure_policy: Default::default(),
+                    cache: None,
+                    request_decompression: None,
+                    request_body_limit: None,
+                    streaming: None,
+                    sticky: None,
+                    mirror: None,
+                    success_objective: None,
                     request_timeout: None,
                     distribution: RouteDistribution::FirstAvailable(Arc::new([RouteBackend {
                         filters: Arc::new([]),
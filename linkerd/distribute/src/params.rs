@@ -99,10 +99,15 @@ impl<K> Distribution<K> {
 // === impl WeightedKeys ===
 
 impl<K> WeightedKeys<K> {
-    pub(crate) fn keys(&self) -> &[K] {
+    pub fn keys(&self) -> &[K] {
         &self.keys
     }
 
+    /// Returns the weight of each key, in the same order as [`Self::keys`].
+    pub fn weights(&self) -> &[u32] {
+        &self.weights
+    }
+
     pub(crate) fn index(&self) -> WeightedIndex<u32> {
         WeightedIndex::new(self.weights.iter().copied()).expect("distribution must be valid")
     }
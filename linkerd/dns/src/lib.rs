@@ -3,22 +3,42 @@
 
 use linkerd_dns_name::NameRef;
 pub use linkerd_dns_name::{InvalidName, Name, Suffix};
-use std::{fmt, net};
+use std::{fmt, net, sync::Arc};
 use thiserror::Error;
 use tokio::time::{self, Instant};
 use tracing::{debug, trace};
 pub use trust_dns_resolver::config::ResolverOpts;
 use trust_dns_resolver::{
-    config::ResolverConfig, error, proto::rr::rdata, system_conf, AsyncResolver, TokioAsyncResolver,
+    config::{NameServerConfigGroup, ResolverConfig},
+    error,
+    proto::rr::rdata,
+    system_conf, AsyncResolver, TokioAsyncResolver,
 };
 
 #[derive(Clone)]
 pub struct Resolver {
     dns: TokioAsyncResolver,
+    /// Resolvers for names with particular suffixes, ordered from the most to
+    /// the least specific suffix.
+    routes: Arc<[(Suffix, TokioAsyncResolver)]>,
+}
+
+/// Routes lookups for names with a suffix to a specific name server, e.g. so
+/// that `.consul` names are resolved by a local Consul agent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuffixRoute {
+    pub suffix: Suffix,
+    pub server: net::SocketAddr,
 }
 
 pub trait ConfigureResolver {
     fn configure_resolver(&self, _: &mut ResolverOpts);
+
+    /// Returns the name servers that resolve names with particular suffixes.
+    /// Names that match no route are resolved with the system configuration.
+    fn suffix_routes(&self) -> Vec<SuffixRoute> {
+        Vec::new()
+    }
 }
 
 #[derive(Debug, Clone, Error)]
@@ -60,16 +80,54 @@ impl Resolver {
         c.configure_resolver(&mut opts);
         trace!("DNS config: {:?}", &config);
         trace!("DNS opts: {:?}", &opts);
-        Ok(Self::new(config, opts))
+        Ok(Self::new(config, opts).with_suffix_routes(c.suffix_routes(), opts))
+    }
+
+    pub fn new(config: ResolverConfig, opts: ResolverOpts) -> Self {
+        let dns = Self::mk_resolver(config, opts);
+        Resolver {
+            dns,
+            routes: Arc::new([]),
+        }
     }
 
-    pub fn new(config: ResolverConfig, mut opts: ResolverOpts) -> Self {
+    /// Resolves names that match each route's suffix with the route's name
+    /// server instead of the system configuration. When several suffixes
+    /// match a name, the most specific one is used.
+    pub fn with_suffix_routes(
+        self,
+        routes: impl IntoIterator<Item = SuffixRoute>,
+        opts: ResolverOpts,
+    ) -> Self {
+        let mut routes = routes
+            .into_iter()
+            .map(|SuffixRoute { suffix, server }| {
+                debug!(%suffix, %server, "Routing DNS lookups");
+                let servers =
+                    NameServerConfigGroup::from_ips_clear(&[server.ip()], server.port(), true);
+                let config = ResolverConfig::from_parts(None, vec![], servers);
+                (suffix, Self::mk_resolver(config, opts))
+            })
+            .collect::<Vec<_>>();
+        routes.sort_by_key(|(suffix, _)| std::cmp::Reverse(suffix_len(suffix)));
+        Resolver {
+            routes: routes.into(),
+            ..self
+        }
+    }
+
+    fn mk_resolver(config: ResolverConfig, mut opts: ResolverOpts) -> TokioAsyncResolver {
         // Disable Trust-DNS's caching.
         opts.cache_size = 0;
         // This function is synchronous, but needs to be called within the Tokio
         // 0.2 runtime context, since it gets a handle.
-        let dns = AsyncResolver::tokio(config, opts).expect("system DNS config must be valid");
-        Resolver { dns }
+        AsyncResolver::tokio(config, opts).expect("system DNS config must be valid")
+    }
+
+    /// Returns the resolver for the name's most specific suffix route, or the
+    /// system resolver if no route matches.
+    fn dns_for(&self, name: NameRef<'_>) -> &TokioAsyncResolver {
+        route(&self.routes, name).unwrap_or(&self.dns)
     }
 
     /// Resolves a name to a set of addresses, preferring SRV records to normal A/AAAA
@@ -103,7 +161,7 @@ impl Resolver {
         name: NameRef<'_>,
     ) -> Result<(Vec<net::IpAddr>, time::Sleep), ARecordError> {
         debug!(%name, "Resolving an A/AAAA record");
-        let lookup = self.dns.lookup_ip(name.as_str()).await?;
+        let lookup = self.dns_for(name).lookup_ip(name.as_str()).await?;
         let valid_until = Instant::from_std(lookup.valid_until());
         let ips = lookup.iter().collect::<Vec<_>>();
         Ok((ips, time::sleep_until(valid_until)))
@@ -114,7 +172,7 @@ impl Resolver {
         name: NameRef<'_>,
     ) -> Result<(Vec<net::SocketAddr>, time::Sleep), SrvRecordError> {
         debug!(%name, "Resolving a SRV record");
-        let srv = self.dns.srv_lookup(name.as_str()).await?;
+        let srv = self.dns_for(name).srv_lookup(name.as_str()).await?;
 
         let valid_until = Instant::from_std(srv.as_lookup().valid_until());
         let addrs = srv
@@ -145,12 +203,34 @@ impl Resolver {
     }
 }
 
+/// Returns the value of the first route whose suffix contains the name.
+/// Routes must be ordered from the most to the least specific suffix.
+fn route<'r, T>(routes: &'r [(Suffix, T)], name: NameRef<'_>) -> Option<&'r T> {
+    if routes.is_empty() {
+        return None;
+    }
+    let name = name.to_owned();
+    routes
+        .iter()
+        .find(|(suffix, _)| suffix.contains(&name))
+        .map(|(_, value)| value)
+}
+
+fn suffix_len(suffix: &Suffix) -> usize {
+    match suffix {
+        Suffix::Root => 0,
+        Suffix::Name(name) => name.without_trailing_dot().len(),
+    }
+}
+
 /// Note: `AsyncResolver` does not implement `Debug`, so we must manually
 ///       implement this.
 impl fmt::Debug for Resolver {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffixes = self.routes.iter().map(|(s, _)| s).collect::<Vec<_>>();
         f.debug_struct("Resolver")
             .field("resolver", &"...")
+            .field("suffix_routes", &suffixes)
             .finish()
     }
 }
@@ -185,7 +265,7 @@ impl ResolveError {
 
 #[cfg(test)]
 mod tests {
-    use super::{Name, Suffix};
+    use super::{route, suffix_len, Name, Suffix};
     use std::str::FromStr;
 
     #[test]
@@ -289,6 +369,31 @@ mod tests {
 
         assert!(Suffix::from_str("").is_err(), "suffix must not be empty");
     }
+
+    #[test]
+    fn suffix_routes() {
+        let mut routes = ["consul", "service.dc1.consul", "internal.", "."]
+            .iter()
+            .map(|s| (Suffix::from_str(s).unwrap(), *s))
+            .collect::<Vec<_>>();
+        routes.sort_by_key(|(s, _)| std::cmp::Reverse(suffix_len(s)));
+
+        for (name, expected) in &[
+            ("web.service.consul", "consul"),
+            ("web.service.dc1.consul", "service.dc1.consul"),
+            ("web.service.dc1.consul.", "service.dc1.consul"),
+            ("db.internal", "internal."),
+            ("notconsul", "."),
+            ("example.com", "."),
+        ] {
+            let n = Name::from_str(name).unwrap();
+            assert_eq!(route(&routes, n.as_ref()), Some(expected), "{name}");
+        }
+
+        routes.pop();
+        let n = Name::from_str("example.com").unwrap();
+        assert_eq!(route(&routes, n.as_ref()), None);
+    }
 }
 
 #[cfg(fuzzing)]
@@ -11,17 +11,55 @@
 
 use bytes::{Buf, BufMut};
 use futures::ready;
-use linkerd_io::{self as io, AsyncRead, AsyncWrite};
+use linkerd_io::{self as io, AsyncRead, AsyncWrite, BufPool, PooledBuf};
 use pin_project::pin_project;
 use std::task::{Context, Poll};
-use std::{future::Future, pin::Pin};
-use tracing::{error, trace};
+use std::{future::Future, pin::Pin, time::Duration};
+use tokio::time::{self, Sleep};
+use tracing::{debug, error, trace};
+
+/// Buffers used to copy data between sockets.
+///
+/// Each forwarded connection holds two buffers, one for each direction.
+/// By default, up to 1024 idle buffers (8MiB) are retained for reuse; the
+/// limit may be changed with [`BufPool::set_max_idle`].
+pub static COPY_BUFFERS: BufPool = BufPool::new(8 * 1024, 1024);
+
+/// Configures how data is forwarded between sockets.
+///
+/// Protocols differ in how they use half-closed connections: some clients
+/// close their write side after sending a request and then await a response,
+/// while some servers never close idle connections. The defaults forward
+/// half-closes and never time out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Config {
+    /// Whether a peer's half-close is propagated to the other peer. When
+    /// disabled, the connection is closed as soon as either peer closes its
+    /// write side.
+    pub half_close: bool,
+
+    /// Limits how long the connection may remain half-closed before it is
+    /// closed.
+    pub linger: Option<Duration>,
+
+    /// Closes the connection when no data has been read from either peer for
+    /// this long.
+    pub idle_timeout: Option<Duration>,
+}
 
 /// A future piping data bi-directionally to In and Out.
 #[pin_project]
 pub struct Duplex<In, Out> {
     half_in: HalfDuplex<In>,
     half_out: HalfDuplex<Out>,
+    config: Config,
+    // Reset whenever data is read, if an idle timeout is configured.
+    #[pin]
+    idle: Option<Sleep>,
+    // Set when the connection becomes half-closed, if a linger timeout is
+    // configured.
+    #[pin]
+    linger: Option<Sleep>,
 }
 
 #[pin_project]
@@ -29,6 +67,8 @@ struct HalfDuplex<T> {
     // None means socket met eof, and bytes have been drained into other half.
     buf: Option<CopyBuf>,
     is_shutdown: bool,
+    // Set when data is read, so that idle connections may be detected.
+    is_active: bool,
     #[pin]
     io: T,
     direction: &'static str,
@@ -43,7 +83,7 @@ struct CopyBuf {
     // In linkerd-tcp, a shared buffer is used to start, and an allocation is
     // only made if NotReady is found trying to flush the buffer. We could
     // consider making the same optimization here.
-    buf: Box<[u8]>,
+    buf: PooledBuf,
     read_pos: usize,
     write_pos: usize,
 }
@@ -60,15 +100,34 @@ enum Drained {
     All(usize),
 }
 
+// === impl Config ===
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            half_close: true,
+            linger: None,
+            idle_timeout: None,
+        }
+    }
+}
+
 impl<In, Out> Duplex<In, Out>
 where
     In: AsyncRead + AsyncWrite + Unpin,
     Out: AsyncRead + AsyncWrite + Unpin,
 {
     pub fn new(in_io: In, out_io: Out) -> Self {
+        Self::with_config(Config::default(), in_io, out_io)
+    }
+
+    pub fn with_config(config: Config, in_io: In, out_io: Out) -> Self {
         Duplex {
             half_in: HalfDuplex::new(in_io, "client->server"),
             half_out: HalfDuplex::new(out_io, "server->client"),
+            idle: config.idle_timeout.map(time::sleep),
+            linger: None,
+            config,
         }
     }
 }
@@ -81,7 +140,7 @@ where
     type Output = io::Result<()>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
-        let this = self.project();
+        let mut this = self.project();
         // This purposefully ignores the Async part, since we don't want to
         // return early if the first half isn't ready, but the other half
         // could make progress.
@@ -89,10 +148,44 @@ where
         let _ = this.half_in.copy_into(this.half_out, cx)?;
         let _ = this.half_out.copy_into(this.half_in, cx)?;
         if this.half_in.is_done() && this.half_out.is_done() {
-            Poll::Ready(Ok(()))
-        } else {
-            Poll::Pending
+            return Poll::Ready(Ok(()));
+        }
+
+        if this.half_in.is_done() || this.half_out.is_done() {
+            // One peer has closed its write side. Unless half-closes are
+            // propagated, the connection is closed by dropping both sockets.
+            if !this.config.half_close {
+                debug!("Closing half-closed connection");
+                return Poll::Ready(Ok(()));
+            }
+            if let Some(linger) = this.config.linger {
+                if this.linger.is_none() {
+                    this.linger.set(Some(time::sleep(linger)));
+                }
+                if let Some(sleep) = this.linger.as_mut().as_pin_mut() {
+                    if sleep.poll(cx).is_ready() {
+                        debug!(?linger, "Closing connection after linger timeout");
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+            }
         }
+
+        if let Some(mut sleep) = this.idle.as_mut().as_pin_mut() {
+            let timeout = this.config.idle_timeout.unwrap_or_default();
+            if this.half_in.take_active() | this.half_out.take_active() {
+                sleep.as_mut().reset(time::Instant::now() + timeout);
+            }
+            if sleep.poll(cx).is_ready() {
+                debug!(?timeout, "Closing idle connection");
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "connection idle timeout",
+                )));
+            }
+        }
+
+        Poll::Pending
     }
 }
 
@@ -104,6 +197,7 @@ where
         Self {
             buf: Some(CopyBuf::new()),
             is_shutdown: false,
+            is_active: false,
             io,
             direction,
             flushing: false,
@@ -224,6 +318,7 @@ where
 
             // If data was read, return the number of bytes read.
             if sz > 0 {
+                self.is_active = true;
                 return Poll::Ready(Ok(Buffered::Read(sz)));
             }
         }
@@ -283,6 +378,11 @@ where
     fn is_done(&self) -> bool {
         self.is_shutdown
     }
+
+    /// Returns whether data has been read since the last call.
+    fn take_active(&mut self) -> bool {
+        std::mem::take(&mut self.is_active)
+    }
 }
 
 fn write_zero() -> io::Error {
@@ -292,7 +392,7 @@ fn write_zero() -> io::Error {
 impl CopyBuf {
     fn new() -> Self {
         CopyBuf {
-            buf: Box::new([0; 8 * 1024]),
+            buf: COPY_BUFFERS.get(),
             read_pos: 0,
             write_pos: 0,
         }
@@ -342,3 +442,70 @@ unsafe impl BufMut for CopyBuf {
         self.write_pos += cnt;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn closes_half_closed_connections() {
+        let (mut client, proxy_in) = io::duplex(64);
+        let (proxy_out, mut server) = io::duplex(64);
+        let config = Config {
+            half_close: false,
+            ..Config::default()
+        };
+        let proxy = tokio::spawn(Duplex::with_config(config, proxy_in, proxy_out));
+
+        client.write_all(b"hello").await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut buf = Vec::new();
+        server.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hello");
+
+        // The server's response is not forwarded to the half-closed client.
+        proxy.await.unwrap().unwrap();
+        assert!(server.write_all(b"world").await.is_err());
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn lingers_after_half_close() {
+        let (mut client, proxy_in) = io::duplex(64);
+        let (proxy_out, _server) = io::duplex(64);
+        let config = Config {
+            linger: Some(Duration::from_secs(5)),
+            ..Config::default()
+        };
+        let proxy = tokio::spawn(Duplex::with_config(config, proxy_in, proxy_out));
+
+        client.shutdown().await.unwrap();
+        time::sleep(Duration::from_secs(4)).await;
+        assert!(!proxy.is_finished());
+        time::sleep(Duration::from_secs(2)).await;
+        proxy.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn closes_idle_connections() {
+        let (mut client, proxy_in) = io::duplex(64);
+        let (proxy_out, mut server) = io::duplex(64);
+        let config = Config {
+            idle_timeout: Some(Duration::from_secs(10)),
+            ..Config::default()
+        };
+        let proxy = tokio::spawn(Duplex::with_config(config, proxy_in, proxy_out));
+
+        time::sleep(Duration::from_secs(6)).await;
+        client.write_all(b"hello").await.unwrap();
+        let mut buf = [0; 5];
+        server.read_exact(&mut buf).await.unwrap();
+
+        // Activity resets the idle timeout.
+        time::sleep(Duration::from_secs(6)).await;
+        assert!(!proxy.is_finished());
+        time::sleep(Duration::from_secs(5)).await;
+        let err = proxy.await.unwrap().expect_err("connection must time out");
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -31,6 +31,11 @@ pub struct AccessLogContext<S> {
     client_id: Option<identity::Id>,
 }
 
+/// Path parameters captured by the route that handled a request, set as a
+/// response extension so that they may be included in the access log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathParams(pub String);
+
 struct ResponseFutureInner {
     span: Span,
     start: Instant,
@@ -137,6 +142,7 @@ where
             response_bytes = field::Empty,
             total_ns = field::Empty,
             processing_ns = field::Empty,
+            path_params = field::Empty,
             user_agent = get_header(http::header::USER_AGENT),
             host = get_header(http::header::HOST),
         );
@@ -204,6 +210,9 @@ where
         span.record("status", response.status().as_u16());
         span.record("total_ns", &field::display(total_ns));
         span.record("processing_ns", &field::display(processing_ns));
+        if let Some(PathParams(params)) = response.extensions().get::<PathParams>() {
+            span.record("path_params", params.as_str());
+        }
 
         Poll::Ready(Ok(response))
     }
@@ -41,6 +41,12 @@ impl BoxBody {
     }
 }
 
+impl From<bytes::Bytes> for BoxBody {
+    fn from(bytes: bytes::Bytes) -> Self {
+        Self::new(http_body::Full::new(bytes))
+    }
+}
+
 impl Body for BoxBody {
     type Data = Data;
     type Error = Error;
@@ -4,15 +4,16 @@ mod service;
 pub use self::service::{NewHttpMetrics, ResponseBody};
 use super::Report;
 use linkerd_http_classify::ClassifyResponse;
-use linkerd_metrics::{latency, Counter, FmtMetrics, Histogram, LastUpdate, NewMetrics};
+use linkerd_metrics::{latency, Counter, FmtMetrics, LastUpdate, NewMetrics};
 use linkerd_stack::{self as svc, layer};
+use parking_lot::Mutex;
 use std::{collections::HashMap, fmt::Debug, hash::Hash};
 use tokio::time::{Duration, Instant};
 
 type Registry<T, C> = super::Registry<T, Metrics<C>>;
 
 #[derive(Debug)]
-pub struct Requests<T, C>(Registry<T, C>)
+pub struct Requests<T, C>(Registry<T, C>, latency::Buckets)
 where
     T: Hash + Eq,
     C: Hash + Eq;
@@ -24,6 +25,7 @@ where
 {
     last_update: Instant,
     total: Counter,
+    latency_buckets: latency::Buckets,
     by_status: HashMap<Option<http::StatusCode>, StatusMetrics<C>>,
 }
 
@@ -32,7 +34,7 @@ pub struct StatusMetrics<C>
 where
     C: Hash + Eq,
 {
-    latency: Histogram<latency::Ms>,
+    latency: latency::LatencyHistogram,
     by_class: HashMap<C, ClassMetrics>,
 }
 
@@ -45,11 +47,17 @@ pub struct ClassMetrics {
 
 impl<T: Hash + Eq, C: Hash + Eq> Default for Requests<T, C> {
     fn default() -> Self {
-        Requests(Registry::default())
+        Self::new(latency::Buckets::default())
     }
 }
 
 impl<T: Hash + Eq, C: Hash + Eq> Requests<T, C> {
+    /// Returns a new set of request metrics whose response latencies are
+    /// recorded in histograms with the given buckets.
+    pub fn new(latency_buckets: latency::Buckets) -> Self {
+        Requests(Registry::default(), latency_buckets)
+    }
+
     pub fn into_report(self, retain_idle: Duration) -> Report<T, Metrics<C>>
     where
         Report<T, Metrics<C>>: FmtMetrics,
@@ -63,15 +71,16 @@ impl<T: Hash + Eq, C: Hash + Eq> Requests<T, C> {
     where
         L: ClassifyResponse<Class = C> + Send + Sync + 'static,
         N: svc::NewService<Tgt>,
+        C: 'static,
     {
         let reg = self.0.clone();
-        NewMetrics::layer(reg)
+        NewMetrics::layer_with(reg, Metrics::new_fn(self.1))
     }
 }
 
 impl<T: Hash + Eq, C: Hash + Eq> Clone for Requests<T, C> {
     fn clone(&self) -> Self {
-        Requests(self.0.clone())
+        Requests(self.0.clone(), self.1)
     }
 }
 
@@ -79,12 +88,26 @@ impl<T: Hash + Eq, C: Hash + Eq> Clone for Requests<T, C> {
 
 impl<C: Hash + Eq> Default for Metrics<C> {
     fn default() -> Self {
+        Self::new(latency::Buckets::default())
+    }
+}
+
+impl<C: Hash + Eq> Metrics<C> {
+    fn new(latency_buckets: latency::Buckets) -> Self {
         Self {
             last_update: Instant::now(),
             total: Counter::default(),
+            latency_buckets,
             by_status: HashMap::default(),
         }
     }
+
+    fn new_fn(latency_buckets: latency::Buckets) -> impl Fn() -> Mutex<Self> + Send + Sync
+    where
+        C: 'static,
+    {
+        move || Mutex::new(Self::new(latency_buckets))
+    }
 }
 
 #[cfg(feature = "test-util")]
@@ -104,13 +127,13 @@ impl<C: Hash + Eq> LastUpdate for Metrics<C> {
     }
 }
 
-impl<C> Default for StatusMetrics<C>
+impl<C> StatusMetrics<C>
 where
     C: Hash + Eq,
 {
-    fn default() -> Self {
+    fn new(latency_buckets: latency::Buckets) -> Self {
         Self {
-            latency: Histogram::default(),
+            latency: latency::LatencyHistogram::new(latency_buckets),
             by_class: HashMap::default(),
         }
     }
@@ -44,6 +44,7 @@ where
     classify: Option<C>,
     metrics: Option<Arc<Mutex<Metrics<C::Class>>>>,
     stream_open_at: Instant,
+    trace_id: Option<String>,
     #[pin]
     inner: F,
 }
@@ -72,6 +73,9 @@ where
     classify: Option<C>,
     metrics: Option<Arc<Mutex<Metrics<C::Class>>>>,
     stream_open_at: Instant,
+    /// The request's trace ID, if any, which is recorded as an exemplar of
+    /// the response latency.
+    trace_id: Option<String>,
     latency_recorded: bool,
     #[pin]
     inner: B,
@@ -108,6 +112,12 @@ where
     }
 }
 
+/// Returns the ID of the sampled trace to which a request belongs, if any, so
+/// that its latency may be recorded as an exemplar.
+fn trace_id<B>(req: &http::Request<B>) -> Option<String> {
+    linkerd_trace_context::sampled_trace_id(req).map(|id| id.to_string())
+}
+
 #[inline]
 fn classify_unwrap_if_debug_else_default<C, B>(req: &http::Request<B>) -> C
 where
@@ -134,6 +144,7 @@ where
 
     fn proxy(&self, svc: &mut S, req: http::Request<A>) -> Self::Future {
         let mut req_metrics = self.metrics.clone();
+        let trace_id = trace_id(&req);
 
         if req.body().is_end_stream() {
             if let Some(lock) = req_metrics.take() {
@@ -157,6 +168,7 @@ where
             classify: Some(classify_unwrap_if_debug_else_default(&req)),
             metrics: self.metrics.clone(),
             stream_open_at: Instant::now(),
+            trace_id,
             inner: self.inner.proxy(svc, req),
         }
     }
@@ -182,6 +194,7 @@ where
 
     fn call(&mut self, req: http::Request<A>) -> Self::Future {
         let mut req_metrics = self.metrics.clone();
+        let trace_id = trace_id(&req);
 
         if req.body().is_end_stream() {
             if let Some(lock) = req_metrics.take() {
@@ -205,6 +218,7 @@ where
             classify: Some(classify_unwrap_if_debug_else_default(&req)),
             metrics: self.metrics.clone(),
             stream_open_at: Instant::now(),
+            trace_id,
             inner: self.inner.call(req),
         }
     }
@@ -235,6 +249,7 @@ where
                     classify,
                     metrics,
                     stream_open_at: *this.stream_open_at,
+                    trace_id: this.trace_id.take(),
                     latency_recorded: false,
                     inner,
                 };
@@ -322,6 +337,26 @@ where
             stream_open_at: Instant::now(),
             classify: None,
             metrics: None,
+            trace_id: None,
+            latency_recorded: false,
+        }
+    }
+}
+
+impl<B, C> From<bytes::Bytes> for ResponseBody<B, C>
+where
+    B: Body + From<bytes::Bytes>,
+    C: ClassifyEos,
+    C::Class: Hash + Eq,
+{
+    fn from(bytes: bytes::Bytes) -> Self {
+        Self {
+            status: http::StatusCode::OK,
+            inner: B::from(bytes),
+            stream_open_at: Instant::now(),
+            classify: None,
+            metrics: None,
+            trace_id: None,
             latency_recorded: false,
         }
     }
@@ -345,13 +380,19 @@ where
 
         metrics.last_update = now;
 
+        let latency_buckets = metrics.latency_buckets;
         let status_metrics = metrics
             .by_status
             .entry(Some(*this.status))
-            .or_insert_with(StatusMetrics::default);
+            .or_insert_with(|| StatusMetrics::new(latency_buckets));
 
         let elapsed = now.saturating_duration_since(*this.stream_open_at);
-        status_metrics.latency.add(elapsed);
+        match this.trace_id.take() {
+            Some(id) => status_metrics
+                .latency
+                .add_with_exemplar(elapsed, vec![("trace_id", id)]),
+            None => status_metrics.latency.add(elapsed),
+        }
 
         *this.latency_recorded = true;
     }
@@ -387,7 +428,11 @@ fn measure_class<C: Hash + Eq>(
 
     metrics.last_update = now;
 
-    let status_metrics = metrics.by_status.entry(status).or_default();
+    let latency_buckets = metrics.latency_buckets;
+    let status_metrics = metrics
+        .by_status
+        .entry(status)
+        .or_insert_with(|| StatusMetrics::new(latency_buckets));
 
     let class_metrics = status_metrics.by_class.entry(class).or_default();
 
@@ -18,10 +18,13 @@ pub struct Metrics {
     last_update: Instant,
     retryable: Counter,
     no_budget: Counter,
+    body_too_large: Counter,
 }
 
 struct NoBudgetLabel;
 
+struct BodyTooLargeLabel;
+
 // === impl Retries ===
 
 impl<T: Hash + Eq> Default for Retries<T> {
@@ -58,6 +61,15 @@ impl Handle {
             m.no_budget.incr();
         }
     }
+
+    /// Records a retryable response whose request was not retried because its
+    /// body was too large to be buffered.
+    pub fn incr_body_too_large(&self) {
+        let mut m = self.0.lock();
+        m.last_update = Instant::now();
+        m.retryable.incr();
+        m.body_too_large.incr();
+    }
 }
 
 // === impl Metrics ===
@@ -68,6 +80,7 @@ impl Default for Metrics {
             last_update: Instant::now(),
             retryable: Counter::default(),
             no_budget: Counter::default(),
+            body_too_large: Counter::default(),
         }
     }
 }
@@ -115,6 +128,8 @@ where
             m.retryable.fmt_metric_labeled(f, &metric.name, tgt)?;
             m.no_budget
                 .fmt_metric_labeled(f, &metric.name, (tgt, NoBudgetLabel))?;
+            m.body_too_large
+                .fmt_metric_labeled(f, &metric.name, (tgt, BodyTooLargeLabel))?;
         }
 
         registry.retain_since(Instant::now() - self.retain_idle);
@@ -128,3 +143,9 @@ impl FmtLabels for NoBudgetLabel {
         write!(f, "skipped=\"no_budget\"")
     }
 }
+
+impl FmtLabels for BodyTooLargeLabel {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "skipped=\"body_too_large\"")
+    }
+}
@@ -153,6 +153,23 @@ impl<B: Body> ReplayBody<B> {
                     .is_capped()
             })
     }
+
+    /// Returns `true` if the initial body has ended and all of its data has
+    /// been buffered, so that clones replay the complete body.
+    ///
+    /// This is false while the initial body is still being read, even if its
+    /// buffered data may ultimately be replayed.
+    pub fn is_fully_buffered(&self) -> bool {
+        if self.shared.was_empty {
+            return true;
+        }
+        let complete = |state: &BodyState<B>| state.is_completed && !state.is_capped();
+        match self.state.as_ref() {
+            Some(state) => complete(state),
+            // If another clone owns the state, it's still being read.
+            None => self.shared.body.lock().as_ref().map_or(false, complete),
+        }
+    }
 }
 
 impl<B> Body for ReplayBody<B>
@@ -889,6 +906,26 @@ mod tests {
         assert!(err.is::<Capped>())
     }
 
+    #[tokio::test]
+    async fn fully_buffered_once_initial_body_completes() {
+        let Test {
+            mut tx,
+            mut initial,
+            replay,
+            _trace,
+        } = Test::new();
+        tx.send_data("hello").await;
+        assert_eq!(chunk(&mut initial).await, Some("hello".to_string()));
+        assert!(!initial.is_fully_buffered());
+
+        drop(tx);
+        assert_eq!(chunk(&mut initial).await, None);
+        assert!(initial.is_fully_buffered());
+
+        drop(initial);
+        assert!(replay.is_fully_buffered());
+    }
+
     #[test]
     fn body_too_big() {
         let max_size = 8;
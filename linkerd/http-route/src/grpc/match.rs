@@ -54,6 +54,10 @@ impl crate::Match for MatchRoute {
 
         Some(RouteMatch { rpc, headers })
     }
+
+    fn matches_all(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 // === impl RouteMatch ===
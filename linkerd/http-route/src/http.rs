@@ -1,9 +1,13 @@
+mod builder;
 pub mod filter;
 pub mod r#match;
 #[cfg(test)]
 mod tests;
 
-pub use self::r#match::{HostMatch, MatchHeader, MatchHost, MatchRequest};
+pub use self::{
+    builder::{Diagnostic, InvalidMatch, InvalidRoute, MatchBuilder, RouteBuilder},
+    r#match::{HostMatch, MatchHeader, MatchHost, MatchRequest},
+};
 
 pub type RouteMatch = crate::RouteMatch<r#match::RequestMatch>;
 
@@ -0,0 +1,345 @@
+//! Builds HTTP routes from loosely-typed configuration, validating the route's
+//! rules as a whole.
+
+use super::{
+    r#match::{InvalidHost, InvalidPathTemplate, MatchPath, MatchQueryParam},
+    MatchHeader, MatchHost, MatchRequest, Route, Rule,
+};
+use crate::lint::Lint;
+
+/// Builds an HTTP [`Route`], collecting a [`Diagnostic`] for each problem with
+/// its hosts or rules.
+#[derive(Debug)]
+pub struct RouteBuilder<P> {
+    hosts: Vec<MatchHost>,
+    rules: Vec<(Vec<MatchBuilder>, P)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Builds a single [`MatchRequest`], recording the first invalid field.
+#[derive(Debug, Default)]
+pub struct MatchBuilder {
+    r#match: MatchRequest,
+    error: Option<InvalidMatch>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidMatch {
+    #[error("invalid header name: {0}")]
+    HeaderName(#[from] http::header::InvalidHeaderName),
+
+    #[error("invalid header value: {0}")]
+    HeaderValue(#[from] http::header::InvalidHeaderValue),
+
+    #[error("invalid method: {0}")]
+    Method(#[from] http::method::InvalidMethod),
+
+    #[error("invalid path template: {0}")]
+    PathTemplate(#[from] InvalidPathTemplate),
+
+    #[error("invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+}
+
+/// Describes a problem with a route.
+#[derive(Debug, thiserror::Error)]
+pub enum Diagnostic {
+    #[error("invalid host: {0}")]
+    InvalidHost(#[source] InvalidHost),
+
+    #[error("rule {rule}: {error}")]
+    InvalidMatch {
+        rule: usize,
+        #[source]
+        error: InvalidMatch,
+    },
+
+    #[error("route has no rules")]
+    NoRules,
+
+    /// A match is identical to a match of an earlier rule, which is always
+    /// preferred.
+    #[error("rule {rule} duplicates a match of rule {duplicate_of}")]
+    DuplicateMatch { rule: usize, duplicate_of: usize },
+
+    /// A rule only matches all requests, but an earlier rule also matches
+    /// all requests and is always preferred.
+    #[error("rule {rule} is unreachable: rule {shadowed_by} matches all requests")]
+    Unreachable { rule: usize, shadowed_by: usize },
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid route: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct InvalidRoute(pub Vec<Diagnostic>);
+
+// === impl RouteBuilder ===
+
+impl<P> Default for RouteBuilder<P> {
+    fn default() -> Self {
+        Self {
+            hosts: Vec::new(),
+            rules: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl<P> RouteBuilder<P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a host name match, like `example.com` or `*.example.com:8080`.
+    pub fn host(mut self, host: &str) -> Self {
+        match host.parse() {
+            Ok(host) => self.hosts.push(host),
+            Err(error) => self.diagnostics.push(Diagnostic::InvalidHost(error)),
+        }
+        self
+    }
+
+    /// Adds a rule that applies `policy` to requests that satisfy any of
+    /// `matches`. A rule without matches applies to all requests.
+    pub fn rule(mut self, matches: impl IntoIterator<Item = MatchBuilder>, policy: P) -> Self {
+        self.rules.push((matches.into_iter().collect(), policy));
+        self
+    }
+
+    /// Builds the route if none of its hosts or rules are invalid.
+    pub fn build(self) -> Result<Route<P>, InvalidRoute> {
+        let Self {
+            hosts,
+            rules,
+            mut diagnostics,
+        } = self;
+
+        if rules.is_empty() {
+            diagnostics.push(Diagnostic::NoRules);
+        }
+
+        let mut built = Vec::with_capacity(rules.len());
+        for (rule, (matches, policy)) in rules.into_iter().enumerate() {
+            let mut valid = Vec::with_capacity(matches.len());
+            for m in matches {
+                match m.build() {
+                    Ok(m) => valid.push(m),
+                    Err(error) => diagnostics.push(Diagnostic::InvalidMatch { rule, error }),
+                }
+            }
+            built.push(Rule {
+                matches: valid,
+                policy,
+            });
+        }
+
+        let (built, lints) = validate_rules(built);
+        diagnostics.extend(lints);
+        if !diagnostics.is_empty() {
+            return Err(InvalidRoute(diagnostics));
+        }
+
+        Ok(Route {
+            hosts,
+            rules: built,
+        })
+    }
+}
+
+/// Finds rules that are shadowed by earlier rules.
+fn validate_rules<P>(rules: Vec<Rule<P>>) -> (Vec<Rule<P>>, Vec<Diagnostic>) {
+    let route = [Route {
+        hosts: vec![],
+        rules,
+    }];
+    let diagnostics = crate::lint::lint(&route)
+        .into_iter()
+        .map(|lint| match lint {
+            Lint::DuplicateMatch { rule, duplicate_of } => Diagnostic::DuplicateMatch {
+                rule: rule.rule,
+                duplicate_of: duplicate_of.rule,
+            },
+            Lint::Unreachable { rule, shadowed_by } => Diagnostic::Unreachable {
+                rule: rule.rule,
+                shadowed_by: shadowed_by.rule,
+            },
+        })
+        .collect();
+    let [Route { rules, .. }] = route;
+    (rules, diagnostics)
+}
+
+// === impl MatchBuilder ===
+
+impl MatchBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn path_exact(mut self, path: &str) -> Self {
+        self.r#match.path = Some(MatchPath::Exact(path.to_string()));
+        self
+    }
+
+    pub fn path_prefix(mut self, prefix: &str) -> Self {
+        self.r#match.path = Some(MatchPath::Prefix(prefix.to_string()));
+        self
+    }
+
+    pub fn path_regex(self, re: &str) -> Self {
+        self.try_with(|m| {
+            m.path = Some(MatchPath::Regex(re.parse()?));
+            Ok(())
+        })
+    }
+
+    pub fn path_template(self, template: &str) -> Self {
+        self.try_with(|m| {
+            m.path = Some(MatchPath::Template(template.parse()?));
+            Ok(())
+        })
+    }
+
+    pub fn header_exact(self, name: &str, value: &str) -> Self {
+        self.try_with(|m| {
+            m.headers
+                .push(MatchHeader::Exact(name.parse()?, value.parse()?));
+            Ok(())
+        })
+    }
+
+    pub fn header_regex(self, name: &str, re: &str) -> Self {
+        self.try_with(|m| {
+            m.headers
+                .push(MatchHeader::Regex(name.parse()?, re.parse()?));
+            Ok(())
+        })
+    }
+
+    pub fn query_param_exact(mut self, name: &str, value: &str) -> Self {
+        self.r#match
+            .query_params
+            .push(MatchQueryParam::Exact(name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn query_param_regex(self, name: &str, re: &str) -> Self {
+        self.try_with(|m| {
+            m.query_params
+                .push(MatchQueryParam::Regex(name.to_string(), re.parse()?));
+            Ok(())
+        })
+    }
+
+    pub fn method(self, method: &str) -> Self {
+        self.try_with(|m| {
+            m.method = Some(method.parse()?);
+            Ok(())
+        })
+    }
+
+    pub fn build(self) -> Result<MatchRequest, InvalidMatch> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.r#match),
+        }
+    }
+
+    fn try_with(mut self, f: impl FnOnce(&mut MatchRequest) -> Result<(), InvalidMatch>) -> Self {
+        if self.error.is_none() {
+            if let Err(error) = f(&mut self.r#match) {
+                self.error = Some(error);
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_routes() {
+        let route = RouteBuilder::new()
+            .host("example.com")
+            .rule(
+                [MatchBuilder::new()
+                    .path_prefix("/api")
+                    .header_exact("x-user", "alice")],
+                1,
+            )
+            .rule([MatchBuilder::new().path_regex("/v[0-9]+/.*")], 2)
+            .rule([], 3)
+            .build()
+            .expect("route must be valid");
+        assert_eq!(route.hosts, vec!["example.com".parse().unwrap()]);
+        assert_eq!(route.rules.len(), 3);
+
+        let req = http::Request::builder()
+            .uri("http://example.com/v2/users")
+            .body(())
+            .unwrap();
+        let routes = [route];
+        let (_, policy) = crate::http::find(&routes, &req).expect("route must match");
+        assert_eq!(*policy, 2);
+    }
+
+    #[test]
+    fn reports_invalid_matches() {
+        let InvalidRoute(diagnostics) = RouteBuilder::new()
+            .host("10.0.0.1")
+            .rule([MatchBuilder::new().path_regex("/(unclosed")], ())
+            .rule([MatchBuilder::new().header_exact("bad header", "v")], ())
+            .build()
+            .expect_err("route must be invalid");
+        assert!(matches!(
+            diagnostics[..],
+            [
+                Diagnostic::InvalidHost(InvalidHost::NumericHost),
+                Diagnostic::InvalidMatch {
+                    rule: 0,
+                    error: InvalidMatch::Regex(_)
+                },
+                Diagnostic::InvalidMatch {
+                    rule: 1,
+                    error: InvalidMatch::HeaderName(_)
+                },
+            ]
+        ));
+
+        let InvalidRoute(diagnostics) = RouteBuilder::<()>::new()
+            .build()
+            .expect_err("route must be invalid");
+        assert!(matches!(diagnostics[..], [Diagnostic::NoRules]));
+    }
+
+    #[test]
+    fn reports_unreachable_rules() {
+        let InvalidRoute(diagnostics) = RouteBuilder::new()
+            .rule([MatchBuilder::new().path_exact("/foo")], ())
+            .rule([MatchBuilder::new().path_prefix("/")], ())
+            .rule(
+                [
+                    MatchBuilder::new().path_exact("/bar"),
+                    MatchBuilder::new().path_exact("/foo"),
+                ],
+                (),
+            )
+            .rule([], ())
+            .build()
+            .expect_err("route must be invalid");
+        assert!(matches!(
+            diagnostics[..],
+            [
+                Diagnostic::DuplicateMatch {
+                    rule: 2,
+                    duplicate_of: 0
+                },
+                Diagnostic::Unreachable {
+                    rule: 3,
+                    shadowed_by: 1
+                },
+            ]
+        ));
+    }
+}
@@ -1,11 +1,19 @@
+pub mod direct_response;
+pub mod extension;
 pub mod inject_failure;
+pub mod inject_metadata;
 pub mod modify_header;
 pub mod redirect;
+pub mod websocket;
 
 pub use self::{
+    direct_response::DirectResponse,
+    extension::{Extension, ExtensionFilter},
     inject_failure::{Distribution, FailureResponse, InjectFailure},
+    inject_metadata::{InjectMetadata, Metadata},
     modify_header::ModifyHeader,
     redirect::{InvalidRedirect, RedirectRequest, Redirection},
+    websocket::WebSocketPolicy,
 };
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -0,0 +1,55 @@
+use bytes::Bytes;
+use http::header::{HeaderName, HeaderValue, CONTENT_LENGTH};
+
+/// A filter that answers requests with a static response rather than
+/// forwarding them to a backend.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct DirectResponse {
+    pub status: http::StatusCode,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    pub body: Bytes,
+}
+
+// === impl DirectResponse ===
+
+impl DirectResponse {
+    pub fn response<B: From<Bytes>>(&self) -> http::Response<B> {
+        let mut rsp = http::Response::new(B::from(self.body.clone()));
+        *rsp.status_mut() = self.status;
+        let headers = rsp.headers_mut();
+        for (name, value) in &self.headers {
+            headers.append(name.clone(), value.clone());
+        }
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(self.body.len()));
+        rsp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_response() {
+        let direct = DirectResponse {
+            status: http::StatusCode::SERVICE_UNAVAILABLE,
+            headers: vec![
+                (
+                    HeaderName::from_static("content-type"),
+                    HeaderValue::from_static("text/plain"),
+                ),
+                (
+                    HeaderName::from_static("content-length"),
+                    HeaderValue::from_static("1000"),
+                ),
+            ],
+            body: Bytes::from_static(b"down for maintenance"),
+        };
+
+        let rsp = direct.response::<Bytes>();
+        assert_eq!(rsp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(rsp.headers()["content-type"], "text/plain");
+        assert_eq!(rsp.headers()["content-length"], "20");
+        assert_eq!(rsp.body(), "down for maintenance");
+    }
+}
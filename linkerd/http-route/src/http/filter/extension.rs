@@ -0,0 +1,62 @@
+use super::DirectResponse;
+use http::header::HeaderMap;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// A request filter that is implemented outside of the route policy model,
+/// e.g. by a plugin linked into the proxy.
+pub trait ExtensionFilter: fmt::Debug + Send + Sync + 'static {
+    /// Identifies the filter. Extensions with the same name are considered
+    /// equivalent.
+    fn name(&self) -> &str;
+
+    /// Processes a request's headers. The request is answered directly when a
+    /// response is returned.
+    fn apply_request(
+        &self,
+        headers: &mut HeaderMap,
+    ) -> Result<Option<DirectResponse>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// A filter that delegates to an [`ExtensionFilter`].
+#[derive(Clone, Debug)]
+pub struct Extension(pub Arc<dyn ExtensionFilter>);
+
+// === impl Extension ===
+
+impl Extension {
+    pub fn new(filter: impl ExtensionFilter) -> Self {
+        Self(Arc::new(filter))
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+impl std::ops::Deref for Extension {
+    type Target = dyn ExtensionFilter;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl Hash for Extension {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name().hash(state)
+    }
+}
+
+impl PartialEq for Extension {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for Extension {}
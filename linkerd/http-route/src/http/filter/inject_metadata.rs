@@ -0,0 +1,130 @@
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use std::net::SocketAddr;
+
+/// A filter that describes a request's connection to the server in request
+/// headers.
+///
+/// Each header is only set when it is configured. Values that a client sets
+/// for a configured header are always replaced (or removed, when the
+/// metadata is unknown), so backends may trust them.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct InjectMetadata {
+    pub client_addr: Option<HeaderName>,
+    pub client_identity: Option<HeaderName>,
+    pub tls: Option<HeaderName>,
+    pub route: Option<HeaderName>,
+}
+
+/// Describes the connection and route on which a request was received.
+#[derive(Clone, Debug)]
+pub struct Metadata<'a> {
+    pub client_addr: SocketAddr,
+    pub client_identity: Option<&'a str>,
+    pub tls: bool,
+    pub route: &'a str,
+}
+
+// === impl InjectMetadata ===
+
+impl InjectMetadata {
+    pub fn apply(&self, headers: &mut HeaderMap, meta: &Metadata<'_>) {
+        if let Some(name) = &self.client_addr {
+            let ip = HeaderValue::from_str(&meta.client_addr.ip().to_string())
+                .expect("IP addresses must be valid header values");
+            headers.insert(name, ip);
+        }
+
+        if let Some(name) = &self.client_identity {
+            match meta
+                .client_identity
+                .and_then(|id| HeaderValue::from_str(id).ok())
+            {
+                Some(id) => {
+                    headers.insert(name, id);
+                }
+                None => {
+                    headers.remove(name);
+                }
+            }
+        }
+
+        if let Some(name) = &self.tls {
+            let tls = if meta.tls { "true" } else { "false" };
+            headers.insert(name, HeaderValue::from_static(tls));
+        }
+
+        if let Some(name) = &self.route {
+            match HeaderValue::from_str(meta.route) {
+                Ok(route) => {
+                    headers.insert(name, route);
+                }
+                Err(_) => {
+                    headers.remove(name);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_client_headers() {
+        let filter = InjectMetadata {
+            client_addr: Some(HeaderName::from_static("x-client-ip")),
+            client_identity: Some(HeaderName::from_static("x-client-id")),
+            tls: Some(HeaderName::from_static("x-client-tls")),
+            route: Some(HeaderName::from_static("x-route")),
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-client-ip", HeaderValue::from_static("10.0.0.1"));
+        headers.insert("x-client-id", HeaderValue::from_static("spoofed"));
+        filter.apply(
+            &mut headers,
+            &Metadata {
+                client_addr: ([192, 0, 2, 3], 41234).into(),
+                client_identity: None,
+                tls: false,
+                route: "default",
+            },
+        );
+        assert_eq!(headers["x-client-ip"], "192.0.2.3");
+        assert!(headers.get("x-client-id").is_none());
+        assert_eq!(headers["x-client-tls"], "false");
+        assert_eq!(headers["x-route"], "default");
+
+        filter.apply(
+            &mut headers,
+            &Metadata {
+                client_addr: ([192, 0, 2, 3], 41234).into(),
+                client_identity: Some("web.ns.serviceaccount.identity.linkerd.cluster.local"),
+                tls: true,
+                route: "default",
+            },
+        );
+        assert_eq!(
+            headers["x-client-id"],
+            "web.ns.serviceaccount.identity.linkerd.cluster.local"
+        );
+        assert_eq!(headers["x-client-tls"], "true");
+    }
+
+    #[test]
+    fn ignores_unconfigured_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-client-id", HeaderValue::from_static("client"));
+        InjectMetadata::default().apply(
+            &mut headers,
+            &Metadata {
+                client_addr: ([192, 0, 2, 3], 41234).into(),
+                client_identity: None,
+                tls: false,
+                route: "default",
+            },
+        );
+        assert_eq!(headers.len(), 1);
+    }
+}
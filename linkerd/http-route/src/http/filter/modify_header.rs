@@ -1,3 +1,4 @@
+use crate::http::r#match::PathCaptures;
 use http::header::{HeaderMap, HeaderName, HeaderValue};
 
 // Note that while `set` and `remove` could perhaps be better-modeled as a Map
@@ -14,11 +15,17 @@ pub struct ModifyHeader {
 
 impl ModifyHeader {
     pub fn apply(&self, headers: &mut HeaderMap) {
+        self.apply_with_captures(headers, &PathCaptures::default())
+    }
+
+    /// Modifies headers, substituting `{name}` in added and set values with
+    /// the path segment captured as `name` by a path template match.
+    pub fn apply_with_captures(&self, headers: &mut HeaderMap, captures: &PathCaptures) {
         for (hdr, val) in &self.add {
-            headers.append(hdr, val.clone());
+            headers.append(hdr, expand(val, captures));
         }
         for (hdr, val) in &self.set {
-            headers.insert(hdr, val.clone());
+            headers.insert(hdr, expand(val, captures));
         }
         for hdr in &self.remove {
             headers.remove(hdr);
@@ -26,6 +33,16 @@ impl ModifyHeader {
     }
 }
 
+fn expand(val: &HeaderValue, captures: &PathCaptures) -> HeaderValue {
+    if captures.is_empty() {
+        return val.clone();
+    }
+    val.to_str()
+        .ok()
+        .and_then(|v| HeaderValue::from_str(&captures.expand(v)).ok())
+        .unwrap_or_else(|| val.clone())
+}
+
 #[cfg(feature = "proto")]
 pub mod proto {
     use super::*;
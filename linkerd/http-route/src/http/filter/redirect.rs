@@ -161,8 +161,15 @@ impl RedirectRequest {
                 .clone()),
 
             // If the redirect specifies a full path (potentially including a
-            // query), use it.
-            Some(ModifyPath::ReplaceFullPath(p)) => p.clone().try_into().map_err(Into::into),
+            // query), use it, substituting any segments captured by a path
+            // template match.
+            Some(ModifyPath::ReplaceFullPath(p)) => rm
+                .route
+                .captures()
+                .expand(p)
+                .into_owned()
+                .try_into()
+                .map_err(Into::into),
 
             // If the redirect specifies a prefix rewrite, using the original
             // query parameters.
@@ -403,6 +410,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn replace_path_full_with_captures() {
+        let rule = Rule {
+            matches: vec![MatchRequest {
+                path: Some(MatchPath::Template(
+                    "/users/{id}/orders/{order}".parse().unwrap(),
+                )),
+                ..MatchRequest::default()
+            }],
+            policy: RedirectRequest {
+                path: Some(ModifyPath::ReplaceFullPath(
+                    "/v2/orders/{order}?user={id}".to_string(),
+                )),
+                ..RedirectRequest::default()
+            },
+        };
+        assert_eq!(
+            apply!("http://example.com/users/7/orders/1234", rule).expect("must apply"),
+            Some(Redirection {
+                status: http::StatusCode::MOVED_PERMANENTLY,
+                location: "http://example.com/v2/orders/1234?user=7".parse().unwrap()
+            }),
+        );
+    }
+
     #[test]
     fn replace_path_full_overwrites_query_params() {
         let rule = Rule {
@@ -0,0 +1,11 @@
+use std::time::Duration;
+
+/// Limits the lifetime of the WebSocket sessions established on a route.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct WebSocketPolicy {
+    /// Closes sessions that have been open for this long.
+    pub max_session_duration: Option<Duration>,
+
+    /// Closes sessions on which no data has been sent for this long.
+    pub idle_timeout: Option<Duration>,
+}
@@ -1,16 +1,20 @@
+pub mod content_type;
 pub mod header;
 pub mod host;
 pub mod path;
 pub mod query_param;
 #[cfg(test)]
 mod tests;
+pub mod upgrade;
 
 pub(crate) use self::path::PathMatch;
 pub use self::{
+    content_type::{InvalidContentType, MatchContentType},
     header::MatchHeader,
-    host::{HostMatch, InvalidHost, MatchHost},
-    path::MatchPath,
+    host::{HostMatch, InvalidHost, InvalidPort, MatchHost, MatchPort, PortMatch},
+    path::{InvalidPathTemplate, MatchPath, PathCaptures, PathTemplate},
     query_param::MatchQueryParam,
+    upgrade::MatchUpgrade,
 };
 
 /// Matches HTTP requests.
@@ -20,6 +24,8 @@ pub struct MatchRequest {
     pub headers: Vec<MatchHeader>,
     pub query_params: Vec<MatchQueryParam>,
     pub method: Option<http::Method>,
+    pub upgrade: Option<MatchUpgrade>,
+    pub content_type: Option<MatchContentType>,
 }
 
 /// Summarizes a matched HTTP request.
@@ -29,14 +35,49 @@ pub struct RequestMatch {
     headers: usize,
     query_params: usize,
     method: bool,
+    upgrade: bool,
+    content_type: bool,
 }
 
 // === impl MatchRequest ===
 
+impl MatchRequest {
+    /// Requires that matched requests also satisfy `other`.
+    ///
+    /// The path, method, upgrade, and content type matches of `other` replace
+    /// those of this match, when set; its header and query parameter matches
+    /// are added to this match's.
+    pub fn narrow(&mut self, other: &MatchRequest) {
+        if let Some(path) = &other.path {
+            self.path = Some(path.clone());
+        }
+        self.headers.extend(other.headers.iter().cloned());
+        self.query_params.extend(other.query_params.iter().cloned());
+        if let Some(method) = &other.method {
+            self.method = Some(method.clone());
+        }
+        if let Some(upgrade) = &other.upgrade {
+            self.upgrade = Some(upgrade.clone());
+        }
+        if let Some(content_type) = &other.content_type {
+            self.content_type = Some(content_type.clone());
+        }
+    }
+}
+
 impl RequestMatch {
     pub(crate) fn path(&self) -> &PathMatch {
         &self.path_match
     }
+
+    /// Returns the path segments captured by a template match, if any.
+    pub fn captures(&self) -> &PathCaptures {
+        static NONE: PathCaptures = PathCaptures::none();
+        match &self.path_match {
+            PathMatch::Template { captures, .. } => captures,
+            _ => &NONE,
+        }
+    }
 }
 
 impl crate::Match for MatchRequest {
@@ -66,8 +107,36 @@ impl crate::Match for MatchRequest {
         }
         summary.query_params = self.query_params.len();
 
+        if let Some(upgrade) = &self.upgrade {
+            if !upgrade.is_match(req) {
+                return None;
+            }
+            summary.upgrade = true;
+        }
+
+        if let Some(content_type) = &self.content_type {
+            if !content_type.is_match(req) {
+                return None;
+            }
+            summary.content_type = true;
+        }
+
         Some(summary)
     }
+
+    fn matches_all(&self) -> bool {
+        let any_path = match &self.path {
+            None => true,
+            Some(MatchPath::Prefix(p)) => p == "/",
+            Some(_) => false,
+        };
+        any_path
+            && self.headers.is_empty()
+            && self.query_params.is_empty()
+            && self.method.is_none()
+            && self.upgrade.is_none()
+            && self.content_type.is_none()
+    }
 }
 
 impl Default for RequestMatch {
@@ -81,6 +150,8 @@ impl Default for RequestMatch {
             headers: 0,
             query_params: 0,
             method: false,
+            upgrade: false,
+            content_type: false,
         }
     }
 }
@@ -100,6 +171,8 @@ impl std::cmp::Ord for RequestMatch {
             .then_with(|| self.headers.cmp(&other.headers))
             .then_with(|| self.query_params.cmp(&other.query_params))
             .then_with(|| self.method.cmp(&other.method))
+            .then_with(|| self.upgrade.cmp(&other.upgrade))
+            .then_with(|| self.content_type.cmp(&other.content_type))
     }
 }
 
@@ -146,6 +219,10 @@ pub mod proto {
                 headers,
                 query_params,
                 method,
+                // Upgrade and content type matches are not yet expressible in
+                // the control plane API.
+                upgrade: None,
+                content_type: None,
             })
         }
     }
@@ -0,0 +1,106 @@
+/// Matches a request's `content-type` media type. Media type parameters, like
+/// `charset`, are ignored.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum MatchContentType {
+    /// Matches a media type exactly, e.g. `application/json`.
+    Exact(String),
+
+    /// Matches any subtype of a media type, e.g. `multipart/*`.
+    Type(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid media type: {0:?}")]
+pub struct InvalidContentType(String);
+
+// === impl MatchContentType ===
+
+impl std::str::FromStr for MatchContentType {
+    type Err = InvalidContentType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidContentType(s.to_string());
+        let (ty, subtype) = s.split_once('/').ok_or_else(invalid)?;
+        let is_token = |t: &str| {
+            !t.is_empty()
+                && t.bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&b))
+        };
+        if !is_token(ty) {
+            return Err(invalid());
+        }
+        if subtype == "*" {
+            return Ok(Self::Type(ty.to_ascii_lowercase()));
+        }
+        if !is_token(subtype) {
+            return Err(invalid());
+        }
+        Ok(Self::Exact(s.to_ascii_lowercase()))
+    }
+}
+
+impl MatchContentType {
+    pub fn is_match<B>(&self, req: &http::Request<B>) -> bool {
+        let Some(media_type) = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.split_once(';').map_or(v, |(t, _)| t).trim())
+        else {
+            return false;
+        };
+
+        match self {
+            Self::Exact(t) => media_type.eq_ignore_ascii_case(t),
+            Self::Type(t) => media_type
+                .split_once('/')
+                .map_or(false, |(ty, _)| ty.eq_ignore_ascii_case(t)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(content_type: &'static str) -> http::Request<()> {
+        http::Request::builder()
+            .header(http::header::CONTENT_TYPE, content_type)
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn exact() {
+        let m = "application/json".parse::<MatchContentType>().unwrap();
+        assert!(m.is_match(&req("application/json")));
+        assert!(m.is_match(&req("Application/JSON; charset=utf-8")));
+        assert!(!m.is_match(&req("application/grpc+proto")));
+        assert!(!m.is_match(&http::Request::new(())));
+    }
+
+    #[test]
+    fn any_subtype() {
+        let m = "multipart/*".parse::<MatchContentType>().unwrap();
+        assert_eq!(m, MatchContentType::Type("multipart".to_string()));
+        assert!(m.is_match(&req("multipart/form-data; boundary=xyz")));
+        assert!(m.is_match(&req("multipart/mixed")));
+        assert!(!m.is_match(&req("application/json")));
+    }
+
+    #[test]
+    fn invalid() {
+        for invalid in [
+            "json",
+            "/json",
+            "application/",
+            "*/*",
+            "text/plain; charset=utf-8",
+        ] {
+            assert!(
+                invalid.parse::<MatchContentType>().is_err(),
+                "{invalid} must be invalid"
+            );
+        }
+    }
+}
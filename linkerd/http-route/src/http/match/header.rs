@@ -5,7 +5,17 @@ use regex::Regex;
 #[derive(Clone, Debug)]
 pub enum MatchHeader {
     Exact(HeaderName, HeaderValue),
+
+    /// Matches a header value exactly, ignoring ASCII case.
+    ExactIgnoreCase(HeaderName, HeaderValue),
+
     Regex(HeaderName, Regex),
+
+    /// Matches when the header is set, regardless of its value.
+    Present(HeaderName),
+
+    /// Matches when the header is not set.
+    Absent(HeaderName),
 }
 
 // === impl MatchHeader ===
@@ -14,6 +24,10 @@ impl MatchHeader {
     pub fn is_match(&self, headers: &http::HeaderMap) -> bool {
         match self {
             Self::Exact(n, v) => headers.get_all(n).iter().any(|h| h == v),
+            Self::ExactIgnoreCase(n, v) => headers
+                .get_all(n)
+                .iter()
+                .any(|h| h.as_bytes().eq_ignore_ascii_case(v.as_bytes())),
             Self::Regex(n, re) => headers
                 .get_all(n)
                 .iter()
@@ -27,14 +41,17 @@ impl MatchHeader {
                         false
                     }
                 }),
+            Self::Present(n) => headers.contains_key(n),
+            Self::Absent(n) => !headers.contains_key(n),
         }
     }
 }
 
 impl std::hash::Hash for MatchHeader {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
         match self {
-            Self::Exact(n, s) => {
+            Self::Exact(n, s) | Self::ExactIgnoreCase(n, s) => {
                 n.hash(state);
                 s.hash(state)
             }
@@ -42,6 +59,7 @@ impl std::hash::Hash for MatchHeader {
                 n.hash(state);
                 r.as_str().hash(state);
             }
+            Self::Present(n) | Self::Absent(n) => n.hash(state),
         }
     }
 }
@@ -52,7 +70,10 @@ impl std::cmp::PartialEq for MatchHeader {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Exact(n, s), Self::Exact(m, o)) => n == m && s == o,
+            (Self::ExactIgnoreCase(n, s), Self::ExactIgnoreCase(m, o)) => n == m && s == o,
             (Self::Regex(n, s), Self::Regex(m, o)) => n == m && s.as_str() == o.as_str(),
+            (Self::Present(n), Self::Present(m)) => n == m,
+            (Self::Absent(n), Self::Absent(m)) => n == m,
             _ => false,
         }
     }
@@ -181,4 +202,35 @@ mod tests {
             h
         }));
     }
+
+    #[test]
+    fn headers_exact_ignore_case() {
+        let m = MatchHeader::ExactIgnoreCase(
+            HeaderName::from_static("foo"),
+            HeaderValue::from_static("Bar"),
+        );
+        let headers = |v: &'static str| {
+            let mut h = http::HeaderMap::new();
+            h.insert(HeaderName::from_static("foo"), HeaderValue::from_static(v));
+            h
+        };
+        assert!(m.is_match(&headers("bar")));
+        assert!(m.is_match(&headers("BAR")));
+        assert!(!m.is_match(&headers("baz")));
+        assert!(!m.is_match(&http::HeaderMap::new()));
+    }
+
+    #[test]
+    fn headers_presence() {
+        let present = MatchHeader::Present(HeaderName::from_static("foo"));
+        let absent = MatchHeader::Absent(HeaderName::from_static("foo"));
+
+        let mut h = http::HeaderMap::new();
+        assert!(!present.is_match(&h));
+        assert!(absent.is_match(&h));
+
+        h.insert(HeaderName::from_static("foo"), HeaderValue::from_static(""));
+        assert!(present.is_match(&h));
+        assert!(!absent.is_match(&h));
+    }
 }
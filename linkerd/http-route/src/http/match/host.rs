@@ -9,12 +9,40 @@ pub enum MatchHost {
     /// For example: the match `*.example.com` is stored as `["com",
     /// "example"]`.
     Suffix(Vec<String>),
+
+    /// Matches a host name and the port of the request's authority.
+    ///
+    /// For example: the match `svc:8080` is stored as
+    /// `Port(Exact("svc"), Exact(8080))`.
+    Port(Box<MatchHost>, MatchPort),
+}
+
+/// Matches the port of a request's authority. When the authority does not
+/// specify a port, the scheme's default port is matched.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum MatchPort {
+    Exact(u16),
+
+    /// An inclusive range of ports, e.g. `8000-8099`.
+    Range {
+        min: u16,
+        max: u16,
+    },
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum HostMatch {
     Exact(usize),
     Suffix(usize),
+    Port(Box<HostMatch>, PortMatch),
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum PortMatch {
+    Exact,
+
+    /// The number of ports in the matched range.
+    Range(u32),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -26,6 +54,10 @@ pub enum InvalidHost {
     NumericHost,
 }
 
+#[derive(Debug, thiserror::Error)]
+#[error("{0:?} is not a port or port range")]
+pub struct InvalidPort(String);
+
 // === impl MatchHost ===
 
 impl std::str::FromStr for MatchHost {
@@ -48,10 +80,25 @@ impl std::str::FromStr for MatchHost {
 }
 
 impl MatchHost {
+    /// Restricts this match to requests whose authority has a port that
+    /// matches `port`.
+    pub fn with_port(self, port: MatchPort) -> Self {
+        match self {
+            Self::Port(host, _) => Self::Port(host, port),
+            host => Self::Port(Box::new(host), port),
+        }
+    }
+
     pub fn summarize_match(&self, uri: &Uri) -> Option<HostMatch> {
         let mut host = uri.authority()?.host();
 
         match self {
+            Self::Port(name, port) => {
+                let port = port.summarize_match(uri)?;
+                let name = name.summarize_match(uri)?;
+                Some(HostMatch::Port(Box::new(name), port))
+            }
+
             Self::Exact(h) => {
                 if !h.ends_with('.') {
                     host = host.strip_suffix('.').unwrap_or(host);
@@ -80,6 +127,49 @@ impl MatchHost {
     }
 }
 
+// === impl MatchPort ===
+
+impl std::str::FromStr for MatchPort {
+    type Err = InvalidPort;
+
+    fn from_str(port: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidPort(port.to_string());
+        match port.split_once('-') {
+            None => port.parse().map(Self::Exact).map_err(|_| invalid()),
+            Some((min, max)) => {
+                let min = min.parse().map_err(|_| invalid())?;
+                let max = max.parse().map_err(|_| invalid())?;
+                if min > max {
+                    return Err(invalid());
+                }
+                Ok(Self::Range { min, max })
+            }
+        }
+    }
+}
+
+impl MatchPort {
+    pub fn summarize_match(&self, uri: &Uri) -> Option<PortMatch> {
+        let port = uri.authority()?.port_u16().or_else(|| {
+            let scheme = uri.scheme()?;
+            if *scheme == http::uri::Scheme::HTTP {
+                Some(80)
+            } else if *scheme == http::uri::Scheme::HTTPS {
+                Some(443)
+            } else {
+                None
+            }
+        })?;
+
+        match *self {
+            Self::Exact(p) => (p == port).then_some(PortMatch::Exact),
+            Self::Range { min, max } => (min..=max)
+                .contains(&port)
+                .then_some(PortMatch::Range(u32::from(max - min) + 1)),
+        }
+    }
+}
+
 // === impl HostMatch ===
 
 impl std::cmp::PartialOrd for HostMatch {
@@ -96,6 +186,32 @@ impl std::cmp::Ord for HostMatch {
             (Self::Suffix(l), Self::Suffix(r)) => l.cmp(r),
             (Self::Exact(_), Self::Suffix(_)) => Ordering::Greater,
             (Self::Suffix(_), Self::Exact(_)) => Ordering::Less,
+            // Host names are compared first, so that a port match only
+            // distinguishes otherwise equivalent host matches.
+            (Self::Port(l, lp), Self::Port(r, rp)) => l.cmp(r).then_with(|| lp.cmp(rp)),
+            (Self::Port(l, _), r) => (**l).cmp(r).then(Ordering::Greater),
+            (l, Self::Port(r, _)) => l.cmp(r).then(Ordering::Less),
+        }
+    }
+}
+
+// === impl PortMatch ===
+
+impl std::cmp::PartialOrd for PortMatch {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::cmp::Ord for PortMatch {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        match (self, other) {
+            (Self::Exact, Self::Exact) => Ordering::Equal,
+            // Narrower ranges are preferred.
+            (Self::Range(l), Self::Range(r)) => r.cmp(l),
+            (Self::Exact, Self::Range(_)) => Ordering::Greater,
+            (Self::Range(_), Self::Exact) => Ordering::Less,
         }
     }
 }
@@ -209,6 +325,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn port() {
+        let m = "svc"
+            .parse::<MatchHost>()
+            .expect("svc parses")
+            .with_port(MatchPort::Exact(8080));
+        assert_eq!(
+            m,
+            MatchHost::Port(
+                Box::new(MatchHost::Exact("svc".to_string())),
+                MatchPort::Exact(8080)
+            )
+        );
+        assert_eq!(
+            m.summarize_match(&"http://svc:8080/foo".parse().unwrap()),
+            Some(HostMatch::Port(
+                Box::new(HostMatch::Exact("svc".len())),
+                PortMatch::Exact
+            ))
+        );
+        assert_eq!(
+            m.summarize_match(&"http://svc:9090/foo".parse().unwrap()),
+            None
+        );
+        assert_eq!(m.summarize_match(&"http://svc/foo".parse().unwrap()), None);
+        assert_eq!(
+            m.summarize_match(&"http://other:8080/foo".parse().unwrap()),
+            None
+        );
+
+        let m = "*.example.com"
+            .parse::<MatchHost>()
+            .expect("*.example.com parses")
+            .with_port("8000-8099".parse().expect("port range parses"));
+        assert_eq!(
+            m.summarize_match(&"http://foo.example.com:8080/".parse().unwrap()),
+            Some(HostMatch::Port(
+                Box::new(HostMatch::Suffix(".example.com".len())),
+                PortMatch::Range(100)
+            ))
+        );
+        assert_eq!(
+            m.summarize_match(&"http://foo.example.com:8100/".parse().unwrap()),
+            None
+        );
+
+        // The scheme's default port is matched when the authority has none.
+        let m = "example.com"
+            .parse::<MatchHost>()
+            .unwrap()
+            .with_port(MatchPort::Exact(443));
+        assert!(m
+            .summarize_match(&"https://example.com/".parse().unwrap())
+            .is_some());
+        assert!(m
+            .summarize_match(&"http://example.com/".parse().unwrap())
+            .is_none());
+
+        // Ports are not part of the host name.
+        for invalid in ["svc:8080", "[::1]:80"] {
+            assert!(
+                invalid.parse::<MatchHost>().is_err(),
+                "{invalid} must be invalid"
+            );
+        }
+        for invalid in ["", "http", "9090-8080", "1:2"] {
+            assert!(
+                invalid.parse::<MatchPort>().is_err(),
+                "{invalid} must be invalid"
+            );
+        }
+    }
+
     #[test]
     fn cmp() {
         assert!(HostMatch::Exact("example.com".len()) > HostMatch::Suffix(".example.com".len()));
@@ -220,5 +409,21 @@ mod tests {
             HostMatch::Suffix(".foo.example.com".len()),
             HostMatch::Suffix(".bar.example.com".len())
         );
+
+        let port = |h, p| HostMatch::Port(Box::new(h), p);
+        assert!(
+            port(HostMatch::Exact("svc".len()), PortMatch::Exact) > HostMatch::Exact("svc".len())
+        );
+        assert!(
+            port(HostMatch::Exact("svc".len()), PortMatch::Exact)
+                > port(HostMatch::Exact("svc".len()), PortMatch::Range(10))
+        );
+        assert!(
+            port(HostMatch::Exact("svc".len()), PortMatch::Range(10))
+                > port(HostMatch::Exact("svc".len()), PortMatch::Range(100))
+        );
+        assert!(
+            HostMatch::Exact("svc".len()) > port(HostMatch::Suffix(".svc".len()), PortMatch::Exact)
+        );
     }
 }
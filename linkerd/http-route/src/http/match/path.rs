@@ -1,19 +1,56 @@
 use http::Uri;
 use regex::Regex;
+use std::{borrow::Cow, str::FromStr, sync::Arc};
 
 #[derive(Clone, Debug)]
 pub enum MatchPath {
     Exact(String),
     Prefix(String),
     Regex(Regex),
+    /// Matches paths against a template like `/users/{id}/orders/{order}`,
+    /// capturing the segments named in braces. This match is not yet
+    /// expressible in the control plane API.
+    Template(PathTemplate),
 }
 
+/// A path whose segments are either literal or captured by name.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct PathTemplate {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Capture(Arc<str>),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidPathTemplate {
+    #[error("path templates must start with '/'")]
+    Relative,
+
+    #[error("invalid path template segment: {0}")]
+    Segment(String),
+
+    #[error("duplicate path template capture: {0}")]
+    DuplicateCapture(String),
+}
+
+/// Path segments captured by a template match, by name.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct PathCaptures(Vec<(Arc<str>, String)>);
+
 /// The number of characters matched in the path.
+///
+/// Template matches count only the characters matched by the template's
+/// literal segments, so that more specific templates are preferred.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub(crate) enum PathMatch {
     Exact(usize),
     Regex(usize),
     Prefix(usize),
+    Template { len: usize, captures: PathCaptures },
 }
 
 // === impl MatchPath ===
@@ -49,18 +86,146 @@ impl MatchPath {
                     return Some(PathMatch::Prefix(prefix.len()));
                 }
             }
+
+            Self::Template(template) => return template.match_path(uri.path()),
         }
 
         None
     }
 }
 
+// === impl PathTemplate ===
+
+impl PathTemplate {
+    fn match_path(&self, path: &str) -> Option<PathMatch> {
+        let mut segments = path.strip_prefix('/')?.split('/');
+        let mut captures = Vec::new();
+        let mut captured_len = 0;
+        for segment in &self.segments {
+            let value = segments.next()?;
+            match segment {
+                Segment::Literal(lit) => {
+                    if lit != value {
+                        return None;
+                    }
+                }
+                Segment::Capture(name) => {
+                    if value.is_empty() {
+                        return None;
+                    }
+                    captured_len += value.len();
+                    captures.push((name.clone(), value.to_string()));
+                }
+            }
+        }
+        if segments.next().is_some() {
+            return None;
+        }
+
+        Some(PathMatch::Template {
+            len: path.len() - captured_len,
+            captures: PathCaptures(captures),
+        })
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = InvalidPathTemplate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s.strip_prefix('/').ok_or(InvalidPathTemplate::Relative)?;
+        let mut segments = Vec::new();
+        for segment in path.split('/') {
+            let name = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}'));
+            let segment = match name {
+                Some(name) if !name.is_empty() && !name.contains(['{', '}']) => {
+                    if segments.contains(&Segment::Capture(name.into())) {
+                        return Err(InvalidPathTemplate::DuplicateCapture(name.to_string()));
+                    }
+                    Segment::Capture(name.into())
+                }
+                None if !segment.contains(['{', '}']) => Segment::Literal(segment.to_string()),
+                _ => return Err(InvalidPathTemplate::Segment(segment.to_string())),
+            };
+            segments.push(segment);
+        }
+        Ok(Self { segments })
+    }
+}
+
+// === impl PathCaptures ===
+
+impl PathCaptures {
+    pub(crate) const fn none() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the segment captured with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| &**n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(n, v)| (&**n, v.as_str()))
+    }
+
+    /// Replaces each `{name}` in `s` with the segment captured as `name`.
+    /// Names that were not captured are left as-is.
+    pub fn expand<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        if self.is_empty() || !s.contains('{') {
+            return Cow::Borrowed(s);
+        }
+
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            match tail
+                .find('}')
+                .and_then(|end| Some((self.get(&tail[1..end])?, end)))
+            {
+                Some((value, end)) => {
+                    out.push_str(value);
+                    rest = &tail[end + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = &tail[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        Cow::Owned(out)
+    }
+}
+
+impl std::fmt::Display for PathCaptures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, value)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
 impl std::hash::Hash for MatchPath {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
             Self::Exact(s) => s.hash(state),
             Self::Prefix(s) => s.hash(state),
             Self::Regex(r) => r.as_str().hash(state),
+            Self::Template(t) => t.hash(state),
         };
     }
 }
@@ -73,6 +238,7 @@ impl std::cmp::PartialEq for MatchPath {
             (Self::Exact(s), Self::Exact(o)) => s == o,
             (Self::Prefix(s), Self::Prefix(o)) => s == o,
             (Self::Regex(s), Self::Regex(o)) => s.as_str() == o.as_str(),
+            (Self::Template(s), Self::Template(o)) => s == o,
             _ => false,
         }
     }
@@ -86,6 +252,7 @@ impl PathMatch {
             Self::Exact(len) => *len,
             Self::Regex(len) => *len,
             Self::Prefix(len) => *len,
+            Self::Template { len, .. } => *len,
         }
     }
 }
@@ -199,4 +366,83 @@ mod tests {
         assert_eq!(m.match_length(&"/foo/4/bar".parse().unwrap()), None);
         assert_eq!(m.match_length(&"/foo/bar".parse().unwrap()), None);
     }
+
+    #[test]
+    fn path_template() {
+        let m = MatchPath::Template("/users/{id}/orders/{order}".parse().unwrap());
+        let Some(PathMatch::Template { len, captures }) =
+            m.match_length(&"/users/7/orders/1234".parse().unwrap())
+        else {
+            panic!("template must match");
+        };
+        assert_eq!(len, "/users//orders/".len());
+        assert_eq!(captures.get("id"), Some("7"));
+        assert_eq!(captures.get("order"), Some("1234"));
+        assert_eq!(
+            captures.iter().collect::<Vec<_>>(),
+            vec![("id", "7"), ("order", "1234")]
+        );
+
+        for path in [
+            "/users/7/orders",
+            "/users/7/orders/",
+            "/users//orders/1234",
+            "/users/7/orders/1234/items",
+            "/accounts/7/orders/1234",
+        ] {
+            assert_eq!(
+                m.match_length(&path.parse().unwrap()),
+                None,
+                "{path} must not match"
+            );
+        }
+    }
+
+    #[test]
+    fn path_template_precedence() {
+        let uri = "/users/7".parse().unwrap();
+        let template = MatchPath::Template("/users/{id}".parse().unwrap())
+            .match_length(&uri)
+            .unwrap();
+        let prefix = MatchPath::Prefix("/users".into())
+            .match_length(&uri)
+            .unwrap();
+        let exact = MatchPath::Exact("/users/7".into())
+            .match_length(&uri)
+            .unwrap();
+        assert!(template > prefix);
+        assert!(exact > template);
+    }
+
+    #[test]
+    fn invalid_path_templates() {
+        for template in [
+            "users/{id}",
+            "/users/{}",
+            "/users/{id",
+            "/users/x{id}",
+            "/{id}/{id}",
+        ] {
+            assert!(
+                template.parse::<PathTemplate>().is_err(),
+                "{template} must be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn expands_captures() {
+        let m = MatchPath::Template("/users/{id}".parse().unwrap());
+        let Some(PathMatch::Template { captures, .. }) =
+            m.match_length(&"/users/7".parse().unwrap())
+        else {
+            panic!("template must match");
+        };
+        assert_eq!(captures.expand("/v2/users/{id}"), "/v2/users/7");
+        assert_eq!(captures.expand("{id}-{id}"), "7-7");
+        assert_eq!(captures.expand("{other}/{id}"), "{other}/7");
+        assert_eq!(captures.expand("{id"), "{id");
+        assert_eq!(PathCaptures::default().expand("{id}"), "{id}");
+        assert_eq!(captures.to_string(), "id=7");
+    }
 }
@@ -44,6 +44,92 @@ fn method() {
     assert_eq!(m.match_request(&req), None);
 }
 
+#[test]
+fn websocket_upgrade() {
+    let m = MatchRequest {
+        upgrade: Some(MatchUpgrade::WebSocket),
+        ..MatchRequest::default()
+    };
+
+    let req = http::Request::builder()
+        .uri("http://example.com/chat")
+        .header("connection", "keep-alive, Upgrade")
+        .header("upgrade", "WebSocket")
+        .body(())
+        .unwrap();
+    assert_eq!(
+        m.match_request(&req),
+        Some(RequestMatch {
+            upgrade: true,
+            ..Default::default()
+        })
+    );
+
+    // Plain HTTP requests do not match.
+    let req = http::Request::builder()
+        .uri("http://example.com/chat")
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None);
+
+    // Upgrades to other protocols do not match.
+    let req = http::Request::builder()
+        .uri("http://example.com/chat")
+        .header("connection", "upgrade")
+        .header("upgrade", "h2c")
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None);
+
+    // The upgrade must be requested by the connection header.
+    let req = http::Request::builder()
+        .uri("http://example.com/chat")
+        .header("upgrade", "websocket")
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None);
+
+    // WebSocket handshakes must be GET requests.
+    let req = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("http://example.com/chat")
+        .header("connection", "upgrade")
+        .header("upgrade", "websocket")
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None);
+}
+
+#[test]
+fn content_type() {
+    let m = MatchRequest {
+        content_type: Some("application/json".parse().unwrap()),
+        ..MatchRequest::default()
+    };
+
+    let req = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("http://example.com/upload")
+        .header("content-type", "application/json; charset=utf-8")
+        .body(())
+        .unwrap();
+    assert_eq!(
+        m.match_request(&req),
+        Some(RequestMatch {
+            content_type: true,
+            ..Default::default()
+        })
+    );
+
+    let req = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("http://example.com/upload")
+        .header("content-type", "application/x-protobuf")
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None);
+}
+
 #[test]
 fn headers() {
     let m = MatchRequest {
@@ -132,6 +218,8 @@ fn multiple() {
         )],
         query_params: vec![MatchQueryParam::Exact("foo".to_string(), "bar".to_string())],
         method: Some(http::Method::GET),
+        upgrade: None,
+        content_type: None,
     };
 
     let req = http::Request::builder()
@@ -146,6 +234,8 @@ fn multiple() {
             headers: 1,
             query_params: 1,
             method: true,
+            upgrade: false,
+            content_type: false,
         })
     );
 
@@ -158,3 +248,42 @@ fn multiple() {
         .unwrap();
     assert_eq!(m.match_request(&req), None);
 }
+
+#[test]
+fn narrow() {
+    let mut m = MatchRequest {
+        path: Some(MatchPath::Prefix("/users".to_string())),
+        method: Some(http::Method::GET),
+        ..MatchRequest::default()
+    };
+    m.narrow(&MatchRequest {
+        path: Some(MatchPath::Template("/users/{id}".parse().unwrap())),
+        headers: vec![MatchHeader::Present(HeaderName::from_static("x-user"))],
+        ..MatchRequest::default()
+    });
+
+    let req = http::Request::builder()
+        .uri("http://example.com/users/1")
+        .header("x-user", "1")
+        .body(())
+        .unwrap();
+    assert!(m.match_request(&req).is_some());
+
+    let req = http::Request::builder()
+        .uri("http://example.com/users/1")
+        .body(())
+        .unwrap();
+    assert_eq!(
+        m.match_request(&req),
+        None,
+        "requires the added header match"
+    );
+
+    let req = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("http://example.com/users/1")
+        .header("x-user", "1")
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None, "retains the method match");
+}
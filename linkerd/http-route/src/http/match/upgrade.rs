@@ -0,0 +1,40 @@
+/// Matches requests that ask to upgrade their connection to another protocol.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum MatchUpgrade {
+    /// Matches WebSocket opening handshakes.
+    WebSocket,
+}
+
+// === impl MatchUpgrade ===
+
+impl MatchUpgrade {
+    pub fn is_match<B>(&self, req: &http::Request<B>) -> bool {
+        match self {
+            Self::WebSocket => is_websocket(req),
+        }
+    }
+}
+
+/// Returns true if the request is a WebSocket opening handshake, i.e. an
+/// HTTP/1.1 `GET` request that asks to upgrade its connection to `websocket`.
+pub fn is_websocket<B>(req: &http::Request<B>) -> bool {
+    req.method() == http::Method::GET
+        && has_token(req.headers(), http::header::CONNECTION, "upgrade")
+        && has_token(req.headers(), http::header::UPGRADE, "websocket")
+}
+
+/// Returns true if any of the comma-separated values of the named header
+/// matches the token, ignoring case.
+fn has_token(headers: &http::HeaderMap, name: http::header::HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|v| {
+            // Upgrade protocols may be versioned, e.g. `websocket/13`.
+            let v = v.trim();
+            let v = v.split_once('/').map_or(v, |(p, _)| p);
+            v.eq_ignore_ascii_case(token)
+        })
+}
@@ -8,6 +8,7 @@ use tracing::trace;
 
 pub mod grpc;
 pub mod http;
+pub mod lint;
 
 // Matchers used by both HTTP and gRPC routes.
 pub use self::http::{HostMatch, MatchHeader, MatchHost};
@@ -51,6 +52,12 @@ pub trait Match {
     type Summary: Default + Ord;
 
     fn match_request<B>(&self, req: &::http::Request<B>) -> Option<Self::Summary>;
+
+    /// Returns true if the match applies to all requests with the lowest
+    /// precedence, i.e. it is equivalent to a rule without matches.
+    fn matches_all(&self) -> bool {
+        false
+    }
 }
 
 /// Finds the best matching route policy for a request.
@@ -105,3 +112,13 @@ fn best<M: Ord, P>(matches: impl Iterator<Item = (M, P)>) -> Option<(M, P)> {
     // that the first match wins.
     matches.reduce(|(m0, p0), (m1, p1)| if m0 >= m1 { (m0, p0) } else { (m1, p1) })
 }
+
+// === impl RouteMatch ===
+
+impl<T> RouteMatch<T> {
+    /// Summarizes how the request matched the route's rule.
+    #[inline]
+    pub fn route(&self) -> &T {
+        &self.route
+    }
+}
@@ -0,0 +1,153 @@
+//! Finds rules that can never be selected by [`find`](crate::find).
+//!
+//! Rules are compared by their best match and, when matches are equivalent,
+//! the earliest rule is preferred. So a match that is identical to a match of
+//! an earlier rule (on a route with the same hosts) is never selected.
+
+use crate::{Match, Route};
+
+/// Identifies a rule by the index of its route and its index in that route.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RuleRef {
+    pub route: usize,
+    pub rule: usize,
+}
+
+/// Describes a rule that is (at least partially) shadowed by an earlier rule.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Lint {
+    /// A match is identical to a match of an earlier rule, so requests that
+    /// satisfy it are never handled by the rule.
+    DuplicateMatch {
+        rule: RuleRef,
+        duplicate_of: RuleRef,
+    },
+
+    /// A rule only matches all requests, but an earlier rule also matches all
+    /// requests, so the rule is never selected.
+    Unreachable { rule: RuleRef, shadowed_by: RuleRef },
+}
+
+// === impl Lint ===
+
+impl Lint {
+    /// The rule that is shadowed.
+    pub fn rule(&self) -> RuleRef {
+        match self {
+            Self::DuplicateMatch { rule, .. } | Self::Unreachable { rule, .. } => *rule,
+        }
+    }
+
+    /// The earlier rule that shadows [`Lint::rule`].
+    pub fn shadowed_by(&self) -> RuleRef {
+        match self {
+            Self::DuplicateMatch { duplicate_of, .. } => *duplicate_of,
+            Self::Unreachable { shadowed_by, .. } => *shadowed_by,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DuplicateMatch { .. } => "duplicate_match",
+            Self::Unreachable { .. } => "unreachable_rule",
+        }
+    }
+}
+
+/// Finds the rules in a route table that are shadowed by earlier rules.
+pub fn lint<M: Match + PartialEq, P>(routes: &[Route<M, P>]) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for (route, rt) in routes.iter().enumerate() {
+        // Only rules on routes with the same hosts are compared directly.
+        let earlier = routes[..=route]
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.hosts == rt.hosts)
+            .flat_map(|(i, r)| {
+                r.rules
+                    .iter()
+                    .enumerate()
+                    .map(move |(j, rule)| (RuleRef { route: i, rule: j }, rule))
+            })
+            .collect::<Vec<_>>();
+
+        for (rule, r) in rt.rules.iter().enumerate() {
+            let this = RuleRef { route, rule };
+            let earlier = earlier.iter().take_while(|(e, _)| *e != this);
+
+            for m in &r.matches {
+                let duplicate_of = earlier.clone().find(|(_, e)| e.matches.contains(m));
+                if let Some((duplicate_of, _)) = duplicate_of {
+                    lints.push(Lint::DuplicateMatch {
+                        rule: this,
+                        duplicate_of: *duplicate_of,
+                    });
+                }
+            }
+
+            if r.matches.iter().all(Match::matches_all) {
+                let shadowed_by = earlier
+                    .clone()
+                    .find(|(_, e)| e.matches.iter().all(Match::matches_all));
+                if let Some((shadowed_by, _)) = shadowed_by {
+                    lints.push(Lint::Unreachable {
+                        rule: this,
+                        shadowed_by: *shadowed_by,
+                    });
+                }
+            }
+        }
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{MatchRequest, Rule};
+
+    fn rule(matches: Vec<MatchRequest>) -> Rule<()> {
+        Rule {
+            matches,
+            policy: (),
+        }
+    }
+
+    fn path(p: &str) -> MatchRequest {
+        MatchRequest {
+            path: Some(crate::http::r#match::MatchPath::Exact(p.to_string())),
+            ..MatchRequest::default()
+        }
+    }
+
+    #[test]
+    fn finds_shadowed_rules() {
+        let routes = vec![
+            Route {
+                hosts: vec![],
+                rules: vec![rule(vec![path("/foo")]), rule(vec![])],
+            },
+            Route {
+                hosts: vec!["example.com".parse().unwrap()],
+                rules: vec![rule(vec![path("/foo")]), rule(vec![])],
+            },
+            Route {
+                hosts: vec![],
+                rules: vec![rule(vec![path("/bar"), path("/foo")]), rule(vec![])],
+            },
+        ];
+        assert_eq!(
+            lint(&routes),
+            vec![
+                Lint::DuplicateMatch {
+                    rule: RuleRef { route: 2, rule: 0 },
+                    duplicate_of: RuleRef { route: 0, rule: 0 },
+                },
+                Lint::Unreachable {
+                    rule: RuleRef { route: 2, rule: 1 },
+                    shadowed_by: RuleRef { route: 0, rule: 1 },
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Transcodes JSON HTTP requests into unary gRPC requests.
+//!
+//! A [`Transcoder`] is built from a serialized protobuf `FileDescriptorSet`
+//! that describes the gRPC services it may call. A JSON request is posted to
+//! the path of a unary gRPC method, i.e. `/<package>.<Service>/<Method>`, and
+//! its body is encoded as the method's input message using the proto3 JSON
+//! mapping. The method's output message is likewise returned as JSON.
+//!
+//! `google.api.http` annotations are not interpreted, so methods may only be
+//! called at their gRPC paths, and streaming methods may not be transcoded.
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MethodDescriptor};
+use std::{fmt, hash};
+
+#[cfg(test)]
+mod tests;
+
+/// Transcodes requests to the unary methods described by a descriptor set.
+#[derive(Clone)]
+pub struct Transcoder {
+    descriptor_set: Bytes,
+    pool: DescriptorPool,
+}
+
+/// A unary gRPC method that may be called with JSON messages.
+#[derive(Clone, Debug)]
+pub struct Method(MethodDescriptor);
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid descriptor set: {0}")]
+pub struct InvalidDescriptorSet(#[from] prost_reflect::DescriptorError);
+
+#[derive(Debug, thiserror::Error)]
+pub enum TranscodeError {
+    #[error("invalid JSON message: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid protobuf message: {0}")]
+    Protobuf(#[from] prost::DecodeError),
+
+    #[error("response must contain exactly one gRPC message")]
+    Frame,
+
+    #[error("compressed gRPC messages are not supported")]
+    Compressed,
+}
+
+/// The gRPC status code that indicates success.
+pub const GRPC_OK: i32 = 0;
+
+/// The gRPC status code that indicates an invalid request.
+pub const GRPC_INVALID_ARGUMENT: i32 = 3;
+
+/// The gRPC status code that indicates an internal failure.
+pub const GRPC_INTERNAL: i32 = 13;
+
+// === impl Transcoder ===
+
+impl Transcoder {
+    pub fn from_descriptor_set(descriptor_set: Bytes) -> Result<Self, InvalidDescriptorSet> {
+        let pool = DescriptorPool::decode(descriptor_set.clone())?;
+        Ok(Self {
+            descriptor_set,
+            pool,
+        })
+    }
+
+    /// Returns the unary method served at the given request path, if one is
+    /// described by the descriptor set.
+    pub fn method(&self, path: &str) -> Option<Method> {
+        let (service, method) = path.strip_prefix('/')?.split_once('/')?;
+        let method = self
+            .pool
+            .get_service_by_name(service)?
+            .methods()
+            .find(|m| m.name() == method)?;
+        if method.is_client_streaming() || method.is_server_streaming() {
+            return None;
+        }
+        Some(Method(method))
+    }
+}
+
+impl fmt::Debug for Transcoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let services = self
+            .pool
+            .services()
+            .map(|s| s.full_name().to_string())
+            .collect::<Vec<_>>();
+        f.debug_struct("Transcoder")
+            .field("services", &services)
+            .finish()
+    }
+}
+
+// Transcoders are compared by their descriptor sets so that unchanged policies
+// are recognized as such.
+
+impl PartialEq for Transcoder {
+    fn eq(&self, other: &Self) -> bool {
+        self.descriptor_set == other.descriptor_set
+    }
+}
+
+impl Eq for Transcoder {}
+
+impl hash::Hash for Transcoder {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.descriptor_set.hash(state);
+    }
+}
+
+// === impl Method ===
+
+impl Method {
+    pub fn full_name(&self) -> &str {
+        self.0.full_name()
+    }
+
+    /// Encodes a JSON input message as a length-prefixed gRPC message.
+    pub fn encode_request(&self, json: &[u8]) -> Result<Bytes, TranscodeError> {
+        let mut de = serde_json::Deserializer::from_slice(json);
+        let msg = DynamicMessage::deserialize(self.0.input(), &mut de)?;
+        de.end()?;
+
+        let len = msg.encoded_len();
+        let mut buf = BytesMut::with_capacity(5 + len);
+        buf.put_u8(0);
+        buf.put_u32(len as u32);
+        msg.encode(&mut buf)
+            .expect("buffer must have sufficient capacity");
+        Ok(buf.freeze())
+    }
+
+    /// Decodes a gRPC response body that holds a single length-prefixed
+    /// output message as JSON.
+    pub fn decode_response(&self, mut body: &[u8]) -> Result<Bytes, TranscodeError> {
+        if body.remaining() < 5 {
+            return Err(TranscodeError::Frame);
+        }
+        if body.get_u8() != 0 {
+            return Err(TranscodeError::Compressed);
+        }
+        let len = body.get_u32() as usize;
+        if body.remaining() != len {
+            return Err(TranscodeError::Frame);
+        }
+
+        let msg = DynamicMessage::decode(self.0.output(), body)?;
+        Ok(serde_json::to_vec(&msg)?.into())
+    }
+}
+
+/// Returns the HTTP status that corresponds to a gRPC status code.
+pub fn http_status(code: i32) -> http::StatusCode {
+    use http::StatusCode;
+    match code {
+        0 => StatusCode::OK,
+        1 => StatusCode::from_u16(499).expect("499 is a valid status code"),
+        3 | 9 | 11 => StatusCode::BAD_REQUEST,
+        4 => StatusCode::GATEWAY_TIMEOUT,
+        5 => StatusCode::NOT_FOUND,
+        6 | 10 => StatusCode::CONFLICT,
+        7 => StatusCode::FORBIDDEN,
+        8 => StatusCode::TOO_MANY_REQUESTS,
+        12 => StatusCode::NOT_IMPLEMENTED,
+        14 => StatusCode::SERVICE_UNAVAILABLE,
+        16 => StatusCode::UNAUTHORIZED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Describes a gRPC status as a JSON response body.
+pub fn error_body(code: i32, message: &str) -> Bytes {
+    serde_json::json!({ "code": code, "message": message })
+        .to_string()
+        .into()
+}
@@ -0,0 +1,112 @@
+use super::*;
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    MethodDescriptorProto, ServiceDescriptorProto,
+};
+
+fn field(name: &str, number: i32, ty: Type) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        name: Some(name.to_string()),
+        json_name: Some(name.to_string()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(ty as i32),
+        ..Default::default()
+    }
+}
+
+fn method(name: &str, server_streaming: bool) -> MethodDescriptorProto {
+    MethodDescriptorProto {
+        name: Some(name.to_string()),
+        input_type: Some(".test.HelloRequest".to_string()),
+        output_type: Some(".test.HelloReply".to_string()),
+        server_streaming: Some(server_streaming),
+        ..Default::default()
+    }
+}
+
+fn transcoder() -> Transcoder {
+    let file = FileDescriptorProto {
+        name: Some("test.proto".to_string()),
+        package: Some("test".to_string()),
+        syntax: Some("proto3".to_string()),
+        message_type: vec![
+            DescriptorProto {
+                name: Some("HelloRequest".to_string()),
+                field: vec![field("name", 1, Type::String)],
+                ..Default::default()
+            },
+            DescriptorProto {
+                name: Some("HelloReply".to_string()),
+                field: vec![
+                    field("greeting", 1, Type::String),
+                    field("count", 2, Type::Int32),
+                ],
+                ..Default::default()
+            },
+        ],
+        service: vec![ServiceDescriptorProto {
+            name: Some("Greeter".to_string()),
+            method: vec![method("Hello", false), method("HelloStream", true)],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let set = FileDescriptorSet { file: vec![file] };
+    Transcoder::from_descriptor_set(set.encode_to_vec().into()).expect("descriptors must be valid")
+}
+
+#[test]
+fn finds_unary_methods() {
+    let t = transcoder();
+    assert_eq!(
+        t.method("/test.Greeter/Hello").unwrap().full_name(),
+        "test.Greeter.Hello"
+    );
+    assert!(t.method("/test.Greeter/HelloStream").is_none());
+    assert!(t.method("/test.Greeter/Goodbye").is_none());
+    assert!(t.method("/test.Other/Hello").is_none());
+    assert!(t.method("test.Greeter/Hello").is_none());
+}
+
+#[test]
+fn encodes_requests() {
+    let method = transcoder().method("/test.Greeter/Hello").unwrap();
+    let frame = method.encode_request(br#"{"name": "world"}"#).unwrap();
+    // A field tag, the string's length, and the string.
+    assert_eq!(&frame[..], b"\0\0\0\0\x07\x0a\x05world");
+
+    assert!(matches!(
+        method.encode_request(br#"{"name": 1}"#),
+        Err(TranscodeError::Json(_))
+    ));
+    assert!(matches!(
+        method.encode_request(br#"{"name": "a"} trailing"#),
+        Err(TranscodeError::Json(_))
+    ));
+}
+
+#[test]
+fn decodes_responses() {
+    let method = transcoder().method("/test.Greeter/Hello").unwrap();
+    let json = method
+        .decode_response(b"\0\0\0\0\x09\x0a\x05hello\x10\x02")
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json, serde_json::json!({ "greeting": "hello", "count": 2 }));
+
+    assert!(matches!(
+        method.decode_response(b"\0\0\0\0\x09\x0a"),
+        Err(TranscodeError::Frame)
+    ));
+    assert!(matches!(
+        method.decode_response(b"\x01\0\0\0\0"),
+        Err(TranscodeError::Compressed)
+    ));
+}
+
+#[test]
+fn compares_descriptor_sets() {
+    assert_eq!(transcoder(), transcoder());
+}
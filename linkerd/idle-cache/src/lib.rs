@@ -320,7 +320,6 @@ where
 
 // === impl Cached ===
 
-#[cfg(feature = "test-util")]
 impl<V> Cached<V> {
     /// Returns a new `Cached` handle wrapping the provided value, but *not*
     /// associated with a cache.
@@ -3,17 +3,22 @@
 
 mod boxed;
 mod either;
+mod pool;
 mod prefixed;
+mod rate_limit;
 mod scoped;
 mod sensor;
 
 pub use self::{
     boxed::BoxedIo,
     either::EitherIo,
+    pool::{BufPool, BufPoolStats, PooledBuf},
     prefixed::PrefixedIo,
+    rate_limit::RateLimitedIo,
     scoped::ScopedIo,
     sensor::{Sensor, SensorIo},
 };
+pub use linkerd_errno::Errno;
 pub use std::io::*;
 use std::net::SocketAddr;
 pub use tokio::io::{
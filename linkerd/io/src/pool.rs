@@ -0,0 +1,193 @@
+use parking_lot::{const_mutex, Mutex};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+/// A pool of fixed-size byte buffers.
+///
+/// Buffers are returned to the pool when they are dropped, so that buffers
+/// may be reused rather than allocated for each connection. At most
+/// `max_idle` buffers are retained by the pool; additional buffers are
+/// freed. This bounds the memory held by an idle pool to
+/// `buf_size * max_idle` bytes.
+#[derive(Debug)]
+pub struct BufPool {
+    buf_size: usize,
+    max_idle: AtomicUsize,
+    idle: Mutex<Vec<Box<[u8]>>>,
+    in_use: AtomicUsize,
+    allocated: AtomicU64,
+    reused: AtomicU64,
+}
+
+/// A buffer that is returned to its pool when dropped.
+#[derive(Debug)]
+pub struct PooledBuf {
+    buf: Box<[u8]>,
+    pool: &'static BufPool,
+}
+
+/// A snapshot of a pool's utilization.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BufPoolStats {
+    /// The size of each buffer in the pool, in bytes.
+    pub buf_size: usize,
+
+    /// The maximum number of buffers held by the pool.
+    pub max_idle: usize,
+
+    /// The number of buffers currently held by the pool.
+    pub idle: usize,
+
+    /// The number of buffers taken from the pool that have not been returned.
+    pub in_use: usize,
+
+    /// The total number of buffers allocated by the pool.
+    pub allocated: u64,
+
+    /// The total number of buffers reused from the pool.
+    pub reused: u64,
+}
+
+// === impl BufPool ===
+
+impl BufPool {
+    pub const fn new(buf_size: usize, max_idle: usize) -> Self {
+        Self {
+            buf_size,
+            max_idle: AtomicUsize::new(max_idle),
+            idle: const_mutex(Vec::new()),
+            in_use: AtomicUsize::new(0),
+            allocated: AtomicU64::new(0),
+            reused: AtomicU64::new(0),
+        }
+    }
+
+    /// Sets the maximum number of buffers retained by the pool, freeing any
+    /// idle buffers in excess of the new limit.
+    pub fn set_max_idle(&self, max_idle: usize) {
+        self.max_idle.store(max_idle, Ordering::Relaxed);
+        self.idle.lock().truncate(max_idle);
+    }
+
+    /// Takes a buffer from the pool, allocating a new buffer if none are idle.
+    ///
+    /// Reused buffers are not zeroed and may hold data from prior uses.
+    pub fn get(&'static self) -> PooledBuf {
+        let buf = match self.idle.lock().pop() {
+            Some(buf) => {
+                self.reused.fetch_add(1, Ordering::Relaxed);
+                buf
+            }
+            None => {
+                self.allocated.fetch_add(1, Ordering::Relaxed);
+                vec![0; self.buf_size].into_boxed_slice()
+            }
+        };
+        self.in_use.fetch_add(1, Ordering::Relaxed);
+        PooledBuf { buf, pool: self }
+    }
+
+    pub fn stats(&self) -> BufPoolStats {
+        BufPoolStats {
+            buf_size: self.buf_size,
+            max_idle: self.max_idle.load(Ordering::Relaxed),
+            idle: self.idle.lock().len(),
+            in_use: self.in_use.load(Ordering::Relaxed),
+            allocated: self.allocated.load(Ordering::Relaxed),
+            reused: self.reused.load(Ordering::Relaxed),
+        }
+    }
+
+    fn put(&self, buf: Box<[u8]>) {
+        self.in_use.fetch_sub(1, Ordering::Relaxed);
+        let mut idle = self.idle.lock();
+        if idle.len() < self.max_idle.load(Ordering::Relaxed) {
+            idle.push(buf);
+        }
+    }
+}
+
+// === impl PooledBuf ===
+
+impl Deref for PooledBuf {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl DerefMut for PooledBuf {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.buf
+    }
+}
+
+impl Drop for PooledBuf {
+    fn drop(&mut self) {
+        let buf = std::mem::take(&mut self.buf);
+        self.pool.put(buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_buffers() {
+        static POOL: BufPool = BufPool::new(16, 1);
+
+        let a = POOL.get();
+        let b = POOL.get();
+        assert_eq!(a.len(), 16);
+        assert_eq!(
+            POOL.stats(),
+            BufPoolStats {
+                buf_size: 16,
+                max_idle: 1,
+                idle: 0,
+                in_use: 2,
+                allocated: 2,
+                reused: 0,
+            }
+        );
+
+        // Only one buffer is retained when both are returned.
+        drop((a, b));
+        assert_eq!(POOL.stats().idle, 1);
+        assert_eq!(POOL.stats().in_use, 0);
+
+        let _c = POOL.get();
+        let stats = POOL.stats();
+        assert_eq!(stats.idle, 0);
+        assert_eq!(stats.allocated, 2);
+        assert_eq!(stats.reused, 1);
+    }
+
+    #[test]
+    fn sets_max_idle() {
+        static POOL: BufPool = BufPool::new(16, 2);
+
+        drop((POOL.get(), POOL.get()));
+        assert_eq!(POOL.stats().idle, 2);
+
+        // Excess idle buffers are freed when the limit is lowered.
+        POOL.set_max_idle(1);
+        assert_eq!(POOL.stats().idle, 1);
+
+        drop((POOL.get(), POOL.get()));
+        let stats = POOL.stats();
+        assert_eq!(stats.max_idle, 1);
+        assert_eq!(stats.idle, 1);
+
+        // No buffers are retained when the pool is disabled.
+        POOL.set_max_idle(0);
+        drop(POOL.get());
+        assert_eq!(POOL.stats().idle, 0);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{IoSlice, PeerAddr, Poll};
+use futures::ready;
+use pin_project::pin_project;
+use std::{future::Future, num::NonZeroU64, pin::Pin, task::Context};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf, Result},
+    time::{self, Duration, Instant, Sleep},
+};
+
+/// Limits the rate at which bytes are read from and written to a transport.
+///
+/// Each direction is limited independently by a token bucket that holds up to
+/// one second's worth of bytes. An operation may overdraw the bucket, in which
+/// case subsequent operations in that direction are delayed until the debt is
+/// repaid.
+#[pin_project]
+#[derive(Debug)]
+pub struct RateLimitedIo<T> {
+    #[pin]
+    io: T,
+    read: Option<Bucket>,
+    write: Option<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    bytes_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+    sleep: Pin<Box<Sleep>>,
+    waiting: bool,
+}
+
+// === impl RateLimitedIo ===
+
+impl<T> RateLimitedIo<T> {
+    /// Wraps `io` so that at most `bytes_per_second` bytes are read and
+    /// written per second. When no limit is provided, the transport is not
+    /// limited.
+    pub fn new(io: T, bytes_per_second: Option<NonZeroU64>) -> Self {
+        Self {
+            io,
+            read: bytes_per_second.map(Bucket::new),
+            write: bytes_per_second.map(Bucket::new),
+        }
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for RateLimitedIo<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<()> {
+        let this = self.project();
+        if let Some(bucket) = this.read.as_mut() {
+            ready!(bucket.poll_acquire(cx));
+        }
+        let prev_filled = buf.filled().len();
+        ready!(this.io.poll_read(cx, buf))?;
+        if let Some(bucket) = this.read.as_mut() {
+            bucket.consume(buf.filled().len() - prev_filled);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for RateLimitedIo<T> {
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.project().io.poll_shutdown(cx)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.project().io.poll_flush(cx)
+    }
+
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<usize> {
+        let this = self.project();
+        if let Some(bucket) = this.write.as_mut() {
+            ready!(bucket.poll_acquire(cx));
+        }
+        let sz = ready!(this.io.poll_write(cx, buf))?;
+        if let Some(bucket) = this.write.as_mut() {
+            bucket.consume(sz);
+        }
+        Poll::Ready(Ok(sz))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<usize> {
+        let this = self.project();
+        if let Some(bucket) = this.write.as_mut() {
+            ready!(bucket.poll_acquire(cx));
+        }
+        let sz = ready!(this.io.poll_write_vectored(cx, bufs))?;
+        if let Some(bucket) = this.write.as_mut() {
+            bucket.consume(sz);
+        }
+        Poll::Ready(Ok(sz))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+impl<T: PeerAddr> PeerAddr for RateLimitedIo<T> {
+    fn peer_addr(&self) -> Result<std::net::SocketAddr> {
+        self.io.peer_addr()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: crate::Peek + Send + Sync> crate::Peek for RateLimitedIo<T> {
+    async fn peek(&self, buf: &mut [u8]) -> Result<usize> {
+        // Peeked bytes are accounted for when they are read.
+        self.io.peek(buf).await
+    }
+}
+
+// === impl Bucket ===
+
+impl Bucket {
+    fn new(bytes_per_second: NonZeroU64) -> Self {
+        let bytes_per_second = bytes_per_second.get() as f64;
+        let now = Instant::now();
+        Self {
+            bytes_per_second,
+            tokens: bytes_per_second,
+            last_refill: now,
+            sleep: Box::pin(time::sleep_until(now)),
+            waiting: false,
+        }
+    }
+
+    /// Waits until the bucket is no longer overdrawn.
+    fn poll_acquire(&mut self, cx: &mut Context<'_>) -> std::task::Poll<()> {
+        if !self.waiting {
+            self.refill();
+            if self.tokens > 0.0 {
+                return std::task::Poll::Ready(());
+            }
+
+            let wait = Duration::from_secs_f64(-self.tokens / self.bytes_per_second);
+            self.sleep.as_mut().reset(self.last_refill + wait);
+            self.waiting = true;
+        }
+
+        // Once the sleep completes, the debt has been repaid.
+        ready!(self.sleep.as_mut().poll(cx));
+        self.waiting = false;
+        self.refill();
+        std::task::Poll::Ready(())
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.last_refill = now;
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.bytes_per_second)
+            .min(self.bytes_per_second);
+    }
+
+    fn consume(&mut self, sz: usize) {
+        self.tokens -= sz as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test(start_paused = true)]
+    async fn limits_reads() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut io = RateLimitedIo::new(client, NonZeroU64::new(100));
+
+        let start = Instant::now();
+        server.write_all(&[0; 300]).await.unwrap();
+        let mut buf = [0; 300];
+        io.read_exact(&mut buf).await.unwrap();
+        assert_eq!(Instant::now(), start);
+
+        // The first read overdraws the bucket, so the next read is delayed
+        // until the debt is repaid.
+        server.write_all(&[0; 1]).await.unwrap();
+        io.read_exact(&mut buf[..1]).await.unwrap();
+        assert!(Instant::now().saturating_duration_since(start) >= Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limits_writes() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut io = RateLimitedIo::new(client, NonZeroU64::new(100));
+
+        let start = Instant::now();
+        io.write_all(&[0; 200]).await.unwrap();
+        assert_eq!(Instant::now(), start);
+
+        // The first write overdraws the bucket, so the next write is delayed
+        // until the debt is repaid.
+        io.write_all(&[0; 1]).await.unwrap();
+        assert!(Instant::now().saturating_duration_since(start) >= Duration::from_secs(1));
+
+        let mut buf = [0; 201];
+        server.read_exact(&mut buf).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unlimited() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut io = RateLimitedIo::new(client, None);
+
+        let start = Instant::now();
+        io.write_all(&[0; 1000]).await.unwrap();
+        let mut buf = [0; 1000];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(Instant::now(), start);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::creds::CredsRx;
+use crate::creds::{CredsRx, Roots};
 use linkerd_identity as id;
 use linkerd_io as io;
 use linkerd_meshtls_verifier as verifier;
@@ -8,7 +8,11 @@ use std::{future::Future, pin::Pin, sync::Arc, task::Context};
 use tracing::{debug, trace};
 
 #[derive(Clone)]
-pub struct NewClient(CredsRx);
+pub struct NewClient {
+    rx: CredsRx,
+    roots: Option<Roots>,
+    pins: Arc<[verifier::SpkiPin]>,
+}
 
 #[derive(Clone)]
 pub struct Connect {
@@ -16,6 +20,8 @@ pub struct Connect {
     alpn: Option<Arc<[Vec<u8>]>>,
     id: id::Id,
     server: ServerName,
+    roots: Option<Roots>,
+    pins: Arc<[verifier::SpkiPin]>,
 }
 
 pub type ConnectFuture<I> = Pin<Box<dyn Future<Output = io::Result<ClientIo<I>>> + Send>>;
@@ -27,7 +33,22 @@ pub struct ClientIo<I>(tokio_boring::SslStream<I>);
 
 impl NewClient {
     pub(crate) fn new(rx: CredsRx) -> Self {
-        Self(rx)
+        Self {
+            rx,
+            roots: None,
+            pins: Arc::new([]),
+        }
+    }
+
+    /// Verifies servers with the given trust anchors, rather than the
+    /// workload's, and requires that their certificate chains match one of the
+    /// given public key pins, if any.
+    pub fn with_server_trust(self, roots: Option<Roots>, pins: Arc<[verifier::SpkiPin]>) -> Self {
+        Self {
+            roots,
+            pins,
+            ..self
+        }
     }
 }
 
@@ -35,7 +56,7 @@ impl NewService<ClientTls> for NewClient {
     type Service = Connect;
 
     fn new_service(&self, target: ClientTls) -> Self::Service {
-        Connect::new(target, self.0.clone())
+        Connect::new(target, self)
     }
 }
 
@@ -48,12 +69,14 @@ impl std::fmt::Debug for NewClient {
 // === impl Connect ===
 
 impl Connect {
-    pub(crate) fn new(client_tls: ClientTls, rx: CredsRx) -> Self {
+    pub(crate) fn new(client_tls: ClientTls, new_client: &NewClient) -> Self {
         Self {
-            rx,
+            rx: new_client.rx.clone(),
             alpn: client_tls.alpn.map(|AlpnProtocols(ps)| ps.into()),
             server: client_tls.server_name,
             id: client_tls.server_id.into(),
+            roots: new_client.roots.clone(),
+            pins: new_client.pins.clone(),
         }
     }
 }
@@ -73,10 +96,11 @@ where
     fn call(&mut self, io: I) -> Self::Future {
         let server_name = self.server.clone();
         let server_id = self.id.clone();
+        let pins = self.pins.clone();
         let connector = self
             .rx
             .borrow()
-            .connector(self.alpn.as_deref().unwrap_or(&[]));
+            .connector(self.alpn.as_deref().unwrap_or(&[]), self.roots.as_ref());
         Box::pin(async move {
             let config = connector
                 .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
@@ -108,6 +132,16 @@ where
             })?;
             let cert_der = id::DerX509(cert.to_der()?);
             verifier::verify_id(&cert_der, &server_id)?;
+            if !pins.is_empty() {
+                let chain = io
+                    .ssl()
+                    .peer_cert_chain()
+                    .into_iter()
+                    .flatten()
+                    .map(|c| c.to_der())
+                    .collect::<Result<Vec<_>, _>>()?;
+                verifier::verify_pins(chain.iter().map(Vec::as_slice), &pins)?;
+            }
 
             debug!(
                 tls = io.ssl().version_str(),
@@ -30,6 +30,19 @@ pub fn watch(
     Ok((store, rx))
 }
 
+pub fn roots(roots_pem: &str) -> Result<Roots> {
+    let roots = X509::stack_from_pem(roots_pem.as_bytes())?;
+    if roots.is_empty() {
+        return Err("no trust roots in PEM file".into());
+    }
+    Ok(Roots(roots.into()))
+}
+
+/// Trust anchors that override the workload's trust anchors when a client
+/// verifies servers.
+#[derive(Clone)]
+pub struct Roots(Arc<[X509]>);
+
 pub(crate) struct Creds {
     base: Arc<BaseCreds>,
     certs: Option<Certs>,
@@ -104,7 +117,11 @@ impl Creds {
     }
 
     // TODO(ver) Specify certificate types, signing algorithms, cipher suites..
-    pub(crate) fn connector(&self, alpn_protocols: &[Vec<u8>]) -> Result<ssl::SslConnector> {
+    pub(crate) fn connector(
+        &self,
+        alpn_protocols: &[Vec<u8>],
+        roots: Option<&Roots>,
+    ) -> Result<ssl::SslConnector> {
         // XXX(ver) This function reads from the environment and/or the filesystem. This likely is
         // at best wasteful and at worst unsafe (if another thread were to mutate these environment
         // variables simultaneously, for instance). Unfortunately, the boring APIs don't really give
@@ -117,17 +134,15 @@ impl Creds {
         //conn.set_options(ssl::SslOptions::NO_TLSV1_2);
         conn.clear_options(ssl::SslOptions::NO_TLSV1_3);
 
+        let roots = roots.map_or(&self.base.roots[..], |Roots(roots)| roots);
         tracing::debug!(
-            roots = ?self
-                .base
-                .roots
+            roots = ?roots
                 .iter()
                 .filter_map(|c| super::fingerprint(c))
                 .collect::<Vec<_>>(),
             "Configuring connector roots",
         );
-        let roots = self.root_store()?;
-        conn.set_cert_store(roots);
+        conn.set_cert_store(root_store(roots)?);
 
         if let Some(certs) = &self.certs {
             tracing::debug!(
@@ -152,12 +167,24 @@ impl Creds {
     }
 
     fn root_store(&self) -> Result<boring::x509::store::X509Store> {
-        let mut store = X509StoreBuilder::new()?;
-        for c in &self.base.roots {
-            store.add_cert(c.to_owned())?;
-        }
+        root_store(&self.base.roots)
+    }
+}
+
+fn root_store(roots: &[X509]) -> Result<boring::x509::store::X509Store> {
+    let mut store = X509StoreBuilder::new()?;
+    for c in roots {
+        store.add_cert(c.to_owned())?;
+    }
+
+    Ok(store.build())
+}
+
+// === impl Roots ===
 
-        Ok(store.build())
+impl std::fmt::Debug for Roots {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Roots").field("len", &self.0.len()).finish()
     }
 }
 
@@ -3,8 +3,8 @@ use linkerd_dns_name as dns;
 use linkerd_io as io;
 use linkerd_meshtls_verifier as verifier;
 use linkerd_stack::{Param, Service};
-use linkerd_tls::{ClientId, NegotiatedProtocol, ServerName, ServerTls};
-use std::{future::Future, pin::Pin, sync::Arc, task::Context};
+use linkerd_tls::{CertFingerprint, ClientId, NegotiatedProtocol, ServerName, ServerTls};
+use std::{convert::TryFrom, future::Future, pin::Pin, sync::Arc, task::Context};
 use tracing::debug;
 
 #[derive(Clone)]
@@ -80,6 +80,7 @@ where
                 })?;
 
             let client_id = io.client_identity();
+            let client_fingerprint = io.client_fingerprint();
             let negotiated_protocol = io.negotiated_protocol();
 
             debug!(
@@ -92,6 +93,7 @@ where
             );
             let tls = ServerTls::Established {
                 client_id,
+                client_fingerprint,
                 negotiated_protocol,
             };
             Ok((tls, io))
@@ -110,6 +112,15 @@ impl<I> ServerIo<I> {
             .map(|p| NegotiatedProtocol(p.to_vec()))
     }
 
+    fn client_fingerprint(&self) -> Option<CertFingerprint> {
+        let cert = self.0.ssl().peer_certificate()?;
+        let digest = cert
+            .digest(boring::hash::MessageDigest::sha256())
+            .map_err(|error| tracing::warn!(%error, "Failed to digest client end cert"))
+            .ok()?;
+        <[u8; 32]>::try_from(&digest[..]).ok().map(CertFingerprint)
+    }
+
     fn client_identity(&self) -> Option<ClientId> {
         match self.0.ssl().peer_certificate() {
             Some(cert) => {
@@ -1,3 +1,4 @@
+use crate::creds::Roots;
 use futures::prelude::*;
 use linkerd_identity as id;
 use linkerd_io as io;
@@ -12,6 +13,8 @@ use tokio_rustls::rustls::{self, ClientConfig};
 #[derive(Clone)]
 pub struct NewClient {
     config: watch::Receiver<Arc<ClientConfig>>,
+    roots: Option<Roots>,
+    pins: Arc<[verifier::SpkiPin]>,
 }
 
 /// A `Service` that initiates client-side TLS connections.
@@ -20,6 +23,7 @@ pub struct Connect {
     server_id: id::Id,
     server_name: rustls::ServerName,
     config: Arc<ClientConfig>,
+    pins: Arc<[verifier::SpkiPin]>,
 }
 
 pub type ConnectFuture<I> = Pin<Box<dyn Future<Output = io::Result<ClientIo<I>>> + Send>>;
@@ -31,7 +35,22 @@ pub struct ClientIo<I>(tokio_rustls::client::TlsStream<I>);
 
 impl NewClient {
     pub(crate) fn new(config: watch::Receiver<Arc<ClientConfig>>) -> Self {
-        Self { config }
+        Self {
+            config,
+            roots: None,
+            pins: Arc::new([]),
+        }
+    }
+
+    /// Verifies servers with the given trust anchors, rather than the
+    /// workload's, and requires that their certificate chains match one of the
+    /// given public key pins, if any.
+    pub fn with_server_trust(self, roots: Option<Roots>, pins: Arc<[verifier::SpkiPin]>) -> Self {
+        Self {
+            roots,
+            pins,
+            ..self
+        }
     }
 }
 
@@ -39,7 +58,17 @@ impl NewService<ClientTls> for NewClient {
     type Service = Connect;
 
     fn new_service(&self, target: ClientTls) -> Self::Service {
-        Connect::new(target, (*self.config.borrow()).clone())
+        let config = match self.roots {
+            None => (*self.config.borrow()).clone(),
+            Some(Roots(ref verifier)) => {
+                // The client certificate is still provided by the workload's
+                // configuration.
+                let mut c = (**self.config.borrow()).clone();
+                c.dangerous().set_certificate_verifier(verifier.clone());
+                Arc::new(c)
+            }
+        };
+        Connect::new(target, config, self.pins.clone())
     }
 }
 
@@ -52,7 +81,11 @@ impl std::fmt::Debug for NewClient {
 // === impl Connect ===
 
 impl Connect {
-    pub(crate) fn new(client_tls: ClientTls, config: Arc<ClientConfig>) -> Self {
+    pub(crate) fn new(
+        client_tls: ClientTls,
+        config: Arc<ClientConfig>,
+        pins: Arc<[verifier::SpkiPin]>,
+    ) -> Self {
         // If ALPN protocols are configured by the endpoint, we have to clone the entire
         // configuration and set the protocols. If there are no ALPN options, clone the Arc'd base
         // configuration without extra allocation.
@@ -75,6 +108,7 @@ impl Connect {
             server_id: client_tls.server_id.into(),
             server_name,
             config,
+            pins,
         }
     }
 }
@@ -100,6 +134,7 @@ where
 
     fn call(&mut self, io: I) -> Self::Future {
         let server_id = self.server_id.clone();
+        let pins = self.pins.clone();
         Box::pin(
             // Connect to the server, sending the `server_name` SNI in the
             // client handshake. The provided config should use the
@@ -114,6 +149,8 @@ where
                     let (_, conn) = s.get_ref();
                     let end_cert = extract_cert(conn)?;
                     verifier::verify_id(&end_cert.0, &server_id)?;
+                    let chain = conn.peer_certificates().unwrap_or_default();
+                    verifier::verify_pins(chain.iter().map(|c| &c.0[..]), &pins)?;
                     Ok(ClientIo(s))
                 }),
         )
@@ -21,31 +21,17 @@ pub struct InvalidKey(KeyRejected);
 #[error("invalid trust roots")]
 pub struct InvalidTrustRoots(());
 
+/// Trust anchors that override the workload's trust anchors when a client
+/// verifies servers.
+#[derive(Clone)]
+pub struct Roots(pub(crate) Arc<dyn rustls::client::ServerCertVerifier>);
+
 pub fn watch(
     local_id: id::Id,
     server_name: dns::Name,
     roots_pem: &str,
 ) -> Result<(Store, Receiver)> {
-    let mut roots = rustls::RootCertStore::empty();
-    let certs = match rustls_pemfile::certs(&mut std::io::Cursor::new(roots_pem)) {
-        Err(error) => {
-            warn!(%error, "invalid trust anchors file");
-            return Err(error.into());
-        }
-        Ok(certs) if certs.is_empty() => {
-            warn!("no valid certs in trust anchors file");
-            return Err("no trust roots in PEM file".into());
-        }
-        Ok(certs) => certs,
-    };
-
-    let (added, skipped) = roots.add_parsable_certificates(&certs[..]);
-    if skipped != 0 {
-        warn!("Skipped {} invalid trust anchors", skipped);
-    }
-    if added == 0 {
-        return Err("no trust roots loaded".into());
-    }
+    let roots = parse_roots(roots_pem)?;
 
     // XXX: Rustls's built-in verifiers don't let us tweak things as fully as we'd like (e.g.
     // controlling the set of trusted signature algorithms), but they provide good enough
@@ -88,6 +74,44 @@ pub fn watch(
     Ok((store, rx))
 }
 
+pub fn roots(roots_pem: &str) -> Result<Roots> {
+    let roots = parse_roots(roots_pem)?;
+    Ok(Roots(Arc::new(verify::AnySanVerifier::new(roots))))
+}
+
+fn parse_roots(roots_pem: &str) -> Result<rustls::RootCertStore> {
+    let mut roots = rustls::RootCertStore::empty();
+    let certs = match rustls_pemfile::certs(&mut std::io::Cursor::new(roots_pem)) {
+        Err(error) => {
+            warn!(%error, "invalid trust anchors file");
+            return Err(error.into());
+        }
+        Ok(certs) if certs.is_empty() => {
+            warn!("no valid certs in trust anchors file");
+            return Err("no trust roots in PEM file".into());
+        }
+        Ok(certs) => certs,
+    };
+
+    let (added, skipped) = roots.add_parsable_certificates(&certs[..]);
+    if skipped != 0 {
+        warn!("Skipped {} invalid trust anchors", skipped);
+    }
+    if added == 0 {
+        return Err("no trust roots loaded".into());
+    }
+
+    Ok(roots)
+}
+
+// === impl Roots ===
+
+impl std::fmt::Debug for Roots {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Roots").finish()
+    }
+}
+
 #[cfg(feature = "test-util")]
 pub fn for_test(ent: &linkerd_tls_test_util::Entity) -> (Store, Receiver) {
     watch(
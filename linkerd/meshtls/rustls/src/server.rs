@@ -3,8 +3,10 @@ use linkerd_dns_name as dns;
 use linkerd_io as io;
 use linkerd_meshtls_verifier as verifier;
 use linkerd_stack::{Param, Service};
-use linkerd_tls::{ClientId, NegotiatedProtocol, NegotiatedProtocolRef, ServerName, ServerTls};
-use std::{pin::Pin, sync::Arc, task::Context};
+use linkerd_tls::{
+    CertFingerprint, ClientId, NegotiatedProtocol, NegotiatedProtocolRef, ServerName, ServerTls,
+};
+use std::{convert::TryInto, pin::Pin, sync::Arc, task::Context};
 use thiserror::Error;
 use tokio::sync::watch;
 use tokio_rustls::rustls::{Certificate, ServerConfig};
@@ -109,6 +111,7 @@ where
             .map_ok(|io| {
                 // Determine the peer's identity, if it exist.
                 let client_id = client_identity(&io);
+                let client_fingerprint = client_fingerprint(&io);
 
                 let negotiated_protocol = io
                     .get_ref()
@@ -119,6 +122,7 @@ where
                 debug!(client.id = ?client_id, alpn = ?negotiated_protocol, "Accepted TLS connection");
                 let tls = ServerTls::Established {
                     client_id,
+                    client_fingerprint,
                     negotiated_protocol,
                 };
                 (tls, ServerIo(io))
@@ -134,6 +138,13 @@ fn client_identity<I>(tls: &tokio_rustls::server::TlsStream<I>) -> Option<Client
     verifier::client_identity(c).map(ClientId)
 }
 
+fn client_fingerprint<I>(tls: &tokio_rustls::server::TlsStream<I>) -> Option<CertFingerprint> {
+    let (_io, session) = tls.get_ref();
+    let c = session.peer_certificates()?.first()?;
+    let digest = ring::digest::digest(&ring::digest::SHA256, c.as_ref());
+    digest.as_ref().try_into().ok().map(CertFingerprint)
+}
+
 // === impl ServerIo ===
 
 impl<I: io::AsyncRead + io::AsyncWrite + Unpin> io::AsyncRead for ServerIo<I> {
@@ -1,9 +1,12 @@
+use linkerd_error::Result;
 use linkerd_io as io;
+use linkerd_meshtls_verifier::SpkiPin;
 use linkerd_stack::{NewService, Service};
 use linkerd_tls::{ClientTls, NegotiatedProtocol};
 use std::{
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
@@ -28,6 +31,26 @@ pub enum NewClient {
     NoTls,
 }
 
+/// Overrides how a client authenticates servers, so that connections (e.g. to
+/// the control plane) may be verified independently of the workload's trust
+/// anchors.
+#[derive(Clone, Debug)]
+pub struct ServerTrust {
+    #[cfg_attr(not(feature = "__has_any_tls_impls"), allow(dead_code))]
+    roots: Option<Roots>,
+    #[cfg_attr(not(feature = "__has_any_tls_impls"), allow(dead_code))]
+    pins: Arc<[SpkiPin]>,
+}
+
+#[derive(Clone, Debug)]
+enum Roots {
+    #[cfg(feature = "boring")]
+    Boring(boring::creds::Roots),
+
+    #[cfg(feature = "rustls")]
+    Rustls(rustls::creds::Roots),
+}
+
 #[derive(Clone)]
 pub enum Connect {
     #[cfg(feature = "boring")]
@@ -67,6 +90,29 @@ pub enum ClientIo<I> {
 
 // === impl NewClient ===
 
+impl NewClient {
+    /// Returns a client that authenticates servers as configured by the given
+    /// `ServerTrust`.
+    pub fn with_server_trust(self, trust: &ServerTrust) -> Self {
+        match self {
+            #[cfg(feature = "boring")]
+            Self::Boring(new_client) => Self::Boring(
+                new_client
+                    .with_server_trust(trust.roots.as_ref().map(Roots::boring), trust.pins.clone()),
+            ),
+
+            #[cfg(feature = "rustls")]
+            Self::Rustls(new_client) => Self::Rustls(
+                new_client
+                    .with_server_trust(trust.roots.as_ref().map(Roots::rustls), trust.pins.clone()),
+            ),
+
+            #[cfg(not(feature = "__has_any_tls_impls"))]
+            _ => crate::no_tls!(trust),
+        }
+    }
+}
+
 impl NewService<ClientTls> for NewClient {
     type Service = Connect;
 
@@ -85,6 +131,64 @@ impl NewService<ClientTls> for NewClient {
     }
 }
 
+// === impl ServerTrust ===
+
+impl ServerTrust {
+    /// Parses trust anchors for the given TLS implementation.
+    ///
+    /// If no trust anchors are provided, servers are verified with the
+    /// workload's trust anchors.
+    pub fn new(
+        mode: crate::Mode,
+        roots_pem: Option<&str>,
+        pins: impl IntoIterator<Item = SpkiPin>,
+    ) -> Result<Self> {
+        let roots = roots_pem
+            .map(|roots_pem| Roots::parse(mode, roots_pem))
+            .transpose()?;
+
+        Ok(Self {
+            roots,
+            pins: pins.into_iter().collect(),
+        })
+    }
+}
+
+// === impl Roots ===
+
+impl Roots {
+    fn parse(mode: crate::Mode, roots_pem: &str) -> Result<Self> {
+        match mode {
+            #[cfg(feature = "boring")]
+            crate::Mode::Boring => Ok(Self::Boring(boring::creds::roots(roots_pem)?)),
+
+            #[cfg(feature = "rustls")]
+            crate::Mode::Rustls => Ok(Self::Rustls(rustls::creds::roots(roots_pem)?)),
+
+            #[cfg(not(feature = "__has_any_tls_impls"))]
+            _ => crate::no_tls!(roots_pem),
+        }
+    }
+
+    #[cfg(feature = "boring")]
+    fn boring(&self) -> boring::creds::Roots {
+        match self {
+            Self::Boring(roots) => roots.clone(),
+            #[allow(unreachable_patterns)]
+            _ => panic!("trust anchors must be parsed by the client's TLS implementation"),
+        }
+    }
+
+    #[cfg(feature = "rustls")]
+    fn rustls(&self) -> rustls::creds::Roots {
+        match self {
+            Self::Rustls(roots) => roots.clone(),
+            #[allow(unreachable_patterns)]
+            _ => panic!("trust anchors must be parsed by the client's TLS implementation"),
+        }
+    }
+}
+
 // === impl Connect ===
 
 impl<I> Service<I> for Connect
@@ -17,12 +17,13 @@ pub mod creds;
 mod server;
 
 pub use self::{
-    client::{ClientIo, Connect, ConnectFuture, NewClient},
+    client::{ClientIo, Connect, ConnectFuture, NewClient, ServerTrust},
     server::{Server, ServerIo, TerminateFuture},
 };
 use linkerd_dns_name as dns;
 use linkerd_error::{Error, Result};
 use linkerd_identity as id;
+pub use linkerd_meshtls_verifier::SpkiPin;
 use std::str::FromStr;
 
 #[cfg(feature = "boring")]
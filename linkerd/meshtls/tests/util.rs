@@ -10,7 +10,7 @@ use linkerd_meshtls as meshtls;
 use linkerd_proxy_transport::{
     addrs::*,
     listen::{Addrs, Bind, BindTcp},
-    ConnectTcp, Keepalive, ListenAddr,
+    AcceptShards, ConnectTcp, Keepalive, ListenAddr, ReusePort, SocketOptions,
 };
 use linkerd_stack::{
     layer::Layer, service_fn, ExtractParam, InsertParam, NewService, Param, ServiceExt,
@@ -76,6 +76,12 @@ pub async fn proxy_to_proxy_tls_works(mode: meshtls::Mode) {
         server_result.tls,
         Some(Conditional::Some(tls::ServerTls::Established {
             client_id: Some(tls::ClientId(test_util::BAR_NS1.name.parse().unwrap())),
+            // SHA-256 of the client's DER-encoded leaf certificate.
+            client_fingerprint: Some(
+                "d04d1a9df87b929798dd64928414d70fae3dd0a46460254a78464553dd86c533"
+                    .parse()
+                    .unwrap()
+            ),
             negotiated_protocol: None,
         }))
     );
@@ -243,7 +249,7 @@ where
         let tls = Some(client_server_id.clone());
         let client = async move {
             let conn = tls::Client::layer(client_tls)
-                .layer(ConnectTcp::new(Keepalive(None)))
+                .layer(ConnectTcp::new(Keepalive(None), SocketOptions::default()))
                 .oneshot(Target(server_addr.into(), client_server_id))
                 .await;
             match conn {
@@ -367,6 +373,24 @@ impl Param<Keepalive> for Server {
     }
 }
 
+impl Param<SocketOptions> for Server {
+    fn param(&self) -> SocketOptions {
+        SocketOptions::default()
+    }
+}
+
+impl Param<AcceptShards> for Server {
+    fn param(&self) -> AcceptShards {
+        AcceptShards::default()
+    }
+}
+
+impl Param<ReusePort> for Server {
+    fn param(&self) -> ReusePort {
+        ReusePort::default()
+    }
+}
+
 // === impl ServerParams ===
 
 impl<T> ExtractParam<tls::server::Timeout, T> for ServerParams {
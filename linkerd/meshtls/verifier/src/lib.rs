@@ -1,6 +1,13 @@
-use linkerd_error::Result;
+use linkerd_error::{Error, Result};
 use linkerd_identity::Id;
-use std::io;
+use sha2::{Digest, Sha256};
+use std::{io, str::FromStr};
+
+/// The SHA-256 digest of a certificate's DER-encoded SubjectPublicKeyInfo.
+///
+/// Pins are written as 64 hex characters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SpkiPin([u8; 32]);
 
 fn extract_ids_from_cert(cert: &[u8]) -> Result<Vec<Id>> {
     use x509_parser::prelude::*;
@@ -56,10 +63,57 @@ pub fn verify_id(cert: &[u8], expected_id: &Id) -> io::Result<()> {
     ))
 }
 
+/// Verifies that at least one certificate in the server's chain has a public
+/// key matching one of the given pins. Any chain is accepted if there are no
+/// pins.
+pub fn verify_pins<'c>(
+    chain: impl IntoIterator<Item = &'c [u8]>,
+    pins: &[SpkiPin],
+) -> io::Result<()> {
+    if pins.is_empty() {
+        return Ok(());
+    }
+
+    for cert in chain {
+        match SpkiPin::of_cert(cert) {
+            Ok(pin) if pins.contains(&pin) => return Ok(()),
+            Ok(_) => {}
+            Err(error) => tracing::warn!(%error, "Failed to extract public key from certificate"),
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "certificate chain does not match any pinned public key",
+    ))
+}
+
+// === impl SpkiPin ===
+
+impl SpkiPin {
+    pub fn of_cert(cert: &[u8]) -> Result<Self> {
+        use x509_parser::prelude::*;
+        let (_, c) = X509Certificate::from_der(cert)?;
+        Ok(Self(Sha256::digest(c.public_key().raw).into()))
+    }
+}
+
+impl FromStr for SpkiPin {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut digest = [0; 32];
+        hex::decode_to_slice(s, &mut digest)
+            .map_err(|_| "public key pins must be hex-encoded SHA-256 digests")?;
+        Ok(Self(digest))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::client_identity;
     use crate::verify_id;
+    use crate::{verify_pins, SpkiPin};
     use linkerd_identity::Id;
     use rcgen::{Certificate, CertificateParams, SanType};
 
@@ -285,4 +339,28 @@ mod tests {
         let client_id = client_identity(&cert);
         assert_eq!(client_id, None);
     }
+
+    #[test]
+    fn verifies_pinned_public_keys() {
+        let cert = generate_cert_with_names(vec![]);
+        let other = generate_cert_with_names(vec![]);
+        let pin = SpkiPin::of_cert(&cert).expect("should extract public key");
+        let other_pin = SpkiPin::of_cert(&other).expect("should extract public key");
+
+        assert!(verify_pins([&cert[..]], &[]).is_ok());
+        assert!(verify_pins([&cert[..]], &[other_pin, pin]).is_ok());
+        assert!(verify_pins([&other[..], &cert[..]], &[pin]).is_ok());
+        assert!(verify_pins([&other[..]], &[pin]).is_err());
+        assert!(verify_pins(std::iter::empty(), &[pin]).is_err());
+    }
+
+    #[test]
+    fn parses_hex_pins() {
+        let hex = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        assert!(hex.parse::<SpkiPin>().is_ok());
+        assert!(hex[1..].parse::<SpkiPin>().is_err());
+        assert!("sha256/n4bQgYhMfWWaL+qgxVrQFaO/TxsrC4Is0V1sFbDwCgg="
+            .parse::<SpkiPin>()
+            .is_err());
+    }
 }
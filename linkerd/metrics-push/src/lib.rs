@@ -0,0 +1,131 @@
+//! Pushes the proxy's metrics to an HTTP endpoint, for environments in which
+//! the proxy cannot be scraped reliably (e.g. short-lived pods).
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+mod remote_write;
+
+use hyper::{client::HttpConnector, Body};
+use linkerd_error::Error;
+use linkerd_metrics::{text, FmtMetrics};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time;
+use tracing::{debug, trace, warn};
+
+const TEXT_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The protocol used to push metrics.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Metrics are sent to a Prometheus remote-write receiver.
+    #[default]
+    RemoteWrite,
+    /// Metrics are `PUT` to a Prometheus Pushgateway in the text format,
+    /// replacing the metrics previously pushed to the URL's grouping key.
+    Pushgateway,
+}
+
+#[derive(Clone, Debug)]
+pub struct Pusher {
+    client: hyper::Client<HttpConnector>,
+    uri: http::Uri,
+    format: Format,
+    labels: Vec<(String, String)>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("metrics endpoint responded with {0}")]
+pub struct UnexpectedStatus(http::StatusCode);
+
+// === impl Pusher ===
+
+impl Pusher {
+    /// Creates a pusher that sends metrics to `uri`, which must be an
+    /// `http://` URI.
+    ///
+    /// `labels` are added to each remote-write series. Pushgateway groupings
+    /// are instead described by the URI's path.
+    pub fn new(uri: http::Uri, format: Format, labels: Vec<(String, String)>) -> Self {
+        Self {
+            client: hyper::Client::new(),
+            uri,
+            format,
+            labels,
+        }
+    }
+
+    /// Pushes the metrics in `report` each `interval` until `drain` is
+    /// signaled, at which point the metrics are pushed a final time before
+    /// shutdown proceeds.
+    pub async fn run<R: FmtMetrics>(
+        self,
+        report: R,
+        interval: time::Duration,
+        drain: drain::Watch,
+    ) {
+        debug!(uri = %self.uri, format = ?self.format, ?interval, "Metrics pusher running");
+        let mut ticks = time::interval(interval);
+        ticks.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        // The first tick completes immediately. Skip it so that the proxy has
+        // recorded metrics before the first push.
+        ticks.tick().await;
+
+        let signaled = drain.signaled();
+        tokio::pin!(signaled);
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => {}
+                release = &mut signaled => {
+                    debug!("Pushing metrics before shutdown");
+                    if let Err(error) = time::timeout(interval, self.push(&report)).await {
+                        warn!(%error, "Timed out pushing metrics before shutdown");
+                    }
+                    drop(release);
+                    return;
+                }
+            }
+            if time::timeout(interval, self.push(&report)).await.is_err() {
+                warn!(?interval, "Timed out pushing metrics");
+            }
+        }
+    }
+
+    /// Pushes a single snapshot of the metrics in `report`. Failures are
+    /// logged.
+    async fn push<R: FmtMetrics>(&self, report: &R) {
+        match self.try_push(report).await {
+            Ok(()) => trace!("Pushed metrics"),
+            Err(error) => warn!(%error, uri = %self.uri, "Failed to push metrics"),
+        }
+    }
+
+    async fn try_push<R: FmtMetrics>(&self, report: &R) -> Result<(), Error> {
+        let text = report.as_display().to_string();
+        let req = match self.format {
+            Format::RemoteWrite => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis()
+                    .try_into()
+                    .unwrap_or(i64::MAX);
+                let body = remote_write::encode(&text::parse(&text), &self.labels, timestamp)?;
+                http::Request::post(self.uri.clone())
+                    .header(http::header::CONTENT_TYPE, "application/x-protobuf")
+                    .header(http::header::CONTENT_ENCODING, "snappy")
+                    .header("x-prometheus-remote-write-version", "0.1.0")
+                    .body(Body::from(body))?
+            }
+            Format::Pushgateway => http::Request::put(self.uri.clone())
+                .header(http::header::CONTENT_TYPE, TEXT_CONTENT_TYPE)
+                .body(Body::from(text))?,
+        };
+
+        let rsp = self.client.request(req).await?;
+        if !rsp.status().is_success() {
+            return Err(UnexpectedStatus(rsp.status()).into());
+        }
+        Ok(())
+    }
+}
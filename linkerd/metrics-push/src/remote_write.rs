@@ -0,0 +1,132 @@
+//! Encodes metrics as a Prometheus remote-write request.
+//!
+//! See <https://prometheus.io/docs/concepts/remote_write_spec/>.
+
+use linkerd_metrics::text::Family;
+
+/// The labels added to every series.
+pub(crate) type Labels = [(String, String)];
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct TimeSeries {
+    /// Labels must be sorted by name and include the series' `__name__`.
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    /// Milliseconds since the UNIX epoch.
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
+
+/// Returns a snappy-compressed `WriteRequest` with a series for each sample.
+///
+/// `labels` are added to each series that does not already have a label with
+/// the same name.
+pub(crate) fn encode(
+    families: &[Family],
+    labels: &Labels,
+    timestamp: i64,
+) -> Result<Vec<u8>, snap::Error> {
+    let timeseries = families
+        .iter()
+        .flat_map(|family| {
+            family.samples.iter().map(move |sample| {
+                let mut series = vec![Label {
+                    name: "__name__".to_string(),
+                    value: family.sample_name(sample),
+                }];
+                series.extend(sample.labels.iter().map(|(name, value)| Label {
+                    name: name.clone(),
+                    value: value.clone(),
+                }));
+                for (name, value) in labels {
+                    if !series.iter().any(|l| l.name == *name) {
+                        series.push(Label {
+                            name: name.clone(),
+                            value: value.clone(),
+                        });
+                    }
+                }
+                series.sort_by(|a, b| a.name.cmp(&b.name));
+
+                TimeSeries {
+                    labels: series,
+                    samples: vec![Sample {
+                        value: sample.value,
+                        timestamp,
+                    }],
+                }
+            })
+        })
+        .collect();
+
+    let req = prost::Message::encode_to_vec(&WriteRequest { timeseries });
+    snap::raw::Encoder::new().compress_vec(&req)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_metrics::text;
+    use prost::Message;
+
+    #[test]
+    fn encodes_sorted_labels() {
+        let families = text::parse(
+            "# TYPE request_total counter\n\
+             request_total{direction=\"inbound\",pod=\"web-0\"} 3\n",
+        );
+        let labels = [
+            ("job".to_string(), "linkerd-proxy".to_string()),
+            ("pod".to_string(), "ignored".to_string()),
+        ];
+        let body = encode(&families, &labels, 1_000).expect("must encode");
+
+        let req = snap::raw::Decoder::new()
+            .decompress_vec(&body)
+            .expect("must decompress");
+        let req = WriteRequest::decode(&*req).expect("must decode");
+        assert_eq!(req.timeseries.len(), 1);
+        let ts = &req.timeseries[0];
+        assert_eq!(
+            ts.labels
+                .iter()
+                .map(|l| (l.name.as_str(), l.value.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("__name__", "request_total"),
+                ("direction", "inbound"),
+                ("job", "linkerd-proxy"),
+                ("pod", "web-0"),
+            ]
+        );
+        assert_eq!(
+            ts.samples,
+            vec![Sample {
+                value: 3.0,
+                timestamp: 1_000
+            }]
+        );
+    }
+}
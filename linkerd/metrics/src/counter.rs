@@ -1,9 +1,12 @@
 use super::{
-    fmt::{FmtLabels, FmtMetric},
+    fmt::{is_openmetrics, timestamp, FmtLabels, FmtMetric},
+    histogram::Exemplar,
     Factor,
 };
+use parking_lot::Mutex;
 use std::fmt::{self, Display};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
 
 /// A Prometheus counter is represented by a `Wrapping` unsigned 52-bit integer.
 ///
@@ -17,14 +20,27 @@ use std::sync::atomic::{AtomicU64, Ordering};
 /// [`rate()`]: https://prometheus.io/docs/prometheus/latest/querying/functions/#rate()
 /// [`irate()`]: https://prometheus.io/docs/prometheus/latest/querying/functions/#irate()
 /// [`resets()`]: https://prometheus.io/docs/prometheus/latest/querying/functions/#resets
+///
+/// The time at which a counter is created is reported as its `_created`
+/// timestamp in OpenMetrics output.
 #[derive(Debug)]
-pub struct Counter<F = ()>(AtomicU64, std::marker::PhantomData<F>);
+pub struct Counter<F = ()>(AtomicU64, SystemTime, std::marker::PhantomData<F>);
+
+/// A [`Counter`] that is reported with an exemplar (e.g. a trace ID) of its
+/// most recent increment, if one was recorded.
+///
+/// Exemplars are only reported in OpenMetrics output.
+#[derive(Debug, Default)]
+pub struct CounterWithExemplar<F = ()> {
+    counter: Counter<F>,
+    exemplar: Mutex<Option<Exemplar>>,
+}
 
 // === impl Counter ===
 
 impl<F> Default for Counter<F> {
     fn default() -> Self {
-        Self(AtomicU64::default(), std::marker::PhantomData)
+        Self::from(0)
     }
 }
 
@@ -40,6 +56,11 @@ impl<F> Counter<F> {
     pub fn add(&self, n: u64) {
         self.0.fetch_add(n, Ordering::Release);
     }
+
+    /// Returns the time at which the counter was created.
+    pub fn created(&self) -> SystemTime {
+        self.1
+    }
 }
 
 impl<F: Factor> Counter<F> {
@@ -57,14 +78,14 @@ impl<F: Factor> From<&Counter<F>> for f64 {
 }
 
 impl<F> From<&Counter<F>> for u64 {
-    fn from(Counter(ref counter, _): &Counter<F>) -> u64 {
+    fn from(Counter(ref counter, ..): &Counter<F>) -> u64 {
         counter.load(Ordering::Acquire)
     }
 }
 
 impl<F> From<u64> for Counter<F> {
     fn from(value: u64) -> Self {
-        Counter(value.into(), std::marker::PhantomData)
+        Counter(value.into(), SystemTime::now(), std::marker::PhantomData)
     }
 }
 
@@ -72,7 +93,7 @@ impl<F: Factor> FmtMetric for Counter<F> {
     const KIND: &'static str = "counter";
 
     fn fmt_metric<N: Display>(&self, f: &mut fmt::Formatter<'_>, name: N) -> fmt::Result {
-        writeln!(f, "{} {}", name, self.value())
+        self.fmt_samples(f, name, None, None)
     }
 
     fn fmt_metric_labeled<N, L>(
@@ -85,9 +106,104 @@ impl<F: Factor> FmtMetric for Counter<F> {
         L: FmtLabels,
         N: Display,
     {
-        write!(f, "{}{{", name)?;
-        labels.fmt_labels(f)?;
-        writeln!(f, "}} {}", self.value())
+        self.fmt_samples(f, name, Some(&labels), None)
+    }
+}
+
+impl<F: Factor> Counter<F> {
+    fn fmt_samples<N: Display>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: Option<&dyn FmtLabels>,
+        exemplar: Option<&Exemplar>,
+    ) -> fmt::Result {
+        write!(f, "{}", name)?;
+        if let Some(labels) = labels {
+            f.write_str("{")?;
+            labels.fmt_labels(f)?;
+            f.write_str("}")?;
+        }
+        write!(f, " {}", self.value())?;
+        if let Some(ex) = exemplar.filter(|_| is_openmetrics(f)) {
+            ex.fmt_suffix(f)?;
+        }
+        f.write_str("\n")?;
+        self.fmt_created(f, name, labels)
+    }
+}
+
+impl<F> Counter<F> {
+    /// Writes the counter's `_created` sample when formatting OpenMetrics.
+    ///
+    /// Counters whose names do not end in `_total` are reported as `unknown`
+    /// metrics and so have no `_created` sample.
+    fn fmt_created<N: Display>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: Option<&dyn FmtLabels>,
+    ) -> fmt::Result {
+        if !is_openmetrics(f) {
+            return Ok(());
+        }
+        let name = name.to_string();
+        let Some(family) = name.strip_suffix("_total") else {
+            return Ok(());
+        };
+
+        write!(f, "{}_created", family)?;
+        if let Some(labels) = labels {
+            f.write_str("{")?;
+            labels.fmt_labels(f)?;
+            f.write_str("}")?;
+        }
+        writeln!(f, " {}", timestamp(self.created()))
+    }
+}
+
+// === impl CounterWithExemplar ===
+
+impl<F> CounterWithExemplar<F> {
+    pub fn incr(&self) {
+        self.counter.incr()
+    }
+
+    /// Increments the counter, recording the increment as an exemplar with
+    /// the given labels.
+    pub fn incr_with_exemplar(&self, labels: Vec<(&'static str, String)>) {
+        self.counter.incr();
+        *self.exemplar.lock() = Some(Exemplar::new(labels, 1.0));
+    }
+}
+
+impl<F: Factor> CounterWithExemplar<F> {
+    pub fn value(&self) -> f64 {
+        self.counter.value()
+    }
+}
+
+impl<F: Factor> FmtMetric for CounterWithExemplar<F> {
+    const KIND: &'static str = "counter";
+
+    fn fmt_metric<N: Display>(&self, f: &mut fmt::Formatter<'_>, name: N) -> fmt::Result {
+        let exemplar = self.exemplar.lock();
+        self.counter.fmt_samples(f, name, None, exemplar.as_ref())
+    }
+
+    fn fmt_metric_labeled<N, L>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: L,
+    ) -> fmt::Result
+    where
+        L: FmtLabels,
+        N: Display,
+    {
+        let exemplar = self.exemplar.lock();
+        self.counter
+            .fmt_samples(f, name, Some(&labels), exemplar.as_ref())
     }
 }
 
@@ -123,4 +239,31 @@ mod tests {
         let max = Counter::<()>::from(MAX_PRECISE_UINT64);
         assert_eq!(max.value(), MAX_PRECISE_UINT64 as f64);
     }
+
+    #[test]
+    fn exemplars_only_in_openmetrics() {
+        use crate::FmtMetrics;
+
+        struct Fmt(CounterWithExemplar);
+        impl FmtMetrics for Fmt {
+            fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt_metric(f, "requests_total")
+            }
+        }
+
+        let c = Fmt(CounterWithExemplar::default());
+        c.0.incr();
+        c.0.incr_with_exemplar(vec![("trace_id", "abc123".to_string())]);
+        assert_eq!(c.0.value(), 2.0);
+
+        let text = format!("{}", c.as_display());
+        assert_eq!(text, "requests_total 2\n");
+
+        let text = format!("{:#}", c.as_display());
+        assert!(
+            text.starts_with("requests_total 2 # {trace_id=\"abc123\"} 1 "),
+            "{text}"
+        );
+        assert!(text.contains("\nrequests_created "), "{text}");
+    }
 }
@@ -1,7 +1,11 @@
 use std::fmt;
 use std::marker::{PhantomData, Sized};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Writes a block of metrics in prometheus-formatted output.
+///
+/// Metrics are written in the OpenMetrics text format when formatted with the
+/// alternate flag (i.e. `{:#}`) and in the Prometheus text format otherwise.
 pub trait FmtMetrics {
     fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
 
@@ -84,6 +88,21 @@ impl<'a, N: fmt::Display, M: FmtMetric> Metric<'a, N, M> {
 
     /// Formats help messages for this metric.
     pub fn fmt_help(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_openmetrics(f) {
+            // OpenMetrics names counter families without their `_total`
+            // suffix. Counters that lack the suffix can't be represented as
+            // counters, so they're reported as `unknown`.
+            let name = self.name.to_string();
+            let (family, kind) = match (M::KIND, name.strip_suffix("_total")) {
+                ("counter", Some(family)) => (family, M::KIND),
+                ("counter", None) => (&*name, "unknown"),
+                (kind, _) => (&*name, kind),
+            };
+            writeln!(f, "# HELP {} {}", family, self.help)?;
+            writeln!(f, "# TYPE {} {}", family, kind)?;
+            return Ok(());
+        }
+
         writeln!(f, "# HELP {} {}", self.name, self.help)?;
         writeln!(f, "# TYPE {} {}", self.name, M::KIND)?;
         Ok(())
@@ -146,6 +165,18 @@ impl<N: Clone + fmt::Display, M: FmtMetric> Clone for Metric<'_, N, M> {
 
 impl<N: Copy + fmt::Display, M: FmtMetric> Copy for Metric<'_, N, M> {}
 
+/// Returns true if metrics are being written in the OpenMetrics text format.
+pub(crate) fn is_openmetrics(f: &fmt::Formatter<'_>) -> bool {
+    f.alternate()
+}
+
+/// Formats a timestamp as fractional seconds since the Unix epoch.
+pub(crate) fn timestamp(t: SystemTime) -> f64 {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default()
+}
+
 // === impl FmtLabels ===
 
 impl<'a, A: FmtLabels + 'a> FmtLabels for &'a A {
@@ -1,14 +1,22 @@
+use parking_lot::Mutex;
 use std::fmt;
 use std::marker::PhantomData;
+use std::time::SystemTime;
 use std::{cmp, iter, slice};
 
-use super::{Counter, Factor, FmtLabels, FmtMetric};
+use super::{
+    fmt::{is_openmetrics, timestamp},
+    Counter, Factor, FmtLabels, FmtMetric,
+};
 
 /// A series of latency values and counts.
+///
+/// Observed values are scaled by `F` before they are compared to the
+/// histogram's bounds and when the sum of all observations is reported.
 #[derive(Debug)]
 pub struct Histogram<V: Into<u64>, F = ()> {
     bounds: &'static Bounds,
-    buckets: Box<[Counter<F>]>,
+    buckets: Box<[Counter]>,
 
     /// The total sum of all observed latency values.
     ///
@@ -30,11 +38,27 @@ pub struct Histogram<V: Into<u64>, F = ()> {
     // TODO: Implement Prometheus reset semantics correctly, taking into consideration
     //       that Prometheus represents this as `f64` and so there are only 52 significant
     //       bits.
-    sum: Counter,
+    sum: Counter<F>,
+
+    /// The most recent exemplar observed in each bucket. This is empty until
+    /// an exemplar is recorded.
+    exemplars: Mutex<Box<[Option<Exemplar>]>>,
+
+    created: SystemTime,
 
     _p: PhantomData<V>,
 }
 
+/// Associates an observation with a trace.
+///
+/// Exemplars are only reported in OpenMetrics output.
+#[derive(Clone, Debug)]
+pub struct Exemplar {
+    labels: Vec<(&'static str, String)>,
+    value: f64,
+    timestamp: SystemTime,
+}
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Bucket {
     Le(f64),
@@ -45,9 +69,19 @@ pub enum Bucket {
 #[derive(Debug)]
 pub struct Bounds(pub &'static [Bucket]);
 
+#[derive(Debug, thiserror::Error)]
+#[error("histogram bounds must be positive, finite, and strictly increasing")]
+pub struct InvalidBounds(());
+
 /// Helper that lazily formats an `{K}="{V}"`" label.
 struct Label<K: fmt::Display, V: fmt::Display>(K, V);
 
+/// Helper that formats optional labels followed by an optional `le` label.
+pub(crate) struct SampleLabels<'l>(
+    pub(crate) Option<&'l dyn FmtLabels>,
+    pub(crate) Option<&'l Bucket>,
+);
+
 // === impl Histogram ===
 
 impl<V: Into<u64>, F: Factor> Histogram<V, F> {
@@ -64,14 +98,33 @@ impl<V: Into<u64>, F: Factor> Histogram<V, F> {
             bounds,
             buckets: buckets.into_boxed_slice(),
             sum: Counter::default(),
+            exemplars: Mutex::new(Box::new([])),
+            created: SystemTime::now(),
             _p: PhantomData,
         }
     }
 
     pub fn add<U: Into<V>>(&self, u: U) {
+        let v: V = u.into();
+        self.observe(v.into());
+    }
+
+    /// Records an observation along with labels (e.g. a trace ID) that
+    /// identify it as an exemplar of its bucket.
+    pub fn add_with_exemplar<U: Into<V>>(&self, u: U, labels: Vec<(&'static str, String)>) {
         let v: V = u.into();
         let value: u64 = v.into();
+        let idx = self.observe(value);
+
+        let mut exemplars = self.exemplars.lock();
+        if exemplars.is_empty() {
+            *exemplars = vec![None; self.buckets.len()].into_boxed_slice();
+        }
+        exemplars[idx] = Some(Exemplar::new(labels, F::factor(value)));
+    }
 
+    /// Records a value, returning the index of its bucket.
+    fn observe(&self, value: u64) -> usize {
         let idx = self
             .bounds
             .0
@@ -84,6 +137,50 @@ impl<V: Into<u64>, F: Factor> Histogram<V, F> {
 
         self.buckets[idx].incr();
         self.sum.add(value);
+        idx
+    }
+
+    fn fmt_samples<N: fmt::Display>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: Option<&dyn FmtLabels>,
+    ) -> fmt::Result {
+        let openmetrics = is_openmetrics(f);
+        let exemplars = if openmetrics {
+            Some(self.exemplars.lock())
+        } else {
+            None
+        };
+
+        let mut total = 0u64;
+        for (idx, (le, count)) in self.into_iter().enumerate() {
+            total = total.wrapping_add(count.into());
+            write!(
+                f,
+                "{}_bucket{} {}",
+                name,
+                SampleLabels(labels, Some(le)),
+                <()>::factor(total)
+            )?;
+            let exemplar = exemplars
+                .as_ref()
+                .and_then(|ex| ex.get(idx))
+                .and_then(Option::as_ref);
+            if let Some(ex) = exemplar {
+                ex.fmt_suffix(f)?;
+            }
+            f.write_str("\n")?;
+        }
+        drop(exemplars);
+
+        let labels = SampleLabels(labels, None);
+        writeln!(f, "{}_count{} {}", name, labels, <()>::factor(total))?;
+        writeln!(f, "{}_sum{} {}", name, labels, self.sum.value())?;
+        if openmetrics {
+            writeln!(f, "{}_created{} {}", name, labels, timestamp(self.created))?;
+        }
+        Ok(())
     }
 }
 
@@ -172,8 +269,8 @@ impl<V: Into<u64>, F: Factor + std::fmt::Debug> Histogram<V, F> {
 }
 
 impl<'a, V: Into<u64>, F> IntoIterator for &'a Histogram<V, F> {
-    type Item = (&'a Bucket, &'a Counter<F>);
-    type IntoIter = iter::Zip<slice::Iter<'a, Bucket>, slice::Iter<'a, Counter<F>>>;
+    type Item = (&'a Bucket, &'a Counter);
+    type IntoIter = iter::Zip<slice::Iter<'a, Bucket>, slice::Iter<'a, Counter>>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.bounds.0.iter().zip(self.buckets.iter())
@@ -184,14 +281,7 @@ impl<V: Into<u64>, F: Factor> FmtMetric for Histogram<V, F> {
     const KIND: &'static str = "histogram";
 
     fn fmt_metric<N: fmt::Display>(&self, f: &mut fmt::Formatter<'_>, name: N) -> fmt::Result {
-        let total = Counter::<F>::new();
-        for (le, count) in self {
-            total.add(count.into());
-            total.fmt_metric_labeled(f, format_args!("{}_bucket", &name), Label("le", le))?;
-        }
-        total.fmt_metric(f, format_args!("{}_count", &name))?;
-        self.sum.fmt_metric(f, format_args!("{}_sum", &name))?;
-        Ok(())
+        self.fmt_samples(f, name, None)
     }
 
     fn fmt_metric_labeled<N, L>(
@@ -204,19 +294,62 @@ impl<V: Into<u64>, F: Factor> FmtMetric for Histogram<V, F> {
         N: fmt::Display,
         L: FmtLabels,
     {
-        let total = Counter::<F>::new();
-        for (le, count) in self {
-            total.add(count.into());
-            total.fmt_metric_labeled(
-                f,
-                format_args!("{}_bucket", &name),
-                (&labels, Label("le", le)),
-            )?;
+        self.fmt_samples(f, name, Some(&labels))
+    }
+}
+
+// === impl Bounds ===
+
+impl Bounds {
+    /// Builds bounds from a list of bucket upper bounds, followed by a final
+    /// `+Inf` bucket.
+    ///
+    /// Histograms require static bounds, so the bounds are leaked. This is
+    /// intended to be used when the process is configured.
+    pub fn leak(upper_bounds: &[f64]) -> Result<&'static Self, InvalidBounds> {
+        if upper_bounds.is_empty() {
+            return Err(InvalidBounds(()));
         }
-        total.fmt_metric_labeled(f, format_args!("{}_count", &name), &labels)?;
-        self.sum
-            .fmt_metric_labeled(f, format_args!("{}_sum", &name), &labels)?;
-        Ok(())
+        let mut prior = 0.0;
+        for &bound in upper_bounds {
+            if !bound.is_finite() || bound <= prior {
+                return Err(InvalidBounds(()));
+            }
+            prior = bound;
+        }
+
+        let buckets = upper_bounds
+            .iter()
+            .map(|&b| Bucket::Le(b))
+            .chain(Some(Bucket::Inf))
+            .collect::<Vec<_>>();
+        Ok(Box::leak(Box::new(Bounds(Box::leak(
+            buckets.into_boxed_slice(),
+        )))))
+    }
+}
+
+// === impl Exemplar ===
+
+impl Exemplar {
+    pub(crate) fn new(labels: Vec<(&'static str, String)>, value: f64) -> Self {
+        Self {
+            labels,
+            value,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Writes the exemplar as the suffix of an OpenMetrics bucket sample.
+    pub(crate) fn fmt_suffix(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(" # {")?;
+        for (i, (k, v)) in self.labels.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}=\"{}\"", k, v)?;
+        }
+        write!(f, "}} {} {}", self.value, timestamp(self.timestamp))
     }
 }
 
@@ -228,6 +361,33 @@ impl<K: fmt::Display, V: fmt::Display> FmtLabels for Label<K, V> {
     }
 }
 
+// === impl SampleLabels ===
+
+impl fmt::Display for SampleLabels<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.0, self.1) {
+            (None, None) => Ok(()),
+            (Some(labels), None) => {
+                f.write_str("{")?;
+                labels.fmt_labels(f)?;
+                f.write_str("}")
+            }
+            (None, Some(le)) => {
+                f.write_str("{")?;
+                Label("le", le).fmt_labels(f)?;
+                f.write_str("}")
+            }
+            (Some(labels), Some(le)) => {
+                f.write_str("{")?;
+                labels.fmt_labels(f)?;
+                f.write_str(",")?;
+                Label("le", le).fmt_labels(f)?;
+                f.write_str("}")
+            }
+        }
+    }
+}
+
 // === impl Bucket ===
 
 impl fmt::Display for Bucket {
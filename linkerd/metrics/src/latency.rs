@@ -1,6 +1,10 @@
+use std::fmt;
+use std::sync::OnceLock;
 use std::time::Duration;
 
 use super::histogram::{Bounds, Bucket, Histogram};
+use super::sparse::{GrowthFactor, SparseHistogram};
+use super::{Factor, FmtLabels, FmtMetric};
 
 /// The maximum value (inclusive) for each latency bucket in
 /// milliseconds.
@@ -34,6 +38,42 @@ pub const BOUNDS: &Bounds = &Bounds(&[
     Bucket::Inf,
 ]);
 
+/// When set, latency histograms use sparse buckets with this growth factor.
+static SPARSE_GROWTH_FACTOR: OnceLock<GrowthFactor> = OnceLock::new();
+
+/// A latency histogram, in milliseconds, that uses either fixed or sparse
+/// buckets.
+///
+/// Latencies are recorded with microsecond precision so that bucket bounds
+/// may be smaller than a millisecond.
+#[derive(Debug)]
+pub enum LatencyHistogram {
+    Fixed(Histogram<Us, UsAsMs>),
+    Sparse(SparseHistogram<Us, UsAsMs>),
+}
+
+/// Describes the buckets of a [`LatencyHistogram`].
+#[derive(Copy, Clone, Debug)]
+pub enum Buckets {
+    Fixed(&'static Bounds),
+    Sparse(GrowthFactor),
+}
+
+/// Scales microseconds to milliseconds.
+#[derive(Debug)]
+pub struct UsAsMs(());
+
+/// Configures all latency histograms created after this call to use sparse
+/// buckets with the given growth factor, unless their buckets are configured
+/// explicitly.
+///
+/// This is intended to be called once, as the process starts. If sparse
+/// buckets have already been configured, the existing configuration is
+/// retained and an error is returned.
+pub fn use_sparse_buckets(growth_factor: GrowthFactor) -> Result<(), GrowthFactor> {
+    SPARSE_GROWTH_FACTOR.set(growth_factor)
+}
+
 /// A duration in milliseconds.
 #[derive(Debug, Default, Clone)]
 pub struct Ms(Duration);
@@ -83,3 +123,80 @@ impl Default for Histogram<Ms> {
         Histogram::new(BOUNDS)
     }
 }
+
+impl Factor for UsAsMs {
+    fn factor(n: u64) -> f64 {
+        <()>::factor(n) / 1_000.0
+    }
+}
+
+// === impl Buckets ===
+
+impl Default for Buckets {
+    /// Uses sparse buckets if they have been configured and [`BOUNDS`]
+    /// otherwise.
+    fn default() -> Self {
+        match SPARSE_GROWTH_FACTOR.get() {
+            Some(gf) => Self::Sparse(*gf),
+            None => Self::Fixed(BOUNDS),
+        }
+    }
+}
+
+// === impl LatencyHistogram ===
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new(Buckets::default())
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new(buckets: Buckets) -> Self {
+        match buckets {
+            Buckets::Fixed(bounds) => Self::Fixed(Histogram::new(bounds)),
+            Buckets::Sparse(gf) => Self::Sparse(SparseHistogram::new(gf)),
+        }
+    }
+
+    pub fn add<U: Into<Us>>(&self, u: U) {
+        match self {
+            Self::Fixed(h) => h.add(u),
+            Self::Sparse(h) => h.add(u),
+        }
+    }
+
+    pub fn add_with_exemplar<U: Into<Us>>(&self, u: U, labels: Vec<(&'static str, String)>) {
+        match self {
+            Self::Fixed(h) => h.add_with_exemplar(u, labels),
+            Self::Sparse(h) => h.add_with_exemplar(u, labels),
+        }
+    }
+}
+
+impl FmtMetric for LatencyHistogram {
+    const KIND: &'static str = "histogram";
+
+    fn fmt_metric<N: fmt::Display>(&self, f: &mut fmt::Formatter<'_>, name: N) -> fmt::Result {
+        match self {
+            Self::Fixed(h) => h.fmt_metric(f, name),
+            Self::Sparse(h) => h.fmt_metric(f, name),
+        }
+    }
+
+    fn fmt_metric_labeled<N, L>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: L,
+    ) -> fmt::Result
+    where
+        N: fmt::Display,
+        L: FmtLabels,
+    {
+        match self {
+            Self::Fixed(h) => h.fmt_metric_labeled(f, name, labels),
+            Self::Sparse(h) => h.fmt_metric_labeled(f, name, labels),
+        }
+    }
+}
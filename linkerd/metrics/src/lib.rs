@@ -13,16 +13,19 @@ mod new_metrics;
 #[cfg(feature = "process")]
 pub mod process;
 mod serve;
+mod sparse;
 mod store;
+pub mod text;
 
 #[cfg(feature = "linkerd-stack")]
 pub use self::new_metrics::NewMetrics;
 pub use self::{
-    counter::Counter,
+    counter::{Counter, CounterWithExemplar},
     fmt::{FmtLabels, FmtMetric, FmtMetrics, Metric},
     gauge::Gauge,
-    histogram::Histogram,
+    histogram::{Bounds, Bucket, Exemplar, Histogram, InvalidBounds},
     serve::Serve,
+    sparse::{GrowthFactor, InvalidGrowthFactor, SparseHistogram},
     store::{LastUpdate, SharedStore, Store},
 };
 
@@ -53,7 +56,11 @@ pub mod prom {
     impl crate::FmtMetrics for Report {
         #[inline]
         fn fmt_metrics(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            encoding::text::encode(f, self)
+            // The registry's output is terminated by an `# EOF` marker, which
+            // must only be written once all other metrics have been written.
+            let mut buf = String::new();
+            encoding::text::encode(&mut buf, self)?;
+            f.write_str(buf.strip_suffix("# EOF\n").unwrap_or(&buf))
         }
     }
 }
@@ -9,6 +9,7 @@ use std::{fmt, hash::Hash, marker::PhantomData, sync::Arc};
 /// service uses the inner service and the `M`-typed sensor to construct a new `S`-typed service.
 pub struct NewMetrics<N, K: Hash + Eq, M, S> {
     store: SharedStore<K, M>,
+    new_metric: Arc<dyn Fn() -> M + Send + Sync>,
     inner: N,
     _svc: PhantomData<fn() -> S>,
 }
@@ -17,9 +18,23 @@ impl<N, K, M, S> NewMetrics<N, K, M, S>
 where
     K: Hash + Eq,
 {
-    pub fn layer(store: SharedStore<K, M>) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+    pub fn layer(store: SharedStore<K, M>) -> impl svc::layer::Layer<N, Service = Self> + Clone
+    where
+        M: Default + 'static,
+    {
+        Self::layer_with(store, M::default)
+    }
+
+    /// Like [`NewMetrics::layer`], but sensors are constructed with
+    /// `new_metric` rather than by their `Default` implementation.
+    pub fn layer_with(
+        store: SharedStore<K, M>,
+        new_metric: impl Fn() -> M + Send + Sync + 'static,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let new_metric: Arc<dyn Fn() -> M + Send + Sync> = Arc::new(new_metric);
         svc::layer::mk(move |inner| Self {
             store: store.clone(),
+            new_metric: new_metric.clone(),
             inner,
             _svc: PhantomData,
         })
@@ -31,7 +46,6 @@ where
     T: svc::Param<K>,
     N: svc::NewService<T>,
     S: From<(N::Service, Arc<M>)>,
-    M: Default,
     K: Hash + Eq,
 {
     type Service = S;
@@ -39,7 +53,12 @@ where
     fn new_service(&self, target: T) -> Self::Service {
         let key = target.param();
         let inner = self.inner.new_service(target);
-        let metric = self.store.lock().get_or_default(key).clone();
+        let metric = self
+            .store
+            .lock()
+            .entry(key)
+            .or_insert_with(|| Arc::new((self.new_metric)()))
+            .clone();
         S::from((inner, metric))
     }
 }
@@ -52,6 +71,7 @@ where
     fn clone(&self) -> Self {
         Self {
             store: self.store.clone(),
+            new_metric: self.new_metric.clone(),
             inner: self.inner.clone(),
             _svc: PhantomData,
         }
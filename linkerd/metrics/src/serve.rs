@@ -5,7 +5,15 @@ use tracing::trace;
 
 use super::FmtMetrics;
 
+const TEXT_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const OPENMETRICS_MEDIA_TYPE: &str = "application/openmetrics-text";
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
 /// Serve Prometheues metrics.
+///
+/// Metrics are served in the OpenMetrics text format when the client accepts
+/// it and in the Prometheus text format otherwise. Responses are
+/// gzip-compressed when the client accepts gzip encoding.
 #[derive(Debug, Clone)]
 pub struct Serve<M> {
     metrics: M,
@@ -19,37 +27,171 @@ impl<M> Serve<M> {
     }
 
     fn is_gzip<B>(req: &http::Request<B>) -> bool {
+        Self::accepts(req, http::header::ACCEPT_ENCODING, |coding| {
+            coding.eq_ignore_ascii_case("gzip") || coding == "*"
+        })
+    }
+
+    fn is_openmetrics<B>(req: &http::Request<B>) -> bool {
+        Self::accepts(req, http::header::ACCEPT, |media_type| {
+            media_type.eq_ignore_ascii_case(OPENMETRICS_MEDIA_TYPE)
+        })
+    }
+
+    /// Returns true if the request's `header` lists a value matching `matches`
+    /// that has not been refused with a quality of zero (e.g. `gzip;q=0`).
+    fn accepts<B>(
+        req: &http::Request<B>,
+        header: http::header::HeaderName,
+        matches: impl Fn(&str) -> bool,
+    ) -> bool {
         req.headers()
-            .get_all(http::header::ACCEPT_ENCODING)
+            .get_all(header)
             .iter()
-            .any(|value| {
-                value
-                    .to_str()
-                    .ok()
-                    .map(|value| value.contains("gzip"))
-                    .unwrap_or(false)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|item| {
+                let mut params = item.split(';').map(str::trim);
+                let value = params.next().unwrap_or_default();
+                let refused = params.any(|p| {
+                    p.strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .map(|q| q <= 0.0)
+                        .unwrap_or(false)
+                });
+                matches(value) && !refused
             })
     }
 }
 
 impl<M: FmtMetrics> Serve<M> {
     pub fn serve<B>(&self, req: http::Request<B>) -> std::io::Result<http::Response<Body>> {
+        let mut body = Vec::<u8>::new();
+        let content_type = if Self::is_openmetrics(&req) {
+            trace!("formatting metrics as OpenMetrics");
+            write!(&mut body, "{:#}", self.metrics.as_display())?;
+            body.extend_from_slice(b"# EOF\n");
+            OPENMETRICS_CONTENT_TYPE
+        } else {
+            write!(&mut body, "{}", self.metrics.as_display())?;
+            TEXT_CONTENT_TYPE
+        };
+
+        let rsp = http::Response::builder()
+            .header(http::header::CONTENT_TYPE, content_type)
+            .header(http::header::VARY, "Accept, Accept-Encoding");
+
         if Self::is_gzip(&req) {
             trace!("gzipping metrics");
             let mut writer = GzEncoder::new(Vec::<u8>::new(), CompressionOptions::fast());
-            write!(&mut writer, "{}", self.metrics.as_display())?;
-            Ok(http::Response::builder()
+            writer.write_all(&body)?;
+            Ok(rsp
                 .header(http::header::CONTENT_ENCODING, "gzip")
-                .header(http::header::CONTENT_TYPE, "text/plain")
                 .body(writer.finish()?.into())
                 .expect("Response must be valid"))
         } else {
-            let mut writer = Vec::<u8>::new();
-            write!(&mut writer, "{}", self.metrics.as_display())?;
-            Ok(http::Response::builder()
-                .header(http::header::CONTENT_TYPE, "text/plain")
-                .body(Body::from(writer))
-                .expect("Response must be valid"))
+            Ok(rsp.body(Body::from(body)).expect("Response must be valid"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{metrics, Counter, Histogram};
+    use std::fmt;
+
+    metrics! {
+        requests_total: Counter { "Total requests" },
+        latency_ms: Histogram<u64> { "Request latency" }
+    }
+
+    struct Report {
+        requests: Counter,
+        latency: Histogram<u64>,
+    }
+
+    impl FmtMetrics for Report {
+        fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            requests_total.fmt_help(f)?;
+            requests_total.fmt_metric(f, &self.requests)?;
+            latency_ms.fmt_help(f)?;
+            latency_ms.fmt_metric(f, &self.latency)?;
+            Ok(())
+        }
+    }
+
+    fn report() -> Report {
+        let report = Report {
+            requests: Counter::from(3),
+            latency: Histogram::new(crate::latency::BOUNDS),
+        };
+        report
+            .latency
+            .add_with_exemplar(7u64, vec![("trace_id", "abc123".to_string())]);
+        report
+    }
+
+    async fn body(rsp: http::Response<Body>) -> String {
+        let bytes = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn prometheus_text() {
+        let serve = Serve::new(report());
+        let rsp = serve
+            .serve(http::Request::get("/metrics").body(()).unwrap())
+            .unwrap();
+        assert_eq!(rsp.headers()[http::header::CONTENT_TYPE], TEXT_CONTENT_TYPE);
+
+        let text = body(rsp).await;
+        assert!(text.contains("# TYPE requests_total counter\n"));
+        assert!(text.contains("requests_total 3\n"));
+        assert!(text.contains("latency_ms_bucket{le=\"10\"} 1\n"));
+        assert!(!text.contains("_created"));
+        assert!(!text.contains("trace_id"));
+        assert!(!text.contains("# EOF"));
+    }
+
+    #[tokio::test]
+    async fn openmetrics() {
+        let serve = Serve::new(report());
+        let req = http::Request::get("/metrics")
+            .header(
+                http::header::ACCEPT,
+                "application/openmetrics-text;version=1.0.0,text/plain;q=0.5",
+            )
+            .body(())
+            .unwrap();
+        let rsp = serve.serve(req).unwrap();
+        assert_eq!(
+            rsp.headers()[http::header::CONTENT_TYPE],
+            OPENMETRICS_CONTENT_TYPE
+        );
+
+        let text = body(rsp).await;
+        assert!(text.contains("# TYPE requests counter\n"));
+        assert!(text.contains("requests_total 3\n"));
+        assert!(text.contains("requests_created "));
+        assert!(text.contains("latency_ms_bucket{le=\"10\"} 1 # {trace_id=\"abc123\"} 7 "));
+        assert!(text.contains("latency_ms_created "));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn negotiates_gzip() {
+        fn req(accept_encoding: &str) -> http::Request<()> {
+            http::Request::get("/metrics")
+                .header(http::header::ACCEPT_ENCODING, accept_encoding)
+                .body(())
+                .unwrap()
         }
+
+        assert!(Serve::<()>::is_gzip(&req("gzip")));
+        assert!(Serve::<()>::is_gzip(&req("deflate, gzip;q=1.0")));
+        assert!(Serve::<()>::is_gzip(&req("*")));
+        assert!(!Serve::<()>::is_gzip(&req("gzip;q=0")));
+        assert!(!Serve::<()>::is_gzip(&req("identity")));
     }
 }
@@ -0,0 +1,261 @@
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::SystemTime;
+
+use super::{
+    fmt::{is_openmetrics, timestamp},
+    histogram::{Bucket, Exemplar, SampleLabels},
+    Counter, Factor, FmtLabels, FmtMetric,
+};
+
+/// A histogram with exponentially-sized buckets that are only allocated (and
+/// reported) once a value has been observed in them.
+///
+/// Like Prometheus' native histograms, each bucket's upper bound is a power of
+/// the histogram's growth factor, so the relative error of a quantile estimate
+/// is bounded by the growth factor regardless of the range of observed values.
+/// Only populated buckets are formatted, which typically produces far fewer
+/// series than a histogram with fixed buckets.
+#[derive(Debug)]
+pub struct SparseHistogram<V: Into<u64>, F = ()> {
+    growth_factor: GrowthFactor,
+    buckets: Mutex<Buckets>,
+    sum: Counter<F>,
+    created: SystemTime,
+    _p: PhantomData<(V, F)>,
+}
+
+/// The ratio between the upper bounds of adjacent buckets in a
+/// [`SparseHistogram`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GrowthFactor(f64);
+
+#[derive(Debug, thiserror::Error)]
+#[error("histogram growth factor must be greater than 1: {0}")]
+pub struct InvalidGrowthFactor(f64);
+
+#[derive(Debug, Default)]
+struct Buckets {
+    /// Counts observations of zero.
+    zero: SparseBucket,
+    /// Counts observations by the exponent of their bucket's upper bound.
+    by_index: BTreeMap<i32, SparseBucket>,
+}
+
+#[derive(Debug, Default)]
+struct SparseBucket {
+    count: u64,
+    exemplar: Option<Exemplar>,
+}
+
+// === impl GrowthFactor ===
+
+impl GrowthFactor {
+    pub fn new(factor: f64) -> Result<Self, InvalidGrowthFactor> {
+        if factor.is_finite() && factor > 1.0 {
+            Ok(Self(factor))
+        } else {
+            Err(InvalidGrowthFactor(factor))
+        }
+    }
+
+    /// Returns the exponent of the upper bound of the bucket containing
+    /// `value`, which must be positive.
+    fn index(&self, value: f64) -> i32 {
+        let idx = (value.ln() / self.0.ln()).ceil() as i32;
+        // Correct for floating point error so that the value never exceeds
+        // its bucket's upper bound.
+        if self.upper_bound(idx) < value {
+            idx + 1
+        } else {
+            idx
+        }
+    }
+
+    fn upper_bound(&self, idx: i32) -> f64 {
+        self.0.powi(idx)
+    }
+}
+
+impl std::str::FromStr for GrowthFactor {
+    type Err = InvalidGrowthFactor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let factor = s
+            .parse::<f64>()
+            .map_err(|_| InvalidGrowthFactor(f64::NAN))?;
+        Self::new(factor)
+    }
+}
+
+// === impl SparseHistogram ===
+
+impl<V: Into<u64>, F: Factor> SparseHistogram<V, F> {
+    pub fn new(growth_factor: GrowthFactor) -> Self {
+        Self {
+            growth_factor,
+            buckets: Mutex::default(),
+            sum: Counter::default(),
+            created: SystemTime::now(),
+            _p: PhantomData,
+        }
+    }
+
+    pub fn add<U: Into<V>>(&self, u: U) {
+        let v: V = u.into();
+        self.observe(v.into(), None);
+    }
+
+    /// Records an observation along with labels (e.g. a trace ID) that
+    /// identify it as an exemplar of its bucket.
+    pub fn add_with_exemplar<U: Into<V>>(&self, u: U, labels: Vec<(&'static str, String)>) {
+        let v: V = u.into();
+        self.observe(v.into(), Some(labels));
+    }
+
+    fn observe(&self, value: u64, exemplar: Option<Vec<(&'static str, String)>>) {
+        let v = F::factor(value);
+        let mut buckets = self.buckets.lock();
+        let bucket = if v > 0.0 {
+            let idx = self.growth_factor.index(v);
+            buckets.by_index.entry(idx).or_default()
+        } else {
+            &mut buckets.zero
+        };
+        bucket.count += 1;
+        if let Some(labels) = exemplar {
+            bucket.exemplar = Some(Exemplar::new(labels, v));
+        }
+        drop(buckets);
+
+        self.sum.add(value);
+    }
+
+    fn fmt_samples<N: fmt::Display>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: Option<&dyn FmtLabels>,
+    ) -> fmt::Result {
+        let openmetrics = is_openmetrics(f);
+        let buckets = self.buckets.lock();
+
+        let zero = (0.0, &buckets.zero);
+        let populated = buckets
+            .by_index
+            .iter()
+            .map(|(idx, b)| (self.growth_factor.upper_bound(*idx), b));
+        let mut total = 0u64;
+        for (le, bucket) in Some(zero)
+            .filter(|(_, b)| b.count > 0)
+            .into_iter()
+            .chain(populated)
+        {
+            total = total.wrapping_add(bucket.count);
+            let le = Bucket::Le(le);
+            write!(
+                f,
+                "{}_bucket{} {}",
+                name,
+                SampleLabels(labels, Some(&le)),
+                <()>::factor(total)
+            )?;
+            if let Some(ex) = bucket.exemplar.as_ref().filter(|_| openmetrics) {
+                ex.fmt_suffix(f)?;
+            }
+            f.write_str("\n")?;
+        }
+        drop(buckets);
+
+        writeln!(
+            f,
+            "{}_bucket{} {}",
+            name,
+            SampleLabels(labels, Some(&Bucket::Inf)),
+            <()>::factor(total)
+        )?;
+        let labels = SampleLabels(labels, None);
+        writeln!(f, "{}_count{} {}", name, labels, <()>::factor(total))?;
+        writeln!(f, "{}_sum{} {}", name, labels, self.sum.value())?;
+        if openmetrics {
+            writeln!(f, "{}_created{} {}", name, labels, timestamp(self.created))?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: Into<u64>, F: Factor> FmtMetric for SparseHistogram<V, F> {
+    const KIND: &'static str = "histogram";
+
+    fn fmt_metric<N: fmt::Display>(&self, f: &mut fmt::Formatter<'_>, name: N) -> fmt::Result {
+        self.fmt_samples(f, name, None)
+    }
+
+    fn fmt_metric_labeled<N, L>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: L,
+    ) -> fmt::Result
+    where
+        N: fmt::Display,
+        L: FmtLabels,
+    {
+        self.fmt_samples(f, name, Some(&labels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FmtMetrics;
+
+    struct Fmt<'h>(&'h SparseHistogram<u64>);
+
+    impl FmtMetrics for Fmt<'_> {
+        fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fmt_metric(f, "h")
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_growth_factors() {
+        assert!(GrowthFactor::new(1.0).is_err());
+        assert!(GrowthFactor::new(0.5).is_err());
+        assert!(GrowthFactor::new(f64::INFINITY).is_err());
+        assert!("1.5".parse::<GrowthFactor>().is_ok());
+        assert!("nope".parse::<GrowthFactor>().is_err());
+    }
+
+    #[test]
+    fn values_fit_their_buckets() {
+        let g = GrowthFactor::new(1.1).unwrap();
+        for v in [1.0, 2.0, 3.0, 10.0, 999.0, 1_000.0, 123_456.0] {
+            let idx = g.index(v);
+            assert!(g.upper_bound(idx) >= v, "value={v}");
+            assert!(g.upper_bound(idx - 1) < v, "value={v}");
+        }
+    }
+
+    #[test]
+    fn only_formats_populated_buckets() {
+        let h = SparseHistogram::<u64>::new(GrowthFactor::new(2.0).unwrap());
+        h.add(0u64);
+        h.add(3u64);
+        h.add(4u64);
+        h.add(100u64);
+
+        let text = Fmt(&h).as_display().to_string();
+        assert_eq!(
+            text,
+            "h_bucket{le=\"0\"} 1\n\
+             h_bucket{le=\"4\"} 3\n\
+             h_bucket{le=\"128\"} 4\n\
+             h_bucket{le=\"+Inf\"} 4\n\
+             h_count 4\n\
+             h_sum 107\n"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Parses metrics in the Prometheus text exposition format.
+//!
+//! The proxy's metrics are all written via [`FmtMetrics`], so rather than
+//! maintaining a second set of instruments, exporters that push metrics
+//! render the same report that is served to Prometheus and translate it.
+//!
+//! [`FmtMetrics`]: crate::FmtMetrics
+
+/// The kind of a metric family, as described by its `# TYPE` line.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+    /// Families without a `# TYPE` line or with a type that has no OTLP
+    /// equivalent are exported as gauges.
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Family {
+    pub name: String,
+    pub help: String,
+    pub kind: Kind,
+    pub samples: Vec<Sample>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    /// The sample's name with the family name stripped, e.g. `_bucket`.
+    pub suffix: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+/// Parses a report into its metric families.
+///
+/// Malformed lines are skipped, since a single unparseable metric should not
+/// prevent the remainder of the report from being exported.
+pub fn parse(text: &str) -> Vec<Family> {
+    let mut families = Vec::<Family>::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut parts = comment.trim_start().splitn(3, ' ');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("HELP"), Some(name), help) => {
+                    family(&mut families, name).help = unescape(help.unwrap_or_default());
+                }
+                (Some("TYPE"), Some(name), Some(kind)) => {
+                    family(&mut families, name).kind = match kind.trim() {
+                        "counter" => Kind::Counter,
+                        "gauge" => Kind::Gauge,
+                        "histogram" => Kind::Histogram,
+                        _ => Kind::Unknown,
+                    };
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let Some((name, labels, value)) = parse_sample(line) else {
+            tracing::trace!(line, "Skipping malformed sample");
+            continue;
+        };
+        let fam = match families.last_mut() {
+            Some(fam) if is_sample_of(name, &fam.name) => fam,
+            _ => family(&mut families, name),
+        };
+        fam.samples.push(Sample {
+            suffix: name[fam.name.len()..].to_string(),
+            labels,
+            value,
+        });
+    }
+    families
+}
+
+/// Returns true if `sample` names a sample of the family `family`, e.g.
+/// `latency_bucket` for the `latency` family.
+fn is_sample_of(sample: &str, family: &str) -> bool {
+    const SUFFIXES: &[&str] = &["", "_total", "_bucket", "_count", "_sum", "_info"];
+    sample
+        .strip_prefix(family)
+        .map(|suffix| SUFFIXES.contains(&suffix))
+        .unwrap_or(false)
+}
+
+/// Returns the family with the given name, starting a new one if the most
+/// recent family has a different name.
+fn family<'f>(families: &'f mut Vec<Family>, name: &str) -> &'f mut Family {
+    if families.last().map(|f| f.name != name).unwrap_or(true) {
+        families.push(Family {
+            name: name.to_string(),
+            help: String::new(),
+            kind: Kind::Unknown,
+            samples: Vec::new(),
+        });
+    }
+    families.last_mut().expect("a family must exist")
+}
+
+/// Parses a line like `name{k="v",...} 1.0 [timestamp]`.
+fn parse_sample(line: &str) -> Option<(&str, Vec<(String, String)>, f64)> {
+    let name_end = line.find(['{', ' '])?;
+    let (name, mut rest) = line.split_at(name_end);
+
+    let mut labels = Vec::new();
+    if let Some(mut s) = rest.strip_prefix('{') {
+        loop {
+            s = s.trim_start_matches([',', ' ']);
+            if let Some(r) = s.strip_prefix('}') {
+                rest = r;
+                break;
+            }
+            let (key, r) = s.split_once("=\"")?;
+            let (value, r) = split_quoted(r)?;
+            labels.push((key.trim().to_string(), value));
+            s = r;
+        }
+    }
+
+    let value = rest.split_whitespace().next()?.parse().ok()?;
+    Some((name, labels, value))
+}
+
+/// Splits an escaped label value from the remainder of the line, returning
+/// the unescaped value.
+fn split_quoted(s: &str) -> Option<(String, &str)> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some((unescape(&s[..i]), &s[i + 1..])),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+// === impl Family ===
+
+impl Family {
+    /// Returns the full name of one of the family's samples, e.g.
+    /// `latency_bucket`.
+    pub fn sample_name(&self, sample: &Sample) -> String {
+        format!("{}{}", self.name, sample.suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_families() {
+        let families = parse(
+            "# HELP request_total Total count of HTTP requests.\n\
+             # TYPE request_total counter\n\
+             request_total{direction=\"inbound\",authority=\"a\\\"b\"} 3\n\
+             request_total{direction=\"outbound\"} 4\n\
+             # HELP response_latency_ms Elapsed times.\n\
+             # TYPE response_latency_ms histogram\n\
+             response_latency_ms_bucket{le=\"1\"} 1\n\
+             response_latency_ms_bucket{le=\"+Inf\"} 2\n\
+             response_latency_ms_count 2\n\
+             response_latency_ms_sum 7.5\n\
+             process_start_time_seconds 1700000000\n",
+        );
+
+        assert_eq!(
+            families,
+            vec![
+                Family {
+                    name: "request_total".to_string(),
+                    help: "Total count of HTTP requests.".to_string(),
+                    kind: Kind::Counter,
+                    samples: vec![
+                        Sample {
+                            suffix: String::new(),
+                            labels: vec![
+                                ("direction".to_string(), "inbound".to_string()),
+                                ("authority".to_string(), "a\"b".to_string()),
+                            ],
+                            value: 3.0,
+                        },
+                        Sample {
+                            suffix: String::new(),
+                            labels: vec![("direction".to_string(), "outbound".to_string())],
+                            value: 4.0,
+                        },
+                    ],
+                },
+                Family {
+                    name: "response_latency_ms".to_string(),
+                    help: "Elapsed times.".to_string(),
+                    kind: Kind::Histogram,
+                    samples: vec![
+                        Sample {
+                            suffix: "_bucket".to_string(),
+                            labels: vec![("le".to_string(), "1".to_string())],
+                            value: 1.0,
+                        },
+                        Sample {
+                            suffix: "_bucket".to_string(),
+                            labels: vec![("le".to_string(), "+Inf".to_string())],
+                            value: 2.0,
+                        },
+                        Sample {
+                            suffix: "_count".to_string(),
+                            labels: vec![],
+                            value: 2.0,
+                        },
+                        Sample {
+                            suffix: "_sum".to_string(),
+                            labels: vec![],
+                            value: 7.5,
+                        },
+                    ],
+                },
+                Family {
+                    name: "process_start_time_seconds".to_string(),
+                    help: String::new(),
+                    kind: Kind::Unknown,
+                    samples: vec![Sample {
+                        suffix: String::new(),
+                        labels: vec![],
+                        value: 1_700_000_000.0,
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_malformed_samples() {
+        let families = parse("# TYPE c counter\nc{x=\"unterminated} 1\nc 2\n");
+        assert_eq!(families.len(), 1);
+        assert_eq!(families[0].samples.len(), 1);
+        assert_eq!(families[0].samples[0].value, 2.0);
+    }
+}
@@ -0,0 +1,425 @@
+//! Translates parsed metric families into OTLP metrics.
+
+use linkerd_metrics::text::{Family, Kind, Sample};
+use opentelemetry_proto::{
+    common::v1::{any_value, AnyValue, KeyValue},
+    metrics::v1::{self as otlp, metric::Data, number_data_point},
+};
+use std::collections::HashMap;
+
+/// Describes how the values of counters and histograms relate to the
+/// interval over which they are reported.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Temporality {
+    /// Values are totals since the exporter started.
+    #[default]
+    Cumulative,
+    /// Values are the change since the previous successful export.
+    Delta,
+}
+
+type Labels = Vec<(String, String)>;
+
+/// Translates reports into OTLP metrics, tracking the state needed to
+/// report deltas.
+#[derive(Debug)]
+pub(crate) struct Converter {
+    temporality: Temporality,
+    /// The time at which the exporter started, in nanoseconds since the UNIX
+    /// epoch. This is the start time of all cumulative data points.
+    start_time: u64,
+    /// The time of the last successful export, which starts the next delta
+    /// interval.
+    last_export: u64,
+    /// The cumulative values reported by the last successful export.
+    prior: HashMap<(String, Labels), Cumulative>,
+}
+
+/// Metrics that have been converted but not yet exported.
+///
+/// Deltas are computed against the last *successful* export, so that values
+/// are not lost when an export fails.
+#[derive(Debug)]
+pub(crate) struct Pending {
+    pub metrics: Vec<otlp::Metric>,
+    time: u64,
+    cumulative: HashMap<(String, Labels), Cumulative>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Cumulative {
+    Sum(f64),
+    Histogram(Histogram),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Histogram {
+    /// Cumulative bucket counts, ordered by upper bound.
+    buckets: Vec<(f64, f64)>,
+    count: f64,
+    sum: f64,
+}
+
+// === impl Converter ===
+
+impl Converter {
+    pub(crate) fn new(temporality: Temporality, start_time: u64) -> Self {
+        Self {
+            temporality,
+            start_time,
+            last_export: start_time,
+            prior: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn convert(&self, families: Vec<Family>, time: u64) -> Pending {
+        let mut cumulative = HashMap::new();
+        let metrics = families
+            .into_iter()
+            .filter_map(|family| {
+                let Family {
+                    name,
+                    help,
+                    kind,
+                    samples,
+                } = family;
+                let data = match kind {
+                    Kind::Counter => self.sum(&name, samples, time, &mut cumulative),
+                    Kind::Histogram => self.histogram(&name, samples, time, &mut cumulative),
+                    Kind::Gauge | Kind::Unknown => Self::gauge(samples, time),
+                }?;
+                Some(otlp::Metric {
+                    name,
+                    description: help,
+                    unit: String::new(),
+                    data: Some(data),
+                })
+            })
+            .collect();
+
+        Pending {
+            metrics,
+            time,
+            cumulative,
+        }
+    }
+
+    /// Records that the pending metrics were exported.
+    pub(crate) fn commit(&mut self, pending: Pending) {
+        self.last_export = pending.time;
+        if self.temporality == Temporality::Delta {
+            self.prior = pending.cumulative;
+        }
+    }
+
+    fn interval_start(&self) -> u64 {
+        match self.temporality {
+            Temporality::Cumulative => self.start_time,
+            Temporality::Delta => self.last_export,
+        }
+    }
+
+    fn aggregation_temporality(&self) -> i32 {
+        let temporality = match self.temporality {
+            Temporality::Cumulative => otlp::AggregationTemporality::Cumulative,
+            Temporality::Delta => otlp::AggregationTemporality::Delta,
+        };
+        temporality.into()
+    }
+
+    fn sum(
+        &self,
+        name: &str,
+        samples: Vec<Sample>,
+        time: u64,
+        cumulative: &mut HashMap<(String, Labels), Cumulative>,
+    ) -> Option<Data> {
+        let data_points = samples
+            .into_iter()
+            .map(|Sample { labels, value, .. }| {
+                let key = (name.to_string(), labels);
+                let delta = match self.prior.get(&key) {
+                    // If the counter was reset, report its entire value.
+                    Some(Cumulative::Sum(prior)) if *prior <= value => value - prior,
+                    _ => value,
+                };
+                cumulative.insert(key.clone(), Cumulative::Sum(value));
+                otlp::NumberDataPoint {
+                    attributes: attributes(key.1),
+                    start_time_unix_nano: self.interval_start(),
+                    time_unix_nano: time,
+                    value: Some(number_data_point::Value::AsDouble(delta)),
+                }
+            })
+            .collect::<Vec<_>>();
+        if data_points.is_empty() {
+            return None;
+        }
+
+        Some(Data::Sum(otlp::Sum {
+            data_points,
+            aggregation_temporality: self.aggregation_temporality(),
+            is_monotonic: true,
+        }))
+    }
+
+    fn histogram(
+        &self,
+        name: &str,
+        samples: Vec<Sample>,
+        time: u64,
+        cumulative: &mut HashMap<(String, Labels), Cumulative>,
+    ) -> Option<Data> {
+        // Group the family's samples by their labels (excluding `le`),
+        // preserving the order in which each series was first reported.
+        let mut series = Vec::<(Labels, Histogram)>::new();
+        for Sample {
+            suffix,
+            mut labels,
+            value,
+        } in samples
+        {
+            let le = labels
+                .iter()
+                .position(|(k, _)| k == "le")
+                .map(|i| labels.remove(i).1);
+            let idx = match series.iter().position(|(l, _)| *l == labels) {
+                Some(idx) => idx,
+                None => {
+                    series.push((labels, Histogram::default()));
+                    series.len() - 1
+                }
+            };
+            let h = &mut series[idx].1;
+            match (suffix.as_str(), le) {
+                ("_bucket", Some(le)) => {
+                    if let Ok(le) = le.parse::<f64>() {
+                        h.buckets.push((le, value));
+                    }
+                }
+                ("_count", _) => h.count = value,
+                ("_sum", _) => h.sum = value,
+                _ => {}
+            }
+        }
+        if series.is_empty() {
+            return None;
+        }
+
+        let data_points = series
+            .into_iter()
+            .map(|(labels, mut h)| {
+                h.buckets.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+                let key = (name.to_string(), labels);
+                let point = match self.prior.get(&key) {
+                    Some(Cumulative::Histogram(prior)) => {
+                        h.delta(prior).unwrap_or_else(|| h.clone())
+                    }
+                    _ => h.clone(),
+                };
+                cumulative.insert(key.clone(), Cumulative::Histogram(h));
+                point.data_point(attributes(key.1), self.interval_start(), time)
+            })
+            .collect();
+
+        Some(Data::Histogram(otlp::Histogram {
+            data_points,
+            aggregation_temporality: self.aggregation_temporality(),
+        }))
+    }
+
+    fn gauge(samples: Vec<Sample>, time: u64) -> Option<Data> {
+        let data_points = samples
+            .into_iter()
+            .map(|Sample { labels, value, .. }| otlp::NumberDataPoint {
+                attributes: attributes(labels),
+                start_time_unix_nano: 0,
+                time_unix_nano: time,
+                value: Some(number_data_point::Value::AsDouble(value)),
+            })
+            .collect::<Vec<_>>();
+        if data_points.is_empty() {
+            return None;
+        }
+        Some(Data::Gauge(otlp::Gauge { data_points }))
+    }
+}
+
+// === impl Histogram ===
+
+impl Histogram {
+    /// Returns the observations recorded since `prior`, or `None` if the
+    /// histogram has been reset.
+    ///
+    /// Sparse histograms only report populated buckets, so the set of bounds
+    /// may differ between reports. Since bucket counts are cumulative, the
+    /// prior count for a bound that was not reported is the count of the
+    /// nearest lower bound that was.
+    fn delta(&self, prior: &Self) -> Option<Self> {
+        if self.count < prior.count {
+            return None;
+        }
+        let prior_at = |le: f64| {
+            prior
+                .buckets
+                .iter()
+                .take_while(|(b, _)| *b <= le)
+                .last()
+                .map(|(_, n)| *n)
+                .unwrap_or(0.0)
+        };
+        let buckets = self
+            .buckets
+            .iter()
+            .map(|(le, n)| {
+                let prior = prior_at(*le);
+                (*n >= prior).then_some((*le, n - prior))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            buckets,
+            count: self.count - prior.count,
+            sum: self.sum - prior.sum,
+        })
+    }
+
+    fn data_point(
+        self,
+        attributes: Vec<KeyValue>,
+        start: u64,
+        time: u64,
+    ) -> otlp::HistogramDataPoint {
+        let mut explicit_bounds = Vec::with_capacity(self.buckets.len());
+        let mut bucket_counts = Vec::with_capacity(self.buckets.len() + 1);
+        let mut below = 0.0;
+        for (le, n) in self.buckets.into_iter().filter(|(le, _)| le.is_finite()) {
+            explicit_bounds.push(le);
+            bucket_counts.push((n - below).max(0.0) as u64);
+            below = n;
+        }
+        // The final, unbounded bucket holds all remaining observations.
+        bucket_counts.push((self.count - below).max(0.0) as u64);
+
+        otlp::HistogramDataPoint {
+            attributes,
+            start_time_unix_nano: start,
+            time_unix_nano: time,
+            count: self.count as u64,
+            sum: Some(self.sum),
+            bucket_counts,
+            explicit_bounds,
+        }
+    }
+}
+
+fn attributes(labels: Labels) -> Vec<KeyValue> {
+    labels
+        .into_iter()
+        .map(|(key, value)| KeyValue {
+            key,
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value)),
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_metrics::text;
+
+    fn point(metric: &otlp::Metric) -> (u64, f64) {
+        match metric.data.as_ref().unwrap() {
+            Data::Sum(sum) => {
+                let pt = &sum.data_points[0];
+                match pt.value {
+                    Some(number_data_point::Value::AsDouble(v)) => (pt.start_time_unix_nano, v),
+                    _ => panic!("unexpected value: {pt:?}"),
+                }
+            }
+            data => panic!("unexpected data: {data:?}"),
+        }
+    }
+
+    fn histogram(metric: &otlp::Metric) -> &otlp::HistogramDataPoint {
+        match metric.data.as_ref().unwrap() {
+            Data::Histogram(h) => &h.data_points[0],
+            data => panic!("unexpected data: {data:?}"),
+        }
+    }
+
+    #[test]
+    fn cumulative_sums() {
+        let mut conv = Converter::new(Temporality::Cumulative, 1);
+        let pending = conv.convert(text::parse("# TYPE c counter\nc 3\n"), 10);
+        assert_eq!(point(&pending.metrics[0]), (1, 3.0));
+        conv.commit(pending);
+
+        let pending = conv.convert(text::parse("# TYPE c counter\nc 5\n"), 20);
+        assert_eq!(point(&pending.metrics[0]), (1, 5.0));
+    }
+
+    #[test]
+    fn delta_sums() {
+        let mut conv = Converter::new(Temporality::Delta, 1);
+        let pending = conv.convert(text::parse("# TYPE c counter\nc 3\n"), 10);
+        assert_eq!(point(&pending.metrics[0]), (1, 3.0));
+        conv.commit(pending);
+
+        let pending = conv.convert(text::parse("# TYPE c counter\nc 5\n"), 20);
+        assert_eq!(point(&pending.metrics[0]), (10, 2.0));
+        // A failed export is not committed, so the next delta covers both
+        // intervals.
+        drop(pending);
+
+        let pending = conv.convert(text::parse("# TYPE c counter\nc 6\n"), 30);
+        assert_eq!(point(&pending.metrics[0]), (10, 3.0));
+        conv.commit(pending);
+
+        // Counter resets report the counter's entire value.
+        let pending = conv.convert(text::parse("# TYPE c counter\nc 1\n"), 40);
+        assert_eq!(point(&pending.metrics[0]), (30, 1.0));
+    }
+
+    #[test]
+    fn delta_histograms() {
+        let mut conv = Converter::new(Temporality::Delta, 1);
+        let pending = conv.convert(
+            text::parse(
+                "# TYPE h histogram\n\
+                 h_bucket{le=\"1\"} 1\n\
+                 h_bucket{le=\"4\"} 3\n\
+                 h_bucket{le=\"+Inf\"} 3\n\
+                 h_count 3\n\
+                 h_sum 6\n",
+            ),
+            10,
+        );
+        let pt = histogram(&pending.metrics[0]);
+        assert_eq!(pt.explicit_bounds, vec![1.0, 4.0]);
+        assert_eq!(pt.bucket_counts, vec![1, 2, 0]);
+        assert_eq!((pt.count, pt.sum), (3, Some(6.0)));
+        conv.commit(pending);
+
+        // A sparse histogram may report a bucket that it did not previously.
+        let pending = conv.convert(
+            text::parse(
+                "# TYPE h histogram\n\
+                 h_bucket{le=\"1\"} 1\n\
+                 h_bucket{le=\"2\"} 2\n\
+                 h_bucket{le=\"4\"} 4\n\
+                 h_bucket{le=\"16\"} 5\n\
+                 h_bucket{le=\"+Inf\"} 5\n\
+                 h_count 5\n\
+                 h_sum 20\n",
+            ),
+            20,
+        );
+        let pt = histogram(&pending.metrics[0]);
+        assert_eq!(pt.start_time_unix_nano, 10);
+        assert_eq!(pt.explicit_bounds, vec![1.0, 2.0, 4.0, 16.0]);
+        assert_eq!(pt.bucket_counts, vec![0, 1, 0, 1, 0]);
+        assert_eq!((pt.count, pt.sum), (2, Some(14.0)));
+    }
+}
@@ -0,0 +1,102 @@
+//! Exports the proxy's metrics to an OpenTelemetry collector via OTLP/gRPC.
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+mod convert;
+
+pub use self::convert::Temporality;
+use http_body::Body as HttpBody;
+use linkerd_error::Error;
+use linkerd_metrics::{text, FmtMetrics};
+pub use opentelemetry_proto as proto;
+use opentelemetry_proto::{
+    collector::metrics::v1::{
+        metrics_service_client::MetricsServiceClient, ExportMetricsServiceRequest,
+    },
+    common::v1::InstrumentationScope,
+    metrics::v1::{ResourceMetrics, ScopeMetrics},
+    resource::v1::Resource,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time;
+use tonic::{body::BoxBody, client::GrpcService};
+use tracing::{debug, trace, warn};
+
+const SCOPE_NAME: &str = "linkerd-proxy";
+
+/// Exports the metrics in `report` to the MetricsService each `interval`.
+///
+/// Exports are not retried: when an export fails, its values are included in
+/// the next export.
+pub async fn export_metrics<T, R>(
+    client: T,
+    resource: Resource,
+    report: R,
+    interval: time::Duration,
+    temporality: Temporality,
+) where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<Error>,
+    T::ResponseBody: Default + HttpBody<Data = tonic::codegen::Bytes> + Send + 'static,
+    <T::ResponseBody as HttpBody>::Error: Into<Error> + Send,
+    R: FmtMetrics,
+{
+    debug!(?interval, ?temporality, "Metrics exporter running");
+    let mut client = MetricsServiceClient::new(client);
+    let mut converter = convert::Converter::new(temporality, unix_nanos(SystemTime::now()));
+    let scope = InstrumentationScope {
+        name: SCOPE_NAME.to_string(),
+        ..Default::default()
+    };
+
+    let mut interval = time::interval(interval);
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+    // The first tick completes immediately. Skip it so that the first export
+    // describes a full interval.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+
+        let families = text::parse(&report.as_display().to_string());
+        let mut pending = converter.convert(families, unix_nanos(SystemTime::now()));
+        trace!(metrics = pending.metrics.len(), "Exporting metrics");
+        let req = ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: Some(resource.clone()),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: Some(scope.clone()),
+                    metrics: std::mem::take(&mut pending.metrics),
+                    schema_url: String::new(),
+                }],
+                schema_url: String::new(),
+            }],
+        };
+
+        match client.export(req).await {
+            Ok(rsp) => {
+                if let Some(partial) = rsp
+                    .into_inner()
+                    .partial_success
+                    .filter(|p| p.rejected_data_points > 0)
+                {
+                    warn!(
+                        rejected = partial.rejected_data_points,
+                        message = %partial.error_message,
+                        "Collector rejected metrics"
+                    );
+                }
+                converter.commit(pending);
+            }
+            Err(status) => warn!(%status, "Failed to export metrics"),
+        }
+    }
+}
+
+fn unix_nanos(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
@@ -7,7 +7,7 @@ use linkerd_stack::Param;
 use linkerd_tonic_stream::{LimitReceiveFuture, ReceiveLimits};
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use tonic::{self as grpc, body::BoxBody, client::GrpcService};
+use tonic::{self as grpc, body::BoxBody, client::GrpcService, codec::CompressionEncoding};
 use tower::Service;
 use tracing::{debug, info, trace};
 
@@ -30,7 +30,7 @@ where
 {
     pub fn new(svc: S, context_token: String, limits: ReceiveLimits) -> Self {
         Self {
-            client: DestinationClient::new(svc),
+            client: DestinationClient::new(svc).accept_compressed(CompressionEncoding::Gzip),
             context_token,
             limits,
         }
@@ -37,6 +37,13 @@ pub fn default(distribution: crate::RouteDistribution<Filter>) -> Route {
                 filters: Arc::new([]),
                 distribution,
                 failure_policy: Codes::default(),
+                cache: None,
+                request_decompression: None,
+                request_body_limit: None,
+                streaming: None,
+                sticky: None,
+                mirror: None,
+                success_objective: None,
                 request_timeout: None,
             },
         }],
@@ -177,11 +184,7 @@ pub mod proto {
             rules,
             metadata,
         } = proto;
-        let meta = Arc::new(
-            metadata
-                .ok_or(InvalidMeta("missing metadata"))?
-                .try_into()?,
-        );
+        let meta = Meta::try_from(metadata.ok_or(InvalidMeta("missing metadata"))?)?.interned();
         let hosts = hosts
             .into_iter()
             .map(MatchHost::try_from)
@@ -229,6 +232,13 @@ pub mod proto {
                 filters,
                 distribution,
                 failure_policy: Codes::default(),
+                cache: None,
+                request_decompression: None,
+                request_body_limit: None,
+                streaming: None,
+                sticky: None,
+                mirror: None,
+                success_objective: None,
                 request_timeout,
             },
         })
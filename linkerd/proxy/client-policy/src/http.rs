@@ -32,12 +32,122 @@ pub enum Filter {
     Redirect(filter::RedirectRequest),
     RequestHeaders(filter::ModifyHeader),
     ResponseHeaders(filter::ModifyHeader),
+    /// Answers requests with a static response without forwarding them to a
+    /// backend.
+    ///
+    /// This filter is not yet expressible in the control plane API, so it is
+    /// only configured locally.
+    DirectResponse(filter::DirectResponse),
+    /// Delegates to a filter that is implemented outside of the proxy, e.g. by
+    /// a WebAssembly module.
+    Extension(filter::Extension),
     InternalError(&'static str),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct StatusRanges(pub Arc<[RangeInclusive<u16>]>);
 
+/// Configures an in-memory cache of a route's responses.
+///
+/// Responses are cached as directed by their `Cache-Control` headers and are
+/// revalidated with their `ETag`s once they become stale.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Cache {
+    /// The maximum total size of cached response bodies, in bytes.
+    pub max_bytes: usize,
+
+    /// The maximum size of a single cached response body, in bytes. Larger
+    /// responses, and responses of unknown length, are not cached.
+    pub max_entry_bytes: usize,
+
+    /// Request headers that distinguish cached responses, in addition to
+    /// those named by a response's `Vary` header.
+    pub vary: Vec<::http::HeaderName>,
+}
+
+/// Configures the decompression of a route's request bodies.
+///
+/// Decompressed bodies are guarded against decompression bombs: requests fail
+/// once their bodies expand beyond `max_expansion_ratio` times their
+/// compressed size.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Decompression {
+    /// The maximum size of a decompressed request body, in bytes. When unset,
+    /// bodies are limited only by `max_expansion_ratio`.
+    pub max_bytes: Option<usize>,
+
+    /// The maximum ratio of a request body's decompressed size to its
+    /// compressed size.
+    pub max_expansion_ratio: u32,
+}
+
+/// Limits the size of a route's request bodies.
+///
+/// Requests that declare a larger `content-length` are rejected before their
+/// bodies are read. Other bodies fail once they exceed the limit.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RequestBodyLimit {
+    pub max_bytes: u64,
+}
+
+/// Configures timeouts for a route whose responses are streamed, e.g. as
+/// server-sent events.
+///
+/// A streaming route's request timeout bounds the time until the first byte of
+/// the response body rather than only the response headers. Thereafter, the
+/// response body is terminated once no data has been received for
+/// `idle_timeout`, so that long-lived streams need not be bounded by the
+/// request timeout.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Streaming {
+    pub idle_timeout: std::time::Duration,
+}
+
+/// Identifies the requests that a route with a weighted distribution must
+/// consistently assign to the same backend, e.g. for A/B tests.
+///
+/// The key's value is hashed onto the distribution's weights, so each value is
+/// assigned to a backend in proportion to the weights and keeps that
+/// assignment for as long as the route's backends and weights are unchanged.
+/// Requests without a key are distributed at random.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StickyKey {
+    /// The value of a request header.
+    Header(::http::HeaderName),
+
+    /// The value of a named cookie.
+    Cookie(String),
+
+    /// The client's IP address.
+    ClientIp,
+}
+
+/// Mirrors a route's requests to a shadow backend, e.g. to validate a rewritten
+/// service before it receives traffic.
+///
+/// The shadow backend's responses are discarded after they are compared with
+/// the route's responses by status, by the values of `compare_headers`, and by
+/// a hash of their bodies.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mirror {
+    pub backend: crate::Backend,
+    pub compare_headers: Vec<::http::HeaderName>,
+}
+
+/// Configures a route's success-rate objective.
+///
+/// The route's error budget is the fraction of its requests that may fail
+/// without missing the objective. The proxy reports the rate at which failures
+/// consume the budget over each of the `windows`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SuccessObjective {
+    /// The fraction of requests that must succeed, in parts per million, e.g.
+    /// 999_000 for 99.9%.
+    pub target_ppm: u32,
+
+    pub windows: Vec<std::time::Duration>,
+}
+
 pub fn default(distribution: crate::RouteDistribution<Filter>) -> Route {
     Route {
         hosts: vec![],
@@ -48,6 +158,13 @@ pub fn default(distribution: crate::RouteDistribution<Filter>) -> Route {
                 filters: Arc::new([]),
                 distribution,
                 failure_policy: StatusRanges::default(),
+                cache: None,
+                request_decompression: None,
+                request_body_limit: None,
+                streaming: None,
+                sticky: None,
+                mirror: None,
+                success_objective: None,
                 request_timeout: None,
             },
         }],
@@ -196,11 +313,7 @@ pub mod proto {
             rules,
             metadata,
         } = proto;
-        let meta = Arc::new(
-            metadata
-                .ok_or(InvalidMeta("missing metadata"))?
-                .try_into()?,
-        );
+        let meta = Meta::try_from(metadata.ok_or(InvalidMeta("missing metadata"))?)?.interned();
         let hosts = hosts
             .into_iter()
             .map(r#match::MatchHost::try_from)
@@ -250,6 +363,13 @@ pub mod proto {
                 filters,
                 distribution,
                 failure_policy: StatusRanges::default(),
+                cache: None,
+                request_decompression: None,
+                request_body_limit: None,
+                streaming: None,
+                sticky: None,
+                mirror: None,
+                success_objective: None,
                 request_timeout,
             },
         })
@@ -7,6 +7,7 @@ use std::{borrow::Cow, fmt, hash::Hash, net::SocketAddr, num::NonZeroU16, sync::
 pub mod grpc;
 pub mod http;
 pub mod opaq;
+pub mod overrides;
 
 pub use linkerd_http_route as route;
 pub use linkerd_proxy_api_resolve::Metadata as EndpointMetadata;
@@ -73,6 +74,54 @@ pub struct RoutePolicy<T, F> {
 
     /// Configures what responses are classified as failures.
     pub failure_policy: F,
+
+    /// Configures an in-memory cache of the route's responses.
+    ///
+    /// Like `request_timeout`, caching wraps the response future, so it can't
+    /// be modeled as a filter. Only responses to `GET` requests are cached, so
+    /// this has no effect on gRPC routes, and it is ignored by opaque routes.
+    pub cache: Option<http::Cache>,
+
+    /// Decompresses gzip-encoded request bodies before they are forwarded, so
+    /// that body size limits apply to the decompressed body.
+    ///
+    /// Decompression replaces the request's body, so, like caching, it can't
+    /// be modeled as a filter. This is ignored by opaque routes.
+    pub request_decompression: Option<http::Decompression>,
+
+    /// Limits the size of the route's request bodies.
+    ///
+    /// Like `request_timeout`, the limit may fail a request before it is
+    /// dispatched, so it can't be modeled as a filter. This is ignored by
+    /// opaque routes.
+    pub request_body_limit: Option<http::RequestBodyLimit>,
+
+    /// Configures timeouts for streamed responses.
+    ///
+    /// Streaming timeouts wrap the response body, so, like caching, they can't
+    /// be modeled as a filter. This is ignored by opaque routes.
+    pub streaming: Option<http::Streaming>,
+
+    /// Assigns requests to the route's weighted backends by a request key
+    /// rather than at random.
+    ///
+    /// Backends are selected before requests are dispatched, so this can't be
+    /// modeled as a filter. This is ignored by opaque routes.
+    pub sticky: Option<http::StickyKey>,
+
+    /// Mirrors the route's requests to a shadow backend and compares its
+    /// responses with the route's.
+    ///
+    /// Mirroring wraps the response future, so, like caching, it can't be
+    /// modeled as a filter. This is ignored by opaque routes.
+    pub mirror: Option<http::Mirror>,
+
+    /// Configures the route's success-rate objective, so that the proxy
+    /// reports how quickly the route consumes its error budget.
+    ///
+    /// Responses are classified by the route's `failure_policy`. This is
+    /// ignored by opaque routes.
+    pub success_objective: Option<http::SuccessObjective>,
 }
 
 // TODO(ver) Weighted random WITHOUT availability awareness, as required by
@@ -88,6 +137,9 @@ pub enum RouteDistribution<T> {
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct RouteBackend<T> {
+    /// Filters applied only to requests dispatched to this backend, after the
+    /// route's filters. When traffic is split, these may, for instance, add a
+    /// header that distinguishes each backend's requests.
     pub filters: Arc<[T]>,
     pub backend: Backend,
     pub request_timeout: Option<time::Duration>,
@@ -168,6 +220,13 @@ impl ClientPolicy {
                         .collect(),
                         distribution: RouteDistribution::Empty,
                         failure_policy: http::StatusRanges::default(),
+                        cache: None,
+                        request_decompression: None,
+                        request_body_limit: None,
+                        streaming: None,
+                        sticky: None,
+                        mirror: None,
+                        success_objective: None,
                         request_timeout: None,
                     },
                 }],
@@ -333,10 +392,15 @@ pub mod proto {
     };
     use linkerd_error::Error;
     use linkerd_proxy_api_resolve::pb as resolve;
+    use linkerd_proxy_core::Interner;
     use std::time::Duration;
 
     pub(crate) type BackendSet = ahash::AHashSet<Backend>;
 
+    /// Shares metadata across policy updates so that unchanged metadata is
+    /// not reallocated.
+    static METAS: Lazy<Interner<Meta>> = Lazy::new(Interner::new);
+
     #[derive(Debug, thiserror::Error)]
     pub enum InvalidPolicy {
         #[error("invalid HTTP route: {0}")]
@@ -439,7 +503,7 @@ pub mod proto {
         fn try_from(policy: outbound::OutboundPolicy) -> Result<Self, Self::Error> {
             use outbound::proxy_protocol;
 
-            let parent = policy
+            let parent: Meta = policy
                 .metadata
                 .ok_or(InvalidPolicy::MissingMeta)?
                 .try_into()?;
@@ -517,13 +581,20 @@ pub mod proto {
             }
 
             Ok(ClientPolicy {
-                parent: Arc::new(parent),
+                parent: parent.interned(),
                 protocol,
                 backends: backends.into_iter().collect(),
             })
         }
     }
 
+    impl Meta {
+        /// Returns a shared reference to metadata equal to `self`.
+        pub(crate) fn interned(self) -> Arc<Self> {
+            METAS.intern(Arc::new(self))
+        }
+    }
+
     impl TryFrom<meta::Metadata> for Meta {
         type Error = InvalidMeta;
 
@@ -642,13 +713,12 @@ pub mod proto {
                     .map_err(|error| InvalidBackend::Duration { field, error })
             }
 
-            let meta: Arc<Meta> = {
-                let meta = backend
+            let meta = Meta::try_from(
+                backend
                     .metadata
-                    .ok_or(InvalidBackend::Missing("backend metadata"))?
-                    .try_into()?;
-                Arc::new(meta)
-            };
+                    .ok_or(InvalidBackend::Missing("backend metadata"))?,
+            )?
+            .interned();
 
             let dispatcher = match backend.kind {
                 Some(backend::Kind::Balancer(BalanceP2c { discovery, load })) => {
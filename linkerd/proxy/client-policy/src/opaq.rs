@@ -81,11 +81,7 @@ pub(crate) mod proto {
         fn try_from(
             outbound::OpaqueRoute { metadata, rules }: outbound::OpaqueRoute,
         ) -> Result<Self, Self::Error> {
-            let meta = Arc::new(
-                metadata
-                    .ok_or(InvalidMeta("missing metadata"))?
-                    .try_into()?,
-            );
+            let meta = Meta::try_from(metadata.ok_or(InvalidMeta("missing metadata"))?)?.interned();
 
             // Currently, opaque rules have no match expressions, so if there's
             // more than one rule, we have no way of determining which one to
@@ -126,6 +122,13 @@ pub(crate) mod proto {
             meta: meta.clone(),
             filters: NO_FILTERS.clone(),
             failure_policy: NonIoErrors,
+            cache: None,
+            request_decompression: None,
+            request_body_limit: None,
+            streaming: None,
+            sticky: None,
+            mirror: None,
+            success_objective: None,
             distribution,
             // Request timeouts are ignored on opaque routes.
             request_timeout: None,
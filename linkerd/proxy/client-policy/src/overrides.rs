@@ -0,0 +1,277 @@
+//! Client policy settings that are not yet expressible in the control plane
+//! API.
+//!
+//! These settings are configured locally and applied to each policy as it is
+//! discovered, so that they take effect regardless of the policy's source.
+
+use crate::{grpc, http, route, ClientPolicy, Protocol, RoutePolicy};
+use std::{collections::HashMap, sync::Arc};
+
+/// Locally-configured settings that are applied to client policies.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Overrides {
+    /// Settings for HTTP and gRPC routes, by the names of the route
+    /// resources.
+    pub routes: HashMap<String, RouteOverrides>,
+}
+
+/// Settings for a route's rules.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteOverrides {
+    /// Answers the route's requests with a static response. This only applies
+    /// to HTTP routes.
+    pub direct_response: Option<http::filter::DirectResponse>,
+
+    /// Caches the route's responses.
+    pub cache: Option<http::Cache>,
+
+    /// Decompresses the route's gzip-encoded request bodies.
+    pub request_decompression: Option<http::Decompression>,
+
+    /// Limits the size of the route's request bodies.
+    pub request_body_limit: Option<http::RequestBodyLimit>,
+
+    /// Treats the route's responses as streams that time out when idle.
+    pub streaming: Option<http::Streaming>,
+
+    /// Consistently assigns requests to the route's weighted backends by a
+    /// request key.
+    pub sticky: Option<http::StickyKey>,
+
+    /// Mirrors the route's requests to a shadow backend.
+    pub mirror: Option<http::Mirror>,
+
+    /// Reports the route's error budget consumption.
+    pub success_objective: Option<http::SuccessObjective>,
+
+    /// Narrows the requests that the route's rules match. Only header matches
+    /// apply to gRPC routes.
+    pub matches: Option<route::http::MatchRequest>,
+}
+
+/// Builds the filters that are configured for a route, so that settings can
+/// be applied to HTTP and gRPC routes alike.
+trait RouteFilters: Sized {
+    fn filters(overrides: &RouteOverrides) -> Vec<Self>;
+}
+
+/// Narrows the matches of a route's rules, so that settings can be applied to
+/// HTTP and gRPC routes alike.
+trait RouteMatches: Sized {
+    fn narrow(matches: &mut Vec<Self>, narrow: &route::http::MatchRequest);
+}
+
+// === impl Overrides ===
+
+impl Overrides {
+    /// Applies the settings configured for each of the policy's routes.
+    pub fn apply(&self, mut policy: ClientPolicy) -> ClientPolicy {
+        if self.routes.is_empty() {
+            return policy;
+        }
+
+        policy.protocol = match policy.protocol {
+            Protocol::Detect {
+                timeout,
+                http1,
+                http2,
+                opaque,
+            } => Protocol::Detect {
+                timeout,
+                http1: http::Http1 {
+                    routes: self.apply_routes(&http1.routes),
+                    ..http1
+                },
+                http2: http::Http2 {
+                    routes: self.apply_routes(&http2.routes),
+                    ..http2
+                },
+                opaque,
+            },
+            Protocol::Http1(http1) => Protocol::Http1(http::Http1 {
+                routes: self.apply_routes(&http1.routes),
+                ..http1
+            }),
+            Protocol::Http2(http2) => Protocol::Http2(http::Http2 {
+                routes: self.apply_routes(&http2.routes),
+                ..http2
+            }),
+            Protocol::Grpc(grpc) => Protocol::Grpc(grpc::Grpc {
+                routes: self.apply_routes(&grpc.routes),
+                ..grpc
+            }),
+            protocol => protocol,
+        };
+        policy
+    }
+
+    fn apply_routes<M: Clone + RouteMatches, T: Clone + RouteFilters, F: Clone>(
+        &self,
+        routes: &[route::Route<M, RoutePolicy<T, F>>],
+    ) -> Arc<[route::Route<M, RoutePolicy<T, F>>]> {
+        routes
+            .iter()
+            .cloned()
+            .map(|mut route| {
+                for rule in &mut route.rules {
+                    if let Some(overrides) = self.routes.get(rule.policy.meta.name()) {
+                        if let Some(matches) = &overrides.matches {
+                            M::narrow(&mut rule.matches, matches);
+                        }
+                        overrides.apply(&mut rule.policy);
+                    }
+                }
+                route
+            })
+            .collect()
+    }
+}
+
+// === impl RouteOverrides ===
+
+impl RouteOverrides {
+    fn apply<T: Clone + RouteFilters, F>(&self, policy: &mut RoutePolicy<T, F>) {
+        if let Some(cache) = &self.cache {
+            policy.cache = Some(cache.clone());
+        }
+        if let Some(decompression) = &self.request_decompression {
+            policy.request_decompression = Some(decompression.clone());
+        }
+        if let Some(limit) = &self.request_body_limit {
+            policy.request_body_limit = Some(limit.clone());
+        }
+        if let Some(streaming) = &self.streaming {
+            policy.streaming = Some(streaming.clone());
+        }
+        if let Some(sticky) = &self.sticky {
+            policy.sticky = Some(sticky.clone());
+        }
+        if let Some(mirror) = &self.mirror {
+            policy.mirror = Some(mirror.clone());
+        }
+        if let Some(objective) = &self.success_objective {
+            policy.success_objective = Some(objective.clone());
+        }
+
+        let filters = T::filters(self);
+        if !filters.is_empty() {
+            policy.filters = policy.filters.iter().cloned().chain(filters).collect();
+        }
+    }
+}
+
+impl RouteFilters for http::Filter {
+    fn filters(overrides: &RouteOverrides) -> Vec<Self> {
+        let direct = overrides.direct_response.clone().map(Self::DirectResponse);
+        direct.into_iter().collect()
+    }
+}
+
+impl RouteFilters for grpc::Filter {
+    fn filters(_: &RouteOverrides) -> Vec<Self> {
+        Vec::new()
+    }
+}
+
+impl RouteMatches for route::http::MatchRequest {
+    fn narrow(matches: &mut Vec<Self>, narrow: &route::http::MatchRequest) {
+        // A rule without matches matches all requests.
+        if matches.is_empty() {
+            matches.push(Self::default());
+        }
+        for m in matches {
+            m.narrow(narrow);
+        }
+    }
+}
+
+impl RouteMatches for route::grpc::MatchRoute {
+    fn narrow(matches: &mut Vec<Self>, narrow: &route::http::MatchRequest) {
+        if narrow.headers.is_empty() {
+            return;
+        }
+        if matches.is_empty() {
+            matches.push(Self::default());
+        }
+        for m in matches {
+            m.headers.extend(narrow.headers.iter().cloned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Meta, RouteDistribution};
+
+    fn policy(routes: Arc<[http::Route]>) -> ClientPolicy {
+        ClientPolicy {
+            parent: Meta::new_default("parent"),
+            protocol: Protocol::Http1(http::Http1 {
+                routes,
+                failure_accrual: Default::default(),
+            }),
+            backends: Arc::new([]),
+        }
+    }
+
+    fn route(name: &str) -> http::Route {
+        let mut route = http::default(RouteDistribution::Empty);
+        route.rules[0].policy.meta = Arc::new(Meta::Resource {
+            group: "gateway.networking.k8s.io".into(),
+            kind: "HTTPRoute".into(),
+            name: name.into(),
+            namespace: "ns".into(),
+            section: None,
+            port: None,
+        });
+        route
+    }
+
+    #[test]
+    fn applies_route_settings() {
+        let direct = http::filter::DirectResponse {
+            status: ::http::StatusCode::SERVICE_UNAVAILABLE,
+            headers: vec![],
+            body: Default::default(),
+        };
+        let cache = http::Cache {
+            max_bytes: 1024,
+            max_entry_bytes: 128,
+            vary: vec![],
+        };
+        let template = route::http::MatchRequest {
+            path: Some(route::http::r#match::MatchPath::Template(
+                "/users/{id}".parse().unwrap(),
+            )),
+            ..Default::default()
+        };
+        let overrides = Overrides {
+            routes: Some((
+                "api".to_string(),
+                RouteOverrides {
+                    direct_response: Some(direct.clone()),
+                    cache: Some(cache.clone()),
+                    matches: Some(template.clone()),
+                    ..Default::default()
+                },
+            ))
+            .into_iter()
+            .collect(),
+        };
+
+        let policy = overrides.apply(policy(Arc::new([route("api"), route("web")])));
+        let Protocol::Http1(http1) = policy.protocol else {
+            panic!("protocol must not change");
+        };
+        assert_eq!(
+            http1.routes[0].rules[0].policy.filters[..],
+            [http::Filter::DirectResponse(direct)]
+        );
+        assert_eq!(http1.routes[0].rules[0].policy.cache, Some(cache));
+        assert!(http1.routes[1].rules[0].policy.filters.is_empty());
+        assert_eq!(http1.routes[1].rules[0].policy.cache, None);
+        assert_eq!(http1.routes[0].rules[0].matches, [template]);
+        assert!(http1.routes[1].rules[0].matches.is_empty());
+    }
+}
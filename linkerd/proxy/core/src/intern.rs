@@ -0,0 +1,120 @@
+use parking_lot::Mutex;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Weak},
+};
+
+/// Shares equal values so that values that are rebuilt (e.g. as policy
+/// updates are received) reference a single allocation.
+///
+/// The interner only holds weak references, so values are freed once they are
+/// no longer referenced elsewhere.
+#[derive(Debug)]
+pub struct Interner<T: ?Sized> {
+    values: Mutex<Values<T>>,
+}
+
+#[derive(Debug)]
+struct Values<T: ?Sized> {
+    by_hash: HashMap<u64, Vec<Weak<T>>>,
+    /// The number of hash buckets after which released values are pruned.
+    prune_at: usize,
+}
+
+const MIN_PRUNE_AT: usize = 64;
+
+// === impl Interner ===
+
+impl<T: ?Sized + Eq + Hash> Interner<T> {
+    pub fn new() -> Self {
+        Self {
+            values: Mutex::new(Values {
+                by_hash: HashMap::new(),
+                prune_at: MIN_PRUNE_AT,
+            }),
+        }
+    }
+
+    /// Returns a previously-interned value that is equal to `value`, if one
+    /// is still referenced. Otherwise, `value` is interned and returned.
+    pub fn intern(&self, value: Arc<T>) -> Arc<T> {
+        let hash = {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let mut values = self.values.lock();
+        let bucket = values.by_hash.entry(hash).or_default();
+        bucket.retain(|v| v.strong_count() > 0);
+        if let Some(interned) = bucket
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|v| **v == *value)
+        {
+            return interned;
+        }
+        bucket.push(Arc::downgrade(&value));
+
+        if values.by_hash.len() >= values.prune_at {
+            values.prune();
+        }
+        value
+    }
+}
+
+impl<T: ?Sized + Eq + Hash> Default for Interner<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// === impl Values ===
+
+impl<T: ?Sized> Values<T> {
+    fn prune(&mut self) {
+        self.by_hash.retain(|_, bucket| {
+            bucket.retain(|v| v.strong_count() > 0);
+            !bucket.is_empty()
+        });
+        self.prune_at = (self.by_hash.len() * 2).max(MIN_PRUNE_AT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_equal_values() {
+        let interner = Interner::<str>::new();
+
+        let a = interner.intern("a".into());
+        let b = interner.intern("a".into());
+        assert!(Arc::ptr_eq(&a, &b));
+
+        let c = interner.intern("c".into());
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+
+    #[test]
+    fn releases_values() {
+        let interner = Interner::<str>::new();
+
+        let a = interner.intern("a".into());
+        let weak = Arc::downgrade(&a);
+        drop(a);
+        assert!(weak.upgrade().is_none());
+
+        // A new value is interned once the prior value has been released.
+        let b: Arc<str> = "a".into();
+        assert!(Arc::ptr_eq(&interner.intern(b.clone()), &b));
+
+        // Released values are pruned as the interner grows.
+        for i in 0..MIN_PRUNE_AT {
+            interner.intern(i.to_string().into());
+        }
+        assert!(interner.values.lock().by_hash.len() < MIN_PRUNE_AT);
+    }
+}
@@ -1,6 +1,10 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+pub mod intern;
 pub mod resolve;
 
-pub use self::resolve::{Resolve, ResolveService, Update};
+pub use self::{
+    intern::Interner,
+    resolve::{Resolve, ResolveService, Update},
+};
@@ -32,7 +32,10 @@ pub struct MakeClient<C, B> {
     _marker: PhantomData<fn(B)>,
 }
 
-pub enum Client<C, T, B> {
+pub enum Client<C, T, B>
+where
+    C: MakeConnection<(crate::Version, T)>,
+{
     H2(h2::Connection<B>),
     Http1(h1::Client<C, T, B>),
     OrigProtoUpgrade(orig_proto::Upgrade<C, T, B>),
@@ -134,7 +137,8 @@ impl<C, T, B> Service<http::Request<B>> for Client<C, T, B>
 where
     T: Clone + Send + Sync + 'static,
     C: MakeConnection<(crate::Version, T)> + Clone + Send + Sync + 'static,
-    C::Connection: Unpin + Send,
+    C::Connection: Unpin + Send + 'static,
+    C::Metadata: Send + 'static,
     C::Future: Unpin + Send + 'static,
     C::Error: Into<Error>,
     B: hyper::body::HttpBody + Send + 'static,
@@ -0,0 +1,248 @@
+//! Deadline propagation for plain HTTP requests.
+//!
+//! Much like gRPC's `grpc-timeout` header, a deadline header carries the
+//! number of milliseconds a client is willing to wait for a response. The
+//! server-side [`EnforceDeadline`] service fails requests that outlive their
+//! deadline and the client-side [`PropagateDeadline`] service rewrites the
+//! header with the time that remains, so that time spent in the proxy is
+//! deducted from the deadline seen by the next hop.
+
+use futures::{ready, TryFuture};
+use http::{HeaderMap, HeaderName, HeaderValue};
+use linkerd_error::Error;
+use linkerd_stack::layer;
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::time::{self, Instant};
+
+/// A request extension that records when a request's deadline expires.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+#[derive(Clone, Debug, Error)]
+#[error("HTTP request deadline of {0:?} exceeded")]
+pub struct DeadlineExceededError(Duration);
+
+/// Fails requests that are not answered before the deadline in their
+/// deadline header.
+#[derive(Clone, Debug)]
+pub struct EnforceDeadline<S> {
+    inner: S,
+    header: Option<HeaderName>,
+}
+
+/// Sets the deadline header on requests with the time remaining until their
+/// [`Deadline`].
+#[derive(Clone, Debug)]
+pub struct PropagateDeadline<S> {
+    inner: S,
+    header: Option<HeaderName>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    #[pin]
+    sleep: Option<time::Sleep>,
+    timeout: Duration,
+}
+
+// === impl Deadline ===
+
+impl Deadline {
+    /// Returns the time remaining until the deadline expires.
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+}
+
+fn parse_timeout(headers: &HeaderMap, header: &HeaderName) -> Option<Duration> {
+    let value = headers.get(header)?;
+    let ms = value.to_str().ok().and_then(|v| v.trim().parse().ok());
+    if ms.is_none() {
+        tracing::debug!(?value, %header, "Ignoring invalid deadline header");
+    }
+    ms.map(Duration::from_millis)
+}
+
+// === impl EnforceDeadline ===
+
+impl<S> EnforceDeadline<S> {
+    /// Enforces deadlines read from `header`. When no header is configured,
+    /// requests are passed through unmodified.
+    pub fn layer(
+        header: Option<HeaderName>,
+    ) -> impl tower::layer::Layer<S, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            header: header.clone(),
+        })
+    }
+}
+
+impl<B, S> tower::Service<http::Request<B>> for EnforceDeadline<S>
+where
+    S: tower::Service<http::Request<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        // The header is set by clients, so a deadline that does not fit in an
+        // `Instant` is ignored rather than allowed to overflow.
+        let deadline = self
+            .header
+            .as_ref()
+            .and_then(|h| parse_timeout(req.headers(), h))
+            .and_then(|timeout| Some((timeout, Instant::now().checked_add(timeout)?)));
+        let sleep = deadline.map(|(_, deadline)| {
+            req.extensions_mut().insert(Deadline(deadline));
+            time::sleep_until(deadline)
+        });
+        ResponseFuture {
+            inner: self.inner.call(req),
+            sleep,
+            timeout: deadline.map(|(timeout, _)| timeout).unwrap_or_default(),
+        }
+    }
+}
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: TryFuture,
+    F::Error: Into<Error>,
+{
+    type Output = Result<F::Ok, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Poll::Ready(res) = this.inner.try_poll(cx) {
+            return Poll::Ready(res.map_err(Into::into));
+        }
+        if let Some(sleep) = this.sleep.as_pin_mut() {
+            ready!(sleep.poll(cx));
+            return Poll::Ready(Err(DeadlineExceededError(*this.timeout).into()));
+        }
+        Poll::Pending
+    }
+}
+
+// === impl PropagateDeadline ===
+
+impl<S> PropagateDeadline<S> {
+    /// Writes the remaining time to `header`. When no header is configured,
+    /// requests are passed through unmodified.
+    pub fn layer(
+        header: Option<HeaderName>,
+    ) -> impl tower::layer::Layer<S, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            header: header.clone(),
+        })
+    }
+}
+
+impl<B, S> tower::Service<http::Request<B>> for PropagateDeadline<S>
+where
+    S: tower::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if let Some(header) = self.header.as_ref() {
+            if let Some(deadline) = req.extensions().get::<Deadline>().copied() {
+                let ms = deadline.remaining().as_millis();
+                req.headers_mut()
+                    .insert(header.clone(), HeaderValue::from(ms as u64));
+            }
+        }
+        self.inner.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::{Layer, Service, ServiceExt};
+
+    fn header() -> HeaderName {
+        HeaderName::from_static("x-deadline-ms")
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn fails_requests_after_deadline() {
+        let (inner, mut handle) = tower_test::mock::pair::<http::Request<()>, ()>();
+        let mut svc = EnforceDeadline::layer(Some(header())).layer(inner);
+        handle.allow(1);
+
+        let req = http::Request::builder()
+            .header(header(), "100")
+            .body(())
+            .unwrap();
+        let rsp = svc.ready().await.unwrap().call(req);
+        let (req, _send) = handle.next_request().await.unwrap();
+        assert!(req.extensions().get::<Deadline>().is_some());
+
+        let err = rsp.await.expect_err("deadline must be exceeded");
+        assert!(err.is::<DeadlineExceededError>());
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn tolerates_large_deadlines() {
+        let (inner, mut handle) = tower_test::mock::pair::<http::Request<()>, ()>();
+        let mut svc = EnforceDeadline::layer(Some(header())).layer(inner);
+        handle.allow(1);
+
+        let req = http::Request::builder()
+            .header(header(), u64::MAX.to_string())
+            .body(())
+            .unwrap();
+        let rsp = svc.ready().await.unwrap().call(req);
+        // The deadline must not overflow, whether or not it can be recorded.
+        let (_req, send) = handle.next_request().await.unwrap();
+        time::sleep(Duration::from_secs(60 * 60)).await;
+        send.send_response(());
+        rsp.await.expect("request must not fail");
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn propagates_remaining_time() {
+        let (inner, mut handle) = tower_test::mock::pair::<http::Request<()>, ()>();
+        let mut svc = PropagateDeadline::layer(Some(header())).layer(inner);
+        handle.allow(1);
+
+        let mut req = http::Request::builder()
+            .header(header(), "100")
+            .body(())
+            .unwrap();
+        req.extensions_mut()
+            .insert(Deadline(Instant::now() + Duration::from_millis(100)));
+        time::sleep(Duration::from_millis(30)).await;
+        let _rsp = svc.ready().await.unwrap().call(req);
+
+        let (req, _send) = handle.next_request().await.unwrap();
+        assert_eq!(req.headers()[header()], "70");
+    }
+}
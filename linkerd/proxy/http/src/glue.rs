@@ -1,6 +1,10 @@
-use crate::{upgrade::Http11Upgrade, HasH2Reason};
+use crate::{
+    h1::{RequestCount, Warm},
+    upgrade::Http11Upgrade,
+    HasH2Reason,
+};
 use bytes::Bytes;
-use futures::TryFuture;
+use futures::{future, TryFuture, TryFutureExt};
 use hyper::body::HttpBody;
 use hyper::client::connect as hyper_connect;
 use linkerd_error::{Error, Result};
@@ -31,11 +35,14 @@ pub struct HyperServerSvc<S> {
 }
 
 /// Glue for any `tokio_connect::Connect` to implement `hyper::client::Connect`.
-#[derive(Debug, Clone)]
-pub struct HyperConnect<C, T> {
+pub struct HyperConnect<C, T>
+where
+    C: MakeConnection<(crate::Version, T)>,
+{
     connect: C,
     absolute_form: bool,
     target: T,
+    warm: Option<Warm<C::Connection, C::Metadata>>,
 }
 
 #[pin_project]
@@ -44,6 +51,7 @@ pub struct Connection<T> {
     #[pin]
     transport: T,
     absolute_form: bool,
+    requests: RequestCount,
 }
 
 /// Future returned by `HyperConnect`.
@@ -159,36 +167,93 @@ where
 
 // === impl HyperConnect ===
 
-impl<C, T> HyperConnect<C, T> {
-    pub(super) fn new(connect: C, target: T, absolute_form: bool) -> Self {
+impl<C, T> HyperConnect<C, T>
+where
+    C: MakeConnection<(crate::Version, T)>,
+{
+    pub(super) fn new(
+        connect: C,
+        target: T,
+        absolute_form: bool,
+        warm: Option<Warm<C::Connection, C::Metadata>>,
+    ) -> Self {
         HyperConnect {
             connect,
             target,
             absolute_form,
+            warm,
         }
     }
 }
 
+impl<C, T> std::fmt::Debug for HyperConnect<C, T>
+where
+    C: MakeConnection<(crate::Version, T)> + std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HyperConnect")
+            .field("connect", &self.connect)
+            .field("absolute_form", &self.absolute_form)
+            .field("target", &self.target)
+            .field("warm", &self.warm)
+            .finish()
+    }
+}
+
+impl<C, T> Clone for HyperConnect<C, T>
+where
+    C: MakeConnection<(crate::Version, T)> + Clone,
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            connect: self.connect.clone(),
+            target: self.target.clone(),
+            absolute_form: self.absolute_form,
+            warm: self.warm.clone(),
+        }
+    }
+}
+
+type WarmConnection<C, T> = Result<(
+    <C as MakeConnection<(crate::Version, T)>>::Connection,
+    <C as MakeConnection<(crate::Version, T)>>::Metadata,
+)>;
+
 impl<C, T> Service<hyper::Uri> for HyperConnect<C, T>
 where
-    C: MakeConnection<(crate::Version, T)> + Clone + Send + Sync,
-    C::Connection: Unpin + Send,
+    C: MakeConnection<(crate::Version, T)> + Clone + Send + Sync + 'static,
+    C::Connection: Unpin + Send + 'static,
+    C::Metadata: Send + 'static,
     C::Future: Unpin + Send + 'static,
-    T: Clone + Send + Sync,
+    T: Clone + Send + Sync + 'static,
 {
     type Response = Connection<C::Connection>;
     type Error = Error;
-    type Future = HyperConnectFuture<C::Future>;
+    type Future = HyperConnectFuture<
+        future::Either<future::ErrInto<C::Future, Error>, future::Ready<WarmConnection<C, T>>>,
+    >;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         self.connect.poll_ready(cx).map_err(Into::into)
     }
 
     fn call(&mut self, _dst: hyper::Uri) -> Self::Future {
+        let warm = self.warm.as_ref().and_then(|warm| warm.take());
+        let inner = match warm {
+            Some(conn) => {
+                debug!("Using warm connection");
+                future::Either::Right(future::ok(conn))
+            }
+            None => future::Either::Left(
+                self.connect
+                    .connect((crate::Version::Http1, self.target.clone()))
+                    .err_into(),
+            ),
+        };
         HyperConnectFuture {
-            inner: self
-                .connect
-                .connect((crate::Version::Http1, self.target.clone())),
+            inner,
             absolute_form: self.absolute_form,
         }
     }
@@ -207,6 +272,7 @@ where
         Poll::Ready(Ok(Connection {
             transport,
             absolute_form: *this.absolute_form,
+            requests: RequestCount::default(),
         }))
     }
 }
@@ -269,6 +335,8 @@ where
 
 impl<C> hyper_connect::Connection for Connection<C> {
     fn connected(&self) -> hyper_connect::Connected {
-        hyper_connect::Connected::new().proxy(self.absolute_form)
+        hyper_connect::Connected::new()
+            .proxy(self.absolute_form)
+            .extra(self.requests.clone())
     }
 }
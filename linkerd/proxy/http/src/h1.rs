@@ -4,15 +4,28 @@ use crate::{
 };
 use futures::prelude::*;
 use http::{
-    header::{CONNECTION, CONTENT_LENGTH, HOST, TRANSFER_ENCODING, UPGRADE},
+    header::{HeaderValue, CONNECTION, CONTENT_LENGTH, HOST, TE, TRANSFER_ENCODING, UPGRADE},
     uri::{Authority, Parts, Scheme, Uri},
 };
 use linkerd_error::{Error, Result};
 use linkerd_http_box::BoxBody;
 use linkerd_stack::MakeConnection;
-use std::{future::Future, mem, pin::Pin, time::Duration};
+use std::{
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tracing::{debug, trace};
 
+mod warm;
+
+pub(crate) use self::warm::Warm;
+
 #[derive(Copy, Clone, Debug)]
 pub struct WasAbsoluteForm(pub(crate) ());
 
@@ -20,42 +33,100 @@ pub struct WasAbsoluteForm(pub(crate) ());
 pub struct PoolSettings {
     pub max_idle: usize,
     pub idle_timeout: Duration,
+
+    /// The maximum number of requests that may be dispatched on a single
+    /// connection before it is retired from the pool. Unlimited when `None`.
+    pub max_requests_per_connection: Option<usize>,
+
+    /// The number of connections that are established when a client is built,
+    /// before any requests are dispatched, and kept ready for use.
+    pub warm_connections: usize,
 }
 
+/// Counts the responses received on a pooled connection.
+///
+/// Set by `HyperConnect` as connection metadata, so that it is attached to
+/// each response received on the connection.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RequestCount(Arc<AtomicUsize>);
+
 /// Communicates with HTTP/1.x servers.
 ///
 /// The client handles both absolute-form and origin-form requests by lazily
 /// building a client for each form, as needed. This is needed because Hyper
 /// requires that this is configured at the connection level; so we can possibly
 /// end up maintaining two independent connection pools, if needed.
-#[derive(Debug)]
-pub struct Client<C, T, B> {
+pub struct Client<C, T, B>
+where
+    C: MakeConnection<(crate::Version, T)>,
+{
     connect: C,
     target: T,
     absolute_form: Option<hyper::Client<HyperConnect<C, T>, B>>,
     origin_form: Option<hyper::Client<HyperConnect<C, T>, B>>,
+    warm: Option<Warm<C::Connection, C::Metadata>>,
     pool: PoolSettings,
 }
 
-impl<C, T, B> Client<C, T, B> {
+impl<C, T, B> Client<C, T, B>
+where
+    T: Clone + Send + 'static,
+    C: MakeConnection<(crate::Version, T)> + Clone + Send + 'static,
+    C::Connection: Unpin + Send + 'static,
+    C::Metadata: Send + 'static,
+    C::Future: Send,
+{
     pub fn new(connect: C, target: T, pool: PoolSettings) -> Self {
+        let warm = if pool.warm_connections > 0 {
+            Some(Warm::spawn(
+                connect.clone(),
+                target.clone(),
+                pool.warm_connections,
+                pool.idle_timeout,
+            ))
+        } else {
+            None
+        };
         Self {
             connect,
             target,
             absolute_form: None,
             origin_form: None,
+            warm,
             pool,
         }
     }
 }
 
-impl<C: Clone, T: Clone, B> Clone for Client<C, T, B> {
+impl<C, T, B> std::fmt::Debug for Client<C, T, B>
+where
+    C: MakeConnection<(crate::Version, T)> + std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("connect", &self.connect)
+            .field("target", &self.target)
+            .field("absolute_form", &self.absolute_form)
+            .field("origin_form", &self.origin_form)
+            .field("warm", &self.warm)
+            .field("pool", &self.pool)
+            .finish()
+    }
+}
+
+impl<C, T, B> Clone for Client<C, T, B>
+where
+    C: MakeConnection<(crate::Version, T)> + Clone,
+    T: Clone,
+{
     fn clone(&self) -> Self {
         Self {
             connect: self.connect.clone(),
             target: self.target.clone(),
             absolute_form: self.absolute_form.clone(),
             origin_form: self.origin_form.clone(),
+            warm: self.warm.clone(),
             pool: self.pool,
         }
     }
@@ -67,7 +138,8 @@ impl<C, T, B> Client<C, T, B>
 where
     T: Clone + Send + Sync + 'static,
     C: MakeConnection<(crate::Version, T)> + Clone + Send + Sync + 'static,
-    C::Connection: Unpin + Send,
+    C::Connection: Unpin + Send + 'static,
+    C::Metadata: Send + 'static,
     C::Future: Unpin + Send + 'static,
     B: hyper::body::HttpBody + Send + 'static,
     B::Data: Send,
@@ -88,6 +160,7 @@ where
             .map(|v| v.is_empty())
             .unwrap_or(true);
 
+        let mut max_requests = None;
         let rsp_fut = if req.version() == http::Version::HTTP_10 || is_missing_host {
             // If there's no authority, we assume we're on some weird HTTP/1.0
             // ish, so we just build a one-off client for the connection.
@@ -100,6 +173,7 @@ where
                     self.connect.clone(),
                     self.target.clone(),
                     use_absolute_form,
+                    self.warm.clone(),
                 ))
                 .request(req)
         } else {
@@ -115,6 +189,14 @@ where
                 &mut self.origin_form
             };
 
+            // When the number of requests per connection is limited, capture
+            // the connection so that it may be poisoned once the limit is
+            // reached; a poisoned connection is not returned to the pool.
+            max_requests = self
+                .pool
+                .max_requests_per_connection
+                .map(|max| (max, hyper::client::connect::capture_connection(&mut req)));
+
             if client.is_none() {
                 debug!(use_absolute_form, "Caching new client");
                 *client = Some(
@@ -126,6 +208,7 @@ where
                             self.connect.clone(),
                             self.target.clone(),
                             use_absolute_form,
+                            self.warm.clone(),
                         )),
                 );
             }
@@ -134,6 +217,17 @@ where
         };
 
         Box::pin(rsp_fut.err_into().map_ok(move |mut rsp| {
+            let count = rsp.extensions_mut().remove::<RequestCount>();
+            if let (Some((max, conn)), Some(RequestCount(count))) = (max_requests, count) {
+                let requests = count.fetch_add(1, Ordering::Relaxed) + 1;
+                if requests >= max {
+                    if let Some(connected) = conn.connection_metadata().as_ref() {
+                        debug!(requests, "Retiring connection");
+                        connected.poison();
+                    }
+                }
+            }
+
             if is_http_connect {
                 debug_assert!(
                     upgrade.is_some(),
@@ -193,6 +287,34 @@ pub(crate) fn set_authority(uri: &mut http::Uri, auth: Authority) {
     *uri = new;
 }
 
+/// Like [`strip_connection_headers`], but retains a client's acceptance of
+/// trailers.
+///
+/// HTTP/1.1 clients must nominate the `te` header in the `connection` header,
+/// so it would otherwise be stripped and trailers could not be forwarded to
+/// the client (e.g. from an HTTP/2 server).
+pub(crate) fn strip_connection_headers_except_trailers(headers: &mut http::HeaderMap) {
+    let trailers = accepts_trailers(headers);
+    strip_connection_headers(headers);
+    if trailers {
+        headers.insert(TE, HeaderValue::from_static("trailers"));
+    } else {
+        headers.remove(TE);
+    }
+}
+
+/// Returns true if the `te` header indicates that trailers are accepted.
+pub(crate) fn accepts_trailers(headers: &http::HeaderMap) -> bool {
+    headers.get_all(TE).iter().any(|te| {
+        te.to_str()
+            .map(|te| {
+                te.split(',')
+                    .any(|v| v.trim().eq_ignore_ascii_case("trailers"))
+            })
+            .unwrap_or(false)
+    })
+}
+
 pub(crate) fn strip_connection_headers(headers: &mut http::HeaderMap) {
     if let Some(val) = headers.remove(CONNECTION) {
         if let Ok(conn_header) = val.to_str() {
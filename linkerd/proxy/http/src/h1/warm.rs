@@ -0,0 +1,288 @@
+use futures::{prelude::*, task::noop_waker_ref};
+use linkerd_error::Error;
+use linkerd_exp_backoff::ExponentialBackoff;
+use linkerd_io::{AsyncRead, ReadBuf};
+use linkerd_stack::MakeConnection;
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Weak},
+    task::Context,
+    time::Duration,
+};
+use tokio::{
+    sync::Notify,
+    time::{self, Instant},
+};
+use tracing::{debug, trace, Instrument};
+
+/// Holds connections that are established before they are needed by a
+/// client's connection pool.
+///
+/// A background task keeps the configured number of connections warm. It
+/// replaces connections as they are taken from the set, as they exceed the
+/// idle timeout, and as they are closed by the server. Failed connection
+/// attempts are retried with an exponential backoff. The task completes once
+/// the client is dropped.
+pub(crate) struct Warm<I, M>(Arc<Shared<I, M>>);
+
+struct Shared<I, M> {
+    connections: Mutex<VecDeque<(Instant, I, M)>>,
+    idle_timeout: Duration,
+
+    /// Notifies the background task that a connection was taken.
+    taken: Notify,
+}
+
+/// Bounds the delay between failed attempts to warm a connection.
+const BACKOFF: ExponentialBackoff =
+    ExponentialBackoff::new_unchecked(Duration::from_millis(100), Duration::from_secs(10), 0.1);
+
+/// How often idle warm connections are checked, so that connections that
+/// expire or are closed by the server are replaced before they are needed.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+impl<I, M> Warm<I, M>
+where
+    I: AsyncRead + Unpin + Send + 'static,
+    M: Send + 'static,
+{
+    /// Spawns a task that establishes and maintains `count` connections.
+    pub(crate) fn spawn<C, T>(connect: C, target: T, count: usize, idle_timeout: Duration) -> Self
+    where
+        C: MakeConnection<(crate::Version, T), Connection = I, Metadata = M>,
+        C: Send + 'static,
+        C::Future: Send,
+        T: Clone + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            connections: Mutex::new(VecDeque::with_capacity(count)),
+            idle_timeout,
+            taken: Notify::new(),
+        });
+        debug!(count, "Warming connections");
+        tokio::spawn(
+            Self::refill(Arc::downgrade(&shared), connect, target, count).in_current_span(),
+        );
+        Self(shared)
+    }
+
+    /// Takes the oldest warm connection that has neither exceeded the idle
+    /// timeout nor been closed.
+    pub(crate) fn take(&self) -> Option<(I, M)> {
+        let taken = self.0.pop_usable(Instant::now());
+        self.0.taken.notify_one();
+        taken
+    }
+
+    async fn refill<C, T>(shared: Weak<Shared<I, M>>, mut connect: C, target: T, count: usize)
+    where
+        C: MakeConnection<(crate::Version, T), Connection = I, Metadata = M>,
+        T: Clone,
+    {
+        let mut backoff = BACKOFF.stream();
+        loop {
+            // If the client has been dropped, there's nothing left to do.
+            let Some(warm) = shared.upgrade() else {
+                return;
+            };
+            if warm.retain_usable(Instant::now()) >= count {
+                tokio::select! {
+                    _ = warm.taken.notified() => {}
+                    _ = time::sleep(CHECK_INTERVAL) => {}
+                }
+                continue;
+            }
+            drop(warm);
+
+            let target = target.clone();
+            let connected = async {
+                future::poll_fn(|cx| connect.poll_ready(cx)).await?;
+                connect.connect((crate::Version::Http1, target)).await
+            };
+            let error: Error = match connected.await {
+                Ok((io, meta)) => {
+                    let Some(warm) = shared.upgrade() else {
+                        return;
+                    };
+                    trace!("Connection warmed");
+                    warm.connections
+                        .lock()
+                        .push_back((Instant::now(), io, meta));
+                    backoff = BACKOFF.stream();
+                    continue;
+                }
+                Err(error) => error.into(),
+            };
+            debug!(%error, "Failed to warm connection");
+            backoff.next().await;
+        }
+    }
+}
+
+impl<I, M> Clone for Warm<I, M> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<I, M> std::fmt::Debug for Warm<I, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Warm")
+            .field("connections", &self.0.connections.lock().len())
+            .field("idle_timeout", &self.0.idle_timeout)
+            .finish()
+    }
+}
+
+// === impl Shared ===
+
+impl<I: AsyncRead + Unpin, M> Shared<I, M> {
+    fn pop_usable(&self, now: Instant) -> Option<(I, M)> {
+        let mut connections = self.connections.lock();
+        while let Some((since, mut io, meta)) = connections.pop_front() {
+            if now.saturating_duration_since(since) < self.idle_timeout && is_open(&mut io) {
+                return Some((io, meta));
+            }
+            trace!("Dropping stale warm connection");
+        }
+        None
+    }
+
+    /// Drops connections that have expired or been closed, returning the
+    /// number of connections that remain.
+    fn retain_usable(&self, now: Instant) -> usize {
+        let mut connections = self.connections.lock();
+        connections.retain_mut(|(since, io, _)| {
+            let usable = now.saturating_duration_since(*since) < self.idle_timeout && is_open(io);
+            if !usable {
+                trace!("Dropping stale warm connection");
+            }
+            usable
+        });
+        connections.len()
+    }
+}
+
+/// Checks whether an idle connection may still be used.
+///
+/// No request has been written to a warm connection, so it must not be
+/// readable: a readable connection has either been closed by the server or
+/// received an unsolicited response (e.g. a `408 Request Timeout`).
+fn is_open<I: AsyncRead + Unpin>(io: &mut I) -> bool {
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let mut byte = [0u8; 1];
+    let mut buf = ReadBuf::new(&mut byte);
+    Pin::new(io).poll_read(&mut cx, &mut buf).is_pending()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_io::{self as io, AsyncWriteExt};
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        task::Poll,
+    };
+
+    /// Connects to in-memory servers, failing the first `failures` attempts.
+    #[derive(Clone, Default)]
+    struct Connect {
+        attempts: Arc<AtomicUsize>,
+        failures: usize,
+        servers: Arc<Mutex<Vec<io::DuplexStream>>>,
+    }
+
+    impl tower::Service<(crate::Version, ())> for Connect {
+        type Response = (io::DuplexStream, ());
+        type Error = io::Error;
+        type Future = future::Ready<io::Result<(io::DuplexStream, ())>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: (crate::Version, ())) -> Self::Future {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return future::err(io::ErrorKind::ConnectionRefused.into());
+            }
+            let (client, server) = io::duplex(64);
+            self.servers.lock().push(server);
+            future::ok((client, ()))
+        }
+    }
+
+    async fn settle() {
+        // Lets the refill task run without advancing the paused clock.
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replaces_taken_connections() {
+        let connect = Connect::default();
+        let warm = Warm::spawn(connect.clone(), (), 2, Duration::from_secs(60));
+        settle().await;
+        assert_eq!(connect.attempts.load(Ordering::SeqCst), 2);
+
+        assert!(warm.take().is_some());
+        settle().await;
+        assert_eq!(connect.attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(warm.0.connections.lock().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_failed_connections() {
+        let connect = Connect {
+            failures: 2,
+            ..Default::default()
+        };
+        let warm = Warm::spawn(connect.clone(), (), 1, Duration::from_secs(60));
+        settle().await;
+        assert_eq!(connect.attempts.load(Ordering::SeqCst), 1);
+        assert!(warm.take().is_none());
+
+        time::sleep(Duration::from_secs(1)).await;
+        settle().await;
+        assert_eq!(connect.attempts.load(Ordering::SeqCst), 3);
+        assert!(warm.take().is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replaces_closed_and_expired_connections() {
+        let connect = Connect::default();
+        let warm = Warm::spawn(connect.clone(), (), 1, Duration::from_secs(60));
+        settle().await;
+        assert_eq!(connect.attempts.load(Ordering::SeqCst), 1);
+
+        // The server closes the connection.
+        connect.servers.lock().clear();
+        time::sleep(2 * CHECK_INTERVAL).await;
+        settle().await;
+        assert_eq!(connect.attempts.load(Ordering::SeqCst), 2);
+
+        // The connection exceeds the idle timeout.
+        time::sleep(Duration::from_secs(61)).await;
+        settle().await;
+        assert_eq!(connect.attempts.load(Ordering::SeqCst), 3);
+        assert!(warm.take().is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn completes_when_dropped() {
+        let connect = Connect::default();
+        let warm = Warm::spawn(connect.clone(), (), 1, Duration::from_secs(60));
+        settle().await;
+        drop(warm);
+
+        time::sleep(CHECK_INTERVAL).await;
+        settle().await;
+        let mut server = connect.servers.lock().pop().unwrap();
+        assert!(
+            server.write_all(b"x").await.is_err(),
+            "warm connections must be dropped"
+        );
+    }
+}
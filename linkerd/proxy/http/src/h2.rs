@@ -1,5 +1,5 @@
 use crate::trace;
-use futures::prelude::*;
+use futures::{prelude::*, ready};
 pub use h2::{Error as H2Error, Reason};
 use hyper::{
     body::HttpBody,
@@ -14,14 +14,23 @@ use std::{
     task::{Context, Poll},
     time::Duration,
 };
+use tokio::time::Instant;
 use tracing::instrument::Instrument;
-use tracing::{debug, debug_span, trace_span};
+use tracing::{debug, debug_span, trace_span, warn};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Settings {
     pub initial_stream_window_size: Option<u32>,
     pub initial_connection_window_size: Option<u32>,
     pub keepalive_timeout: Option<Duration>,
+
+    /// The maximum amount of time a client connection may be used to dispatch
+    /// new requests before it is replaced by a new connection.
+    pub max_connection_age: Option<Duration>,
+
+    /// The maximum number of requests a client connection may dispatch before
+    /// it is replaced by a new connection.
+    pub max_requests_per_connection: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -34,6 +43,23 @@ pub struct Connect<C, B> {
 #[derive(Debug)]
 pub struct Connection<B> {
     tx: SendRequest<B>,
+    rotation: Option<Rotation<B>>,
+}
+
+/// Replaces a client connection once it exceeds its age or request limits or
+/// once the server closes it gracefully (i.e. with a GOAWAY).
+///
+/// A replacement connection is established in the background while the
+/// current connection continues to serve requests. Once the replacement is
+/// ready, the current connection's sender is dropped so that it closes once
+/// all of its in-flight streams complete.
+struct Rotation<B> {
+    reconnect: Box<dyn FnMut() -> HandshakeFuture<B> + Send + 'static>,
+    pending: Option<HandshakeFuture<B>>,
+    max_age: Option<Duration>,
+    max_requests: Option<usize>,
+    established: Instant,
+    requests: usize,
 }
 
 // === impl Connect ===
@@ -60,9 +86,12 @@ impl<C: Clone, B> Clone for Connect<C, B> {
 
 type ConnectFuture<B> = Pin<Box<dyn Future<Output = Result<Connection<B>>> + Send + 'static>>;
 
+type HandshakeFuture<B> = Pin<Box<dyn Future<Output = Result<SendRequest<B>>> + Send + 'static>>;
+
 impl<C, B, T> Service<T> for Connect<C, B>
 where
-    C: MakeConnection<(crate::Version, T)>,
+    T: Clone + Send + 'static,
+    C: MakeConnection<(crate::Version, T)> + Clone + Send + 'static,
     C::Connection: Send + Unpin + 'static,
     C::Metadata: Send,
     C::Future: Send + 'static,
@@ -80,55 +109,103 @@ where
     }
 
     fn call(&mut self, target: T) -> Self::Future {
-        let Settings {
-            initial_connection_window_size,
-            initial_stream_window_size,
-            keepalive_timeout,
-        } = self.h2_settings;
+        let settings = self.h2_settings;
+        let connection = handshake(&mut self.connect, target.clone(), settings);
 
-        let connect = self
-            .connect
-            .connect((crate::Version::H2, target))
-            .instrument(trace_span!("connect").or_current());
+        let rotation = if settings.max_connection_age.is_some()
+            || settings.max_requests_per_connection.is_some()
+        {
+            let connect = self.connect.clone();
+            Some(move || {
+                // Replacement connections are established in the background,
+                // so the connector must be driven to readiness before use.
+                let connect = connect.clone();
+                let target = target.clone();
+                Box::pin(async move {
+                    let mut connect = connect;
+                    futures::future::poll_fn(|cx| connect.poll_ready(cx))
+                        .err_into::<Error>()
+                        .await?;
+                    handshake(&mut connect, target, settings).await
+                }) as HandshakeFuture<B>
+            })
+        } else {
+            None
+        };
 
         Box::pin(
             async move {
-                let (io, _meta) = connect.err_into::<Error>().await?;
-                let mut builder = conn::Builder::new();
-                builder
-                    .http2_only(true)
-                    .http2_initial_stream_window_size(initial_stream_window_size)
-                    .http2_initial_connection_window_size(initial_connection_window_size)
-                    .executor(trace::Executor::new());
-
-                // Configure HTTP/2 PING frames
-                if let Some(timeout) = keepalive_timeout {
-                    // XXX(eliza): is this a reasonable interval between
-                    // PING frames?
-                    let interval = timeout / 4;
-                    builder
-                        .http2_keep_alive_timeout(timeout)
-                        .http2_keep_alive_interval(interval)
-                        .http2_keep_alive_while_idle(true);
-                }
-
-                let (tx, conn) = builder
-                    .handshake(io)
-                    .instrument(trace_span!("handshake").or_current())
-                    .await?;
-
-                tokio::spawn(
-                    conn.map_err(|error| debug!(%error, "failed"))
-                        .instrument(trace_span!("conn").or_current()),
-                );
-
-                Ok(Connection { tx })
+                let tx = connection.await?;
+                let rotation = rotation.map(|reconnect| Rotation {
+                    reconnect: Box::new(reconnect),
+                    pending: None,
+                    max_age: settings.max_connection_age,
+                    max_requests: settings.max_requests_per_connection,
+                    established: Instant::now(),
+                    requests: 0,
+                });
+                Ok(Connection { tx, rotation })
             }
             .instrument(debug_span!("h2").or_current()),
         )
     }
 }
 
+fn handshake<C, B, T>(connect: &mut C, target: T, settings: Settings) -> HandshakeFuture<B>
+where
+    C: MakeConnection<(crate::Version, T)>,
+    C::Connection: Send + Unpin + 'static,
+    C::Metadata: Send,
+    C::Future: Send + 'static,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Error> + Send + Sync,
+{
+    let Settings {
+        initial_connection_window_size,
+        initial_stream_window_size,
+        keepalive_timeout,
+        ..
+    } = settings;
+
+    let connect = connect
+        .connect((crate::Version::H2, target))
+        .instrument(trace_span!("connect").or_current());
+
+    Box::pin(async move {
+        let (io, _meta) = connect.err_into::<Error>().await?;
+        let mut builder = conn::Builder::new();
+        builder
+            .http2_only(true)
+            .http2_initial_stream_window_size(initial_stream_window_size)
+            .http2_initial_connection_window_size(initial_connection_window_size)
+            .executor(trace::Executor::new());
+
+        // Configure HTTP/2 PING frames
+        if let Some(timeout) = keepalive_timeout {
+            // XXX(eliza): is this a reasonable interval between
+            // PING frames?
+            let interval = timeout / 4;
+            builder
+                .http2_keep_alive_timeout(timeout)
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+
+        let (tx, conn) = builder
+            .handshake(io)
+            .instrument(trace_span!("handshake").or_current())
+            .await?;
+
+        tokio::spawn(
+            conn.map_err(|error| debug!(%error, "failed"))
+                .instrument(trace_span!("conn").or_current()),
+        );
+
+        Ok(tx)
+    })
+}
+
 // === impl Connection ===
 
 impl<B> tower::Service<http::Request<B>> for Connection<B>
@@ -141,12 +218,32 @@ where
     type Error = hyper::Error;
     type Future = conn::ResponseFuture;
 
-    #[inline]
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.tx.poll_ready(cx).map_err(From::from)
+        let rotation = match self.rotation.as_mut() {
+            Some(rotation) => rotation,
+            None => return self.tx.poll_ready(cx),
+        };
+
+        loop {
+            let ready = self.tx.poll_ready(cx);
+            let closed = matches!(ready, Poll::Ready(Err(ref e)) if e.is_closed());
+            match ready!(rotation.poll_rotate(closed, cx)) {
+                Some(tx) => {
+                    // Dropping the prior sender causes its connection to be
+                    // closed gracefully once all of its in-flight streams
+                    // complete.
+                    self.tx = tx;
+                }
+                None => return ready,
+            }
+        }
     }
 
     fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if let Some(rotation) = self.rotation.as_mut() {
+            rotation.requests += 1;
+        }
+
         debug_assert_eq!(
             req.version(),
             http::Version::HTTP_2,
@@ -164,3 +261,76 @@ where
         self.tx.send_request(req)
     }
 }
+
+// === impl Rotation ===
+
+impl<B> Rotation<B> {
+    fn is_expired(&self) -> bool {
+        if let Some(max) = self.max_requests {
+            if self.requests >= max {
+                return true;
+            }
+        }
+
+        if let Some(max) = self.max_age {
+            if Instant::now().saturating_duration_since(self.established) >= max {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Drives a replacement connection when the current connection has expired
+    /// or has been closed by the server.
+    ///
+    /// Returns pending only when the current connection is closed and a
+    /// replacement is still being established. Returns a new sender once the
+    /// replacement connection is ready.
+    fn poll_rotate(&mut self, closed: bool, cx: &mut Context<'_>) -> Poll<Option<SendRequest<B>>> {
+        if self.pending.is_none() {
+            if !closed && !self.is_expired() {
+                return Poll::Ready(None);
+            }
+            debug!(closed, requests = self.requests, "Rotating connection");
+            self.pending = Some((self.reconnect)());
+        }
+
+        let res = match self.pending.as_mut().expect("must be set").poll_unpin(cx) {
+            Poll::Ready(res) => res,
+            // Continue to use the current connection until the replacement is
+            // ready, unless it's already closed.
+            Poll::Pending if closed => return Poll::Pending,
+            Poll::Pending => return Poll::Ready(None),
+        };
+
+        self.pending = None;
+        self.established = Instant::now();
+        self.requests = 0;
+        match res {
+            Ok(tx) => {
+                debug!("Rotated connection");
+                Poll::Ready(Some(tx))
+            }
+            Err(error) => {
+                // The current connection continues to be used (if it's still
+                // open) and the next rotation is deferred until the limits are
+                // exceeded again.
+                warn!(%error, "Failed to rotate connection");
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for Rotation<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rotation")
+            .field("pending", &self.pending.is_some())
+            .field("max_age", &self.max_age)
+            .field("max_requests", &self.max_requests)
+            .field("established", &self.established)
+            .field("requests", &self.requests)
+            .finish()
+    }
+}
@@ -8,6 +8,7 @@ pub mod balance;
 pub mod classify;
 pub mod client;
 pub mod client_handle;
+pub mod deadline;
 pub mod detect;
 mod glue;
 pub mod h1;
@@ -23,6 +24,7 @@ pub mod strip_header;
 pub mod timeout;
 pub mod trace;
 pub mod upgrade;
+pub mod uri_limit;
 pub mod version;
 
 pub use self::{
@@ -32,6 +34,7 @@ pub use self::{
         NewInsertClassifyResponse,
     },
     client_handle::{ClientHandle, SetClientHandle},
+    deadline::{DeadlineExceededError, EnforceDeadline, PropagateDeadline},
     detect::DetectHttp,
     glue::{HyperServerSvc, UpgradeBody},
     header_from_target::NewHeaderFromTarget,
@@ -41,6 +44,7 @@ pub use self::{
     server::{NewServeHttp, ServeHttp},
     strip_header::StripHeader,
     timeout::{NewTimeout, ResponseTimeout, ResponseTimeoutError},
+    uri_limit::{LimitUri, UriTooLongError},
     version::Version,
 };
 pub use http::{
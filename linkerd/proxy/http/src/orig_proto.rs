@@ -17,7 +17,10 @@ pub const L5D_ORIG_PROTO: &str = "l5d-orig-proto";
 
 /// Upgrades HTTP requests from their original protocol to HTTP2.
 #[derive(Debug)]
-pub struct Upgrade<C, T, B> {
+pub struct Upgrade<C, T, B>
+where
+    C: MakeConnection<(crate::Version, T)>,
+{
     http1: h1::Client<C, T, B>,
     h2: h2::Connection<B>,
 }
@@ -45,7 +48,10 @@ pub struct WasUpgrade(());
 
 // === impl Upgrade ===
 
-impl<C, T, B> Upgrade<C, T, B> {
+impl<C, T, B> Upgrade<C, T, B>
+where
+    C: MakeConnection<(crate::Version, T)>,
+{
     pub(crate) fn new(http1: h1::Client<C, T, B>, h2: h2::Connection<B>) -> Self {
         Self { http1, h2 }
     }
@@ -55,7 +61,8 @@ impl<C, T, B> Service<http::Request<B>> for Upgrade<C, T, B>
 where
     T: Clone + Send + Sync + 'static,
     C: MakeConnection<(crate::Version, T)> + Clone + Send + Sync + 'static,
-    C::Connection: Unpin + Send,
+    C::Connection: Unpin + Send + 'static,
+    C::Metadata: Send + 'static,
     C::Future: Unpin + Send + 'static,
     B: hyper::body::HttpBody + Send + 'static,
     B::Data: Send,
@@ -97,8 +104,7 @@ where
         req.headers_mut()
             .insert(L5D_ORIG_PROTO, HeaderValue::from_static(header));
 
-        // transfer-encoding is illegal in HTTP2
-        req.headers_mut().remove(TRANSFER_ENCODING);
+        strip_h1_headers(req.headers_mut());
 
         *req.version_mut() = http::Version::HTTP_2;
 
@@ -128,6 +134,50 @@ where
     }
 }
 
+/// Strips HTTP/1-specific headers that may not be sent on an HTTP/2 stream.
+fn strip_h1_headers(headers: &mut http::HeaderMap) {
+    // Connection-specific headers, and any headers nominated by the
+    // `connection` header, are illegal in HTTP2. HTTP2 only permits the `te`
+    // header to indicate support for trailers.
+    h1::strip_connection_headers_except_trailers(headers);
+
+    // transfer-encoding is illegal in HTTP2
+    headers.remove(TRANSFER_ENCODING);
+}
+
+#[cfg(test)]
+#[test]
+fn test_strip_h1_headers() {
+    use http::header::{CONNECTION, TE, UPGRADE};
+
+    let mut headers = http::HeaderMap::new();
+    headers.insert(CONNECTION, HeaderValue::from_static("keep-alive, x-hop"));
+    headers.insert("keep-alive", HeaderValue::from_static("timeout=5"));
+    headers.insert("x-hop", HeaderValue::from_static("1"));
+    headers.insert("proxy-connection", HeaderValue::from_static("keep-alive"));
+    headers.insert(UPGRADE, HeaderValue::from_static("websocket"));
+    headers.insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+    headers.insert(TE, HeaderValue::from_static("gzip, trailers"));
+    headers.insert("x-end-to-end", HeaderValue::from_static("1"));
+    strip_h1_headers(&mut headers);
+    assert_eq!(headers.len(), 2, "{headers:?}");
+    assert_eq!(headers.get(TE).unwrap(), "trailers");
+    assert!(headers.contains_key("x-end-to-end"));
+
+    // HTTP/1.1 clients nominate `te` as a connection header.
+    let mut headers = http::HeaderMap::new();
+    headers.insert(CONNECTION, HeaderValue::from_static("te"));
+    headers.insert(TE, HeaderValue::from_static("trailers"));
+    strip_h1_headers(&mut headers);
+    assert_eq!(headers.len(), 1, "{headers:?}");
+    assert_eq!(headers.get(TE).unwrap(), "trailers");
+
+    let mut headers = http::HeaderMap::new();
+    headers.insert(TE, HeaderValue::from_static("gzip"));
+    strip_h1_headers(&mut headers);
+    assert!(headers.is_empty(), "{headers:?}");
+}
+
 /// Handles HTTP/2 client errors for HTTP/1.1 requests by wrapping the error type. This
 /// simplifies error handling elsewhere so that HTTP/2 errors can only be encountered when the
 /// original request was HTTP/2.
@@ -8,10 +8,16 @@ use crate::{
 use linkerd_error::Error;
 use linkerd_io::{self as io, PeerAddr};
 use linkerd_stack::{layer, NewService, Param};
+use pin_project::pin_project;
 use std::{
     future::Future,
     pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::Duration,
 };
 use tower::Service;
 use tracing::{debug, Instrument};
@@ -22,6 +28,8 @@ type Server = hyper::server::conn::Http<trace::Executor>;
 pub struct NewServeHttp<N> {
     inner: N,
     server: Server,
+    max_connection_age: Option<Duration>,
+    preserve_trailers: bool,
     drain: drain::Watch,
 }
 
@@ -30,21 +38,68 @@ pub struct ServeHttp<N> {
     version: Version,
     server: Server,
     inner: N,
+    max_connection_age: Option<Duration>,
+    preserve_trailers: bool,
     drain: drain::Watch,
 }
 
+/// Marks HTTP/1 responses with `connection: close` once the connection is
+/// closing, so that clients do not attempt to reuse it.
+#[derive(Clone, Debug)]
+struct CloseConnection<S> {
+    inner: S,
+    closing: Arc<AtomicBool>,
+}
+
+#[pin_project]
+struct CloseConnectionFuture<F> {
+    #[pin]
+    inner: F,
+    closing: Arc<AtomicBool>,
+}
+
 // === impl NewServeHttp ===
 
 impl<N> NewServeHttp<N> {
+    /// Builds a server layer.
+    ///
+    /// When a maximum connection age is configured, connections are shut down
+    /// gracefully once they reach it, so that clients reconnect periodically:
+    /// HTTP/2 clients receive a GOAWAY and HTTP/1 responses are sent with
+    /// `connection: close`.
+    ///
+    /// When trailers are preserved, HTTP/1 requests retain the client's
+    /// acceptance of trailers (`te: trailers`), so that trailers may be bridged
+    /// from HTTP/2 servers.
+    ///
+    /// Informational (1xx) responses, other than `100 Continue` and `101
+    /// Switching Protocols`, are never forwarded: hyper 0.14 discards them on
+    /// clients and cannot send them from servers.
     pub fn layer(
         h2: H2Settings,
+        max_connection_age: Option<Duration>,
+        preserve_trailers: bool,
         drain: drain::Watch,
     ) -> impl layer::Layer<N, Service = Self> + Clone {
-        layer::mk(move |inner| Self::new(h2, inner, drain.clone()))
+        layer::mk(move |inner| {
+            Self::new(
+                h2,
+                max_connection_age,
+                preserve_trailers,
+                inner,
+                drain.clone(),
+            )
+        })
     }
 
     /// Creates a new `ServeHttp`.
-    fn new(h2: H2Settings, inner: N, drain: drain::Watch) -> Self {
+    fn new(
+        h2: H2Settings,
+        max_connection_age: Option<Duration>,
+        preserve_trailers: bool,
+        inner: N,
+        drain: drain::Watch,
+    ) -> Self {
         let mut server = hyper::server::conn::Http::new().with_executor(trace::Executor::new());
         server
             .http2_initial_stream_window_size(h2.initial_stream_window_size)
@@ -63,6 +118,8 @@ impl<N> NewServeHttp<N> {
         Self {
             inner,
             server,
+            max_connection_age,
+            preserve_trailers,
             drain,
         }
     }
@@ -83,6 +140,8 @@ where
             inner,
             version,
             server: self.server.clone(),
+            max_connection_age: self.max_connection_age,
+            preserve_trailers: self.preserve_trailers,
             drain: self.drain.clone(),
         }
     }
@@ -112,6 +171,8 @@ where
         let Self {
             version,
             inner,
+            max_connection_age,
+            preserve_trailers,
             drain,
             mut server,
         } = self.clone();
@@ -124,13 +185,23 @@ where
                 // TODO(ver): Move this into the inner stack.
                 let svc =
                     SetClientHandle::new(client_handle.clone(), inner.new_service(client_handle));
+                let age_limit = max_age(max_connection_age);
 
                 match version {
                     Version::Http1 => {
+                        let closing = Arc::new(AtomicBool::new(false));
+                        let svc = CloseConnection {
+                            inner: svc,
+                            closing: closing.clone(),
+                        };
                         // Enable support for HTTP upgrades (CONNECT and websockets).
                         let mut conn = server
                             .http1_only(true)
-                            .serve_connection(io, upgrade::Service::new(svc, drain.clone()))
+                            .serve_connection(
+                                io,
+                                upgrade::Service::new(svc, drain.clone())
+                                    .preserve_trailers(preserve_trailers),
+                            )
                             .with_upgrades();
                         tokio::select! {
                             res = &mut conn => {
@@ -147,6 +218,12 @@ where
                                 Pin::new(&mut conn).graceful_shutdown();
                                 conn.await?;
                             }
+                            () = age_limit => {
+                                debug!("The connection has reached its maximum age");
+                                closing.store(true, Ordering::Release);
+                                Pin::new(&mut conn).graceful_shutdown();
+                                conn.await?;
+                            }
                         }
                     }
 
@@ -169,6 +246,11 @@ where
                                 Pin::new(&mut conn).graceful_shutdown();
                                 conn.await?;
                             }
+                            () = age_limit => {
+                                debug!("The connection has reached its maximum age");
+                                Pin::new(&mut conn).graceful_shutdown();
+                                conn.await?;
+                            }
                         }
                     }
                 }
@@ -178,3 +260,155 @@ where
         )
     }
 }
+
+/// Completes when a connection reaches its maximum age, or never if no maximum
+/// age is configured.
+async fn max_age(max_age: Option<Duration>) {
+    match max_age {
+        Some(age) => tokio::time::sleep(age).await,
+        None => futures::future::pending().await,
+    }
+}
+
+// === impl CloseConnection ===
+
+impl<B, S> Service<http::Request<B>> for CloseConnection<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<http::BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = CloseConnectionFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        CloseConnectionFuture {
+            inner: self.inner.call(req),
+            closing: self.closing.clone(),
+        }
+    }
+}
+
+impl<F, E> Future for CloseConnectionFuture<F>
+where
+    F: Future<Output = Result<http::Response<http::BoxBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut rsp = futures::ready!(this.inner.poll(cx))?;
+        if this.closing.load(Ordering::Acquire) {
+            rsp.headers_mut().insert(
+                http::header::CONNECTION,
+                http::HeaderValue::from_static("close"),
+            );
+        }
+        Poll::Ready(Ok(rsp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_stack::{service_fn, NewCloneService};
+    use tokio::{task::JoinHandle, time};
+
+    /// Serves a connection with a maximum age of 5 seconds. Responses are sent
+    /// after 10 seconds, so that the connection reaches its maximum age while
+    /// a request is in flight.
+    fn serve(version: Version, io: io::DuplexStream) -> (drain::Signal, JoinHandle<Result<()>>) {
+        let inner = NewCloneService::from(service_fn(|_: http::Request<UpgradeBody>| async {
+            time::sleep(Duration::from_secs(10)).await;
+            Ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+        }));
+        let (drain_tx, drain) = drain::channel();
+        let NewServeHttp {
+            server,
+            inner,
+            max_connection_age,
+            preserve_trailers,
+            drain,
+        } = NewServeHttp::new(
+            H2Settings::default(),
+            Some(Duration::from_secs(5)),
+            false,
+            inner,
+            drain,
+        );
+        let mut serve = ServeHttp {
+            version,
+            server,
+            inner,
+            max_connection_age,
+            preserve_trailers,
+            drain,
+        };
+        (drain_tx, tokio::spawn(serve.call(io)))
+    }
+
+    type Result<T, E = Error> = std::result::Result<T, E>;
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn http1_closes_connections_at_max_age() {
+        let (client_io, server_io) = io::duplex(1024);
+        let (_drain, server) = serve(Version::Http1, server_io);
+        let (mut client, conn) = hyper::client::conn::Builder::new()
+            .handshake::<_, hyper::Body>(client_io)
+            .await
+            .unwrap();
+        let conn = tokio::spawn(conn);
+
+        // The in-flight request completes, but the connection is not reused.
+        let start = time::Instant::now();
+        let rsp = client
+            .send_request(http::Request::new(hyper::Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(rsp.status(), http::StatusCode::OK);
+        assert_eq!(
+            rsp.headers().get(http::header::CONNECTION).unwrap(),
+            "close"
+        );
+        assert_eq!(
+            time::Instant::now().saturating_duration_since(start),
+            Duration::from_secs(10)
+        );
+
+        server.await.unwrap().expect("server must close gracefully");
+        conn.await.unwrap().expect("client must close gracefully");
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn http2_closes_connections_at_max_age() {
+        let (client_io, server_io) = io::duplex(64 * 1024);
+        let (_drain, server) = serve(Version::H2, server_io);
+        let (mut client, conn) = hyper::client::conn::Builder::new()
+            .http2_only(true)
+            .handshake::<_, hyper::Body>(client_io)
+            .await
+            .unwrap();
+        let conn = tokio::spawn(conn);
+
+        // The connection is sent a GOAWAY when it reaches its maximum age, but
+        // the in-flight request completes.
+        let rsp = client.send_request(http::Request::new(hyper::Body::empty()));
+        time::sleep(Duration::from_secs(6)).await;
+        assert!(!server.is_finished(), "in-flight requests must complete");
+        assert!(
+            client
+                .send_request(http::Request::new(hyper::Body::empty()))
+                .await
+                .is_err(),
+            "new requests must be refused"
+        );
+        assert_eq!(rsp.await.unwrap().status(), http::StatusCode::OK);
+
+        server.await.unwrap().expect("server must close gracefully");
+        conn.await.unwrap().expect("client must close gracefully");
+    }
+}
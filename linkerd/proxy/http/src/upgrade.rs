@@ -1,5 +1,6 @@
 //! HTTP/1.1 Upgrades
 
+pub use self::websocket::{Direction, SessionMetrics, WebSocketSession};
 use crate::{glue::UpgradeBody, h1};
 use futures::{
     future::{self, Either},
@@ -7,6 +8,7 @@ use futures::{
 };
 use hyper::upgrade::OnUpgrade;
 use linkerd_duplex::Duplex;
+use parking_lot::Mutex;
 use std::fmt;
 use std::mem;
 use std::sync::Arc;
@@ -15,6 +17,8 @@ use tracing::instrument::Instrument;
 use tracing::{debug, info, trace};
 use try_lock::TryLock;
 
+mod websocket;
+
 /// A type inserted into `http::Extensions` to bridge together HTTP Upgrades.
 ///
 /// If the HTTP1 server service detects an upgrade request, this will be
@@ -46,6 +50,7 @@ pub struct HttpConnect;
 struct Inner {
     server: TryLock<Option<OnUpgrade>>,
     client: TryLock<Option<OnUpgrade>>,
+    websocket: Mutex<Option<WebSocketSession>>,
     upgrade_drain_signal: Option<drain::Watch>,
 }
 
@@ -60,6 +65,8 @@ pub struct Service<S> {
     service: S,
     /// Watch any spawned HTTP/1.1 upgrade tasks.
     upgrade_drain_signal: drain::Watch,
+    /// Whether requests retain the client's acceptance of trailers.
+    preserve_trailers: bool,
 }
 
 // === impl Http11Upgrade ===
@@ -73,6 +80,7 @@ impl Http11Upgrade {
         let inner = Arc::new(Inner {
             server: TryLock::new(None),
             client: TryLock::new(None),
+            websocket: Mutex::new(None),
             upgrade_drain_signal: Some(upgrade_drain_signal),
         });
 
@@ -110,6 +118,12 @@ impl Http11Upgrade {
             }
         }
     }
+
+    /// Serves the upgraded connection as a WebSocket session, so that it is
+    /// recorded and limited as configured.
+    pub fn set_websocket_session(&self, session: WebSocketSession) {
+        *self.inner.websocket.lock() = Some(session);
+    }
 }
 
 impl fmt::Debug for Http11Upgrade {
@@ -128,6 +142,7 @@ impl Drop for Inner {
         // We can safely take the futures out of their locks.
         let server = mem::replace(&mut self.server, TryLock::new(None)).into_inner();
         let client = mem::replace(&mut self.client, TryLock::new(None)).into_inner();
+        let websocket = self.websocket.get_mut().take();
         if let (Some(server), Some(client)) = (server, client) {
             trace!("HTTP/1.1 upgrade has both halves");
 
@@ -138,7 +153,11 @@ impl Drop for Inner {
             let both_upgrades = async move {
                 let (server_conn, client_conn) = tokio::try_join!(server_upgrade, client_upgrade)?;
                 trace!("HTTP upgrade successful");
-                if let Err(e) = Duplex::new(client_conn, server_conn).await {
+                let duplex = match websocket {
+                    Some(session) => Either::Left(session.serve(client_conn, server_conn)),
+                    None => Either::Right(Duplex::new(client_conn, server_conn)),
+                };
+                if let Err(e) = duplex.await {
                     info!("tcp duplex error: {}", e)
                 }
                 Ok::<(), ()>(())
@@ -166,6 +185,16 @@ impl<S> Service<S> {
         Self {
             service,
             upgrade_drain_signal,
+            preserve_trailers: false,
+        }
+    }
+
+    /// Retains the `te: trailers` header on requests, so that upstream
+    /// servers may send trailers to clients that accept them.
+    pub fn preserve_trailers(self, preserve_trailers: bool) -> Self {
+        Self {
+            preserve_trailers,
+            ..self
         }
     }
 }
@@ -209,6 +238,9 @@ where
             let on_upgrade = hyper::upgrade::on(&mut req);
 
             Some((halves.server, on_upgrade))
+        } else if self.preserve_trailers {
+            h1::strip_connection_headers_except_trailers(req.headers_mut());
+            None
         } else {
             h1::strip_connection_headers(req.headers_mut());
             None
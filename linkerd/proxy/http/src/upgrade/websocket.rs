@@ -0,0 +1,389 @@
+//! Serves upgraded WebSocket connections.
+//!
+//! WebSocket sessions may be limited in duration and closed when idle. Their
+//! traffic is recorded by a [`SessionMetrics`] implementation, which observes
+//! the bytes and the WebSocket messages sent in each direction.
+
+use futures::ready;
+use linkerd_duplex::Duplex;
+use linkerd_io::{self as io, AsyncRead, AsyncWrite, ReadBuf};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{self, Instant};
+
+/// Configures how an upgraded WebSocket connection is served.
+#[derive(Clone, Debug, Default)]
+pub struct WebSocketSession {
+    /// Closes the session once it has been open for this long.
+    pub max_duration: Option<Duration>,
+
+    /// Closes the session once no data has been sent for this long.
+    pub idle_timeout: Option<Duration>,
+
+    pub metrics: Option<Arc<dyn SessionMetrics>>,
+}
+
+/// Records the lifecycle and traffic of WebSocket sessions.
+pub trait SessionMetrics: fmt::Debug + Send + Sync + 'static {
+    fn opened(&self);
+
+    fn closed(&self);
+
+    /// Records data sent in the given direction, including the number of
+    /// data messages that were completed by frames starting in it.
+    fn transferred(&self, direction: Direction, bytes: usize, messages: usize);
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Data sent by the client that requested the upgrade.
+    FromClient,
+    /// Data sent by the server that accepted the upgrade.
+    FromServer,
+}
+
+/// Wraps one side of an upgraded connection to record the data read from
+/// it.
+#[pin_project]
+struct SessionIo<T> {
+    #[pin]
+    io: T,
+    direction: Direction,
+    frames: Frames,
+    metrics: Option<Arc<dyn SessionMetrics>>,
+    last_activity: Arc<Mutex<Instant>>,
+}
+
+/// Counts the data messages in a stream of WebSocket frames.
+#[derive(Debug, Default)]
+struct Frames {
+    header: [u8; MAX_HEADER_LEN],
+    header_len: usize,
+    payload_remaining: u64,
+}
+
+/// Calls [`SessionMetrics::closed`] when the session ends.
+struct Closed(Option<Arc<dyn SessionMetrics>>);
+
+/// A frame header holds up to 2 bytes of flags and length, an 8 byte
+/// extended length, and a 4 byte masking key.
+const MAX_HEADER_LEN: usize = 14;
+
+// === impl WebSocketSession ===
+
+impl WebSocketSession {
+    /// Copies data between the client and server connections until either
+    /// closes or the session's limits are exceeded.
+    pub(super) async fn serve<C, S>(self, client_conn: C, server_conn: S) -> io::Result<()>
+    where
+        C: AsyncRead + AsyncWrite + Unpin,
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.opened();
+        }
+        let _closed = Closed(self.metrics.clone());
+
+        let last_activity = Arc::new(Mutex::new(Instant::now()));
+        // The proxy's server connection is with the client that requested the
+        // upgrade, and vice versa.
+        let duplex = Duplex::new(
+            SessionIo::new(
+                client_conn,
+                Direction::FromServer,
+                self.metrics.clone(),
+                last_activity.clone(),
+            ),
+            SessionIo::new(
+                server_conn,
+                Direction::FromClient,
+                self.metrics.clone(),
+                last_activity.clone(),
+            ),
+        );
+
+        tokio::select! {
+            res = duplex => res,
+            reason = expire(self.max_duration, self.idle_timeout, last_activity) => {
+                tracing::debug!(reason, "Closing WebSocket session");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Completes once the session has exceeded its maximum duration or has been
+/// idle for longer than its idle timeout.
+async fn expire(
+    max_duration: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    last_activity: Arc<Mutex<Instant>>,
+) -> &'static str {
+    let deadline = max_duration.map(|d| Instant::now() + d);
+    loop {
+        let idle_deadline = idle_timeout.map(|t| *last_activity.lock() + t);
+        let next = match (deadline, idle_deadline) {
+            (Some(d), Some(i)) => d.min(i),
+            (Some(d), None) => d,
+            (None, Some(i)) => i,
+            (None, None) => return futures::future::pending().await,
+        };
+        time::sleep_until(next).await;
+
+        let now = Instant::now();
+        if deadline.map_or(false, |d| now >= d) {
+            return "maximum session duration exceeded";
+        }
+        // Data may have been sent while sleeping, in which case the idle
+        // deadline is extended.
+        if idle_timeout.map_or(false, |t| now >= *last_activity.lock() + t) {
+            return "session idle timeout exceeded";
+        }
+    }
+}
+
+// === impl SessionIo ===
+
+impl<T> SessionIo<T> {
+    fn new(
+        io: T,
+        direction: Direction,
+        metrics: Option<Arc<dyn SessionMetrics>>,
+        last_activity: Arc<Mutex<Instant>>,
+    ) -> Self {
+        Self {
+            io,
+            direction,
+            frames: Frames::default(),
+            metrics,
+            last_activity,
+        }
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for SessionIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let prev_filled = buf.filled().len();
+        ready!(this.io.poll_read(cx, buf))?;
+
+        let data = &buf.filled()[prev_filled..];
+        if !data.is_empty() {
+            *this.last_activity.lock() = Instant::now();
+            if let Some(metrics) = this.metrics.as_ref() {
+                let messages = this.frames.observe(data);
+                metrics.transferred(*this.direction, data.len(), messages);
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for SessionIo<T> {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().io.poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().io.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().io.poll_shutdown(cx)
+    }
+}
+
+// === impl Frames ===
+
+impl Frames {
+    /// Consumes data from the stream, returning the number of data messages
+    /// whose final frame header was read.
+    fn observe(&mut self, mut data: &[u8]) -> usize {
+        let mut messages = 0;
+        while !data.is_empty() {
+            if self.payload_remaining > 0 {
+                let n = (data.len() as u64).min(self.payload_remaining);
+                self.payload_remaining -= n;
+                data = &data[n as usize..];
+                continue;
+            }
+
+            self.header[self.header_len] = data[0];
+            self.header_len += 1;
+            data = &data[1..];
+            if let Some((completes_message, len)) = self.parse_header() {
+                if completes_message {
+                    messages += 1;
+                }
+                self.header_len = 0;
+                self.payload_remaining = len;
+            }
+        }
+        messages
+    }
+
+    /// Parses the buffered frame header, if it is complete, returning whether
+    /// the frame completes a data message and the length of its payload.
+    fn parse_header(&self) -> Option<(bool, u64)> {
+        let header = &self.header[..self.header_len];
+        if header.len() < 2 {
+            return None;
+        }
+
+        let (extended_len, len) = match header[1] & 0x7f {
+            126 => (2, None),
+            127 => (8, None),
+            len => (0, Some(len as u64)),
+        };
+        let mask_len = if header[1] & 0x80 == 0 { 0 } else { 4 };
+        if header.len() < 2 + extended_len + mask_len {
+            return None;
+        }
+        let len = len.unwrap_or_else(|| {
+            header[2..2 + extended_len]
+                .iter()
+                .fold(0, |len, b| (len << 8) | *b as u64)
+        });
+
+        // Control frames (close, ping, and pong) have opcodes of 0x8 and
+        // above and are not counted as messages.
+        let fin = header[0] & 0x80 != 0;
+        let opcode = header[0] & 0x0f;
+        Some((fin && opcode < 0x8, len))
+    }
+}
+
+// === impl Closed ===
+
+impl Drop for Closed {
+    fn drop(&mut self) {
+        if let Some(metrics) = self.0.take() {
+            metrics.closed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_messages() {
+        let mut frames = Frames::default();
+
+        // A complete, unmasked text message.
+        assert_eq!(frames.observe(b"\x81\x05hello"), 1);
+
+        // A masked binary message, split across reads.
+        assert_eq!(frames.observe(b"\x82\x83\x01\x02"), 0);
+        assert_eq!(frames.observe(b"\x03\x04abc"), 1);
+
+        // A fragmented message is counted once its final frame is read.
+        assert_eq!(frames.observe(b"\x01\x02he\x00\x01l"), 0);
+        assert_eq!(frames.observe(b"\x80\x02lo"), 1);
+
+        // Control frames are not counted.
+        assert_eq!(frames.observe(b"\x89\x00\x8a\x00\x88\x00"), 0);
+
+        // Messages with extended payload lengths.
+        let mut data = b"\x82\x7e\x01\x00".to_vec();
+        data.extend_from_slice(&[0; 256]);
+        data.extend_from_slice(b"\x81\x7f\0\0\0\0\0\x01\0\0");
+        assert_eq!(frames.observe(&data), 2);
+        assert_eq!(frames.payload_remaining, 65536);
+    }
+
+    #[derive(Debug, Default)]
+    struct TestMetrics {
+        open: Mutex<i32>,
+        from_client: Mutex<(usize, usize)>,
+    }
+
+    impl SessionMetrics for TestMetrics {
+        fn opened(&self) {
+            *self.open.lock() += 1;
+        }
+
+        fn closed(&self) {
+            *self.open.lock() -= 1;
+        }
+
+        fn transferred(&self, direction: Direction, bytes: usize, messages: usize) {
+            if direction == Direction::FromClient {
+                let mut from_client = self.from_client.lock();
+                from_client.0 += bytes;
+                from_client.1 += messages;
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn closes_idle_sessions() {
+        use io::AsyncWriteExt;
+
+        let metrics = Arc::new(TestMetrics::default());
+        let (mut client, server_conn) = io::duplex(1024);
+        let (client_conn, _server) = io::duplex(1024);
+        let session = WebSocketSession {
+            max_duration: None,
+            idle_timeout: Some(Duration::from_secs(10)),
+            metrics: Some(metrics.clone()),
+        };
+        let task = tokio::spawn(session.serve(client_conn, server_conn));
+
+        time::sleep(Duration::from_secs(5)).await;
+        client.write_all(b"\x81\x02hi").await.unwrap();
+        time::sleep(Duration::from_secs(8)).await;
+        assert!(!task.is_finished(), "session must not be idle");
+        assert_eq!(*metrics.open.lock(), 1);
+        assert_eq!(*metrics.from_client.lock(), (4, 1));
+
+        time::sleep(Duration::from_secs(3)).await;
+        task.await.unwrap().unwrap();
+        assert_eq!(*metrics.open.lock(), 0);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn limits_session_duration() {
+        use io::AsyncWriteExt;
+
+        let (mut client, server_conn) = io::duplex(1024);
+        let (client_conn, _server) = io::duplex(1024);
+        let session = WebSocketSession {
+            max_duration: Some(Duration::from_secs(10)),
+            idle_timeout: Some(Duration::from_secs(5)),
+            metrics: None,
+        };
+        let start = Instant::now();
+        let task = tokio::spawn(session.serve(client_conn, server_conn));
+
+        // The session is kept active until it exceeds its maximum duration.
+        for _ in 0..2 {
+            time::sleep(Duration::from_secs(4)).await;
+            client.write_all(b"\x81\x00").await.unwrap();
+        }
+        task.await.unwrap().unwrap();
+        assert_eq!(
+            Instant::now().saturating_duration_since(start),
+            Duration::from_secs(10)
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! Rejects requests with overly long request targets.
+//!
+//! Route matching may evaluate regular expressions against a request's path
+//! and query, so absurdly long URIs are rejected before they are routed.
+
+use futures::{future, TryFutureExt};
+use linkerd_error::Error;
+use linkerd_stack::layer;
+use std::task::{Context, Poll};
+use thiserror::Error;
+
+/// Fails requests whose request target is longer than a configured limit.
+#[derive(Clone, Debug)]
+pub struct LimitUri<S> {
+    inner: S,
+    max: Option<usize>,
+}
+
+#[derive(Clone, Debug, Error)]
+#[error("request target of {len} bytes exceeds the limit of {max} bytes")]
+pub struct UriTooLongError {
+    len: usize,
+    max: usize,
+}
+
+// === impl LimitUri ===
+
+impl<S> LimitUri<S> {
+    /// Limits the length of each request's path and query to `max` bytes.
+    /// When no limit is configured, requests are passed through unmodified.
+    pub fn layer(max: Option<usize>) -> impl tower::layer::Layer<S, Service = Self> + Clone {
+        layer::mk(move |inner| Self { inner, max })
+    }
+}
+
+impl<B, S> tower::Service<http::Request<B>> for LimitUri<S>
+where
+    S: tower::Service<http::Request<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::Ready<Result<S::Response, Error>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(max) = self.max {
+            let len = req.uri().path_and_query().map_or(0, |pq| pq.as_str().len());
+            if len > max {
+                tracing::debug!(len, max, "Request target too long");
+                return future::Either::Right(future::err(UriTooLongError { len, max }.into()));
+            }
+        }
+        future::Either::Left(self.inner.call(req).err_into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::{Layer, Service, ServiceExt};
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn rejects_long_uris() {
+        let (inner, mut handle) = tower_test::mock::pair::<http::Request<()>, ()>();
+        let mut svc = LimitUri::layer(Some(16)).layer(inner);
+        handle.allow(2);
+
+        let req = http::Request::builder()
+            .uri("http://example.com/0123456789abcdef")
+            .body(())
+            .unwrap();
+        let err = svc
+            .ready()
+            .await
+            .unwrap()
+            .call(req)
+            .await
+            .expect_err("request must be rejected");
+        assert!(err.is::<UriTooLongError>());
+
+        let req = http::Request::builder()
+            .uri("http://example.com/0123456789?a=b")
+            .body(())
+            .unwrap();
+        let rsp = svc.ready().await.unwrap().call(req);
+        let (_, send) = handle.next_request().await.unwrap();
+        send.send_response(());
+        rsp.await.expect("request must be served");
+    }
+}
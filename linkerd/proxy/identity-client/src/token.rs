@@ -1,19 +1,41 @@
-use std::{io, path::PathBuf};
+use std::{io, path::PathBuf, sync::Arc};
 
-#[derive(Clone, Debug)]
-pub struct TokenSource(PathBuf);
+/// A file holding a bound service account token.
+///
+/// Bound tokens are rotated in place before they expire, so the file is read
+/// each time a token is needed rather than once at startup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenSource {
+    path: PathBuf,
+    audience: Option<Arc<str>>,
+}
 
 // === impl TokenSource ===
 
 impl TokenSource {
     pub fn if_nonempty_file(p: impl Into<PathBuf>) -> io::Result<Self> {
-        let ts = TokenSource(p.into());
+        let ts = TokenSource {
+            path: p.into(),
+            audience: None,
+        };
         ts.load()?;
         Ok(ts)
     }
 
+    /// Names the audience to which this source's tokens are bound.
+    pub fn with_audience(self, audience: impl Into<Arc<str>>) -> Self {
+        Self {
+            audience: Some(audience.into()),
+            ..self
+        }
+    }
+
+    pub fn audience(&self) -> Option<&str> {
+        self.audience.as_deref()
+    }
+
     pub fn load(&self) -> io::Result<Vec<u8>> {
-        let t = std::fs::read(&self.0)?;
+        let t = std::fs::read(&self.path)?;
 
         if t.is_empty() {
             return Err(io::Error::new(io::ErrorKind::Other, "token is empty"));
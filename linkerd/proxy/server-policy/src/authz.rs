@@ -1,15 +1,41 @@
 use super::Meta;
-use std::{collections::BTreeSet, sync::Arc};
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 mod network;
 
 pub use self::network::Network;
+pub use linkerd_tls::CertFingerprint;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Authorization {
     pub networks: Vec<Network>,
     pub authentication: Authentication,
     pub meta: Arc<Meta>,
+
+    /// Restricts the authorization to connections received on one of these
+    /// local addresses. When empty, connections to any local address match.
+    pub local_addrs: Vec<LocalAddr>,
+
+    /// Restricts the authorization to these time windows, so that temporary
+    /// access expires in the proxy even if the control plane is slow to revoke
+    /// it. When empty, the authorization is always valid.
+    pub validity: Vec<TimeWindow>,
+}
+
+/// Matches the local address on which a connection was received, e.g. to
+/// permit connections only on a node-local interface's address or on a
+/// specific port.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LocalAddr {
+    /// When set, the local IP must be in this network.
+    pub net: Option<ipnet::IpNet>,
+
+    /// When set, the local port must be this port.
+    pub port: Option<u16>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -19,7 +45,50 @@ pub enum Authentication {
     TlsAuthenticated {
         identities: BTreeSet<String>,
         suffixes: Vec<Suffix>,
+        patterns: Vec<IdentityPattern>,
     },
+    /// Authenticates mTLS clients by the fingerprints of their certificates,
+    /// so that only specific certificates are authorized regardless of the
+    /// identities they name.
+    TlsFingerprints(BTreeSet<CertFingerprint>),
+    /// Authenticates HTTP requests that carry a valid signature, so that
+    /// clients without mesh identities (e.g. webhook senders) can be
+    /// authorized. Connections are never authorized by signatures.
+    Hmac(Hmac),
+}
+
+/// Describes how HTTP requests are signed with HMAC-SHA256.
+///
+/// A request's signature covers its timestamp followed by each of the signed
+/// components, separated by newlines. A missing component is signed as an
+/// empty line.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Hmac {
+    /// Names the secret, shared with clients, with which requests are signed.
+    pub secret: String,
+
+    /// The header that carries the base64-encoded signature.
+    pub signature_header: http::HeaderName,
+
+    /// The header that carries the time at which the request was signed, in
+    /// seconds since the Unix epoch.
+    pub timestamp_header: http::HeaderName,
+
+    /// How far a request's timestamp may be from the proxy's clock. This
+    /// bounds the time during which a captured request may be replayed.
+    pub max_skew: Duration,
+
+    /// The parts of the request covered by the signature, in signing order.
+    pub components: Vec<SignedComponent>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SignedComponent {
+    Method,
+    Authority,
+    /// The request's path and query.
+    Path,
+    Header(http::HeaderName),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -27,6 +96,157 @@ pub struct Suffix {
     ends_with: String,
 }
 
+/// Matches client identities by wildcard or by the scope in which they were
+/// issued.
+///
+/// Linkerd identities take the form
+/// `<sa>.<ns>.serviceaccount.identity.<control-ns>.<trust-domain>`, and SPIFFE
+/// IDs take the form `spiffe://<trust-domain>/ns/<ns>/sa/<sa>`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IdentityPattern {
+    /// Matches DNS identities with one more leading label than the suffix,
+    /// as written `*.ns.serviceaccount.identity.linkerd.cluster.local`.
+    DnsWildcard { suffix: String },
+
+    /// Matches SPIFFE IDs in a trust domain under a path prefix, as written
+    /// `spiffe://example.org/*` or `spiffe://example.org/ns/prod/*`.
+    SpiffePrefix { prefix: String },
+
+    /// Matches Linkerd identities and SPIFFE IDs issued in a trust domain.
+    ///
+    /// Trust domains are configured locally rather than decoded from identity
+    /// names; see [`crate::overrides::AuthzOverrides`].
+    TrustDomain { domain: String },
+
+    /// Matches identities in the same trust domain and namespace as the
+    /// proxy's own identity.
+    ///
+    /// This is configured locally rather than decoded from identity names;
+    /// see [`crate::overrides::AuthzOverrides`].
+    SameNamespace,
+}
+
+/// A period of time during which an authorization is valid.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TimeWindow {
+    /// When set, the window starts at this time.
+    pub not_before: Option<SystemTime>,
+
+    /// When set, the window ends at this time.
+    pub not_after: Option<SystemTime>,
+}
+
+// === impl Authorization ===
+
+impl Authorization {
+    /// Returns true if a connection received on the local address `addr` may
+    /// be authorized.
+    pub fn matches_local_addr(&self, addr: std::net::SocketAddr) -> bool {
+        self.local_addrs.is_empty() || self.local_addrs.iter().any(|l| l.contains(addr))
+    }
+
+    /// Returns true if the authorization is valid at `now`.
+    pub fn is_valid_at(&self, now: SystemTime) -> bool {
+        self.validity.is_empty() || self.validity.iter().any(|w| w.contains(now))
+    }
+}
+
+// === impl TimeWindow ===
+
+impl TimeWindow {
+    #[inline]
+    pub fn contains(&self, t: SystemTime) -> bool {
+        self.not_before.map_or(true, |start| start <= t)
+            && self.not_after.map_or(true, |end| t < end)
+    }
+}
+
+// === impl LocalAddr ===
+
+impl LocalAddr {
+    #[inline]
+    pub fn contains(&self, addr: std::net::SocketAddr) -> bool {
+        self.net.map_or(true, |net| net.contains(&addr.ip()))
+            && self.port.map_or(true, |port| port == addr.port())
+    }
+}
+
+// === impl IdentityPattern ===
+
+impl IdentityPattern {
+    /// Parses an identity name that contains a wildcard, returning `None` for
+    /// exact names.
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(suffix) = name.strip_prefix('*') {
+            if suffix.starts_with('.') && suffix.len() > 1 && !suffix.contains('*') {
+                return Some(Self::DnsWildcard {
+                    suffix: suffix.to_string(),
+                });
+            }
+            return None;
+        }
+
+        let path = name.strip_prefix("spiffe://")?.strip_suffix('*')?;
+        let (trust_domain, _) = path.split_once('/')?;
+        if trust_domain.is_empty() || !path.ends_with('/') || path.contains('*') {
+            return None;
+        }
+        Some(Self::SpiffePrefix {
+            prefix: name[..name.len() - 1].to_string(),
+        })
+    }
+
+    /// Returns true if the client identity `id` matches the pattern, given the
+    /// proxy's own identity, if it is known.
+    pub fn matches(&self, id: &str, local_id: Option<&str>) -> bool {
+        match self {
+            Self::DnsWildcard { suffix } => {
+                id.strip_suffix(suffix.as_str()).map_or(false, |label| {
+                    !label.is_empty() && !label.contains(['.', '/'])
+                })
+            }
+            Self::SpiffePrefix { prefix } => id.len() > prefix.len() && id.starts_with(prefix),
+            Self::TrustDomain { domain } => {
+                identity_scope(id).map_or(false, |(td, _)| td == domain)
+            }
+            Self::SameNamespace => match (identity_scope(id), local_id.and_then(identity_scope)) {
+                (Some((td, Some(ns))), Some((local_td, Some(local_ns)))) => {
+                    td == local_td && ns == local_ns
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Returns the trust domain in which an identity was issued and, if the
+/// identity names a workload, the workload's namespace.
+fn identity_scope(id: &str) -> Option<(&str, Option<&str>)> {
+    if let Some(path) = id.strip_prefix("spiffe://") {
+        let (td, path) = path.split_once('/').unwrap_or((path, ""));
+        if td.is_empty() {
+            return None;
+        }
+        let ns = path
+            .strip_prefix("ns/")
+            .and_then(|p| p.split_once('/'))
+            .filter(|(ns, sa)| !ns.is_empty() && sa.starts_with("sa/"))
+            .map(|(ns, _)| ns);
+        return Some((td, ns));
+    }
+
+    let mut labels = id.splitn(6, '.');
+    let (sa, ns) = (labels.next()?, labels.next()?);
+    if labels.next()? != "serviceaccount" || labels.next()? != "identity" {
+        return None;
+    }
+    let (control_ns, td) = (labels.next()?, labels.next()?);
+    if [sa, ns, control_ns, td].iter().any(|l| l.is_empty()) {
+        return None;
+    }
+    Some((td, Some(ns)))
+}
+
 // === impl Suffix ===
 
 impl From<Vec<String>> for Suffix {
@@ -127,11 +347,20 @@ pub mod proto {
                                 Authentication::TlsUnauthenticated
                             }
                             api::authn::permit_mesh_tls::Clients::Identities(ids) => {
-                                let identities = ids
+                                // Names with wildcards are matched as patterns
+                                // rather than exactly.
+                                let (patterns, identities) = ids
                                     .identities
                                     .into_iter()
                                     .map(|api::Identity { name }| name)
+                                    .partition::<Vec<_>, _>(|name| {
+                                        IdentityPattern::parse(name).is_some()
+                                    });
+                                let patterns = patterns
+                                    .iter()
+                                    .filter_map(|name| IdentityPattern::parse(name))
                                     .collect();
+                                let identities = identities.into_iter().collect();
                                 let suffixes = ids
                                     .suffixes
                                     .into_iter()
@@ -140,6 +369,7 @@ pub mod proto {
                                 Authentication::TlsAuthenticated {
                                     identities,
                                     suffixes,
+                                    patterns,
                                 }
                             }
                         }
@@ -150,7 +380,7 @@ pub mod proto {
             // If the response includes `metadata`, use it; otherwise fall-back
             // to using old-style labels.
             let meta = match metadata {
-                Some(m) => Arc::new(m.try_into()?),
+                Some(m) => Meta::try_from(m)?.interned(),
                 None => {
                     Meta::try_new_with_default(labels, "policy.linkerd.io", "serverauthorization")?
                 }
@@ -160,7 +390,103 @@ pub mod proto {
                 networks,
                 authentication: authn,
                 meta,
+                local_addrs: vec![],
+                validity: vec![],
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_identity_patterns() {
+        assert_eq!(
+            IdentityPattern::parse("*.ns.serviceaccount.identity.linkerd.cluster.local"),
+            Some(IdentityPattern::DnsWildcard {
+                suffix: ".ns.serviceaccount.identity.linkerd.cluster.local".to_string(),
+            })
+        );
+        assert_eq!(
+            IdentityPattern::parse("spiffe://example.org/ns/prod/*"),
+            Some(IdentityPattern::SpiffePrefix {
+                prefix: "spiffe://example.org/ns/prod/".to_string(),
+            })
+        );
+        assert_eq!(
+            IdentityPattern::parse("spiffe://example.org/*"),
+            Some(IdentityPattern::SpiffePrefix {
+                prefix: "spiffe://example.org/".to_string(),
             })
+        );
+
+        for exact in [
+            "web.ns.serviceaccount.identity.linkerd.cluster.local",
+            "spiffe://example.org/ns/prod/sa/web",
+            "*",
+            "*.",
+            "*web.ns",
+            "*.*.ns",
+            "spiffe://*",
+            "spiffe:///*",
+            "spiffe://example.org/ns*",
+            "trust-domain:cluster.local",
+            "namespace:self",
+        ] {
+            assert_eq!(IdentityPattern::parse(exact), None, "{exact}");
+        }
+    }
+
+    #[test]
+    fn matches_trust_domains() {
+        let td = IdentityPattern::TrustDomain {
+            domain: "cluster.local".to_string(),
+        };
+        for id in [
+            "web.prod.serviceaccount.identity.linkerd.cluster.local",
+            "spiffe://cluster.local/ns/prod/sa/web",
+            "spiffe://cluster.local/vm/web",
+        ] {
+            assert!(td.matches(id, None), "{id}");
         }
+        for id in [
+            "web.prod.serviceaccount.identity.linkerd.cluster.example",
+            "web.prod.serviceaccount.identity.linkerd.other.cluster.local",
+            "web.prod.svc.cluster.local",
+            "spiffe://other.cluster.local/ns/prod/sa/web",
+        ] {
+            assert!(!td.matches(id, None), "{id}");
+        }
+    }
+
+    #[test]
+    fn matches_same_namespace() {
+        let ns = IdentityPattern::SameNamespace;
+        let local = Some("api.prod.serviceaccount.identity.linkerd.cluster.local");
+
+        assert!(ns.matches(
+            "web.prod.serviceaccount.identity.linkerd.cluster.local",
+            local
+        ));
+        assert!(!ns.matches(
+            "web.dev.serviceaccount.identity.linkerd.cluster.local",
+            local
+        ));
+        assert!(!ns.matches(
+            "web.prod.serviceaccount.identity.linkerd.cluster.example",
+            local
+        ));
+        // The proxy's identity must be known.
+        assert!(!ns.matches(
+            "web.prod.serviceaccount.identity.linkerd.cluster.local",
+            None
+        ));
+
+        let local = Some("spiffe://cluster.local/ns/prod/sa/api");
+        assert!(ns.matches("spiffe://cluster.local/ns/prod/sa/web", local));
+        assert!(!ns.matches("spiffe://cluster.local/ns/dev/sa/web", local));
+        assert!(!ns.matches("spiffe://cluster.local/vm/web", local));
     }
 }
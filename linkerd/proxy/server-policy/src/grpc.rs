@@ -9,6 +9,9 @@ pub type Rule = grpc::Rule<Policy>;
 pub enum Filter {
     InjectFailure(filter::InjectFailure),
     RequestHeaders(http::filter::ModifyHeader),
+    /// Describes the client's connection in request headers. This filter is
+    /// not yet expressible in the control plane API.
+    InjectMetadata(http::filter::InjectMetadata),
     InternalError(&'static str),
 }
 
@@ -29,6 +32,9 @@ pub fn default(authorizations: std::sync::Arc<[crate::Authorization]>) -> Route
                 meta: crate::Meta::new_default("default"),
                 authorizations,
                 filters: vec![],
+                max_in_flight: None,
+                rate_limit: None,
+                priority: crate::Priority::Normal,
             },
         }],
     }
@@ -93,7 +99,7 @@ pub mod proto {
             .collect::<Result<Vec<_>, InvalidHostMatch>>()?;
 
         let authzs = authz::proto::mk_authorizations(authorizations, server_authorizations)?;
-        let meta = Arc::new(Meta::try_from(metadata.ok_or(InvalidMeta::Missing)?)?);
+        let meta = Meta::try_from(metadata.ok_or(InvalidMeta::Missing)?)?.interned();
         let rules = rules
             .into_iter()
             .map(|r| try_rule(authzs.clone(), meta.clone(), r))
@@ -136,6 +142,9 @@ pub mod proto {
                 authorizations,
                 filters,
                 meta,
+                max_in_flight: None,
+                rate_limit: None,
+                priority: crate::Priority::Normal,
             }
         };
 
@@ -1,5 +1,6 @@
 use linkerd_http_route::http;
 pub use linkerd_http_route::http::{filter, r#match, RouteMatch};
+pub use linkerd_http_transcode::Transcoder;
 
 pub type Policy = crate::RoutePolicy<Filter>;
 pub type Route = http::Route<Policy>;
@@ -10,6 +11,19 @@ pub enum Filter {
     InjectFailure(filter::InjectFailure),
     Redirect(filter::RedirectRequest),
     RequestHeaders(filter::ModifyHeader),
+    /// Describes the client's connection in request headers. This filter is
+    /// not yet expressible in the control plane API.
+    InjectMetadata(filter::InjectMetadata),
+    /// Delegates to a filter that is implemented outside of the proxy, e.g. by
+    /// a WebAssembly module.
+    Extension(filter::Extension),
+    /// Transcodes JSON requests into gRPC requests for the methods described
+    /// by a descriptor set. This filter is not yet expressible in the control
+    /// plane API.
+    Transcode(Transcoder),
+    /// Limits the lifetime of WebSocket sessions. This filter is not yet
+    /// expressible in the control plane API.
+    WebSocket(filter::WebSocketPolicy),
     InternalError(&'static str),
 }
 
@@ -30,6 +44,9 @@ pub fn default(authorizations: std::sync::Arc<[crate::Authorization]>) -> Route
                 meta: crate::Meta::new_default("default"),
                 authorizations,
                 filters: vec![],
+                max_in_flight: None,
+                rate_limit: None,
+                priority: crate::Priority::Normal,
             },
         }],
     }
@@ -94,7 +111,7 @@ pub mod proto {
             .collect::<Result<Vec<_>, InvalidHostMatch>>()?;
 
         let authzs = authz::proto::mk_authorizations(authorizations, server_authorizations)?;
-        let meta = Arc::new(Meta::try_from(metadata.ok_or(InvalidMeta::Missing)?)?);
+        let meta = Meta::try_from(metadata.ok_or(InvalidMeta::Missing)?)?.interned();
         let rules = rules
             .into_iter()
             .map(|r| try_rule(authzs.clone(), meta.clone(), r))
@@ -138,6 +155,9 @@ pub mod proto {
                 authorizations,
                 filters,
                 meta,
+                max_in_flight: None,
+                rate_limit: None,
+                priority: crate::Priority::Normal,
             }
         };
 
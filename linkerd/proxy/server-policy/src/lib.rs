@@ -1,23 +1,39 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
-use std::{hash::Hash, sync::Arc, time};
+use std::{
+    hash::{Hash, Hasher},
+    num::{NonZeroU32, NonZeroU64},
+    sync::Arc,
+    time,
+};
 
 pub mod authz;
 pub mod grpc;
 pub mod http;
 pub mod meta;
+pub mod overrides;
+pub mod tls;
 
 pub use self::{
     authz::{Authentication, Authorization},
     meta::Meta,
 };
+pub use linkerd_duplex::Config as DuplexConfig;
 pub use linkerd_http_route as route;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ServerPolicy {
     pub protocol: Protocol,
     pub meta: Arc<Meta>,
+
+    /// Limits the number of bytes per second that may be read from (and
+    /// written to) each connection to the server.
+    pub bandwidth_limit: Option<NonZeroU64>,
+
+    /// Configures how opaque connections to the server handle half-closes
+    /// and idleness.
+    pub duplex: DuplexConfig,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -30,7 +46,13 @@ pub enum Protocol {
     Http1(Arc<[http::Route]>),
     Http2(Arc<[http::Route]>),
     Grpc(Arc<[grpc::Route]>),
-    Tls(Arc<[Authorization]>),
+    Tls {
+        authorizations: Arc<[Authorization]>,
+
+        /// Routes connections that are passed through to the application by
+        /// their SNI.
+        sni_routes: Arc<[tls::Route]>,
+    },
     Opaque(Arc<[Authorization]>),
 }
 
@@ -39,6 +61,49 @@ pub struct RoutePolicy<T> {
     pub meta: Arc<Meta>,
     pub authorizations: Arc<[Authorization]>,
     pub filters: Vec<T>,
+
+    /// Limits the number of requests that may be in flight on the route at
+    /// once. Requests in excess of the limit fail immediately.
+    pub max_in_flight: Option<usize>,
+
+    /// Limits the rate at which requests are admitted on the route. Requests
+    /// in excess of the limit fail immediately.
+    pub rate_limit: Option<RateLimit>,
+
+    /// Determines how soon the route's requests are shed as the server
+    /// approaches its concurrency limit.
+    pub priority: Priority,
+}
+
+/// Describes a route rule that is shadowed by an earlier rule, so that some
+/// or all of the requests it matches are never handled by it.
+#[derive(Clone, Debug)]
+pub struct ShadowedRule {
+    pub lint: route::lint::Lint,
+    pub route: Arc<Meta>,
+    pub shadowed_by: Arc<Meta>,
+}
+
+/// Limits the rate of a route's requests with a token bucket that holds up to
+/// `burst` tokens and is refilled at `requests_per_second`.
+///
+/// Limits are enforced by each proxy unless a quota service is configured, in
+/// which case they are shared by all of a server's replicas.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RateLimit {
+    pub requests_per_second: NonZeroU32,
+    pub burst: NonZeroU32,
+}
+
+/// Orders routes by how long their requests are admitted while a server is
+/// saturated: low-priority requests are shed first and high-priority requests
+/// last.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
 }
 
 impl ServerPolicy {
@@ -60,15 +125,55 @@ impl ServerPolicy {
                             filters: vec![http::Filter::InternalError(
                                 "invalid server configuration",
                             )],
+                            max_in_flight: None,
+                            rate_limit: None,
+                            priority: Priority::Normal,
                         },
                     }],
                 }]),
                 tcp_authorizations: Arc::new([]),
             },
+            bandwidth_limit: None,
+            duplex: Default::default(),
+        }
+    }
+
+    /// Returns a checksum of the policy, so that operators can verify that
+    /// proxies enforce the same policy. Checksums are only comparable between
+    /// proxies of the same version.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Finds the policy's route rules that are shadowed by earlier rules, which
+    /// usually indicates conflicting route resources.
+    pub fn shadowed_rules(&self) -> Vec<ShadowedRule> {
+        match &self.protocol {
+            Protocol::Detect { http, .. } | Protocol::Http1(http) | Protocol::Http2(http) => {
+                shadowed_rules(http)
+            }
+            Protocol::Grpc(grpc) => shadowed_rules(grpc),
+            Protocol::Tls { .. } | Protocol::Opaque(_) => Vec::new(),
         }
     }
 }
 
+fn shadowed_rules<M: route::Match + PartialEq, F>(
+    routes: &[route::Route<M, RoutePolicy<F>>],
+) -> Vec<ShadowedRule> {
+    let meta = |r: route::lint::RuleRef| routes[r.route].rules[r.rule].policy.meta.clone();
+    route::lint::lint(routes)
+        .into_iter()
+        .map(|lint| ShadowedRule {
+            route: meta(lint.rule()),
+            shadowed_by: meta(lint.shadowed_by()),
+            lint,
+        })
+        .collect()
+}
+
 #[cfg(feature = "proto")]
 pub mod proto {
     use super::*;
@@ -142,6 +247,8 @@ pub mod proto {
                     meta: Arc::new(Meta::Default {
                         name: "localhost".into(),
                     }),
+                    local_addrs: vec![],
+                    validity: vec![],
                 };
 
                 authz::proto::mk_authorizations(authorizations, &[localhost])?
@@ -174,7 +281,11 @@ pub mod proto {
                     Protocol::Grpc(mk_routes!(grpc, routes, authorizations)?)
                 }
 
-                api::proxy_protocol::Kind::Tls(_) => Protocol::Tls(authorizations),
+                // TODO Update the API to configure SNI routes.
+                api::proxy_protocol::Kind::Tls(_) => Protocol::Tls {
+                    authorizations,
+                    sni_routes: Arc::new([]),
+                },
                 api::proxy_protocol::Kind::Opaque(_) => Protocol::Opaque(authorizations),
             };
 
@@ -182,7 +293,14 @@ pub mod proto {
             // avoid label inference.
             let meta = Meta::try_new_with_default(labels, "policy.linkerd.io", "server")?;
 
-            Ok(ServerPolicy { protocol, meta })
+            // TODO Update the API to configure bandwidth limits and duplex
+            // forwarding.
+            Ok(ServerPolicy {
+                protocol,
+                meta,
+                bandwidth_limit: None,
+                duplex: Default::default(),
+            })
         }
     }
 }
@@ -61,6 +61,12 @@ impl std::hash::Hash for Meta {
 pub mod proto {
     use super::*;
     use linkerd2_proxy_api::meta as api;
+    use linkerd_proxy_core::Interner;
+    use once_cell::sync::Lazy;
+
+    /// Shares metadata across policy updates so that unchanged metadata is
+    /// not reallocated.
+    static METAS: Lazy<Interner<Meta>> = Lazy::new(Interner::new);
 
     #[derive(Debug, thiserror::Error)]
     pub enum InvalidMeta {
@@ -80,6 +86,11 @@ pub mod proto {
     // === impl Meta ===
 
     impl Meta {
+        /// Returns a shared reference to metadata equal to `self`.
+        pub(crate) fn interned(self) -> Arc<Self> {
+            METAS.intern(Arc::new(self))
+        }
+
         pub(crate) fn try_new_with_default(
             mut labels: std::collections::HashMap<String, String>,
             default_group: &'static str,
@@ -96,17 +107,22 @@ pub mod proto {
                 // indicating that we're dealing with a default, then build a
                 // default.
                 if group.is_empty() && kind == "default" {
-                    return Ok(Self::new_default(name));
+                    return Ok(Self::Default { name: name.into() }.interned());
                 }
 
-                return Ok(Arc::new(Self::Resource { group, kind, name }));
+                return Ok(Self::Resource { group, kind, name }.interned());
             }
 
             // Older control plane versions don't set the kind label and, instead, may
             // encode kinds in the name like `default:deny`.
             let mut parts = name.splitn(2, ':');
             let meta = match (parts.next().unwrap(), parts.next()) {
-                ("default", Some(name)) => return Ok(Self::new_default(name.to_string())),
+                ("default", Some(name)) => {
+                    return Ok(Self::Default {
+                        name: name.to_string().into(),
+                    }
+                    .interned())
+                }
                 (kind, Some(name)) => Self::Resource {
                     group,
                     kind: kind.into(),
@@ -119,7 +135,7 @@ pub mod proto {
                 },
             };
 
-            Ok(Arc::new(meta))
+            Ok(meta.interned())
         }
     }
 
@@ -171,6 +187,19 @@ pub mod proto {
         .unwrap();
         assert!(matches!(&*m, Meta::Default { name } if name == "foo"));
     }
+
+    #[cfg(test)]
+    #[test]
+    fn interned_from_labels() {
+        let labels = maplit::hashmap! {
+            "group".into() => "policy.linkerd.io".into(),
+            "kind".into() => "server".into(),
+            "name".into() => "interned".into(),
+        };
+        let a = Meta::try_new_with_default(labels.clone(), "foog", "fook").unwrap();
+        let b = Meta::try_new_with_default(labels, "foog", "fook").unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,587 @@
+//! Server policy settings that are not yet expressible in the control plane
+//! API.
+//!
+//! These settings are configured locally and applied to each policy as it is
+//! discovered, so that they take effect regardless of the policy's source.
+
+use crate::{
+    authz::{CertFingerprint, Hmac, IdentityPattern, LocalAddr, TimeWindow},
+    grpc, http, route, tls, Authentication, Authorization, DuplexConfig, Priority, Protocol,
+    RateLimit, RoutePolicy, ServerPolicy,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    num::NonZeroU64,
+    sync::Arc,
+};
+
+/// Locally-configured settings that are applied to server policies.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Overrides {
+    /// Settings for the servers on individual ports.
+    pub ports: HashMap<u16, ServerOverrides>,
+
+    /// Settings for HTTP and gRPC routes, by the names of the route
+    /// resources.
+    pub routes: HashMap<String, RouteOverrides>,
+
+    /// Settings for authorizations, by the names of the authorization
+    /// resources.
+    pub authorizations: HashMap<String, AuthzOverrides>,
+}
+
+/// Settings for the server on a port.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerOverrides {
+    pub bandwidth_limit: Option<NonZeroU64>,
+
+    /// Passes TLS connections that are not addressed to the proxy through to
+    /// the application, routing them by SNI. The server's connection
+    /// authorizations are retained.
+    pub sni_routes: Option<Arc<[tls::Route]>>,
+
+    /// Configures how opaque connections handle half-closes and idleness.
+    pub duplex: Option<DuplexConfig>,
+}
+
+/// Settings for a route's rules.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteOverrides {
+    pub max_in_flight: Option<usize>,
+    pub rate_limit: Option<RateLimit>,
+    pub priority: Option<Priority>,
+
+    /// Describes the client's connection in request headers.
+    pub inject_metadata: Option<http::filter::InjectMetadata>,
+
+    /// Transcodes JSON requests into gRPC requests. This only applies to
+    /// HTTP routes.
+    pub transcode: Option<http::Transcoder>,
+
+    /// Narrows the requests that the route's rules match. Only header matches
+    /// apply to gRPC routes.
+    pub matches: Option<route::http::MatchRequest>,
+}
+
+/// Builds the filters that are configured for a route, so that settings can
+/// be applied to HTTP and gRPC routes alike.
+trait RouteFilters: Sized {
+    fn filters(overrides: &RouteOverrides) -> Vec<Self>;
+}
+
+/// Narrows the matches of a route's rules, so that settings can be applied to
+/// HTTP and gRPC routes alike.
+trait RouteMatches: Sized {
+    fn narrow(matches: &mut Vec<Self>, narrow: &route::http::MatchRequest);
+}
+
+/// Settings for an authorization.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuthzOverrides {
+    /// Authenticates requests by their HMAC signatures instead of the
+    /// authorization's authentication.
+    pub hmac: Option<Hmac>,
+
+    /// Restricts the authorization to connections received on one of these
+    /// local addresses.
+    pub local_addrs: Vec<LocalAddr>,
+
+    /// Restricts the authorization to a window of time.
+    pub validity: Option<TimeWindow>,
+
+    /// Authenticates clients by the scopes in which their identities were
+    /// issued instead of the authorization's authentication.
+    pub identity_scopes: Option<IdentityScopes>,
+
+    /// Authenticates clients by the fingerprints of their certificates
+    /// instead of the authorization's authentication.
+    pub cert_fingerprints: BTreeSet<CertFingerprint>,
+}
+
+/// Authenticates mTLS clients by the scope in which their identities were
+/// issued, rather than by the names of their identities.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IdentityScopes {
+    /// Authenticates clients with identities issued in these trust domains.
+    pub trust_domains: Vec<String>,
+
+    /// Authenticates clients in the same trust domain and namespace as the
+    /// proxy's own identity.
+    pub same_namespace: bool,
+}
+
+// === impl Overrides ===
+
+impl Overrides {
+    /// Applies the settings configured for `port` to its server's policy, and
+    /// the settings configured for each of the server's routes and
+    /// authorizations.
+    pub fn apply(&self, port: u16, mut policy: ServerPolicy) -> ServerPolicy {
+        if let Some(server) = self.ports.get(&port) {
+            server.apply(&mut policy);
+        }
+
+        if !self.routes.is_empty() || !self.authorizations.is_empty() {
+            policy.protocol = match policy.protocol {
+                Protocol::Detect {
+                    http,
+                    timeout,
+                    tcp_authorizations,
+                } => Protocol::Detect {
+                    http: self.apply_routes(&http),
+                    timeout,
+                    tcp_authorizations: self.apply_authzs(&tcp_authorizations),
+                },
+                Protocol::Http1(http) => Protocol::Http1(self.apply_routes(&http)),
+                Protocol::Http2(http) => Protocol::Http2(self.apply_routes(&http)),
+                Protocol::Grpc(grpc) => Protocol::Grpc(self.apply_routes(&grpc)),
+                Protocol::Tls {
+                    authorizations,
+                    sni_routes,
+                } => Protocol::Tls {
+                    authorizations: self.apply_authzs(&authorizations),
+                    sni_routes,
+                },
+                Protocol::Opaque(authzs) => Protocol::Opaque(self.apply_authzs(&authzs)),
+            };
+        }
+
+        policy
+    }
+
+    fn apply_routes<M: Clone + RouteMatches, F: Clone + RouteFilters>(
+        &self,
+        routes: &[route::Route<M, RoutePolicy<F>>],
+    ) -> Arc<[route::Route<M, RoutePolicy<F>>]> {
+        routes
+            .iter()
+            .cloned()
+            .map(|mut route| {
+                for rule in &mut route.rules {
+                    if let Some(overrides) = self.routes.get(rule.policy.meta.name()) {
+                        if let Some(matches) = &overrides.matches {
+                            M::narrow(&mut rule.matches, matches);
+                        }
+                        overrides.apply(&mut rule.policy);
+                    }
+                    rule.policy.authorizations = self.apply_authzs(&rule.policy.authorizations);
+                }
+                route
+            })
+            .collect()
+    }
+
+    fn apply_authzs(&self, authzs: &Arc<[Authorization]>) -> Arc<[Authorization]> {
+        if self.authorizations.is_empty() {
+            return authzs.clone();
+        }
+        authzs
+            .iter()
+            .cloned()
+            .map(|mut authz| {
+                if let Some(overrides) = self.authorizations.get(authz.meta.name()) {
+                    overrides.apply(&mut authz);
+                }
+                authz
+            })
+            .collect()
+    }
+}
+
+// === impl AuthzOverrides ===
+
+impl AuthzOverrides {
+    fn apply(&self, authz: &mut Authorization) {
+        if let Some(hmac) = &self.hmac {
+            authz.authentication = Authentication::Hmac(hmac.clone());
+        }
+
+        if !self.local_addrs.is_empty() {
+            authz.local_addrs = self.local_addrs.clone();
+        }
+
+        if let Some(window) = &self.validity {
+            authz.validity = vec![window.clone()];
+        }
+
+        if let Some(scopes) = &self.identity_scopes {
+            authz.authentication = scopes.authentication();
+        }
+
+        if !self.cert_fingerprints.is_empty() {
+            authz.authentication = Authentication::TlsFingerprints(self.cert_fingerprints.clone());
+        }
+    }
+}
+
+// === impl IdentityScopes ===
+
+impl IdentityScopes {
+    fn authentication(&self) -> Authentication {
+        let mut patterns = self
+            .trust_domains
+            .iter()
+            .map(|domain| IdentityPattern::TrustDomain {
+                domain: domain.clone(),
+            })
+            .collect::<Vec<_>>();
+        if self.same_namespace {
+            patterns.push(IdentityPattern::SameNamespace);
+        }
+        Authentication::TlsAuthenticated {
+            identities: Default::default(),
+            suffixes: vec![],
+            patterns,
+        }
+    }
+}
+
+// === impl RouteOverrides ===
+
+impl RouteOverrides {
+    fn apply<F: RouteFilters>(&self, policy: &mut RoutePolicy<F>) {
+        if let Some(max) = self.max_in_flight {
+            policy.max_in_flight = Some(max);
+        }
+
+        if let Some(limit) = self.rate_limit {
+            policy.rate_limit = Some(limit);
+        }
+
+        if let Some(priority) = self.priority {
+            policy.priority = priority;
+        }
+
+        policy.filters.extend(F::filters(self));
+    }
+}
+
+impl RouteFilters for http::Filter {
+    fn filters(overrides: &RouteOverrides) -> Vec<Self> {
+        let inject = overrides.inject_metadata.clone().map(Self::InjectMetadata);
+        let transcode = overrides.transcode.clone().map(Self::Transcode);
+        inject.into_iter().chain(transcode).collect()
+    }
+}
+
+impl RouteFilters for grpc::Filter {
+    fn filters(overrides: &RouteOverrides) -> Vec<Self> {
+        let inject = overrides.inject_metadata.clone().map(Self::InjectMetadata);
+        inject.into_iter().collect()
+    }
+}
+
+impl RouteMatches for route::http::MatchRequest {
+    fn narrow(matches: &mut Vec<Self>, narrow: &route::http::MatchRequest) {
+        // A rule without matches matches all requests.
+        if matches.is_empty() {
+            matches.push(Self::default());
+        }
+        for m in matches {
+            m.narrow(narrow);
+        }
+    }
+}
+
+impl RouteMatches for route::grpc::MatchRoute {
+    fn narrow(matches: &mut Vec<Self>, narrow: &route::http::MatchRequest) {
+        if narrow.headers.is_empty() {
+            return;
+        }
+        if matches.is_empty() {
+            matches.push(Self::default());
+        }
+        for m in matches {
+            m.headers.extend(narrow.headers.iter().cloned());
+        }
+    }
+}
+
+// === impl ServerOverrides ===
+
+impl ServerOverrides {
+    fn apply(&self, policy: &mut ServerPolicy) {
+        if let Some(limit) = self.bandwidth_limit {
+            policy.bandwidth_limit = Some(limit);
+        }
+
+        if let Some(duplex) = self.duplex {
+            policy.duplex = duplex;
+        }
+
+        if let Some(sni_routes) = self.sni_routes.clone() {
+            let authorizations = match &policy.protocol {
+                Protocol::Detect {
+                    tcp_authorizations: authzs,
+                    ..
+                }
+                | Protocol::Tls {
+                    authorizations: authzs,
+                    ..
+                }
+                | Protocol::Opaque(authzs) => authzs.clone(),
+                // HTTP policies only authorize requests, so no connections are
+                // authorized.
+                Protocol::Http1(_) | Protocol::Http2(_) | Protocol::Grpc(_) => Arc::new([]),
+            };
+            policy.protocol = Protocol::Tls {
+                authorizations,
+                sni_routes,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Meta;
+
+    fn opaque() -> ServerPolicy {
+        ServerPolicy {
+            protocol: Protocol::Opaque(Arc::new([])),
+            meta: Meta::new_default("test"),
+            bandwidth_limit: None,
+            duplex: Default::default(),
+        }
+    }
+
+    #[test]
+    fn applies_port_settings() {
+        let limit = NonZeroU64::new(1024).unwrap();
+        let duplex = DuplexConfig {
+            half_close: false,
+            ..Default::default()
+        };
+        let overrides = Overrides {
+            ports: Some((
+                8080,
+                ServerOverrides {
+                    bandwidth_limit: Some(limit),
+                    duplex: Some(duplex),
+                    ..Default::default()
+                },
+            ))
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let policy = overrides.apply(8080, opaque());
+        assert_eq!(policy.bandwidth_limit, Some(limit));
+        assert_eq!(policy.duplex, duplex);
+        assert_eq!(overrides.apply(8081, opaque()), opaque());
+    }
+
+    #[test]
+    fn routes_tls_by_sni() {
+        let routes: Arc<[tls::Route]> = Arc::new([tls::Route {
+            snis: vec!["*.example.com".parse().unwrap()],
+            backend: tls::Backend::Port(8443),
+        }]);
+        let overrides = Overrides {
+            ports: Some((
+                443,
+                ServerOverrides {
+                    sni_routes: Some(routes.clone()),
+                    ..Default::default()
+                },
+            ))
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let policy = overrides.apply(443, opaque());
+        assert_eq!(
+            policy.protocol,
+            Protocol::Tls {
+                authorizations: Arc::new([]),
+                sni_routes: routes,
+            }
+        );
+    }
+
+    #[test]
+    fn applies_route_settings() {
+        let policy = ServerPolicy {
+            protocol: Protocol::Http1(Arc::new([
+                http::default(Arc::new([])),
+                route::Route {
+                    hosts: vec![],
+                    rules: vec![route::Rule {
+                        matches: vec![],
+                        policy: http::Policy {
+                            meta: Arc::new(Meta::Resource {
+                                group: "gateway.networking.k8s.io".into(),
+                                kind: "HTTPRoute".into(),
+                                name: "api".into(),
+                            }),
+                            ..http::default(Arc::new([])).rules[0].policy.clone()
+                        },
+                    }],
+                },
+            ])),
+            ..opaque()
+        };
+        let template = route::http::MatchRequest {
+            path: Some(route::http::r#match::MatchPath::Template(
+                "/users/{id}".parse().unwrap(),
+            )),
+            ..Default::default()
+        };
+        let overrides = Overrides {
+            routes: Some((
+                "api".to_string(),
+                RouteOverrides {
+                    max_in_flight: Some(10),
+                    priority: Some(Priority::High),
+                    inject_metadata: Some(http::filter::InjectMetadata {
+                        route: Some(::http::HeaderName::from_static("x-route")),
+                        ..Default::default()
+                    }),
+                    matches: Some(template.clone()),
+                    ..Default::default()
+                },
+            ))
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let Protocol::Http1(routes) = overrides.apply(8080, policy).protocol else {
+            panic!("protocol must not change");
+        };
+        assert_eq!(routes[0].rules[0].policy.max_in_flight, None);
+        assert_eq!(routes[0].rules[0].policy.priority, Priority::Normal);
+        assert_eq!(routes[1].rules[0].policy.max_in_flight, Some(10));
+        assert_eq!(routes[1].rules[0].policy.priority, Priority::High);
+        assert!(routes[0].rules[0].policy.filters.is_empty());
+        assert!(matches!(
+            routes[1].rules[0].policy.filters[..],
+            [http::Filter::InjectMetadata(_)]
+        ));
+        assert_eq!(
+            routes[0].rules[0].matches,
+            http::default(Arc::new([])).rules[0].matches
+        );
+        assert_eq!(routes[1].rules[0].matches, [template]);
+    }
+
+    #[test]
+    fn applies_authz_settings() {
+        let authz = |name: &str| Authorization {
+            networks: vec![],
+            authentication: Authentication::Unauthenticated,
+            meta: Arc::new(Meta::Resource {
+                group: "policy.linkerd.io".into(),
+                kind: "authorizationpolicy".into(),
+                name: name.into(),
+            }),
+            local_addrs: vec![],
+            validity: vec![],
+        };
+        let authzs: Arc<[Authorization]> = Arc::new([authz("webhooks"), authz("other")]);
+        let policy = ServerPolicy {
+            protocol: Protocol::Detect {
+                http: Arc::new([http::default(authzs.clone())]),
+                timeout: std::time::Duration::from_secs(10),
+                tcp_authorizations: authzs,
+            },
+            ..opaque()
+        };
+        let hmac = Hmac {
+            secret: "webhooks".to_string(),
+            signature_header: ::http::HeaderName::from_static("x-signature"),
+            timestamp_header: ::http::HeaderName::from_static("x-signature-timestamp"),
+            max_skew: std::time::Duration::from_secs(300),
+            components: vec![],
+        };
+        let overrides = Overrides {
+            authorizations: Some((
+                "webhooks".to_string(),
+                AuthzOverrides {
+                    hmac: Some(hmac.clone()),
+                    local_addrs: vec![LocalAddr {
+                        net: None,
+                        port: Some(4143),
+                    }],
+                    validity: Some(TimeWindow {
+                        not_before: None,
+                        not_after: Some(std::time::UNIX_EPOCH),
+                    }),
+                    identity_scopes: None,
+                    cert_fingerprints: Default::default(),
+                },
+            ))
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let Protocol::Detect {
+            http,
+            tcp_authorizations,
+            ..
+        } = overrides.apply(4143, policy).protocol
+        else {
+            panic!("protocol must not change");
+        };
+        for authzs in [&tcp_authorizations, &http[0].rules[0].policy.authorizations] {
+            assert_eq!(authzs[0].authentication, Authentication::Hmac(hmac.clone()));
+            assert!(!authzs[0].matches_local_addr(([10, 0, 0, 1], 8080).into()));
+            assert!(!authzs[0].is_valid_at(std::time::SystemTime::now()));
+            assert_eq!(authzs[1].authentication, Authentication::Unauthenticated);
+            assert!(authzs[1].matches_local_addr(([10, 0, 0, 1], 8080).into()));
+            assert!(authzs[1].is_valid_at(std::time::SystemTime::now()));
+        }
+    }
+    #[test]
+    fn applies_identity_scopes() {
+        let authz = Authorization {
+            networks: vec![],
+            authentication: Authentication::Unauthenticated,
+            meta: Meta::new_default("mesh"),
+            local_addrs: vec![],
+            validity: vec![],
+        };
+        let overrides = AuthzOverrides {
+            identity_scopes: Some(IdentityScopes {
+                trust_domains: vec!["cluster.local".to_string()],
+                same_namespace: true,
+            }),
+            ..Default::default()
+        };
+        let mut scoped = authz.clone();
+        overrides.apply(&mut scoped);
+        assert_eq!(
+            scoped.authentication,
+            Authentication::TlsAuthenticated {
+                identities: Default::default(),
+                suffixes: vec![],
+                patterns: vec![
+                    IdentityPattern::TrustDomain {
+                        domain: "cluster.local".to_string(),
+                    },
+                    IdentityPattern::SameNamespace,
+                ],
+            }
+        );
+
+        let fingerprint = CertFingerprint([7; 32]);
+        let overrides = AuthzOverrides {
+            cert_fingerprints: Some(fingerprint).into_iter().collect(),
+            ..Default::default()
+        };
+        let mut pinned = authz.clone();
+        overrides.apply(&mut pinned);
+        assert_eq!(
+            pinned.authentication,
+            Authentication::TlsFingerprints(Some(fingerprint).into_iter().collect())
+        );
+
+        let mut unscoped = authz.clone();
+        AuthzOverrides::default().apply(&mut unscoped);
+        assert_eq!(unscoped, authz);
+    }
+}
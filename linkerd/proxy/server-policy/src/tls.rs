@@ -0,0 +1,189 @@
+//! Routes TLS connections that are not terminated by the proxy.
+//!
+//! When a server's protocol is TLS and a client's SNI does not name the
+//! proxy's identity, the connection is passed through to the application. A
+//! server may route these connections by SNI to different backends, so that
+//! a single port can front several TLS services (e.g. as a passthrough
+//! gateway).
+
+use std::{net::SocketAddr, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Route {
+    pub snis: Vec<MatchSni>,
+    pub backend: Backend,
+}
+
+/// Matches a client's SNI.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MatchSni {
+    Exact(String),
+
+    /// Matches names with the given suffix, excluding the suffix itself.
+    ///
+    /// For example, the match `*.example.com` is stored as `example.com`.
+    Suffix(String),
+}
+
+/// The target of passthrough connections.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// A port on the connection's original destination address.
+    Port(u16),
+    Addr(SocketAddr),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidRoute {
+    #[error("invalid SNI match: {0:?}")]
+    Sni(String),
+
+    #[error("invalid backend: {0:?}")]
+    Backend(String),
+}
+
+/// Finds the backend of the route that most specifically matches `sni`.
+///
+/// Exact matches are preferred over suffix matches, and longer suffixes are
+/// preferred over shorter suffixes. When multiple routes match equally, the
+/// first route is used.
+pub fn find<'r>(routes: &'r [Route], sni: &str) -> Option<&'r Backend> {
+    let sni = sni.strip_suffix('.').unwrap_or(sni).to_ascii_lowercase();
+    let mut best: Option<((bool, usize), &Backend)> = None;
+    for route in routes {
+        for m in &route.snis {
+            if let Some(score) = m.score(&sni) {
+                if best.map_or(true, |(s, _)| score > s) {
+                    best = Some((score, &route.backend));
+                }
+            }
+        }
+    }
+    best.map(|(_, backend)| backend)
+}
+
+// === impl MatchSni ===
+
+impl MatchSni {
+    /// Scores a match against a normalized SNI, so that more specific matches
+    /// score higher.
+    fn score(&self, sni: &str) -> Option<(bool, usize)> {
+        match self {
+            Self::Exact(name) if name == sni => Some((true, name.len())),
+            Self::Suffix(suffix) => {
+                let prefix = sni.strip_suffix(suffix.as_str())?.strip_suffix('.')?;
+                if prefix.is_empty() {
+                    return None;
+                }
+                Some((false, suffix.len()))
+            }
+            Self::Exact(_) => None,
+        }
+    }
+}
+
+impl FromStr for MatchSni {
+    type Err = InvalidRoute;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        let name = name.strip_suffix('.').unwrap_or(&name);
+        let (suffix, name) = match name.strip_prefix("*.") {
+            Some(name) => (true, name),
+            None => (false, name),
+        };
+
+        let valid =
+            |l: &str| !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !name.split('.').all(valid) {
+            return Err(InvalidRoute::Sni(s.to_string()));
+        }
+
+        if suffix {
+            Ok(Self::Suffix(name.to_string()))
+        } else {
+            Ok(Self::Exact(name.to_string()))
+        }
+    }
+}
+
+// === impl Backend ===
+
+impl Backend {
+    /// Resolves the backend's address for a connection to `orig_dst`.
+    pub fn addr(&self, orig_dst: SocketAddr) -> SocketAddr {
+        match *self {
+            Self::Port(port) => SocketAddr::new(orig_dst.ip(), port),
+            Self::Addr(addr) => addr,
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = InvalidRoute;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(port) = s.parse::<u16>() {
+            if port != 0 {
+                return Ok(Self::Port(port));
+            }
+        }
+        s.parse::<SocketAddr>()
+            .map(Self::Addr)
+            .map_err(|_| InvalidRoute::Backend(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(snis: &[&str], backend: &str) -> Route {
+        Route {
+            snis: snis.iter().map(|s| s.parse().unwrap()).collect(),
+            backend: backend.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn finds_most_specific_route() {
+        let routes = [
+            route(&["*.example.com"], "8443"),
+            route(&["*.api.example.com"], "10.0.0.1:443"),
+            route(&["admin.example.com", "admin.example.org"], "9443"),
+        ];
+
+        assert_eq!(find(&routes, "www.example.com"), Some(&Backend::Port(8443)));
+        assert_eq!(
+            find(&routes, "v1.api.example.com"),
+            Some(&Backend::Addr("10.0.0.1:443".parse().unwrap()))
+        );
+        assert_eq!(
+            find(&routes, "Admin.Example.com."),
+            Some(&Backend::Port(9443))
+        );
+        assert_eq!(
+            find(&routes, "admin.example.org"),
+            Some(&Backend::Port(9443))
+        );
+        assert_eq!(find(&routes, "example.com"), None);
+        assert_eq!(find(&routes, "example.net"), None);
+    }
+
+    #[test]
+    fn parses() {
+        assert!("*.example.com".parse::<MatchSni>().is_ok());
+        assert!("".parse::<MatchSni>().is_err());
+        assert!("*.".parse::<MatchSni>().is_err());
+        assert!("foo..com".parse::<MatchSni>().is_err());
+        assert!("foo.*.com".parse::<MatchSni>().is_err());
+
+        assert_eq!("0".parse::<Backend>().ok(), None);
+        assert_eq!(
+            "[::1]:8443".parse::<Backend>().unwrap(),
+            Backend::Addr("[::1]:8443".parse().unwrap())
+        );
+        assert!("localhost:8443".parse::<Backend>().is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use bytes::{Buf, BytesMut};
+use linkerd2_proxy_api::http_types;
+use std::sync::Arc;
+
+/// The name of the synthetic trailer that carries a captured request body.
+pub(crate) const REQUEST_BODY: &str = "l5d-tap-request-body";
+
+/// The name of the synthetic trailer that carries a captured response body.
+pub(crate) const RESPONSE_BODY: &str = "l5d-tap-response-body";
+
+/// Configures the capture of message bodies in tap events.
+///
+/// Capture is disabled by default. When enabled, the first `max_bytes` bytes
+/// of each request and response body with an allowed content type are
+/// included in the tap's `ResponseEnd` event as synthetic trailers. Bodies are
+/// only captured for taps that extract headers, so body capture is subject to
+/// the same authorization as header extraction.
+#[derive(Clone, Debug, Default)]
+pub struct BodyCapture {
+    max_bytes: usize,
+    content_types: Arc<Vec<String>>,
+}
+
+/// The leading bytes of a single message body.
+#[derive(Debug)]
+pub(crate) struct Captured {
+    max_bytes: usize,
+    buf: BytesMut,
+}
+
+// === impl BodyCapture ===
+
+impl BodyCapture {
+    /// Captures up to `max_bytes` of bodies with the given content types.
+    ///
+    /// A content type that ends with `/` (e.g. `text/`) allows all subtypes.
+    pub fn new(max_bytes: usize, content_types: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            max_bytes,
+            content_types: content_types
+                .into_iter()
+                .map(|ct| ct.trim().to_ascii_lowercase())
+                .filter(|ct| !ct.is_empty())
+                .collect::<Vec<_>>()
+                .into(),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_bytes > 0 && !self.content_types.is_empty()
+    }
+
+    /// Begins capturing a message body, if the message's content type is
+    /// allowed.
+    pub(crate) fn capture(&self, headers: &http::HeaderMap) -> Option<Captured> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let content_type = headers
+            .get(http::header::CONTENT_TYPE)?
+            .to_str()
+            .ok()?
+            .split(';')
+            .next()?
+            .trim()
+            .to_ascii_lowercase();
+        let allowed = self.content_types.iter().any(|ct| {
+            if ct.ends_with('/') {
+                content_type.starts_with(ct.as_str())
+            } else {
+                content_type == *ct
+            }
+        });
+        if !allowed {
+            return None;
+        }
+
+        Some(Captured {
+            max_bytes: self.max_bytes,
+            buf: BytesMut::new(),
+        })
+    }
+}
+
+// === impl Captured ===
+
+impl Captured {
+    pub(crate) fn data<B: Buf>(&mut self, data: &B) {
+        let remaining = self.max_bytes.saturating_sub(self.buf.len());
+        // Body frames are contiguous in practice, so only the first chunk is
+        // inspected.
+        let chunk = data.chunk();
+        self.buf
+            .extend_from_slice(&chunk[..remaining.min(chunk.len())]);
+    }
+
+    pub(crate) fn to_pb(&self, name: &str) -> http_types::headers::Header {
+        http_types::headers::Header {
+            name: name.to_owned(),
+            value: self.buf.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(content_type: &str) -> http::HeaderMap {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(http::header::CONTENT_TYPE, content_type.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn allows_content_types() {
+        let capture = BodyCapture::new(8, ["application/json".into(), "text/".into()]);
+        assert!(capture.capture(&headers("application/json")).is_some());
+        assert!(capture
+            .capture(&headers("Application/JSON; charset=utf-8"))
+            .is_some());
+        assert!(capture.capture(&headers("text/plain")).is_some());
+        assert!(capture.capture(&headers("application/grpc")).is_none());
+        assert!(capture.capture(&http::HeaderMap::new()).is_none());
+
+        let disabled = BodyCapture::new(0, ["text/".into()]);
+        assert!(disabled.capture(&headers("text/plain")).is_none());
+        assert!(BodyCapture::default()
+            .capture(&headers("text/plain"))
+            .is_none());
+    }
+
+    #[test]
+    fn truncates_bodies() {
+        let capture = BodyCapture::new(8, ["text/".into()]);
+        let mut captured = capture.capture(&headers("text/plain")).unwrap();
+        captured.data(&bytes::Bytes::from_static(b"hello "));
+        captured.data(&bytes::Bytes::from_static(b"world"));
+        captured.data(&bytes::Bytes::from_static(b"!"));
+        let header = captured.to_pb(REQUEST_BODY);
+        assert_eq!(header.name, REQUEST_BODY);
+        assert_eq!(header.value, b"hello wo");
+    }
+}
@@ -1,5 +1,8 @@
 use super::match_::Match;
-use crate::{iface, Inspect, Registry};
+use crate::{
+    capture::{self, BodyCapture, Captured},
+    iface, Inspect, Registry,
+};
 use futures::ready;
 use futures::stream::Stream;
 use hyper::body::{Buf, HttpBody};
@@ -7,6 +10,7 @@ use linkerd2_proxy_api::{http_types, tap as api};
 use linkerd_conditional::Conditional;
 use linkerd_proxy_http::HasH2Reason;
 use linkerd_tls as tls;
+use parking_lot::Mutex;
 use pin_project::pin_project;
 use std::convert::TryFrom;
 use std::iter;
@@ -23,6 +27,7 @@ use tracing::{debug, trace, warn};
 pub struct Server {
     base_id: Arc<AtomicUsize>,
     registry: Registry,
+    body_capture: BodyCapture,
 }
 
 #[pin_project]
@@ -40,6 +45,8 @@ struct Shared {
     limit: usize,
     match_: Match,
     extract: ExtractKind,
+    /// Set when bodies should be captured for this tap.
+    body_capture: Option<BodyCapture>,
     events_tx: mpsc::Sender<api::TapEvent>,
 }
 
@@ -60,11 +67,15 @@ pub struct TapResponse {
     request_init_at: Instant,
     /// Should headers be extracted?
     extract_headers: bool,
+    body_capture: Option<BodyCapture>,
+    request_body: Option<Arc<Mutex<Captured>>>,
     tap: TapTx,
 }
 
 #[derive(Debug)]
-pub struct TapRequestPayload {}
+pub struct TapRequestPayload {
+    body: Option<Arc<Mutex<Captured>>>,
+}
 
 #[derive(Debug)]
 pub struct TapResponsePayload {
@@ -75,6 +86,8 @@ pub struct TapResponsePayload {
     tap: TapTx,
     /// Should headers be extracted?
     extract_headers: bool,
+    request_body: Option<Arc<Mutex<Captured>>>,
+    response_body: Option<Captured>,
     // Response-headers may include grpc-status when there is no response body.
     grpc_status: Option<u32>,
 }
@@ -92,27 +105,42 @@ enum ExtractKind {
 // === impl Server ===
 
 impl Server {
-    pub(crate) fn new(registry: Registry) -> Self {
+    pub(crate) fn new(registry: Registry, body_capture: BodyCapture) -> Self {
         let base_id = Arc::new(0.into());
-        Self { base_id, registry }
+        Self {
+            base_id,
+            registry,
+            body_capture,
+        }
+    }
+
+    /// Observes requests on behalf of the proxy itself (e.g. for a capture
+    /// triggered via the admin server), rather than a remote tap client.
+    ///
+    /// Bodies are only captured if `capture_bodies` is set and body capture
+    /// is enabled.
+    pub fn observe_local(
+        &self,
+        req: api::ObserveRequest,
+        capture_bodies: bool,
+    ) -> Result<ResponseStream, grpc::Status> {
+        let body_capture = if capture_bodies {
+            self.body_capture.clone()
+        } else {
+            BodyCapture::default()
+        };
+        self.register(req, &body_capture)
     }
 
     fn invalid_arg(message: String) -> grpc::Status {
         grpc::Status::new(grpc::Code::InvalidArgument, message)
     }
-}
-
-#[tonic::async_trait]
-impl api::tap_server::Tap for Server {
-    type ObserveStream = ResponseStream;
 
-    #[tracing::instrument(skip(self), level = "debug")]
-    async fn observe(
+    fn register(
         &self,
-        req: grpc::Request<api::ObserveRequest>,
-    ) -> Result<grpc::Response<Self::ObserveStream>, grpc::Status> {
-        let req = req.into_inner();
-
+        req: api::ObserveRequest,
+        body_capture: &BodyCapture,
+    ) -> Result<ResponseStream, grpc::Status> {
         let limit = req.limit as usize;
         if limit == 0 {
             let err = Self::invalid_arg("limit must be positive".into());
@@ -149,6 +177,11 @@ impl api::tap_server::Tap for Server {
         let base_id = self.base_id.fetch_add(1, Ordering::Relaxed) as u32;
         debug!(id = ?base_id, r#match = ?match_, ?extract, "tap;");
 
+        // Bodies are only captured for taps that are permitted to extract
+        // headers.
+        let ExtractKind::Http { headers } = extract;
+        let body_capture = Some(body_capture.clone()).filter(|bc| headers && bc.is_enabled());
+
         // The events channel is used to emit tap events to the response stream.
         //
         // At most `limit` copies of `events_tx` are dispatched to `taps_rx`
@@ -164,6 +197,7 @@ impl api::tap_server::Tap for Server {
             limit,
             match_,
             extract,
+            body_capture,
             events_tx,
         });
 
@@ -174,12 +208,24 @@ impl api::tap_server::Tap for Server {
         // Register the tap with the server's tap registry
         self.registry.register(tap);
 
-        let rsp = ResponseStream {
+        Ok(ResponseStream {
             shared: Some(shared),
             events_rx,
-        };
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl api::tap_server::Tap for Server {
+    type ObserveStream = ResponseStream;
 
-        Ok(Response::new(rsp))
+    #[tracing::instrument(skip(self), level = "debug")]
+    async fn observe(
+        &self,
+        req: grpc::Request<api::ObserveRequest>,
+    ) -> Result<grpc::Response<Self::ObserveStream>, grpc::Status> {
+        self.register(req.into_inner(), &self.body_capture)
+            .map(Response::new)
     }
 }
 
@@ -331,12 +377,21 @@ impl iface::Tap for Tap {
 
         let tap = TapTx { id, tx: events_tx };
 
-        let req = TapRequestPayload {};
+        let request_body = shared
+            .body_capture
+            .as_ref()
+            .and_then(|bc| bc.capture(req.headers()))
+            .map(|body| Arc::new(Mutex::new(body)));
+        let req = TapRequestPayload {
+            body: request_body.clone(),
+        };
         let rsp = TapResponse {
             tap,
             base_event,
             request_init_at,
             extract_headers,
+            body_capture: shared.body_capture.clone(),
+            request_body,
         };
         Some((req, rsp))
     }
@@ -387,6 +442,8 @@ impl iface::TapResponse for TapResponse {
             response_bytes: 0,
             tap: self.tap,
             extract_headers: self.extract_headers,
+            request_body: self.request_body,
+            response_body: self.body_capture.and_then(|bc| bc.capture(rsp.headers())),
             grpc_status: rsp
                 .headers()
                 .get("grpc-status")
@@ -423,7 +480,11 @@ impl iface::TapResponse for TapResponse {
 // === impl TapRequestPayload ===
 
 impl iface::TapPayload for TapRequestPayload {
-    fn data<B: Buf>(&mut self, _: &B) {}
+    fn data<B: Buf>(&mut self, data: &B) {
+        if let Some(body) = self.body.as_ref() {
+            body.lock().data(data);
+        }
+    }
 
     fn eos(self, _: Option<&http::HeaderMap>) {}
 
@@ -435,6 +496,9 @@ impl iface::TapPayload for TapRequestPayload {
 impl iface::TapPayload for TapResponsePayload {
     fn data<B: Buf>(&mut self, data: &B) {
         self.response_bytes += data.remaining();
+        if let Some(body) = self.response_body.as_mut() {
+            body.data(data);
+        }
     }
 
     fn eos(self, trls: Option<&http::HeaderMap>) {
@@ -460,12 +524,29 @@ impl iface::TapPayload for TapResponsePayload {
 impl TapResponsePayload {
     fn send(self, end: Option<api::eos::End>, trls: Option<&http::HeaderMap>) {
         let response_end_at = Instant::now();
-        let trailers = if self.extract_headers {
+        let mut trailers = if self.extract_headers {
             trls.map(|trls| headers_to_pb(iter::empty(), trls))
         } else {
             None
         };
 
+        // Captured bodies are carried as synthetic trailers, since tap events
+        // have no dedicated body field.
+        let bodies = self
+            .request_body
+            .map(|body| body.lock().to_pb(capture::REQUEST_BODY))
+            .into_iter()
+            .chain(
+                self.response_body
+                    .map(|body| body.to_pb(capture::RESPONSE_BODY)),
+            );
+        for body in bodies {
+            trailers
+                .get_or_insert_with(Default::default)
+                .headers
+                .push(body);
+        }
+
         let since_request_init = response_end_at.saturating_duration_since(self.request_init_at);
         let since_response_init = response_end_at.saturating_duration_since(self.response_init_at);
         let end = api::tap_event::http::ResponseEnd {
@@ -5,11 +5,17 @@ use linkerd_tls as tls;
 use std::{net, sync::Arc};
 
 mod accept;
+mod capture;
 mod grpc;
 mod registry;
 mod service;
 
-pub use self::{accept::AcceptPermittedClients, service::NewTapHttp};
+pub use self::{
+    accept::AcceptPermittedClients,
+    capture::BodyCapture,
+    grpc::{ResponseStream, Server},
+    service::NewTapHttp,
+};
 
 /// A registry containing all the active taps that have registered with the
 /// gRPC server.
@@ -18,15 +24,37 @@ pub type Registry = registry::Registry<grpc::Tap>;
 // The number of events that may be buffered for a given response.
 const PER_RESPONSE_EVENT_BUFFER_CAPACITY: usize = 400;
 
-pub fn new() -> (Registry, grpc::Server) {
+pub fn new(body_capture: BodyCapture) -> (Registry, grpc::Server) {
     let registry = Registry::new();
-    let server = grpc::Server::new(registry.clone());
+    let server = grpc::Server::new(registry.clone(), body_capture);
     (registry, server)
 }
 
 /// Endpoint labels are lexicographically ordered by key.
 pub type Labels = Arc<std::collections::BTreeMap<String, String>>;
 
+/// Labels describing the policy route that handled a request (e.g.
+/// `route_name`).
+///
+/// Policy routers insert these labels into request extensions so that they
+/// are included in tap events' route metadata and may be matched by route
+/// label matches.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PolicyRouteLabels(pub Labels);
+
+/// Merges two sets of labels. When a key is present in both sets, the value
+/// from `b` is used.
+pub fn merge_labels(a: Option<Labels>, b: Option<Labels>) -> Option<Labels> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let mut labels = (*a).clone();
+            labels.extend(b.iter().map(|(k, v)| (k.clone(), v.clone())));
+            Some(Arc::new(labels))
+        }
+        (a, b) => a.or(b),
+    }
+}
+
 /// Inspects a request for a `Stack`.
 ///
 /// `Stack` target types
@@ -112,3 +140,33 @@ mod iface {
         fn fail<E: HasH2Reason>(self, error: &E);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(kvs: &[(&str, &str)]) -> Labels {
+        Arc::new(
+            kvs.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn merges_labels() {
+        let a = labels(&[("route", "GET /"), ("route_name", "a")]);
+        let b = labels(&[("route_name", "b"), ("route_kind", "HTTPRoute")]);
+        assert_eq!(merge_labels(None, None), None);
+        assert_eq!(merge_labels(Some(a.clone()), None), Some(a.clone()));
+        assert_eq!(merge_labels(None, Some(b.clone())), Some(b.clone()));
+        assert_eq!(
+            merge_labels(Some(a), Some(b)),
+            Some(labels(&[
+                ("route", "GET /"),
+                ("route_kind", "HTTPRoute"),
+                ("route_name", "b"),
+            ]))
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use futures::prelude::*;
-use linkerd_duplex::Duplex;
+use linkerd_duplex::{Config, Duplex};
 use linkerd_error::{Error, Result};
-use linkerd_stack::layer;
+use linkerd_stack::{layer, NewService, Param};
 use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 use tokio::io::{AsyncRead, AsyncWrite};
 use tower::Service;
@@ -13,15 +14,54 @@ use tower::Service;
 #[derive(Clone, Debug)]
 pub struct Forward<C> {
     connect: C,
+    config: Config,
+    max_age: Option<Duration>,
+}
+
+/// Builds a [`Forward`] for each target, configured by the target's
+/// [`Config`].
+#[derive(Clone, Debug)]
+pub struct NewForward<N> {
+    inner: N,
+    max_age: Option<Duration>,
 }
 
 impl<C> Forward<C> {
-    fn new(connect: C) -> Self {
-        Self { connect }
+    fn new(connect: C, max_age: Option<Duration>) -> Self {
+        Self {
+            connect,
+            config: Config::default(),
+            max_age,
+        }
+    }
+
+    /// Forwards connections until they are closed or, if a maximum age is
+    /// configured, until they reach it.
+    pub fn layer(max_age: Option<Duration>) -> impl layer::Layer<C, Service = Self> + Clone + Copy {
+        layer::mk(move |connect| Self::new(connect, max_age))
     }
+}
+
+impl<N> NewForward<N> {
+    pub fn layer(max_age: Option<Duration>) -> impl layer::Layer<N, Service = Self> + Clone + Copy {
+        layer::mk(move |inner| Self { inner, max_age })
+    }
+}
+
+impl<T, N> NewService<T> for NewForward<N>
+where
+    T: Param<Config>,
+    N: NewService<T>,
+{
+    type Service = Forward<N::Service>;
 
-    pub fn layer() -> impl layer::Layer<C, Service = Self> + Clone + Copy {
-        layer::mk(Self::new)
+    fn new_service(&self, target: T) -> Self::Service {
+        let config = target.param();
+        Forward {
+            connect: self.inner.new_service(target),
+            config,
+            max_age: self.max_age,
+        }
     }
 }
 
@@ -43,11 +83,58 @@ where
     }
 
     fn call(&mut self, src_io: I) -> Self::Future {
-        Box::pin(
-            self.connect
-                .call(())
-                .err_into::<Error>()
-                .and_then(|dst_io| Duplex::new(src_io, dst_io).err_into::<Error>()),
-        )
+        let config = self.config;
+        let max_age = self.max_age;
+        let connect = self.connect.call(()).err_into::<Error>();
+        Box::pin(async move {
+            let dst_io = connect.await?;
+            let forward = Duplex::with_config(config, src_io, dst_io);
+            let Some(max_age) = max_age else {
+                return forward.await.map_err(Into::into);
+            };
+            // Both sockets are closed when the forward is dropped.
+            match tokio::time::timeout(max_age, forward).await {
+                Ok(res) => res.map_err(Into::into),
+                Err(_) => {
+                    tracing::debug!(?max_age, "Closing connection at its maximum age");
+                    Ok(())
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        time,
+    };
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn closes_connections_at_max_age() {
+        let (mut client, src_io) = tokio::io::duplex(1024);
+        let (dst_io, mut server) = tokio::io::duplex(1024);
+        let mut dst_io = Some(dst_io);
+        let connect = tower::service_fn(move |()| future::ok::<_, Error>(dst_io.take().unwrap()));
+        let mut forward = Forward::new(connect, Some(Duration::from_secs(5)));
+        let task = tokio::spawn(forward.call(src_io));
+
+        // Data is forwarded until the connection reaches its maximum age.
+        client.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+
+        time::sleep(Duration::from_secs(4)).await;
+        assert!(!task.is_finished(), "connection must remain open");
+
+        time::sleep(Duration::from_secs(1)).await;
+        task.await.unwrap().expect("forward must complete");
+
+        // Both sides of the connection are closed.
+        assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+        assert_eq!(server.read(&mut buf).await.unwrap(), 0);
     }
 }
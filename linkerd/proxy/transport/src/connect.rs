@@ -1,4 +1,4 @@
-use crate::{ClientAddr, Keepalive, Local, Remote, ServerAddr};
+use crate::{ClientAddr, Keepalive, Local, Remote, ServerAddr, SocketOptions};
 use linkerd_io as io;
 use linkerd_stack::{Param, Service};
 use std::{
@@ -12,11 +12,15 @@ use tracing::debug;
 #[derive(Copy, Clone, Debug)]
 pub struct ConnectTcp {
     keepalive: Keepalive,
+    socket_options: SocketOptions,
 }
 
 impl ConnectTcp {
-    pub fn new(keepalive: Keepalive) -> Self {
-        Self { keepalive }
+    pub fn new(keepalive: Keepalive, socket_options: SocketOptions) -> Self {
+        Self {
+            keepalive,
+            socket_options,
+        }
     }
 }
 
@@ -30,17 +34,20 @@ impl<T: Param<Remote<ServerAddr>>> Service<T> for ConnectTcp {
     }
 
     fn call(&mut self, t: T) -> Self::Future {
-        let Keepalive(keepalive) = self.keepalive;
+        let Self {
+            keepalive,
+            socket_options,
+        } = *self;
         let Remote(ServerAddr(addr)) = t.param();
         debug!(server.addr = %addr, "Connecting");
         Box::pin(async move {
             let io = TcpStream::connect(&addr).await?;
-            super::set_nodelay_or_warn(&io);
-            let io = super::set_keepalive_or_warn(io, keepalive)?;
+            let io = super::set_socket_options_or_warn(io, keepalive, socket_options)?;
             let local_addr = io.local_addr()?;
             debug!(
                 local.addr = %local_addr,
                 ?keepalive,
+                ?socket_options,
                 "Connected",
             );
             Ok((io::ScopedIo::client(io), Local(ClientAddr(local_addr))))
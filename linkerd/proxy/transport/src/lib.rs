@@ -1,6 +1,7 @@
 //! Utilities for use TCP servers & clients.
 //!
-//! Uses unsafe code to interact with socket options for SO_ORIGINAL_DST.
+//! Uses unsafe code to interact with socket options for SO_ORIGINAL_DST and
+//! TCP_INFO.
 
 #![deny(
     rust_2018_idioms,
@@ -13,12 +14,14 @@ pub mod addrs;
 mod connect;
 pub mod listen;
 pub mod orig_dst;
+pub mod tcp_info;
 
 pub use self::{
     addrs::{AddrPair, ClientAddr, ListenAddr, Local, OrigDstAddr, Remote, ServerAddr},
     connect::ConnectTcp,
-    listen::{Bind, BindTcp},
+    listen::{AcceptShards, Bind, BindTcp, ReusePort},
     orig_dst::BindWithOrigDst,
+    tcp_info::{GetTcpInfo, TcpInfo},
 };
 use linkerd_io as io;
 use socket2::TcpKeepalive;
@@ -28,24 +31,54 @@ use tokio::net::TcpStream;
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Keepalive(pub Option<Duration>);
 
+/// Configures TCP socket options (in addition to the keepalive time).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Disables Nagle's algorithm (`TCP_NODELAY`).
+    pub nodelay: bool,
+
+    /// The interval between TCP keepalive probes (`TCP_KEEPINTVL`).
+    pub keepalive_interval: Option<Duration>,
+
+    /// The number of unacknowledged keepalive probes that are sent before a
+    /// connection is dropped (`TCP_KEEPCNT`).
+    pub keepalive_count: Option<u32>,
+
+    /// The amount of time that transmitted data may remain unacknowledged
+    /// before a connection is dropped (`TCP_USER_TIMEOUT`).
+    pub user_timeout: Option<Duration>,
+}
+
 impl From<Keepalive> for Option<Duration> {
     fn from(Keepalive(duration): Keepalive) -> Option<Duration> {
         duration
     }
 }
 
-// Misc.
+// === impl SocketOptions ===
 
-fn set_nodelay_or_warn(socket: &TcpStream) {
-    if let Err(e) = socket.set_nodelay(true) {
-        tracing::warn!("failed to set nodelay: {}", e);
+impl Default for SocketOptions {
+    fn default() -> Self {
+        Self {
+            nodelay: true,
+            keepalive_interval: None,
+            keepalive_count: None,
+            user_timeout: None,
+        }
     }
 }
 
-fn set_keepalive_or_warn(
+// Misc.
+
+fn set_socket_options_or_warn(
     tcp: TcpStream,
-    keepalive_duration: Option<Duration>,
+    Keepalive(keepalive_duration): Keepalive,
+    opts: SocketOptions,
 ) -> io::Result<TcpStream> {
+    if let Err(e) = tcp.set_nodelay(opts.nodelay) {
+        tracing::warn!("failed to set nodelay: {}", e);
+    }
+
     let sock = {
         let stream = tokio::net::TcpStream::into_std(tcp)?;
         socket2::Socket::from(stream)
@@ -53,9 +86,36 @@ fn set_keepalive_or_warn(
     let ka = keepalive_duration
         .into_iter()
         .fold(TcpKeepalive::new(), |k, t| k.with_time(t));
+    #[cfg(target_os = "linux")]
+    let ka = {
+        let ka = opts
+            .keepalive_interval
+            .into_iter()
+            .fold(ka, |k, i| k.with_interval(i));
+        opts.keepalive_count
+            .into_iter()
+            .fold(ka, |k, c| k.with_retries(c))
+    };
     if let Err(e) = sock.set_tcp_keepalive(&ka) {
         tracing::warn!("failed to set keepalive: {}", e);
     }
+
+    #[cfg(target_os = "linux")]
+    if let Some(timeout) = opts.user_timeout {
+        if let Err(e) = sock.set_tcp_user_timeout(Some(timeout)) {
+            tracing::warn!("failed to set user timeout: {}", e);
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    if opts.keepalive_interval.is_some()
+        || opts.keepalive_count.is_some()
+        || opts.user_timeout.is_some()
+    {
+        tracing::warn!(
+            "TCP keepalive interval, keepalive count, and user timeout are only supported on Linux"
+        );
+    }
+
     let stream: std::net::TcpStream = socket2::Socket::into(sock);
     tokio::net::TcpStream::from_std(stream)
 }
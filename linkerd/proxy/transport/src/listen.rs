@@ -1,9 +1,9 @@
-use crate::{addrs::*, Keepalive};
+use crate::{addrs::*, Keepalive, SocketOptions};
 use futures::prelude::*;
 use linkerd_error::Result;
 use linkerd_io as io;
 use linkerd_stack::Param;
-use std::{fmt, pin::Pin};
+use std::{fmt, num::NonZeroUsize, pin::Pin};
 use thiserror::Error;
 use tokio::net::TcpStream;
 use tokio_stream::wrappers::TcpListenerStream;
@@ -33,6 +33,23 @@ pub type Bound<I> = (Local<ServerAddr>, I);
 #[derive(Copy, Clone, Debug, Default)]
 pub struct BindTcp(());
 
+/// Configures the number of sockets that are bound to a listen address, each
+/// of which accepts connections independently.
+///
+/// When more than one socket is bound, each socket is bound with
+/// `SO_REUSEPORT` so that the kernel distributes connections across sockets.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AcceptShards(pub NonZeroUsize);
+
+/// Configures whether a listener's sockets are bound with `SO_REUSEPORT`, even
+/// when only a single accept shard is used.
+///
+/// This permits a new proxy process to bind the same address while a prior
+/// process is still running, so that the prior process may be drained once the
+/// new process is accepting connections.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReusePort(pub bool);
+
 #[derive(Clone, Debug)]
 pub struct Addrs {
     pub server: Local<ServerAddr>,
@@ -44,8 +61,8 @@ pub struct Addrs {
 struct AcceptError(#[source] io::Error);
 
 #[derive(Debug, Error)]
-#[error("failed to set TCP keepalive: {0}")]
-struct KeepaliveError(#[source] io::Error);
+#[error("failed to set TCP socket options: {0}")]
+struct SocketOptionsError(#[source] io::Error);
 
 #[derive(Debug, Error)]
 #[error("failed to obtain peer address: {0}")]
@@ -61,7 +78,8 @@ impl BindTcp {
 
 impl<T> Bind<T> for BindTcp
 where
-    T: Param<ListenAddr> + Param<Keepalive>,
+    T: Param<ListenAddr> + Param<Keepalive> + Param<SocketOptions> + Param<AcceptShards>,
+    T: Param<ReusePort>,
 {
     type Addrs = Addrs;
     type Incoming = Pin<Box<dyn Stream<Item = Result<(Self::Addrs, Self::Io)>> + Send + Sync>>;
@@ -70,17 +88,24 @@ where
     fn bind(self, params: &T) -> Result<Bound<Self::Incoming>> {
         let listen = {
             let ListenAddr(addr) = params.param();
-            let l = std::net::TcpListener::bind(addr)?;
+            let AcceptShards(shards) = params.param();
+            let ReusePort(reuse_port) = params.param();
+            let l = if reuse_port || shards.get() > 1 {
+                bind_reuse_port(addr)?
+            } else {
+                std::net::TcpListener::bind(addr)?
+            };
             // Ensure that O_NONBLOCK is set on the socket before using it with Tokio.
             l.set_nonblocking(true)?;
             tokio::net::TcpListener::from_std(l).expect("listener must be valid")
         };
         let server = Local(ServerAddr(listen.local_addr()?));
-        let Keepalive(keepalive) = params.param();
+        let keepalive: Keepalive = params.param();
+        let socket_options: SocketOptions = params.param();
         let accept = TcpListenerStream::new(listen).map(move |res| {
             let tcp = res.map_err(AcceptError)?;
-            super::set_nodelay_or_warn(&tcp);
-            let tcp = super::set_keepalive_or_warn(tcp, keepalive).map_err(KeepaliveError)?;
+            let tcp = super::set_socket_options_or_warn(tcp, keepalive, socket_options)
+                .map_err(SocketOptionsError)?;
             let client = Remote(ClientAddr(tcp.peer_addr().map_err(PeerAddrError)?));
             Ok((Addrs { server, client }, tcp))
         });
@@ -89,6 +114,35 @@ where
     }
 }
 
+#[cfg(unix)]
+fn bind_reuse_port(addr: std::net::SocketAddr) -> io::Result<std::net::TcpListener> {
+    use socket2::{Domain, Socket, Type};
+
+    // Mirror the options set by `std::net::TcpListener::bind`.
+    let sock = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+    sock.set_reuse_address(true)?;
+    sock.set_reuse_port(true)?;
+    sock.bind(&addr.into())?;
+    sock.listen(128)?;
+    Ok(sock.into())
+}
+
+#[cfg(not(unix))]
+fn bind_reuse_port(_: std::net::SocketAddr) -> io::Result<std::net::TcpListener> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "SO_REUSEPORT not supported on this operating system",
+    ))
+}
+
+// === impl AcceptShards ===
+
+impl Default for AcceptShards {
+    fn default() -> Self {
+        Self(NonZeroUsize::new(1).unwrap())
+    }
+}
+
 // === impl Addrs ===
 
 impl Param<Remote<ClientAddr>> for Addrs {
@@ -113,3 +167,90 @@ impl Param<AddrPair> for Addrs {
         AddrPair(client, server)
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    struct Params(ListenAddr, AcceptShards, ReusePort);
+
+    impl Param<ListenAddr> for Params {
+        fn param(&self) -> ListenAddr {
+            self.0
+        }
+    }
+
+    impl Param<Keepalive> for Params {
+        fn param(&self) -> Keepalive {
+            Keepalive(None)
+        }
+    }
+
+    impl Param<SocketOptions> for Params {
+        fn param(&self) -> SocketOptions {
+            SocketOptions::default()
+        }
+    }
+
+    impl Param<AcceptShards> for Params {
+        fn param(&self) -> AcceptShards {
+            self.1
+        }
+    }
+
+    impl Param<ReusePort> for Params {
+        fn param(&self) -> ReusePort {
+            self.2
+        }
+    }
+
+    #[tokio::test]
+    async fn shards_share_addr() {
+        let shards = AcceptShards(NonZeroUsize::new(2).unwrap());
+        let (Local(ServerAddr(addr)), _a) = BindTcp::default()
+            .bind(&Params(
+                ListenAddr(([127, 0, 0, 1], 0).into()),
+                shards,
+                ReusePort::default(),
+            ))
+            .expect("must bind");
+        let (Local(ServerAddr(addr2)), _b) = BindTcp::default()
+            .bind(&Params(ListenAddr(addr), shards, ReusePort::default()))
+            .expect("shards must bind the same address");
+        assert_eq!(addr, addr2);
+
+        // Without sharding, the address may only be bound once.
+        assert!(BindTcp::default()
+            .bind(&Params(
+                ListenAddr(addr),
+                AcceptShards::default(),
+                ReusePort::default()
+            ))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn reuse_port_takeover() {
+        let reuse = ReusePort(true);
+        let (Local(ServerAddr(addr)), old) = BindTcp::default()
+            .bind(&Params(
+                ListenAddr(([127, 0, 0, 1], 0).into()),
+                AcceptShards::default(),
+                reuse,
+            ))
+            .expect("must bind");
+
+        // A subsequent listener may bind the address while the original
+        // listener is still open.
+        let (_, mut new) = BindTcp::default()
+            .bind(&Params(ListenAddr(addr), AcceptShards::default(), reuse))
+            .expect("listeners must bind the same address");
+
+        // Once the original listener is closed, connections are accepted by
+        // the new listener.
+        drop(old);
+        let _client = TcpStream::connect(addr).await.expect("must connect");
+        let (addrs, _) = new.next().await.unwrap().expect("must accept");
+        assert_eq!(addrs.server, Local(ServerAddr(addr)));
+    }
+}
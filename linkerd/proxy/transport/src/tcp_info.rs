@@ -0,0 +1,96 @@
+//! Reads kernel statistics about a TCP connection (`TCP_INFO`).
+
+use linkerd_io as io;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// Statistics that the kernel maintains for a TCP connection.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TcpInfo {
+    /// The smoothed round-trip time.
+    pub rtt: Duration,
+    /// The variance of the round-trip time.
+    pub rtt_var: Duration,
+    /// The total number of segments retransmitted over the connection's
+    /// lifetime.
+    pub total_retransmits: u32,
+    /// The sender's congestion window, in segments.
+    pub snd_cwnd: u32,
+}
+
+/// Exposes the [`TcpInfo`] of a transport's underlying TCP connection.
+pub trait GetTcpInfo {
+    fn tcp_info(&self) -> io::Result<TcpInfo>;
+}
+
+impl GetTcpInfo for TcpStream {
+    #[cfg(target_os = "linux")]
+    #[allow(unsafe_code)]
+    fn tcp_info(&self) -> io::Result<TcpInfo> {
+        use std::os::unix::io::AsRawFd;
+
+        let info = unsafe { linux::tcp_info(self.as_raw_fd()) }?;
+        Ok(TcpInfo {
+            rtt: Duration::from_micros(info.tcpi_rtt.into()),
+            rtt_var: Duration::from_micros(info.tcpi_rttvar.into()),
+            total_retransmits: info.tcpi_total_retrans,
+            snd_cwnd: info.tcpi_snd_cwnd,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn tcp_info(&self) -> io::Result<TcpInfo> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "TCP_INFO not supported on this operating system",
+        ))
+    }
+}
+
+impl<I: GetTcpInfo> GetTcpInfo for io::ScopedIo<I> {
+    fn tcp_info(&self) -> io::Result<TcpInfo> {
+        self.get_ref().tcp_info()
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+mod linux {
+    use std::os::unix::io::RawFd;
+    use std::{io, mem};
+
+    pub unsafe fn tcp_info(fd: RawFd) -> io::Result<libc::tcp_info> {
+        let mut info: libc::tcp_info = mem::zeroed();
+        let mut len = mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+
+        let ret = libc::getsockopt(
+            fd,
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            &mut info as *mut _ as *mut _,
+            &mut len,
+        );
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(info)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_tcp_info() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (client, server) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        let (client, _server) = (client.unwrap(), server.unwrap());
+
+        let info = client.tcp_info().expect("TCP_INFO must be readable");
+        assert_eq!(info.total_retransmits, 0);
+        assert!(info.snd_cwnd > 0, "congestion window must be open");
+    }
+}
@@ -10,7 +10,7 @@ use std::{
     sync::Arc,
     task::{Context, Poll},
 };
-use tonic::{body::BoxBody, client::GrpcService};
+use tonic::{body::BoxBody, client::GrpcService, codec::CompressionEncoding};
 use tracing::debug;
 
 /// Creates watches on service profiles.
@@ -121,7 +121,7 @@ where
         Self {
             context_token,
             limits,
-            client: DestinationClient::new(inner),
+            client: DestinationClient::new(inner).accept_compressed(CompressionEncoding::Gzip),
         }
     }
 }
@@ -2,11 +2,13 @@
 #![forbid(unsafe_code)]
 
 mod layer;
+mod queue;
 mod service;
 
 pub use self::layer::TrackServiceLayer;
+pub use self::queue::{Dequeue, DequeueLayer, Enqueue, EnqueueLayer, Enqueued, TrackQueue};
 pub use self::service::TrackService;
-use linkerd_metrics::{metrics, Counter, FmtLabels, FmtMetrics};
+use linkerd_metrics::{latency, metrics, Counter, FmtLabels, FmtMetrics, Gauge, Histogram};
 use parking_lot::Mutex;
 use std::{collections::HashMap, fmt, hash::Hash, sync::Arc};
 
@@ -14,13 +16,18 @@ metrics! {
     stack_create_total: Counter { "Total number of services created" },
     stack_drop_total: Counter { "Total number of services dropped" },
     stack_poll_total: Counter { "Total number of stack polls" },
-    stack_poll_total_ms: Counter { "Total number of milliseconds this service has spent awaiting readiness" }
+    stack_poll_total_ms: Counter { "Total number of milliseconds this service has spent awaiting readiness" },
+    stack_queue_depth: Gauge { "The number of requests waiting in a stack's queue" },
+    stack_queue_latency_ms: Histogram<latency::Ms> { "The time requests spend waiting in a stack's queue, in milliseconds" }
 }
 
-type Shared<L> = Arc<Mutex<HashMap<L, Arc<Metrics>>>>;
+type Shared<L, M> = Arc<Mutex<HashMap<L, Arc<M>>>>;
 
 #[derive(Debug)]
-pub struct Registry<L: Hash + Eq>(Shared<L>);
+pub struct Registry<L: Hash + Eq> {
+    services: Shared<L, Metrics>,
+    queues: Shared<L, QueueMetrics>,
+}
 
 #[derive(Debug, Default)]
 struct Metrics {
@@ -32,31 +39,56 @@ struct Metrics {
     error_total: Counter,
 }
 
+#[derive(Debug, Default)]
+struct QueueMetrics {
+    depth: Gauge,
+    latency: Histogram<latency::Ms>,
+}
+
 impl<L> Registry<L>
 where
     L: Hash + Eq,
 {
     pub fn layer(&self, labels: L) -> TrackServiceLayer {
-        let metrics = self.0.lock().entry(labels).or_default().clone();
+        let metrics = self.services.lock().entry(labels).or_default().clone();
         TrackServiceLayer::new(metrics)
     }
+
+    pub fn queue(&self, labels: L) -> TrackQueue {
+        let metrics = self.queues.lock().entry(labels).or_default().clone();
+        TrackQueue::new(metrics)
+    }
 }
 
 impl<L: Hash + Eq> Default for Registry<L> {
     fn default() -> Self {
-        Registry(Shared::default())
+        Registry {
+            services: Shared::default(),
+            queues: Shared::default(),
+        }
     }
 }
 
 impl<L: Hash + Eq> Clone for Registry<L> {
     fn clone(&self) -> Self {
-        Registry(self.0.clone())
+        Registry {
+            services: self.services.clone(),
+            queues: self.queues.clone(),
+        }
     }
 }
 
 impl<L: FmtLabels + Hash + Eq> FmtMetrics for Registry<L> {
     fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let metrics = self.0.lock();
+        self.fmt_services(f)?;
+        self.fmt_queues(f)?;
+        Ok(())
+    }
+}
+
+impl<L: FmtLabels + Hash + Eq> Registry<L> {
+    fn fmt_services(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metrics = self.services.lock();
         if metrics.is_empty() {
             return Ok(());
         }
@@ -89,6 +121,21 @@ impl<L: FmtLabels + Hash + Eq> FmtMetrics for Registry<L> {
 
         Ok(())
     }
+
+    fn fmt_queues(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metrics = self.queues.lock();
+        if metrics.is_empty() {
+            return Ok(());
+        }
+
+        stack_queue_depth.fmt_help(f)?;
+        stack_queue_depth.fmt_scopes(f, metrics.iter(), |m| &m.depth)?;
+
+        stack_queue_latency_ms.fmt_help(f)?;
+        stack_queue_latency_ms.fmt_scopes(f, metrics.iter(), |m| &m.latency)?;
+
+        Ok(())
+    }
 }
 
 enum Readiness {
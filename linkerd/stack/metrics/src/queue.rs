@@ -0,0 +1,184 @@
+use crate::QueueMetrics;
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time;
+
+/// Tracks the requests waiting in a queue (e.g. a buffer).
+///
+/// The enqueue layer wraps the queue so that each request is tagged as it
+/// enters the queue. The dequeue layer wraps the service that the queue
+/// dispatches requests to, so that the time each request spent in the queue is
+/// recorded when it leaves.
+#[derive(Clone, Debug)]
+pub struct TrackQueue(Arc<QueueMetrics>);
+
+/// A request that is waiting in a queue.
+///
+/// The queue's depth is decremented when the request is dispatched or dropped.
+#[derive(Debug)]
+pub struct Enqueued<Req> {
+    req: Req,
+    queued: Queued,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnqueueLayer(Arc<QueueMetrics>);
+
+#[derive(Clone, Debug)]
+pub struct DequeueLayer(());
+
+#[derive(Clone, Debug)]
+pub struct Enqueue<S> {
+    inner: S,
+    metrics: Arc<QueueMetrics>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Dequeue<S> {
+    inner: S,
+}
+
+#[derive(Debug)]
+struct Queued {
+    metrics: Arc<QueueMetrics>,
+    t0: time::Instant,
+}
+
+// === impl TrackQueue ===
+
+impl TrackQueue {
+    pub(crate) fn new(metrics: Arc<QueueMetrics>) -> Self {
+        Self(metrics)
+    }
+
+    /// Returns a layer that wraps a queue so that requests are tracked as they
+    /// are enqueued.
+    pub fn enqueue_layer(&self) -> EnqueueLayer {
+        EnqueueLayer(self.0.clone())
+    }
+
+    /// Returns a layer that wraps the service behind a queue so that requests
+    /// are tracked as they are dequeued.
+    pub fn dequeue_layer(&self) -> DequeueLayer {
+        DequeueLayer(())
+    }
+}
+
+// === impl EnqueueLayer ===
+
+impl<S> tower::layer::Layer<S> for EnqueueLayer {
+    type Service = Enqueue<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Enqueue {
+            inner,
+            metrics: self.0.clone(),
+        }
+    }
+}
+
+// === impl DequeueLayer ===
+
+impl<S> tower::layer::Layer<S> for DequeueLayer {
+    type Service = Dequeue<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Dequeue { inner }
+    }
+}
+
+// === impl Enqueue ===
+
+impl<Req, S> tower::Service<Req> for Enqueue<S>
+where
+    S: tower::Service<Enqueued<Req>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.metrics.depth.incr();
+        self.inner.call(Enqueued {
+            req,
+            queued: Queued {
+                metrics: self.metrics.clone(),
+                t0: time::Instant::now(),
+            },
+        })
+    }
+}
+
+// === impl Dequeue ===
+
+impl<Req, S> tower::Service<Enqueued<Req>> for Dequeue<S>
+where
+    S: tower::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, Enqueued { req, queued }: Enqueued<Req>) -> Self::Future {
+        let waited = time::Instant::now().saturating_duration_since(queued.t0);
+        queued.metrics.latency.add(waited);
+        drop(queued);
+        self.inner.call(req)
+    }
+}
+
+// === impl Queued ===
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        self.metrics.depth.decr();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::{Layer, Service};
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn tracks_queued_requests() {
+        let metrics = Arc::new(QueueMetrics::default());
+        let track = TrackQueue::new(metrics.clone());
+
+        // Stand in for a queue by holding requests until they are dispatched.
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Enqueued<()>>();
+        let mut enqueue = track.enqueue_layer().layer(tower::service_fn(move |req| {
+            tx.send(req).unwrap();
+            futures::future::ok::<_, ()>(())
+        }));
+        let mut dequeue = track
+            .dequeue_layer()
+            .layer(tower::service_fn(|()| futures::future::ok::<_, ()>(())));
+
+        enqueue.call(()).await.unwrap();
+        enqueue.call(()).await.unwrap();
+        assert_eq!(metrics.depth.value(), 2);
+
+        time::sleep(time::Duration::from_millis(3)).await;
+        dequeue.call(rx.recv().await.unwrap()).await.unwrap();
+        assert_eq!(metrics.depth.value(), 1);
+        metrics.latency.assert_bucket_exactly(3.0, 1.0);
+
+        // Requests that are dropped from the queue are no longer counted.
+        drop(rx.recv().await.unwrap());
+        assert_eq!(metrics.depth.value(), 0);
+        metrics.latency.assert_bucket_exactly(3.0, 1.0);
+    }
+}
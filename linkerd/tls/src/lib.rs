@@ -6,7 +6,9 @@ pub mod server;
 
 pub use self::{
     client::{Client, ClientTls, ConditionalClientTls, ConnectMeta, NoClientTls, ServerId},
-    server::{ClientId, ConditionalServerTls, NewDetectTls, NoServerTls, ServerTls},
+    server::{
+        CertFingerprint, ClientId, ConditionalServerTls, NewDetectTls, NoServerTls, ServerTls,
+    },
 };
 
 use linkerd_dns_name as dns;
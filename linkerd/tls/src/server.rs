@@ -22,11 +22,21 @@ use tracing::{debug, trace, warn};
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct ClientId(pub id::Id);
 
+/// The SHA-256 digest of a client's DER-encoded leaf certificate.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct CertFingerprint(pub [u8; 32]);
+
+#[derive(Clone, Debug, Error)]
+#[error("certificate fingerprints must be 64 hexadecimal digits")]
+pub struct InvalidCertFingerprint(());
+
 /// Indicates a server-side connection's TLS status.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum ServerTls {
     Established {
         client_id: Option<ClientId>,
+        /// The fingerprint of the client's certificate, if it presented one.
+        client_fingerprint: Option<CertFingerprint>,
         negotiated_protocol: Option<NegotiatedProtocol>,
     },
     Passthru {
@@ -281,6 +291,47 @@ impl std::str::FromStr for ClientId {
     }
 }
 
+// === impl CertFingerprint ===
+
+impl fmt::Debug for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CertFingerprint({self})")
+    }
+}
+
+impl fmt::Display for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in self.0 {
+            write!(f, "{b:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for CertFingerprint {
+    type Err = InvalidCertFingerprint;
+
+    /// Parses a fingerprint from hexadecimal digits, which may be separated by
+    /// colons as printed by `openssl x509 -fingerprint`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut digits = s.chars().filter(|c| *c != ':');
+        let mut fingerprint = [0u8; 32];
+        for b in fingerprint.iter_mut() {
+            let mut digit = || {
+                digits
+                    .next()
+                    .and_then(|c| c.to_digit(16))
+                    .ok_or(InvalidCertFingerprint(()))
+            };
+            *b = (digit()? << 4 | digit()?) as u8;
+        }
+        if digits.next().is_some() {
+            return Err(InvalidCertFingerprint(()));
+        }
+        Ok(Self(fingerprint))
+    }
+}
+
 // === impl NoClientId ===
 
 impl fmt::Display for NoServerTls {
@@ -303,6 +354,16 @@ impl ServerTls {
             _ => None,
         }
     }
+
+    pub fn client_fingerprint(&self) -> Option<&CertFingerprint> {
+        match self {
+            ServerTls::Established {
+                ref client_fingerprint,
+                ..
+            } => client_fingerprint.as_ref(),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -310,6 +371,32 @@ mod tests {
     use super::*;
     use linkerd_io::AsyncWriteExt;
 
+    #[test]
+    fn parses_cert_fingerprints() {
+        let hex = "00112233445566778899aabbccddeeff00112233445566778899AABBCCDDEEFF";
+        let fp = hex.parse::<CertFingerprint>().expect("must parse");
+        assert_eq!(fp.0[..4], [0x00, 0x11, 0x22, 0x33]);
+        assert_eq!(fp.0[31], 0xff);
+        assert_eq!(fp.to_string(), hex.to_ascii_lowercase());
+
+        let colons =
+            fp.0.iter()
+                .map(|b| format!("{b:02X}"))
+                .collect::<Vec<_>>()
+                .join(":");
+        assert_eq!(colons.parse::<CertFingerprint>().unwrap(), fp);
+
+        for invalid in [
+            "",
+            "0011",
+            &hex[1..],
+            &format!("{hex}00"),
+            &hex.replace('a', "g"),
+        ] {
+            assert!(invalid.parse::<CertFingerprint>().is_err(), "{invalid}");
+        }
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn detect_buffered() {
         let _trace = linkerd_tracing::test::trace_init();
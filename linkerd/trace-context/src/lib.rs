@@ -37,6 +37,16 @@ pub struct Span {
     pub labels: HashMap<&'static str, String>,
 }
 
+/// Returns the ID of the trace to which a request belongs, if the request
+/// carries a sampled trace context.
+///
+/// This is used to link metrics to representative traces via exemplars.
+pub fn sampled_trace_id<B>(req: &http::Request<B>) -> Option<Id> {
+    propagation::unpack_trace_context(req)
+        .filter(|ctx| ctx.is_sampled())
+        .map(|ctx| ctx.trace_id)
+}
+
 pub trait SpanSink {
     fn is_enabled(&self) -> bool;
 
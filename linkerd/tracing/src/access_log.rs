@@ -109,6 +109,7 @@ impl ApacheCommon {
         "total_ns",
         "processing_ns",
         "response_bytes",
+        "path_params",
         "user_agent",
         "host",
     ];
@@ -0,0 +1,213 @@
+//! Rate-limits repeated warnings.
+//!
+//! During an incident, the proxy may log the same warning (e.g. a connection
+//! failure to an unavailable endpoint) many times per second. Once a warning
+//! has been logged, identical warnings are suppressed until the end of the
+//! current window, when a single summary of the suppressed warnings is logged
+//! instead.
+
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    sync::{Arc, Weak},
+    time::Duration,
+};
+use tracing::{field, Event, Level, Metadata};
+use tracing_subscriber::layer::{self, Filter};
+
+/// The target of the summaries emitted for suppressed warnings. Summaries are
+/// never suppressed.
+const SUMMARY_TARGET: &str = "linkerd_tracing::aggregate";
+
+/// A per-layer filter that suppresses warnings that are identical to a warning
+/// that has already been logged in the current window.
+#[derive(Clone, Debug)]
+pub(crate) struct Aggregate {
+    warnings: Arc<Mutex<Warnings>>,
+}
+
+/// Logs a summary of the warnings suppressed in each window.
+#[derive(Clone, Debug)]
+pub(crate) struct Summarize {
+    window: Duration,
+    warnings: Weak<Mutex<Warnings>>,
+}
+
+type Warnings = HashMap<String, Warning>;
+
+#[derive(Debug)]
+struct Warning {
+    target: String,
+    message: String,
+    suppressed: u64,
+}
+
+/// Formats an event's fields so that identical events may be detected.
+struct Fields<'a> {
+    message: &'a mut String,
+    fields: &'a mut String,
+}
+
+// === impl Aggregate ===
+
+impl Aggregate {
+    /// Returns a filter and a handle that logs a summary of the warnings
+    /// suppressed in each `window`.
+    pub(crate) fn new(window: Duration) -> (Self, Summarize) {
+        let warnings = Arc::new(Mutex::new(Warnings::new()));
+        let summarize = Summarize {
+            window,
+            warnings: Arc::downgrade(&warnings),
+        };
+        (Self { warnings }, summarize)
+    }
+
+    /// Returns true if the event should be logged.
+    fn observe(&self, event: &Event<'_>) -> bool {
+        let meta = event.metadata();
+        if *meta.level() != Level::WARN || meta.target() == SUMMARY_TARGET {
+            return true;
+        }
+
+        let mut message = String::new();
+        let mut fields = String::new();
+        event.record(&mut Fields {
+            message: &mut message,
+            fields: &mut fields,
+        });
+        let key = format!("{}:{}{}", meta.target(), message, fields);
+
+        let mut warnings = self.warnings.lock();
+        if let Some(w) = warnings.get_mut(&key) {
+            w.suppressed += 1;
+            return false;
+        }
+        warnings.insert(
+            key,
+            Warning {
+                target: meta.target().to_string(),
+                message: format!("{message}{fields}"),
+                suppressed: 0,
+            },
+        );
+        true
+    }
+}
+
+impl<S> Filter<S> for Aggregate {
+    #[inline]
+    fn enabled(&self, _: &Metadata<'_>, _: &layer::Context<'_, S>) -> bool {
+        true
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _: &layer::Context<'_, S>) -> bool {
+        self.observe(event)
+    }
+}
+
+// === impl Summarize ===
+
+impl Summarize {
+    /// Logs a summary of the suppressed warnings at the end of each window.
+    ///
+    /// Completes once the filter has been dropped.
+    pub(crate) async fn run(self) {
+        let Self { window, warnings } = self;
+        loop {
+            tokio::time::sleep(window).await;
+            let Some(warnings) = warnings.upgrade() else {
+                return;
+            };
+            // Release the lock before logging summaries.
+            let flushed = std::mem::take(&mut *warnings.lock());
+            drop(warnings);
+            for w in flushed.into_values().filter(|w| w.suppressed > 0) {
+                tracing::warn!(
+                    target: SUMMARY_TARGET,
+                    suppressed = w.suppressed,
+                    warning.target = %w.target,
+                    ?window,
+                    "Suppressed repeated warnings: {}",
+                    w.message,
+                );
+            }
+        }
+    }
+}
+
+// === impl Fields ===
+
+impl field::Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    /// Counts the events that are not suppressed.
+    #[derive(Clone, Default)]
+    struct Count(Arc<Mutex<Vec<String>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Count {
+        fn on_event(&self, event: &Event<'_>, _: layer::Context<'_, S>) {
+            let mut message = String::new();
+            let mut fields = String::new();
+            event.record(&mut Fields {
+                message: &mut message,
+                fields: &mut fields,
+            });
+            self.0.lock().push(message);
+        }
+    }
+
+    #[test]
+    fn suppresses_repeated_warnings() {
+        let (aggregate, _summarize) = Aggregate::new(Duration::from_secs(10));
+        let count = Count::default();
+        let subscriber =
+            tracing_subscriber::registry().with(count.clone().with_filter(aggregate.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..3 {
+                tracing::warn!(addr = "10.1.1.1:80", "Failed to connect");
+            }
+            tracing::warn!(addr = "10.1.1.2:80", "Failed to connect");
+            tracing::info!("Informational");
+            tracing::info!("Informational");
+        });
+
+        assert_eq!(
+            *count.0.lock(),
+            [
+                "Failed to connect",
+                "Failed to connect",
+                "Informational",
+                "Informational"
+            ]
+        );
+        let warnings = aggregate.warnings.lock();
+        let suppressed = warnings
+            .values()
+            .map(|w| (w.message.as_str(), w.suppressed))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(suppressed["Failed to connect addr=10.1.1.1:80"], 2);
+        assert_eq!(suppressed["Failed to connect addr=10.1.1.2:80"], 0);
+    }
+}
@@ -2,6 +2,7 @@
 #![forbid(unsafe_code)]
 
 pub mod access_log;
+mod aggregate;
 pub mod level;
 #[cfg(feature = "stream")]
 pub mod stream;
@@ -10,7 +11,7 @@ mod uptime;
 
 use self::uptime::Uptime;
 use linkerd_error::Error;
-use std::str;
+use std::{str, time::Duration};
 use tokio::time;
 use tracing::Dispatch;
 use tracing_subscriber::{
@@ -26,6 +27,11 @@ const ENV_LOG_LEVEL: &str = "LINKERD2_PROXY_LOG";
 const ENV_LOG_FORMAT: &str = "LINKERD2_PROXY_LOG_FORMAT";
 const ENV_ACCESS_LOG: &str = "LINKERD2_PROXY_ACCESS_LOG";
 
+/// Configures the window in which repeated identical warnings are suppressed,
+/// e.g. `10s`. A summary of the suppressed warnings is logged at the end of
+/// each window. Warnings are not suppressed unless this is set.
+const ENV_LOG_AGGREGATION_WINDOW: &str = "LINKERD2_PROXY_LOG_AGGREGATION_WINDOW";
+
 const DEFAULT_LOG_LEVEL: &str = "warn,linkerd=info,trust_dns=error";
 const DEFAULT_LOG_FORMAT: &str = "PLAIN";
 
@@ -36,12 +42,14 @@ pub struct Settings {
     format: String,
     start_time: Option<time::Instant>,
     access_log: Option<access_log::Format>,
+    aggregation_window: Option<Duration>,
     is_test: bool,
 }
 
 #[derive(Clone)]
 pub struct Handle {
     level: Option<level::Handle>,
+    summarize: Option<aggregate::Summarize>,
     #[cfg(feature = "stream")]
     stream: stream::StreamHandle<LogStack>,
 }
@@ -72,6 +80,7 @@ impl Settings {
                 .ok()
                 .unwrap_or_else(|| DEFAULT_LOG_FORMAT.to_string()),
             access_log: Self::access_log_format(),
+            aggregation_window: Self::aggregation_window(),
             start_time: Some(now),
             is_test: false,
         }
@@ -83,6 +92,7 @@ impl Settings {
             format,
             start_time: None,
             access_log: Self::access_log_format(),
+            aggregation_window: None,
             is_test: true,
         }
     }
@@ -98,6 +108,17 @@ impl Settings {
         }
     }
 
+    fn aggregation_window() -> Option<Duration> {
+        let env = std::env::var(ENV_LOG_AGGREGATION_WINDOW).ok()?;
+        match parse_duration(&env) {
+            Some(window) => (window > Duration::ZERO).then_some(window),
+            None => {
+                eprintln!("Invalid {}={:?}", ENV_LOG_AGGREGATION_WINDOW, env);
+                None
+            }
+        }
+    }
+
     fn timer(&self) -> Uptime {
         self.start_time
             .map(Uptime::starting_at)
@@ -156,6 +177,7 @@ impl Settings {
         if self.filter.trim().eq_ignore_ascii_case("off") {
             return Ok(Handle {
                 level: None,
+                summarize: None,
 
                 // logging is disabled, but log streaming might still be enabled later
                 #[cfg(feature = "stream")]
@@ -192,14 +214,23 @@ impl Settings {
         let registry = tracing_subscriber::registry();
 
         // Build the default stdout logger.
-        let (registry, level) = {
+        let (registry, (level, summarize)) = {
             // Make a formatted logging layer configured to write to stdout.
-            let stdout = if self.format.trim().eq_ignore_ascii_case("json") {
+            let mut stdout = if self.format.trim().eq_ignore_ascii_case("json") {
                 self.mk_json()
             } else {
                 self.mk_plain()
             };
 
+            // Suppress repeated warnings. Summaries are logged by the task
+            // returned from `Handle::summarize_warnings`.
+            let mut summarize = None;
+            if let Some(window) = self.aggregation_window {
+                let (aggregate, summ) = aggregate::Aggregate::new(window);
+                stdout = Box::new(stdout.with_filter(aggregate));
+                summarize = Some(summ);
+            }
+
             // Parse the initial filter. If the filter includes invalid
             // directives, an error is printed sto stderr.
             let filter = level::filter_builder()
@@ -208,7 +239,7 @@ impl Settings {
 
             // Make the level dynamic and register the layer.
             let (layer, level) = reload::Layer::new(stdout.with_filter(filter));
-            (registry.with(Some(layer)), level)
+            (registry.with(Some(layer)), (level, summarize))
         };
 
         // Log streaming (via the admin API) is currently feature-gated. When it
@@ -226,6 +257,7 @@ impl Settings {
         // The handle controls the logging system at runtime.
         let handle = Handle {
             level: Some(level::Handle::new(level)),
+            summarize,
             #[cfg(feature = "stream")]
             stream,
         };
@@ -245,6 +277,7 @@ impl Handle {
     pub fn disabled() -> Self {
         Self {
             level: None,
+            summarize: None,
             #[cfg(feature = "stream")]
             stream: stream::StreamHandle::new().0,
         }
@@ -254,8 +287,48 @@ impl Handle {
         self.level.as_ref()
     }
 
+    /// Returns a task that logs summaries of suppressed warnings, if warning
+    /// aggregation is enabled. The task must be spawned on a runtime and
+    /// completes when the logging system is dropped.
+    pub fn summarize_warnings(&self) -> Option<impl std::future::Future<Output = ()> + Send> {
+        self.summarize.clone().map(aggregate::Summarize::run)
+    }
+
     #[cfg(feature = "stream")]
     pub fn into_stream(self) -> stream::StreamHandle<LogStack> {
         self.stream
     }
 }
+
+/// Parses a duration with a unit suffix (`ms`, `s`, or `m`). `0` may be
+/// specified without a unit.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    if s == "0" {
+        return Some(Duration::ZERO);
+    }
+    let (n, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit())?);
+    let n = n.parse::<u64>().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(n)),
+        "s" => Some(Duration::from_secs(n)),
+        "m" => Some(Duration::from_secs(n * 60)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("0"), Some(Duration::ZERO));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration(" 10s "), Some(Duration::from_secs(10)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("s"), None);
+        assert_eq!(parse_duration("10h"), None);
+    }
+}
@@ -0,0 +1,408 @@
+//! Socket health metrics derived from the kernel's `TCP_INFO`.
+//!
+//! Round-trip times, retransmissions, and congestion windows describe the
+//! network path beneath a connection, independently of how quickly the
+//! application responds. These are sampled periodically from long-lived
+//! connections that cross the network--inbound connections accepted from
+//! clients and outbound connections to endpoints--and summarized as
+//! histograms.
+
+use futures::{ready, TryFuture};
+use linkerd_io as io;
+use linkerd_metrics::{
+    latency::LatencyHistogram, metrics, Bounds, Bucket, FmtLabels, FmtMetric, FmtMetrics, Histogram,
+};
+use linkerd_proxy_transport::{GetTcpInfo, TcpInfo};
+use linkerd_stack::{layer, MakeConnection, Service};
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time::{Duration, Instant};
+
+metrics! {
+    tcp_info_rtt_ms: LatencyHistogram {
+        "Smoothed round-trip times of long-lived connections, as sampled from the kernel"
+    },
+    tcp_info_retransmits: Histogram<u32> {
+        "Segments retransmitted by long-lived connections between samples"
+    },
+    tcp_info_snd_cwnd: Histogram<u32> {
+        "Congestion windows of long-lived connections, in segments"
+    }
+}
+
+const RETRANSMIT_BOUNDS: &Bounds = &Bounds(&[
+    Bucket::Le(1.0),
+    Bucket::Le(2.0),
+    Bucket::Le(5.0),
+    Bucket::Le(10.0),
+    Bucket::Le(20.0),
+    Bucket::Le(50.0),
+    Bucket::Le(100.0),
+    Bucket::Le(200.0),
+    Bucket::Le(500.0),
+    Bucket::Le(1_000.0),
+    Bucket::Inf,
+]);
+
+const CWND_BOUNDS: &Bounds = &Bounds(&[
+    Bucket::Le(1.0),
+    Bucket::Le(2.0),
+    Bucket::Le(4.0),
+    Bucket::Le(8.0),
+    Bucket::Le(16.0),
+    Bucket::Le(32.0),
+    Bucket::Le(64.0),
+    Bucket::Le(128.0),
+    Bucket::Le(256.0),
+    Bucket::Le(512.0),
+    Bucket::Le(1_024.0),
+    Bucket::Inf,
+]);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// The minimum time between samples of a connection. Connections are
+    /// first sampled once they have been open for this long.
+    pub interval: Duration,
+}
+
+/// Creates a registry and report for socket health metrics.
+///
+/// The default `Registry` and `Report` are disabled and record nothing.
+pub fn new(config: Config) -> (Registry, Report) {
+    let inner = Arc::new(Inner {
+        config,
+        inbound: Arc::default(),
+        outbound: Arc::default(),
+    });
+    (Registry(Some(inner.clone())), Report(Some(inner)))
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Registry(Option<Arc<Inner>>);
+
+/// Implements `FmtMetrics` to render socket health metrics.
+#[derive(Clone, Debug, Default)]
+pub struct Report(Option<Arc<Inner>>);
+
+/// Samples the health of each connection established by the inner connector.
+#[derive(Clone, Debug)]
+pub struct Client<S> {
+    inner: S,
+    registry: Registry,
+}
+
+#[pin_project]
+pub struct ConnectFuture<F> {
+    #[pin]
+    inner: F,
+    registry: Registry,
+}
+
+/// Samples a connection's `TCP_INFO` as it is used.
+#[pin_project]
+#[derive(Debug)]
+pub struct HealthIo<I> {
+    #[pin]
+    io: I,
+    sampler: Option<Sampler>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    config: Config,
+    inbound: Arc<Metrics>,
+    outbound: Arc<Metrics>,
+}
+
+#[derive(Debug)]
+struct Metrics {
+    rtt: LatencyHistogram,
+    retransmits: Histogram<u32>,
+    snd_cwnd: Histogram<u32>,
+}
+
+/// Labels metrics by the direction of the connection's proxy.
+///
+/// Implements `FmtLabels`.
+#[derive(Copy, Clone, Debug)]
+struct Direction(&'static str);
+
+#[derive(Debug)]
+struct Sampler {
+    metrics: Arc<Metrics>,
+    interval: Duration,
+    next_sample: Instant,
+    total_retransmits: u32,
+}
+
+// === impl Registry ===
+
+impl Registry {
+    /// Wraps a connection accepted by the inbound proxy.
+    pub fn inbound<I>(&self, io: I) -> HealthIo<I> {
+        let sampler = self
+            .0
+            .as_ref()
+            .map(|inner| Sampler::new(inner.inbound.clone(), inner.config.interval));
+        HealthIo { io, sampler }
+    }
+
+    /// Wraps a connection established by the outbound proxy.
+    fn outbound<I>(&self, io: I) -> HealthIo<I> {
+        let sampler = self
+            .0
+            .as_ref()
+            .map(|inner| Sampler::new(inner.outbound.clone(), inner.config.interval));
+        HealthIo { io, sampler }
+    }
+}
+
+// === impl Report ===
+
+impl FmtMetrics for Report {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = match self.0.as_ref() {
+            Some(inner) => inner,
+            None => return Ok(()),
+        };
+        let by_direction = [
+            (Direction("inbound"), &inner.inbound),
+            (Direction("outbound"), &inner.outbound),
+        ];
+
+        tcp_info_rtt_ms.fmt_help(f)?;
+        for (direction, m) in by_direction.iter() {
+            m.rtt
+                .fmt_metric_labeled(f, tcp_info_rtt_ms.name, direction)?;
+        }
+
+        tcp_info_retransmits.fmt_help(f)?;
+        for (direction, m) in by_direction.iter() {
+            m.retransmits
+                .fmt_metric_labeled(f, tcp_info_retransmits.name, direction)?;
+        }
+
+        tcp_info_snd_cwnd.fmt_help(f)?;
+        for (direction, m) in by_direction.iter() {
+            m.snd_cwnd
+                .fmt_metric_labeled(f, tcp_info_snd_cwnd.name, direction)?;
+        }
+
+        Ok(())
+    }
+}
+
+// === impl Metrics ===
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            rtt: LatencyHistogram::default(),
+            retransmits: Histogram::new(RETRANSMIT_BOUNDS),
+            snd_cwnd: Histogram::new(CWND_BOUNDS),
+        }
+    }
+}
+
+// === impl Direction ===
+
+impl FmtLabels for Direction {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "direction=\"{}\"", self.0)
+    }
+}
+
+// === impl Client ===
+
+impl<S> Client<S> {
+    pub fn layer(registry: Registry) -> impl layer::Layer<S, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            registry: registry.clone(),
+        })
+    }
+}
+
+impl<T, S: MakeConnection<T>> Service<T> for Client<S> {
+    type Response = (HealthIo<S::Connection>, S::Metadata);
+    type Error = S::Error;
+    type Future = ConnectFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        ConnectFuture {
+            inner: self.inner.connect(target),
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+// === impl ConnectFuture ===
+
+impl<I, M, F: TryFuture<Ok = (I, M)>> Future for ConnectFuture<F> {
+    type Output = Result<(HealthIo<I>, M), F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let (io, meta) = ready!(this.inner.try_poll(cx))?;
+        Poll::Ready(Ok((this.registry.outbound(io), meta)))
+    }
+}
+
+// === impl HealthIo ===
+
+impl<I: GetTcpInfo> HealthIo<I> {
+    fn sample(sampler: &mut Option<Sampler>, io: &I) {
+        if let Some(sampler) = sampler {
+            let now = Instant::now();
+            if now >= sampler.next_sample {
+                sampler.next_sample = now + sampler.interval;
+                match io.tcp_info() {
+                    Ok(info) => sampler.record(info),
+                    Err(error) => tracing::trace!(%error, "Failed to read TCP_INFO"),
+                }
+            }
+        }
+    }
+}
+
+impl<I: io::AsyncRead + GetTcpInfo> io::AsyncRead for HealthIo<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> io::Poll<()> {
+        let mut this = self.project();
+        ready!(this.io.as_mut().poll_read(cx, buf))?;
+        Self::sample(this.sampler, &*this.io);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<I: io::AsyncWrite + GetTcpInfo> io::AsyncWrite for HealthIo<I> {
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_shutdown(cx)
+    }
+
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+        let mut this = self.project();
+        let sz = ready!(this.io.as_mut().poll_write(cx, buf))?;
+        Self::sample(this.sampler, &*this.io);
+        Poll::Ready(Ok(sz))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> io::Poll<usize> {
+        let mut this = self.project();
+        let sz = ready!(this.io.as_mut().poll_write_vectored(cx, bufs))?;
+        Self::sample(this.sampler, &*this.io);
+        Poll::Ready(Ok(sz))
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: io::Peek + Send + Sync> io::Peek for HealthIo<I> {
+    async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.peek(buf).await
+    }
+}
+
+impl<I: io::PeerAddr> io::PeerAddr for HealthIo<I> {
+    #[inline]
+    fn peer_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.io.peer_addr()
+    }
+}
+
+impl<I: GetTcpInfo> GetTcpInfo for HealthIo<I> {
+    #[inline]
+    fn tcp_info(&self) -> io::Result<TcpInfo> {
+        self.io.tcp_info()
+    }
+}
+
+// === impl Sampler ===
+
+impl Sampler {
+    fn new(metrics: Arc<Metrics>, interval: Duration) -> Self {
+        Self {
+            metrics,
+            interval,
+            // Only connections that outlive a sampling interval are sampled.
+            next_sample: Instant::now() + interval,
+            total_retransmits: 0,
+        }
+    }
+
+    fn record(&mut self, info: TcpInfo) {
+        self.metrics.rtt.add(info.rtt);
+        self.metrics.snd_cwnd.add(info.snd_cwnd);
+        self.metrics.retransmits.add(
+            info.total_retransmits
+                .saturating_sub(self.total_retransmits),
+        );
+        self.total_retransmits = info.total_retransmits;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_retransmits_between_samples() {
+        let (registry, report) = new(Config {
+            interval: Duration::from_secs(10),
+        });
+        let io = registry.inbound(());
+        let mut sampler = io.sampler.expect("sampler must be enabled");
+        for total_retransmits in [2, 2, 7] {
+            sampler.record(TcpInfo {
+                rtt: Duration::from_millis(3),
+                total_retransmits,
+                snd_cwnd: 10,
+                ..TcpInfo::default()
+            });
+        }
+
+        let text = report.as_display().to_string();
+        assert!(text.contains("tcp_info_retransmits_bucket{direction=\"inbound\",le=\"1\"} 1\n"));
+        assert!(text.contains("tcp_info_retransmits_bucket{direction=\"inbound\",le=\"2\"} 2\n"));
+        assert!(text.contains("tcp_info_retransmits_sum{direction=\"inbound\"} 7\n"));
+        assert!(text.contains("tcp_info_snd_cwnd_bucket{direction=\"inbound\",le=\"16\"} 3\n"));
+        assert!(text.contains("tcp_info_rtt_ms_count{direction=\"inbound\"} 3\n"));
+        assert!(text.contains("tcp_info_rtt_ms_count{direction=\"outbound\"} 0\n"));
+    }
+
+    #[test]
+    fn disabled() {
+        let registry = Registry::default();
+        assert!(registry.inbound(()).sampler.is_none());
+        assert_eq!(Report::default().as_display().to_string(), "");
+    }
+}
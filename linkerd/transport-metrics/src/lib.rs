@@ -2,6 +2,8 @@
 #![forbid(unsafe_code)]
 
 mod client;
+pub mod health;
+pub mod peer;
 mod report;
 mod sensor;
 mod server;
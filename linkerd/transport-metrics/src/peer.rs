@@ -0,0 +1,539 @@
+//! Per-peer TCP metrics for client connections.
+//!
+//! Aggregate transport metrics can show that connections are slow or lossy, but
+//! not which node is responsible. These metrics are labeled by the remote
+//! peer's IP (or IP and port) so that degradation can be localized to a
+//! specific node.
+//!
+//! Because the set of peers is unbounded, only a configured number of peers is
+//! tracked at a time. Connections to any other peer are aggregated under
+//! `peer="other"`. Peers without open connections are evicted once they have
+//! been idle for the retention period.
+
+use futures::{ready, TryFuture};
+use linkerd_io as io;
+use linkerd_metrics::{
+    latency::LatencyHistogram, metrics, Counter, FmtLabels, FmtMetrics, Gauge, LastUpdate, Store,
+};
+use linkerd_proxy_transport::{GetTcpInfo, Remote, ServerAddr};
+use linkerd_stack::{layer, MakeConnection, Param, Service};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time::{Duration, Instant};
+
+metrics! {
+    tcp_peer_connect_latency_ms: LatencyHistogram {
+        "Time to establish a connection to a peer"
+    },
+    tcp_peer_connect_errors_total: Counter {
+        "Total count of failed attempts to connect to a peer"
+    },
+    tcp_peer_open_connections: Gauge { "Number of currently-open connections to a peer" },
+    tcp_peer_read_bytes_total: Counter { "Total count of bytes read from a peer" },
+    tcp_peer_write_bytes_total: Counter { "Total count of bytes written to a peer" },
+    tcp_peer_rtt_ms: LatencyHistogram {
+        "Smoothed round-trip times to a peer, as sampled from the kernel"
+    },
+    tcp_peer_retransmits_total: Counter {
+        "Total count of TCP segments retransmitted to a peer"
+    }
+}
+
+/// The minimum time between samples of a connection's `TCP_INFO`.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub granularity: Granularity,
+
+    /// The maximum number of peers for which metrics are tracked individually.
+    pub max_peers: usize,
+}
+
+/// Determines how connections are grouped into peers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Granularity {
+    /// Groups connections by the peer's IP address.
+    Ip,
+    /// Groups connections by the peer's IP address and port.
+    Endpoint,
+}
+
+/// Creates a registry and report for per-peer metrics.
+///
+/// The default `Registry` and `Report` are disabled and record nothing.
+pub fn new(config: Config, retain_idle: Duration) -> (Registry, Report) {
+    let inner = Arc::new(Mutex::new(Inner {
+        config,
+        peers: Store::new(),
+    }));
+    let report = Report(Some((inner.clone(), retain_idle)));
+    (Registry(Some(inner)), report)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Registry(Option<Arc<Mutex<Inner>>>);
+
+/// Implements `FmtMetrics` to render per-peer metrics.
+#[derive(Clone, Debug, Default)]
+pub struct Report(Option<(Arc<Mutex<Inner>>, Duration)>);
+
+/// Records per-peer metrics for each connection established by the inner
+/// connector.
+#[derive(Clone, Debug)]
+pub struct Connect<S> {
+    inner: S,
+    registry: Registry,
+}
+
+#[pin_project]
+pub struct ConnectFuture<F> {
+    #[pin]
+    inner: F,
+    metrics: Option<Arc<PeerMetrics>>,
+    started_at: Instant,
+}
+
+/// Records per-peer metrics for a connection.
+#[pin_project]
+#[derive(Debug)]
+pub struct PeerIo<I> {
+    #[pin]
+    io: I,
+    sampler: Option<Sampler>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    config: Config,
+    peers: Store<Peer, PeerMetrics>,
+}
+
+/// Identifies a peer.
+///
+/// Implements `FmtLabels`.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+enum Peer {
+    Ip(IpAddr),
+    Endpoint(SocketAddr),
+    /// Aggregates all peers beyond the configured maximum.
+    Other,
+}
+
+#[derive(Debug)]
+pub struct PeerMetrics {
+    connect_latency: LatencyHistogram,
+    connect_errors_total: Counter,
+    open_connections: Gauge,
+    read_bytes_total: Counter,
+    write_bytes_total: Counter,
+    rtt: LatencyHistogram,
+    retransmits_total: Counter,
+    last_update: Mutex<Instant>,
+}
+
+/// Holds a connection's metrics and the state of its `TCP_INFO` samples.
+#[derive(Debug)]
+struct Sampler {
+    metrics: Arc<PeerMetrics>,
+    sampled_at: Option<Instant>,
+    total_retransmits: u32,
+}
+
+// === impl Registry ===
+
+impl Registry {
+    fn metrics(&self, addr: SocketAddr) -> Option<Arc<PeerMetrics>> {
+        let inner = self.0.as_ref()?;
+        Some(inner.lock().metrics(addr))
+    }
+}
+
+// === impl Inner ===
+
+impl Inner {
+    fn metrics(&mut self, addr: SocketAddr) -> Arc<PeerMetrics> {
+        let peer = match self.config.granularity {
+            Granularity::Ip => Peer::Ip(addr.ip()),
+            Granularity::Endpoint => Peer::Endpoint(addr),
+        };
+        if let Some(metrics) = self.peers.get(&peer) {
+            return metrics.clone();
+        }
+
+        let tracked = self.peers.len() - usize::from(self.peers.get(&Peer::Other).is_some());
+        let peer = if tracked < self.config.max_peers {
+            peer
+        } else {
+            Peer::Other
+        };
+        self.peers.get_or_default(peer).clone()
+    }
+}
+
+// === impl Peer ===
+
+impl FmtLabels for Peer {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ip(ip) => write!(f, "peer=\"{}\"", ip),
+            Self::Endpoint(addr) => write!(f, "peer=\"{}\"", addr),
+            Self::Other => f.pad("peer=\"other\""),
+        }
+    }
+}
+
+// === impl PeerMetrics ===
+
+impl Default for PeerMetrics {
+    fn default() -> Self {
+        Self {
+            connect_latency: LatencyHistogram::default(),
+            connect_errors_total: Counter::default(),
+            open_connections: Gauge::default(),
+            read_bytes_total: Counter::default(),
+            write_bytes_total: Counter::default(),
+            rtt: LatencyHistogram::default(),
+            retransmits_total: Counter::default(),
+            last_update: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl PeerMetrics {
+    fn touch(&self) {
+        *self.last_update.lock() = Instant::now();
+    }
+}
+
+impl LastUpdate for PeerMetrics {
+    fn last_update(&self) -> Instant {
+        *self.last_update.lock()
+    }
+}
+
+// === impl Report ===
+
+impl FmtMetrics for Report {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (inner, retain_idle) = match self.0.as_ref() {
+            Some(report) => report,
+            None => return Ok(()),
+        };
+        let mut inner = inner.lock();
+        let peers = &mut inner.peers;
+        if peers.is_empty() {
+            return Ok(());
+        }
+
+        tcp_peer_connect_latency_ms.fmt_help(f)?;
+        peers.fmt_by(f, tcp_peer_connect_latency_ms, |m| &m.connect_latency)?;
+
+        tcp_peer_connect_errors_total.fmt_help(f)?;
+        peers.fmt_by(f, tcp_peer_connect_errors_total, |m| {
+            &m.connect_errors_total
+        })?;
+
+        tcp_peer_open_connections.fmt_help(f)?;
+        peers.fmt_by(f, tcp_peer_open_connections, |m| &m.open_connections)?;
+
+        tcp_peer_read_bytes_total.fmt_help(f)?;
+        peers.fmt_by(f, tcp_peer_read_bytes_total, |m| &m.read_bytes_total)?;
+
+        tcp_peer_write_bytes_total.fmt_help(f)?;
+        peers.fmt_by(f, tcp_peer_write_bytes_total, |m| &m.write_bytes_total)?;
+
+        tcp_peer_rtt_ms.fmt_help(f)?;
+        peers.fmt_by(f, tcp_peer_rtt_ms, |m| &m.rtt)?;
+
+        tcp_peer_retransmits_total.fmt_help(f)?;
+        peers.fmt_by(f, tcp_peer_retransmits_total, |m| &m.retransmits_total)?;
+
+        peers.retain_since(Instant::now() - *retain_idle);
+
+        Ok(())
+    }
+}
+
+// === impl Connect ===
+
+impl<S> Connect<S> {
+    pub fn layer(registry: Registry) -> impl layer::Layer<S, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            registry: registry.clone(),
+        })
+    }
+}
+
+impl<T, S> Service<T> for Connect<S>
+where
+    T: Param<Remote<ServerAddr>>,
+    S: MakeConnection<T>,
+{
+    type Response = (PeerIo<S::Connection>, S::Metadata);
+    type Error = S::Error;
+    type Future = ConnectFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let Remote(ServerAddr(addr)) = target.param();
+        let metrics = self.registry.metrics(addr);
+        if let Some(m) = metrics.as_ref() {
+            m.touch();
+        }
+        ConnectFuture {
+            inner: self.inner.connect(target),
+            metrics,
+            started_at: Instant::now(),
+        }
+    }
+}
+
+// === impl ConnectFuture ===
+
+impl<I, M, F: TryFuture<Ok = (I, M)>> Future for ConnectFuture<F> {
+    type Output = Result<(PeerIo<I>, M), F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.inner.try_poll(cx));
+        let metrics = this.metrics.take();
+        match res {
+            Ok((io, meta)) => {
+                let sampler = metrics.map(|metrics| {
+                    metrics
+                        .connect_latency
+                        .add(Instant::now().saturating_duration_since(*this.started_at));
+                    metrics.open_connections.incr();
+                    metrics.touch();
+                    Sampler {
+                        metrics,
+                        sampled_at: None,
+                        total_retransmits: 0,
+                    }
+                });
+                Poll::Ready(Ok((PeerIo { io, sampler }, meta)))
+            }
+            Err(error) => {
+                if let Some(metrics) = metrics {
+                    metrics.connect_errors_total.incr();
+                    metrics.touch();
+                }
+                Poll::Ready(Err(error))
+            }
+        }
+    }
+}
+
+// === impl PeerIo ===
+
+impl<I: GetTcpInfo> PeerIo<I> {
+    /// Samples the connection's `TCP_INFO`, unless it was sampled recently.
+    fn sample(sampler: &mut Option<Sampler>, io: &I) {
+        if let Some(sampler) = sampler {
+            let now = Instant::now();
+            if sampler
+                .sampled_at
+                .map(|at| now - at >= SAMPLE_INTERVAL)
+                .unwrap_or(true)
+            {
+                sampler.sample(io, now);
+            }
+        }
+    }
+}
+
+impl<I: io::AsyncRead + GetTcpInfo> io::AsyncRead for PeerIo<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> io::Poll<()> {
+        let mut this = self.project();
+        let prev_filled = buf.filled().len();
+        ready!(this.io.as_mut().poll_read(cx, buf))?;
+        if let Some(sampler) = this.sampler.as_ref() {
+            let sz = buf.filled().len() - prev_filled;
+            sampler.metrics.read_bytes_total.add(sz as u64);
+        }
+        Self::sample(this.sampler, &*this.io);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<I: io::AsyncWrite + GetTcpInfo> io::AsyncWrite for PeerIo<I> {
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_shutdown(cx)
+    }
+
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+        let mut this = self.project();
+        let sz = ready!(this.io.as_mut().poll_write(cx, buf))?;
+        if let Some(sampler) = this.sampler.as_ref() {
+            sampler.metrics.write_bytes_total.add(sz as u64);
+        }
+        Self::sample(this.sampler, &*this.io);
+        Poll::Ready(Ok(sz))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> io::Poll<usize> {
+        let mut this = self.project();
+        let sz = ready!(this.io.as_mut().poll_write_vectored(cx, bufs))?;
+        if let Some(sampler) = this.sampler.as_ref() {
+            sampler.metrics.write_bytes_total.add(sz as u64);
+        }
+        Self::sample(this.sampler, &*this.io);
+        Poll::Ready(Ok(sz))
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+impl<I: io::PeerAddr> io::PeerAddr for PeerIo<I> {
+    #[inline]
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.peer_addr()
+    }
+}
+
+// === impl Sampler ===
+
+impl Sampler {
+    fn sample<I: GetTcpInfo>(&mut self, io: &I, now: Instant) {
+        self.sampled_at = Some(now);
+        let info = match io.tcp_info() {
+            Ok(info) => info,
+            Err(error) => {
+                tracing::trace!(%error, "Failed to read TCP_INFO");
+                return;
+            }
+        };
+
+        self.metrics.rtt.add(info.rtt);
+        let retransmits = info
+            .total_retransmits
+            .saturating_sub(self.total_retransmits);
+        self.metrics.retransmits_total.add(retransmits.into());
+        self.total_retransmits = info.total_retransmits;
+        self.metrics.touch();
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        self.metrics.open_connections.decr();
+        self.metrics.touch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry_with(granularity: Granularity, max_peers: usize) -> (Registry, Report) {
+        new(
+            Config {
+                granularity,
+                max_peers,
+            },
+            Duration::from_secs(1),
+        )
+    }
+
+    fn peers(registry: &Registry) -> Vec<Peer> {
+        let inner = registry.0.as_ref().unwrap().lock();
+        let mut peers = inner.peers.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+        peers.sort_by_key(|p| format!("{p:?}"));
+        peers
+    }
+
+    #[test]
+    fn caps_tracked_peers() {
+        let (registry, _report) = registry_with(Granularity::Ip, 2);
+        let a = registry.metrics(([192, 0, 2, 1], 80).into()).unwrap();
+        let b = registry.metrics(([192, 0, 2, 2], 80).into()).unwrap();
+        let a2 = registry.metrics(([192, 0, 2, 1], 8080).into()).unwrap();
+        assert!(Arc::ptr_eq(&a, &a2), "ports must be aggregated by IP");
+
+        let c = registry.metrics(([192, 0, 2, 3], 80).into()).unwrap();
+        let d = registry.metrics(([192, 0, 2, 4], 80).into()).unwrap();
+        assert!(Arc::ptr_eq(&c, &d), "excess peers must be aggregated");
+        assert!(!Arc::ptr_eq(&b, &c));
+
+        assert_eq!(
+            peers(&registry),
+            vec![
+                Peer::Ip([192, 0, 2, 1].into()),
+                Peer::Ip([192, 0, 2, 2].into()),
+                Peer::Other,
+            ]
+        );
+    }
+
+    #[test]
+    fn evicts_idle_peers() {
+        let (registry, report) = registry_with(Granularity::Endpoint, 1);
+        let open = registry.metrics(([192, 0, 2, 1], 80).into()).unwrap();
+        drop(registry.metrics(([192, 0, 2, 2], 80).into()).unwrap());
+        assert_eq!(
+            peers(&registry),
+            vec![Peer::Endpoint(([192, 0, 2, 1], 80).into()), Peer::Other]
+        );
+
+        registry
+            .0
+            .as_ref()
+            .unwrap()
+            .lock()
+            .peers
+            .retain_since(Instant::now() + Duration::from_secs(1));
+        assert_eq!(
+            peers(&registry),
+            vec![Peer::Endpoint(([192, 0, 2, 1], 80).into())],
+            "only peers without handles may be evicted"
+        );
+
+        // Once the tracked peer is evicted, a new peer may take its place.
+        drop(open);
+        let text = report.as_display().to_string();
+        assert!(text.contains("tcp_peer_open_connections{peer=\"192.0.2.1:80\"} 0\n"));
+        registry
+            .0
+            .as_ref()
+            .unwrap()
+            .lock()
+            .peers
+            .retain_since(Instant::now() + Duration::from_secs(1));
+        let _ = registry.metrics(([192, 0, 2, 3], 80).into()).unwrap();
+        assert_eq!(
+            peers(&registry),
+            vec![Peer::Endpoint(([192, 0, 2, 3], 80).into())]
+        );
+    }
+}
@@ -42,6 +42,10 @@ fn main() {
         vendor = BUILD_INFO.vendor,
     );
 
+    // Capture backtraces for panics so that they may be recorded when panics
+    // are caught while serving connections.
+    linkerd_app::core::errors::panic::install_hook();
+
     // Load configuration from the environment without binding ports.
     let config = match Config::try_from_env() {
         Ok(config) => config,
@@ -72,6 +76,9 @@ fn main() {
 
         info!("Admin interface on {}", app.admin_addr());
         info!("Inbound interface on {}", app.inbound_addr());
+        for addr in app.inbound_additional_addrs() {
+            info!("Additional inbound interface on {}", addr);
+        }
         info!("Outbound interface on {}", app.outbound_addr());
 
         match app.tap_addr() {
@@ -110,6 +117,22 @@ fn main() {
             }
         }
 
+        if let Some(otlp) = app.otlp_metrics_addr() {
+            match otlp.identity.value() {
+                None => info!("OTLP metrics collector at {}", otlp.addr),
+                Some(tls) => {
+                    info!(
+                        "OTLP metrics collector at {} ({})",
+                        otlp.addr, tls.server_id
+                    )
+                }
+            }
+        }
+
+        if let Some(uri) = app.metrics_push_uri() {
+            info!("Pushing metrics to {}", uri);
+        }
+
         let drain = app.spawn();
         tokio::select! {
             _ = signal::shutdown() => {
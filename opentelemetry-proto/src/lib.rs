@@ -0,0 +1,33 @@
+//! gRPC bindings for OpenTelemetry.
+//!
+//! Vendored from <https://github.com/open-telemetry/opentelemetry-proto/>.
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![allow(clippy::derive_partial_eq_without_eq)]
+#![forbid(unsafe_code)]
+
+pub mod collector {
+    pub mod metrics {
+        pub mod v1 {
+            include!("gen/opentelemetry.proto.collector.metrics.v1.rs");
+        }
+    }
+}
+
+pub mod common {
+    pub mod v1 {
+        include!("gen/opentelemetry.proto.common.v1.rs");
+    }
+}
+
+pub mod metrics {
+    pub mod v1 {
+        include!("gen/opentelemetry.proto.metrics.v1.rs");
+    }
+}
+
+pub mod resource {
+    pub mod v1 {
+        include!("gen/opentelemetry.proto.resource.v1.rs");
+    }
+}